@@ -21,7 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{conversions::datetime_to_timestamp, tari_rpc as grpc};
-use tari_comms::{connectivity::ConnectivityStatus, net_address::MutliaddrWithStats, peer_manager::Peer};
+use tari_comms::{
+    bandwidth::{BandwidthStats, BandwidthTotals},
+    connectivity::ConnectivityStatus,
+    net_address::MutliaddrWithStats,
+    peer_manager::{Peer, PeerScore},
+};
 use tari_core::crypto::tari_utilities::ByteArray;
 
 impl From<Peer> for grpc::Peer {
@@ -54,6 +59,7 @@ impl From<Peer> for grpc::Peer {
         };
         let supported_protocols = peer.supported_protocols.into_iter().map(|p| p.to_vec()).collect();
         let user_agent = peer.user_agent;
+        let bandwidth = Some(peer.bandwidth.into());
         Self {
             public_key,
             node_id,
@@ -67,6 +73,48 @@ impl From<Peer> for grpc::Peer {
             last_connected_at,
             supported_protocols,
             user_agent,
+            bandwidth,
+        }
+    }
+}
+
+impl From<BandwidthTotals> for grpc::BandwidthTotals {
+    fn from(totals: BandwidthTotals) -> Self {
+        Self {
+            bytes_sent: totals.bytes_sent,
+            bytes_received: totals.bytes_received,
+        }
+    }
+}
+
+impl From<BandwidthStats> for grpc::BandwidthStats {
+    fn from(stats: BandwidthStats) -> Self {
+        let protocols = stats
+            .protocols_by_usage()
+            .into_iter()
+            .map(|(protocol, totals)| grpc::ProtocolBandwidth {
+                protocol: protocol.to_vec(),
+                totals: Some((*totals).into()),
+            })
+            .collect();
+        Self {
+            total: Some(stats.total.into()),
+            protocols,
+        }
+    }
+}
+
+impl From<PeerScore> for grpc::PeerReputation {
+    fn from(score: PeerScore) -> Self {
+        Self {
+            public_key: score.public_key.to_vec(),
+            node_id: score.node_id.to_vec(),
+            score: score.score,
+            num_bans: score.num_bans,
+            is_banned: score.is_banned,
+            updated_at: score
+                .updated_at
+                .map(|dt| datetime_to_timestamp((dt.timestamp() as u64).into())),
         }
     }
 }
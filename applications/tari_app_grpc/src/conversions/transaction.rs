@@ -70,6 +70,7 @@ mod wallet {
                 Imported => grpc::TransactionStatus::Imported,
                 Pending => grpc::TransactionStatus::Pending,
                 Coinbase => grpc::TransactionStatus::Coinbase,
+                Scheduled => grpc::TransactionStatus::Scheduled,
             }
         }
     }
@@ -198,11 +198,11 @@ impl ExitCodes {
 /// `config` - The reference to the configuration in which to set up the comms stack, see [GlobalConfig]
 ///
 /// ##Returns
-/// TransportType based on the configuration
-pub fn create_transport_type(config: &GlobalConfig) -> TransportType {
+/// TransportType based on the configuration, or a config error if the transport is not supported by this build
+pub fn create_transport_type(config: &GlobalConfig) -> Result<TransportType, ExitCodes> {
     debug!(target: LOG_TARGET, "Transport is set to '{:?}'", config.comms_transport);
 
-    match config.comms_transport.clone() {
+    let transport_type = match config.comms_transport.clone() {
         CommsTransport::Tcp {
             listener_address,
             tor_socks_address,
@@ -223,6 +223,7 @@ pub fn create_transport_type(config: &GlobalConfig) -> TransportType {
             onion_port,
             tor_proxy_bypass_addresses,
             tor_proxy_bypass_for_outbound_tcp,
+            tor_client_auth,
         } => {
             let identity = Some(&config.base_node_tor_identity_file)
                 .filter(|p| p.exists())
@@ -256,6 +257,11 @@ pub fn create_transport_type(config: &GlobalConfig) -> TransportType {
                 socks_auth: socks::Authentication::None,
                 tor_proxy_bypass_addresses,
                 tor_proxy_bypass_for_outbound_tcp,
+                // An invalid key must not be skipped, since that could leave the hidden service open to all clients
+                client_auth: tor_client_auth
+                    .iter()
+                    .map(|key| key.parse().expect("Invalid tor client auth key"))
+                    .collect(),
             })
         },
         CommsTransport::Socks5 {
@@ -270,7 +276,18 @@ pub fn create_transport_type(config: &GlobalConfig) -> TransportType {
             },
             listener_address,
         },
-    }
+        #[cfg(feature = "quic")]
+        CommsTransport::Quic { listener_address } => TransportType::Quic { listener_address },
+        #[cfg(not(feature = "quic"))]
+        CommsTransport::Quic { .. } => {
+            return Err(ExitCodes::ConfigError(
+                "The QUIC transport is not supported by this build. Rebuild with the `quic` feature enabled."
+                    .to_string(),
+            ));
+        },
+    };
+
+    Ok(transport_type)
 }
 
 /// Converts one socks authentication struct into another
@@ -20,14 +20,29 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{cmp, fs, str::FromStr, sync::Arc, time::Duration};
+use std::{cmp, collections::HashMap, fs, str::FromStr, sync::Arc, time::Duration};
 
 use anyhow::anyhow;
 use log::*;
 
 use tari_app_utilities::{consts, identity_management, utilities::create_transport_type};
 use tari_common::{configuration::bootstrap::ApplicationType, GlobalConfig};
-use tari_comms::{peer_manager::Peer, protocol::rpc::RpcServer, NodeIdentity, UnspawnedCommsNode};
+use tari_comms::{
+    bandwidth::{BandwidthConfig, BandwidthLimit},
+    connection_manager::FirewallRules,
+    peer_manager::Peer,
+    protocol::{
+        rpc::{
+            recording::{RpcRecorder, RpcRecorderConfig},
+            RpcServer,
+        },
+        ProtocolId,
+    },
+    reachability::ReachabilityConfig,
+    utils::cidr::parse_cidrs,
+    NodeIdentity,
+    UnspawnedCommsNode,
+};
 use tari_comms_dht::{DbConnectionUrl, Dht, DhtConfig};
 use tari_core::{
     base_node,
@@ -96,7 +111,7 @@ where B: BlockchainBackend + 'static
         };
         let mempool_config = MempoolServiceConfig::default(); // TODO - make this configurable
 
-        let comms_config = self.create_comms_config();
+        let comms_config = self.create_comms_config()?;
         let transport_type = comms_config.transport_type.clone();
 
         let sync_peers = config
@@ -217,6 +232,29 @@ where B: BlockchainBackend + 'static
                 builder.with_unlimited_simultaneous_sessions()
             },
         };
+        let builder = match config.rpc_max_sessions_per_peer {
+            Some(limit) => builder.with_maximum_sessions_per_client(limit),
+            None => builder.with_unlimited_sessions_per_client(),
+        };
+        let builder = match config.rpc_peer_request_budget {
+            Some(budget) => builder.with_client_request_budget(budget, Duration::from_secs(60)),
+            None => builder,
+        };
+        let builder = match config.rpc_recording_file.as_ref() {
+            Some(path) => match RpcRecorder::start(RpcRecorderConfig::new(config.data_dir.join(path))) {
+                Ok(recorder) => builder.with_recorder(recorder),
+                Err(err) => {
+                    warn!(target: LOG_TARGET, "Failed to start RPC recorder: {}", err);
+                    builder
+                },
+            },
+            None => builder,
+        };
+        let builder = base_node::sync_rpc_method_limits()
+            .into_iter()
+            .fold(builder, |builder, (protocol, method, limits)| {
+                builder.with_method_limits(protocol, method, limits)
+            });
         let rpc_server = builder.finish();
         handles.register(rpc_server.get_handle());
 
@@ -236,14 +274,44 @@ where B: BlockchainBackend + 'static
         comms.add_protocol_extension(rpc_server)
     }
 
-    fn create_comms_config(&self) -> P2pConfig {
-        P2pConfig {
+    fn create_comms_config(&self) -> Result<P2pConfig, anyhow::Error> {
+        let firewall_rules = FirewallRules {
+            allow_cidrs: parse_cidrs(&self.config.firewall_allow_cidrs)
+                .map_err(|err| anyhow!("Invalid firewall_allow_cidrs: {}", err))?,
+            deny_cidrs: parse_cidrs(&self.config.firewall_deny_cidrs)
+                .map_err(|err| anyhow!("Invalid firewall_deny_cidrs: {}", err))?,
+            max_connections_per_ip: self.config.firewall_max_connections_per_ip,
+            max_connections_per_subnet: self.config.firewall_max_connections_per_subnet,
+            max_handshake_failures: self.config.firewall_max_handshake_failures,
+            handshake_failure_block_duration: self.config.firewall_handshake_failure_block_period,
+            ..Default::default()
+        };
+
+        let bandwidth = BandwidthConfig {
+            global: BandwidthLimit::new(
+                self.config.bandwidth_max_upload_rate,
+                self.config.bandwidth_max_download_rate,
+            ),
+            protocols: parse_protocol_bandwidth_limits(&self.config.bandwidth_protocol_limits)
+                .map_err(|err| anyhow!("Invalid bandwidth_protocol_limits: {}", err))?,
+        };
+
+        Ok(P2pConfig {
             network: self.config.network,
             node_identity: self.node_identity.clone(),
-            transport_type: create_transport_type(self.config),
+            transport_type: create_transport_type(self.config)?,
             auxilary_tcp_listener_address: self.config.auxilary_tcp_listener_address.clone(),
+            reachability: ReachabilityConfig {
+                is_self_check_enabled: self.config.reachability_check_enabled,
+                check_interval: self.config.reachability_check_interval,
+                is_port_mapping_enabled: self.config.reachability_port_mapping_enabled,
+                port_mapping_gateway: self.config.reachability_port_mapping_gateway,
+                tor_fallback_address: self.config.reachability_tor_fallback_address.clone(),
+                ..Default::default()
+            },
             datastore_path: self.config.peer_db_path.clone(),
             peer_database_name: "peers".to_string(),
+            peer_database_cipher: None,
             max_concurrent_inbound_tasks: 100,
             outbound_buffer_size: 100,
             dht: DhtConfig {
@@ -258,6 +326,8 @@ where B: BlockchainBackend + 'static
             allow_test_addresses: self.config.allow_test_addresses,
             listener_liveness_allowlist_cidrs: self.config.listener_liveness_allowlist_cidrs.clone(),
             listener_liveness_max_sessions: self.config.listnener_liveness_max_sessions,
+            firewall_rules,
+            bandwidth,
             user_agent: format!("tari/basenode/{}", env!("CARGO_PKG_VERSION")),
             // Also add sync peers to the peer seed list. Duplicates are acceptable.
             peer_seeds: self
@@ -270,6 +340,32 @@ where B: BlockchainBackend + 'static
             dns_seeds: self.config.dns_seeds.clone(),
             dns_seeds_name_server: self.config.dns_seeds_name_server,
             dns_seeds_use_dnssec: self.config.dns_seeds_use_dnssec,
-        }
+        })
     }
 }
+
+/// Parses protocol bandwidth limits of the form `<protocol>=<upload KiB/s>,<download KiB/s>`. A rate of 0 is unlimited.
+fn parse_protocol_bandwidth_limits(limits: &[String]) -> Result<HashMap<ProtocolId, BandwidthLimit>, String> {
+    let parse_rate = |rate: &str| {
+        rate.trim()
+            .parse::<usize>()
+            .map(|kib| Some(kib * 1024).filter(|r| *r > 0))
+            .map_err(|err| format!("invalid rate '{}': {}", rate, err))
+    };
+
+    limits
+        .iter()
+        .map(|limit| {
+            let (protocol, rates) = limit
+                .split_once('=')
+                .ok_or_else(|| format!("expected '<protocol>=<upload>,<download>' but got '{}'", limit))?;
+            let (upload, download) = rates
+                .split_once(',')
+                .ok_or_else(|| format!("expected '<upload>,<download>' rates for '{}'", protocol))?;
+            Ok((
+                ProtocolId::from(protocol.trim().as_bytes().to_vec()),
+                BandwidthLimit::new(parse_rate(upload)?, parse_rate(download)?),
+            ))
+        })
+        .collect()
+}
@@ -21,18 +21,23 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::LOG_TARGET;
-use crate::{builder::BaseNodeContext, status_line::StatusLine, table::Table, utils::format_duration_basic};
+use crate::{
+    builder::BaseNodeContext,
+    status_line::StatusLine,
+    table::Table,
+    utils::{format_bytes_basic, format_duration_basic},
+};
 use chrono::{DateTime, Utc};
 use log::*;
 use std::{
     cmp,
-    fs::File,
+    fs::{self, File},
     io::{self, Write},
     string::ToString,
     sync::Arc,
     time::{Duration, Instant},
 };
-use tari_app_utilities::consts;
+use tari_app_utilities::{consts, identity_management};
 use tari_common::GlobalConfig;
 use tari_common_types::{
     emoji::EmojiId,
@@ -40,11 +45,13 @@ use tari_common_types::{
 };
 use tari_comms::{
     connectivity::ConnectivityRequester,
-    peer_manager::{NodeId, Peer, PeerFeatures, PeerManager, PeerManagerError, PeerQuery},
+    peer_manager::{NodeId, Peer, PeerExport, PeerFeatures, PeerManager, PeerManagerError, PeerQuery},
     protocol::rpc::RpcServerHandle,
+    reachability::ReachabilityRequester,
+    tor::HiddenServiceRequester,
     NodeIdentity,
 };
-use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester, MetricsCollectorHandle};
+use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester, DhtRequester, MetricsCollectorHandle};
 use tari_core::{
     base_node::{
         comms_interface::BlockEvent,
@@ -79,11 +86,14 @@ pub struct CommandHandler {
     config: Arc<GlobalConfig>,
     blockchain_db: AsyncBlockchainDb<LMDBDatabase>,
     discovery_service: DhtDiscoveryRequester,
+    dht_requester: DhtRequester,
     dht_metrics_collector: MetricsCollectorHandle,
     rpc_server: RpcServerHandle,
     base_node_identity: Arc<NodeIdentity>,
     peer_manager: Arc<PeerManager>,
     connectivity: ConnectivityRequester,
+    reachability: ReachabilityRequester,
+    hidden_service: Option<HiddenServiceRequester>,
     liveness: LivenessHandle,
     node_service: LocalNodeCommsInterface,
     mempool_service: LocalMempoolService,
@@ -98,11 +108,14 @@ impl CommandHandler {
             config: ctx.config(),
             blockchain_db: ctx.blockchain_db().into(),
             discovery_service: ctx.base_node_dht().discovery_service_requester(),
+            dht_requester: ctx.base_node_dht().dht_requester(),
             dht_metrics_collector: ctx.base_node_dht().metrics_collector(),
             rpc_server: ctx.rpc_server(),
             base_node_identity: ctx.base_node_identity(),
             peer_manager: ctx.base_node_comms().peer_manager(),
             connectivity: ctx.base_node_comms().connectivity(),
+            reachability: ctx.base_node_comms().reachability(),
+            hidden_service: ctx.base_node_comms().hidden_service().map(|hs| hs.requester()),
             liveness: ctx.liveness(),
             node_service: ctx.local_node(),
             mempool_service: ctx.local_mempool(),
@@ -119,6 +132,7 @@ impl CommandHandler {
         let mut connectivity = self.connectivity.clone();
         let mut metrics = self.dht_metrics_collector.clone();
         let mut rpc_server = self.rpc_server.clone();
+        let reachability = self.reachability.get_report();
         let config = self.config.clone();
 
         self.executor.spawn(async move {
@@ -164,6 +178,7 @@ impl CommandHandler {
             status_line.add_field("Connections", conns.len());
             let banned_peers = fetch_banned_peers(&peer_manager).await.unwrap();
             status_line.add_field("Banned", banned_peers.len());
+            status_line.add_field("Reachability", reachability.status);
 
             let num_messages = metrics
                 .get_total_message_count_in_timespan(Duration::from_secs(60))
@@ -699,6 +714,8 @@ impl CommandHandler {
                         "Address",
                         "Direction",
                         "Age",
+                        "Sent",
+                        "Received",
                         "Role",
                         "User Agent",
                         "Info",
@@ -713,6 +730,7 @@ impl CommandHandler {
                             .get_metadata(1)
                             .and_then(|v| bincode::deserialize::<PeerMetadata>(v).ok())
                             .map(|metadata| format!("height: {}", metadata.metadata.height_of_longest_chain()));
+                        let bandwidth = conn.bandwidth_stats().total;
 
                         table.add_row(row![
                             peer.node_id,
@@ -720,6 +738,8 @@ impl CommandHandler {
                             conn.address(),
                             conn.direction(),
                             format_duration_basic(conn.age()),
+                            format_bytes_basic(bandwidth.bytes_sent),
+                            format_bytes_basic(bandwidth.bytes_received),
                             {
                                 if peer.features == PeerFeatures::COMMUNICATION_CLIENT {
                                     "Wallet"
@@ -750,6 +770,96 @@ impl CommandHandler {
         });
     }
 
+    /// Function to process the list-reputation command
+    pub fn list_reputation(&self) {
+        let mut connectivity = self.connectivity.clone();
+
+        self.executor.spawn(async move {
+            match connectivity.get_reputation_scores().await {
+                Ok(scores) if scores.is_empty() => {
+                    println!("No peers have a reputation score yet.");
+                },
+                Ok(scores) => {
+                    println!();
+                    let num_scores = scores.len();
+                    let mut table = Table::new();
+                    table.set_titles(vec!["NodeId", "Public Key", "Score", "Bans", "Last Change", "Status"]);
+                    let now = Utc::now().naive_utc();
+                    for score in scores {
+                        table.add_row(row![
+                            score.node_id,
+                            score.public_key,
+                            format!("{:.1}", score.score),
+                            score.num_bans,
+                            score
+                                .updated_at
+                                .and_then(|dt| (now - dt).to_std().ok())
+                                .map(|d| format!("{} ago", format_duration_basic(d)))
+                                .unwrap_or_else(|| "--".to_string()),
+                            if score.is_banned { "Banned" } else { "" },
+                        ]);
+                    }
+
+                    table.print_stdout();
+
+                    println!("{} scored peer(s)", num_scores);
+                },
+                Err(err) => {
+                    println!("Failed to list reputation scores: {:?}", err);
+                    error!(target: LOG_TARGET, "Could not list reputation scores: {:?}", err);
+                },
+            }
+        });
+    }
+
+    pub fn export_peers(&self, filename: String) {
+        let peer_manager = self.peer_manager.clone();
+
+        self.executor.spawn(async move {
+            let export = try_or_print!(peer_manager.export_peers().await);
+            let json = try_or_print!(export.to_json());
+            let mut file = try_or_print!(File::create(&filename));
+            try_or_print!(file.write_all(json.as_bytes()));
+            println!("Exported {} peer(s) to {}", export.peers.len(), filename);
+        });
+    }
+
+    pub fn import_peers(&self, filename: String) {
+        let peer_manager = self.peer_manager.clone();
+        let our_public_key = self.base_node_identity.public_key().clone();
+
+        self.executor.spawn(async move {
+            let json = try_or_print!(fs::read_to_string(&filename));
+            let mut export = try_or_print!(PeerExport::from_json(&json));
+            export.peers.retain(|peer| peer.public_key != our_public_key);
+            match peer_manager.import_peers(export).await {
+                Ok(num_added) => {
+                    println!("Imported {} new peer(s) from {}", num_added, filename);
+                },
+                Err(err) => {
+                    println!("Failed to import peers: {}", err);
+                    error!(target: LOG_TARGET, "Could not import peers: {:?}", err);
+                },
+            }
+        });
+    }
+
+    pub fn compact_peers(&self) {
+        let mut connectivity = self.connectivity.clone();
+
+        self.executor.spawn(async move {
+            match connectivity.compact_peers().await {
+                Ok(num_removed) => {
+                    println!("Removed {} inactive peer(s) from the peer database", num_removed);
+                },
+                Err(err) => {
+                    println!("Failed to compact the peer database: {:?}", err);
+                    error!(target: LOG_TARGET, "Could not compact peers: {:?}", err);
+                },
+            }
+        });
+    }
+
     pub fn reset_offline_peers(&self) {
         let peer_manager = self.peer_manager.clone();
         self.executor.spawn(async move {
@@ -1107,9 +1217,56 @@ impl CommandHandler {
         });
     }
 
+    /// Function to process the rotate-tor-identity command
+    pub fn rotate_tor_identity(&self) {
+        let hidden_service = match self.hidden_service.clone() {
+            Some(hidden_service) => hidden_service,
+            None => {
+                println!("The node is not using the tor transport");
+                return;
+            },
+        };
+        let node_identity = self.base_node_identity.clone();
+        let mut dht = self.dht_requester.clone();
+        let config = self.config.clone();
+
+        self.executor.spawn(async move {
+            let identities = try_or_print!(hidden_service.get_identities().await);
+            // The first identity is always the comms hidden service
+            let service_id = match identities.into_iter().next() {
+                Some(identity) => identity.service_id,
+                None => {
+                    println!("The node has no hidden service");
+                    return;
+                },
+            };
+            let identity = try_or_print!(hidden_service.rotate_identity(service_id).await);
+            let public_address = try_or_print!(identity.try_get_onion_address());
+            node_identity.set_public_address(public_address.clone());
+            try_or_print!(identity_management::save_as_json(
+                &config.base_node_tor_identity_file,
+                &identity
+            ));
+            try_or_print!(identity_management::save_as_json(
+                &config.base_node_identity_file,
+                &*node_identity
+            ));
+            println!(
+                "Tor identity rotated. The node's public address is now {}",
+                public_address
+            );
+
+            // Let the network know about the new address
+            if let Err(err) = dht.send_join().await {
+                println!("Failed to announce the new address: {}", err);
+            }
+        });
+    }
+
     /// Function to process the whoami command
     pub fn whoami(&self) {
         println!("{}", self.base_node_identity);
+        println!("{}", self.reachability.get_report());
     }
 
     pub(crate) fn get_software_updater(&self) -> SoftwareUpdaterHandle {
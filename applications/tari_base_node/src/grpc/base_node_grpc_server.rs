@@ -1112,6 +1112,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
             .map_err(|err| Status::internal(err.to_string()))?;
 
         let mut peers = Vec::with_capacity(connected_peers.len());
+        let mut connection_bandwidth = Vec::with_capacity(connected_peers.len());
         for peer in connected_peers {
             peers.push(
                 peer_manager
@@ -1119,10 +1120,33 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
                     .await
                     .map_err(|err| Status::internal(err.to_string()))?,
             );
+            connection_bandwidth.push(tari_rpc::ConnectionBandwidth {
+                node_id: peer.peer_node_id().to_vec(),
+                stats: Some(peer.bandwidth_stats().into()),
+            });
         }
 
         let resp = tari_rpc::ListConnectedPeersResponse {
             connected_peers: peers.into_iter().map(Into::into).collect(),
+            connection_bandwidth,
+        };
+
+        Ok(Response::new(resp))
+    }
+
+    async fn list_peer_reputation(
+        &self,
+        _: Request<tari_rpc::Empty>,
+    ) -> Result<Response<tari_rpc::ListPeerReputationResponse>, Status> {
+        let scores = self
+            .comms
+            .connectivity()
+            .get_reputation_scores()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let resp = tari_rpc::ListPeerReputationResponse {
+            peers: scores.into_iter().map(Into::into).collect(),
         };
 
         Ok(Response::new(resp))
@@ -1144,6 +1168,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
             unconfirmed_txs: mempool_stats.unconfirmed_txs as u64,
             reorg_txs: mempool_stats.reorg_txs as u64,
             total_weight: mempool_stats.total_weight,
+            min_fee_per_gram: mempool_stats.min_fee_per_gram.as_u64(),
         };
 
         Ok(Response::new(response))
@@ -116,6 +116,7 @@ use tari_app_utilities::{
 use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, GlobalConfig};
 use tari_comms::{peer_manager::PeerFeatures, tor::HiddenServiceControllerError};
 use tari_core::chain_storage::ChainStorageError;
+use tari_metrics::server::MetricsServer;
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
     runtime,
@@ -231,6 +232,10 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
         task::spawn(run_grpc(grpc, node_config.grpc_base_node_address, shutdown.to_signal()));
     }
 
+    if let Some(address) = node_config.base_node_metrics_server_address {
+        task::spawn(run_metrics_server(address, shutdown.to_signal()));
+    }
+
     // Run, node, run!
     let command_handler = Arc::new(CommandHandler::new(runtime::Handle::current(), &ctx));
     if bootstrap.non_interactive_mode {
@@ -303,6 +308,16 @@ async fn run_grpc(
     Ok(())
 }
 
+/// Serves the Prometheus metrics of the node
+async fn run_metrics_server(address: SocketAddr, interrupt_signal: ShutdownSignal) {
+    if let Err(err) = MetricsServer::new(address)
+        .run_with_shutdown(interrupt_signal.map(|_| ()))
+        .await
+    {
+        error!(target: LOG_TARGET, "Metrics server on {} stopped: {}", address, err);
+    }
+}
+
 async fn read_command(mut rustyline: Editor<Parser>) -> Result<(String, Editor<Parser>), String> {
     task::spawn_blocking(|| {
         let readline = rustyline.readline(">> ");
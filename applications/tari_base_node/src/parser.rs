@@ -69,6 +69,10 @@ pub enum BaseNodeCommand {
     UnbanPeer,
     UnbanAllPeers,
     ListBannedPeers,
+    ListReputation,
+    ExportPeers,
+    ImportPeers,
+    CompactPeers,
     ListConnections,
     ListHeaders,
     CheckDb,
@@ -83,6 +87,7 @@ pub enum BaseNodeCommand {
     GetMempoolStats,
     GetMempoolState,
     Whoami,
+    RotateTorIdentity,
     GetStateInfo,
     Quit,
     Exit,
@@ -236,6 +241,18 @@ impl Parser {
             ListBannedPeers => {
                 self.command_handler.list_banned_peers();
             },
+            ListReputation => {
+                self.command_handler.list_reputation();
+            },
+            ExportPeers => {
+                self.process_export_peers(args);
+            },
+            ImportPeers => {
+                self.process_import_peers(args);
+            },
+            CompactPeers => {
+                self.command_handler.compact_peers();
+            },
             ListConnections => {
                 self.command_handler.list_connections();
             },
@@ -263,6 +280,9 @@ impl Parser {
             Whoami => {
                 self.command_handler.whoami();
             },
+            RotateTorIdentity => {
+                self.command_handler.rotate_tor_identity();
+            },
             Exit | Quit => {
                 println!("Shutting down...");
                 info!(
@@ -336,6 +356,22 @@ impl Parser {
             ListBannedPeers => {
                 println!("Lists peers that have been banned by the node or wallet");
             },
+            ListReputation => {
+                println!("Lists the reputation scores of peers, highest score first");
+            },
+            ExportPeers => {
+                println!("Exports the known, non-banned peers to a portable JSON file");
+                println!("export-peers (file)");
+                println!("e.g.");
+                println!("export-peers peers.json");
+            },
+            ImportPeers => {
+                println!("Imports peers from a file created by export-peers");
+                println!("import-peers [file]");
+            },
+            CompactPeers => {
+                println!("Removes long-inactive, low-value peers from the peer database");
+            },
             CheckDb => {
                 println!("Checks the blockchain database for missing blocks and headers");
             },
@@ -406,8 +442,14 @@ impl Parser {
             },
             Whoami => {
                 println!(
-                    "Display identity information about this node, including: public key, node ID and the public \
-                     address"
+                    "Display identity information about this node, including: public key, node ID, the public address \
+                     and whether the public address is reachable by other peers"
+                );
+            },
+            RotateTorIdentity => {
+                println!(
+                    "Replaces the node's tor hidden service key with a new key. The node will be reachable at a new \
+                     onion address and the previous address will stop working"
                 );
             },
             Exit | Quit => {
@@ -707,6 +749,19 @@ impl Parser {
             .save_header_stats(start_height, end_height, filename, algo)
     }
 
+    fn process_export_peers<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let filename = args.next().unwrap_or("peers.json").to_string();
+        self.command_handler.export_peers(filename);
+    }
+
+    fn process_import_peers<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let filename = try_or_print!(args.next().ok_or_else(|| {
+            self.print_help(BaseNodeCommand::ImportPeers);
+            "No file provided".to_string()
+        }));
+        self.command_handler.import_peers(filename.to_string());
+    }
+
     fn process_rewind_blockchain<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
         let new_height = try_or_print!(args
             .next()
@@ -37,6 +37,20 @@ pub fn format_duration_basic(duration: Duration) -> String {
     }
 }
 
+pub fn format_bytes_basic(num_bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if num_bytes < 1024 {
+        return format!("{} B", num_bytes);
+    }
+    let mut value = num_bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -50,4 +64,13 @@ mod test {
         let s = format_duration_basic(Duration::from_secs(9 * 60 * 60 + 35 * 60 + 45));
         assert_eq!(s, "9h 35m 45s");
     }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes_basic(0), "0 B");
+        assert_eq!(format_bytes_basic(1023), "1023 B");
+        assert_eq!(format_bytes_basic(1536), "1.5 KiB");
+        assert_eq!(format_bytes_basic(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_bytes_basic(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
 }
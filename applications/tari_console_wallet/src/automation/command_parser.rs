@@ -33,7 +33,10 @@ use tari_app_utilities::utilities::parse_emoji_id_or_public_key;
 use tari_comms::multiaddr::Multiaddr;
 
 use tari_common_types::types::PublicKey;
-use tari_core::transactions::tari_amount::MicroTari;
+use tari_core::{
+    tari_utilities::hex::{from_hex, Hex},
+    transactions::tari_amount::MicroTari,
+};
 
 #[derive(Debug)]
 pub struct ParsedCommand {
@@ -48,6 +51,15 @@ impl Display for ParsedCommand {
             GetBalance => "get-balance",
             SendTari => "send-tari",
             SendOneSided => "send-one-sided",
+            SendTimeLocked => "send-time-locked",
+            ScheduleSend => "schedule-send",
+            ListScheduled => "list-scheduled",
+            CancelScheduled => "cancel-scheduled",
+            ListHeldTransactions => "list-held-transactions",
+            ApproveHeldTransaction => "approve-held-transaction",
+            RejectHeldTransaction => "reject-held-transaction",
+            CreateBackup => "create-backup",
+            VerifyBackup => "verify-backup",
             MakeItRain => "make-it-rain",
             CoinSplit => "coin-split",
             DiscoverPeer => "discover-peer",
@@ -58,6 +70,41 @@ impl Display for ParsedCommand {
             SetBaseNode => "set-base-node",
             SetCustomBaseNode => "set-custom-base-node",
             ClearCustomBaseNode => "clear-custom-base-node",
+            PrepareOfflineTx => "prepare-offline-tx",
+            SignOfflineTx => "sign-offline-tx",
+            RelayOfflineTx => "relay-offline-tx",
+            ExportOfflineReply => "export-offline-reply",
+            FinalizeOfflineTx => "finalize-offline-tx",
+            ImportSignedTx => "import-signed-tx",
+            CancelOfflineTx => "cancel-offline-tx",
+            MultisigNewParticipant => "multisig-new-participant",
+            MultisigCreate => "multisig-create",
+            MultisigImportWallet => "multisig-import-wallet",
+            MultisigBalance => "multisig-balance",
+            MultisigFund => "multisig-fund",
+            MultisigImportUpdate => "multisig-import-update",
+            MultisigPropose => "multisig-propose",
+            MultisigCommitNonces => "multisig-commit-nonces",
+            MultisigAddCommitment => "multisig-add-commitment",
+            MultisigRevealNonces => "multisig-reveal-nonces",
+            MultisigAddNonces => "multisig-add-nonces",
+            MultisigSign => "multisig-sign",
+            MultisigAddSignatures => "multisig-add-signatures",
+            MultisigCancel => "multisig-cancel",
+            HtlcPreimage => "htlc-preimage",
+            HtlcAddPreimage => "htlc-add-preimage",
+            HtlcSend => "htlc-send",
+            HtlcImport => "htlc-import",
+            HtlcList => "htlc-list",
+            HtlcSpend => "htlc-spend",
+            CreateAccount => "create-account",
+            ListAccounts => "list-accounts",
+            SendFromAccount => "send-from-account",
+            TransferBetweenAccounts => "transfer-between-accounts",
+            AccountHistory => "account-history",
+            CreatePaymentRequest => "create-payment-request",
+            ListPaymentRequests => "list-payment-requests",
+            PayRequest => "pay-request",
         };
 
         let args = self
@@ -83,6 +130,10 @@ pub enum ParsedArgument {
     CSVFileName(String),
     Address(Multiaddr),
     Negotiated(bool),
+    FileName(String),
+    PublicKeys(Vec<PublicKey>),
+    Bytes(Vec<u8>),
+    Confirmed(bool),
 }
 
 impl Display for ParsedArgument {
@@ -99,6 +150,10 @@ impl Display for ParsedArgument {
             CSVFileName(v) => write!(f, "{}", v.to_string()),
             Address(v) => write!(f, "{}", v.to_string()),
             Negotiated(v) => write!(f, "{}", v.to_string()),
+            FileName(v) => write!(f, "{}", v.to_string()),
+            PublicKeys(v) => write!(f, "{}", v.iter().map(|k| k.to_hex()).collect::<Vec<String>>().join(",")),
+            Bytes(v) => write!(f, "{}", v.to_hex()),
+            Confirmed(v) => write!(f, "{}", v.to_string()),
         }
     }
 }
@@ -115,6 +170,15 @@ pub fn parse_command(command: &str) -> Result<ParsedCommand, ParseError> {
         GetBalance => Vec::new(),
         SendTari => parse_send_tari(args)?,
         SendOneSided => parse_send_tari(args)?,
+        SendTimeLocked => parse_send_time_locked(args)?,
+        ScheduleSend => parse_schedule_send(args)?,
+        ListScheduled => Vec::new(),
+        CancelScheduled => parse_tx_id(args)?,
+        ListHeldTransactions => Vec::new(),
+        ApproveHeldTransaction => parse_tx_id(args)?,
+        RejectHeldTransaction => parse_tx_id(args)?,
+        CreateBackup => parse_output_file(args)?,
+        VerifyBackup => parse_input_file(args)?,
         MakeItRain => parse_make_it_rain(args)?,
         CoinSplit => parse_coin_split(args)?,
         DiscoverPeer => parse_public_key(args)?,
@@ -125,6 +189,41 @@ pub fn parse_command(command: &str) -> Result<ParsedCommand, ParseError> {
         SetBaseNode => parse_public_key_and_address(args)?,
         SetCustomBaseNode => parse_public_key_and_address(args)?,
         ClearCustomBaseNode => Vec::new(),
+        PrepareOfflineTx => parse_prepare_offline_tx(args)?,
+        SignOfflineTx => parse_sign_offline_tx(args)?,
+        RelayOfflineTx => parse_input_file(args)?,
+        ExportOfflineReply => parse_export_offline_reply(args)?,
+        FinalizeOfflineTx => parse_input_and_output_files(args)?,
+        ImportSignedTx => parse_input_file(args)?,
+        CancelOfflineTx => parse_tx_id(args)?,
+        MultisigNewParticipant => parse_output_file(args)?,
+        MultisigCreate => parse_multisig_create(args)?,
+        MultisigImportWallet => parse_input_file(args)?,
+        MultisigBalance => parse_multisig_balance(args)?,
+        MultisigFund => parse_multisig_fund(args)?,
+        MultisigImportUpdate => parse_input_file(args)?,
+        MultisigPropose => parse_multisig_propose(args)?,
+        MultisigCommitNonces => parse_input_and_output_files(args)?,
+        MultisigAddCommitment => parse_input_and_output_files(args)?,
+        MultisigRevealNonces => parse_input_and_output_files(args)?,
+        MultisigAddNonces => parse_input_and_output_files(args)?,
+        MultisigSign => parse_input_and_output_files(args)?,
+        MultisigAddSignatures => parse_input_and_output_files(args)?,
+        MultisigCancel => parse_tx_id(args)?,
+        HtlcPreimage => Vec::new(),
+        HtlcAddPreimage => parse_htlc_add_preimage(args)?,
+        HtlcSend => parse_htlc_send(args)?,
+        HtlcImport => parse_input_file(args)?,
+        HtlcList => Vec::new(),
+        HtlcSpend => parse_htlc_spend(args)?,
+        CreateAccount => parse_account_name(args)?,
+        ListAccounts => Vec::new(),
+        SendFromAccount => parse_send_from_account(args)?,
+        TransferBetweenAccounts => parse_transfer_between_accounts(args)?,
+        AccountHistory => parse_account_name(args)?,
+        CreatePaymentRequest => parse_create_payment_request(args)?,
+        ListPaymentRequests => Vec::new(),
+        PayRequest => parse_pay_request(args)?,
     };
 
     Ok(ParsedCommand { command, args })
@@ -232,19 +331,225 @@ fn parse_make_it_rain(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>,
     parsed_args.push(ParsedArgument::PublicKey(pubkey));
 
     // transaction type
+    let negotiated = parse_transaction_type(&mut args)?;
+    parsed_args.push(ParsedArgument::Negotiated(negotiated));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_send_tari(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // public key/emoji id
+    let pubkey = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("public key or emoji id".to_string()))?;
+    let pubkey = parse_emoji_id_or_public_key(pubkey).ok_or(ParseError::PublicKey)?;
+    parsed_args.push(ParsedArgument::PublicKey(pubkey));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_account_name(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let name = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("account name".to_string()))?;
+    Ok(vec![ParsedArgument::Text(name.to_string())])
+}
+
+fn parse_send_from_account(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // account
+    let account = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("account name".to_string()))?;
+    parsed_args.push(ParsedArgument::Text(account.to_string()));
+
+    // amount, public key/emoji id and message
+    parsed_args.extend(parse_send_tari(args)?);
+
+    Ok(parsed_args)
+}
+
+fn parse_transfer_between_accounts(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // from account
+    let from = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("from account name".to_string()))?;
+    parsed_args.push(ParsedArgument::Text(from.to_string()));
+
+    // to account
+    let to = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("to account name".to_string()))?;
+    parsed_args.push(ParsedArgument::Text(to.to_string()));
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_create_payment_request(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // expiry height
+    let expiry = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("expiry height".to_string()))?;
+    let expiry = expiry.parse::<u64>().map_err(ParseError::Int)?;
+    parsed_args.push(ParsedArgument::Int(expiry));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_pay_request(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // transaction type
+    let txn_type = parse_transaction_type(&mut args)?;
+    parsed_args.push(ParsedArgument::Negotiated(txn_type));
+
+    // payment request string or tari:// URI
+    let request = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("payment request".to_string()))?;
+    parsed_args.push(ParsedArgument::Text(request.to_string()));
+
+    Ok(parsed_args)
+}
+
+fn parse_send_time_locked(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // public key/emoji id
+    let pubkey = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("public key or emoji id".to_string()))?;
+    let pubkey = parse_emoji_id_or_public_key(pubkey).ok_or(ParseError::PublicKey)?;
+    parsed_args.push(ParsedArgument::PublicKey(pubkey));
+
+    // maturity
+    let maturity = args.next().ok_or_else(|| ParseError::Empty("maturity".to_string()))?;
+    let maturity = maturity.parse::<u64>().map_err(ParseError::Int)?;
+    parsed_args.push(ParsedArgument::Int(maturity));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_schedule_send(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // public key/emoji id
+    let pubkey = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("public key or emoji id".to_string()))?;
+    let pubkey = parse_emoji_id_or_public_key(pubkey).ok_or(ParseError::PublicKey)?;
+    parsed_args.push(ParsedArgument::PublicKey(pubkey));
+
+    // block height or utc time
+    let schedule = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("block height or time".to_string()))?;
+    match schedule.parse::<u64>() {
+        Ok(height) => parsed_args.push(ParsedArgument::Int(height)),
+        Err(_) => {
+            let time = parse_date_string(schedule, Utc::now(), Dialect::Uk).map_err(ParseError::Date)?;
+            parsed_args.push(ParsedArgument::Date(time));
+        },
+    }
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_transaction_type(args: &mut SplitWhitespace) -> Result<bool, ParseError> {
     let txn_type = args
         .next()
         .ok_or_else(|| ParseError::Empty("transaction type".to_string()))?;
-    let negotiated = match txn_type {
-        "negotiated" => true,
-        "one_sided" => false,
+    match txn_type {
+        "negotiated" => Ok(true),
+        "one_sided" => Ok(false),
         _ => {
             println!("Invalid data provided for <transaction type>, must be 'negotiated' or 'one_sided'\n");
-            return Err(ParseError::Invalid(
+            Err(ParseError::Invalid(
                 "Invalid data provided for <transaction type>, must be 'negotiated' or 'one_sided'".to_string(),
-            ));
+            ))
         },
-    };
+    }
+}
+
+fn parse_prepare_offline_tx(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // output file
+    let file_name = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(file_name.to_string()));
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // public key/emoji id
+    let pubkey = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("public key or emoji id".to_string()))?;
+    let pubkey = parse_emoji_id_or_public_key(pubkey).ok_or(ParseError::PublicKey)?;
+    parsed_args.push(ParsedArgument::PublicKey(pubkey));
+
+    // transaction type
+    let negotiated = parse_transaction_type(&mut args)?;
     parsed_args.push(ParsedArgument::Negotiated(negotiated));
 
     // message
@@ -254,9 +559,205 @@ fn parse_make_it_rain(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>,
     Ok(parsed_args)
 }
 
-fn parse_send_tari(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+fn parse_input_file(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let file_name = args.next().ok_or_else(|| ParseError::Empty("input file".to_string()))?;
+    Ok(vec![ParsedArgument::FileName(file_name.to_string())])
+}
+
+fn parse_input_and_output_files(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    let in_file = args.next().ok_or_else(|| ParseError::Empty("input file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(in_file.to_string()));
+
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
+    Ok(parsed_args)
+}
+
+fn parse_sign_offline_tx(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    let in_file = args.next().ok_or_else(|| ParseError::Empty("input file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(in_file.to_string()));
+
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
+    // Signing is confirmed on the console unless --yes is given
+    let confirmed = match args.next() {
+        None => false,
+        Some("--yes") => true,
+        Some(v) => return Err(ParseError::Invalid(format!("Unexpected argument '{}'", v))),
+    };
+    parsed_args.push(ParsedArgument::Confirmed(confirmed));
+
+    Ok(parsed_args)
+}
+
+fn parse_tx_id(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let tx_id = args.next().ok_or_else(|| ParseError::Empty("tx_id".to_string()))?;
+    let tx_id = tx_id.parse::<u64>().map_err(ParseError::Int)?;
+    Ok(vec![ParsedArgument::Int(tx_id)])
+}
+
+fn parse_export_offline_reply(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    let tx_id = args.next().ok_or_else(|| ParseError::Empty("tx_id".to_string()))?;
+    let tx_id = tx_id.parse::<u64>().map_err(ParseError::Int)?;
+    parsed_args.push(ParsedArgument::Int(tx_id));
+
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
+    Ok(parsed_args)
+}
+
+fn parse_output_file(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let file_name = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    Ok(vec![ParsedArgument::FileName(file_name.to_string())])
+}
+
+fn parse_multisig_wallet_id(args: &mut SplitWhitespace) -> Result<ParsedArgument, ParseError> {
+    let wallet_id = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("multisig wallet id".to_string()))?;
+    let wallet_id = wallet_id.parse::<u64>().map_err(ParseError::Int)?;
+    Ok(ParsedArgument::Int(wallet_id))
+}
+
+fn parse_multisig_create(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
     let mut parsed_args = Vec::new();
 
+    // output file
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
+    // threshold
+    let threshold = args.next().ok_or_else(|| ParseError::Empty("threshold".to_string()))?;
+    let threshold = threshold.parse::<u64>().map_err(ParseError::Int)?;
+    parsed_args.push(ParsedArgument::Int(threshold));
+
+    // participant files
+    for file_name in args {
+        parsed_args.push(ParsedArgument::FileName(file_name.to_string()));
+    }
+    if parsed_args.len() < 4 {
+        return Err(ParseError::Invalid(
+            "A multisig wallet requires at least two participant files".to_string(),
+        ));
+    }
+
+    Ok(parsed_args)
+}
+
+fn parse_multisig_balance(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    Ok(vec![parse_multisig_wallet_id(&mut args)?])
+}
+
+fn parse_multisig_fund(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // output file
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
+    // multisig wallet id
+    parsed_args.push(parse_multisig_wallet_id(&mut args)?);
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_multisig_propose(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // output file
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
+    // multisig wallet id
+    parsed_args.push(parse_multisig_wallet_id(&mut args)?);
+
+    // amount
+    let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
+    let amount = MicroTari::from_str(amount)?;
+    parsed_args.push(ParsedArgument::Amount(amount));
+
+    // destination public key/emoji id
+    let pubkey = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("public key or emoji id".to_string()))?;
+    let pubkey = parse_emoji_id_or_public_key(pubkey).ok_or(ParseError::PublicKey)?;
+    parsed_args.push(ParsedArgument::PublicKey(pubkey));
+
+    // comma separated signer key shares
+    let signers = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("signer key shares".to_string()))?;
+    let signers = signers
+        .split(',')
+        .map(|key| parse_emoji_id_or_public_key(key).ok_or(ParseError::PublicKey))
+        .collect::<Result<Vec<_>, _>>()?;
+    parsed_args.push(ParsedArgument::PublicKeys(signers));
+
+    // message
+    let message = args.collect::<Vec<&str>>().join(" ");
+    parsed_args.push(ParsedArgument::Text(message));
+
+    Ok(parsed_args)
+}
+
+fn parse_hex_32(args: &mut SplitWhitespace, name: &str) -> Result<ParsedArgument, ParseError> {
+    let hex = args.next().ok_or_else(|| ParseError::Empty(name.to_string()))?;
+    let bytes = from_hex(hex).map_err(|_| ParseError::Invalid(format!("{} is not valid hex", name)))?;
+    if bytes.len() != 32 {
+        return Err(ParseError::Invalid(format!("{} must be 32 bytes", name)));
+    }
+    Ok(ParsedArgument::Bytes(bytes))
+}
+
+fn parse_htlc_add_preimage(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    Ok(vec![parse_hex_32(&mut args, "preimage")?])
+}
+
+fn parse_htlc_spend(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    Ok(vec![parse_hex_32(&mut args, "commitment")?])
+}
+
+fn parse_htlc_send(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, ParseError> {
+    let mut parsed_args = Vec::new();
+
+    // output file
+    let out_file = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("output file".to_string()))?;
+    parsed_args.push(ParsedArgument::FileName(out_file.to_string()));
+
     // amount
     let amount = args.next().ok_or_else(|| ParseError::Empty("amount".to_string()))?;
     let amount = MicroTari::from_str(amount)?;
@@ -269,6 +770,16 @@ fn parse_send_tari(mut args: SplitWhitespace) -> Result<Vec<ParsedArgument>, Par
     let pubkey = parse_emoji_id_or_public_key(pubkey).ok_or(ParseError::PublicKey)?;
     parsed_args.push(ParsedArgument::PublicKey(pubkey));
 
+    // hash lock
+    parsed_args.push(parse_hex_32(&mut args, "hash lock")?);
+
+    // timeout height
+    let timeout = args
+        .next()
+        .ok_or_else(|| ParseError::Empty("timeout height".to_string()))?;
+    let timeout = timeout.parse::<u64>().map_err(ParseError::Int)?;
+    parsed_args.push(ParsedArgument::Int(timeout));
+
     // message
     let message = args.collect::<Vec<&str>>().join(" ");
     parsed_args.push(ParsedArgument::Text(message));
@@ -480,5 +991,246 @@ mod test {
                 _ => panic!("Expected parsing <transaction type> to return an error here"),
             },
         }
+
+        let command_str = format!(
+            "prepare-offline-tx unsigned.json 10T {} one_sided {}",
+            public_key, message
+        );
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::FileName(file) = parsed.args[0].clone() {
+            assert_eq!(file, "unsigned.json".to_string());
+        } else {
+            panic!("Parsed output file name is not the same as provided.");
+        }
+        if let ParsedArgument::PublicKey(pk) = parsed.args[2].clone() {
+            assert_eq!(pk, public_key);
+        } else {
+            panic!("Parsed public key is not the same as provided.");
+        }
+        if let ParsedArgument::Negotiated(negotiated) = parsed.args[3].clone() {
+            assert!(!negotiated);
+        } else {
+            panic!("Parsed <transaction type> is not the same as provided.");
+        }
+
+        let command_str = "sign-offline-tx unsigned.json";
+        let parsed = parse_command(command_str);
+        assert!(parsed.is_err());
+
+        let command_str = "sign-offline-tx unsigned.json signed.json";
+        let parsed = parse_command(command_str).unwrap();
+
+        if let ParsedArgument::FileName(file) = parsed.args[1].clone() {
+            assert_eq!(file, "signed.json".to_string());
+        } else {
+            panic!("Parsed output file name is not the same as provided.");
+        }
+        if let ParsedArgument::Confirmed(confirmed) = parsed.args[2].clone() {
+            assert!(!confirmed);
+        } else {
+            panic!("Parsed confirmation is not the same as provided.");
+        }
+
+        let command_str = "sign-offline-tx unsigned.json signed.json --yes";
+        let parsed = parse_command(command_str).unwrap();
+        if let ParsedArgument::Confirmed(confirmed) = parsed.args[2].clone() {
+            assert!(confirmed);
+        } else {
+            panic!("Parsed confirmation is not the same as provided.");
+        }
+
+        let command_str = "sign-offline-tx unsigned.json signed.json --no";
+        assert!(parse_command(command_str).is_err());
+
+        let command_str = "export-offline-reply 123 reply.json";
+        let parsed = parse_command(command_str).unwrap();
+
+        if let ParsedArgument::Int(tx_id) = parsed.args[0].clone() {
+            assert_eq!(tx_id, 123);
+        } else {
+            panic!("Parsed tx_id is not the same as provided.");
+        }
+
+        let command_str = "multisig-create wallet.json 2 alice.json";
+        let parsed = parse_command(command_str);
+        assert!(parsed.is_err());
+
+        let command_str = "multisig-create wallet.json 2 alice.json bob.json carol.json";
+        let parsed = parse_command(command_str).unwrap();
+
+        if let ParsedArgument::Int(threshold) = parsed.args[1].clone() {
+            assert_eq!(threshold, 2);
+        } else {
+            panic!("Parsed threshold is not the same as provided.");
+        }
+        assert_eq!(parsed.args.len(), 5);
+
+        let (_secret_key, signer_a) = PublicKey::random_keypair(&mut OsRng);
+        let (_secret_key, signer_b) = PublicKey::random_keypair(&mut OsRng);
+        let command_str = format!(
+            "multisig-propose proposal.json 123 10T {} {},{} {}",
+            public_key, signer_a, signer_b, message
+        );
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::Int(wallet_id) = parsed.args[1].clone() {
+            assert_eq!(wallet_id, 123);
+        } else {
+            panic!("Parsed multisig wallet id is not the same as provided.");
+        }
+        if let ParsedArgument::PublicKeys(signers) = parsed.args[4].clone() {
+            assert_eq!(signers, vec![signer_a, signer_b]);
+        } else {
+            panic!("Parsed signer key shares are not the same as provided.");
+        }
+        if let ParsedArgument::Text(msg) = parsed.args[5].clone() {
+            assert_eq!(msg, message);
+        } else {
+            panic!("Parsed message is not the same as provided.");
+        }
+
+        let command_str = format!("multisig-propose proposal.json 123 10T {} not_a_key", public_key);
+        let parsed = parse_command(&command_str);
+        assert!(parsed.is_err());
+
+        let hash_lock = "11".repeat(32);
+        let command_str = format!("htlc-send htlc.json 10T {} {} 1000 {}", public_key, hash_lock, message);
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::Bytes(bytes) = parsed.args[3].clone() {
+            assert_eq!(bytes, vec![0x11u8; 32]);
+        } else {
+            panic!("Parsed hash lock is not the same as provided.");
+        }
+        if let ParsedArgument::Int(timeout) = parsed.args[4].clone() {
+            assert_eq!(timeout, 1000);
+        } else {
+            panic!("Parsed timeout height is not the same as provided.");
+        }
+
+        let command_str = format!("htlc-send htlc.json 10T {} 1111 1000 {}", public_key, message);
+        let parsed = parse_command(&command_str);
+        assert!(parsed.is_err());
+
+        let command_str = "htlc-add-preimage not_hex";
+        let parsed = parse_command(command_str);
+        assert!(parsed.is_err());
+
+        let command_str = format!("send-time-locked 10T {} 5000 {}", public_key, message);
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::Int(maturity) = parsed.args[2].clone() {
+            assert_eq!(maturity, 5000);
+        } else {
+            panic!("Parsed maturity is not the same as provided.");
+        }
+        if let ParsedArgument::Text(msg) = parsed.args[3].clone() {
+            assert_eq!(msg, message);
+        } else {
+            panic!("Parsed message is not the same as provided.");
+        }
+
+        let command_str = format!("schedule-send 10T {} 5000 {}", public_key, message);
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::Int(height) = parsed.args[2].clone() {
+            assert_eq!(height, 5000);
+        } else {
+            panic!("Parsed schedule height is not the same as provided.");
+        }
+
+        let command_str = format!("schedule-send 10T {} 2031-06-01T12:00:00Z {}", public_key, message);
+        let parsed = parse_command(&command_str).unwrap();
+
+        if let ParsedArgument::Date(time) = parsed.args[2].clone() {
+            assert_eq!(time.to_rfc3339(), "2031-06-01T12:00:00+00:00");
+        } else {
+            panic!("Parsed schedule time is not the same as provided.");
+        }
+
+        let command_str = format!("schedule-send 10T {} not_a_time {}", public_key, message);
+        let parsed = parse_command(&command_str);
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn test_parse_account_commands() {
+        let (_secret_key, public_key) = PublicKey::random_keypair(&mut OsRng);
+
+        let parsed = parse_command("create-account savings").unwrap();
+        if let ParsedArgument::Text(name) = parsed.args[0].clone() {
+            assert_eq!(name, "savings");
+        } else {
+            panic!("Parsed account name is not the same as provided.");
+        }
+        assert!(parse_command("create-account").is_err());
+
+        let command_str = format!("send-from-account savings 10T {} msg text", public_key);
+        let parsed = parse_command(&command_str).unwrap();
+        if let ParsedArgument::Text(name) = parsed.args[0].clone() {
+            assert_eq!(name, "savings");
+        } else {
+            panic!("Parsed account name is not the same as provided.");
+        }
+        if let ParsedArgument::PublicKey(pk) = parsed.args[2].clone() {
+            assert_eq!(pk, public_key);
+        } else {
+            panic!("Parsed public key is not the same as provided.");
+        }
+
+        let parsed = parse_command("transfer-between-accounts default savings 10T msg text").unwrap();
+        if let ParsedArgument::Text(name) = parsed.args[1].clone() {
+            assert_eq!(name, "savings");
+        } else {
+            panic!("Parsed account name is not the same as provided.");
+        }
+        if let ParsedArgument::Amount(amount) = parsed.args[2].clone() {
+            assert_eq!(amount, MicroTari::from_str("10T").unwrap());
+        } else {
+            panic!("Parsed MicroTari amount not the same as provided.");
+        }
+        if let ParsedArgument::Text(msg) = parsed.args[3].clone() {
+            assert_eq!(msg, "msg text");
+        } else {
+            panic!("Parsed message is not the same as provided.");
+        }
+        assert!(parse_command("transfer-between-accounts default savings").is_err());
+    }
+
+    #[test]
+    fn test_parse_payment_request_commands() {
+        let parsed = parse_command("create-payment-request 10T 5000 coffee beans").unwrap();
+        if let ParsedArgument::Amount(amount) = parsed.args[0].clone() {
+            assert_eq!(amount, MicroTari::from_str("10T").unwrap());
+        } else {
+            panic!("Parsed MicroTari amount not the same as provided.");
+        }
+        if let ParsedArgument::Int(height) = parsed.args[1].clone() {
+            assert_eq!(height, 5000);
+        } else {
+            panic!("Parsed expiry height is not the same as provided.");
+        }
+        if let ParsedArgument::Text(msg) = parsed.args[2].clone() {
+            assert_eq!(msg, "coffee beans");
+        } else {
+            panic!("Parsed message is not the same as provided.");
+        }
+        assert!(parse_command("create-payment-request 10T").is_err());
+        assert!(parse_command("create-payment-request 10T soon").is_err());
+
+        let parsed = parse_command("pay-request one_sided tari://stibbons/payment_request/01ab").unwrap();
+        if let ParsedArgument::Negotiated(negotiated) = parsed.args[0].clone() {
+            assert!(!negotiated);
+        } else {
+            panic!("Parsed transaction type is not the same as provided.");
+        }
+        if let ParsedArgument::Text(request) = parsed.args[1].clone() {
+            assert_eq!(request, "tari://stibbons/payment_request/01ab");
+        } else {
+            panic!("Parsed payment request is not the same as provided.");
+        }
+        assert!(parse_command("pay-request negotiated").is_err());
+        assert!(parse_command("pay-request instant 01ab").is_err());
     }
 }
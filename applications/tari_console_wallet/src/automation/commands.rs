@@ -22,6 +22,7 @@
 
 use super::error::CommandError;
 use log::*;
+use rpassword::prompt_password_stdout;
 use std::{
     fs::File,
     io::{LineWriter, Write},
@@ -31,6 +32,7 @@ use std::{
 
 use chrono::{DateTime, Utc};
 use futures::FutureExt;
+use qrcode::{render::unicode, QrCode};
 use strum_macros::{Display, EnumIter, EnumString};
 use tari_crypto::ristretto::pedersen::PedersenCommitmentFactory;
 
@@ -39,7 +41,10 @@ use crate::{
     utils::db::{CUSTOM_BASE_NODE_ADDRESS_KEY, CUSTOM_BASE_NODE_PUBLIC_KEY_KEY},
 };
 use tari_common::GlobalConfig;
-use tari_common_types::{emoji::EmojiId, types::PublicKey};
+use tari_common_types::{
+    emoji::EmojiId,
+    types::{Commitment, PublicKey},
+};
 use tari_comms::{
     connectivity::{ConnectivityEvent, ConnectivityRequester},
     multiaddr::Multiaddr,
@@ -47,15 +52,29 @@ use tari_comms::{
 };
 use tari_comms_dht::{envelope::NodeDestination, DhtDiscoveryRequester};
 use tari_core::{
-    tari_utilities::hex::Hex,
+    tari_utilities::{hex::Hex, ByteArray},
     transactions::{
         tari_amount::{uT, MicroTari, Tari},
         transaction::UnblindedOutput,
     },
 };
 use tari_wallet::{
-    output_manager_service::{handle::OutputManagerHandle, TxId},
-    transaction_service::handle::{TransactionEvent, TransactionServiceHandle},
+    output_manager_service::{
+        error::OutputManagerError,
+        handle::OutputManagerHandle,
+        htlc::HtlcDetails,
+        storage::models::{Account, AccountId},
+        TxId,
+    },
+    storage::backup::EncryptedWalletBackup,
+    transaction_service::{
+        error::{MultisigError, OfflineSigningError},
+        handle::{TransactionEvent, TransactionServiceHandle},
+        multisig::{MultisigOutputStatus, MultisigPackage, MultisigWalletId},
+        offline_signing::OfflinePackage,
+        payment_request::PaymentRequest,
+        storage::models::{CompletedTransaction, TransactionSchedule},
+    },
     WalletSqlite,
 };
 use tokio::{
@@ -82,6 +101,50 @@ pub enum WalletCommand {
     SetBaseNode,
     SetCustomBaseNode,
     ClearCustomBaseNode,
+    PrepareOfflineTx,
+    SignOfflineTx,
+    RelayOfflineTx,
+    ExportOfflineReply,
+    FinalizeOfflineTx,
+    ImportSignedTx,
+    CancelOfflineTx,
+    MultisigNewParticipant,
+    MultisigCreate,
+    MultisigImportWallet,
+    MultisigBalance,
+    MultisigFund,
+    MultisigImportUpdate,
+    MultisigPropose,
+    MultisigCommitNonces,
+    MultisigAddCommitment,
+    MultisigRevealNonces,
+    MultisigAddNonces,
+    MultisigSign,
+    MultisigAddSignatures,
+    MultisigCancel,
+    HtlcPreimage,
+    HtlcAddPreimage,
+    HtlcSend,
+    HtlcImport,
+    HtlcList,
+    HtlcSpend,
+    SendTimeLocked,
+    ScheduleSend,
+    ListScheduled,
+    CancelScheduled,
+    ListHeldTransactions,
+    ApproveHeldTransaction,
+    RejectHeldTransaction,
+    CreateBackup,
+    VerifyBackup,
+    CreateAccount,
+    ListAccounts,
+    SendFromAccount,
+    TransferBetweenAccounts,
+    AccountHistory,
+    CreatePaymentRequest,
+    ListPaymentRequests,
+    PayRequest,
 }
 
 #[derive(Debug, EnumString, PartialEq, Clone)]
@@ -150,6 +213,351 @@ pub async fn send_one_sided(
         .map_err(CommandError::TransactionServiceError)
 }
 
+/// Send a negotiated transaction whose output to the recipient is time-locked until the given block height
+pub async fn send_time_locked(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    use ParsedArgument::*;
+    let (fee_per_gram, amount, dest_pubkey, message) = get_scheduled_transaction_parameters(&args)?;
+    let maturity = match args[2] {
+        Int(maturity) => Ok(maturity),
+        _ => Err(CommandError::Argument),
+    }?;
+    wallet_transaction_service
+        .send_time_locked_transaction(dest_pubkey, amount, maturity, fee_per_gram, message)
+        .await
+        .map_err(CommandError::TransactionServiceError)
+}
+
+/// Negotiate a transaction now but only broadcast it once the given block height or time is reached
+pub async fn schedule_send(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    use ParsedArgument::*;
+    let (fee_per_gram, amount, dest_pubkey, message) = get_scheduled_transaction_parameters(&args)?;
+    let schedule = match args[2] {
+        Int(height) => Ok(TransactionSchedule::Height(height)),
+        Date(time) => Ok(TransactionSchedule::Time(time.naive_utc())),
+        _ => Err(CommandError::Argument),
+    }?;
+    let tx_id = wallet_transaction_service
+        .schedule_transaction(dest_pubkey, amount, fee_per_gram, message, schedule)
+        .await?;
+    println!("Transaction {} scheduled for broadcast {}", tx_id, schedule);
+    Ok(tx_id)
+}
+
+/// List the transactions waiting for their scheduled broadcast
+pub async fn list_scheduled(mut wallet_transaction_service: TransactionServiceHandle) -> Result<(), CommandError> {
+    let scheduled = wallet_transaction_service.get_scheduled_transactions().await?;
+    println!("{} scheduled transactions", scheduled.len());
+    for tx in scheduled {
+        println!("{}: broadcast {}", tx.tx_id, tx.schedule);
+    }
+    Ok(())
+}
+
+/// Cancel a scheduled transaction before it is broadcast, releasing its inputs
+pub async fn cancel_scheduled(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let tx_id = get_tx_id(&args, 0)?;
+    wallet_transaction_service.cancel_transaction(tx_id).await?;
+    println!("Scheduled transaction {} cancelled", tx_id);
+    Ok(())
+}
+
+/// List the incoming transactions held for manual approval by the acceptance policy
+pub async fn list_held_transactions(
+    mut wallet_transaction_service: TransactionServiceHandle,
+) -> Result<(), CommandError> {
+    let held = wallet_transaction_service.get_held_transactions().await?;
+    println!("{} held transactions", held.len());
+    for tx in held {
+        println!(
+            "{}: {} from {} received {} ({})",
+            tx.tx_id, tx.amount, tx.source_public_key, tx.timestamp, tx.message
+        );
+    }
+    Ok(())
+}
+
+/// Accept a held incoming transaction and reply to the sender
+pub async fn approve_held_transaction(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let tx_id = get_tx_id(&args, 0)?;
+    wallet_transaction_service.approve_held_transaction(tx_id).await?;
+    println!("Held transaction {} approved", tx_id);
+    Ok(())
+}
+
+/// Reject a held incoming transaction and notify the sender
+pub async fn reject_held_transaction(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let tx_id = get_tx_id(&args, 0)?;
+    wallet_transaction_service.reject_held_transaction(tx_id).await?;
+    println!("Held transaction {} rejected", tx_id);
+    Ok(())
+}
+
+fn get_text(args: &[ParsedArgument], index: usize) -> Result<String, CommandError> {
+    match args.get(index) {
+        Some(ParsedArgument::Text(text)) => Ok(text.clone()),
+        _ => Err(CommandError::Argument),
+    }
+}
+
+/// Look up the id of the account with the given name
+async fn get_account_id(output_service: &mut OutputManagerHandle, name: &str) -> Result<AccountId, CommandError> {
+    output_service
+        .get_accounts()
+        .await?
+        .into_iter()
+        .find(|a| a.name == name)
+        .map(|a| a.id)
+        .ok_or_else(|| OutputManagerError::AccountNotFound(name.to_string()).into())
+}
+
+/// Create a new named account with its own key chains
+pub async fn create_account(
+    mut output_service: OutputManagerHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<Account, CommandError> {
+    let account = output_service.create_account(get_text(&args, 0)?).await?;
+    println!("Account `{}` created with id {}", account.name, account.id);
+    Ok(account)
+}
+
+/// List the accounts of the wallet and their balances
+pub async fn list_accounts(mut output_service: OutputManagerHandle) -> Result<(), CommandError> {
+    let accounts = output_service.get_accounts().await?;
+    println!("{} accounts", accounts.len());
+    for account in accounts {
+        let balance = output_service.get_account_balance(account.id).await?;
+        println!("{}. {}: {}", account.id, account.name, balance);
+    }
+    Ok(())
+}
+
+/// Send a normal negotiated transaction to a recipient, funded from the named account
+pub async fn send_from_account(
+    mut output_service: OutputManagerHandle,
+    mut wallet_transaction_service: TransactionServiceHandle,
+    mut args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    let account_id = get_account_id(&mut output_service, &get_text(&args, 0)?).await?;
+    let (fee_per_gram, amount, dest_pubkey, message) = get_transaction_parameters(args.split_off(1))?;
+    wallet_transaction_service
+        .send_transaction_from_account(account_id, dest_pubkey, amount, fee_per_gram, message)
+        .await
+        .map_err(CommandError::TransactionServiceError)
+}
+
+/// Move funds between two named accounts of the wallet
+pub async fn transfer_between_accounts(
+    mut output_service: OutputManagerHandle,
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    // TODO: Consolidate "fee per gram" in codebase
+    let fee_per_gram = 25 * uT;
+    let from = get_account_id(&mut output_service, &get_text(&args, 0)?).await?;
+    let to = get_account_id(&mut output_service, &get_text(&args, 1)?).await?;
+    let amount = match args.get(2) {
+        Some(ParsedArgument::Amount(amount)) => Ok(*amount),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = get_text(&args, 3)?;
+    let tx_id = wallet_transaction_service
+        .transfer_between_accounts(from, to, amount, fee_per_gram, message)
+        .await?;
+    println!("Transferred {} in transaction {}", amount, tx_id);
+    Ok(tx_id)
+}
+
+/// List the transactions that created or spent outputs of the named account
+pub async fn account_history(
+    mut output_service: OutputManagerHandle,
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let account_id = get_account_id(&mut output_service, &get_text(&args, 0)?).await?;
+    let tx_ids = output_service.get_account_transaction_ids(account_id).await?;
+    println!("{} transactions", tx_ids.len());
+    for tx_id in tx_ids {
+        match wallet_transaction_service.get_any_transaction(tx_id).await? {
+            Some(tx) => {
+                let tx = CompletedTransaction::from(tx);
+                println!("{}: {} {}, fee {}, {}", tx_id, tx.status, tx.amount, tx.fee, tx.message);
+            },
+            None => println!("{}", tx_id),
+        }
+    }
+    Ok(())
+}
+
+/// Create a signed payment request and print its compact string, URI and QR code
+pub async fn create_payment_request(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    config: &GlobalConfig,
+    args: Vec<ParsedArgument>,
+) -> Result<PaymentRequest, CommandError> {
+    let amount = match args.get(0) {
+        Some(ParsedArgument::Amount(amount)) => Ok(*amount),
+        _ => Err(CommandError::Argument),
+    }?;
+    let expiry_height = match args.get(1) {
+        Some(ParsedArgument::Int(height)) => Ok(*height),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = get_text(&args, 2)?;
+    let request = wallet_transaction_service
+        .create_payment_request(amount, message, expiry_height)
+        .await?;
+
+    let uri = request.to_uri(&config.network.to_string())?;
+    let code = QrCode::new(&uri).map_err(|e| CommandError::Config(e.to_string()))?;
+    let image = code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Dark)
+        .light_color(unicode::Dense1x2::Light)
+        .build();
+    println!(
+        "Payment request {} for {} expires at height {}",
+        request.reference_hex(),
+        request.amount,
+        request.expiry_height
+    );
+    println!("{}", request.to_compact_string()?);
+    println!("{}", uri);
+    println!("{}", image);
+    Ok(request)
+}
+
+/// List the payment requests created by this wallet and how much has been received against them
+pub async fn list_payment_requests(
+    mut wallet_transaction_service: TransactionServiceHandle,
+) -> Result<(), CommandError> {
+    let requests = wallet_transaction_service.get_payment_requests().await?;
+    println!("{} payment requests", requests.len());
+    for record in requests {
+        println!(
+            "{}: {} of {} received, {}, expires at height {}, {}",
+            record.request.reference_hex(),
+            record.amount_received,
+            record.request.amount,
+            record.status,
+            record.request.expiry_height,
+            record.request.message
+        );
+    }
+    Ok(())
+}
+
+/// Pay a payment request given as a compact string or `tari://` URI
+pub async fn pay_request(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    config: &GlobalConfig,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    // TODO: Consolidate "fee per gram" in codebase
+    let fee_per_gram = 25 * uT;
+    let negotiated = match args.get(0) {
+        Some(ParsedArgument::Negotiated(negotiated)) => Ok(*negotiated),
+        _ => Err(CommandError::Argument),
+    }?;
+    let encoded = get_text(&args, 1)?;
+    let request = if encoded.starts_with("tari://") {
+        PaymentRequest::from_uri(&encoded, &config.network.to_string())?
+    } else {
+        PaymentRequest::from_compact_string(&encoded)?
+    };
+    let message = request.payment_message();
+    println!(
+        "Paying {} to {} for payment request {}",
+        request.amount,
+        request.recipient,
+        request.reference_hex()
+    );
+    let tx_id = if negotiated {
+        wallet_transaction_service
+            .send_transaction(request.recipient, request.amount, fee_per_gram, message)
+            .await?
+    } else {
+        wallet_transaction_service
+            .send_one_sided_transaction(request.recipient, request.amount, fee_per_gram, message)
+            .await?
+    };
+    Ok(tx_id)
+}
+
+/// Write a passphrase encrypted backup of the whole wallet to a file
+pub async fn create_backup(wallet: &WalletSqlite, args: Vec<ParsedArgument>) -> Result<(), CommandError> {
+    let out_file = get_file_name(&args, 0)?;
+    let passphrase = prompt_password_stdout("Backup password: ")?;
+    if passphrase.is_empty() {
+        return Err(CommandError::Argument);
+    }
+    if passphrase != prompt_password_stdout("Confirm backup password: ")? {
+        return Err(CommandError::Config("Passwords don't match!".to_string()));
+    }
+
+    wallet.create_backup(passphrase).await?.write_to_file(&out_file)?;
+    println!("Wallet backup written to {}", out_file);
+    Ok(())
+}
+
+/// Check the integrity of a wallet backup file and summarise its contents
+pub fn verify_backup(args: Vec<ParsedArgument>) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let encrypted = EncryptedWalletBackup::read_from_file(&in_file)?;
+    let backup = encrypted.decrypt(&prompt_password_stdout("Backup password: ")?)?;
+    println!(
+        "Wallet backup version {} created at {} is valid",
+        encrypted.version, encrypted.created_at
+    );
+    println!("  Outputs: {}", backup.outputs.len());
+    println!("  Transactions: {}", backup.transactions.len());
+    println!("  Contacts: {}", backup.contacts.len());
+    println!(
+        "  Known one-sided payment scripts: {}",
+        backup.known_one_sided_payment_scripts.len()
+    );
+    Ok(())
+}
+
+fn get_scheduled_transaction_parameters(
+    args: &[ParsedArgument],
+) -> Result<(MicroTari, MicroTari, PublicKey, String), CommandError> {
+    // TODO: Consolidate "fee per gram" in codebase
+    let fee_per_gram = 25 * uT;
+
+    use ParsedArgument::*;
+    let amount = match args[0] {
+        Amount(mtari) => Ok(mtari),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let dest_pubkey = match args[1].clone() {
+        PublicKey(key) => Ok(key),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let message = match args[3].clone() {
+        Text(msg) => Ok(msg),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    Ok((fee_per_gram, amount, dest_pubkey, message))
+}
+
 pub async fn coin_split(
     args: &[ParsedArgument],
     output_service: &mut OutputManagerHandle,
@@ -176,6 +584,553 @@ pub async fn coin_split(
     Ok(tx_id)
 }
 
+fn get_file_name(args: &[ParsedArgument], index: usize) -> Result<String, CommandError> {
+    match args.get(index) {
+        Some(ParsedArgument::FileName(file)) => Ok(file.clone()),
+        _ => Err(CommandError::Argument),
+    }
+}
+
+fn get_tx_id(args: &[ParsedArgument], index: usize) -> Result<TxId, CommandError> {
+    match args.get(index) {
+        Some(ParsedArgument::Int(tx_id)) => Ok(*tx_id),
+        _ => Err(CommandError::Argument),
+    }
+}
+
+/// Select and encumber the inputs of a transaction and write the unsigned package for the offline signer to a file
+pub async fn prepare_offline_tx(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    use ParsedArgument::*;
+    let file = get_file_name(&args, 0)?;
+    let fee_per_gram = 25 * uT;
+    let amount = match args[1].clone() {
+        Amount(mtari) => Ok(mtari),
+        _ => Err(CommandError::Argument),
+    }?;
+    let dest_pubkey = match args[2].clone() {
+        PublicKey(key) => Ok(key),
+        _ => Err(CommandError::Argument),
+    }?;
+    let negotiated = match args[3].clone() {
+        Negotiated(negotiated) => Ok(negotiated),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = match args[4].clone() {
+        Text(msg) => Ok(msg),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let unsigned = wallet_transaction_service
+        .prepare_offline_transaction(dest_pubkey, amount, fee_per_gram, message, !negotiated)
+        .await?;
+    let tx_id = unsigned.tx_id;
+    OfflinePackage::Unsigned(unsigned).write_to_file(&file)?;
+    println!("Unsigned transaction {} written to {}", tx_id, file);
+    Ok(tx_id)
+}
+
+/// Sign an unsigned transaction package with this wallet's keys. A one-sided transaction is written out as a signed
+/// package, an interactive transaction as a sender message that must be relayed to the recipient. The package comes
+/// from the online wallet, so what it pays is shown and must be confirmed before it is signed, unless `--yes` is given.
+pub async fn sign_offline_tx(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let confirmed = matches!(args.get(2), Some(ParsedArgument::Confirmed(true)));
+    let unsigned = match OfflinePackage::read_from_file(&in_file)? {
+        OfflinePackage::Unsigned(unsigned) => unsigned,
+        _ => return Err(OfflineSigningError::UnexpectedPackage("Unsigned").into()),
+    };
+
+    let summary = wallet_transaction_service
+        .summarize_offline_transaction(unsigned.clone())
+        .await?;
+    println!("{}", summary);
+    if !confirmed {
+        println!("Sign this transaction? Type yes followed by Enter to sign.");
+        let mut buf = String::new();
+        std::io::stdin().read_line(&mut buf)?;
+        if buf.trim() != "yes" {
+            return Err(CommandError::SigningNotConfirmed(summary.tx_id));
+        }
+    }
+
+    let package = OfflinePackage::from(wallet_transaction_service.sign_offline_transaction(unsigned).await?);
+    package.write_to_file(&out_file)?;
+    match package {
+        OfflinePackage::Signed(_) => println!("Signed transaction {} written to {}", package.tx_id(), out_file),
+        _ => println!(
+            "Sender message for transaction {} written to {}, relay it to the recipient",
+            package.tx_id(),
+            out_file
+        ),
+    }
+    Ok(())
+}
+
+/// Send the sender message of an interactive offline transaction to the recipient
+pub async fn relay_offline_tx(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let sender_message = match OfflinePackage::read_from_file(&in_file)? {
+        OfflinePackage::SenderMessage(sender_message) => sender_message,
+        _ => return Err(OfflineSigningError::UnexpectedPackage("SenderMessage").into()),
+    };
+    let tx_id = sender_message.tx_id;
+    wallet_transaction_service
+        .relay_offline_sender_message(sender_message)
+        .await?;
+    println!("Transaction {} relayed to the recipient", tx_id);
+    Ok(())
+}
+
+/// Write the recipient's reply to an interactive offline transaction to a file for the offline signer
+pub async fn export_offline_reply(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let tx_id = get_tx_id(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let reply = wallet_transaction_service.get_offline_recipient_reply(tx_id).await?;
+    OfflinePackage::RecipientReply(reply).write_to_file(&out_file)?;
+    println!("Recipient reply for transaction {} written to {}", tx_id, out_file);
+    Ok(())
+}
+
+/// Finalize an interactive offline transaction with the recipient's reply and write the signed package to a file
+pub async fn finalize_offline_tx(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let reply = match OfflinePackage::read_from_file(&in_file)? {
+        OfflinePackage::RecipientReply(reply) => reply,
+        _ => return Err(OfflineSigningError::UnexpectedPackage("RecipientReply").into()),
+    };
+    let signed = wallet_transaction_service.finalize_offline_transaction(reply).await?;
+    let tx_id = signed.tx_id;
+    OfflinePackage::Signed(signed).write_to_file(&out_file)?;
+    println!("Signed transaction {} written to {}", tx_id, out_file);
+    Ok(())
+}
+
+/// Import a signed transaction package and broadcast it
+pub async fn import_signed_tx(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let signed = match OfflinePackage::read_from_file(&in_file)? {
+        OfflinePackage::Signed(signed) => signed,
+        _ => return Err(OfflineSigningError::UnexpectedPackage("Signed").into()),
+    };
+    let tx_id = signed.tx_id;
+    wallet_transaction_service.submit_offline_transaction(signed).await?;
+    println!("Signed transaction {} imported", tx_id);
+    Ok(tx_id)
+}
+
+fn get_multisig_wallet_id(args: &[ParsedArgument], index: usize) -> Result<MultisigWalletId, CommandError> {
+    match args.get(index) {
+        Some(ParsedArgument::Int(wallet_id)) => Ok(*wallet_id),
+        _ => Err(CommandError::Argument),
+    }
+}
+
+/// Create a new key share and write it to a file to be shared with the other participants of a multisig wallet
+pub async fn multisig_new_participant(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let out_file = get_file_name(&args, 0)?;
+    let participant = wallet_transaction_service.create_multisig_participant().await?;
+    println!("Multisig key share: {}", participant.public_key);
+    MultisigPackage::Participant(participant).write_to_file(&out_file)?;
+    println!("Multisig participant written to {}", out_file);
+    Ok(())
+}
+
+/// Set up a multisig wallet from the participant files and write it to a file to be imported by the other
+/// participants
+pub async fn multisig_create(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let out_file = get_file_name(&args, 0)?;
+    let threshold = match args.get(1) {
+        Some(ParsedArgument::Int(threshold)) => Ok(*threshold as usize),
+        _ => Err(CommandError::Argument),
+    }?;
+    let mut participants = Vec::new();
+    for i in 2..args.len() {
+        match MultisigPackage::read_from_file(get_file_name(&args, i)?)? {
+            MultisigPackage::Participant(participant) => participants.push(participant),
+            _ => return Err(MultisigError::UnexpectedPackage("Participant").into()),
+        }
+    }
+    let wallet = wallet_transaction_service
+        .create_multisig_wallet(threshold, participants)
+        .await?;
+    println!(
+        "Created {}-of-{} multisig wallet {}",
+        wallet.threshold,
+        wallet.participants.len(),
+        wallet.id
+    );
+    MultisigPackage::Wallet(wallet).write_to_file(&out_file)?;
+    println!("Multisig wallet written to {}", out_file);
+    Ok(())
+}
+
+/// Import a multisig wallet that was set up by another participant
+pub async fn multisig_import_wallet(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let wallet = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::Wallet(wallet) => wallet,
+        _ => return Err(MultisigError::UnexpectedPackage("Wallet").into()),
+    };
+    let wallet_id = wallet.id;
+    wallet_transaction_service.import_multisig_wallet(wallet).await?;
+    println!("Multisig wallet {} imported", wallet_id);
+    Ok(())
+}
+
+pub async fn multisig_balance(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let wallet_id = get_multisig_wallet_id(&args, 0)?;
+    let outputs = wallet_transaction_service.get_multisig_outputs(wallet_id).await?;
+    let mut balance = MicroTari::from(0);
+    let mut pending = MicroTari::from(0);
+    for output in outputs {
+        match output.status {
+            MultisigOutputStatus::Unspent => balance += output.value,
+            MultisigOutputStatus::PendingSpend(_) => pending += output.value,
+            MultisigOutputStatus::Spent => {},
+        }
+    }
+    println!("Available balance: {}", balance);
+    println!("Pending outgoing balance: {}", pending);
+    Ok(())
+}
+
+/// Send funds to a multisig wallet and write the update for the other participants to a file
+pub async fn multisig_fund(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    use ParsedArgument::*;
+    let out_file = get_file_name(&args, 0)?;
+    let fee_per_gram = 25 * uT;
+    let wallet_id = get_multisig_wallet_id(&args, 1)?;
+    let amount = match args[2].clone() {
+        Amount(mtari) => Ok(mtari),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = match args[3].clone() {
+        Text(msg) => Ok(msg),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let update = wallet_transaction_service
+        .send_to_multisig(wallet_id, amount, fee_per_gram, message)
+        .await?;
+    let tx_id = update.tx_id;
+    MultisigPackage::Update(update).write_to_file(&out_file)?;
+    println!("Multisig update for transaction {} written to {}", tx_id, out_file);
+    Ok(tx_id)
+}
+
+/// Import the outputs created for, or spent from, a multisig wallet by another participant
+pub async fn multisig_import_update(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let update = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::Update(update) => update,
+        _ => return Err(MultisigError::UnexpectedPackage("Update").into()),
+    };
+    let added = wallet_transaction_service.import_multisig_update(update).await?;
+    println!("Multisig update imported with {} new outputs", added);
+    Ok(())
+}
+
+/// Propose a one-sided payment from a multisig wallet, as the coordinator, and write the proposal for the signers to
+/// a file
+pub async fn multisig_propose(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    use ParsedArgument::*;
+    let out_file = get_file_name(&args, 0)?;
+    let fee_per_gram = 25 * uT;
+    let wallet_id = get_multisig_wallet_id(&args, 1)?;
+    let amount = match args[2].clone() {
+        Amount(mtari) => Ok(mtari),
+        _ => Err(CommandError::Argument),
+    }?;
+    let dest_pubkey = match args[3].clone() {
+        PublicKey(key) => Ok(key),
+        _ => Err(CommandError::Argument),
+    }?;
+    let signers = match args[4].clone() {
+        PublicKeys(keys) => Ok(keys),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = match args[5].clone() {
+        Text(msg) => Ok(msg),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let proposal = wallet_transaction_service
+        .propose_multisig_spend(wallet_id, signers, dest_pubkey, amount, fee_per_gram, message)
+        .await?;
+    let session_id = proposal.session_id;
+    MultisigPackage::SpendProposal(proposal).write_to_file(&out_file)?;
+    println!("Multisig spend proposal {} written to {}", session_id, out_file);
+    Ok(())
+}
+
+/// Commit to the nonces of this wallet for a spend proposal, as a signer
+pub async fn multisig_commit_nonces(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let proposal = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::SpendProposal(proposal) => proposal,
+        _ => return Err(MultisigError::UnexpectedPackage("SpendProposal").into()),
+    };
+    let commitment = wallet_transaction_service.commit_multisig_nonces(proposal).await?;
+    MultisigPackage::NonceCommitment(commitment).write_to_file(&out_file)?;
+    println!("Multisig nonce commitment written to {}", out_file);
+    Ok(())
+}
+
+/// Add the nonce commitment of a signer, as the coordinator. Once all signers have committed the nonce request is
+/// written to a file.
+pub async fn multisig_add_commitment(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let commitment = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::NonceCommitment(commitment) => commitment,
+        _ => return Err(MultisigError::UnexpectedPackage("NonceCommitment").into()),
+    };
+    match wallet_transaction_service
+        .add_multisig_nonce_commitment(commitment)
+        .await?
+    {
+        Some(request) => {
+            MultisigPackage::NonceRequest(request).write_to_file(&out_file)?;
+            println!("Multisig nonce request written to {}", out_file);
+        },
+        None => println!("Nonce commitment added, waiting for the remaining signers"),
+    }
+    Ok(())
+}
+
+/// Reveal the nonces of this wallet once all signers have committed, as a signer
+pub async fn multisig_reveal_nonces(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let request = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::NonceRequest(request) => request,
+        _ => return Err(MultisigError::UnexpectedPackage("NonceRequest").into()),
+    };
+    let nonces = wallet_transaction_service.reveal_multisig_nonces(request).await?;
+    MultisigPackage::Nonces(nonces).write_to_file(&out_file)?;
+    println!("Multisig nonces written to {}", out_file);
+    Ok(())
+}
+
+/// Add the nonces of a signer, as the coordinator. Once all signers have revealed their nonces the signing request is
+/// written to a file.
+pub async fn multisig_add_nonces(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let nonces = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::Nonces(nonces) => nonces,
+        _ => return Err(MultisigError::UnexpectedPackage("Nonces").into()),
+    };
+    match wallet_transaction_service.add_multisig_nonces(nonces).await? {
+        Some(request) => {
+            MultisigPackage::SigningRequest(request).write_to_file(&out_file)?;
+            println!("Multisig signing request written to {}", out_file);
+        },
+        None => println!("Nonces added, waiting for the remaining signers"),
+    }
+    Ok(())
+}
+
+/// Sign a signing request with this wallet's key share, as a signer
+pub async fn multisig_sign(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let request = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::SigningRequest(request) => request,
+        _ => return Err(MultisigError::UnexpectedPackage("SigningRequest").into()),
+    };
+    let partial_signatures = wallet_transaction_service.sign_multisig_request(request).await?;
+    MultisigPackage::PartialSignatures(partial_signatures).write_to_file(&out_file)?;
+    println!("Multisig partial signatures written to {}", out_file);
+    Ok(())
+}
+
+/// Add the partial signatures of a signer, as the coordinator. Once all signers have signed the transaction is
+/// broadcast and the update for the other participants is written to a file.
+pub async fn multisig_add_signatures(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<Option<TxId>, CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let out_file = get_file_name(&args, 1)?;
+    let partial_signatures = match MultisigPackage::read_from_file(&in_file)? {
+        MultisigPackage::PartialSignatures(partial_signatures) => partial_signatures,
+        _ => return Err(MultisigError::UnexpectedPackage("PartialSignatures").into()),
+    };
+    match wallet_transaction_service
+        .add_multisig_partial_signatures(partial_signatures)
+        .await?
+    {
+        Some(update) => {
+            let tx_id = update.tx_id;
+            MultisigPackage::Update(update).write_to_file(&out_file)?;
+            println!("Multisig transaction {} submitted", tx_id);
+            println!("Multisig update written to {}", out_file);
+            Ok(Some(tx_id))
+        },
+        None => {
+            println!("Partial signatures added, waiting for the remaining signers");
+            Ok(None)
+        },
+    }
+}
+
+fn get_bytes(args: &[ParsedArgument], index: usize) -> Result<Vec<u8>, CommandError> {
+    match args.get(index) {
+        Some(ParsedArgument::Bytes(bytes)) => Ok(bytes.clone()),
+        _ => Err(CommandError::Argument),
+    }
+}
+
+/// Send an HTLC output and write its details to a file to be imported by the recipient
+pub async fn htlc_send(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    use ParsedArgument::*;
+    let out_file = get_file_name(&args, 0)?;
+    let fee_per_gram = 25 * uT;
+    let amount = match args[1].clone() {
+        Amount(mtari) => Ok(mtari),
+        _ => Err(CommandError::Argument),
+    }?;
+    let dest_pubkey = match args[2].clone() {
+        PublicKey(key) => Ok(key),
+        _ => Err(CommandError::Argument),
+    }?;
+    let mut hash_lock = [0u8; 32];
+    hash_lock.copy_from_slice(&get_bytes(&args, 3)?);
+    let timeout_height = match args[4].clone() {
+        Int(height) => Ok(height),
+        _ => Err(CommandError::Argument),
+    }?;
+    let message = match args[5].clone() {
+        Text(msg) => Ok(msg),
+        _ => Err(CommandError::Argument),
+    }?;
+
+    let (tx_id, details) = wallet_transaction_service
+        .send_htlc(dest_pubkey, amount, fee_per_gram, hash_lock, timeout_height, message)
+        .await?;
+    details.write_to_file(&out_file)?;
+    println!("HTLC {} details written to {}", details.commitment.to_hex(), out_file);
+    Ok(tx_id)
+}
+
+/// Import the details of an HTLC sent to this wallet
+pub async fn htlc_import(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<(), CommandError> {
+    let in_file = get_file_name(&args, 0)?;
+    let fee_per_gram = 25 * uT;
+    let details = HtlcDetails::read_from_file(&in_file)?;
+    let htlc = wallet_transaction_service.import_htlc(details, fee_per_gram).await?;
+    println!(
+        "HTLC {} of {} imported, claimable until height {}",
+        htlc.commitment.to_hex(),
+        htlc.unblinded_output.value,
+        htlc.timeout_height
+    );
+    if htlc.preimage.is_none() {
+        println!("The preimage of this HTLC is not known yet, add it with `htlc-add-preimage` to claim it");
+    }
+    Ok(())
+}
+
+/// List the HTLC outputs sent or received by this wallet
+pub async fn htlc_list(mut output_service: OutputManagerHandle) -> Result<(), CommandError> {
+    let htlcs = output_service.get_htlcs().await?;
+    println!("{} HTLCs", htlcs.len());
+    for htlc in htlcs {
+        println!(
+            "{}: {} {}, role {}, timeout height {}, hash lock {}{}",
+            htlc.commitment.to_hex(),
+            htlc.status,
+            htlc.unblinded_output.value,
+            htlc.role,
+            htlc.timeout_height,
+            htlc.hash_lock.to_hex(),
+            if htlc.preimage.is_some() {
+                ", preimage known"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Claim or refund an HTLC output now
+pub async fn htlc_spend(
+    mut wallet_transaction_service: TransactionServiceHandle,
+    args: Vec<ParsedArgument>,
+) -> Result<TxId, CommandError> {
+    let commitment = Commitment::from_bytes(&get_bytes(&args, 0)?).map_err(|_| CommandError::Argument)?;
+    let tx_id = wallet_transaction_service
+        .spend_htlc(commitment, "Spent HTLC".to_string())
+        .await?;
+    println!("HTLC spent in transaction {}", tx_id);
+    Ok(tx_id)
+}
+
 async fn wait_for_comms(connectivity_requester: &ConnectivityRequester) -> Result<(), CommandError> {
     let mut connectivity = connectivity_requester.get_event_subscription();
     print!("Waiting for connectivity... ");
@@ -694,6 +1649,203 @@ pub async fn command_runner(
                     .await?;
                 println!("Custom base node peer cleared from wallet database.");
             },
+            PrepareOfflineTx => {
+                prepare_offline_tx(transaction_service.clone(), parsed.args).await?;
+            },
+            SignOfflineTx => {
+                sign_offline_tx(transaction_service.clone(), parsed.args).await?;
+            },
+            RelayOfflineTx => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                relay_offline_tx(transaction_service.clone(), parsed.args).await?;
+            },
+            ExportOfflineReply => {
+                export_offline_reply(transaction_service.clone(), parsed.args).await?;
+            },
+            FinalizeOfflineTx => {
+                finalize_offline_tx(transaction_service.clone(), parsed.args).await?;
+            },
+            ImportSignedTx => {
+                let tx_id = import_signed_tx(transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "import-signed-tx tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            CancelOfflineTx => {
+                let tx_id = get_tx_id(&parsed.args, 0)?;
+                transaction_service.clone().cancel_offline_transaction(tx_id).await?;
+                println!("Offline transaction {} cancelled", tx_id);
+            },
+            MultisigNewParticipant => {
+                multisig_new_participant(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigCreate => {
+                multisig_create(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigImportWallet => {
+                multisig_import_wallet(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigBalance => {
+                multisig_balance(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigFund => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                let tx_id = multisig_fund(transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "multisig-fund tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            MultisigImportUpdate => {
+                multisig_import_update(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigPropose => {
+                multisig_propose(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigCommitNonces => {
+                multisig_commit_nonces(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigAddCommitment => {
+                multisig_add_commitment(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigRevealNonces => {
+                multisig_reveal_nonces(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigAddNonces => {
+                multisig_add_nonces(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigSign => {
+                multisig_sign(transaction_service.clone(), parsed.args).await?;
+            },
+            MultisigAddSignatures => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                if let Some(tx_id) = multisig_add_signatures(transaction_service.clone(), parsed.args).await? {
+                    debug!(target: LOG_TARGET, "multisig-add-signatures tx_id {}", tx_id);
+                    tx_ids.push(tx_id);
+                }
+            },
+            MultisigCancel => {
+                let tx_id = get_tx_id(&parsed.args, 0)?;
+                transaction_service.clone().cancel_multisig_spend(tx_id).await?;
+                println!("Multisig spend {} cancelled", tx_id);
+            },
+            HtlcPreimage => {
+                let (hash_lock, preimage) = output_service.create_htlc_preimage().await?;
+                println!("Hash lock: {}", hash_lock.to_hex());
+                println!("Preimage: {}", preimage.to_hex());
+            },
+            HtlcAddPreimage => {
+                let hash_lock = output_service.add_htlc_preimage(get_bytes(&parsed.args, 0)?).await?;
+                println!("Preimage of hash lock {} added", hash_lock.to_hex());
+            },
+            HtlcSend => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                let tx_id = htlc_send(transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "htlc-send tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            HtlcImport => {
+                htlc_import(transaction_service.clone(), parsed.args).await?;
+            },
+            HtlcList => {
+                htlc_list(output_service.clone()).await?;
+            },
+            HtlcSpend => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                let tx_id = htlc_spend(transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "htlc-spend tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            SendTimeLocked => {
+                let tx_id = send_time_locked(transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "send-time-locked tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            ScheduleSend => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                // Not monitored: the transaction is only broadcast once its schedule is due
+                let tx_id = schedule_send(transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "schedule-send tx_id {}", tx_id);
+            },
+            ListScheduled => {
+                list_scheduled(transaction_service.clone()).await?;
+            },
+            CancelScheduled => {
+                cancel_scheduled(transaction_service.clone(), parsed.args).await?;
+            },
+            ListHeldTransactions => {
+                list_held_transactions(transaction_service.clone()).await?;
+            },
+            ApproveHeldTransaction => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                approve_held_transaction(transaction_service.clone(), parsed.args).await?;
+            },
+            RejectHeldTransaction => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                reject_held_transaction(transaction_service.clone(), parsed.args).await?;
+            },
+            CreateBackup => {
+                create_backup(&wallet, parsed.args).await?;
+            },
+            VerifyBackup => {
+                verify_backup(parsed.args)?;
+            },
+            CreateAccount => {
+                create_account(output_service.clone(), parsed.args).await?;
+            },
+            ListAccounts => {
+                list_accounts(output_service.clone()).await?;
+            },
+            SendFromAccount => {
+                let tx_id = send_from_account(output_service.clone(), transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "send-from-account tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            TransferBetweenAccounts => {
+                let tx_id =
+                    transfer_between_accounts(output_service.clone(), transaction_service.clone(), parsed.args).await?;
+                debug!(target: LOG_TARGET, "transfer-between-accounts tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
+            AccountHistory => {
+                account_history(output_service.clone(), transaction_service.clone(), parsed.args).await?;
+            },
+            CreatePaymentRequest => {
+                create_payment_request(transaction_service.clone(), &config, parsed.args).await?;
+            },
+            ListPaymentRequests => {
+                list_payment_requests(transaction_service.clone()).await?;
+            },
+            PayRequest => {
+                if !online {
+                    wait_for_comms(&connectivity_requester).await?;
+                    online = true;
+                }
+                let tx_id = pay_request(transaction_service.clone(), &config, parsed.args).await?;
+                debug!(target: LOG_TARGET, "pay-request tx_id {}", tx_id);
+                tx_ids.push(tx_id);
+            },
         }
     }
 
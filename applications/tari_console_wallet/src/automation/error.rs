@@ -27,9 +27,12 @@ use log::*;
 use tari_app_utilities::utilities::ExitCodes;
 use tari_core::transactions::{tari_amount::MicroTariError, transaction::TransactionError};
 use tari_wallet::{
-    error::{WalletError, WalletStorageError},
-    output_manager_service::error::OutputManagerError,
-    transaction_service::error::TransactionServiceError,
+    error::{WalletBackupError, WalletError, WalletStorageError},
+    output_manager_service::{
+        error::{HtlcError, OutputManagerError},
+        TxId,
+    },
+    transaction_service::error::{MultisigError, OfflineSigningError, PaymentRequestError, TransactionServiceError},
 };
 use thiserror::Error;
 use tokio::task::JoinError;
@@ -59,6 +62,20 @@ pub enum CommandError {
     WalletError(#[from] WalletError),
     #[error("Wallet storage error `{0}`")]
     WalletStorageError(#[from] WalletStorageError),
+    #[error("Offline signing error `{0}`")]
+    OfflineSigningError(#[from] OfflineSigningError),
+    #[error("Multisig error `{0}`")]
+    MultisigError(#[from] MultisigError),
+    #[error("HTLC error `{0}`")]
+    HtlcError(#[from] HtlcError),
+    #[error("Payment request error `{0}`")]
+    PaymentRequestError(#[from] PaymentRequestError),
+    #[error("Wallet backup error `{0}`")]
+    WalletBackupError(#[from] WalletBackupError),
+    #[error("IO error `{0}`")]
+    IoError(#[from] std::io::Error),
+    #[error("Signing of transaction {0} was not confirmed")]
+    SigningNotConfirmed(TxId),
 }
 
 impl From<CommandError> for ExitCodes {
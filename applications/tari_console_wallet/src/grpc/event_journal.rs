@@ -0,0 +1,455 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Keeps the recent wallet events for the gRPC streaming subscriptions.
+//!
+//! Every event is given a cursor that increases by one with each event. The journal keeps the most recent events in
+//! memory so that a client that reconnects can resume after the cursor of the last event it received. Cursors start
+//! at the time the wallet started, in nanoseconds, so a cursor from before a restart is reported as unavailable rather
+//! than silently resuming at the wrong event.
+
+use super::wallet_grpc_server::convert_wallet_transaction_into_transaction_info;
+use chrono::Utc;
+use futures::{channel::mpsc, SinkExt};
+use log::*;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, RwLock},
+};
+use tari_app_grpc::tari_rpc::{
+    BalanceUpdate,
+    RecoveryProgressUpdate,
+    RecoveryStatus,
+    TransactionEventType,
+    TransactionEventUpdate,
+};
+use tari_comms::types::CommsPublicKey;
+use tari_wallet::{
+    output_manager_service::{handle::OutputManagerHandle, service::Balance, TxId},
+    transaction_service::{
+        handle::{TransactionEvent, TransactionServiceHandle},
+        storage::models::WalletTransaction,
+    },
+    utxo_scanner_service::handle::UtxoScannerEvent,
+    WalletSqlite,
+};
+use tokio::{
+    sync::{broadcast, broadcast::error::RecvError},
+    task,
+};
+use tonic::Status;
+
+const LOG_TARGET: &str = "wallet::ui::grpc::event_journal";
+/// The number of recent events kept for clients that resume from a cursor
+pub const EVENT_JOURNAL_CAPACITY: usize = 10_000;
+const NOTIFICATION_CHANNEL_SIZE: usize = 100;
+const STREAM_BUFFER_SIZE: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WalletEventRecord {
+    Transaction(TransactionEventUpdate),
+    Balance(BalanceUpdate),
+    Recovery(RecoveryProgressUpdate),
+}
+
+impl WalletEventRecord {
+    pub fn cursor(&self) -> u64 {
+        match self {
+            WalletEventRecord::Transaction(update) => update.cursor,
+            WalletEventRecord::Balance(update) => update.cursor,
+            WalletEventRecord::Recovery(update) => update.cursor,
+        }
+    }
+
+    fn set_cursor(&mut self, cursor: u64) {
+        match self {
+            WalletEventRecord::Transaction(update) => update.cursor = cursor,
+            WalletEventRecord::Balance(update) => update.cursor = cursor,
+            WalletEventRecord::Recovery(update) => update.cursor = cursor,
+        }
+    }
+}
+
+struct JournalState {
+    events: VecDeque<WalletEventRecord>,
+    last_cursor: u64,
+    /// The cursor of the oldest event that can still be replayed
+    oldest_available: u64,
+}
+
+pub struct EventJournal {
+    state: RwLock<JournalState>,
+    capacity: usize,
+    notifier: broadcast::Sender<u64>,
+}
+
+impl EventJournal {
+    pub fn new(capacity: usize) -> Self {
+        let start = Utc::now().timestamp_nanos() as u64;
+        let (notifier, _) = broadcast::channel(NOTIFICATION_CHANNEL_SIZE);
+        Self {
+            state: RwLock::new(JournalState {
+                events: VecDeque::with_capacity(capacity),
+                last_cursor: start,
+                oldest_available: start + 1,
+            }),
+            capacity,
+            notifier,
+        }
+    }
+
+    /// Add an event to the journal, returning its cursor
+    pub fn append(&self, mut event: WalletEventRecord) -> u64 {
+        let cursor = {
+            let mut state = self.state.write().expect("event journal lock poisoned");
+            state.last_cursor += 1;
+            let cursor = state.last_cursor;
+            event.set_cursor(cursor);
+            state.events.push_back(event);
+            if state.events.len() > self.capacity {
+                state.events.pop_front();
+                state.oldest_available = state.events.front().map(|e| e.cursor()).unwrap_or(cursor + 1);
+            }
+            cursor
+        };
+        // There are no receivers when no client is subscribed
+        let _ = self.notifier.send(cursor);
+        cursor
+    }
+
+    pub fn last_cursor(&self) -> u64 {
+        self.state.read().expect("event journal lock poisoned").last_cursor
+    }
+
+    /// The events that followed the given cursor, or an `OUT_OF_RANGE` status if some of them are no longer kept
+    pub fn events_after(&self, cursor: u64) -> Result<Vec<WalletEventRecord>, Status> {
+        let state = self.state.read().expect("event journal lock poisoned");
+        if cursor > state.last_cursor || cursor.saturating_add(1) < state.oldest_available {
+            return Err(Status::out_of_range(format!(
+                "Events after cursor {} are no longer available, the oldest available cursor is {}",
+                cursor, state.oldest_available
+            )));
+        }
+        Ok(state.events.iter().filter(|e| e.cursor() > cursor).cloned().collect())
+    }
+
+    /// Stream the events after the cursor, and then every new event, that `select` picks out. A cursor of 0 streams
+    /// only the new events.
+    pub fn subscribe<T, F>(
+        self: &Arc<Self>,
+        cursor: u64,
+        select: F,
+    ) -> Result<mpsc::Receiver<Result<T, Status>>, Status>
+    where
+        T: Send + 'static,
+        F: Fn(WalletEventRecord) -> Option<T> + Send + 'static,
+    {
+        // Subscribe before reading the journal so that no event can slip in between
+        let mut notifications = self.notifier.subscribe();
+        let mut cursor = if cursor == 0 { self.last_cursor() } else { cursor };
+        let mut events = self.events_after(cursor)?;
+
+        let (mut sender, receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let journal = self.clone();
+        task::spawn(async move {
+            loop {
+                for event in events {
+                    cursor = event.cursor();
+                    if let Some(item) = select(event) {
+                        if sender.send(Ok(item)).await.is_err() {
+                            debug!(target: LOG_TARGET, "gRPC event stream client disconnected");
+                            return;
+                        }
+                    }
+                }
+                // A lagging notification receiver only means that several events were appended, which are all read
+                // back from the journal
+                match notifications.recv().await {
+                    Ok(_) | Err(RecvError::Lagged(_)) => {},
+                    Err(RecvError::Closed) => return,
+                }
+                events = match journal.events_after(cursor) {
+                    Ok(events) => events,
+                    Err(status) => {
+                        warn!(
+                            target: LOG_TARGET,
+                            "gRPC event stream client fell behind: {}",
+                            status.message()
+                        );
+                        let _ = sender.send(Err(status)).await;
+                        return;
+                    },
+                };
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    /// Record the wallet's transaction, balance and recovery events until the wallet's event streams close
+    pub async fn run(self: Arc<Self>, wallet: WalletSqlite) {
+        let mut transaction_events = wallet.transaction_service.get_event_stream();
+        let mut output_manager_events = wallet.output_manager_service.get_event_stream();
+        let mut utxo_scanner_events = wallet.utxo_scanner_service.clone().get_event_receiver();
+        let mut recorder = EventRecorder {
+            journal: self,
+            transaction_service: wallet.transaction_service.clone(),
+            output_manager_service: wallet.output_manager_service.clone(),
+            wallet_pk: wallet.comms.node_identity_ref().public_key().clone(),
+            last_balance: None,
+        };
+        drop(wallet);
+        recorder.update_balance().await;
+
+        loop {
+            tokio::select! {
+                event = transaction_events.recv() => match event {
+                    Ok(event) => {
+                        recorder.record_transaction_event(&event).await;
+                        recorder.update_balance().await;
+                    },
+                    Err(RecvError::Lagged(n)) => warn!(target: LOG_TARGET, "Event journal missed {} transaction events", n),
+                    Err(RecvError::Closed) => break,
+                },
+                event = output_manager_events.recv() => match event {
+                    Ok(_) => recorder.update_balance().await,
+                    Err(RecvError::Lagged(n)) => warn!(target: LOG_TARGET, "Event journal missed {} output manager events", n),
+                    Err(RecvError::Closed) => break,
+                },
+                event = utxo_scanner_events.recv() => match event {
+                    Ok(event) => {
+                        recorder.record_utxo_scanner_event(&event);
+                        recorder.update_balance().await;
+                    },
+                    Err(RecvError::Lagged(n)) => warn!(target: LOG_TARGET, "Event journal missed {} recovery events", n),
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Wallet event streams closed, stopping the gRPC event journal"
+        );
+    }
+}
+
+struct EventRecorder {
+    journal: Arc<EventJournal>,
+    transaction_service: TransactionServiceHandle,
+    output_manager_service: OutputManagerHandle,
+    wallet_pk: CommsPublicKey,
+    last_balance: Option<Balance>,
+}
+
+impl EventRecorder {
+    async fn record_transaction_event(&mut self, event: &TransactionEvent) {
+        let (tx_id, event_type, num_confirmations) = match transaction_event_type(event) {
+            Some(e) => e,
+            None => return,
+        };
+        let transaction = match self.transaction_service.get_any_transaction(tx_id).await {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not fetch transaction {} for event journal: {}", tx_id, e
+                );
+                None
+            },
+        };
+        let num_confirmations = match &transaction {
+            Some(WalletTransaction::Completed(tx)) => num_confirmations.or(tx.confirmations),
+            _ => num_confirmations,
+        };
+        self.journal
+            .append(WalletEventRecord::Transaction(TransactionEventUpdate {
+                cursor: 0,
+                event: event_type as i32,
+                tx_id,
+                num_confirmations: num_confirmations.unwrap_or_default(),
+                transaction: transaction
+                    .map(|tx| convert_wallet_transaction_into_transaction_info(tx, &self.wallet_pk)),
+            }));
+    }
+
+    fn record_utxo_scanner_event(&self, event: &UtxoScannerEvent) {
+        if let Some(update) = recovery_progress_update(event) {
+            self.journal.append(WalletEventRecord::Recovery(update));
+        }
+    }
+
+    /// Record a balance update if the balance changed since the last one
+    async fn update_balance(&mut self) {
+        let balance = match self.output_manager_service.get_balance().await {
+            Ok(balance) => balance,
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Could not fetch balance for event journal: {}", e);
+                return;
+            },
+        };
+        let previous = self.last_balance.replace(balance.clone());
+        // The first balance is the baseline that later changes are compared against
+        if previous.is_none() || previous.as_ref() == Some(&balance) {
+            return;
+        }
+        self.journal.append(WalletEventRecord::Balance(BalanceUpdate {
+            cursor: 0,
+            available_balance: balance.available_balance.as_u64(),
+            pending_incoming_balance: balance.pending_incoming_balance.as_u64(),
+            pending_outgoing_balance: balance.pending_outgoing_balance.as_u64(),
+        }));
+    }
+}
+
+/// The recovery progress update for a UTXO scanner event, if it is one that is streamed
+pub fn recovery_progress_update(event: &UtxoScannerEvent) -> Option<RecoveryProgressUpdate> {
+    match event {
+        UtxoScannerEvent::Progress {
+            current_block,
+            current_chain_height,
+            time_remaining,
+        } => Some(RecoveryProgressUpdate {
+            status: RecoveryStatus::InProgress as i32,
+            current_block: *current_block,
+            chain_height: *current_chain_height,
+            seconds_remaining: time_remaining.map(|t| t.as_secs()).unwrap_or_default(),
+            ..Default::default()
+        }),
+        UtxoScannerEvent::ScanningRoundFailed { error, .. } => Some(RecoveryProgressUpdate {
+            status: RecoveryStatus::InProgress as i32,
+            error: error.clone(),
+            ..Default::default()
+        }),
+        UtxoScannerEvent::Completed {
+            number_received,
+            value_received,
+            ..
+        } => Some(RecoveryProgressUpdate {
+            status: RecoveryStatus::Completed as i32,
+            num_recovered: *number_received,
+            value_recovered: value_received.as_u64(),
+            ..Default::default()
+        }),
+        UtxoScannerEvent::ScanningFailed => Some(RecoveryProgressUpdate {
+            status: RecoveryStatus::Failed as i32,
+            error: "Recovery failed".to_string(),
+            ..Default::default()
+        }),
+        _ => None,
+    }
+}
+
+/// The lifecycle event type, transaction id and number of confirmations of the transaction events that are streamed
+fn transaction_event_type(event: &TransactionEvent) -> Option<(TxId, TransactionEventType, Option<u64>)> {
+    use TransactionEvent::*;
+    match event {
+        ReceivedTransaction(tx_id) => Some((*tx_id, TransactionEventType::Received, None)),
+        ReceivedTransactionReply(tx_id) |
+        ReceivedFinalizedTransaction(tx_id) |
+        TransactionCompletedImmediately(tx_id) => Some((*tx_id, TransactionEventType::Negotiated, None)),
+        TransactionBroadcast(tx_id) => Some((*tx_id, TransactionEventType::Broadcast, None)),
+        TransactionMinedUnconfirmed {
+            tx_id,
+            num_confirmations,
+            ..
+        } => Some((*tx_id, TransactionEventType::MinedUnconfirmed, Some(*num_confirmations))),
+        TransactionMined { tx_id, .. } => Some((*tx_id, TransactionEventType::Confirmed, None)),
+        TransactionCancelled(tx_id) | TransactionRejected(tx_id) => {
+            Some((*tx_id, TransactionEventType::Cancelled, None))
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::StreamExt;
+    use tonic::Code;
+
+    fn balance_event(available_balance: u64) -> WalletEventRecord {
+        WalletEventRecord::Balance(BalanceUpdate {
+            available_balance,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn it_replays_events_after_a_cursor() {
+        let journal = EventJournal::new(3);
+        let start = journal.last_cursor();
+        let cursors = (1..=4).map(|i| journal.append(balance_event(i))).collect::<Vec<_>>();
+        assert_eq!(cursors, vec![start + 1, start + 2, start + 3, start + 4]);
+
+        let events = journal.events_after(cursors[1]).unwrap();
+        assert_eq!(events, vec![
+            WalletEventRecord::Balance(BalanceUpdate {
+                cursor: cursors[2],
+                available_balance: 3,
+                ..Default::default()
+            }),
+            WalletEventRecord::Balance(BalanceUpdate {
+                cursor: cursors[3],
+                available_balance: 4,
+                ..Default::default()
+            }),
+        ]);
+        assert!(journal.events_after(cursors[3]).unwrap().is_empty());
+        // The first event no longer fits in the journal
+        assert_eq!(journal.events_after(cursors[0]).unwrap().len(), 3);
+        assert_eq!(journal.events_after(start).unwrap_err().code(), Code::OutOfRange);
+        // Cursors from an earlier run of the wallet or that were never handed out are not available either
+        assert_eq!(journal.events_after(1).unwrap_err().code(), Code::OutOfRange);
+        assert_eq!(
+            journal.events_after(cursors[3] + 1).unwrap_err().code(),
+            Code::OutOfRange
+        );
+    }
+
+    #[tokio::test]
+    async fn it_streams_selected_events_from_a_cursor() {
+        let journal = Arc::new(EventJournal::new(10));
+        let first = journal.append(balance_event(1));
+        journal.append(WalletEventRecord::Transaction(TransactionEventUpdate {
+            tx_id: 1,
+            ..Default::default()
+        }));
+        journal.append(balance_event(2));
+
+        let select_balance = |event: WalletEventRecord| match event {
+            WalletEventRecord::Balance(update) => Some(update),
+            _ => None,
+        };
+        let mut resumed = journal.subscribe(first, select_balance).unwrap();
+        let mut new_only = journal.subscribe(0, select_balance).unwrap();
+        journal.append(balance_event(3));
+
+        let balances = resumed
+            .by_ref()
+            .take(2)
+            .map(|update| update.unwrap().available_balance)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(balances, vec![2, 3]);
+        assert_eq!(new_only.next().await.unwrap().unwrap().available_balance, 3);
+    }
+}
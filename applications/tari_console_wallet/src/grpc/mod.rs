@@ -1,3 +1,4 @@
+mod event_journal;
 mod wallet_grpc_server;
 
 pub use self::wallet_grpc_server::*;
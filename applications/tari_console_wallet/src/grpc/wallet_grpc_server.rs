@@ -1,6 +1,14 @@
+use super::event_journal::{recovery_progress_update, EventJournal, WalletEventRecord, EVENT_JOURNAL_CAPACITY};
 use futures::{channel::mpsc, future, SinkExt};
 use log::*;
-use std::convert::TryFrom;
+use rand::rngs::OsRng;
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use tari_app_grpc::{
     conversions::naive_datetime_to_timestamp,
     tari_rpc,
@@ -23,6 +31,7 @@ use tari_app_grpc::{
         GetVersionResponse,
         ImportUtxosRequest,
         ImportUtxosResponse,
+        StreamEventsRequest,
         TransactionDirection,
         TransactionInfo,
         TransactionStatus,
@@ -31,15 +40,22 @@ use tari_app_grpc::{
         TransferResult,
     },
 };
-use tari_common_types::types::Signature;
+use tari_common_types::types::{PrivateKey, PublicKey, Signature};
 use tari_comms::{types::CommsPublicKey, CommsNode};
 use tari_core::{
     tari_utilities::{hex::Hex, ByteArray},
     transactions::{tari_amount::MicroTari, transaction::UnblindedOutput},
 };
+use tari_crypto::{commitment::HomomorphicCommitmentFactory, keys::SecretKey};
+use tari_shutdown::Shutdown;
 use tari_wallet::{
+    contacts_service::storage::database::Contact,
+    error::{WalletError, WalletStorageError},
     output_manager_service::handle::OutputManagerHandle,
+    storage::{backup::EncryptedWalletBackup, sqlite_db::WalletSqliteDatabase},
     transaction_service::{handle::TransactionServiceHandle, storage::models},
+    util::birthday::birthday_to_date,
+    utxo_scanner_service::utxo_scanning::UtxoScannerService,
     WalletSqlite,
 };
 use tokio::task;
@@ -49,11 +65,30 @@ const LOG_TARGET: &str = "wallet::ui::grpc";
 
 pub struct WalletGrpcServer {
     wallet: WalletSqlite,
+    event_journal: Arc<EventJournal>,
+    recovery_in_progress: Arc<AtomicBool>,
+    seed_words_enabled: bool,
 }
 
 impl WalletGrpcServer {
     pub fn new(wallet: WalletSqlite) -> Self {
-        Self { wallet }
+        Self {
+            wallet,
+            event_journal: Arc::new(EventJournal::new(EVENT_JOURNAL_CAPACITY)),
+            recovery_in_progress: Arc::new(AtomicBool::new(false)),
+            seed_words_enabled: false,
+        }
+    }
+
+    /// Allow clients that know the passphrase of an encrypted wallet to read its seed words
+    pub fn with_seed_words_enabled(mut self, enabled: bool) -> Self {
+        self.seed_words_enabled = enabled;
+        self
+    }
+
+    /// Start recording the wallet events for the streaming subscriptions. Must be called from within the runtime.
+    pub fn spawn_event_journal(&self) {
+        task::spawn(self.event_journal.clone().run(self.wallet.clone()));
     }
 
     fn get_transaction_service(&self) -> TransactionServiceHandle {
@@ -72,6 +107,9 @@ impl WalletGrpcServer {
 #[tonic::async_trait]
 impl wallet_server::Wallet for WalletGrpcServer {
     type GetCompletedTransactionsStream = mpsc::Receiver<Result<GetCompletedTransactionsResponse, Status>>;
+    type StreamBalanceUpdatesStream = mpsc::Receiver<Result<tari_rpc::BalanceUpdate, Status>>;
+    type StreamRecoveryProgressStream = mpsc::Receiver<Result<tari_rpc::RecoveryProgressUpdate, Status>>;
+    type StreamTransactionEventsStream = mpsc::Receiver<Result<tari_rpc::TransactionEventUpdate, Status>>;
 
     async fn get_version(&self, _: Request<GetVersionRequest>) -> Result<Response<GetVersionResponse>, Status> {
         Ok(Response::new(GetVersionResponse {
@@ -395,6 +433,7 @@ impl wallet_server::Wallet for WalletGrpcServer {
             .map_err(|err| Status::internal(err.to_string()))?;
 
         let mut peers = Vec::with_capacity(connected_peers.len());
+        let mut connection_bandwidth = Vec::with_capacity(connected_peers.len());
         for peer in connected_peers {
             peers.push(
                 peer_manager
@@ -402,10 +441,15 @@ impl wallet_server::Wallet for WalletGrpcServer {
                     .await
                     .map_err(|err| Status::internal(err.to_string()))?,
             );
+            connection_bandwidth.push(tari_rpc::ConnectionBandwidth {
+                node_id: peer.peer_node_id().to_vec(),
+                stats: Some(peer.bandwidth_stats().into()),
+            });
         }
 
         let resp = tari_rpc::ListConnectedPeersResponse {
             connected_peers: peers.into_iter().map(Into::into).collect(),
+            connection_bandwidth,
         };
 
         Ok(Response::new(resp))
@@ -437,9 +481,465 @@ impl wallet_server::Wallet for WalletGrpcServer {
             },
         }
     }
+
+    async fn create_backup(
+        &self,
+        request: Request<tari_rpc::CreateBackupRequest>,
+    ) -> Result<Response<tari_rpc::CreateBackupResponse>, Status> {
+        let message = request.into_inner();
+        debug!(target: LOG_TARGET, "Incoming gRPC request to create a wallet backup");
+        if message.passphrase.is_empty() {
+            return Err(Status::invalid_argument("Backup passphrase cannot be empty"));
+        }
+
+        let backup = self
+            .wallet
+            .create_backup(message.passphrase)
+            .await
+            .and_then(|backup| backup.to_json().map_err(Into::into))
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::CreateBackupResponse { backup }))
+    }
+
+    async fn verify_backup(
+        &self,
+        request: Request<tari_rpc::VerifyBackupRequest>,
+    ) -> Result<Response<tari_rpc::VerifyBackupResponse>, Status> {
+        let message = request.into_inner();
+        let encrypted =
+            EncryptedWalletBackup::from_json(&message.backup).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let backup = encrypted
+            .decrypt(&message.passphrase)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::VerifyBackupResponse {
+            version: encrypted.version,
+            num_outputs: backup.outputs.len() as u64,
+            num_transactions: backup.transactions.len() as u64,
+            num_contacts: backup.contacts.len() as u64,
+            num_known_scripts: backup.known_one_sided_payment_scripts.len() as u64,
+        }))
+    }
+
+    async fn stream_transaction_events(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamTransactionEventsStream>, Status> {
+        let cursor = request.into_inner().cursor;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming gRPC subscription to transaction events from cursor {}", cursor
+        );
+        let receiver = self.event_journal.subscribe(cursor, |event| match event {
+            WalletEventRecord::Transaction(update) => Some(update),
+            _ => None,
+        })?;
+        Ok(Response::new(receiver))
+    }
+
+    async fn stream_balance_updates(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamBalanceUpdatesStream>, Status> {
+        let cursor = request.into_inner().cursor;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming gRPC subscription to balance updates from cursor {}", cursor
+        );
+        let receiver = self.event_journal.subscribe(cursor, |event| match event {
+            WalletEventRecord::Balance(update) => Some(update),
+            _ => None,
+        })?;
+        Ok(Response::new(receiver))
+    }
+
+    async fn stream_recovery_progress(
+        &self,
+        request: Request<StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamRecoveryProgressStream>, Status> {
+        let cursor = request.into_inner().cursor;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming gRPC subscription to recovery progress from cursor {}", cursor
+        );
+        let receiver = self.event_journal.subscribe(cursor, |event| match event {
+            WalletEventRecord::Recovery(update) => Some(update),
+            _ => None,
+        })?;
+        Ok(Response::new(receiver))
+    }
+
+    async fn get_contacts(
+        &self,
+        _: Request<tari_rpc::Empty>,
+    ) -> Result<Response<tari_rpc::GetContactsResponse>, Status> {
+        let contacts = self
+            .wallet
+            .contacts_service
+            .clone()
+            .get_contacts()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::GetContactsResponse {
+            contacts: contacts.into_iter().map(convert_contact).collect(),
+        }))
+    }
+
+    async fn upsert_contact(
+        &self,
+        request: Request<tari_rpc::UpsertContactRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        let contact = parse_contact(request.into_inner().contact)?;
+
+        self.wallet
+            .contacts_service
+            .clone()
+            .upsert_contact(contact)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn remove_contact(
+        &self,
+        request: Request<tari_rpc::RemoveContactRequest>,
+    ) -> Result<Response<tari_rpc::RemoveContactResponse>, Status> {
+        let public_key = parse_public_key(&request.into_inner().public_key)?;
+        let contact = self
+            .wallet
+            .contacts_service
+            .clone()
+            .remove_contact(public_key)
+            .await
+            .map_err(|e| Status::not_found(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::RemoveContactResponse {
+            contact: Some(convert_contact(contact)),
+        }))
+    }
+
+    async fn get_seed_words(
+        &self,
+        request: Request<tari_rpc::GetSeedWordsRequest>,
+    ) -> Result<Response<tari_rpc::GetSeedWordsResponse>, Status> {
+        debug!(target: LOG_TARGET, "Incoming gRPC request for the wallet seed words");
+        if !self.seed_words_enabled {
+            return Err(Status::permission_denied(
+                "Reading the seed words via gRPC is disabled, see `grpc_seed_words_enabled`",
+            ));
+        }
+        // Only an encrypted wallet has a passphrase that proves the client may read the seed words
+        self.wallet
+            .verify_passphrase(request.into_inner().passphrase)
+            .await
+            .map_err(passphrase_error_to_status)?;
+        let seed_words = self
+            .get_output_manager_service()
+            .get_seed_words()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let birthday = self
+            .wallet
+            .db
+            .get_wallet_birthday()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .map(|birthday| birthday_to_date(birthday).to_string())
+            .unwrap_or_default();
+
+        Ok(Response::new(tari_rpc::GetSeedWordsResponse { seed_words, birthday }))
+    }
+
+    async fn set_base_node(
+        &self,
+        request: Request<tari_rpc::SetBaseNodeRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        let message = request.into_inner();
+        let public_key = parse_public_key(&message.public_key)?;
+        debug!(
+            target: LOG_TARGET,
+            "Incoming gRPC request to set the base node to {}::{}", public_key, message.net_address
+        );
+
+        self.wallet
+            .clone()
+            .set_base_node_peer(public_key, message.net_address)
+            .await
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn apply_encryption(
+        &self,
+        request: Request<tari_rpc::ApplyEncryptionRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        let message = request.into_inner();
+        debug!(target: LOG_TARGET, "Incoming gRPC request to encrypt the wallet");
+        check_new_passphrase(&message.passphrase)?;
+
+        self.wallet
+            .clone()
+            .apply_encryption(message.passphrase)
+            .await
+            .map_err(passphrase_error_to_status)?;
+
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn remove_encryption(
+        &self,
+        request: Request<tari_rpc::RemoveEncryptionRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        let message = request.into_inner();
+        debug!(
+            target: LOG_TARGET,
+            "Incoming gRPC request to remove the wallet encryption"
+        );
+        let mut wallet = self.wallet.clone();
+        wallet
+            .verify_passphrase(message.passphrase)
+            .await
+            .map_err(passphrase_error_to_status)?;
+        wallet.remove_encryption().await.map_err(passphrase_error_to_status)?;
+
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn change_passphrase(
+        &self,
+        request: Request<tari_rpc::ChangePassphraseRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        let message = request.into_inner();
+        debug!(
+            target: LOG_TARGET,
+            "Incoming gRPC request to change the wallet passphrase"
+        );
+        check_new_passphrase(&message.new_passphrase)?;
+        self.wallet
+            .change_passphrase(message.passphrase, message.new_passphrase)
+            .await
+            .map_err(passphrase_error_to_status)?;
+
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn sign_message(
+        &self,
+        request: Request<tari_rpc::SignMessageRequest>,
+    ) -> Result<Response<tari_rpc::SignMessageResponse>, Status> {
+        let message = request.into_inner().message;
+        let identity = self.wallet.comms.node_identity();
+        let nonce = PrivateKey::random(&mut OsRng);
+        let signature = self
+            .wallet
+            .clone()
+            .sign_message(identity.secret_key().clone(), nonce, &message)
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(tari_rpc::SignMessageResponse {
+            public_key: identity.public_key().to_hex(),
+            public_nonce: signature.get_public_nonce().to_hex(),
+            signature: signature.get_signature().to_hex(),
+        }))
+    }
+
+    async fn verify_message_signature(
+        &self,
+        request: Request<tari_rpc::VerifyMessageSignatureRequest>,
+    ) -> Result<Response<tari_rpc::VerifyMessageSignatureResponse>, Status> {
+        let message = request.into_inner();
+        let (public_key, public_nonce, signature) = parse_message_signature(&message)?;
+
+        let is_valid =
+            self.wallet
+                .clone()
+                .verify_message_signature(public_key, public_nonce, signature, message.message);
+
+        Ok(Response::new(tari_rpc::VerifyMessageSignatureResponse { is_valid }))
+    }
+
+    async fn get_unspent_outputs(
+        &self,
+        request: Request<tari_rpc::GetUnspentOutputsRequest>,
+    ) -> Result<Response<tari_rpc::GetUnspentOutputsResponse>, Status> {
+        let filter = request.into_inner();
+        let outputs = self
+            .get_output_manager_service()
+            .get_unspent_outputs()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let (outputs, total_count, total_value) = filter_unspent_outputs(outputs, &filter)?;
+        let factories = &self.wallet.factories;
+        let outputs = outputs
+            .into_iter()
+            .map(|o| tari_rpc::UnspentOutput {
+                commitment: factories
+                    .commitment
+                    .commit_value(&o.spending_key, o.value.as_u64())
+                    .to_hex(),
+                value: o.value.as_u64(),
+                features: Some(tari_rpc::OutputFeatures {
+                    flags: o.features.flags.bits() as u32,
+                    maturity: o.features.maturity,
+                }),
+            })
+            .collect();
+
+        Ok(Response::new(tari_rpc::GetUnspentOutputsResponse {
+            outputs,
+            total_count,
+            total_value,
+        }))
+    }
+
+    async fn start_recovery(
+        &self,
+        request: Request<tari_rpc::StartRecoveryRequest>,
+    ) -> Result<Response<tari_rpc::Empty>, Status> {
+        let message = request.into_inner();
+        let mut peers = message
+            .base_node_public_keys
+            .iter()
+            .map(|key| parse_public_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        if peers.is_empty() {
+            let base_node = self
+                .wallet
+                .clone()
+                .get_base_node_peer()
+                .await
+                .ok_or_else(|| Status::failed_precondition("No base node is set to recover from"))?;
+            peers.push(base_node.public_key);
+        }
+        if self.recovery_in_progress.swap(true, Ordering::SeqCst) {
+            return Err(Status::already_exists("A recovery is already in progress"));
+        }
+        info!(target: LOG_TARGET, "Starting recovery requested via gRPC");
+
+        // The recovery is stopped when the shutdown is dropped, so it is kept until the recovery ends
+        let shutdown = Shutdown::new();
+        let mut recovery_task = UtxoScannerService::<WalletSqliteDatabase>::builder()
+            .with_peers(peers)
+            .with_retry_limit(3)
+            .build_with_wallet(&self.wallet, shutdown.to_signal());
+        let mut event_stream = recovery_task.get_event_receiver();
+        let event_journal = self.event_journal.clone();
+        task::spawn(async move {
+            while let Ok(event) = event_stream.recv().await {
+                if let Some(update) = recovery_progress_update(&event) {
+                    event_journal.append(WalletEventRecord::Recovery(update));
+                }
+            }
+        });
+        let recovery_in_progress = self.recovery_in_progress.clone();
+        task::spawn(async move {
+            if let Err(e) = recovery_task.run().await {
+                error!(target: LOG_TARGET, "Recovery requested via gRPC failed: {}", e);
+            }
+            recovery_in_progress.store(false, Ordering::SeqCst);
+            drop(shutdown);
+        });
+
+        Ok(Response::new(tari_rpc::Empty {}))
+    }
+
+    async fn get_recovery_status(
+        &self,
+        _: Request<tari_rpc::Empty>,
+    ) -> Result<Response<tari_rpc::GetRecoveryStatusResponse>, Status> {
+        Ok(Response::new(tari_rpc::GetRecoveryStatusResponse {
+            in_progress: self.recovery_in_progress.load(Ordering::SeqCst),
+        }))
+    }
+}
+
+fn parse_public_key(public_key: &str) -> Result<CommsPublicKey, Status> {
+    CommsPublicKey::from_hex(public_key).map_err(|_| Status::invalid_argument("Public key is malformed"))
+}
+
+fn parse_contact(contact: Option<tari_rpc::Contact>) -> Result<Contact, Status> {
+    let contact = contact.ok_or_else(|| Status::invalid_argument("Contact is missing"))?;
+    if contact.alias.is_empty() {
+        return Err(Status::invalid_argument("Contact alias cannot be empty"));
+    }
+    Ok(Contact {
+        public_key: parse_public_key(&contact.public_key)?,
+        alias: contact.alias,
+    })
+}
+
+fn parse_message_signature(
+    message: &tari_rpc::VerifyMessageSignatureRequest,
+) -> Result<(PublicKey, PublicKey, PrivateKey), Status> {
+    let public_key = parse_public_key(&message.public_key)?;
+    let public_nonce = PublicKey::from_hex(&message.public_nonce)
+        .map_err(|_| Status::invalid_argument("Public nonce is malformed"))?;
+    let signature =
+        PrivateKey::from_hex(&message.signature).map_err(|_| Status::invalid_argument("Signature is malformed"))?;
+    Ok((public_key, public_nonce, signature))
 }
 
-fn convert_wallet_transaction_into_transaction_info(
+fn check_new_passphrase(passphrase: &str) -> Result<(), Status> {
+    if passphrase.is_empty() {
+        return Err(Status::invalid_argument("Passphrase cannot be empty"));
+    }
+    Ok(())
+}
+
+/// A wrong passphrase is refused, and the encryption can only be applied to an unencrypted wallet and changed or
+/// removed on an encrypted one
+fn passphrase_error_to_status(error: WalletError) -> Status {
+    match error {
+        WalletError::WalletStorageError(e @ WalletStorageError::IncorrectPassword) => {
+            Status::permission_denied(e.to_string())
+        },
+        WalletError::WalletStorageError(WalletStorageError::InvalidEncryptionCipher) => {
+            Status::failed_precondition("The wallet is not encrypted")
+        },
+        WalletError::WalletStorageError(e @ WalletStorageError::AlreadyEncrypted) => {
+            Status::failed_precondition(e.to_string())
+        },
+        e => Status::internal(e.to_string()),
+    }
+}
+
+/// Select the outputs that match the filter, smallest first and up to its limit. The number and total value of all the
+/// matching outputs are returned with them.
+fn filter_unspent_outputs(
+    outputs: Vec<UnblindedOutput>,
+    filter: &tari_rpc::GetUnspentOutputsRequest,
+) -> Result<(Vec<UnblindedOutput>, u64, u64), Status> {
+    if filter.max_value > 0 && filter.max_value < filter.min_value {
+        return Err(Status::invalid_argument("Maximum value is below the minimum value"));
+    }
+    let mut outputs = outputs
+        .into_iter()
+        .filter(|o| o.value.as_u64() >= filter.min_value)
+        .filter(|o| filter.max_value == 0 || o.value.as_u64() <= filter.max_value)
+        .filter(|o| filter.spendable_at_height == 0 || o.features.maturity <= filter.spendable_at_height)
+        .collect::<Vec<_>>();
+    outputs.sort_by_key(|o| o.value);
+
+    let total_count = outputs.len() as u64;
+    let total_value = outputs.iter().map(|o| o.value).sum::<MicroTari>().as_u64();
+    if filter.limit > 0 {
+        outputs.truncate(filter.limit as usize);
+    }
+    Ok((outputs, total_count, total_value))
+}
+
+fn convert_contact(contact: Contact) -> tari_rpc::Contact {
+    tari_rpc::Contact {
+        alias: contact.alias,
+        public_key: contact.public_key.to_hex(),
+    }
+}
+
+pub(crate) fn convert_wallet_transaction_into_transaction_info(
     tx: models::WalletTransaction,
     wallet_pk: &CommsPublicKey,
 ) -> TransactionInfo {
@@ -493,3 +993,151 @@ fn convert_wallet_transaction_into_transaction_info(
         },
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_common_types::types::ComSignature;
+    use tari_core::transactions::transaction::OutputFeatures;
+    use tari_crypto::{
+        keys::PublicKey as PublicKeyTrait,
+        script::{ExecutionStack, TariScript},
+    };
+    use tonic::Code;
+
+    fn output(value: u64, maturity: u64) -> UnblindedOutput {
+        UnblindedOutput::new(
+            MicroTari::from(value),
+            PrivateKey::random(&mut OsRng),
+            OutputFeatures::with_maturity(maturity),
+            TariScript::default(),
+            ExecutionStack::default(),
+            PrivateKey::default(),
+            PublicKey::default(),
+            ComSignature::default(),
+        )
+    }
+
+    #[test]
+    fn it_rejects_malformed_contacts() {
+        let (_, public_key) = PublicKey::random_keypair(&mut OsRng);
+        assert_eq!(parse_contact(None).unwrap_err().code(), Code::InvalidArgument);
+        let contact = tari_rpc::Contact {
+            alias: "".to_string(),
+            public_key: public_key.to_hex(),
+        };
+        assert_eq!(parse_contact(Some(contact)).unwrap_err().code(), Code::InvalidArgument);
+        let contact = tari_rpc::Contact {
+            alias: "Bob".to_string(),
+            public_key: "not a public key".to_string(),
+        };
+        assert_eq!(parse_contact(Some(contact)).unwrap_err().code(), Code::InvalidArgument);
+
+        let contact = tari_rpc::Contact {
+            alias: "Bob".to_string(),
+            public_key: public_key.to_hex(),
+        };
+        let contact = parse_contact(Some(contact)).unwrap();
+        assert_eq!(contact.alias, "Bob");
+        assert_eq!(contact.public_key, public_key);
+    }
+
+    #[test]
+    fn it_rejects_malformed_message_signatures() {
+        let (secret_key, public_key) = PublicKey::random_keypair(&mut OsRng);
+        let (_, public_nonce) = PublicKey::random_keypair(&mut OsRng);
+        let request = tari_rpc::VerifyMessageSignatureRequest {
+            message: "Tari".to_string(),
+            public_key: public_key.to_hex(),
+            public_nonce: public_nonce.to_hex(),
+            signature: secret_key.to_hex(),
+        };
+        assert!(parse_message_signature(&request).is_ok());
+
+        for malformed in &[
+            tari_rpc::VerifyMessageSignatureRequest {
+                public_key: "00".to_string(),
+                ..request.clone()
+            },
+            tari_rpc::VerifyMessageSignatureRequest {
+                public_nonce: "".to_string(),
+                ..request.clone()
+            },
+            tari_rpc::VerifyMessageSignatureRequest {
+                signature: "zz".to_string(),
+                ..request.clone()
+            },
+        ] {
+            assert_eq!(
+                parse_message_signature(malformed).unwrap_err().code(),
+                Code::InvalidArgument
+            );
+        }
+    }
+
+    #[test]
+    fn it_rejects_empty_passphrases() {
+        assert_eq!(check_new_passphrase("").unwrap_err().code(), Code::InvalidArgument);
+        assert!(check_new_passphrase("kensentme").is_ok());
+    }
+
+    #[test]
+    fn it_maps_passphrase_errors() {
+        let status = passphrase_error_to_status(WalletStorageError::IncorrectPassword.into());
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(status.message().contains("password"));
+        assert_eq!(
+            passphrase_error_to_status(WalletStorageError::InvalidEncryptionCipher.into()).code(),
+            Code::FailedPrecondition
+        );
+        assert_eq!(
+            passphrase_error_to_status(WalletStorageError::AlreadyEncrypted.into()).code(),
+            Code::FailedPrecondition
+        );
+        assert_eq!(
+            passphrase_error_to_status(WalletStorageError::NoPasswordError.into()).code(),
+            Code::Internal
+        );
+    }
+
+    #[test]
+    fn it_filters_unspent_outputs() {
+        let outputs = vec![output(500, 0), output(100, 0), output(300, 10), output(200, 0)];
+        let filter = tari_rpc::GetUnspentOutputsRequest {
+            min_value: 150,
+            max_value: 0,
+            spendable_at_height: 0,
+            limit: 2,
+        };
+        let (selected, total_count, total_value) = filter_unspent_outputs(outputs.clone(), &filter).unwrap();
+        assert_eq!(selected.iter().map(|o| o.value.as_u64()).collect::<Vec<_>>(), vec![
+            200, 300
+        ]);
+        assert_eq!(total_count, 3);
+        assert_eq!(total_value, 1000);
+
+        let filter = tari_rpc::GetUnspentOutputsRequest {
+            min_value: 0,
+            max_value: 400,
+            spendable_at_height: 5,
+            limit: 0,
+        };
+        let (selected, total_count, total_value) = filter_unspent_outputs(outputs.clone(), &filter).unwrap();
+        assert_eq!(selected.iter().map(|o| o.value.as_u64()).collect::<Vec<_>>(), vec![
+            100, 200
+        ]);
+        assert_eq!(total_count, 2);
+        assert_eq!(total_value, 300);
+
+        let filter = tari_rpc::GetUnspentOutputsRequest {
+            min_value: 400,
+            max_value: 300,
+            spendable_at_height: 0,
+            limit: 0,
+        };
+        assert_eq!(
+            filter_unspent_outputs(outputs, &filter).unwrap_err().code(),
+            Code::InvalidArgument
+        );
+    }
+}
@@ -20,9 +20,15 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{fs, path::PathBuf, str::FromStr, sync::Arc};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use log::*;
+use rand::{rngs::OsRng, RngCore};
 use rpassword::prompt_password_stdout;
 use rustyline::Editor;
 
@@ -30,12 +36,13 @@ use tari_app_utilities::utilities::{create_transport_type, ExitCodes};
 use tari_common::{ConfigBootstrap, GlobalConfig};
 use tari_common_types::types::PrivateKey;
 use tari_comms::{
-    peer_manager::{Peer, PeerFeatures},
+    peer_manager::{Peer, PeerDatabaseCipher, PeerFeatures, PEER_DATABASE_KEY_BYTES},
+    reachability::ReachabilityConfig,
     types::CommsSecretKey,
     NodeIdentity,
 };
 use tari_comms_dht::{DbConnectionUrl, DhtConfig};
-use tari_core::transactions::CryptoFactories;
+use tari_core::transactions::{tari_amount::MicroTari, CryptoFactories};
 use tari_p2p::{
     auto_update::AutoUpdateConfig,
     initialization::P2pConfig,
@@ -47,9 +54,14 @@ use tari_shutdown::ShutdownSignal;
 use tari_wallet::{
     base_node_service::config::BaseNodeServiceConfig,
     error::{WalletError, WalletStorageError},
-    output_manager_service::config::OutputManagerServiceConfig,
-    storage::{database::WalletDatabase, sqlite_utilities::initialize_sqlite_database_backends},
-    transaction_service::config::{TransactionRoutingMechanism, TransactionServiceConfig},
+    output_manager_service::config::{OutputManagerServiceConfig, UtxoConsolidationConfig},
+    storage::{
+        backup::EncryptedWalletBackup,
+        database::WalletDatabase,
+        sqlite_utilities::{change_passphrase, initialize_sqlite_database_backends, restore_wallet_backup},
+    },
+    transaction_service::config::{AcceptancePolicyConfig, TransactionRoutingMechanism, TransactionServiceConfig},
+    util::birthday::birthday_to_date,
     Wallet,
     WalletConfig,
     WalletSqlite,
@@ -114,14 +126,44 @@ fn prompt_password(prompt: &str) -> Result<String, ExitCodes> {
     Ok(password)
 }
 
-/// Allows the user to change the password of the wallet.
+/// Allows the user to change the password of the wallet. An encrypted wallet is re-encrypted under the new password
+/// without starting it, an unencrypted wallet is started and encrypted with the new password.
 pub async fn change_password(
     config: &GlobalConfig,
     arg_password: Option<String>,
     shutdown_signal: ShutdownSignal,
 ) -> Result<(), ExitCodes> {
-    let mut wallet = init_wallet(config, arg_password, None, None, shutdown_signal).await?;
+    let db_path = config.console_wallet_db_file.clone();
+    let wallet_encrypted = if db_path.exists() {
+        match initialize_sqlite_database_backends(db_path.clone(), None) {
+            Ok(_) => false,
+            Err(WalletStorageError::NoPasswordError) => true,
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        false
+    };
+
+    if wallet_encrypted {
+        let old_passphrase = get_or_prompt_password(arg_password, config.console_wallet_password.clone())?
+            .ok_or_else(|| ExitCodes::InputError("No wallet password provided".to_string()))?;
+        let passphrase = prompt_new_password()?;
+        change_passphrase(db_path, old_passphrase, passphrase)?;
+    } else {
+        let mut wallet = init_wallet(config, arg_password, None, None, None, shutdown_signal).await?;
+        let passphrase = prompt_new_password()?;
+        wallet
+            .apply_encryption(passphrase)
+            .await
+            .map_err(|e| ExitCodes::WalletError(e.to_string()))?;
+    }
+
+    println!("Wallet password changed successfully.");
 
+    Ok(())
+}
+
+fn prompt_new_password() -> Result<String, ExitCodes> {
     let passphrase = prompt_password("New wallet password: ")?;
     let confirmed = prompt_password("Confirm new password: ")?;
 
@@ -129,18 +171,33 @@ pub async fn change_password(
         return Err(ExitCodes::InputError("Passwords don't match!".to_string()));
     }
 
-    wallet
-        .remove_encryption()
-        .await
-        .map_err(|e| ExitCodes::WalletError(e.to_string()))?;
+    Ok(passphrase)
+}
 
-    wallet
-        .apply_encryption(passphrase)
-        .await
-        .map_err(|e| ExitCodes::WalletError(e.to_string()))?;
+/// Restores the wallet from an encrypted backup file into the configured wallet database, which must not exist yet.
+pub async fn restore_backup(config: &GlobalConfig, backup_file: &Path) -> Result<(), ExitCodes> {
+    let db_path = config.console_wallet_db_file.clone();
+    if db_path.exists() {
+        return Err(ExitCodes::RecoveryError(format!(
+            "Wallet already exists at {:#?}. Remove it if you really want to restore a backup in this directory!",
+            db_path
+        )));
+    }
+    fs::create_dir_all(
+        &db_path
+            .parent()
+            .expect("console_wallet_db_file cannot be set to a root directory"),
+    )
+    .map_err(|e| ExitCodes::WalletError(format!("Error creating Wallet folder. {}", e)))?;
 
-    println!("Wallet password changed successfully.");
+    let backup = EncryptedWalletBackup::read_from_file(backup_file)
+        .map_err(|e| ExitCodes::RecoveryError(format!("Could not read wallet backup: {}", e)))?;
+    let passphrase = prompt_password("Backup password: ")?;
+    restore_wallet_backup(db_path, &backup, &passphrase, &CryptoFactories::default())
+        .await
+        .map_err(|e| ExitCodes::RecoveryError(format!("Could not restore wallet backup: {}", e)))?;
 
+    println!("Wallet backup restored, its outputs will be validated with the base node.");
     Ok(())
 }
 
@@ -255,6 +312,7 @@ pub async fn init_wallet(
     arg_password: Option<String>,
     seed_words_file_name: Option<PathBuf>,
     recovery_master_key: Option<PrivateKey>,
+    recovery_birthday: Option<u16>,
     shutdown_signal: ShutdownSignal,
 ) -> Result<WalletSqlite, ExitCodes> {
     fs::create_dir_all(
@@ -292,6 +350,20 @@ pub async fn init_wallet(
     let (wallet_backend, transaction_backend, output_manager_backend, contacts_backend) = backends;
     let wallet_db = WalletDatabase::new(wallet_backend);
 
+    // The peer database is encrypted with its own random key, which is stored in the wallet database and encrypted
+    // with the wallet cipher. Changing the passphrase only re-encrypts the key, and once a key exists it is kept even
+    // if the wallet encryption is removed, so the peer records never become unreadable.
+    let peer_database_cipher = match wallet_db.get_peer_database_key().await? {
+        Some(key) => Some(PeerDatabaseCipher::from_key(&key)),
+        None if wallet_encrypted => {
+            let mut key = [0u8; PEER_DATABASE_KEY_BYTES];
+            OsRng.fill_bytes(&mut key);
+            wallet_db.set_peer_database_key(key).await?;
+            Some(PeerDatabaseCipher::from_key(&key))
+        },
+        None => None,
+    };
+
     debug!(
         target: LOG_TARGET,
         "Databases Initialized. Wallet encrypted? {}.", wallet_encrypted
@@ -313,7 +385,7 @@ pub async fn init_wallet(
         node_features,
     ));
 
-    let transport_type = create_transport_type(config);
+    let transport_type = create_transport_type(config)?;
     let transport_type = match transport_type {
         Tor(mut tor_config) => {
             tor_config.identity = wallet_db.get_tor_id().await?.map(Box::new);
@@ -328,8 +400,14 @@ pub async fn init_wallet(
         user_agent: format!("tari/wallet/{}", env!("CARGO_PKG_VERSION")),
         transport_type,
         auxilary_tcp_listener_address: None,
+        // Wallets are not expected to be reachable by other peers
+        reachability: ReachabilityConfig {
+            is_self_check_enabled: false,
+            ..Default::default()
+        },
         datastore_path: config.console_wallet_peer_db_path.clone(),
         peer_database_name: "peers".to_string(),
+        peer_database_cipher,
         max_concurrent_inbound_tasks: 100,
         outbound_buffer_size: 100,
         dht: DhtConfig {
@@ -344,6 +422,8 @@ pub async fn init_wallet(
         // This should be false unless testing locally
         allow_test_addresses: config.allow_test_addresses,
         listener_liveness_allowlist_cidrs: Vec::new(),
+        firewall_rules: Default::default(),
+        bandwidth: Default::default(),
         listener_liveness_max_sessions: 0,
         dns_seeds_name_server: DEFAULT_DNS_NAME_SERVER.parse().unwrap(),
         peer_seeds: Default::default(),
@@ -380,6 +460,16 @@ pub async fn init_wallet(
             ),
             num_confirmations_required: config.transaction_num_confirmations_required,
             transaction_event_channel_size: config.transaction_event_channel_size,
+            acceptance_policy: AcceptancePolicyConfig {
+                minimum_amount: config.wallet_acceptance_min_amount.map(MicroTari::from),
+                contacts_only: config.wallet_acceptance_contacts_only,
+                rate_limit: config
+                    .wallet_acceptance_rate_limit
+                    .map(|max_transactions| (max_transactions, config.wallet_acceptance_rate_limit_period)),
+                approval_threshold: config.wallet_acceptance_approval_threshold.map(MicroTari::from),
+            },
+            held_transaction_approval_timeout: config.wallet_held_transaction_approval_timeout,
+            max_held_transactions: config.wallet_max_held_transactions,
             ..Default::default()
         }),
         Some(OutputManagerServiceConfig {
@@ -387,6 +477,18 @@ pub async fn init_wallet(
             prevent_fee_gt_amount: config.prevent_fee_gt_amount,
             event_channel_size: config.output_manager_event_channel_size,
             num_confirmations_required: config.transaction_num_confirmations_required,
+            utxo_consolidation: UtxoConsolidationConfig {
+                enabled: config.wallet_utxo_consolidation_enabled,
+                dry_run: config.wallet_utxo_consolidation_dry_run,
+                interval: config.wallet_utxo_consolidation_interval,
+                utxo_count_threshold: config.wallet_utxo_consolidation_threshold,
+                max_inputs_per_transaction: config.wallet_utxo_consolidation_max_inputs,
+                max_transactions_per_run: config.wallet_utxo_consolidation_max_transactions,
+                fee_per_gram: MicroTari::from(config.wallet_utxo_consolidation_fee_per_gram),
+                max_network_fee_per_gram: config
+                    .wallet_utxo_consolidation_max_network_fee_per_gram
+                    .map(MicroTari::from),
+            },
             ..Default::default()
         }),
         config.network.into(),
@@ -425,6 +527,13 @@ pub async fn init_wallet(
             .await
             .map_err(|e| ExitCodes::WalletError(format!("Problem writing tor identity. {}", e)))?;
     }
+    if let Some(birthday) = recovery_birthday {
+        wallet
+            .db
+            .set_wallet_birthday(birthday)
+            .await
+            .map_err(|e| ExitCodes::WalletError(format!("Problem writing wallet birthday. {}", e)))?;
+    }
 
     if !wallet_encrypted {
         debug!(target: LOG_TARGET, "Wallet is not encrypted.");
@@ -470,10 +579,12 @@ pub async fn init_wallet(
     Ok(wallet)
 }
 
-/// Starts the wallet by setting the base node peer, and restarting the transaction and broadcast protocols.
+/// Starts the wallet by setting the base node peer and the pool of base nodes to fail over to, and restarting the
+/// transaction and broadcast protocols.
 pub async fn start_wallet(
     wallet: &mut WalletSqlite,
     base_node: &Peer,
+    base_node_pool: Vec<Peer>,
     wallet_mode: &WalletMode,
 ) -> Result<(), ExitCodes> {
     // TODO gRPC interfaces for setting base node
@@ -490,6 +601,16 @@ pub async fn start_wallet(
         .await
         .map_err(|e| ExitCodes::WalletError(format!("Error setting wallet base node peer. {}", e)))?;
 
+    debug!(
+        target: LOG_TARGET,
+        "Setting base node pool with {} base node(s)",
+        base_node_pool.len()
+    );
+    wallet
+        .set_base_node_pool(base_node_pool)
+        .await
+        .map_err(|e| ExitCodes::WalletError(format!("Error setting wallet base node pool. {}", e)))?;
+
     // Restart transaction protocols if not running in script or command modes
 
     if !matches!(wallet_mode, WalletMode::Command(_)) && !matches!(wallet_mode, WalletMode::Script(_)) {
@@ -509,7 +630,7 @@ pub async fn start_wallet(
     Ok(())
 }
 
-async fn validate_txos(wallet: &mut WalletSqlite) -> Result<(), ExitCodes> {
+pub async fn validate_txos(wallet: &mut WalletSqlite) -> Result<(), ExitCodes> {
     debug!(target: LOG_TARGET, "Starting TXO validations.");
 
     wallet.output_manager_service.validate_txos().await.map_err(|e| {
@@ -524,6 +645,11 @@ async fn validate_txos(wallet: &mut WalletSqlite) -> Result<(), ExitCodes> {
 
 async fn confirm_seed_words(wallet: &mut WalletSqlite) -> Result<(), ExitCodes> {
     let seed_words = wallet.output_manager_service.get_seed_words().await?;
+    let birthday = wallet
+        .db
+        .get_wallet_birthday()
+        .await
+        .map_err(|e| ExitCodes::WalletError(format!("Problem reading wallet birthday. {}", e)))?;
 
     println!();
     println!("=========================");
@@ -536,6 +662,11 @@ async fn confirm_seed_words(wallet: &mut WalletSqlite) -> Result<(), ExitCodes>
     println!("=========================");
     println!("{}", seed_words.join(" "));
     println!("=========================");
+    if let Some(birthday) = birthday {
+        println!("Wallet birthday: {}", birthday_to_date(birthday));
+        println!("Keep the birthday with your seed words, it lets a recovery skip the blocks mined before it.");
+        println!("=========================");
+    }
     println!("\x07"); // beep!
 
     let mut rl = Editor::<()>::new();
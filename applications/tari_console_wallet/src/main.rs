@@ -13,14 +13,16 @@ use init::{
     get_base_node_peer_config,
     get_notify_script,
     init_wallet,
+    restore_backup,
     start_wallet,
     tari_splash_screen,
+    validate_txos,
     wallet_mode,
     WalletBoot,
 };
 use log::*;
 use opentelemetry::{self, global, KeyValue};
-use recovery::prompt_private_key_from_seed_words;
+use recovery::{parse_wallet_birthday, prompt_private_key_from_seed_words, prompt_wallet_birthday};
 use std::{env, process};
 use tari_app_utilities::{consts, initialization::init_configuration, utilities::ExitCodes};
 use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap};
@@ -34,6 +36,7 @@ pub const LOG_TARGET: &str = "wallet::console_wallet::main";
 mod automation;
 mod grpc;
 mod init;
+mod metrics;
 mod notifier;
 mod recovery;
 mod ui;
@@ -82,10 +85,20 @@ fn main_inner() -> Result<(), ExitCodes> {
         tari_splash_screen("Console Wallet");
     }
 
+    // a restored backup is then started as an existing wallet
+    let restored_backup = match bootstrap.restore_backup.as_ref() {
+        Some(backup_file) => {
+            runtime.block_on(restore_backup(&global_config, backup_file))?;
+            true
+        },
+        None => false,
+    };
+
     // check for recovery based on existence of wallet file
     let mut boot_mode = boot(&bootstrap, &global_config)?;
 
     let recovery_master_key: Option<PrivateKey> = get_recovery_master_key(boot_mode, &bootstrap)?;
+    let recovery_birthday = get_recovery_birthday(boot_mode, &bootstrap)?;
 
     if bootstrap.init {
         info!(target: LOG_TARGET, "Default configuration created. Done.");
@@ -110,6 +123,7 @@ fn main_inner() -> Result<(), ExitCodes> {
         arg_password,
         seed_words_file_name,
         recovery_master_key,
+        recovery_birthday,
         shutdown_signal,
     ))?;
 
@@ -126,7 +140,17 @@ fn main_inner() -> Result<(), ExitCodes> {
     let wallet_mode = wallet_mode(&bootstrap, boot_mode);
 
     // start wallet
-    runtime.block_on(start_wallet(&mut wallet, &base_node_selected, &wallet_mode))?;
+    runtime.block_on(start_wallet(
+        &mut wallet,
+        &base_node_selected,
+        base_node_config.get_base_node_pool(),
+        &wallet_mode,
+    ))?;
+
+    // start_wallet only validates the outputs in the interactive modes, but a restored wallet always has to
+    if restored_backup && matches!(wallet_mode, WalletMode::Command(_) | WalletMode::Script(_)) {
+        runtime.block_on(validate_txos(&mut wallet))?;
+    }
 
     // optional path to notify script
     let notify_script = get_notify_script(&bootstrap, &global_config)?;
@@ -185,6 +209,18 @@ fn get_recovery_master_key(
     }
 }
 
+fn get_recovery_birthday(boot_mode: WalletBoot, bootstrap: &ConfigBootstrap) -> Result<Option<u16>, ExitCodes> {
+    if !matches!(boot_mode, WalletBoot::Recovery) {
+        return Ok(None);
+    }
+    match bootstrap.wallet_birthday {
+        Some(ref date) => Ok(Some(parse_wallet_birthday(date)?)),
+        // Only ask for the birthday when the seed words were also typed in
+        None if bootstrap.seed_words.is_none() => prompt_wallet_birthday(),
+        None => Ok(None),
+    }
+}
+
 fn enable_tracing_if_specified(bootstrap: &ConfigBootstrap) {
     if bootstrap.tracing_enabled {
         // To run: docker run -d -p6831:6831/udp -p6832:6832/udp -p16686:16686 -p14268:14268 \
@@ -0,0 +1,129 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Prometheus metrics for the console wallet. The comms metrics are registered in the same registry, so they are
+//! served alongside these when the metrics server is enabled.
+
+use log::*;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use tari_metrics::{IntGauge, IntGaugeVec};
+use tari_wallet::{
+    transaction_service::{
+        error::TransactionServiceError,
+        handle::TransactionServiceHandle,
+        storage::models::TransactionStatus,
+    },
+    WalletSqlite,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+const LOG_TARGET: &str = "wallet::metrics";
+
+/// The label used for cancelled transactions, which keep the status they had when they were cancelled
+const CANCELLED: &str = "cancelled";
+
+pub fn transactions(state: &str) -> IntGauge {
+    static METER: Lazy<IntGaugeVec> = Lazy::new(|| {
+        tari_metrics::register_int_gauge_vec(
+            "wallet_transactions",
+            "Number of wallet transactions by transaction state",
+            &["state"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[state])
+}
+
+/// Keep the transaction state metrics up to date until the transaction event stream closes
+pub async fn run(wallet: WalletSqlite) {
+    let mut transaction_service = wallet.transaction_service.clone();
+    let mut events = transaction_service.get_event_stream();
+    drop(wallet);
+
+    loop {
+        if let Err(e) = update_transaction_states(&mut transaction_service).await {
+            warn!(target: LOG_TARGET, "Could not update transaction metrics: {}", e);
+        }
+        match events.recv().await {
+            Ok(_) | Err(RecvError::Lagged(_)) => {},
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn update_transaction_states(
+    transaction_service: &mut TransactionServiceHandle,
+) -> Result<(), TransactionServiceError> {
+    // Start every state at zero so that states without transactions are reported too
+    let mut counts = STATES.iter().map(|state| (*state, 0)).collect::<HashMap<_, i64>>();
+
+    let num_pending = transaction_service.get_pending_inbound_transactions().await?.len() +
+        transaction_service.get_pending_outbound_transactions().await?.len();
+    *counts.entry(state_label(&TransactionStatus::Pending)).or_default() += num_pending as i64;
+
+    for tx in transaction_service.get_completed_transactions().await?.values() {
+        *counts.entry(state_label(&tx.status)).or_default() += 1;
+    }
+
+    let num_cancelled = transaction_service
+        .get_cancelled_pending_inbound_transactions()
+        .await?
+        .len() +
+        transaction_service
+            .get_cancelled_pending_outbound_transactions()
+            .await?
+            .len() +
+        transaction_service.get_cancelled_completed_transactions().await?.len();
+    *counts.entry(CANCELLED).or_default() += num_cancelled as i64;
+
+    for (state, count) in counts {
+        transactions(state).set(count);
+    }
+    Ok(())
+}
+
+const STATES: [&str; 9] = [
+    "completed",
+    "broadcast",
+    "mined_unconfirmed",
+    "mined_confirmed",
+    "imported",
+    "pending",
+    "coinbase",
+    "scheduled",
+    CANCELLED,
+];
+
+fn state_label(status: &TransactionStatus) -> &'static str {
+    match status {
+        TransactionStatus::Completed => "completed",
+        TransactionStatus::Broadcast => "broadcast",
+        TransactionStatus::MinedUnconfirmed => "mined_unconfirmed",
+        TransactionStatus::MinedConfirmed => "mined_confirmed",
+        TransactionStatus::Imported => "imported",
+        TransactionStatus::Pending => "pending",
+        TransactionStatus::Coinbase => "coinbase",
+        TransactionStatus::Scheduled => "scheduled",
+    }
+}
@@ -0,0 +1,543 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Delivers the wallet's events to HTTP webhooks.
+//!
+//! Every transaction, output manager and UTXO scanner event is posted as a JSON object with an `id`, a `timestamp`,
+//! the `source` service and the `event` itself. When a secret is configured the body is signed with HMAC-SHA256 and
+//! the hex encoded signature is sent in the [SIGNATURE_HEADER] header.
+//!
+//! Events are first written to an outbox directory, one file per event and webhook, and are only removed once the
+//! webhook has answered with a success status. Failed deliveries are retried with an exponential backoff until the
+//! maximum number of retries is reached, after which they are moved to the `failed` subdirectory of the outbox.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use log::*;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tari_common::GlobalConfig;
+use tari_core::tari_utilities::hex::Hex;
+use tari_wallet::WalletSqlite;
+use thiserror::Error;
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc},
+    time,
+};
+
+pub const LOG_TARGET: &str = "wallet::notifier::webhook";
+/// The header carrying the hex encoded HMAC-SHA256 signature of the request body
+pub const SIGNATURE_HEADER: &str = "X-Tari-Signature";
+
+const OUTBOX_DIR: &str = "webhook_outbox";
+const FAILED_DIR: &str = "failed";
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("Webhook outbox IO error: `{0}`")]
+    IoError(#[from] io::Error),
+    #[error("Webhook serialization error: `{0}`")]
+    SerializationError(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    pub secret: Option<String>,
+    pub max_retries: u32,
+    /// The delay before the first retry, which doubles with every following attempt
+    pub retry_base_delay: Duration,
+    pub outbox_path: PathBuf,
+}
+
+impl WebhookConfig {
+    /// The webhook settings of the console wallet, or `None` if no webhooks are configured. The outbox is kept next
+    /// to the wallet database.
+    pub fn from_global_config(config: &GlobalConfig) -> Option<Self> {
+        if config.console_wallet_webhooks.is_empty() {
+            return None;
+        }
+        let outbox_path = match config.console_wallet_db_file.parent() {
+            Some(dir) => dir.join(OUTBOX_DIR),
+            None => PathBuf::from(OUTBOX_DIR),
+        };
+        Some(Self {
+            urls: config.console_wallet_webhooks.clone(),
+            secret: config.console_wallet_webhook_secret.clone(),
+            max_retries: config.console_wallet_webhook_max_retries,
+            retry_base_delay: Duration::from_secs(1),
+            outbox_path,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a, T> {
+    id: u64,
+    timestamp: DateTime<Utc>,
+    source: &'a str,
+    event: &'a T,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+struct OutboxEntry {
+    url: String,
+    body: String,
+    attempts: u32,
+    next_attempt: DateTime<Utc>,
+}
+
+/// The deliveries that have not been made yet, kept on disk so that they survive a restart
+struct Outbox {
+    path: PathBuf,
+    last_id: AtomicU64,
+}
+
+impl Outbox {
+    fn open(path: PathBuf) -> Result<Self, WebhookError> {
+        fs::create_dir_all(path.join(FAILED_DIR))?;
+        Ok(Self {
+            path,
+            last_id: AtomicU64::new(0),
+        })
+    }
+
+    /// Event ids follow the clock so that they remain unique across restarts
+    fn next_id(&self) -> u64 {
+        let now = Utc::now().timestamp_nanos() as u64;
+        let previous = self
+            .last_id
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(now.max(last + 1)))
+            .unwrap_or_default();
+        now.max(previous + 1)
+    }
+
+    fn push(&self, id: u64, webhook_index: usize, entry: &OutboxEntry) -> Result<(), WebhookError> {
+        self.write(&self.path.join(format!("{:020}-{}.json", id, webhook_index)), entry)
+    }
+
+    fn write(&self, file: &Path, entry: &OutboxEntry) -> Result<(), WebhookError> {
+        // Write to a temporary file first so that a crash never leaves a partial entry behind
+        let tmp_file = file.with_extension("tmp");
+        fs::write(&tmp_file, serde_json::to_vec(entry)?)?;
+        fs::rename(tmp_file, file)?;
+        Ok(())
+    }
+
+    /// The pending entries, oldest first
+    fn pending(&self) -> Result<Vec<(PathBuf, OutboxEntry)>, WebhookError> {
+        let mut files = fs::read_dir(&self.path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        let mut entries = Vec::with_capacity(files.len());
+        for file in files {
+            match serde_json::from_slice(&fs::read(&file)?) {
+                Ok(entry) => entries.push((file, entry)),
+                Err(e) => {
+                    error!(
+                        target: LOG_TARGET,
+                        "Invalid webhook outbox entry {}: {}",
+                        file.display(),
+                        e
+                    );
+                    self.fail(&file)?;
+                },
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove(&self, file: &Path) -> Result<(), WebhookError> {
+        fs::remove_file(file)?;
+        Ok(())
+    }
+
+    /// Set aside an entry that will not be retried any more
+    fn fail(&self, file: &Path) -> Result<(), WebhookError> {
+        if let Some(name) = file.file_name() {
+            fs::rename(file, self.path.join(FAILED_DIR).join(name))?;
+        }
+        Ok(())
+    }
+}
+
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+    outbox: Outbox,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Result<Self, WebhookError> {
+        let outbox = Outbox::open(config.outbox_path.clone())?;
+        Ok(Self {
+            config,
+            outbox,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Queue an event in the outbox for delivery to every webhook
+    pub fn notify<T: Serialize>(&self, source: &str, event: &T) -> Result<(), WebhookError> {
+        let id = self.outbox.next_id();
+        let now = Utc::now();
+        let body = serde_json::to_string(&WebhookPayload {
+            id,
+            timestamp: now,
+            source,
+            event,
+        })?;
+        for (index, url) in self.config.urls.iter().enumerate() {
+            let entry = OutboxEntry {
+                url: url.clone(),
+                body: body.clone(),
+                attempts: 0,
+                next_attempt: now,
+            };
+            self.outbox.push(id, index, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Attempt every delivery in the outbox that is due and return the number that succeeded
+    pub async fn deliver_pending(&self) -> Result<usize, WebhookError> {
+        let now = Utc::now();
+        let mut delivered = 0;
+        for (file, mut entry) in self.outbox.pending()? {
+            if entry.next_attempt > now {
+                continue;
+            }
+            match self.deliver(&entry).await {
+                Ok(()) => {
+                    self.outbox.remove(&file)?;
+                    delivered += 1;
+                },
+                Err(e) => {
+                    entry.attempts += 1;
+                    if entry.attempts > self.config.max_retries {
+                        error!(
+                            target: LOG_TARGET,
+                            "Giving up on webhook delivery to {} after {} attempts: {}", entry.url, entry.attempts, e
+                        );
+                        self.outbox.fail(&file)?;
+                    } else {
+                        let delay = self.retry_delay(entry.attempts);
+                        warn!(
+                            target: LOG_TARGET,
+                            "Webhook delivery to {} failed, retrying in {:?}: {}", entry.url, delay, e
+                        );
+                        entry.next_attempt =
+                            now + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+                        self.outbox.write(&file, &entry)?;
+                    }
+                },
+            }
+        }
+        Ok(delivered)
+    }
+
+    fn retry_delay(&self, attempts: u32) -> Duration {
+        self.config
+            .retry_base_delay
+            .checked_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+            .unwrap_or(MAX_RETRY_DELAY)
+            .min(MAX_RETRY_DELAY)
+    }
+
+    async fn deliver(&self, entry: &OutboxEntry) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(&entry.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .timeout(REQUEST_TIMEOUT);
+        if let Some(secret) = &self.config.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, &entry.body));
+        }
+        let response = request
+            .body(entry.body.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("webhook responded with {}", response.status()))
+        }
+    }
+
+    /// Queue the wallet's events and deliver them until the wallet's event streams close. Deliveries run in their
+    /// own task so that a slow webhook does not hold up the event streams.
+    pub async fn run(self, wallet: WalletSqlite) {
+        let mut transaction_events = wallet.transaction_service.get_event_stream();
+        let mut output_manager_events = wallet.output_manager_service.get_event_stream();
+        let mut utxo_scanner_events = wallet.utxo_scanner_service.clone().get_event_receiver();
+        drop(wallet);
+
+        info!(
+            target: LOG_TARGET,
+            "Delivering wallet events to {} webhook(s)",
+            self.config.urls.len()
+        );
+        let notifier = Arc::new(self);
+        let (wake_sender, wake_receiver) = mpsc::channel(1);
+        tokio::spawn(notifier.clone().delivery_loop(wake_receiver));
+
+        loop {
+            let queued = tokio::select! {
+                event = transaction_events.recv() => notifier.queue("transaction", event.map(|e| (*e).clone())),
+                event = output_manager_events.recv() => notifier.queue("output_manager", event.map(|e| (*e).clone())),
+                event = utxo_scanner_events.recv() => notifier.queue("utxo_scanner", event),
+            };
+            match queued {
+                Some(()) => {
+                    let _ = wake_sender.try_send(());
+                },
+                None => break,
+            }
+        }
+        info!(
+            target: LOG_TARGET,
+            "Wallet event streams closed, stopping webhook notifier"
+        );
+    }
+
+    /// Queue a received event. Returns `None` once the event stream has closed.
+    fn queue<T: Serialize>(&self, source: &str, event: Result<T, RecvError>) -> Option<()> {
+        match event {
+            Ok(event) => {
+                if let Err(e) = self.notify(source, &event) {
+                    error!(
+                        target: LOG_TARGET,
+                        "Could not queue {} event for webhooks: {}", source, e
+                    );
+                }
+                Some(())
+            },
+            Err(RecvError::Lagged(n)) => {
+                warn!(target: LOG_TARGET, "Webhook notifier missed {} {} events", n, source);
+                Some(())
+            },
+            Err(RecvError::Closed) => None,
+        }
+    }
+
+    async fn delivery_loop(self: Arc<Self>, mut wake_receiver: mpsc::Receiver<()>) {
+        let mut retry_interval = time::interval(RETRY_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                woken = wake_receiver.recv() => {
+                    if woken.is_none() {
+                        break;
+                    }
+                },
+                _ = retry_interval.tick() => {},
+            }
+            if let Err(e) = self.deliver_pending().await {
+                error!(target: LOG_TARGET, "Error delivering webhook events: {}", e);
+            }
+        }
+    }
+}
+
+/// The hex encoded HMAC-SHA256 of the body, keyed with the webhook secret
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes().to_vec().to_hex()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+    use tari_wallet::transaction_service::handle::TransactionEvent;
+    use tempfile::tempdir;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// A local HTTP stand-in for a webhook that records the requests it receives and answers with the given status
+    /// codes in turn, repeating the last one
+    struct WebhookStandIn {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl WebhookStandIn {
+        async fn start(statuses: Vec<u16>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/events", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+            tokio::spawn(async move {
+                let mut statuses = statuses.into_iter().peekable();
+                let mut status = 200;
+                loop {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 4096];
+                    // Read until the whole body announced by the Content-Length header has arrived
+                    loop {
+                        let n = socket.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..n]);
+                        let text = String::from_utf8_lossy(&request).to_string();
+                        if let Some(header_end) = text.find("\r\n\r\n") {
+                            let content_length = text
+                                .lines()
+                                .find_map(|l| l.to_lowercase().strip_prefix("content-length: ").map(String::from))
+                                .and_then(|l| l.trim().parse::<usize>().ok())
+                                .unwrap_or(0);
+                            if request.len() >= header_end + 4 + content_length || n == 0 {
+                                break;
+                            }
+                        }
+                    }
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push(String::from_utf8_lossy(&request).to_string());
+                    if let Some(next) = statuses.next() {
+                        status = next;
+                    }
+                    let response = format!(
+                        "HTTP/1.1 {} Stand-in\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                        status
+                    );
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn config(urls: Vec<String>, outbox_path: PathBuf) -> WebhookConfig {
+        WebhookConfig {
+            urls,
+            secret: Some("secret".to_string()),
+            max_retries: 2,
+            retry_base_delay: Duration::from_millis(0),
+            outbox_path,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_delivers_signed_events() {
+        let temp_dir = tempdir().unwrap();
+        let webhook = WebhookStandIn::start(vec![200]).await;
+        let notifier = WebhookNotifier::new(config(vec![webhook.url.clone()], temp_dir.path().to_path_buf())).unwrap();
+
+        notifier
+            .notify("transaction", &TransactionEvent::ReceivedTransaction(42))
+            .unwrap();
+        assert_eq!(notifier.deliver_pending().await.unwrap(), 1);
+        assert!(notifier.outbox.pending().unwrap().is_empty());
+
+        let requests = webhook.requests();
+        assert_eq!(requests.len(), 1);
+        let (headers, body) = requests[0].split_at(requests[0].find("\r\n\r\n").unwrap());
+        let body = body.trim_start();
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["source"], "transaction");
+        assert_eq!(payload["event"]["ReceivedTransaction"], 42);
+        assert!(headers.to_lowercase().contains(&format!(
+            "{}: {}",
+            SIGNATURE_HEADER.to_lowercase(),
+            sign("secret", body)
+        )));
+    }
+
+    #[tokio::test]
+    async fn it_retries_failed_deliveries_from_the_persistent_outbox() {
+        let temp_dir = tempdir().unwrap();
+        let webhook = WebhookStandIn::start(vec![500, 200]).await;
+        let notifier = WebhookNotifier::new(config(vec![webhook.url.clone()], temp_dir.path().to_path_buf())).unwrap();
+
+        notifier
+            .notify("transaction", &TransactionEvent::TransactionCancelled(7))
+            .unwrap();
+        assert_eq!(notifier.deliver_pending().await.unwrap(), 0);
+        let pending = notifier.outbox.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1.attempts, 1);
+        drop(notifier);
+
+        // The failed delivery is still in the outbox after a restart
+        let notifier = WebhookNotifier::new(config(vec![webhook.url.clone()], temp_dir.path().to_path_buf())).unwrap();
+        assert_eq!(notifier.deliver_pending().await.unwrap(), 1);
+        assert!(notifier.outbox.pending().unwrap().is_empty());
+        let requests = webhook.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].split("\r\n\r\n").last(),
+            requests[1].split("\r\n\r\n").last()
+        );
+    }
+
+    #[tokio::test]
+    async fn it_sets_aside_deliveries_after_the_maximum_retries() {
+        let temp_dir = tempdir().unwrap();
+        let webhook = WebhookStandIn::start(vec![503]).await;
+        let notifier = WebhookNotifier::new(config(vec![webhook.url.clone()], temp_dir.path().to_path_buf())).unwrap();
+
+        notifier
+            .notify("transaction", &TransactionEvent::TransactionBroadcast(1))
+            .unwrap();
+        for _ in 0..3 {
+            assert_eq!(notifier.deliver_pending().await.unwrap(), 0);
+        }
+        assert!(notifier.outbox.pending().unwrap().is_empty());
+        assert_eq!(fs::read_dir(temp_dir.path().join(FAILED_DIR)).unwrap().count(), 1);
+        assert_eq!(webhook.requests().len(), 3);
+    }
+
+    #[test]
+    fn it_backs_off_exponentially() {
+        let temp_dir = tempdir().unwrap();
+        let mut config = config(vec![], temp_dir.path().to_path_buf());
+        config.retry_base_delay = Duration::from_secs(1);
+        let notifier = WebhookNotifier::new(config).unwrap();
+        assert_eq!(notifier.retry_delay(1), Duration::from_secs(1));
+        assert_eq!(notifier.retry_delay(4), Duration::from_secs(8));
+        assert_eq!(notifier.retry_delay(40), MAX_RETRY_DELAY);
+    }
+}
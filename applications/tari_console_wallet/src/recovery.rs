@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use chrono::offset::Local;
+use chrono::{offset::Local, NaiveDate};
 use futures::FutureExt;
 use log::*;
 use rustyline::Editor;
@@ -31,6 +31,7 @@ use tari_key_manager::mnemonic::to_secretkey;
 use tari_shutdown::Shutdown;
 use tari_wallet::{
     storage::sqlite_db::WalletSqliteDatabase,
+    util::birthday::birthday_from_date,
     utxo_scanner_service::{handle::UtxoScannerEvent, utxo_scanning::UtxoScannerService},
     WalletSqlite,
 };
@@ -76,6 +77,44 @@ pub fn get_private_key_from_seed_words(seed_words: Vec<String>) -> Result<Privat
     }
 }
 
+/// Prompt the user for the optional wallet birthday that was shown with their seed words.
+pub fn prompt_wallet_birthday() -> Result<Option<u16>, ExitCodes> {
+    debug!(target: LOG_TARGET, "Prompting for wallet birthday.");
+    let mut rl = Editor::<()>::new();
+
+    loop {
+        println!();
+        println!(
+            "Type the wallet birthday shown with your seed words (YYYY-MM-DD), or press Enter to scan the whole chain."
+        );
+        let input = rl.readline(">> ").map_err(|e| ExitCodes::IOError(e.to_string()))?;
+        if input.trim().is_empty() {
+            break Ok(None);
+        }
+
+        match parse_wallet_birthday(&input) {
+            Ok(birthday) => break Ok(Some(birthday)),
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            },
+        }
+    }
+}
+
+/// Return the wallet birthday for a date in the YYYY-MM-DD format.
+pub fn parse_wallet_birthday(date: &str) -> Result<u16, ExitCodes> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .ok()
+        .and_then(birthday_from_date)
+        .ok_or_else(|| {
+            ExitCodes::RecoveryError(format!(
+                "Invalid wallet birthday '{}', expected a date such as 2021-11-08",
+                date.trim()
+            ))
+        })
+}
+
 /// Recovers wallet funds by connecting to a given base node peer, downloading the transaction outputs stored in the
 /// blockchain, and attempting to rewind them. Any outputs that are successfully rewound are then imported into the
 /// wallet.
@@ -121,22 +160,31 @@ pub async fn wallet_recovery(wallet: &WalletSqlite, base_node_config: &PeerConfi
             Ok(UtxoScannerEvent::Progress {
                 current_block: current,
                 current_chain_height: total,
+                time_remaining,
             }) => {
-                let percentage_progress = ((current as f32) * 100f32 / (total as f32)).round() as u32;
+                let percentage_progress = ((current as f32) * 100f32 / (total.max(1) as f32)).round() as u32;
+                let eta = time_remaining
+                    .map(|t| {
+                        let secs = t.as_secs();
+                        format!(", about {}h {:02}m {:02}s left", secs / 3600, secs / 60 % 60, secs % 60)
+                    })
+                    .unwrap_or_default();
                 debug!(
                     target: LOG_TARGET,
-                    "{}: Recovery process {}% complete ({} of {} utxos).",
+                    "{}: Recovery process {}% complete (block {} of {}{}).",
                     Local::now(),
                     percentage_progress,
                     current,
-                    total
+                    total,
+                    eta
                 );
                 println!(
-                    "{}: Recovery process {}% complete ({} of {} utxos).",
+                    "{}: Recovery process {}% complete (block {} of {}{}).",
                     Local::now(),
                     percentage_progress,
                     current,
-                    total
+                    total,
+                    eta
                 );
             },
             Ok(UtxoScannerEvent::ScanningRoundFailed {
@@ -23,6 +23,7 @@ pub struct TransactionsTab {
     selected_tx_list: SelectedTransactionList,
     pending_list_state: WindowedListState,
     completed_list_state: WindowedListState,
+    held_list_state: WindowedListState,
     detailed_transaction: Option<CompletedTransactionInfo>,
     error_message: Option<String>,
     confirmation_dialog: bool,
@@ -35,6 +36,7 @@ impl TransactionsTab {
             selected_tx_list: SelectedTransactionList::None,
             pending_list_state: WindowedListState::new(),
             completed_list_state: WindowedListState::new(),
+            held_list_state: WindowedListState::new(),
             detailed_transaction: None,
             error_message: None,
             confirmation_dialog: false,
@@ -43,8 +45,30 @@ impl TransactionsTab {
 
     fn draw_transaction_lists<B>(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
     where B: Backend {
+        // Held transactions are only listed while there are any waiting for approval
+        let area = if app_state.get_held_txs().is_empty() {
+            if self.selected_tx_list == SelectedTransactionList::HeldTxs {
+                self.selected_tx_list = SelectedTransactionList::None;
+            }
+            area
+        } else {
+            let areas = Layout::default()
+                .constraints(
+                    [
+                        Constraint::Length((3 + app_state.get_held_txs().len()).min(6) as u16),
+                        Constraint::Min(7),
+                    ]
+                    .as_ref(),
+                )
+                .split(area);
+            self.draw_held_transactions(f, areas[0], app_state);
+            areas[1]
+        };
+
         let (pending_constraint, completed_constraint) = if app_state.get_pending_txs().is_empty() {
-            self.selected_tx_list = SelectedTransactionList::CompletedTxs;
+            if self.selected_tx_list != SelectedTransactionList::HeldTxs {
+                self.selected_tx_list = SelectedTransactionList::CompletedTxs;
+            }
             (Constraint::Max(3), Constraint::Min(4))
         } else {
             (
@@ -136,6 +160,54 @@ impl TransactionsTab {
         column_list.render(f, area, &mut pending_list_state);
     }
 
+    fn draw_held_transactions<B>(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
+    where B: Backend {
+        let style = if self.selected_tx_list == SelectedTransactionList::HeldTxs {
+            Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled("(H)eld Transactions", style));
+        f.render_widget(block, area);
+
+        self.held_list_state.set_num_items(app_state.get_held_txs().len());
+        let mut held_list_state = self
+            .held_list_state
+            .get_list_state((area.height as usize).saturating_sub(3));
+        let window = self.held_list_state.get_start_end();
+        let windowed_view = app_state.get_held_txs_slice(window.0, window.1);
+
+        let mut column0_items = Vec::new();
+        let mut column1_items = Vec::new();
+        let mut column2_items = Vec::new();
+        let mut column3_items = Vec::new();
+        for t in windowed_view.iter() {
+            column0_items.push(ListItem::new(Span::raw(app_state.get_alias(&t.source_public_key))));
+            column1_items.push(ListItem::new(Span::styled(
+                format!("{}", t.amount),
+                Style::default().fg(Color::Green),
+            )));
+            let local_time = DateTime::<Local>::from_utc(t.timestamp, Local::now().offset().to_owned());
+            column2_items.push(ListItem::new(Span::raw(format!(
+                "{}",
+                local_time.format("%Y-%m-%d %H:%M:%S")
+            ))));
+            column3_items.push(ListItem::new(Span::raw(t.message.as_str())));
+        }
+
+        let column_list = MultiColumnList::new()
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD).fg(Color::Magenta))
+            .heading_style(Style::default().fg(Color::Magenta))
+            .max_width(MAX_WIDTH)
+            .add_column(Some("Source Public Key"), Some(67), column0_items)
+            .add_column(Some("Amount"), Some(18), column1_items)
+            .add_column(Some("Local Date/Time"), Some(20), column2_items)
+            .add_column(Some("Message"), None, column3_items);
+        column_list.render(f, area, &mut held_list_state);
+    }
+
     fn draw_completed_transactions<B>(&mut self, f: &mut Frame<B>, area: Rect, app_state: &AppState)
     where B: Backend {
         //  Completed Transactions
@@ -436,6 +508,10 @@ impl<B: Backend> Component<B> for TransactionsTab {
         span_vec.push(Span::raw(" cancels a selected Pending Tx, "));
         span_vec.push(Span::styled("A", Style::default().add_modifier(Modifier::BOLD)));
         span_vec.push(Span::raw(" shows abandoned coinbase Txs, "));
+        if !app_state.get_held_txs().is_empty() {
+            span_vec.push(Span::styled("V/R", Style::default().add_modifier(Modifier::BOLD)));
+            span_vec.push(Span::raw(" approves/rejects a selected Held Tx, "));
+        }
         span_vec.push(Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)));
         span_vec.push(Span::raw(" exits the list."));
 
@@ -491,7 +567,51 @@ impl<B: Backend> Component<B> for TransactionsTab {
         }
 
         match c {
+            'h' => {
+                if app_state.get_held_txs().is_empty() {
+                    return;
+                }
+                self.pending_list_state.select(None);
+                self.completed_list_state.select(None);
+                self.selected_tx_list = SelectedTransactionList::HeldTxs;
+                self.held_list_state.set_num_items(app_state.get_held_txs().len());
+                if self.held_list_state.selected().is_none() {
+                    self.held_list_state.select_first();
+                }
+                self.detailed_transaction = None;
+            },
+            'v' | 'r' => {
+                if self.selected_tx_list != SelectedTransactionList::HeldTxs {
+                    return;
+                }
+                if let Some(held_tx) = self
+                    .held_list_state
+                    .selected()
+                    .and_then(|i| app_state.get_held_tx(i).cloned())
+                {
+                    let (result, action) = if 'v' == c {
+                        (
+                            Handle::current().block_on(app_state.approve_held_transaction(held_tx.tx_id)),
+                            "approve",
+                        )
+                    } else {
+                        (
+                            Handle::current().block_on(app_state.reject_held_transaction(held_tx.tx_id)),
+                            "reject",
+                        )
+                    };
+                    if let Err(e) = result {
+                        self.error_message = Some(format!(
+                            "Could not {} held transaction.\n{}\nPress Enter to continue.",
+                            action, e
+                        ));
+                    }
+                    self.held_list_state.select(None);
+                    self.selected_tx_list = SelectedTransactionList::None;
+                }
+            },
             'p' => {
+                self.held_list_state.select(None);
                 self.completed_list_state.select(None);
                 self.selected_tx_list = SelectedTransactionList::PendingTxs;
                 self.pending_list_state.set_num_items(app_state.get_pending_txs().len());
@@ -505,6 +625,7 @@ impl<B: Backend> Component<B> for TransactionsTab {
                 self.detailed_transaction = app_state.get_pending_tx(idx).cloned()
             },
             't' => {
+                self.held_list_state.select(None);
                 self.pending_list_state.select(None);
                 self.selected_tx_list = SelectedTransactionList::CompletedTxs;
                 self.completed_list_state
@@ -525,7 +646,7 @@ impl<B: Backend> Component<B> for TransactionsTab {
             },
             'a' => app_state.toggle_abandoned_coinbase_filter(),
             '\n' => match self.selected_tx_list {
-                SelectedTransactionList::None => {},
+                SelectedTransactionList::None | SelectedTransactionList::HeldTxs => {},
                 SelectedTransactionList::PendingTxs => {
                     self.detailed_transaction = match self.pending_list_state.selected() {
                         None => None,
@@ -549,6 +670,10 @@ impl<B: Backend> Component<B> for TransactionsTab {
         }
         match self.selected_tx_list {
             SelectedTransactionList::None => {},
+            SelectedTransactionList::HeldTxs => {
+                self.held_list_state.set_num_items(app_state.get_held_txs().len());
+                self.held_list_state.previous();
+            },
             SelectedTransactionList::PendingTxs => {
                 self.pending_list_state.set_num_items(app_state.get_pending_txs().len());
                 self.pending_list_state.previous();
@@ -575,6 +700,10 @@ impl<B: Backend> Component<B> for TransactionsTab {
         }
         match self.selected_tx_list {
             SelectedTransactionList::None => {},
+            SelectedTransactionList::HeldTxs => {
+                self.held_list_state.set_num_items(app_state.get_held_txs().len());
+                self.held_list_state.next();
+            },
             SelectedTransactionList::PendingTxs => {
                 self.pending_list_state.set_num_items(app_state.get_pending_txs().len());
                 self.pending_list_state.next();
@@ -597,6 +726,7 @@ impl<B: Backend> Component<B> for TransactionsTab {
 
     fn on_esc(&mut self, _app_state: &mut AppState) {
         self.selected_tx_list = SelectedTransactionList::None;
+        self.held_list_state.select(None);
         self.pending_list_state.select(None);
         self.completed_list_state.select(None);
         self.detailed_transaction = None;
@@ -607,6 +737,7 @@ impl<B: Backend> Component<B> for TransactionsTab {
 #[derive(PartialEq)]
 pub enum SelectedTransactionList {
     None,
+    HeldTxs,
     PendingTxs,
     CompletedTxs,
 }
@@ -54,6 +54,7 @@ use tari_wallet::{
     contacts_service::storage::database::Contact,
     output_manager_service::{handle::OutputManagerEventReceiver, service::Balance, TxId},
     transaction_service::{
+        acceptance_policy::IncomingTransaction,
         handle::TransactionEventReceiver,
         storage::models::{CompletedTransaction, TransactionStatus},
     },
@@ -298,6 +299,26 @@ impl AppState {
         Ok(())
     }
 
+    pub async fn approve_held_transaction(&mut self, tx_id: TxId) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        let mut tx_service_handle = inner.wallet.transaction_service.clone();
+        tx_service_handle.approve_held_transaction(tx_id).await?;
+        inner.refresh_full_transaction_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
+    pub async fn reject_held_transaction(&mut self, tx_id: TxId) -> Result<(), UiError> {
+        let mut inner = self.inner.write().await;
+        let mut tx_service_handle = inner.wallet.transaction_service.clone();
+        tx_service_handle.reject_held_transaction(tx_id).await?;
+        inner.refresh_full_transaction_state().await?;
+        drop(inner);
+        self.update_cache().await;
+        Ok(())
+    }
+
     pub fn get_identity(&self) -> &MyIdentity {
         &self.cached_data.my_identity
     }
@@ -342,6 +363,22 @@ impl AppState {
         }
     }
 
+    pub fn get_held_txs(&self) -> &Vec<IncomingTransaction> {
+        &self.cached_data.held_txs
+    }
+
+    pub fn get_held_txs_slice(&self, start: usize, end: usize) -> &[IncomingTransaction] {
+        if self.cached_data.held_txs.is_empty() || start > end || end > self.cached_data.held_txs.len() {
+            return &[];
+        }
+
+        &self.cached_data.held_txs[start..end]
+    }
+
+    pub fn get_held_tx(&self, index: usize) -> Option<&IncomingTransaction> {
+        self.cached_data.held_txs.get(index)
+    }
+
     pub fn get_completed_txs(&self) -> Vec<&CompletedTransactionInfo> {
         if self
             .completed_tx_filter
@@ -518,6 +555,19 @@ impl AppStateInner {
                 .collect::<Vec<CompletedTransaction>>(),
         );
 
+        let mut completed_transactions: Vec<CompletedTransaction> = Vec::new();
+        // Scheduled transactions are still waiting to be broadcast, so they are listed with the pending ones
+        let (scheduled, completed): (Vec<CompletedTransaction>, Vec<CompletedTransaction>) = self
+            .wallet
+            .transaction_service
+            .get_completed_transactions()
+            .await?
+            .values()
+            .cloned()
+            .partition(|t| t.status == TransactionStatus::Scheduled);
+        pending_transactions.extend(scheduled);
+        completed_transactions.extend(completed);
+
         pending_transactions.sort_by(|a: &CompletedTransaction, b: &CompletedTransaction| {
             b.timestamp.partial_cmp(&a.timestamp).unwrap()
         });
@@ -526,17 +576,6 @@ impl AppStateInner {
             .map(|tx| CompletedTransactionInfo::from(tx.clone()))
             .collect();
 
-        let mut completed_transactions: Vec<CompletedTransaction> = Vec::new();
-        completed_transactions.extend(
-            self.wallet
-                .transaction_service
-                .get_completed_transactions()
-                .await?
-                .values()
-                .cloned()
-                .collect::<Vec<CompletedTransaction>>(),
-        );
-
         completed_transactions.extend(
             self.wallet
                 .transaction_service
@@ -557,6 +596,9 @@ impl AppStateInner {
             .iter()
             .map(|tx| CompletedTransactionInfo::from(tx.clone()))
             .collect();
+
+        // Held transactions are waiting for the user to approve or reject them
+        self.data.held_txs = self.wallet.transaction_service.get_held_transactions().await?;
         self.updated = true;
         Ok(())
     }
@@ -600,14 +642,18 @@ impl AppStateInner {
             Some(tx) => {
                 let tx = CompletedTransactionInfo::from(CompletedTransaction::from(tx));
                 if let Some(index) = self.data.pending_txs.iter().position(|i| i.tx_id == tx_id) {
-                    if tx.status == TransactionStatus::Pending && !tx.cancelled {
+                    if (tx.status == TransactionStatus::Pending || tx.status == TransactionStatus::Scheduled) &&
+                        !tx.cancelled
+                    {
                         self.data.pending_txs[index] = tx;
                         self.updated = true;
                         return Ok(());
                     } else {
                         let _ = self.data.pending_txs.remove(index);
                     }
-                } else if tx.status == TransactionStatus::Pending && !tx.cancelled {
+                } else if (tx.status == TransactionStatus::Pending || tx.status == TransactionStatus::Scheduled) &&
+                    !tx.cancelled
+                {
                     self.data.pending_txs.push(tx);
                     self.data.pending_txs.sort_by(|a, b| {
                         b.timestamp
@@ -906,6 +952,7 @@ impl From<CompletedTransaction> for CompletedTransactionInfo {
 struct AppStateData {
     pending_txs: Vec<CompletedTransactionInfo>,
     completed_txs: Vec<CompletedTransactionInfo>,
+    held_txs: Vec<IncomingTransaction>,
     confirmations: HashMap<TxId, u64>,
     my_identity: MyIdentity,
     contacts: Vec<UiContact>,
@@ -972,6 +1019,7 @@ impl AppStateData {
         AppStateData {
             pending_txs: Vec::new(),
             completed_txs: Vec::new(),
+            held_txs: Vec::new(),
             confirmations: HashMap::new(),
             my_identity: identity,
             contacts: Vec::new(),
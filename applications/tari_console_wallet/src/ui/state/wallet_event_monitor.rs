@@ -107,7 +107,8 @@ impl WalletEventMonitor {
                                     TransactionEvent::ReceivedTransaction(tx_id) |
                                     TransactionEvent::ReceivedTransactionReply(tx_id) |
                                     TransactionEvent::TransactionBroadcast(tx_id) |
-                                    TransactionEvent::TransactionMinedRequestTimedOut(tx_id) | TransactionEvent::TransactionImported(tx_id) => {
+                                    TransactionEvent::TransactionMinedRequestTimedOut(tx_id) | TransactionEvent::TransactionImported(tx_id) |
+                                    TransactionEvent::TransactionScheduled(tx_id) => {
                                         self.trigger_tx_state_refresh(tx_id).await;
                                         self.trigger_balance_refresh();
                                     },
@@ -118,6 +119,13 @@ impl WalletEventMonitor {
                                         self.trigger_balance_refresh();
                                         notifier.transaction_sent(tx_id);
                                     },
+                                    TransactionEvent::TransactionHeldForApproval(tx_id) => {
+                                        self.trigger_full_tx_state_refresh().await;
+                                        self.add_notification(format!(
+                                            "Incoming transaction {} is held for approval on the Transactions tab",
+                                            tx_id
+                                        )).await;
+                                    },
                                     TransactionEvent::TransactionValidationSuccess(_) => {
                                         self.trigger_full_tx_state_refresh().await;
                                         self.trigger_balance_refresh();
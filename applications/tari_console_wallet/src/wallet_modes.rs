@@ -22,7 +22,11 @@
 use crate::{
     automation::{command_parser::parse_command, commands::command_runner},
     grpc::WalletGrpcServer,
-    notifier::Notifier,
+    metrics,
+    notifier::{
+        webhook::{WebhookConfig, WebhookNotifier},
+        Notifier,
+    },
     recovery::wallet_recovery,
     ui,
     ui::App,
@@ -34,6 +38,7 @@ use std::{fs, io::Stdout, net::SocketAddr, path::PathBuf};
 use tari_app_utilities::utilities::ExitCodes;
 use tari_common::{ConfigBootstrap, GlobalConfig};
 use tari_comms::peer_manager::Peer;
+use tari_metrics::server::MetricsServer;
 use tari_wallet::WalletSqlite;
 use tokio::runtime::Handle;
 use tonic::transport::Server;
@@ -107,6 +112,24 @@ impl PeerConfig {
         }
     }
 
+    /// Returns the base nodes the wallet may fail over to, in order of preference: the custom base node followed by
+    /// the configured base node peers. The peer seeds are only used if no base node has been configured.
+    pub fn get_base_node_pool(&self) -> Vec<Peer> {
+        let mut peers = self.base_node_custom.iter().cloned().collect::<Vec<_>>();
+        peers.extend(self.base_node_peers.clone());
+        if peers.is_empty() {
+            peers.extend(self.peer_seeds.clone());
+        }
+
+        let mut pool = Vec::with_capacity(peers.len());
+        for peer in peers {
+            if !pool.iter().any(|p: &Peer| p.node_id == peer.node_id) {
+                pool.push(peer);
+            }
+        }
+        pool
+    }
+
     /// Returns all the peers from the PeerConfig.
     /// In order: Custom base node, service peers, peer seeds.
     pub fn get_all_peers(&self) -> Vec<Peer> {
@@ -213,9 +236,13 @@ pub fn tui_mode(config: WalletModeConfig, mut wallet: WalletSqlite) -> Result<()
         notify_script,
         ..
     } = config;
-    let grpc = WalletGrpcServer::new(wallet.clone());
+    let grpc =
+        WalletGrpcServer::new(wallet.clone()).with_seed_words_enabled(global_config.wallet_grpc_seed_words_enabled);
     handle.spawn(run_grpc(grpc, global_config.grpc_console_wallet_address));
 
+    spawn_webhook_notifier(&global_config, &handle, wallet.clone());
+    spawn_metrics_server(&global_config, &handle, wallet.clone());
+
     let notifier = Notifier::new(notify_script, handle.clone(), wallet.clone());
 
     // update the selected/custom base node since it may have been changed by script/command mode
@@ -286,8 +313,11 @@ pub fn grpc_mode(config: WalletModeConfig, wallet: WalletSqlite) -> Result<(), E
     let WalletModeConfig {
         global_config, handle, ..
     } = config;
+    spawn_webhook_notifier(&global_config, &handle, wallet.clone());
+    spawn_metrics_server(&global_config, &handle, wallet.clone());
+
     println!("Starting grpc server");
-    let grpc = WalletGrpcServer::new(wallet);
+    let grpc = WalletGrpcServer::new(wallet).with_seed_words_enabled(global_config.wallet_grpc_seed_words_enabled);
     handle
         .block_on(run_grpc(grpc, global_config.grpc_console_wallet_address))
         .map_err(ExitCodes::GrpcError)?;
@@ -295,8 +325,31 @@ pub fn grpc_mode(config: WalletModeConfig, wallet: WalletSqlite) -> Result<(), E
     Ok(())
 }
 
+fn spawn_webhook_notifier(global_config: &GlobalConfig, handle: &Handle, wallet: WalletSqlite) {
+    if let Some(config) = WebhookConfig::from_global_config(global_config) {
+        match WebhookNotifier::new(config) {
+            Ok(notifier) => {
+                handle.spawn(notifier.run(wallet));
+            },
+            Err(e) => error!(target: LOG_TARGET, "Could not start the webhook notifier: {}", e),
+        }
+    }
+}
+
+fn spawn_metrics_server(global_config: &GlobalConfig, handle: &Handle, wallet: WalletSqlite) {
+    if let Some(address) = global_config.console_wallet_metrics_server_address {
+        handle.spawn(metrics::run(wallet));
+        handle.spawn(async move {
+            if let Err(e) = MetricsServer::new(address).run().await {
+                error!(target: LOG_TARGET, "Metrics server on {} stopped: {}", address, e);
+            }
+        });
+    }
+}
+
 async fn run_grpc(grpc: WalletGrpcServer, grpc_console_wallet_address: SocketAddr) -> Result<(), String> {
     info!(target: LOG_TARGET, "Starting GRPC on {}", grpc_console_wallet_address);
+    grpc.spawn_event_journal();
 
     Server::builder()
         .add_service(tari_app_grpc::tari_rpc::wallet_server::WalletServer::new(grpc))
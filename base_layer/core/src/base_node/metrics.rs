@@ -0,0 +1,95 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use once_cell::sync::Lazy;
+use tari_metrics::{Histogram, HistogramVec, IntGauge, IntGaugeVec};
+
+pub fn sync_local_height(stage: SyncStage) -> IntGauge {
+    static METER: Lazy<IntGaugeVec> = Lazy::new(|| {
+        tari_metrics::register_int_gauge_vec(
+            "base_node_sync_local_height",
+            "Height that has been synced up to by sync stage",
+            &["stage"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[stage.as_str()])
+}
+
+pub fn sync_remote_height(stage: SyncStage) -> IntGauge {
+    static METER: Lazy<IntGaugeVec> = Lazy::new(|| {
+        tari_metrics::register_int_gauge_vec(
+            "base_node_sync_remote_height",
+            "Chain height of the sync peer by sync stage",
+            &["stage"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[stage.as_str()])
+}
+
+pub fn block_validation_duration(source: BlockSource) -> Histogram {
+    static METER: Lazy<HistogramVec> = Lazy::new(|| {
+        tari_metrics::register_histogram_vec(
+            "base_node_block_validation_duration_seconds",
+            "Time taken to validate a block by where the block came from",
+            &["source"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[source.as_str()])
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SyncStage {
+    Header,
+    Block,
+}
+
+impl SyncStage {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SyncStage::Header => "header",
+            SyncStage::Block => "block",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BlockSource {
+    /// A block added with `add_block`, i.e. a propagated or locally mined block
+    Propagated,
+    /// A block body received during block sync
+    Sync,
+}
+
+impl BlockSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BlockSource::Propagated => "propagated",
+            BlockSource::Sync => "sync",
+        }
+    }
+}
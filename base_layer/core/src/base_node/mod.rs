@@ -40,6 +40,9 @@ pub mod comms_interface;
 #[cfg(feature = "base_node")]
 pub use comms_interface::{LocalNodeCommsInterface, OutboundNodeCommsInterface};
 
+#[cfg(feature = "base_node")]
+pub(crate) mod metrics;
+
 #[cfg(feature = "base_node")]
 pub mod service;
 
@@ -53,7 +56,7 @@ pub mod sync;
 
 #[cfg(feature = "base_node")]
 pub use sync::{
-    rpc::{create_base_node_sync_rpc_service, BaseNodeSyncService},
+    rpc::{create_base_node_sync_rpc_service, sync_rpc_method_limits, BaseNodeSyncService},
     BlockSyncConfig,
     SyncValidators,
 };
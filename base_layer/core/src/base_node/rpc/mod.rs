@@ -95,6 +95,12 @@ pub trait BaseNodeWalletService: Send + Sync + 'static {
         &self,
         request: Request<u64>,
     ) -> Result<Response<proto::core::BlockHeader>, RpcStatus>;
+
+    #[rpc(method = 10)]
+    async fn get_mempool_stats(
+        &self,
+        request: Request<()>,
+    ) -> Result<Response<proto::mempool::StatsResponse>, RpcStatus>;
 }
 
 #[cfg(feature = "base_node")]
@@ -23,6 +23,7 @@
 use crate::{
     base_node::{
         comms_interface::BlockEvent,
+        metrics::{self, SyncStage},
         state_machine_service::states::{BlockSyncInfo, HorizonStateSync, StateEvent, StateInfo, StatusInfo},
         sync::BlockSynchronizer,
         BaseNodeStateMachine,
@@ -79,6 +80,8 @@ impl BlockSync {
         let randomx_vm_flags = shared.get_randomx_vm_flags();
         synchronizer.on_progress(move |block, remote_tip_height, sync_peers| {
             let local_height = block.height();
+            metrics::sync_local_height(SyncStage::Block).set(local_height as i64);
+            metrics::sync_remote_height(SyncStage::Block).set(remote_tip_height as i64);
             local_nci.publish_block_event(BlockEvent::ValidBlockAdded(
                 block.block().clone().into(),
                 BlockAddResult::Ok(block),
@@ -23,6 +23,7 @@
 use crate::{
     base_node::{
         comms_interface::BlockEvent,
+        metrics::{self, SyncStage},
         state_machine_service::states::{BlockSyncInfo, Listening, StateEvent, StateInfo, StatusInfo},
         sync::{BlockHeaderSyncError, HeaderSynchronizer, SyncPeers},
         BaseNodeStateMachine,
@@ -77,6 +78,10 @@ impl HeaderSync {
         let randomx_vm_cnt = shared.get_randomx_vm_cnt();
         let randomx_vm_flags = shared.get_randomx_vm_flags();
         synchronizer.on_progress(move |details, sync_peers| {
+            if let Some((current_height, remote_tip_height)) = details {
+                metrics::sync_local_height(SyncStage::Header).set(current_height as i64);
+                metrics::sync_remote_height(SyncStage::Header).set(remote_tip_height as i64);
+            }
             let details = details.map(|(current_height, remote_tip_height)| BlockSyncInfo {
                 tip_height: remote_tip_height,
                 local_height: current_height,
@@ -44,8 +44,8 @@ pub enum BlockSyncError {
     NoSyncPeers,
     #[error("Block validation failed: {0}")]
     ValidationError(#[from] ValidationError),
-    #[error("Failed to ban peer: {0}")]
-    FailedToBan(ConnectivityError),
+    #[error("Failed to report peer: {0}")]
+    FailedToReportPeer(ConnectivityError),
     #[error("Failed to construct valid chain block")]
     FailedToConstructChainBlock,
 }
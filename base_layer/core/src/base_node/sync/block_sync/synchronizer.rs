@@ -23,6 +23,7 @@
 use super::error::BlockSyncError;
 use crate::{
     base_node::{
+        metrics::{self, BlockSource},
         sync::{hooks::Hooks, rpc},
         BlockSyncConfig,
     },
@@ -43,7 +44,7 @@ use std::{
 };
 use tari_comms::{
     connectivity::{ConnectivityRequester, ConnectivitySelection},
-    peer_manager::NodeId,
+    peer_manager::{GoodBehaviour, Misbehaviour, NodeId},
     PeerConnection,
 };
 use tracing;
@@ -98,10 +99,15 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
         match self.attempt_block_sync(peer_conn).await {
             Ok(_) => {
                 self.db.cleanup_orphans().await?;
+                self.connectivity
+                    .credit_peer(node_id, GoodBehaviour::SuccessfulSync)
+                    .await
+                    .map_err(BlockSyncError::FailedToReportPeer)?;
                 Ok(())
             },
             Err(err @ BlockSyncError::ValidationError(_)) | Err(err @ BlockSyncError::ReceivedInvalidBlockBody(_)) => {
-                self.ban_peer(node_id, &err).await?;
+                self.report_peer(node_id, Misbehaviour::ConsensusViolation, &err)
+                    .await?;
                 Err(err)
             },
             Err(err) => Err(err),
@@ -210,7 +216,9 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
             let timer = Instant::now();
             let (header, header_accum_data) = header.into_parts();
 
+            let validation_timer = metrics::block_validation_duration(BlockSource::Sync).start_timer();
             let block = self.block_validator.validate_body(Block::new(header, body)).await?;
+            validation_timer.observe_duration();
 
             let block = ChainBlock::try_construct(Arc::new(block), header_accum_data)
                 .map(Arc::new)
@@ -265,20 +273,31 @@ impl<B: BlockchainBackend + 'static> BlockSynchronizer<B> {
         Ok(())
     }
 
-    async fn ban_peer<T: ToString>(&mut self, node_id: NodeId, reason: T) -> Result<(), BlockSyncError> {
+    async fn report_peer<T: ToString>(
+        &mut self,
+        node_id: NodeId,
+        misbehaviour: Misbehaviour,
+        reason: T,
+    ) -> Result<(), BlockSyncError> {
         let reason = reason.to_string();
         if self.config.sync_peers.contains(&node_id) {
             debug!(
                 target: LOG_TARGET,
-                "Not banning peer that is allowlisted for sync. Ban reason = {}", reason
+                "Not reporting peer that is allowlisted for sync. Reason = {}", reason
             );
             return Ok(());
         }
-        warn!(target: LOG_TARGET, "Banned sync peer because {}", reason);
+        warn!(target: LOG_TARGET, "Reporting sync peer because {}", reason);
+        if misbehaviour.always_bans() {
+            self.connectivity
+                .ban_peer_until(node_id.clone(), self.config.ban_period, reason.clone())
+                .await
+                .map_err(BlockSyncError::FailedToReportPeer)?;
+        }
         self.connectivity
-            .ban_peer_until(node_id, self.config.ban_period, reason)
+            .report_misbehaviour(node_id, misbehaviour, reason)
             .await
-            .map_err(BlockSyncError::FailedToBan)?;
+            .map_err(BlockSyncError::FailedToReportPeer)?;
         Ok(())
     }
 }
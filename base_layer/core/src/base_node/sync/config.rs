@@ -25,7 +25,10 @@ use tari_comms::peer_manager::NodeId;
 
 #[derive(Debug, Clone)]
 pub struct BlockSyncConfig {
+    /// The ban duration for a sync peer that sends consensus-invalid headers or blocks or misrepresents its chain.
+    /// Other offences are reported against the peer's reputation.
     pub ban_period: Duration,
+    /// The ban duration for a sync peer that claims a tip below the chain split it reported
     pub short_ban_period: Duration,
     pub sync_peers: Vec<NodeId>,
 }
@@ -43,8 +43,8 @@ pub enum BlockHeaderSyncError {
     SyncFailedAllPeers,
     #[error("Peer sent a found hash index that was out of range (Expected less than {0}, Found: {1})")]
     FoundHashIndexOutOfRange(u64, u64),
-    #[error("Failed to ban peer: {0}")]
-    FailedToBan(ConnectivityError),
+    #[error("Failed to report peer: {0}")]
+    FailedToReportPeer(ConnectivityError),
     #[error("Connectivity Error: {0}")]
     ConnectivityError(#[from] ConnectivityError),
     #[error("Peer could not send a stronger chain than the local chain")]
@@ -44,7 +44,7 @@ use std::{
 use tari_common_types::types::HashOutput;
 use tari_comms::{
     connectivity::{ConnectivityError, ConnectivityRequester, ConnectivitySelection},
-    peer_manager::NodeId,
+    peer_manager::{GoodBehaviour, Misbehaviour, NodeId},
     protocol::rpc::{RpcError, RpcHandshakeError},
     PeerConnection,
 };
@@ -117,19 +117,20 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
 
                 Err(err @ BlockHeaderSyncError::RpcError(RpcError::HandshakeError(RpcHandshakeError::TimedOut))) => {
                     warn!(target: LOG_TARGET, "{}", err);
-                    self.ban_peer_short(node_id, BanReason::RpcNegotiationTimedOut).await?;
+                    self.report_peer(node_id, MisbehaviourReason::RpcNegotiationTimedOut)
+                        .await?;
                 },
                 Err(BlockHeaderSyncError::ValidationFailed(err)) => {
                     warn!(target: LOG_TARGET, "Block header validation failed: {}", err);
-                    self.ban_peer_long(node_id, err.into()).await?;
+                    self.report_peer(node_id, err.into()).await?;
                 },
                 Err(BlockHeaderSyncError::ChainSplitNotFound(peer)) => {
                     warn!(target: LOG_TARGET, "Chain split not found for peer {}.", peer);
-                    self.ban_peer_long(peer, BanReason::ChainSplitNotFound).await?;
+                    self.report_peer(peer, MisbehaviourReason::ChainSplitNotFound).await?;
                 },
                 Err(err @ BlockHeaderSyncError::InvalidBlockHeight { .. }) => {
                     warn!(target: LOG_TARGET, "{}", err);
-                    self.ban_peer_long(node_id, BanReason::GeneralHeaderSyncFailure(err))
+                    self.report_peer(node_id, MisbehaviourReason::GeneralHeaderSyncFailure(err))
                         .await?;
                 },
                 Err(err) => {
@@ -218,32 +219,25 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
         Ok(connections)
     }
 
-    async fn ban_peer_long(&mut self, node_id: NodeId, reason: BanReason) -> Result<(), BlockHeaderSyncError> {
-        self.ban_peer_for(node_id, reason, self.config.ban_period).await
-    }
-
-    async fn ban_peer_short(&mut self, node_id: NodeId, reason: BanReason) -> Result<(), BlockHeaderSyncError> {
-        self.ban_peer_for(node_id, reason, self.config.short_ban_period).await
-    }
-
-    async fn ban_peer_for(
-        &mut self,
-        node_id: NodeId,
-        reason: BanReason,
-        duration: Duration,
-    ) -> Result<(), BlockHeaderSyncError> {
+    async fn report_peer(&mut self, node_id: NodeId, reason: MisbehaviourReason) -> Result<(), BlockHeaderSyncError> {
         if self.config.sync_peers.contains(&node_id) {
             debug!(
                 target: LOG_TARGET,
-                "Not banning peer that is allowlisted for sync. Ban reason = {}", reason
+                "Not reporting peer that is allowlisted for sync. Reason = {}", reason
             );
             return Ok(());
         }
-        warn!(target: LOG_TARGET, "Banned sync peer because {}", reason);
+        warn!(target: LOG_TARGET, "Reporting sync peer because {}", reason);
+        if let Some(duration) = reason.ban_duration(&self.config) {
+            self.connectivity
+                .ban_peer_until(node_id.clone(), duration, reason.to_string())
+                .await
+                .map_err(BlockHeaderSyncError::FailedToReportPeer)?;
+        }
         self.connectivity
-            .ban_peer_until(node_id, duration, reason.to_string())
+            .report_misbehaviour(node_id, reason.misbehaviour(), reason.to_string())
             .await
-            .map_err(BlockHeaderSyncError::FailedToBan)?;
+            .map_err(BlockHeaderSyncError::FailedToReportPeer)?;
         Ok(())
     }
 
@@ -270,6 +264,10 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
                     self.sync_peers,
                 );
                 self.synchronize_headers(&peer, &mut client, *split_info).await?;
+                self.connectivity
+                    .credit_peer(peer, GoodBehaviour::SuccessfulSync)
+                    .await
+                    .map_err(BlockHeaderSyncError::FailedToReportPeer)?;
                 Ok(())
             },
         }
@@ -357,8 +355,11 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
             .find_chain_split(peer, client, NUM_INITIAL_HEADERS_TO_REQUEST)
             .await?;
         if resp.headers.len() > NUM_INITIAL_HEADERS_TO_REQUEST as usize {
-            self.ban_peer_long(peer.clone(), BanReason::PeerSentTooManyHeaders(resp.headers.len()))
-                .await?;
+            self.report_peer(
+                peer.clone(),
+                MisbehaviourReason::PeerSentTooManyHeaders(resp.headers.len()),
+            )
+            .await?;
             return Err(BlockHeaderSyncError::NotInSync);
         }
         let proto::FindChainSplitResponse {
@@ -376,7 +377,7 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
 
         if fork_hash_index >= block_hashes.len() as u64 {
             let _ = self
-                .ban_peer_long(peer.clone(), BanReason::SplitHashGreaterThanHashes {
+                .report_peer(peer.clone(), MisbehaviourReason::SplitHashGreaterThanHashes {
                     fork_hash_index,
                     num_block_hashes: block_hashes.len(),
                 })
@@ -435,7 +436,7 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
         // Basic sanity check that the peer sent tip height greater than the split.
         let split_height = local_tip_header.height().saturating_sub(steps_back);
         if remote_tip_height < split_height {
-            self.ban_peer_short(peer.clone(), BanReason::PeerSentInvalidTipHeight {
+            self.report_peer(peer.clone(), MisbehaviourReason::PeerSentInvalidTipHeight {
                 actual: remote_tip_height,
                 expected: split_height,
             })
@@ -648,7 +649,7 @@ impl<'a, B: BlockchainBackend + 'static> HeaderSynchronizer<'a, B> {
 }
 
 #[derive(Debug, thiserror::Error)]
-enum BanReason {
+enum MisbehaviourReason {
     #[error("This peer sent too many headers ({0}) in response to a chain split request")]
     PeerSentTooManyHeaders(usize),
     #[error("This peer sent an invalid tip height {actual} expected a height greater than or equal to {expected}")]
@@ -671,6 +672,35 @@ enum BanReason {
     RpcNegotiationTimedOut,
 }
 
+impl MisbehaviourReason {
+    fn misbehaviour(&self) -> Misbehaviour {
+        use MisbehaviourReason::*;
+        match self {
+            PeerSentTooManyHeaders(_) | SplitHashGreaterThanHashes { .. } | PeerSentInvalidTipHeight { .. } => {
+                Misbehaviour::ProtocolViolation
+            },
+            ValidationFailed(_) => Misbehaviour::ConsensusViolation,
+            ChainSplitNotFound => Misbehaviour::ChainMisrepresentation,
+            GeneralHeaderSyncFailure(_) => Misbehaviour::InvalidData,
+            RpcNegotiationTimedOut => Misbehaviour::Unresponsive,
+        }
+    }
+
+    /// Returns the duration to ban the peer for, regardless of its reputation, or None if the offence only counts
+    /// against the peer's reputation.
+    fn ban_duration(&self, config: &BlockSyncConfig) -> Option<Duration> {
+        use MisbehaviourReason::*;
+        match self {
+            ValidationFailed(_) | ChainSplitNotFound => Some(config.ban_period),
+            PeerSentInvalidTipHeight { .. } => Some(config.short_ban_period),
+            PeerSentTooManyHeaders(_) |
+            SplitHashGreaterThanHashes { .. } |
+            GeneralHeaderSyncFailure(_) |
+            RpcNegotiationTimedOut => None,
+        }
+    }
+}
+
 struct ChainSplitInfo {
     local_tip_header: ChainHeader,
     remote_tip_height: u64,
@@ -49,7 +49,10 @@ use crate::{
         SyncUtxosResponse,
     },
 };
-use tari_comms::protocol::rpc::{Request, Response, RpcStatus, Streaming};
+use tari_comms::protocol::{
+    rpc::{NamedProtocolService, Request, Response, RpcMethodLimits, RpcStatus, Streaming},
+    ProtocolId,
+};
 use tari_comms_rpc_macros::tari_rpc;
 
 #[tari_rpc(protocol_name = b"t/blksync/1", server_struct = BaseNodeSyncRpcServer, client_struct = BaseNodeSyncRpcClient)]
@@ -94,6 +97,23 @@ pub trait BaseNodeSyncService: Send + Sync + 'static {
     async fn sync_utxos(&self, request: Request<SyncUtxosRequest>) -> Result<Streaming<SyncUtxosResponse>, RpcStatus>;
 }
 
+/// The number of request budget tokens used by each call to a streaming sync method
+const SYNC_METHOD_COST: usize = 10;
+
+/// Returns the limits for the streaming sync methods, which are expensive to serve. A client may only have one call to
+/// each of these methods in progress at a time.
+pub fn sync_rpc_method_limits() -> Vec<(ProtocolId, u32, RpcMethodLimits)> {
+    let protocol = ProtocolId::from_static(BaseNodeSyncRpcClient::PROTOCOL_NAME);
+    let limits = RpcMethodLimits::new()
+        .with_cost(SYNC_METHOD_COST)
+        .with_maximum_concurrent_per_client(1);
+    // sync_blocks, sync_headers, sync_kernels and sync_utxos
+    [1, 2, 6, 8]
+        .iter()
+        .map(|method| (protocol.clone(), *method, limits))
+        .collect()
+}
+
 #[cfg(feature = "base_node")]
 pub fn create_base_node_sync_rpc_service<B: BlockchainBackend + 'static>(
     db: AsyncBlockchainDb<B>,
@@ -22,9 +22,11 @@
 
 use crate::{
     blocks::Block,
+    consensus::ConsensusManager,
     mempool::{
         error::MempoolError,
         mempool_storage::MempoolStorage,
+        metrics,
         MempoolConfig,
         StateResponse,
         StatsResponse,
@@ -46,27 +48,37 @@ pub struct Mempool {
 
 impl Mempool {
     /// Create a new Mempool with an UnconfirmedPool, OrphanPool, PendingPool and ReOrgPool.
-    pub fn new(config: MempoolConfig, validator: Arc<dyn MempoolTransactionValidation>) -> Self {
+    pub fn new(
+        config: MempoolConfig,
+        rules: ConsensusManager,
+        validator: Arc<dyn MempoolTransactionValidation>,
+    ) -> Self {
         Self {
-            pool_storage: Arc::new(RwLock::new(MempoolStorage::new(config, validator))),
+            pool_storage: Arc::new(RwLock::new(MempoolStorage::new(config, rules, validator))),
         }
     }
 
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
     /// pipeline already and will thus always be internally consistent by this stage
     pub fn insert(&self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
-        self.pool_storage
+        let mut storage = self
+            .pool_storage
             .write()
-            .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .insert(tx)
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        let response = storage.insert(tx)?;
+        metrics::inserted_transactions(&response).inc();
+        storage.update_metrics()?;
+        Ok(response)
     }
 
     /// Update the Mempool based on the received published block.
     pub fn process_published_block(&self, published_block: Arc<Block>) -> Result<(), MempoolError> {
-        self.pool_storage
+        let mut storage = self
+            .pool_storage
             .write()
-            .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .process_published_block(published_block)
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        storage.process_published_block(published_block)?;
+        storage.update_metrics()
     }
 
     /// In the event of a ReOrg, resubmit all ReOrged transactions into the Mempool and process each newly introduced
@@ -76,10 +88,12 @@ impl Mempool {
         removed_blocks: Vec<Arc<Block>>,
         new_blocks: Vec<Arc<Block>>,
     ) -> Result<(), MempoolError> {
-        self.pool_storage
+        let mut storage = self
+            .pool_storage
             .write()
-            .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .process_reorg(removed_blocks, new_blocks)
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        storage.process_reorg(removed_blocks, new_blocks)?;
+        storage.update_metrics()
     }
 
     /// Returns all unconfirmed transaction stored in the Mempool, except the transactions stored in the ReOrgPool.
@@ -22,8 +22,10 @@
 
 use crate::{
     blocks::Block,
+    consensus::ConsensusManager,
     mempool::{
         error::MempoolError,
+        metrics,
         reorg_pool::ReorgPool,
         unconfirmed_pool::UnconfirmedPool,
         MempoolConfig,
@@ -48,15 +50,23 @@ pub struct MempoolStorage {
     unconfirmed_pool: UnconfirmedPool,
     reorg_pool: ReorgPool,
     validator: Arc<dyn MempoolTransactionValidation>,
+    rules: ConsensusManager,
+    tip_height: u64,
 }
 
 impl MempoolStorage {
     /// Create a new Mempool with an UnconfirmedPool and ReOrgPool.
-    pub fn new(config: MempoolConfig, validators: Arc<dyn MempoolTransactionValidation>) -> Self {
+    pub fn new(
+        config: MempoolConfig,
+        rules: ConsensusManager,
+        validators: Arc<dyn MempoolTransactionValidation>,
+    ) -> Self {
         Self {
             unconfirmed_pool: UnconfirmedPool::new(config.unconfirmed_pool),
             reorg_pool: ReorgPool::new(config.reorg_pool),
             validator: validators,
+            rules,
+            tip_height: 0,
         }
     }
 
@@ -117,6 +127,7 @@ impl MempoolStorage {
             self.unconfirmed_pool
                 .remove_published_and_discard_deprecated_transactions(&published_block),
         )?;
+        self.tip_height = published_block.header.height;
 
         Ok(())
     }
@@ -219,13 +230,27 @@ impl MempoolStorage {
         Ok(self.unconfirmed_pool.len())
     }
 
+    /// Updates the exported mempool metrics to the current state of the Mempool.
+    pub fn update_metrics(&self) -> Result<(), MempoolError> {
+        metrics::transactions("unconfirmed").set(self.unconfirmed_pool.len() as i64);
+        metrics::transactions("reorg").set(self.reorg_pool.len()? as i64);
+        metrics::weight().set(self.unconfirmed_pool.calculate_weight() as i64);
+        metrics::total_fees().set(self.unconfirmed_pool.calculate_total_fees().as_u64() as i64);
+        Ok(())
+    }
+
     /// Gathers and returns the stats of the Mempool.
     pub fn stats(&self) -> Result<StatsResponse, MempoolError> {
+        let max_block_weight = self
+            .rules
+            .consensus_constants(self.tip_height + 1)
+            .get_max_block_weight_excluding_coinbase();
         Ok(StatsResponse {
             total_txs: self.len()?,
             unconfirmed_txs: self.unconfirmed_pool.len(),
             reorg_txs: self.reorg_pool.len()?,
             total_weight: self.unconfirmed_pool.calculate_weight(),
+            min_fee_per_gram: self.unconfirmed_pool.min_fee_per_gram(max_block_weight),
         })
     }
 
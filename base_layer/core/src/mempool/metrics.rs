@@ -0,0 +1,83 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::mempool::TxStorageResponse;
+use once_cell::sync::Lazy;
+use tari_metrics::{IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
+
+pub fn transactions(pool: &str) -> IntGauge {
+    static METER: Lazy<IntGaugeVec> = Lazy::new(|| {
+        tari_metrics::register_int_gauge_vec(
+            "mempool_transactions",
+            "Number of transactions in the mempool by pool",
+            &["pool"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[pool])
+}
+
+pub fn weight() -> IntGauge {
+    static METER: Lazy<IntGauge> = Lazy::new(|| {
+        tari_metrics::register_int_gauge(
+            "mempool_weight",
+            "Total weight of the unconfirmed transactions in the mempool",
+        )
+        .unwrap()
+    });
+
+    METER.clone()
+}
+
+pub fn total_fees() -> IntGauge {
+    static METER: Lazy<IntGauge> = Lazy::new(|| {
+        tari_metrics::register_int_gauge(
+            "mempool_total_fees",
+            "Total fees in µT of the unconfirmed transactions in the mempool",
+        )
+        .unwrap()
+    });
+
+    METER.clone()
+}
+
+pub fn inserted_transactions(response: &TxStorageResponse) -> IntCounter {
+    static METER: Lazy<IntCounterVec> = Lazy::new(|| {
+        tari_metrics::register_int_counter_vec(
+            "mempool_inserted_transactions",
+            "Number of transactions submitted to the mempool by storage result",
+            &["result"],
+        )
+        .unwrap()
+    });
+
+    let result = match response {
+        TxStorageResponse::UnconfirmedPool => "unconfirmed_pool",
+        TxStorageResponse::ReorgPool => "reorg_pool",
+        TxStorageResponse::NotStoredOrphan => "orphan",
+        TxStorageResponse::NotStoredTimeLocked => "time_locked",
+        TxStorageResponse::NotStoredAlreadySpent => "already_spent",
+        TxStorageResponse::NotStored => "not_stored",
+    };
+    METER.with_label_values(&[result])
+}
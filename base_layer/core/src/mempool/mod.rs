@@ -35,6 +35,8 @@ mod mempool;
 #[cfg(feature = "base_node")]
 mod mempool_storage;
 #[cfg(feature = "base_node")]
+mod metrics;
+#[cfg(feature = "base_node")]
 mod priority;
 #[cfg(feature = "base_node")]
 mod reorg_pool;
@@ -72,7 +74,7 @@ mod sync_protocol;
 #[cfg(feature = "base_node")]
 pub use sync_protocol::MempoolSyncInitializer;
 
-use crate::transactions::transaction::Transaction;
+use crate::transactions::{tari_amount::MicroTari, transaction::Transaction};
 use core::fmt::{Display, Error, Formatter};
 use serde::{Deserialize, Serialize};
 use tari_common_types::types::Signature;
@@ -84,14 +86,17 @@ pub struct StatsResponse {
     pub unconfirmed_txs: usize,
     pub reorg_txs: usize,
     pub total_weight: u64,
+    /// The lowest fee per gram of the transactions that fill the next block, or zero if the next block is not full
+    pub min_fee_per_gram: MicroTari,
 }
 
 impl Display for StatsResponse {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
         write!(
             fmt,
-            "Mempool stats: Total transactions: {}, Unconfirmed: {}, Published: {}, Total Weight: {}",
-            self.total_txs, self.unconfirmed_txs, self.reorg_txs, self.total_weight
+            "Mempool stats: Total transactions: {}, Unconfirmed: {}, Published: {}, Total Weight: {}, Min Fee Per \
+             Gram: {}",
+            self.total_txs, self.unconfirmed_txs, self.reorg_txs, self.total_weight, self.min_fee_per_gram
         )
     }
 }
@@ -52,6 +52,7 @@ mod get_stats {
 
             reorg_txs: 5,
             total_weight: 6,
+            min_fee_per_gram: 7.into(),
         };
         mempool.set_get_stats_response(expected_stats.clone()).await;
 
@@ -133,6 +133,7 @@ mod test {
             unconfirmed_txs: 3,
             reorg_txs: 4,
             total_weight: 1000,
+            min_fee_per_gram: 5.into(),
         }
     }
 
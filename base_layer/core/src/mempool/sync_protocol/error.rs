@@ -22,7 +22,7 @@
 
 use crate::mempool::MempoolError;
 use futures::io;
-use tari_comms::peer_manager::NodeId;
+use tari_comms::peer_manager::{Misbehaviour, NodeId};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -41,3 +41,15 @@ pub enum MempoolProtocolError {
     #[error("Wire message from `{peer}` failed to convert to local type: {message}")]
     MessageConversionFailed { peer: NodeId, message: String },
 }
+
+impl MempoolProtocolError {
+    /// Returns the misbehaviour to report against the peer if the peer is at fault for this error
+    pub fn misbehaviour(&self) -> Option<Misbehaviour> {
+        use MempoolProtocolError::*;
+        match self {
+            ExcessSignatureMissing(_) => Some(Misbehaviour::InvalidData),
+            DecodeFailed { .. } | MessageConversionFailed { .. } => Some(Misbehaviour::ProtocolViolation),
+            SubstreamClosed(_) | MempoolError(_) | IoError(_) => None,
+        }
+    }
+}
@@ -96,7 +96,7 @@ impl ServiceInitializer for MempoolSyncInitializer {
                 }
             }
 
-            MempoolSyncProtocol::new(config, notif_rx, connectivity_event_subscription, mempool)
+            MempoolSyncProtocol::new(config, notif_rx, connectivity_event_subscription, connectivity, mempool)
                 .run()
                 .await;
         });
@@ -89,11 +89,11 @@ use std::{
     },
 };
 use tari_comms::{
-    connectivity::{ConnectivityEvent, ConnectivityEventRx},
+    connectivity::{ConnectivityEvent, ConnectivityEventRx, ConnectivityRequester},
     framing,
     framing::CanonicalFraming,
     message::MessageExt,
-    peer_manager::{NodeId, PeerFeatures},
+    peer_manager::{GoodBehaviour, NodeId, PeerFeatures},
     protocol::{ProtocolEvent, ProtocolNotification, ProtocolNotificationRx},
     Bytes,
     PeerConnection,
@@ -114,6 +114,7 @@ pub struct MempoolSyncProtocol<TSubstream> {
     config: MempoolServiceConfig,
     protocol_notifier: ProtocolNotificationRx<TSubstream>,
     connectivity_events: ConnectivityEventRx,
+    connectivity: ConnectivityRequester,
     mempool: Mempool,
     num_synched: Arc<AtomicUsize>,
     permits: Arc<Semaphore>,
@@ -126,12 +127,14 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static
         config: MempoolServiceConfig,
         protocol_notifier: ProtocolNotificationRx<TSubstream>,
         connectivity_events: ConnectivityEventRx,
+        connectivity: ConnectivityRequester,
         mempool: Mempool,
     ) -> Self {
         Self {
             config,
             protocol_notifier,
             connectivity_events,
+            connectivity,
             mempool,
             num_synched: Arc::new(AtomicUsize::new(0)),
             permits: Arc::new(Semaphore::new(1)),
@@ -187,6 +190,7 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static
         let mempool = self.mempool.clone();
         let permits = self.permits.clone();
         let num_synched = self.num_synched.clone();
+        let mut connectivity = self.connectivity.clone();
         let config = self.config;
         task::spawn(async move {
             // Only initiate this protocol with a single peer at a time
@@ -205,6 +209,12 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static
                                 conn.peer_node_id().short_str(),
                             );
                             num_synched.fetch_add(1, Ordering::SeqCst);
+                            if let Err(err) = connectivity
+                                .credit_peer(conn.peer_node_id().clone(), GoodBehaviour::SuccessfulSync)
+                                .await
+                            {
+                                debug!(target: LOG_TARGET, "Failed to credit peer: {}", err);
+                            }
                         },
                         Err(err) => {
                             debug!(
@@ -213,6 +223,7 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static
                                 conn.peer_node_id().short_str(),
                                 err
                             );
+                            report_protocol_error(&mut connectivity, conn.peer_node_id().clone(), &err).await;
                         },
                     }
                 },
@@ -228,6 +239,7 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static
 
     fn spawn_inbound_handler(&self, node_id: NodeId, substream: TSubstream) {
         let mempool = self.mempool.clone();
+        let mut connectivity = self.connectivity.clone();
         let config = self.config;
         task::spawn(async move {
             let framed = framing::canonical(substream, MAX_FRAME_SIZE);
@@ -247,12 +259,24 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static
                         node_id.short_str(),
                         err
                     );
+                    report_protocol_error(&mut connectivity, node_id, &err).await;
                 },
             }
         });
     }
 }
 
+async fn report_protocol_error(connectivity: &mut ConnectivityRequester, node_id: NodeId, err: &MempoolProtocolError) {
+    if let Some(misbehaviour) = err.misbehaviour() {
+        if let Err(err) = connectivity
+            .report_misbehaviour(node_id, misbehaviour, err.to_string())
+            .await
+        {
+            debug!(target: LOG_TARGET, "Failed to report peer misbehaviour: {}", err);
+        }
+    }
+}
+
 struct MempoolPeerProtocol<TSubstream> {
     config: MempoolServiceConfig,
     framed: CanonicalFraming<TSubstream>,
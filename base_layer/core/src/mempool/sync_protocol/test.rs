@@ -21,6 +21,7 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    consensus::ConsensusManager,
     mempool::{
         async_mempool,
         proto,
@@ -32,6 +33,7 @@ use crate::{
 };
 use futures::{Sink, SinkExt, Stream, StreamExt};
 use std::{fmt, io, iter::repeat_with, sync::Arc};
+use tari_common::configuration::Network;
 use tari_comms::{
     connectivity::{ConnectivityEvent, ConnectivityEventTx},
     framing,
@@ -39,7 +41,10 @@ use tari_comms::{
     message::MessageExt,
     peer_manager::PeerFeatures,
     protocol::{ProtocolEvent, ProtocolNotification, ProtocolNotificationTx},
-    test_utils::{mocks::create_peer_connection_mock_pair, node_identity::build_node_identity},
+    test_utils::{
+        mocks::{create_connectivity_mock, create_peer_connection_mock_pair},
+        node_identity::build_node_identity,
+    },
     Bytes,
     BytesMut,
 };
@@ -59,7 +64,11 @@ pub fn create_transactions(n: usize) -> Vec<Transaction> {
 }
 
 fn new_mempool_with_transactions(n: usize) -> (Mempool, Vec<Transaction>) {
-    let mempool = Mempool::new(Default::default(), Arc::new(MockValidator::new(true)));
+    let mempool = Mempool::new(
+        Default::default(),
+        ConsensusManager::builder(Network::LocalNet).build(),
+        Arc::new(MockValidator::new(true)),
+    );
 
     let transactions = create_transactions(n);
     for txn in &transactions {
@@ -80,10 +89,13 @@ fn setup(
     let (protocol_notif_tx, protocol_notif_rx) = mpsc::channel(1);
     let (connectivity_events_tx, connectivity_events_rx) = broadcast::channel(10);
     let (mempool, transactions) = new_mempool_with_transactions(num_txns);
+    let (connectivity, connectivity_mock) = create_connectivity_mock();
+    connectivity_mock.spawn();
     let protocol = MempoolSyncProtocol::new(
         Default::default(),
         protocol_notif_rx,
         connectivity_events_rx,
+        connectivity,
         mempool.clone(),
     );
 
@@ -36,7 +36,7 @@ use crate::{
         priority::{FeePriority, PrioritizedTransaction},
         unconfirmed_pool::UnconfirmedPoolError,
     },
-    transactions::transaction::Transaction,
+    transactions::{tari_amount::MicroTari, transaction::Transaction},
 };
 use tari_common_types::types::{HashOutput, Signature};
 
@@ -230,6 +230,29 @@ impl UnconfirmedPool {
         Ok(results)
     }
 
+    /// Returns the lowest average fee per gram of the highest priority transactions that fill a block of
+    /// `total_weight`, i.e. an estimate of the fee per gram a transaction needs to be included in the next block. Zero
+    /// is returned if all the transactions fit in the block. Unlike `highest_priority_txs`, dependencies between
+    /// transactions are not taken into account.
+    pub fn min_fee_per_gram(&self, total_weight: u64) -> MicroTari {
+        let mut curr_weight = 0;
+        let mut min_fee_per_gram = None;
+        for prioritized_transaction in self
+            .txs_by_priority
+            .values()
+            .rev()
+            .filter_map(|tx_key| self.txs_by_signature.get(tx_key))
+        {
+            let fee_per_gram = MicroTari::from(prioritized_transaction.transaction.calculate_ave_fee_per_gram() as u64);
+            if curr_weight + prioritized_transaction.weight > total_weight {
+                return min_fee_per_gram.unwrap_or(fee_per_gram);
+            }
+            curr_weight += prioritized_transaction.weight;
+            min_fee_per_gram = Some(fee_per_gram);
+        }
+        MicroTari::from(0)
+    }
+
     fn get_all_dependant_transactions(
         &self,
         transaction: &PrioritizedTransaction,
@@ -460,6 +483,14 @@ impl UnconfirmedPool {
             .fold(0, |weight, (_, ptx)| weight + ptx.transaction.calculate_weight())
     }
 
+    /// Returns the total fees of all transactions stored in the pool.
+    pub fn calculate_total_fees(&self) -> MicroTari {
+        self.txs_by_signature
+            .iter()
+            .map(|(_, ptx)| ptx.transaction.body.get_total_fee())
+            .sum()
+    }
+
     #[cfg(test)]
     /// Returns false if there are any inconsistencies in the internal mempool state, otherwise true
     fn check_status(&self) -> bool {
@@ -553,6 +584,32 @@ mod test {
         assert!(unconfirmed_pool.check_status());
     }
 
+    #[test]
+    fn test_min_fee_per_gram() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 4, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(100), inputs: 5, outputs: 1).0);
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig::default());
+        assert_eq!(unconfirmed_pool.min_fee_per_gram(1_000), MicroTari(0));
+        unconfirmed_pool
+            .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone()])
+            .unwrap();
+        let total_weight = tx1.calculate_weight() + tx2.calculate_weight() + tx3.calculate_weight();
+        // Everything fits in the block, so any fee per gram will do
+        assert_eq!(unconfirmed_pool.min_fee_per_gram(total_weight), MicroTari(0));
+        // Only the two highest priority transactions fit
+        assert_eq!(
+            unconfirmed_pool.min_fee_per_gram(total_weight - 1),
+            MicroTari::from(tx1.calculate_ave_fee_per_gram() as u64)
+        );
+        // Not even the highest priority transaction fits
+        assert_eq!(
+            unconfirmed_pool.min_fee_per_gram(tx3.calculate_weight() - 1),
+            MicroTari::from(tx3.calculate_ave_fee_per_gram() as u64)
+        );
+    }
+
     #[test]
     fn test_double_spend_inputs() {
         let (tx1, _, _) = tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 1, outputs: 1);
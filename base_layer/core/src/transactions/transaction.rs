@@ -25,6 +25,7 @@
 
 use std::{
     cmp::{max, min, Ordering},
+    convert::TryFrom,
     fmt,
     fmt::{Display, Formatter},
     hash::{Hash, Hasher},
@@ -45,7 +46,7 @@ use tari_crypto::{
         REWIND_USER_MESSAGE_LENGTH,
     },
     ristretto::pedersen::PedersenCommitmentFactory,
-    script::{ExecutionStack, ScriptError, StackItem, TariScript},
+    script::{ExecutionStack, ScriptContext, ScriptError, StackItem, TariScript},
     signatures::CommitmentSignatureError,
     tari_utilities::{hex::Hex, message_format::MessageFormat, ByteArray, Hashable},
 };
@@ -65,6 +66,7 @@ use tari_common_types::types::{
     Commitment,
     CommitmentFactory,
     HashDigest,
+    HashOutput,
     MessageHash,
     PrivateKey,
     PublicKey,
@@ -203,6 +205,8 @@ pub enum TransactionError {
     ScriptOffset,
     #[error("Error executing script: {0}")]
     ScriptExecutionError(String),
+    #[error("Invalid previous block hash of {0} bytes")]
+    InvalidPrevHash(usize),
 }
 
 //-----------------------------------------     UnblindedOutput   ----------------------------------------------------//
@@ -445,10 +449,20 @@ impl TransactionInput {
         self.output_hash() == output.hash()
     }
 
+    /// The context that the script of this input is executed in when it is spent in the block at `height`, which
+    /// follows the block with hash `prev_hash`
+    pub fn script_context(&self, height: u64, prev_hash: &[u8]) -> Result<ScriptContext, TransactionError> {
+        let prev_hash =
+            <[u8; 32]>::try_from(prev_hash).map_err(|_| TransactionError::InvalidPrevHash(prev_hash.len()))?;
+        Ok(ScriptContext::new(height, &prev_hash, &self.commitment))
+    }
+
     /// This will run the script contained in the TransactionInput, returning either a script error or the resulting
-    /// public key.
-    pub fn run_script(&self) -> Result<PublicKey, TransactionError> {
-        match self.script.execute(&self.input_data)? {
+    /// public key. Scripts that inspect the chain (e.g. `CheckHeight`) are evaluated against the provided context, or
+    /// against an empty context at height 0 if none is given.
+    pub fn run_script(&self, context: Option<ScriptContext>) -> Result<PublicKey, TransactionError> {
+        let context = context.unwrap_or_default();
+        match self.script.execute_with_context(&self.input_data, &context)? {
             StackItem::PublicKey(pubkey) => Ok(pubkey),
             _ => Err(TransactionError::ScriptExecutionError(
                 "The script executed successfully but it did not leave a public key on the stack".to_string(),
@@ -482,8 +496,12 @@ impl TransactionInput {
 
     /// This will run the script and verify the script signature. If its valid, it will return the resulting public key
     /// from the script.
-    pub fn run_and_verify_script(&self, factory: &CommitmentFactory) -> Result<PublicKey, TransactionError> {
-        let key = self.run_script()?;
+    pub fn run_and_verify_script(
+        &self,
+        factory: &CommitmentFactory,
+        context: Option<ScriptContext>,
+    ) -> Result<PublicKey, TransactionError> {
+        let key = self.run_script(context)?;
         self.validate_script_signature(&key, factory)?;
         Ok(key)
     }
@@ -1140,13 +1158,16 @@ impl Transaction {
     /// 1. The signature signs the canonical message with the private excess
     /// 1. Range proofs of the outputs are valid
     ///
-    /// This function does NOT check that inputs come from the UTXO set
+    /// This function does NOT check that inputs come from the UTXO set. Input scripts are executed in the context of
+    /// a block at `height` with the previous block hash `prev_header`, if they are known.
     #[allow(clippy::erasing_op)] // This is for 0 * uT
     pub fn validate_internal_consistency(
         &self,
         bypass_range_proof_verification: bool,
         factories: &CryptoFactories,
         reward: Option<MicroTari>,
+        prev_header: Option<HashOutput>,
+        height: Option<u64>,
     ) -> Result<(), TransactionError> {
         let reward = reward.unwrap_or_else(|| 0 * uT);
         self.body.validate_internal_consistency(
@@ -1155,6 +1176,8 @@ impl Transaction {
             bypass_range_proof_verification,
             reward,
             factories,
+            prev_header,
+            height,
         )
     }
 
@@ -1242,6 +1265,8 @@ pub struct TransactionBuilder {
     offset: Option<BlindingFactor>,
     script_offset: Option<BlindingFactor>,
     reward: Option<MicroTari>,
+    prev_header: Option<HashOutput>,
+    height: Option<u64>,
 }
 
 impl TransactionBuilder {
@@ -1297,12 +1322,20 @@ impl TransactionBuilder {
         self
     }
 
+    /// Execute the input scripts in the context of the block at `height` following `prev_header` when validating the
+    /// built transaction
+    pub fn with_script_context(&mut self, prev_header: Option<HashOutput>, height: Option<u64>) -> &mut Self {
+        self.prev_header = prev_header;
+        self.height = height;
+        self
+    }
+
     /// Build the transaction.
     pub fn build(self, factories: &CryptoFactories) -> Result<Transaction, TransactionError> {
         if let (Some(script_offset), Some(offset)) = (self.script_offset, self.offset) {
             let (i, o, k) = self.body.dissolve();
             let tx = Transaction::new(i, o, k, offset, script_offset);
-            tx.validate_internal_consistency(true, factories, self.reward)?;
+            tx.validate_internal_consistency(true, factories, self.reward, self.prev_header, self.height)?;
             Ok(tx)
         } else {
             Err(TransactionError::ValidationError(
@@ -1319,6 +1352,8 @@ impl Default for TransactionBuilder {
             body: AggregateBody::empty(),
             reward: None,
             script_offset: None,
+            prev_header: None,
+            height: None,
         }
     }
 }
@@ -1555,7 +1590,9 @@ mod test {
         let (tx, _, _) = helpers::create_tx(5000.into(), 15.into(), 1, 2, 1, 4);
 
         let factories = CryptoFactories::default();
-        assert!(tx.validate_internal_consistency(false, &factories, None).is_ok());
+        assert!(tx
+            .validate_internal_consistency(false, &factories, None, None, None)
+            .is_ok());
     }
 
     #[test]
@@ -1568,7 +1605,9 @@ mod test {
         assert_eq!(tx.body.kernels().len(), 1);
 
         let factories = CryptoFactories::default();
-        assert!(tx.validate_internal_consistency(false, &factories, None).is_ok());
+        assert!(tx
+            .validate_internal_consistency(false, &factories, None, None, None)
+            .is_ok());
 
         let schema = txn_schema!(from: vec![outputs[1].clone()], to: vec![1 * T, 2 * T]);
         let (tx2, _outputs, _) = helpers::spend_utxos(schema);
@@ -1599,11 +1638,13 @@ mod test {
         }
 
         // Validate basis transaction where cut-through has not been applied.
-        assert!(tx3.validate_internal_consistency(false, &factories, None).is_ok());
+        assert!(tx3
+            .validate_internal_consistency(false, &factories, None, None, None)
+            .is_ok());
 
         // tx3_cut_through has manual cut-through, it should not be possible so this should fail
         assert!(tx3_cut_through
-            .validate_internal_consistency(false, &factories, None)
+            .validate_internal_consistency(false, &factories, None, None, None)
             .is_err());
     }
 
@@ -1641,10 +1682,36 @@ mod test {
         tx.body.inputs_mut()[0].input_data = stack;
 
         let factories = CryptoFactories::default();
-        let err = tx.validate_internal_consistency(false, &factories, None).unwrap_err();
+        let err = tx
+            .validate_internal_consistency(false, &factories, None, None, None)
+            .unwrap_err();
         assert!(matches!(err, TransactionError::InvalidSignatureError(_)));
     }
 
+    #[test]
+    fn scripts_are_executed_at_the_given_height() {
+        let (mut inputs, outputs) = helpers::create_unblinded_txos(5000.into(), 1, 1, 2, 15.into());
+        // Only spendable before height 10
+        inputs[0].script = script!(CheckHeight(10) LtZero IfThen Nop Else Drop EndIf);
+        let tx = helpers::create_transaction_with(1, 15.into(), inputs, outputs);
+
+        let factories = CryptoFactories::default();
+        assert!(tx
+            .validate_internal_consistency(false, &factories, None, None, None)
+            .is_ok());
+        assert!(tx
+            .validate_internal_consistency(false, &factories, None, Some(vec![0; 32]), Some(9))
+            .is_ok());
+        let err = tx
+            .validate_internal_consistency(false, &factories, None, Some(vec![0; 32]), Some(10))
+            .unwrap_err();
+        assert!(matches!(err, TransactionError::ScriptError(_)));
+        let err = tx
+            .validate_internal_consistency(false, &factories, None, Some(vec![0; 16]), Some(9))
+            .unwrap_err();
+        assert!(matches!(err, TransactionError::InvalidPrevHash(16)));
+    }
+
     #[test]
     fn test_output_rewinding() {
         let test_params = TestParams::new();
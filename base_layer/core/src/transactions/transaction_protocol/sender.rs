@@ -47,7 +47,15 @@ use crate::transactions::{
 use digest::Digest;
 use serde::{Deserialize, Serialize};
 use std::fmt;
-use tari_common_types::types::{BlindingFactor, ComSignature, PrivateKey, PublicKey, RangeProofService, Signature};
+use tari_common_types::types::{
+    BlindingFactor,
+    ComSignature,
+    HashOutput,
+    PrivateKey,
+    PublicKey,
+    RangeProofService,
+    Signature,
+};
 use tari_crypto::{
     keys::PublicKey as PublicKeyTrait,
     ristretto::pedersen::{PedersenCommitment, PedersenCommitmentFactory},
@@ -462,6 +470,8 @@ impl SenderTransactionProtocol {
         info: &RawTransactionInfo,
         features: KernelFeatures,
         factories: &CryptoFactories,
+        prev_header: Option<HashOutput>,
+        height: Option<u64>,
     ) -> Result<Transaction, TPE> {
         let mut tx_builder = TransactionBuilder::new();
         for i in &info.inputs {
@@ -484,6 +494,7 @@ impl SenderTransactionProtocol {
             .with_signature(&s_agg)
             .build()?;
         tx_builder.with_kernel(kernel);
+        tx_builder.with_script_context(prev_header, height);
         tx_builder.build(factories).map_err(TPE::from)
     }
 
@@ -541,6 +552,18 @@ impl SenderTransactionProtocol {
     /// the transaction protocol moves to Failed state and we are done; you can't rescue the situation. The function
     /// returns `Ok(false)` in this instance.
     pub fn finalize(&mut self, features: KernelFeatures, factories: &CryptoFactories) -> Result<(), TPE> {
+        self.finalize_at_height(features, factories, None, None)
+    }
+
+    /// Finalise the transaction as with `finalize`, but execute the input scripts in the context of the block at
+    /// `height` following `prev_header`. Use this when spending outputs with scripts that depend on the block height.
+    pub fn finalize_at_height(
+        &mut self,
+        features: KernelFeatures,
+        factories: &CryptoFactories,
+        prev_header: Option<HashOutput>,
+        height: Option<u64>,
+    ) -> Result<(), TPE> {
         // Create the final aggregated signature, moving to the Failed state if anything goes wrong
         match &mut self.state {
             SenderState::Finalizing(_) => {
@@ -556,14 +579,14 @@ impl SenderTransactionProtocol {
             SenderState::Finalizing(info) => {
                 let result = self
                     .validate()
-                    .and_then(|_| Self::build_transaction(info, features, factories));
+                    .and_then(|_| Self::build_transaction(info, features, factories, prev_header.clone(), height));
                 if let Err(e) = result {
                     self.state = SenderState::Failed(e.clone());
                     return Err(e);
                 }
                 let transaction = result.unwrap();
                 let result = transaction
-                    .validate_internal_consistency(true, factories, None)
+                    .validate_internal_consistency(true, factories, None, prev_header, height)
                     .map_err(TPE::TransactionBuildError);
                 if let Err(e) = result {
                     self.state = SenderState::Failed(e.clone());
@@ -970,7 +993,7 @@ mod test {
         assert_eq!(tx.body.outputs().len(), 2);
         assert!(tx
             .clone()
-            .validate_internal_consistency(false, &factories, None)
+            .validate_internal_consistency(false, &factories, None, None, None)
             .is_ok());
     }
 
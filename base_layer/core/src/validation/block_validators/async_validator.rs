@@ -227,6 +227,7 @@ impl<B: BlockchainBackend + 'static> BlockValidator<B> {
         inputs: Vec<TransactionInput>,
     ) -> AbortOnDropJoinHandle<Result<InputValidationData, ValidationError>> {
         let block_height = header.height;
+        let prev_hash = header.prev_hash.clone();
         let commitment_factory = self.factories.commitment.clone();
         let db = self.db.inner().clone();
         task::spawn_blocking(move || {
@@ -269,7 +270,9 @@ impl<B: BlockchainBackend + 'static> BlockValidator<B> {
                 // the tari script
                 if not_found_inputs.is_empty() {
                     // lets count up the input script public keys
-                    aggregate_input_key = aggregate_input_key + input.run_and_verify_script(&commitment_factory)?;
+                    let context = input.script_context(block_height, &prev_hash)?;
+                    aggregate_input_key =
+                        aggregate_input_key + input.run_and_verify_script(&commitment_factory, Some(context))?;
                     commitment_sum = &commitment_sum + &input.commitment;
                 }
             }
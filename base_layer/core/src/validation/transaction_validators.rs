@@ -40,25 +40,35 @@ pub const LOG_TARGET: &str = "c::val::transaction_validators";
 /// 1. The signature signs the canonical message with the private excess
 /// 1. Range proofs of the outputs are valid
 ///
-/// This function does NOT check that inputs come from the UTXO set
-pub struct TxInternalConsistencyValidator {
+/// This function does NOT check that inputs come from the UTXO set. Input scripts are executed in the context of the
+/// block following the current tip, as that is the earliest block the transaction can be mined in.
+pub struct TxInternalConsistencyValidator<B> {
+    db: BlockchainDatabase<B>,
     factories: CryptoFactories,
     bypass_range_proof_verification: bool,
 }
 
-impl TxInternalConsistencyValidator {
-    pub fn new(factories: CryptoFactories, bypass_range_proof_verification: bool) -> Self {
+impl<B: BlockchainBackend> TxInternalConsistencyValidator<B> {
+    pub fn new(factories: CryptoFactories, bypass_range_proof_verification: bool, db: BlockchainDatabase<B>) -> Self {
         Self {
+            db,
             factories,
             bypass_range_proof_verification,
         }
     }
 }
 
-impl MempoolTransactionValidation for TxInternalConsistencyValidator {
+impl<B: BlockchainBackend> MempoolTransactionValidation for TxInternalConsistencyValidator<B> {
     fn validate(&self, tx: &Transaction) -> Result<(), ValidationError> {
-        tx.validate_internal_consistency(self.bypass_range_proof_verification, &self.factories, None)
-            .map_err(ValidationError::TransactionError)?;
+        let metadata = self.db.get_chain_metadata()?;
+        tx.validate_internal_consistency(
+            self.bypass_range_proof_verification,
+            &self.factories,
+            None,
+            Some(metadata.best_block().clone()),
+            Some(metadata.height_of_longest_chain() + 1),
+        )
+        .map_err(ValidationError::TransactionError)?;
         Ok(())
     }
 }
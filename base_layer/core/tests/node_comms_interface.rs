@@ -71,7 +71,11 @@ async fn test_request_responder(
 
 fn new_mempool() -> Mempool {
     let mempool_validator = MockValidator::new(true);
-    Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator))
+    Mempool::new(
+        MempoolConfig::default(),
+        ConsensusManager::builder(Network::LocalNet).build(),
+        Arc::new(mempool_validator),
+    )
 }
 
 #[tokio::test]
@@ -437,7 +441,11 @@ async fn inbound_fetch_blocks_before_horizon_height() {
     };
     let store = create_store_with_consensus_and_validators_and_config(consensus_manager.clone(), validators, config);
     let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        consensus_manager.clone(),
+        Arc::new(mempool_validator),
+    );
     let (block_event_sender, _) = broadcast::channel(50);
     let (request_sender, _) = reply_channel::unbounded();
     let (block_sender, _) = mpsc::unbounded_channel();
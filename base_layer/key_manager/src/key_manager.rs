@@ -125,6 +125,20 @@ where
         }
     }
 
+    /// Constructs a KeyManager for a sub-branch of this KeyManager. The master key of the new KeyManager is derived as
+    /// branch_master_key=DIGEST(master_key||"branch:"||branch_seed), so keys on different branches are independent of
+    /// each other and of the keys derived by `derive_key`
+    pub fn branch(&self, branch_seed: String, primary_key_index: u64) -> Result<KeyManager<K, D>, ByteArrayError> {
+        let concatenated = format!("{}branch:{}", self.master_key.to_hex(), branch_seed);
+        let master_key = K::from_bytes(D::digest(&concatenated.into_bytes()).as_slice())?;
+        Ok(KeyManager {
+            master_key,
+            branch_seed,
+            primary_key_index,
+            digest_type: PhantomData,
+        })
+    }
+
     /// Generate next deterministic private key derived from master key
     pub fn next_key(&mut self) -> Result<DerivedKey<K>, ByteArrayError> {
         self.primary_key_index += 1;
@@ -222,6 +236,22 @@ mod test {
         assert_eq!(next_key2.key_index, desired_key_index2);
     }
 
+    #[test]
+    fn test_branch() {
+        let km = KeyManager::<RistrettoSecretKey, Sha256>::new(&mut OsRng);
+        let branch1 = km.branch("branch1".to_string(), 0).unwrap();
+        let branch1_again = km.branch("branch1".to_string(), 5).unwrap();
+        let branch2 = km.branch("branch2".to_string(), 0).unwrap();
+
+        assert_eq!(branch1.master_key, branch1_again.master_key);
+        assert_eq!(branch1_again.key_index(), 5);
+        assert_eq!(branch1.branch_seed, "branch1");
+        assert_ne!(branch1.master_key, branch2.master_key);
+        assert_ne!(branch1.master_key, km.master_key);
+        assert_ne!(branch1.derive_key(1).unwrap().k, km.derive_key(1).unwrap().k);
+        assert_ne!(branch1.derive_key(1).unwrap().k, branch2.derive_key(1).unwrap().k);
+    }
+
     #[test]
     fn test_to_file_and_from_file() {
         let desired_km = KeyManager::<RistrettoSecretKey, Sha256>::new(&mut OsRng);
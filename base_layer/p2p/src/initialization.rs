@@ -42,16 +42,21 @@ use std::{
     time::{Duration, Instant},
 };
 use tari_common::configuration::Network;
+#[cfg(feature = "quic")]
+use tari_comms::transports::QuicTransport;
 use tari_comms::{
     backoff::ConstantBackoff,
+    bandwidth::BandwidthConfig,
+    connection_manager::FirewallRules,
     multiaddr::Multiaddr,
-    peer_manager::{NodeIdentity, Peer, PeerFeatures, PeerManagerError},
+    peer_manager::{NodeIdentity, Peer, PeerDatabaseCipher, PeerFeatures, PeerManagerError},
     pipeline,
     protocol::{
         messaging::{MessagingEventSender, MessagingProtocolExtension},
         rpc::RpcServer,
         NodeNetworkInfo,
     },
+    reachability::ReachabilityConfig,
     tor,
     tor::HiddenServiceControllerError,
     transports::{MemoryTransport, SocksTransport, TcpWithTorTransport},
@@ -117,6 +122,9 @@ pub struct P2pConfig {
     pub datastore_path: PathBuf,
     /// Name to use for the peer database
     pub peer_database_name: String,
+    /// If set, peer records are encrypted at rest with this cipher. Existing plaintext records are encrypted when the
+    /// peer database is opened.
+    pub peer_database_cipher: Option<PeerDatabaseCipher>,
     /// The maximum number of concurrent Inbound tasks allowed before back-pressure is applied to peers
     pub max_concurrent_inbound_tasks: usize,
     /// The size of the buffer (channel) which holds pending outbound message requests
@@ -139,6 +147,10 @@ pub struct P2pConfig {
     pub listener_liveness_max_sessions: usize,
     /// CIDR for addresses allowed to enter into liveness check mode on the listener.
     pub listener_liveness_allowlist_cidrs: Vec<String>,
+    /// Firewall rules for inbound connections
+    pub firewall_rules: FirewallRules,
+    /// Upload and download rate limits for all connections and for individual protocols
+    pub bandwidth: BandwidthConfig,
     /// User agent string for this node
     pub user_agent: String,
     /// Unparsed peer seeds
@@ -154,6 +166,8 @@ pub struct P2pConfig {
     /// for direct comms between a wallet and base node. If this is set to None, no listener will be bound.
     /// Default: None
     pub auxilary_tcp_listener_address: Option<Multiaddr>,
+    /// Configuration for the reachability service, which checks that the node's public address is reachable by peers
+    pub reachability: ReachabilityConfig,
 }
 
 /// Initialize Tari Comms configured for tests
@@ -290,6 +304,14 @@ pub async fn spawn_comms_using_transport(
                 .spawn_with_transport(transport)
                 .await?
         },
+        #[cfg(feature = "quic")]
+        TransportType::Quic { listener_address } => {
+            debug!(target: LOG_TARGET, "Building QUIC comms stack");
+            comms
+                .with_listener_address(listener_address)
+                .spawn_with_transport(QuicTransport::default())
+                .await?
+        },
     };
 
     Ok(comms)
@@ -305,7 +327,8 @@ async fn initialize_hidden_service(
         .with_socks_authentication(config.socks_auth)
         .with_control_server_auth(config.control_server_auth)
         .with_control_server_address(config.control_server_addr)
-        .with_bypass_proxy_addresses(config.tor_proxy_bypass_addresses.into());
+        .with_bypass_proxy_addresses(config.tor_proxy_bypass_addresses.into())
+        .with_client_auth(config.client_auth);
 
     if config.tor_proxy_bypass_for_outbound_tcp {
         builder = builder.bypass_tor_for_tcp_addresses();
@@ -333,7 +356,10 @@ async fn configure_comms_and_dht(
         .build()
         .unwrap();
     let peer_database = datastore.get_handle(&config.peer_database_name).unwrap();
-    let peer_database = LMDBWrapper::new(Arc::new(peer_database));
+    let mut peer_database = LMDBWrapper::new(Arc::new(peer_database));
+    if let Some(cipher) = config.peer_database_cipher.clone() {
+        peer_database = peer_database.with_cipher(Arc::new(cipher));
+    }
 
     let listener_liveness_allowlist_cidrs = parse_cidrs(&config.listener_liveness_allowlist_cidrs)
         .map_err(CommsInitializationError::InvalidLivenessCidrs)?;
@@ -341,6 +367,9 @@ async fn configure_comms_and_dht(
     let builder = builder
         .with_listener_liveness_max_sessions(config.listener_liveness_max_sessions)
         .with_listener_liveness_allowlist_cidrs(listener_liveness_allowlist_cidrs)
+        .with_firewall_rules(config.firewall_rules.clone())
+        .with_bandwidth_config(config.bandwidth.clone())
+        .with_reachability_config(config.reachability.clone())
         .with_dial_backoff(ConstantBackoff::new(Duration::from_millis(500)))
         .with_peer_storage(peer_database, Some(file_lock));
 
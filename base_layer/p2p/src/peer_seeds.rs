@@ -26,7 +26,7 @@ use anyhow::anyhow;
 use std::{net::SocketAddr, str::FromStr};
 use tari_comms::{
     multiaddr::Multiaddr,
-    peer_manager::{NodeId, Peer, PeerFeatures},
+    peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
     types::CommsPublicKey,
 };
 use tari_utilities::hex::Hex;
@@ -107,7 +107,7 @@ impl From<SeedPeer> for Peer {
             seed.public_key,
             node_id,
             seed.addresses.into(),
-            Default::default(),
+            PeerFlags::SEED,
             PeerFeatures::COMMUNICATION_NODE,
             Default::default(),
             Default::default(),
@@ -41,6 +41,9 @@ pub enum TransportType {
         socks_config: SocksConfig,
         listener_address: Multiaddr,
     },
+    /// Use a QuicTransport. This transport can only connect to QUIC addresses.
+    #[cfg(feature = "quic")]
+    Quic { listener_address: Multiaddr },
 }
 
 #[derive(Debug, Clone)]
@@ -64,6 +67,9 @@ pub struct TorConfig {
     /// Use a direct TCP/IP connection if a TCP address is given instead of the tor proxy. This is worse for privacy
     /// but can use the full available connection bandwidth
     pub tor_proxy_bypass_for_outbound_tcp: bool,
+    /// Only clients holding the private key for one of these public keys may connect to the hidden service. If empty,
+    /// any client may connect.
+    pub client_auth: Vec<tor::ClientAuthPublicKey>,
 }
 
 impl fmt::Display for TorConfig {
@@ -71,12 +77,13 @@ impl fmt::Display for TorConfig {
         write!(
             f,
             "control_server_addr: {}, control_server_auth: {}, {}, socks_address_override: {:?}, \
-             tor_proxy_bypass_outbound_tcp_addresses = {:?}",
+             tor_proxy_bypass_outbound_tcp_addresses = {:?}, authorized clients = {}",
             self.control_server_addr,
             self.control_server_auth,
             self.port_mapping,
             self.socks_address_override,
-            self.tor_proxy_bypass_for_outbound_tcp
+            self.tor_proxy_bypass_for_outbound_tcp,
+            self.client_auth.len()
         )
     }
 }
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS htlc_preimages;
+DROP TABLE IF EXISTS htlcs;
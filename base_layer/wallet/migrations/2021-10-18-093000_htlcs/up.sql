@@ -0,0 +1,29 @@
+CREATE TABLE htlcs (
+    commitment                 BLOB PRIMARY KEY NOT NULL,
+    hash                       BLOB             NOT NULL,
+    tx_id                      INTEGER          NOT NULL,
+    role                       INTEGER          NOT NULL,
+    status                     INTEGER          NOT NULL,
+    value                      INTEGER          NOT NULL,
+    spending_key               BLOB             NOT NULL,
+    flags                      INTEGER          NOT NULL,
+    maturity                   INTEGER          NOT NULL,
+    script                     BLOB             NOT NULL,
+    script_private_key         BLOB             NOT NULL,
+    sender_offset_public_key   BLOB             NOT NULL,
+    metadata_signature_nonce   BLOB             NOT NULL,
+    metadata_signature_u_key   BLOB             NOT NULL,
+    metadata_signature_v_key   BLOB             NOT NULL,
+    hash_lock                  BLOB             NOT NULL,
+    timeout_height             INTEGER          NOT NULL,
+    counterparty               BLOB             NOT NULL,
+    fee_per_gram               INTEGER          NOT NULL,
+    mined_height               INTEGER          NULL,
+    mined_mmr_position         INTEGER          NULL,
+    spent_in_tx_id             INTEGER          NULL
+);
+
+CREATE TABLE htlc_preimages (
+    hash_lock BLOB PRIMARY KEY NOT NULL,
+    preimage  BLOB             NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE scheduled_transactions (
+    tx_id            INTEGER PRIMARY KEY NOT NULL,
+    scheduled_height INTEGER             NULL,
+    scheduled_time   DATETIME            NULL
+);
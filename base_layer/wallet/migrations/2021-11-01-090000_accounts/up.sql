@@ -0,0 +1,8 @@
+CREATE TABLE accounts (
+    id        INTEGER PRIMARY KEY NOT NULL,
+    name      TEXT UNIQUE         NOT NULL,
+    key_index BIGINT              NOT NULL DEFAULT 0
+);
+
+ALTER TABLE outputs
+    ADD COLUMN account_id BIGINT NOT NULL DEFAULT 0;
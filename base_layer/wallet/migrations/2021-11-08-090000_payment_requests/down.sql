@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS payment_request_payments;
+DROP TABLE IF EXISTS payment_requests;
@@ -0,0 +1,19 @@
+CREATE TABLE payment_requests (
+    reference            BIGINT PRIMARY KEY NOT NULL,
+    recipient_public_key BLOB               NOT NULL,
+    amount               BIGINT             NOT NULL,
+    message              TEXT               NOT NULL,
+    expiry_height        BIGINT             NOT NULL,
+    signature_nonce      BLOB               NOT NULL,
+    signature_key        BLOB               NOT NULL,
+    status               INTEGER            NOT NULL,
+    amount_received      BIGINT             NOT NULL,
+    timestamp            DATETIME           NOT NULL
+);
+
+CREATE TABLE payment_request_payments (
+    tx_id     BIGINT PRIMARY KEY NOT NULL,
+    reference BIGINT             NOT NULL,
+    amount    BIGINT             NOT NULL,
+    FOREIGN KEY (reference) REFERENCES payment_requests (reference)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE held_transactions (
+    tx_id             BIGINT PRIMARY KEY NOT NULL,
+    source_public_key BLOB               NOT NULL,
+    amount            BIGINT             NOT NULL,
+    message           TEXT               NOT NULL,
+    sender_message    TEXT               NOT NULL,
+    timestamp         DATETIME           NOT NULL
+);
@@ -31,6 +31,13 @@ pub struct BaseNodeServiceConfig {
     pub base_node_rpc_pool_size: usize,
     pub request_max_age: Duration,
     pub event_channel_size: usize,
+    /// The number of consecutive failed connection attempts or RPC requests after which the wallet fails over to
+    /// another base node in its pool
+    pub base_node_failover_threshold: u32,
+    /// How often the other base nodes in the pool are asked for their chain tip to cross-check the current base node
+    pub base_node_cross_check_interval: Duration,
+    /// How many blocks the current base node's tip may lag behind the best tip in the pool before failing over
+    pub base_node_max_tip_lag: u64,
 }
 
 impl Default for BaseNodeServiceConfig {
@@ -40,6 +47,9 @@ impl Default for BaseNodeServiceConfig {
             base_node_rpc_pool_size: 10,
             request_max_age: Duration::from_secs(60),
             event_channel_size: 250,
+            base_node_failover_threshold: 3,
+            base_node_cross_check_interval: Duration::from_secs(120),
+            base_node_max_tip_lag: 5,
         }
     }
 }
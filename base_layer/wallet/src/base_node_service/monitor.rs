@@ -86,6 +86,11 @@ where
                 },
                 Err(e @ BaseNodeMonitorError::RpcFailed(_)) => {
                     warn!(target: LOG_TARGET, "Connectivity failure to base node: {}", e);
+                    if let Some(base_node_id) = self.wallet_connectivity.get_current_base_node_id() {
+                        self.wallet_connectivity
+                            .report_base_node_rpc_failure(base_node_id)
+                            .await;
+                    }
                     self.map_state(move |_| BaseNodeState {
                         chain_metadata: None,
                         is_synced: None,
@@ -137,6 +142,10 @@ where
                 })?;
             let latency = start.elapsed();
 
+            self.wallet_connectivity
+                .report_base_node_state(base_node_id.clone(), latency, chain_metadata.clone())
+                .await;
+
             let is_synced = tip_info.is_synced;
             debug!(
                 target: LOG_TARGET,
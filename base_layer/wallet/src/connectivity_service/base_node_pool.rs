@@ -0,0 +1,185 @@
+//  Copyright 2021, The Tari Project
+//
+//  Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//  following conditions are met:
+//
+//  1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//  disclaimer.
+//
+//  2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//  following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//  3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//  products derived from this software without specific prior written permission.
+//
+//  THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//  INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//  DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//  SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//  SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::{cmp, time::Duration};
+use tari_common_types::chain_metadata::ChainMetadata;
+use tari_comms::peer_manager::{NodeId, Peer};
+
+/// The health of a base node in the wallet's pool of base nodes
+#[derive(Debug, Clone)]
+pub struct BaseNodeHealth {
+    pub peer: Peer,
+    /// The latency of the last successful request to the node
+    pub latency: Option<Duration>,
+    /// The chain tip the node reported last
+    pub chain_metadata: Option<ChainMetadata>,
+    /// The number of connection attempts and RPC requests that have failed since the last successful one
+    pub consecutive_failures: u32,
+}
+
+impl BaseNodeHealth {
+    fn new(peer: Peer) -> Self {
+        Self {
+            peer,
+            latency: None,
+            chain_metadata: None,
+            consecutive_failures: 0,
+        }
+    }
+
+    pub fn is_failing(&self, failover_threshold: u32) -> bool {
+        self.consecutive_failures >= failover_threshold
+    }
+}
+
+/// The ordered list of base nodes the wallet can use, along with their health. The first node is the most preferred.
+#[derive(Debug, Default)]
+pub(super) struct BaseNodePool {
+    nodes: Vec<BaseNodeHealth>,
+}
+
+impl BaseNodePool {
+    /// Replace the nodes in the pool, keeping what is known about the health of the nodes that were already in it
+    pub fn set_peers(&mut self, peers: Vec<Peer>) {
+        let mut nodes = Vec::with_capacity(peers.len());
+        for peer in peers {
+            if nodes.iter().any(|n: &BaseNodeHealth| n.peer.node_id == peer.node_id) {
+                continue;
+            }
+            let node = match self.nodes.iter().position(|n| n.peer.node_id == peer.node_id) {
+                Some(pos) => {
+                    let mut node = self.nodes.remove(pos);
+                    node.peer = peer;
+                    node
+                },
+                None => BaseNodeHealth::new(peer),
+            };
+            nodes.push(node);
+        }
+        self.nodes = nodes;
+    }
+
+    /// Add a base node that was selected directly to the front of the pool, if it is not in the pool yet
+    pub fn insert_peer(&mut self, peer: Peer) {
+        if !self.contains(&peer.node_id) {
+            self.nodes.insert(0, BaseNodeHealth::new(peer));
+        }
+    }
+
+    pub fn contains(&self, node_id: &NodeId) -> bool {
+        self.nodes.iter().any(|n| n.peer.node_id == *node_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = &Peer> {
+        self.nodes.iter().map(|n| &n.peer)
+    }
+
+    pub fn status(&self) -> Vec<BaseNodeHealth> {
+        self.nodes.clone()
+    }
+
+    pub fn record_success(&mut self, node_id: &NodeId, latency: Duration, chain_metadata: ChainMetadata) {
+        if let Some(node) = self.get_mut(node_id) {
+            node.latency = Some(latency);
+            node.chain_metadata = Some(chain_metadata);
+            node.consecutive_failures = 0;
+        }
+    }
+
+    /// Record a failed connection attempt or RPC request, returning the number of consecutive failures of the node
+    pub fn record_failure(&mut self, node_id: &NodeId) -> u32 {
+        match self.get_mut(node_id) {
+            Some(node) => {
+                node.consecutive_failures = node.consecutive_failures.saturating_add(1);
+                node.consecutive_failures
+            },
+            None => 0,
+        }
+    }
+
+    /// The number of blocks the node's tip is behind the tips of the other nodes that are not failing. Tips and their
+    /// accumulated work are reported by the nodes themselves and are not verified, so no single node is trusted to
+    /// say that another node lags. The lag returned is the largest one that a majority of the other reporting nodes
+    /// agree on, where a tip only counts as ahead if it also claims more accumulated work.
+    pub fn tip_lag(&self, node_id: &NodeId, failover_threshold: u32) -> u64 {
+        let metadata = match self.get(node_id).and_then(|n| n.chain_metadata.as_ref()) {
+            Some(m) => m,
+            None => return 0,
+        };
+        let mut lags = self
+            .nodes
+            .iter()
+            .filter(|n| n.peer.node_id != *node_id && !n.is_failing(failover_threshold))
+            .filter_map(|n| n.chain_metadata.as_ref())
+            .map(|m| {
+                if m.accumulated_difficulty() > metadata.accumulated_difficulty() {
+                    m.height_of_longest_chain()
+                        .saturating_sub(metadata.height_of_longest_chain())
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<_>>();
+        if lags.is_empty() {
+            return 0;
+        }
+        // Sorted from the largest lag, the lag at the middle is the one at least a majority of the nodes report
+        lags.sort_unstable_by(|a, b| b.cmp(a));
+        lags[lags.len() / 2]
+    }
+
+    /// Returns true if a majority of the other nodes report a tip with more work that is more than `max_tip_lag` blocks
+    /// ahead of the node's tip. A node that is stuck, or that hides blocks from the wallet, is detected this way.
+    pub fn is_lagging(&self, node_id: &NodeId, failover_threshold: u32, max_tip_lag: u64) -> bool {
+        self.tip_lag(node_id, failover_threshold) > max_tip_lag
+    }
+
+    /// Choose the healthiest base node other than `current`. Nodes are ranked by their consecutive failures, how far
+    /// their tip lags behind the best tip, their latency and then their position in the pool.
+    pub fn select_failover(&self, current: &NodeId, failover_threshold: u32) -> Option<Peer> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.peer.node_id != *current)
+            .min_by_key(|(i, n)| {
+                (
+                    cmp::min(n.consecutive_failures, failover_threshold),
+                    self.tip_lag(&n.peer.node_id, failover_threshold),
+                    n.latency.unwrap_or(Duration::MAX),
+                    *i,
+                )
+            })
+            .map(|(_, n)| n.peer.clone())
+    }
+
+    fn get(&self, node_id: &NodeId) -> Option<&BaseNodeHealth> {
+        self.nodes.iter().find(|n| n.peer.node_id == *node_id)
+    }
+
+    fn get_mut(&mut self, node_id: &NodeId) -> Option<&mut BaseNodeHealth> {
+        self.nodes.iter_mut().find(|n| n.peer.node_id == *node_id)
+    }
+}
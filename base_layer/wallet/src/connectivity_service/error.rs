@@ -31,6 +31,8 @@ pub enum WalletConnectivityError {
     ConnectivityError(#[from] ConnectivityError),
     #[error("Service is terminated and can no longer response to requests")]
     ServiceTerminated,
+    #[error("Base node cross-check failed: {0}")]
+    CrossCheckFailed(String),
 }
 
 impl From<mpsc::SendError> for WalletConnectivityError {
@@ -20,8 +20,13 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::service::OnlineStatus;
-use crate::{connectivity_service::WalletConnectivityInterface, util::watch::Watch};
+use super::{base_node_pool::BaseNodeHealth, service::OnlineStatus};
+use crate::{
+    connectivity_service::{WalletConnectivityError, WalletConnectivityInterface},
+    util::watch::Watch,
+};
+use std::time::Duration;
+use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     peer_manager::{NodeId, Peer},
     protocol::rpc::RpcClientLease,
@@ -33,6 +38,12 @@ use tokio::sync::{mpsc, oneshot, watch};
 pub enum WalletConnectivityRequest {
     ObtainBaseNodeWalletRpcClient(oneshot::Sender<RpcClientLease<BaseNodeWalletRpcClient>>),
     ObtainBaseNodeSyncRpcClient(oneshot::Sender<RpcClientLease<BaseNodeSyncRpcClient>>),
+    SetBaseNodePool(Vec<Peer>),
+    GetBaseNodePoolStatus(oneshot::Sender<Vec<BaseNodeHealth>>),
+    ReportBaseNodeState(NodeId, Duration, ChainMetadata),
+    ReportBaseNodeRpcFailure(NodeId),
+    ObtainCrossCheckWalletRpcClients(oneshot::Sender<Vec<(NodeId, BaseNodeWalletRpcClient)>>),
+    ReportBaseNodeDisputed(NodeId),
 }
 
 #[derive(Clone)]
@@ -54,6 +65,25 @@ impl WalletConnectivityHandle {
             online_status_rx,
         }
     }
+
+    /// Set the base nodes the wallet may fail over to, in order of preference. If no base node has been selected yet
+    /// the first one is used.
+    pub async fn set_base_node_pool(&mut self, peers: Vec<Peer>) -> Result<(), WalletConnectivityError> {
+        self.sender
+            .send(WalletConnectivityRequest::SetBaseNodePool(peers))
+            .await
+            .map_err(|_| WalletConnectivityError::ServiceTerminated)
+    }
+
+    /// Get the health of each of the base nodes in the pool
+    pub async fn get_base_node_pool_status(&mut self) -> Result<Vec<BaseNodeHealth>, WalletConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(WalletConnectivityRequest::GetBaseNodePoolStatus(reply_tx))
+            .await
+            .map_err(|_| WalletConnectivityError::ServiceTerminated)?;
+        reply_rx.await.map_err(|_| WalletConnectivityError::ServiceTerminated)
+    }
 }
 
 #[async_trait::async_trait]
@@ -128,4 +158,42 @@ impl WalletConnectivityInterface for WalletConnectivityHandle {
     fn is_base_node_set(&self) -> bool {
         self.base_node_watch.borrow().is_some()
     }
+
+    async fn report_base_node_state(&mut self, node_id: NodeId, latency: Duration, chain_metadata: ChainMetadata) {
+        let _ = self
+            .sender
+            .send(WalletConnectivityRequest::ReportBaseNodeState(
+                node_id,
+                latency,
+                chain_metadata,
+            ))
+            .await;
+    }
+
+    async fn report_base_node_rpc_failure(&mut self, node_id: NodeId) {
+        let _ = self
+            .sender
+            .send(WalletConnectivityRequest::ReportBaseNodeRpcFailure(node_id))
+            .await;
+    }
+
+    async fn obtain_cross_check_wallet_rpc_clients(&mut self) -> Vec<(NodeId, BaseNodeWalletRpcClient)> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        if self
+            .sender
+            .send(WalletConnectivityRequest::ObtainCrossCheckWalletRpcClients(reply_tx))
+            .await
+            .is_err()
+        {
+            return Vec::new();
+        }
+        reply_rx.await.unwrap_or_default()
+    }
+
+    async fn report_base_node_disputed(&mut self, node_id: NodeId) {
+        let _ = self
+            .sender
+            .send(WalletConnectivityRequest::ReportBaseNodeDisputed(node_id))
+            .await;
+    }
 }
@@ -63,13 +63,8 @@ impl ServiceInitializer for WalletConnectivityInitializer {
 
         context.spawn_until_shutdown(move |handles| {
             let connectivity = handles.expect_handle();
-            let service = WalletConnectivityService::new(
-                config,
-                receiver,
-                base_node_watch.get_receiver(),
-                online_status_watch,
-                connectivity,
-            );
+            let service =
+                WalletConnectivityService::new(config, receiver, base_node_watch, online_status_watch, connectivity);
             service.start()
         });
 
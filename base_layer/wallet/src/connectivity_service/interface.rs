@@ -21,6 +21,8 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::connectivity_service::OnlineStatus;
+use std::time::Duration;
+use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     peer_manager::{NodeId, Peer},
     protocol::rpc::RpcClientLease,
@@ -62,4 +64,20 @@ pub trait WalletConnectivityInterface: Clone + Send + Sync + 'static {
     fn get_current_base_node_id(&self) -> Option<NodeId>;
 
     fn is_base_node_set(&self) -> bool;
+
+    /// Report the latency and chain tip of a base node, which are used to rank it against the other base nodes in the
+    /// pool
+    async fn report_base_node_state(&mut self, node_id: NodeId, latency: Duration, chain_metadata: ChainMetadata);
+
+    /// Report a failed RPC request to a base node. Enough consecutive failures make the wallet fail over to another
+    /// base node.
+    async fn report_base_node_rpc_failure(&mut self, node_id: NodeId);
+
+    /// Obtain wallet RPC clients for the base nodes in the pool other than the current one, to cross-check the answers
+    /// of the current base node. Base nodes that cannot be reached in time are left out.
+    async fn obtain_cross_check_wallet_rpc_clients(&mut self) -> Vec<(NodeId, BaseNodeWalletRpcClient)>;
+
+    /// Report that the answers of a base node were contradicted by a majority of the other base nodes in the pool. If
+    /// it is the current base node the wallet fails over to another base node.
+    async fn report_base_node_disputed(&mut self, node_id: NodeId);
 }
@@ -24,6 +24,8 @@ use crate::{
     connectivity_service::{OnlineStatus, WalletConnectivityInterface},
     util::watch::Watch,
 };
+use std::time::Duration;
+use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     peer_manager::{NodeId, Peer},
     protocol::rpc::RpcClientLease,
@@ -42,6 +44,8 @@ pub struct WalletConnectivityMock {
     base_node_watch: Watch<Option<Peer>>,
     base_node_wallet_rpc_client: Watch<Option<RpcClientLease<BaseNodeWalletRpcClient>>>,
     base_node_sync_rpc_client: Watch<Option<RpcClientLease<BaseNodeSyncRpcClient>>>,
+    cross_check_wallet_rpc_clients: Watch<Vec<(NodeId, BaseNodeWalletRpcClient)>>,
+    disputed_base_nodes: Watch<Vec<NodeId>>,
 }
 
 impl WalletConnectivityMock {
@@ -51,6 +55,8 @@ impl WalletConnectivityMock {
             base_node_watch: Watch::new(None),
             base_node_wallet_rpc_client: Watch::new(None),
             base_node_sync_rpc_client: Watch::new(None),
+            cross_check_wallet_rpc_clients: Watch::new(Vec::new()),
+            disputed_base_nodes: Watch::new(Vec::new()),
         }
     }
 }
@@ -64,6 +70,14 @@ impl WalletConnectivityMock {
         self.base_node_sync_rpc_client.send(Some(RpcClientLease::new(client)));
     }
 
+    pub fn set_cross_check_wallet_rpc_clients(&self, clients: Vec<(NodeId, BaseNodeWalletRpcClient)>) {
+        self.cross_check_wallet_rpc_clients.send(clients);
+    }
+
+    pub fn get_disputed_base_nodes(&self) -> Vec<NodeId> {
+        self.disputed_base_nodes.borrow().clone()
+    }
+
     pub fn notify_base_node_set(&self, base_node_peer: Peer) {
         self.base_node_watch.send(Some(base_node_peer));
     }
@@ -129,4 +143,18 @@ impl WalletConnectivityInterface for WalletConnectivityMock {
     fn is_base_node_set(&self) -> bool {
         self.base_node_watch.borrow().is_some()
     }
+
+    async fn report_base_node_state(&mut self, _node_id: NodeId, _latency: Duration, _chain_metadata: ChainMetadata) {}
+
+    async fn report_base_node_rpc_failure(&mut self, _node_id: NodeId) {}
+
+    async fn obtain_cross_check_wallet_rpc_clients(&mut self) -> Vec<(NodeId, BaseNodeWalletRpcClient)> {
+        self.cross_check_wallet_rpc_clients.borrow().clone()
+    }
+
+    async fn report_base_node_disputed(&mut self, node_id: NodeId) {
+        let mut disputed = self.disputed_base_nodes.borrow().clone();
+        disputed.push(node_id);
+        self.disputed_base_nodes.send(disputed);
+    }
 }
@@ -22,11 +22,21 @@
 
 use crate::{
     base_node_service::config::BaseNodeServiceConfig,
-    connectivity_service::{error::WalletConnectivityError, handle::WalletConnectivityRequest},
+    connectivity_service::{
+        base_node_pool::BaseNodePool,
+        error::WalletConnectivityError,
+        handle::WalletConnectivityRequest,
+    },
     util::watch::Watch,
 };
+use futures::future;
 use log::*;
-use std::{mem, time::Duration};
+use std::{
+    convert::TryFrom,
+    mem,
+    time::{Duration, Instant},
+};
+use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     connectivity::ConnectivityRequester,
     peer_manager::{NodeId, Peer},
@@ -35,13 +45,19 @@ use tari_comms::{
 };
 use tari_core::base_node::{rpc::BaseNodeWalletRpcClient, sync::rpc::BaseNodeSyncRpcClient};
 use tokio::{
-    sync::{mpsc, oneshot, watch},
+    sync::{mpsc, oneshot},
+    task,
     time,
     time::MissedTickBehavior,
 };
 
 const LOG_TARGET: &str = "wallet::connectivity";
 
+/// The time allowed for a base node in the pool to answer a cross-check
+const CROSS_CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+type CrossCheckResults = Vec<(NodeId, Result<(Duration, ChainMetadata), WalletConnectivityError>)>;
+
 /// Connection status of the Base Node
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum OnlineStatus {
@@ -54,10 +70,13 @@ pub struct WalletConnectivityService {
     config: BaseNodeServiceConfig,
     request_receiver: mpsc::Receiver<WalletConnectivityRequest>,
     connectivity: ConnectivityRequester,
-    base_node_watch: watch::Receiver<Option<Peer>>,
+    base_node_watch: Watch<Option<Peer>>,
+    base_node_pool: BaseNodePool,
     pools: Option<ClientPoolContainer>,
     online_status_watch: Watch<OnlineStatus>,
     pending_requests: Vec<ReplyOneshot>,
+    cross_check_results: (mpsc::Sender<CrossCheckResults>, mpsc::Receiver<CrossCheckResults>),
+    last_cross_check: Instant,
 }
 
 struct ClientPoolContainer {
@@ -69,7 +88,7 @@ impl WalletConnectivityService {
     pub(super) fn new(
         config: BaseNodeServiceConfig,
         request_receiver: mpsc::Receiver<WalletConnectivityRequest>,
-        base_node_watch: watch::Receiver<Option<Peer>>,
+        base_node_watch: Watch<Option<Peer>>,
         online_status_watch: Watch<OnlineStatus>,
         connectivity: ConnectivityRequester,
     ) -> Self {
@@ -78,9 +97,12 @@ impl WalletConnectivityService {
             request_receiver,
             connectivity,
             base_node_watch,
+            base_node_pool: BaseNodePool::default(),
             pools: None,
             pending_requests: Vec::new(),
             online_status_watch,
+            cross_check_results: mpsc::channel(1),
+            last_cross_check: Instant::now(),
         }
     }
 
@@ -94,7 +116,7 @@ impl WalletConnectivityService {
                 // BIASED: select branches are in order of priority
                 biased;
 
-                Ok(_) = self.base_node_watch.changed() => {
+                _ = self.base_node_watch.changed() => {
                     if self.base_node_watch.borrow().is_some() {
                         // This will block the rest until the connection is established. This is what we want.
                         self.setup_base_node_connection().await;
@@ -105,6 +127,10 @@ impl WalletConnectivityService {
                     self.handle_request(req).await;
                 },
 
+                Some(results) = self.cross_check_results.1.recv() => {
+                    self.handle_cross_check_results(results);
+                },
+
                 _ = check_connection.tick() => {
                     self.check_connection().await;
                 }
@@ -119,6 +145,95 @@ impl WalletConnectivityService {
                 self.setup_base_node_connection().await;
             }
         }
+
+        if self.last_cross_check.elapsed() >= self.config.base_node_cross_check_interval &&
+            self.base_node_pool.len() > 1
+        {
+            self.start_cross_check();
+        }
+    }
+
+    /// Ask the other base nodes in the pool for their chain tip in the background. The answers are used to rank the
+    /// nodes and to check that the current base node is not lagging behind or hiding blocks.
+    fn start_cross_check(&mut self) {
+        self.last_cross_check = Instant::now();
+        let current = self.current_base_node();
+        let peers = self
+            .base_node_pool
+            .peers()
+            .filter(|p| Some(&p.node_id) != current.as_ref())
+            .map(|p| p.node_id.clone())
+            .collect::<Vec<_>>();
+        let connectivity = self.connectivity.clone();
+        let results_tx = self.cross_check_results.0.clone();
+        task::spawn(async move {
+            let results = future::join_all(peers.into_iter().map(|node_id| {
+                let connectivity = connectivity.clone();
+                async move {
+                    let result = time::timeout(CROSS_CHECK_TIMEOUT, get_base_node_tip(connectivity, node_id.clone()))
+                        .await
+                        .unwrap_or_else(|_| Err(WalletConnectivityError::CrossCheckFailed("timed out".to_string())));
+                    (node_id, result)
+                }
+            }))
+            .await;
+            let _ = results_tx.send(results).await;
+        });
+    }
+
+    fn handle_cross_check_results(&mut self, results: CrossCheckResults) {
+        for (node_id, result) in results {
+            match result {
+                Ok((latency, chain_metadata)) => {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Base node {} tip: {} latency: {:.2?}",
+                        node_id,
+                        chain_metadata.height_of_longest_chain(),
+                        latency
+                    );
+                    self.base_node_pool.record_success(&node_id, latency, chain_metadata);
+                },
+                Err(e) => {
+                    debug!(target: LOG_TARGET, "Base node {} cross-check failed: {}", node_id, e);
+                    self.base_node_pool.record_failure(&node_id);
+                },
+            }
+        }
+
+        if let Some(current) = self.current_base_node() {
+            let threshold = self.config.base_node_failover_threshold;
+            if self
+                .base_node_pool
+                .is_lagging(&current, threshold, self.config.base_node_max_tip_lag)
+            {
+                warn!(
+                    target: LOG_TARGET,
+                    "Base node {} is {} block(s) behind the chain tip a majority of the other base nodes report",
+                    current,
+                    self.base_node_pool.tip_lag(&current, threshold)
+                );
+                self.fail_over(&current);
+            }
+        }
+    }
+
+    /// Switch to the healthiest other base node in the pool. Returns false if there is no other base node.
+    fn fail_over(&mut self, from: &NodeId) -> bool {
+        match self
+            .base_node_pool
+            .select_failover(from, self.config.base_node_failover_threshold)
+        {
+            Some(peer) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failing over from base node {} to base node {}", from, peer.node_id
+                );
+                self.base_node_watch.send(Some(peer));
+                true
+            },
+            None => false,
+        }
     }
 
     async fn handle_request(&mut self, request: WalletConnectivityRequest) {
@@ -130,9 +245,85 @@ impl WalletConnectivityService {
             ObtainBaseNodeSyncRpcClient(reply) => {
                 self.handle_pool_request(reply.into()).await;
             },
+            SetBaseNodePool(peers) => {
+                self.base_node_pool.set_peers(peers);
+                let current = self.base_node_watch.borrow().clone();
+                match current {
+                    Some(peer) => self.base_node_pool.insert_peer(peer),
+                    None => {
+                        if let Some(peer) = self.base_node_pool.peers().next().cloned() {
+                            self.base_node_watch.send(Some(peer));
+                        }
+                    },
+                }
+            },
+            GetBaseNodePoolStatus(reply) => {
+                let _ = reply.send(self.base_node_pool.status());
+            },
+            ReportBaseNodeState(node_id, latency, chain_metadata) => {
+                self.base_node_pool.record_success(&node_id, latency, chain_metadata);
+            },
+            ReportBaseNodeRpcFailure(node_id) => {
+                let failures = self.base_node_pool.record_failure(&node_id);
+                if failures >= self.config.base_node_failover_threshold &&
+                    self.current_base_node().as_ref() == Some(&node_id)
+                {
+                    self.fail_over(&node_id);
+                }
+            },
+            ObtainCrossCheckWalletRpcClients(reply) => {
+                self.handle_obtain_cross_check_wallet_rpc_clients(reply);
+            },
+            ReportBaseNodeDisputed(node_id) => {
+                if self.current_base_node().as_ref() == Some(&node_id) {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Base node {} was contradicted by a majority of the other base nodes", node_id
+                    );
+                    self.fail_over(&node_id);
+                }
+            },
         }
     }
 
+    /// Connect to the other base nodes in the pool that are not failing in the background, so that the answers of the
+    /// current base node can be cross-checked without holding up the service
+    fn handle_obtain_cross_check_wallet_rpc_clients(
+        &mut self,
+        reply: oneshot::Sender<Vec<(NodeId, BaseNodeWalletRpcClient)>>,
+    ) {
+        let current = self.current_base_node();
+        let threshold = self.config.base_node_failover_threshold;
+        let peers = self
+            .base_node_pool
+            .status()
+            .into_iter()
+            .filter(|n| Some(&n.peer.node_id) != current.as_ref() && !n.is_failing(threshold))
+            .map(|n| n.peer.node_id)
+            .collect::<Vec<_>>();
+        let connectivity = self.connectivity.clone();
+        task::spawn(async move {
+            let clients = future::join_all(peers.into_iter().map(|node_id| {
+                let connectivity = connectivity.clone();
+                async move {
+                    match time::timeout(CROSS_CHECK_TIMEOUT, connect_wallet_rpc(connectivity, node_id.clone())).await {
+                        Ok(Ok(client)) => Some((node_id, client)),
+                        Ok(Err(e)) => {
+                            debug!(target: LOG_TARGET, "Could not connect to base node {}: {}", node_id, e);
+                            None
+                        },
+                        Err(_) => {
+                            debug!(target: LOG_TARGET, "Connecting to base node {} timed out", node_id);
+                            None
+                        },
+                    }
+                }
+            }))
+            .await;
+            let _ = reply.send(clients.into_iter().flatten().collect());
+        });
+    }
+
     async fn handle_pool_request(&mut self, reply: ReplyOneshot) {
         use ReplyOneshot::*;
         match reply {
@@ -208,8 +399,13 @@ impl WalletConnectivityService {
     async fn setup_base_node_connection(&mut self) {
         self.pools = None;
         loop {
-            let node_id = match self.current_base_node() {
-                Some(n) => n,
+            let peer = self.base_node_watch.borrow().clone();
+            let node_id = match peer {
+                Some(peer) => {
+                    let node_id = peer.node_id.clone();
+                    self.base_node_pool.insert_peer(peer);
+                    node_id
+                },
                 None => {
                     self.set_online_status(OnlineStatus::Offline);
                     return;
@@ -234,12 +430,17 @@ impl WalletConnectivityService {
                     continue;
                 },
                 Err(e) => {
+                    warn!(target: LOG_TARGET, "{}", e);
+                    let failures = self.base_node_pool.record_failure(&node_id);
+                    if failures >= self.config.base_node_failover_threshold && self.fail_over(&node_id) {
+                        self.set_online_status(OnlineStatus::Connecting);
+                        continue;
+                    }
                     if self.current_base_node() != Some(node_id) {
                         self.set_online_status(OnlineStatus::Connecting);
                     } else {
                         self.set_online_status(OnlineStatus::Offline);
                     }
-                    warn!(target: LOG_TARGET, "{}", e);
                     time::sleep(self.config.base_node_monitor_refresh_interval).await;
                     continue;
                 },
@@ -298,6 +499,36 @@ impl WalletConnectivityService {
     }
 }
 
+/// Connect a wallet RPC client to a base node other than the current one
+async fn connect_wallet_rpc(
+    mut connectivity: ConnectivityRequester,
+    node_id: NodeId,
+) -> Result<BaseNodeWalletRpcClient, WalletConnectivityError> {
+    let mut conn = connectivity.dial_peer(node_id).await?;
+    conn.connect_rpc::<BaseNodeWalletRpcClient>()
+        .await
+        .map_err(|e| WalletConnectivityError::CrossCheckFailed(e.to_string()))
+}
+
+/// Get the chain tip of a base node and the time it took to answer
+async fn get_base_node_tip(
+    connectivity: ConnectivityRequester,
+    node_id: NodeId,
+) -> Result<(Duration, ChainMetadata), WalletConnectivityError> {
+    let mut client = connect_wallet_rpc(connectivity, node_id).await?;
+    let start = Instant::now();
+    let tip_info = client
+        .get_tip_info()
+        .await
+        .map_err(|e| WalletConnectivityError::CrossCheckFailed(e.to_string()))?;
+    let latency = start.elapsed();
+    let chain_metadata = tip_info
+        .metadata
+        .ok_or_else(|| WalletConnectivityError::CrossCheckFailed("Tip info has no metadata".to_string()))
+        .and_then(|m| ChainMetadata::try_from(m).map_err(WalletConnectivityError::CrossCheckFailed))?;
+    Ok((latency, chain_metadata))
+}
+
 enum ReplyOneshot {
     WalletRpc(oneshot::Sender<RpcClientLease<BaseNodeWalletRpcClient>>),
     SyncRpc(oneshot::Sender<RpcClientLease<BaseNodeSyncRpcClient>>),
@@ -20,14 +20,16 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::service::WalletConnectivityService;
+use super::{base_node_pool::BaseNodePool, service::WalletConnectivityService};
 use crate::{
+    base_node_service::config::BaseNodeServiceConfig,
     connectivity_service::{OnlineStatus, WalletConnectivityHandle, WalletConnectivityInterface},
     util::watch::Watch,
 };
 use core::convert;
 use futures::future;
-use std::{iter, sync::Arc};
+use std::{iter, sync::Arc, time::Duration};
+use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     peer_manager::PeerFeatures,
     protocol::rpc::{
@@ -51,6 +53,17 @@ async fn setup() -> (
     MockRpcServer<MockRpcImpl>,
     ConnectivityManagerMockState,
     Shutdown,
+) {
+    setup_with_config(Default::default()).await
+}
+
+async fn setup_with_config(
+    config: BaseNodeServiceConfig,
+) -> (
+    WalletConnectivityHandle,
+    MockRpcServer<MockRpcImpl>,
+    ConnectivityManagerMockState,
+    Shutdown,
 ) {
     let (tx, rx) = mpsc::channel(1);
     let base_node_watch = Watch::new(None);
@@ -59,13 +72,8 @@ async fn setup() -> (
     let (connectivity, mock) = create_connectivity_mock();
     let mock_state = mock.spawn();
     // let peer_manager = create_peer_manager(tempdir().unwrap());
-    let service = WalletConnectivityService::new(
-        Default::default(),
-        rx,
-        base_node_watch.get_receiver(),
-        online_status_watch,
-        connectivity,
-    );
+    let service =
+        WalletConnectivityService::new(config, rx, base_node_watch.clone(), online_status_watch, connectivity);
     let shutdown = spawn_until_shutdown(service.start());
 
     let mock_svc = MockRpcImpl::new();
@@ -237,3 +245,130 @@ async fn it_gracefully_handles_multiple_connection_failures() {
     // Still able to get a base node rpc client
     pending_request.await.unwrap();
 }
+
+#[tokio::test]
+async fn it_fails_over_to_the_next_base_node_in_the_pool() {
+    let config = BaseNodeServiceConfig {
+        base_node_monitor_refresh_interval: Duration::from_millis(10),
+        base_node_failover_threshold: 2,
+        ..Default::default()
+    };
+    let (mut handle, mock_server, mock_state, _shutdown) = setup_with_config(config).await;
+    let base_node_peer1 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let base_node_peer2 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    // Only the second base node is online
+    let conn2 = mock_server.create_mockimpl_connection(base_node_peer2.to_peer()).await;
+    mock_state.add_active_connection(conn2).await;
+
+    handle
+        .set_base_node_pool(vec![base_node_peer1.to_peer(), base_node_peer2.to_peer()])
+        .await
+        .unwrap();
+
+    let rpc_client = handle.obtain_base_node_wallet_rpc_client().await.unwrap();
+    assert!(rpc_client.is_connected());
+    assert_eq!(handle.get_current_base_node_id().unwrap(), *base_node_peer2.node_id());
+    assert!(mock_state.count_calls_containing("DialPeer").await >= 3);
+
+    let status = handle.get_base_node_pool_status().await.unwrap();
+    assert_eq!(status.len(), 2);
+    assert_eq!(status[0].peer.node_id, *base_node_peer1.node_id());
+    assert_eq!(status[0].consecutive_failures, 2);
+    assert_eq!(status[1].consecutive_failures, 0);
+}
+
+fn chain_metadata(height: u64, accumulated_difficulty: u128) -> ChainMetadata {
+    ChainMetadata::new(height, vec![0; 32], 0, 0, accumulated_difficulty)
+}
+
+#[test]
+fn base_node_pool_ranks_failover_candidates() {
+    let peers = (0..3)
+        .map(|_| build_node_identity(PeerFeatures::COMMUNICATION_NODE).to_peer())
+        .collect::<Vec<_>>();
+    let mut pool = BaseNodePool::default();
+    pool.set_peers(peers.clone());
+
+    // With nothing known about the nodes, the order of the pool is used
+    assert_eq!(
+        pool.select_failover(&peers[0].node_id, 3).unwrap().node_id,
+        peers[1].node_id
+    );
+
+    // A failing node is avoided
+    pool.record_failure(&peers[1].node_id);
+    assert_eq!(
+        pool.select_failover(&peers[0].node_id, 3).unwrap().node_id,
+        peers[2].node_id
+    );
+
+    // A node that answers quicker is preferred
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(100, 1000));
+    pool.record_success(&peers[2].node_id, Duration::from_millis(50), chain_metadata(100, 1000));
+    assert_eq!(
+        pool.select_failover(&peers[0].node_id, 3).unwrap().node_id,
+        peers[1].node_id
+    );
+
+    // A node that lags behind the best tip is avoided
+    pool.record_success(&peers[2].node_id, Duration::from_millis(50), chain_metadata(110, 1100));
+    assert_eq!(
+        pool.select_failover(&peers[0].node_id, 3).unwrap().node_id,
+        peers[2].node_id
+    );
+}
+
+#[test]
+fn base_node_pool_detects_lagging_base_node() {
+    let peers = (0..2)
+        .map(|_| build_node_identity(PeerFeatures::COMMUNICATION_NODE).to_peer())
+        .collect::<Vec<_>>();
+    let mut pool = BaseNodePool::default();
+    pool.set_peers(peers.clone());
+
+    pool.record_success(&peers[0].node_id, Duration::from_millis(10), chain_metadata(100, 1000));
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(104, 1040));
+    assert!(!pool.is_lagging(&peers[0].node_id, 3, 5));
+
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(110, 1100));
+    assert!(pool.is_lagging(&peers[0].node_id, 3, 5));
+    assert!(!pool.is_lagging(&peers[1].node_id, 3, 5));
+
+    // A higher tip without the accumulated work to back it up is not trusted
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(200, 900));
+    assert!(!pool.is_lagging(&peers[0].node_id, 3, 5));
+
+    // Tips reported by failing nodes are ignored
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(110, 1100));
+    for _ in 0..3 {
+        pool.record_failure(&peers[1].node_id);
+    }
+    assert!(!pool.is_lagging(&peers[0].node_id, 3, 5));
+}
+
+#[test]
+fn base_node_pool_requires_majority_to_detect_lagging() {
+    let peers = (0..4)
+        .map(|_| build_node_identity(PeerFeatures::COMMUNICATION_NODE).to_peer())
+        .collect::<Vec<_>>();
+    let mut pool = BaseNodePool::default();
+    pool.set_peers(peers.clone());
+
+    pool.record_success(&peers[0].node_id, Duration::from_millis(10), chain_metadata(100, 1000));
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(101, 1010));
+    pool.record_success(&peers[2].node_id, Duration::from_millis(10), chain_metadata(100, 1000));
+    // A single node claiming a tip far ahead cannot make the others look like they lag
+    pool.record_success(&peers[3].node_id, Duration::from_millis(10), chain_metadata(500, 5000));
+    assert!(!pool.is_lagging(&peers[0].node_id, 3, 5));
+    assert_eq!(pool.tip_lag(&peers[0].node_id, 3), 1);
+    assert_eq!(
+        pool.select_failover(&peers[2].node_id, 3).unwrap().node_id,
+        peers[1].node_id
+    );
+
+    // Once a majority of the other nodes agree the node lags behind, it is lagging
+    pool.record_success(&peers[1].node_id, Duration::from_millis(10), chain_metadata(110, 1100));
+    pool.record_success(&peers[2].node_id, Duration::from_millis(10), chain_metadata(108, 1080));
+    assert!(pool.is_lagging(&peers[0].node_id, 3, 5));
+    assert_eq!(pool.tip_lag(&peers[0].node_id, 3), 10);
+}
@@ -77,6 +77,7 @@ macro_rules! fetch {
     }};
 }
 
+#[derive(Clone)]
 pub struct ContactsDatabase<T>
 where T: ContactsBackend
 {
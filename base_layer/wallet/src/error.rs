@@ -22,10 +22,11 @@
 
 use crate::{
     base_node_service::error::BaseNodeServiceError,
-    contacts_service::error::ContactsServiceError,
-    output_manager_service::error::OutputManagerError,
+    connectivity_service::WalletConnectivityError,
+    contacts_service::error::{ContactsServiceError, ContactsServiceStorageError},
+    output_manager_service::error::{OutputManagerError, OutputManagerStorageError},
     storage::database::DbKey,
-    transaction_service::error::TransactionServiceError,
+    transaction_service::error::{TransactionServiceError, TransactionStorageError},
     utxo_scanner_service::error::UtxoScannerError,
 };
 use diesel::result::Error as DieselError;
@@ -83,6 +84,10 @@ pub enum WalletError {
     ByteArrayError(#[from] tari_crypto::tari_utilities::ByteArrayError),
     #[error("Utxo Scanner Error: {0}")]
     UtxoScannerError(#[from] UtxoScannerError),
+    #[error("Wallet backup error: `{0}`")]
+    WalletBackupError(#[from] WalletBackupError),
+    #[error("Wallet connectivity error: `{0}`")]
+    WalletConnectivityError(#[from] WalletConnectivityError),
 }
 
 #[derive(Debug, Error)]
@@ -139,6 +144,40 @@ pub enum WalletStorageError {
     NoPasswordError,
     #[error("Incorrect password provided for encrypted wallet")]
     IncorrectPassword,
+    #[error("Key derivation error: `{0}`")]
+    KeyDerivationError(String),
+    #[error("Could not re-encrypt the database: `{0}`")]
+    ReencryptionError(String),
     #[error("Deprecated operation error")]
     DeprecatedOperation,
 }
+
+#[derive(Debug, Error)]
+pub enum WalletBackupError {
+    #[error("Unsupported wallet backup version: `{0}`")]
+    UnsupportedVersion(u32),
+    #[error("Wallet backup checksum does not match, the backup is damaged")]
+    ChecksumMismatch,
+    #[error("Incorrect passphrase provided for wallet backup")]
+    IncorrectPassphrase,
+    #[error("Wallet backup encryption error: `{0}`")]
+    EncryptionError(String),
+    #[error("The wallet has no master secret key to back up")]
+    MissingMasterSecretKey,
+    #[error("A wallet already exists, a backup can only be restored into a new wallet")]
+    WalletAlreadyExists,
+    #[error("Wallet storage error: `{0}`")]
+    WalletStorageError(#[from] WalletStorageError),
+    #[error("Output manager storage error: `{0}`")]
+    OutputManagerStorageError(#[from] OutputManagerStorageError),
+    #[error("Transaction storage error: `{0}`")]
+    TransactionStorageError(#[from] TransactionStorageError),
+    #[error("Contacts storage error: `{0}`")]
+    ContactsServiceStorageError(#[from] ContactsServiceStorageError),
+    #[error("Serde json error: `{0}`")]
+    SerdeJsonError(#[from] SerdeJsonError),
+    #[error("Hex error: `{0}`")]
+    HexError(#[from] HexError),
+    #[error("IO Error: `{0}`")]
+    IoError(#[from] std::io::Error),
+}
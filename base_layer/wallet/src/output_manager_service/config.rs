@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::time::Duration;
+use tari_core::transactions::tari_amount::MicroTari;
 use tari_key_manager::mnemonic::MnemonicLanguage;
 
 #[derive(Clone, Debug)]
@@ -33,6 +34,7 @@ pub struct OutputManagerServiceConfig {
     pub event_channel_size: usize,
     pub num_confirmations_required: u64,
     pub tx_validator_batch_size: usize,
+    pub utxo_consolidation: UtxoConsolidationConfig,
 }
 
 impl Default for OutputManagerServiceConfig {
@@ -46,6 +48,44 @@ impl Default for OutputManagerServiceConfig {
             event_channel_size: 250,
             num_confirmations_required: 3,
             tx_validator_batch_size: 100,
+            utxo_consolidation: UtxoConsolidationConfig::default(),
+        }
+    }
+}
+
+/// The policy used by the background task that merges the smallest unspent outputs into pay-to-self transactions
+#[derive(Clone, Debug)]
+pub struct UtxoConsolidationConfig {
+    /// Whether the consolidation task runs at all
+    pub enabled: bool,
+    /// Log and publish the consolidation transactions that would be created without creating them
+    pub dry_run: bool,
+    /// The period between consolidation runs, which limits how often consolidation transactions are created
+    pub interval: Duration,
+    /// Consolidation only starts once the wallet holds more spendable outputs than this
+    pub utxo_count_threshold: usize,
+    /// The maximum number of outputs merged in a single transaction
+    pub max_inputs_per_transaction: usize,
+    /// The maximum number of consolidation transactions created in a single run
+    pub max_transactions_per_run: usize,
+    /// The fee per gram used for consolidation transactions
+    pub fee_per_gram: MicroTari,
+    /// Consolidation only runs while the base node's estimate of the fee per gram needed to be included in the next
+    /// block is at most this, i.e. while fees are low. When not set, `fee_per_gram` is used.
+    pub max_network_fee_per_gram: Option<MicroTari>,
+}
+
+impl Default for UtxoConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dry_run: false,
+            interval: Duration::from_secs(60 * 60),
+            utxo_count_threshold: 500,
+            max_inputs_per_transaction: 100,
+            max_transactions_per_run: 1,
+            fee_per_gram: MicroTari::from(5),
+            max_network_fee_per_gram: None,
         }
     }
 }
@@ -22,6 +22,7 @@
 
 use crate::base_node_service::error::BaseNodeServiceError;
 use diesel::result::Error as DieselError;
+use serde_json::Error as SerdeJsonError;
 use tari_comms::{connectivity::ConnectivityError, peer_manager::node_id::NodeIdError, protocol::rpc::RpcError};
 use tari_comms_dht::outbound::DhtOutboundError;
 use tari_core::transactions::{
@@ -118,6 +119,10 @@ pub enum OutputManagerError {
     },
     #[error("Invalid message received:{0}")]
     InvalidMessageError(String),
+    #[error("HTLC error: `{0}`")]
+    HtlcError(#[from] HtlcError),
+    #[error("Account not found: `{0}`")]
+    AccountNotFound(String),
 }
 
 #[derive(Debug, Error, PartialEq)]
@@ -166,6 +171,8 @@ pub enum OutputManagerStorageError {
     AeadError(String),
     #[error("Tari script error : {0}")]
     ScriptError(#[from] ScriptError),
+    #[error("An account named `{0}` already exists")]
+    DuplicateAccount(String),
 }
 
 /// This error type is used to return OutputManagerError from inside a Output Manager Service protocol but also
@@ -200,3 +207,35 @@ impl<TRes, TErr: Into<OutputManagerError>> OutputManagerProtocolErrorExt<TRes> f
         }
     }
 }
+
+#[derive(Debug, Error)]
+pub enum HtlcError {
+    #[error("IO error: `{0}`")]
+    IoError(#[from] std::io::Error),
+    #[error("Serde json error: `{0}`")]
+    SerdeJsonError(#[from] SerdeJsonError),
+    #[error("The HTLC details do not contain a format version")]
+    MissingVersion,
+    #[error("Unsupported HTLC details version: `{0}`")]
+    UnsupportedVersion(u64),
+    #[error("HTLC preimages must be 32 bytes long, got `{0}` bytes")]
+    InvalidPreimageLength(usize),
+    #[error("HTLC hash locks must be 32 bytes long")]
+    InvalidHashLock,
+    #[error("HTLC `{0}` not found")]
+    HtlcNotFound(String),
+    #[error("This wallet is not the recipient of the HTLC")]
+    NotTheRecipient,
+    #[error("The HTLC commitment does not match its details")]
+    CommitmentMismatch,
+    #[error("The HTLC is not spendable in its current state: `{0}`")]
+    NotSpendable(String),
+    #[error("The preimage for the HTLC hash lock is not known")]
+    PreimageUnknown,
+    #[error("The HTLC timeout at height `{0}` has passed and it can no longer be claimed")]
+    TimeoutPassed(u64),
+    #[error("The HTLC can only be refunded from height `{0}`")]
+    TimeoutNotReached(u64),
+    #[error("The HTLC value does not cover the fee to spend it")]
+    ValueTooSmall,
+}
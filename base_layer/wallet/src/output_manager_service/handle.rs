@@ -20,18 +20,24 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::{
-    error::OutputManagerError,
-    service::Balance,
-    storage::models::KnownOneSidedPaymentScript,
-    TxId,
+use crate::{
+    output_manager_service::{
+        error::OutputManagerError,
+        htlc::HtlcDetails,
+        service::Balance,
+        storage::models::{Account, AccountId, HtlcOutput, KnownOneSidedPaymentScript, DEFAULT_ACCOUNT_ID},
+        ConsolidationBatch,
+        TxId,
+    },
+    transaction_service::offline_signing::{OfflineInput, UnsignedTransaction},
 };
 use aes_gcm::Aes256Gcm;
+use serde::Serialize;
 use std::{fmt, sync::Arc};
-use tari_common_types::types::PublicKey;
+use tari_common_types::types::{Commitment, PrivateKey, PublicKey};
 use tari_core::transactions::{
     tari_amount::MicroTari,
-    transaction::{Transaction, TransactionOutput, UnblindedOutput},
+    transaction::{OutputFeatures, Transaction, TransactionOutput, UnblindedOutput},
     transaction_protocol::sender::TransactionSenderMessage,
     ReceiverTransactionProtocol,
     SenderTransactionProtocol,
@@ -50,8 +56,20 @@ pub enum OutputManagerRequest {
     GetRecipientTransaction(TransactionSenderMessage),
     GetCoinbaseTransaction((u64, MicroTari, MicroTari, u64)),
     ConfirmPendingTransaction(u64),
-    PrepareToSendTransaction((TxId, MicroTari, MicroTari, Option<u64>, String, TariScript)),
+    PrepareToSendTransaction(
+        (
+            TxId,
+            MicroTari,
+            MicroTari,
+            Option<u64>,
+            String,
+            TariScript,
+            OutputFeatures,
+            AccountId,
+        ),
+    ),
     CreatePayToSelfTransaction((TxId, MicroTari, MicroTari, Option<u64>, String)),
+    CreateAccountTransferTransaction((TxId, AccountId, AccountId, MicroTari, MicroTari, Option<u64>, String)),
     CancelTransaction(u64),
     GetSpentOutputs,
     GetUnspentOutputs,
@@ -68,6 +86,21 @@ pub enum OutputManagerRequest {
     AddKnownOneSidedPaymentScript(KnownOneSidedPaymentScript),
     ReinstateCancelledInboundTx(TxId),
     SetCoinbaseAbandoned(TxId, bool),
+    PrepareOfflineTransaction((TxId, MicroTari, MicroTari)),
+    SignOfflineTransaction(Box<UnsignedTransaction>),
+    ConfirmOfflineTransaction((TxId, Box<Transaction>, Option<u64>)),
+    GetNextScriptKey,
+    GetScriptKeyAtIndex(u64),
+    AddHtlc(Box<HtlcOutput>),
+    ImportHtlc((Box<HtlcDetails>, PrivateKey, TxId, MicroTari)),
+    GetHtlcs,
+    CreateHtlcPreimage,
+    AddHtlcPreimage(Vec<u8>),
+    CreateHtlcSpendTransaction((Commitment, u64)),
+    CreateAccount(String),
+    GetAccounts,
+    GetAccountBalance(AccountId),
+    GetAccountTransactionIds(AccountId),
 }
 
 impl fmt::Display for OutputManagerRequest {
@@ -86,8 +119,15 @@ impl fmt::Display for OutputManagerRequest {
             ),
             GetRecipientTransaction(_) => write!(f, "GetRecipientTransaction"),
             ConfirmPendingTransaction(v) => write!(f, "ConfirmPendingTransaction ({})", v),
-            PrepareToSendTransaction((_, _, _, _, msg, _)) => write!(f, "PrepareToSendTransaction ({})", msg),
+            PrepareToSendTransaction((_, _, _, _, msg, _, _, account_id)) => {
+                write!(f, "PrepareToSendTransaction ({}: {})", account_id, msg)
+            },
             CreatePayToSelfTransaction((_, _, _, _, msg)) => write!(f, "CreatePayToSelfTransaction ({})", msg),
+            CreateAccountTransferTransaction((_, from, to, amount, _, _, _)) => write!(
+                f,
+                "CreateAccountTransferTransaction ({} from {} to {})",
+                amount, from, to
+            ),
             CancelTransaction(v) => write!(f, "CancelTransaction ({})", v),
             GetSpentOutputs => write!(f, "GetSpentOutputs"),
             GetUnspentOutputs => write!(f, "GetUnspentOutputs"),
@@ -105,6 +145,28 @@ impl fmt::Display for OutputManagerRequest {
             AddKnownOneSidedPaymentScript(_) => write!(f, "AddKnownOneSidedPaymentScript"),
             ReinstateCancelledInboundTx(_) => write!(f, "ReinstateCancelledInboundTx"),
             SetCoinbaseAbandoned(_, _) => write!(f, "SetCoinbaseAbandoned"),
+            PrepareOfflineTransaction((tx_id, amount, _)) => {
+                write!(f, "PrepareOfflineTransaction ({}: {})", tx_id, amount)
+            },
+            SignOfflineTransaction(v) => write!(f, "SignOfflineTransaction ({})", v.tx_id),
+            ConfirmOfflineTransaction((tx_id, _, _)) => write!(f, "ConfirmOfflineTransaction ({})", tx_id),
+            GetNextScriptKey => write!(f, "GetNextScriptKey"),
+            GetScriptKeyAtIndex(index) => write!(f, "GetScriptKeyAtIndex ({})", index),
+            AddHtlc(v) => write!(f, "AddHtlc ({}: {})", v.tx_id, v.commitment.to_hex()),
+            ImportHtlc((v, _, tx_id, _)) => write!(f, "ImportHtlc ({}: {})", tx_id, v.commitment.to_hex()),
+            GetHtlcs => write!(f, "GetHtlcs"),
+            CreateHtlcPreimage => write!(f, "CreateHtlcPreimage"),
+            AddHtlcPreimage(_) => write!(f, "AddHtlcPreimage"),
+            CreateHtlcSpendTransaction((commitment, tip_height)) => write!(
+                f,
+                "CreateHtlcSpendTransaction ({} at height {})",
+                commitment.to_hex(),
+                tip_height
+            ),
+            CreateAccount(name) => write!(f, "CreateAccount ({})", name),
+            GetAccounts => write!(f, "GetAccounts"),
+            GetAccountBalance(account_id) => write!(f, "GetAccountBalance ({})", account_id),
+            GetAccountTransactionIds(account_id) => write!(f, "GetAccountTransactionIds ({})", account_id),
         }
     }
 }
@@ -138,19 +200,33 @@ pub enum OutputManagerResponse {
     AddKnownOneSidedPaymentScript,
     ReinstatedCancelledInboundTx,
     CoinbaseAbandonedSet,
+    OfflineTransactionInputs((Vec<OfflineInput>, Option<u64>)),
+    OfflineTransactionConfirmed,
+    ScriptKey((u64, PrivateKey)),
+    HtlcAdded,
+    HtlcImported(Box<HtlcOutput>),
+    Htlcs(Vec<HtlcOutput>),
+    HtlcPreimage(([u8; 32], Vec<u8>)),
+    HtlcPreimageAdded([u8; 32]),
+    AccountCreated(Account),
+    Accounts(Vec<Account>),
+    AccountTransactionIds(Vec<TxId>),
 }
 
 pub type OutputManagerEventSender = broadcast::Sender<Arc<OutputManagerEvent>>;
 pub type OutputManagerEventReceiver = broadcast::Receiver<Arc<OutputManagerEvent>>;
 
 /// Events that can be published on the Output Manager Service Event Stream
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub enum OutputManagerEvent {
     TxoValidationTimedOut(u64),
     TxoValidationSuccess(u64),
     TxoValidationFailure(u64),
     TxoValidationAborted(u64),
     TxoValidationDelayed(u64),
+    /// The consolidation transactions that would have been created if dry-run mode was off
+    UtxoConsolidationPlanned(Vec<ConsolidationBatch>),
+    UtxoConsolidationCreated(TxId),
     Error(String),
 }
 
@@ -264,6 +340,7 @@ impl OutputManagerHandle {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn prepare_transaction_to_send(
         &mut self,
         tx_id: TxId,
@@ -272,6 +349,34 @@ impl OutputManagerHandle {
         lock_height: Option<u64>,
         message: String,
         recipient_script: TariScript,
+        recipient_output_features: OutputFeatures,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError> {
+        self.prepare_account_transaction_to_send(
+            DEFAULT_ACCOUNT_ID,
+            tx_id,
+            amount,
+            fee_per_gram,
+            lock_height,
+            message,
+            recipient_script,
+            recipient_output_features,
+        )
+        .await
+    }
+
+    /// Prepare a transaction that is funded from the outputs of the specified account, with any change returned to
+    /// that account
+    #[allow(clippy::too_many_arguments)]
+    pub async fn prepare_account_transaction_to_send(
+        &mut self,
+        account_id: AccountId,
+        tx_id: TxId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+        recipient_script: TariScript,
+        recipient_output_features: OutputFeatures,
     ) -> Result<SenderTransactionProtocol, OutputManagerError> {
         match self
             .handle
@@ -282,6 +387,8 @@ impl OutputManagerHandle {
                 lock_height,
                 message,
                 recipient_script,
+                recipient_output_features,
+                account_id,
             )))
             .await??
         {
@@ -290,6 +397,84 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Select and encumber the inputs for a transaction that will be signed by an offline signer. Returns the inputs
+    /// and the key index reserved for the change output, if change is required.
+    pub async fn prepare_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+    ) -> Result<(Vec<OfflineInput>, Option<u64>), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::PrepareOfflineTransaction((
+                tx_id,
+                amount,
+                fee_per_gram,
+            )))
+            .await??
+        {
+            OutputManagerResponse::OfflineTransactionInputs(v) => Ok(v),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Build the Sender Transaction Protocol for an unsigned offline transaction using keys derived from the seed.
+    pub async fn sign_offline_transaction(
+        &mut self,
+        unsigned: UnsignedTransaction,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::SignOfflineTransaction(Box::new(unsigned)))
+            .await??
+        {
+            OutputManagerResponse::TransactionToSend(stp) => Ok(stp),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Add the change output of a signed offline transaction, if the signer produced one
+    pub async fn confirm_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        transaction: Transaction,
+        change_key_index: Option<u64>,
+    ) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ConfirmOfflineTransaction((
+                tx_id,
+                Box::new(transaction),
+                change_key_index,
+            )))
+            .await??
+        {
+            OutputManagerResponse::OfflineTransactionConfirmed => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Reserve the next index of the script key chain and return it with its key
+    pub async fn get_next_script_key(&mut self) -> Result<(u64, PrivateKey), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetNextScriptKey).await?? {
+            OutputManagerResponse::ScriptKey(v) => Ok(v),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Derive the script key at the specified index of the script key chain
+    pub async fn get_script_key_at_index(&mut self, index: u64) -> Result<PrivateKey, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetScriptKeyAtIndex(index))
+            .await??
+        {
+            OutputManagerResponse::ScriptKey((_, key)) => Ok(key),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     /// Get a fee estimate for an amount of MicroTari, at a specified fee per gram and given number of kernels and
     /// outputs.
     pub async fn fee_estimate(
@@ -480,6 +665,79 @@ impl OutputManagerHandle {
         }
     }
 
+    /// Create a transaction that moves funds from one account of this wallet to another. Returns the fee and the
+    /// transaction.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_account_transfer_transaction(
+        &mut self,
+        tx_id: TxId,
+        from_account_id: AccountId,
+        to_account_id: AccountId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        lock_height: Option<u64>,
+        message: String,
+    ) -> Result<(MicroTari, Transaction), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateAccountTransferTransaction((
+                tx_id,
+                from_account_id,
+                to_account_id,
+                amount,
+                fee_per_gram,
+                lock_height,
+                message,
+            )))
+            .await??
+        {
+            OutputManagerResponse::PayToSelfTransaction(outputs) => Ok(outputs),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a new named account with its own key chains
+    pub async fn create_account(&mut self, name: String) -> Result<Account, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::CreateAccount(name)).await?? {
+            OutputManagerResponse::AccountCreated(account) => Ok(account),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// List the accounts of this wallet, starting with the default account
+    pub async fn get_accounts(&mut self) -> Result<Vec<Account>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetAccounts).await?? {
+            OutputManagerResponse::Accounts(accounts) => Ok(accounts),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_account_balance(&mut self, account_id: AccountId) -> Result<Balance, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAccountBalance(account_id))
+            .await??
+        {
+            OutputManagerResponse::Balance(b) => Ok(b),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// The ids of the transactions that created or spent outputs of the specified account
+    pub async fn get_account_transaction_ids(
+        &mut self,
+        account_id: AccountId,
+    ) -> Result<Vec<TxId>, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::GetAccountTransactionIds(account_id))
+            .await??
+        {
+            OutputManagerResponse::AccountTransactionIds(tx_ids) => Ok(tx_ids),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn reinstate_cancelled_inbound_transaction_outputs(
         &mut self,
         tx_id: TxId,
@@ -504,4 +762,85 @@ impl OutputManagerHandle {
             _ => Err(OutputManagerError::UnexpectedApiResponse),
         }
     }
+
+    pub async fn add_htlc(&mut self, htlc: HtlcOutput) -> Result<(), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::AddHtlc(Box::new(htlc)))
+            .await??
+        {
+            OutputManagerResponse::HtlcAdded => Ok(()),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Import an HTLC output sent to this wallet. `recipient_private_key` is the secret key of the comms public key the
+    /// HTLC was sent to.
+    pub async fn import_htlc(
+        &mut self,
+        details: HtlcDetails,
+        recipient_private_key: PrivateKey,
+        tx_id: TxId,
+        fee_per_gram: MicroTari,
+    ) -> Result<HtlcOutput, OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::ImportHtlc((
+                Box::new(details),
+                recipient_private_key,
+                tx_id,
+                fee_per_gram,
+            )))
+            .await??
+        {
+            OutputManagerResponse::HtlcImported(htlc) => Ok(*htlc),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_htlcs(&mut self) -> Result<Vec<HtlcOutput>, OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::GetHtlcs).await?? {
+            OutputManagerResponse::Htlcs(htlcs) => Ok(htlcs),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Generate and store a new random preimage, returning its hash lock and the preimage
+    pub async fn create_htlc_preimage(&mut self) -> Result<([u8; 32], Vec<u8>), OutputManagerError> {
+        match self.handle.call(OutputManagerRequest::CreateHtlcPreimage).await?? {
+            OutputManagerResponse::HtlcPreimage(v) => Ok(v),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Store a preimage learnt from a counterparty, returning its hash lock
+    pub async fn add_htlc_preimage(&mut self, preimage: Vec<u8>) -> Result<[u8; 32], OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::AddHtlcPreimage(preimage))
+            .await??
+        {
+            OutputManagerResponse::HtlcPreimageAdded(hash_lock) => Ok(hash_lock),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a transaction that claims or refunds an HTLC output into this wallet, depending on this wallet's role
+    /// and the current tip height. Returns the TxId, the transaction, its fee and the value of the HTLC.
+    pub async fn create_htlc_spend_transaction(
+        &mut self,
+        commitment: Commitment,
+        tip_height: u64,
+    ) -> Result<(u64, Transaction, MicroTari, MicroTari), OutputManagerError> {
+        match self
+            .handle
+            .call(OutputManagerRequest::CreateHtlcSpendTransaction((
+                commitment, tip_height,
+            )))
+            .await??
+        {
+            OutputManagerResponse::Transaction(v) => Ok(v),
+            _ => Err(OutputManagerError::UnexpectedApiResponse),
+        }
+    }
 }
@@ -0,0 +1,231 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Hash time-locked contract (HTLC) outputs, as used by cross-chain atomic swaps.
+//!
+//! An HTLC output can be claimed by the recipient with the preimage of a SHA256 hash lock before the timeout height,
+//! or refunded by the sender from the timeout height onwards:
+//!
+//! `CheckHeight(H) LtZero IfThen HashSha256 PushHash(hash_lock) EqualVerify PushPubKey(K_r) Else PushPubKey(K_s)
+//! EndIf`
+//!
+//! The claim is spent with the preimage as the only item on the input stack and the refund with an empty input stack.
+//! Base nodes evaluate `CheckHeight` against the height of the block that mines the spending transaction, so the
+//! timeout is enforced by consensus. The refund transaction also carries a kernel lock height of `H` so that the
+//! mempool does not hold it before the timeout.
+//!
+//! The spending key of the output is derived from a Diffie-Hellman shared secret between the sender offset key and
+//! the recipient's public key, in the same way as one-sided payments, so both parties can open the output. The sender
+//! hands the public details of the output to the recipient as [HtlcDetails].
+
+use crate::output_manager_service::{error::HtlcError, storage::models::HtlcOutput};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{convert::TryFrom, fs, path::Path};
+use tari_common_types::types::{ComSignature, Commitment, PublicKey};
+use tari_core::transactions::{tari_amount::MicroTari, transaction::OutputFeatures};
+use tari_crypto::script::{ExecutionStack, Opcode, StackItem, TariScript};
+
+/// The current format version of [HtlcDetails] files
+pub const HTLC_DETAILS_VERSION: u8 = 1;
+/// The length in bytes of an HTLC preimage
+pub const HTLC_PREIMAGE_LENGTH: usize = 32;
+
+/// Hash a preimage to the hash lock that `HashSha256` compares against
+pub fn hash_preimage(preimage: &[u8]) -> Result<[u8; 32], HtlcError> {
+    if preimage.len() != HTLC_PREIMAGE_LENGTH {
+        return Err(HtlcError::InvalidPreimageLength(preimage.len()));
+    }
+    let mut hash_lock = [0u8; 32];
+    hash_lock.copy_from_slice(Sha256::digest(preimage).as_slice());
+    Ok(hash_lock)
+}
+
+/// Build the HTLC script for the given hash lock, timeout height, recipient and sender
+pub fn htlc_script(hash_lock: &[u8; 32], timeout_height: u64, recipient: &PublicKey, sender: &PublicKey) -> TariScript {
+    TariScript::new(vec![
+        Opcode::CheckHeight(timeout_height),
+        Opcode::LtZero,
+        Opcode::IfThen,
+        Opcode::HashSha256,
+        Opcode::PushHash(Box::new(*hash_lock)),
+        Opcode::EqualVerify,
+        Opcode::PushPubKey(Box::new(recipient.clone())),
+        Opcode::Else,
+        Opcode::PushPubKey(Box::new(sender.clone())),
+        Opcode::EndIf,
+    ])
+}
+
+/// The input stack that claims an HTLC output with its preimage
+pub fn claim_input_data(preimage: &[u8]) -> Result<ExecutionStack, HtlcError> {
+    let preimage = <[u8; 32]>::try_from(preimage).map_err(|_| HtlcError::InvalidPreimageLength(preimage.len()))?;
+    Ok(ExecutionStack::new(vec![StackItem::Hash(preimage)]))
+}
+
+/// The input stack that refunds an HTLC output after the timeout
+pub fn refund_input_data() -> ExecutionStack {
+    ExecutionStack::default()
+}
+
+/// The public details of an HTLC output that the sender shares with the recipient, so that the recipient can
+/// recognise and claim it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct HtlcDetails {
+    pub commitment: Commitment,
+    pub value: MicroTari,
+    pub features: OutputFeatures,
+    pub sender_offset_public_key: PublicKey,
+    pub metadata_signature: ComSignature,
+    pub hash_lock: [u8; 32],
+    pub timeout_height: u64,
+    pub sender: PublicKey,
+    pub recipient: PublicKey,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedDetails {
+    version: u8,
+    details: HtlcDetails,
+}
+
+impl HtlcDetails {
+    /// The details of an HTLC that this wallet sent
+    pub fn from_sent_htlc(htlc: &HtlcOutput, sender: PublicKey) -> Result<Self, HtlcError> {
+        Ok(Self {
+            commitment: htlc.commitment.clone(),
+            value: htlc.unblinded_output.value,
+            features: htlc.unblinded_output.features.clone(),
+            sender_offset_public_key: htlc.unblinded_output.sender_offset_public_key.clone(),
+            metadata_signature: htlc.unblinded_output.metadata_signature.clone(),
+            hash_lock: <[u8; 32]>::try_from(htlc.hash_lock.as_slice()).map_err(|_| HtlcError::InvalidHashLock)?,
+            timeout_height: htlc.timeout_height,
+            sender,
+            recipient: htlc.counterparty.clone(),
+        })
+    }
+
+    pub fn script(&self) -> TariScript {
+        htlc_script(&self.hash_lock, self.timeout_height, &self.recipient, &self.sender)
+    }
+
+    pub fn to_json(&self) -> Result<String, HtlcError> {
+        Ok(serde_json::to_string_pretty(&VersionedDetails {
+            version: HTLC_DETAILS_VERSION,
+            details: self.clone(),
+        })?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, HtlcError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or(HtlcError::MissingVersion)?;
+        if version != u64::from(HTLC_DETAILS_VERSION) {
+            return Err(HtlcError::UnsupportedVersion(version));
+        }
+        let versioned: VersionedDetails = serde_json::from_value(value)?;
+        Ok(versioned.details)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), HtlcError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, HtlcError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{rngs::OsRng, RngCore};
+    use tari_common_types::types::{CommitmentFactory, PrivateKey};
+    use tari_crypto::{
+        commitment::HomomorphicCommitmentFactory,
+        keys::{PublicKey as PublicKeyTrait, SecretKey},
+        script::ScriptContext,
+    };
+
+    fn execute(script: &TariScript, input: &ExecutionStack, height: u64) -> Option<PublicKey> {
+        let commitment = CommitmentFactory::default().commit_value(&PrivateKey::random(&mut OsRng), 1);
+        let context = ScriptContext::new(height, &[0u8; 32], &commitment);
+        match script.execute_with_context(input, &context) {
+            Ok(StackItem::PublicKey(key)) => Some(key),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_htlc_script() {
+        let (_, recipient) = PublicKey::random_keypair(&mut OsRng);
+        let (_, sender) = PublicKey::random_keypair(&mut OsRng);
+        let mut preimage = vec![0u8; HTLC_PREIMAGE_LENGTH];
+        OsRng.fill_bytes(&mut preimage);
+        let hash_lock = hash_preimage(&preimage).unwrap();
+        let script = htlc_script(&hash_lock, 100, &recipient, &sender);
+
+        let claim = claim_input_data(&preimage).unwrap();
+        assert_eq!(execute(&script, &claim, 99), Some(recipient));
+        assert_eq!(execute(&script, &claim, 100), None);
+
+        let wrong_claim = claim_input_data(&[1u8; HTLC_PREIMAGE_LENGTH]).unwrap();
+        assert_eq!(execute(&script, &wrong_claim, 99), None);
+
+        let refund = refund_input_data();
+        assert_eq!(execute(&script, &refund, 99), None);
+        assert_eq!(execute(&script, &refund, 100), Some(sender));
+
+        assert!(matches!(
+            hash_preimage(&[0u8; 16]),
+            Err(HtlcError::InvalidPreimageLength(16))
+        ));
+    }
+
+    #[test]
+    fn test_htlc_details_versioning() {
+        let (_, recipient) = PublicKey::random_keypair(&mut OsRng);
+        let (_, sender) = PublicKey::random_keypair(&mut OsRng);
+        let details = HtlcDetails {
+            commitment: CommitmentFactory::default().zero(),
+            value: MicroTari::from(1000),
+            features: OutputFeatures::default(),
+            sender_offset_public_key: PublicKey::default(),
+            metadata_signature: ComSignature::default(),
+            hash_lock: [3u8; 32],
+            timeout_height: 100,
+            sender,
+            recipient,
+        };
+        let json = details.to_json().unwrap();
+        assert_eq!(HtlcDetails::from_json(&json).unwrap(), details);
+
+        let json = json.replacen("\"version\": 1", "\"version\": 2", 1);
+        assert!(matches!(
+            HtlcDetails::from_json(&json),
+            Err(HtlcError::UnsupportedVersion(2))
+        ));
+    }
+}
@@ -24,15 +24,23 @@ use crate::{
     output_manager_service::{
         error::OutputManagerError,
         handle::PublicRewindKeys,
-        storage::database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase},
+        storage::{
+            database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase},
+            models::{Account, AccountId, DEFAULT_ACCOUNT_ID},
+        },
     },
     types::KeyDigest,
 };
 use futures::lock::Mutex;
 use log::*;
+use std::{collections::HashMap, convert::TryInto};
 use tari_common_types::types::{PrivateKey, PublicKey};
 use tari_core::transactions::transaction_protocol::RewindData;
-use tari_crypto::{keys::PublicKey as PublicKeyTrait, range_proof::REWIND_USER_MESSAGE_LENGTH};
+use tari_crypto::{
+    keys::PublicKey as PublicKeyTrait,
+    range_proof::REWIND_USER_MESSAGE_LENGTH,
+    tari_utilities::ByteArray,
+};
 use tari_key_manager::{
     key_manager::KeyManager,
     mnemonic::{from_secret_key, MnemonicLanguage},
@@ -45,13 +53,30 @@ const KEY_MANAGER_COINBASE_SCRIPT_BRANCH_KEY: &str = "coinbase_script";
 const KEY_MANAGER_SCRIPT_BRANCH_KEY: &str = "script";
 const KEY_MANAGER_RECOVERY_VIEWONLY_BRANCH_KEY: &str = "recovery_viewonly";
 const KEY_MANAGER_RECOVERY_BLINDING_BRANCH_KEY: &str = "recovery_blinding";
+const KEY_MANAGER_ACCOUNT_BRANCH_KEY: &str = "account";
+const KEY_MANAGER_ACCOUNT_SCRIPT_BRANCH_KEY: &str = "account_script";
 const KEY_MANAGER_MAX_SEARCH_DEPTH: u64 = 1_000_000;
+/// How far past the highest index seen so far the key chain of an account other than the default account is searched.
+/// Recovery searches several accounts for every rewound output, so the search has to stay short.
+const ACCOUNT_KEY_SEARCH_GAP_LIMIT: u64 = 1_000;
+/// Outputs of accounts other than the default account carry this prefix followed by the account id in the proof message
+/// of their rewindable range proof, so that recovery can return them to the right account. Outputs of the default
+/// account keep an empty proof message.
+const ACCOUNT_PROOF_MESSAGE_PREFIX: &[u8; 4] = b"acct";
+
+/// The UTXO and script key chains of an account other than the default account
+struct AccountKeyManagers {
+    utxo_key_manager: KeyManager<PrivateKey, KeyDigest>,
+    utxo_script_key_manager: KeyManager<PrivateKey, KeyDigest>,
+}
 
 pub(crate) struct MasterKeyManager<TBackend> {
+    master_key: PrivateKey,
     utxo_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
     utxo_script_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
     coinbase_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
     coinbase_script_key_manager: Mutex<KeyManager<PrivateKey, KeyDigest>>,
+    account_key_managers: Mutex<HashMap<AccountId, AccountKeyManagers>>,
     rewind_data: RewindData,
     db: OutputManagerDatabase<TBackend>,
 }
@@ -114,7 +139,7 @@ where TBackend: OutputManagerBackend + 'static
         let rewind_key = rewind_key_manager.derive_key(0)?.k;
 
         let rewind_blinding_key_manager = KeyManager::<PrivateKey, KeyDigest>::from(
-            key_manager_state.master_key,
+            key_manager_state.master_key.clone(),
             KEY_MANAGER_RECOVERY_BLINDING_BRANCH_KEY.to_string(),
             0,
         );
@@ -126,20 +151,106 @@ where TBackend: OutputManagerBackend + 'static
             proof_message: [0u8; REWIND_USER_MESSAGE_LENGTH],
         };
 
+        let mut account_key_managers = HashMap::new();
+        for account in db.get_accounts().await? {
+            account_key_managers.insert(
+                account.id,
+                Self::account_key_managers(&key_manager_state.master_key, &account)?,
+            );
+        }
+
         Ok(Self {
+            master_key: key_manager_state.master_key,
             utxo_key_manager: Mutex::new(utxo_key_manager),
             utxo_script_key_manager: Mutex::new(utxo_script_key_manager),
             coinbase_key_manager: Mutex::new(coinbase_key_manager),
             coinbase_script_key_manager: Mutex::new(coinbase_script_key_manager),
+            account_key_managers: Mutex::new(account_key_managers),
             rewind_data,
             db,
         })
     }
 
+    /// The key chains of an account are branches of the master key, so they can be recreated from the seed
+    fn account_key_managers(
+        master_key: &PrivateKey,
+        account: &Account,
+    ) -> Result<AccountKeyManagers, OutputManagerError> {
+        let root = KeyManager::<PrivateKey, KeyDigest>::from(master_key.clone(), "".to_string(), 0);
+        Ok(AccountKeyManagers {
+            utxo_key_manager: root.branch(
+                format!("{}/{}", KEY_MANAGER_ACCOUNT_BRANCH_KEY, account.id),
+                account.key_index,
+            )?,
+            utxo_script_key_manager: root.branch(
+                format!("{}/{}", KEY_MANAGER_ACCOUNT_SCRIPT_BRANCH_KEY, account.id),
+                account.key_index,
+            )?,
+        })
+    }
+
     pub fn rewind_data(&self) -> &RewindData {
         &self.rewind_data
     }
 
+    /// The rewind data for outputs of the specified account, which marks the account in the proof message
+    pub fn account_rewind_data(&self, account_id: AccountId) -> RewindData {
+        let mut rewind_data = self.rewind_data.clone();
+        if account_id != DEFAULT_ACCOUNT_ID {
+            rewind_data.proof_message[..4].copy_from_slice(ACCOUNT_PROOF_MESSAGE_PREFIX);
+            rewind_data.proof_message[4..12].copy_from_slice(&account_id.to_le_bytes());
+        }
+        rewind_data
+    }
+
+    /// Read the account an output belongs to from the proof message of its rewound range proof
+    pub fn account_from_proof_message(proof_message: &[u8]) -> AccountId {
+        if proof_message.len() < 12 || &proof_message[..4] != ACCOUNT_PROOF_MESSAGE_PREFIX {
+            return DEFAULT_ACCOUNT_ID;
+        }
+        let id_bytes = proof_message[4..12].try_into().expect("Slice is 8 bytes long");
+        AccountId::from_le_bytes(id_bytes)
+    }
+
+    /// Load the key chains of an account so that keys can be derived for it
+    pub async fn add_account(&self, account: &Account) -> Result<(), OutputManagerError> {
+        let key_managers = Self::account_key_managers(&self.master_key, account)?;
+        self.account_key_managers.lock().await.insert(account.id, key_managers);
+        Ok(())
+    }
+
+    /// The ids of the accounts, other than the default account, whose key chains are loaded
+    pub async fn account_ids(&self) -> Vec<AccountId> {
+        let mut ids = self
+            .account_key_managers
+            .lock()
+            .await
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Return the next pair of (spending_key, script_private_key) from the key chains of the specified account
+    pub async fn get_next_account_spend_and_script_key(
+        &self,
+        account_id: AccountId,
+    ) -> Result<(PrivateKey, PrivateKey), OutputManagerError> {
+        if account_id == DEFAULT_ACCOUNT_ID {
+            return self.get_next_spend_and_script_key().await;
+        }
+        let mut account_key_managers = self.account_key_managers.lock().await;
+        let key_managers = account_key_managers
+            .get_mut(&account_id)
+            .ok_or_else(|| OutputManagerError::AccountNotFound(account_id.to_string()))?;
+        let key = key_managers.utxo_key_manager.next_key()?;
+        let script_key = key_managers.utxo_script_key_manager.next_key()?;
+
+        self.db.set_account_key_index(account_id, key.key_index).await?;
+        Ok((key.k, script_key.k))
+    }
+
     /// Return the next pair of (spending_key, script_private_key) from the key managers. These will always be generated
     /// in tandem and at corresponding increments
     pub async fn get_next_spend_and_script_key(&self) -> Result<(PrivateKey, PrivateKey), OutputManagerError> {
@@ -153,6 +264,37 @@ where TBackend: OutputManagerBackend + 'static
         Ok((key.k, script_key.k))
     }
 
+    /// Reserve the next index of the UTXO key chains without returning the keys. The keys can later be derived from
+    /// the seed using `get_spend_and_script_key_at_index`.
+    pub async fn get_next_key_index(&self) -> Result<u64, OutputManagerError> {
+        let mut km = self.utxo_key_manager.lock().await;
+        let key = km.next_key()?;
+
+        let mut skm = self.utxo_script_key_manager.lock().await;
+        skm.next_key()?;
+
+        self.db.increment_key_index().await?;
+        Ok(key.key_index)
+    }
+
+    /// The current index of the UTXO key chains of the default account
+    pub async fn current_key_index(&self) -> u64 {
+        self.utxo_key_manager.lock().await.key_index()
+    }
+
+    /// Derive the (spending_key, script_private_key) pair at the specified index of the UTXO key chains
+    pub async fn get_spend_and_script_key_at_index(
+        &self,
+        index: u64,
+    ) -> Result<(PrivateKey, PrivateKey), OutputManagerError> {
+        let km = self.utxo_key_manager.lock().await;
+        let key = km.derive_key(index)?;
+
+        let skm = self.utxo_script_key_manager.lock().await;
+        let script_key = skm.derive_key(index)?;
+        Ok((key.k, script_key.k))
+    }
+
     pub async fn get_script_key_at_index(&self, index: u64) -> Result<PrivateKey, OutputManagerError> {
         let skm = self.utxo_script_key_manager.lock().await;
         let script_key = skm.derive_key(index)?;
@@ -202,6 +344,106 @@ where TBackend: OutputManagerBackend + 'static
         Err(OutputManagerError::KeyNotFoundInKeyChain)
     }
 
+    /// Search the key chain up to its current index for the specified keys and return the index of each key, or `None`
+    /// if the key was not derived from the key chain. Keys issued by this wallet never lie beyond the current index, so
+    /// unlike `find_utxo_key_index` this does not search ahead of it.
+    pub async fn find_issued_utxo_key_indices(
+        &self,
+        keys: &[PrivateKey],
+    ) -> Result<Vec<Option<u64>>, OutputManagerError> {
+        let mut indices = vec![None; keys.len()];
+        let mut remaining = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (k.to_vec(), i))
+            .collect::<HashMap<_, _>>();
+        let utxo_key_manager = self.utxo_key_manager.lock().await;
+        for index in 0..=utxo_key_manager.key_index() {
+            if remaining.is_empty() {
+                break;
+            }
+            if let Some(i) = remaining.remove(&utxo_key_manager.derive_key(index)?.k.to_vec()) {
+                indices[i] = Some(index);
+            }
+        }
+        Ok(indices)
+    }
+
+    /// Search the key chain of the specified account to find the index of the specified key. The search stops a gap
+    /// limit past the account's current index.
+    pub async fn find_account_utxo_key_index(
+        &self,
+        account_id: AccountId,
+        key: PrivateKey,
+    ) -> Result<u64, OutputManagerError> {
+        if account_id == DEFAULT_ACCOUNT_ID {
+            return self.find_utxo_key_index(key).await;
+        }
+        let account_key_managers = self.account_key_managers.lock().await;
+        let key_managers = account_key_managers
+            .get(&account_id)
+            .ok_or_else(|| OutputManagerError::AccountNotFound(account_id.to_string()))?;
+        let current_index = key_managers.utxo_key_manager.key_index();
+
+        for i in 0u64..=current_index + ACCOUNT_KEY_SEARCH_GAP_LIMIT {
+            if key_managers.utxo_key_manager.derive_key(i)?.k == key {
+                trace!(
+                    target: LOG_TARGET,
+                    "Key found in account {} Key Chain at index {}",
+                    account_id,
+                    i
+                );
+                return Ok(i);
+            }
+        }
+
+        Err(OutputManagerError::KeyNotFoundInKeyChain)
+    }
+
+    /// Derive the script private key at the specified index of the script key chain of the specified account
+    pub async fn get_account_script_key_at_index(
+        &self,
+        account_id: AccountId,
+        index: u64,
+    ) -> Result<PrivateKey, OutputManagerError> {
+        if account_id == DEFAULT_ACCOUNT_ID {
+            return self.get_script_key_at_index(index).await;
+        }
+        let account_key_managers = self.account_key_managers.lock().await;
+        let key_managers = account_key_managers
+            .get(&account_id)
+            .ok_or_else(|| OutputManagerError::AccountNotFound(account_id.to_string()))?;
+        Ok(key_managers.utxo_script_key_manager.derive_key(index)?.k)
+    }
+
+    /// If the supplied index is higher than the current key chain indices of the specified account then they will be
+    /// updated.
+    pub async fn update_account_index_if_higher(
+        &self,
+        account_id: AccountId,
+        index: u64,
+    ) -> Result<(), OutputManagerError> {
+        if account_id == DEFAULT_ACCOUNT_ID {
+            return self.update_current_index_if_higher(index).await;
+        }
+        let mut account_key_managers = self.account_key_managers.lock().await;
+        let key_managers = account_key_managers
+            .get_mut(&account_id)
+            .ok_or_else(|| OutputManagerError::AccountNotFound(account_id.to_string()))?;
+        if index > key_managers.utxo_key_manager.key_index() {
+            key_managers.utxo_key_manager.update_key_index(index);
+            key_managers.utxo_script_key_manager.update_key_index(index);
+            self.db.set_account_key_index(account_id, index).await?;
+            trace!(
+                target: LOG_TARGET,
+                "Updated account {} Key Index to {}",
+                account_id,
+                index
+            );
+        }
+        Ok(())
+    }
+
     /// If the supplied index is higher than the current UTXO key chain indices then they will be updated.
     pub async fn update_current_index_if_higher(&self, index: u64) -> Result<(), OutputManagerError> {
         let mut utxo_key_manager = self.utxo_key_manager.lock().await;
@@ -46,11 +46,13 @@ use tari_service_framework::{
     ServiceInitializer,
     ServiceInitializerContext,
 };
+pub use tasks::ConsolidationBatch;
 use tokio::sync::broadcast;
 
 pub mod config;
 pub mod error;
 pub mod handle;
+pub mod htlc;
 mod master_key_manager;
 mod recovery;
 pub mod resources;
@@ -22,20 +22,23 @@
 
 use std::sync::Arc;
 
+use futures::future;
 use log::*;
 use tari_crypto::{inputs, keys::PublicKey as PublicKeyTrait, tari_utilities::hex::Hex};
 
-use tari_common_types::types::PublicKey;
+use tari_common_types::types::{PrivateKey, PublicKey};
 use tari_core::transactions::{
     transaction::{TransactionOutput, UnblindedOutput},
+    transaction_protocol::RewindData,
     CryptoFactories,
 };
+use tokio::task;
 
 use crate::output_manager_service::{
     error::{OutputManagerError, OutputManagerStorageError},
     storage::{
         database::{OutputManagerBackend, OutputManagerDatabase},
-        models::DbUnblindedOutput,
+        models::{Account, AccountId, DbUnblindedOutput, DEFAULT_ACCOUNT_ID},
     },
     MasterKeyManager,
 };
@@ -64,52 +67,21 @@ where TBackend: OutputManagerBackend + 'static
     }
 
     /// Attempt to rewind all of the given transaction outputs into unblinded outputs. If they can be rewound then add
-    /// them to the database and increment the key manager index
+    /// them to the database, assigned to the account whose key chain they were derived from, and increment that
+    /// account's key manager index
     pub async fn scan_and_recover_outputs(
         &mut self,
         outputs: Vec<TransactionOutput>,
     ) -> Result<Vec<UnblindedOutput>, OutputManagerError> {
-        let mut rewound_outputs: Vec<UnblindedOutput> = outputs
-            .into_iter()
-            .filter_map(|output| {
-                output
-                    .full_rewind_range_proof(
-                        &self.factories.range_proof,
-                        &self.master_key_manager.rewind_data().rewind_key,
-                        &self.master_key_manager.rewind_data().rewind_blinding_key,
-                    )
-                    .ok()
-                    .map(|v| {
-                        (
-                            v,
-                            output.features,
-                            output.script,
-                            output.sender_offset_public_key,
-                            output.metadata_signature,
-                        )
-                    })
-            })
-            .map(
-                |(output, features, script, sender_offset_public_key, metadata_signature)| {
-                    UnblindedOutput::new(
-                        output.committed_value,
-                        output.blinding_factor.clone(),
-                        features,
-                        script,
-                        inputs!(PublicKey::from_secret_key(&output.blinding_factor)),
-                        output.blinding_factor,
-                        sender_offset_public_key,
-                        metadata_signature,
-                    )
-                },
-            )
-            .collect();
-
-        for output in rewound_outputs.iter_mut() {
-            self.update_outputs_script_private_key_and_update_key_manager_index(output)
+        let mut rewound_outputs = self.rewind_outputs(outputs).await?;
+
+        for (output, account_id) in rewound_outputs.iter_mut() {
+            *account_id = self
+                .update_outputs_script_private_key_and_update_key_manager_index(output, *account_id)
                 .await?;
 
-            let db_output = DbUnblindedOutput::from_unblinded_output(output.clone(), &self.factories)?;
+            let mut db_output = DbUnblindedOutput::from_unblinded_output(output.clone(), &self.factories)?;
+            db_output.account_id = *account_id;
             let output_hex = db_output.commitment.to_hex();
             if let Err(e) = self.db.add_unspent_output(db_output).await {
                 match e {
@@ -125,38 +97,147 @@ where TBackend: OutputManagerBackend + 'static
 
             trace!(
                 target: LOG_TARGET,
-                "Output {} with value {} with {} recovered",
+                "Output {} with value {} with {} recovered into account {}",
                 output
                     .as_transaction_input(&self.factories.commitment)?
                     .commitment
                     .to_hex(),
                 output.value,
                 output.features,
+                account_id,
             );
         }
 
+        Ok(rewound_outputs.into_iter().map(|(output, _)| output).collect())
+    }
+
+    /// Rewinding is CPU bound, so the outputs are split across the available cores and rewound on blocking threads
+    async fn rewind_outputs(
+        &self,
+        outputs: Vec<TransactionOutput>,
+    ) -> Result<Vec<(UnblindedOutput, AccountId)>, OutputManagerError> {
+        if outputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let num_tasks = num_cpus::get().max(1);
+        let chunk_size = (outputs.len() + num_tasks - 1) / num_tasks;
+        let tasks = outputs.chunks(chunk_size).map(|chunk| {
+            let chunk = chunk.to_vec();
+            let factories = self.factories.clone();
+            let rewind_data = self.master_key_manager.rewind_data().clone();
+            task::spawn_blocking(move || rewind_outputs::<TBackend>(chunk, &factories, &rewind_data))
+        });
+
+        let mut rewound_outputs = Vec::new();
+        for result in future::join_all(tasks).await {
+            rewound_outputs.extend(result.map_err(|e| OutputManagerError::BlockingTaskSpawnError(e.to_string()))?);
+        }
         Ok(rewound_outputs)
     }
 
-    /// Find the key manager index that corresponds to the spending key in the rewound output, if found then modify
-    /// output to contain correct associated script private key and update the key manager to the highest index it has
-    /// seen so far.
+    /// Find the account and key manager index that correspond to the spending key in the rewound output, if found
+    /// then modify output to contain correct associated script private key and update the account's key manager to
+    /// the highest index it has seen so far. Returns the account the output belongs to.
     async fn update_outputs_script_private_key_and_update_key_manager_index(
         &mut self,
         output: &mut UnblindedOutput,
-    ) -> Result<(), OutputManagerError> {
-        let found_index = self
-            .master_key_manager
-            .find_utxo_key_index(output.spending_key.clone())
+        account_hint: AccountId,
+    ) -> Result<AccountId, OutputManagerError> {
+        let (account_id, found_index) = self
+            .find_account_and_key_index(output.spending_key.clone(), account_hint)
             .await?;
 
         self.master_key_manager
-            .update_current_index_if_higher(found_index)
+            .update_account_index_if_higher(account_id, found_index)
             .await?;
 
-        let script_private_key = self.master_key_manager.get_script_key_at_index(found_index).await?;
+        let script_private_key = self
+            .master_key_manager
+            .get_account_script_key_at_index(account_id, found_index)
+            .await?;
         output.input_data = inputs!(PublicKey::from_secret_key(&script_private_key));
         output.script_private_key = script_private_key;
-        Ok(())
+        Ok(account_id)
     }
+
+    /// The proof message of an output only hints at its account, e.g. the change output of a transfer between accounts
+    /// carries the receiving account's mark. The hinted account is searched first, followed by the default account and
+    /// then the other known accounts. Accounts that are hinted at but not known yet, as when recovering a wallet from
+    /// its seed words, are recreated with a placeholder name.
+    async fn find_account_and_key_index(
+        &mut self,
+        spending_key: PrivateKey,
+        account_hint: AccountId,
+    ) -> Result<(AccountId, u64), OutputManagerError> {
+        if account_hint != DEFAULT_ACCOUNT_ID && !self.master_key_manager.account_ids().await.contains(&account_hint) {
+            let account = Account {
+                id: account_hint,
+                name: format!("account-{}", account_hint),
+                key_index: 0,
+            };
+            self.db.add_account(account.clone()).await?;
+            self.master_key_manager.add_account(&account).await?;
+            info!(
+                target: LOG_TARGET,
+                "Recreated account {} found while recovering outputs", account_hint
+            );
+        }
+
+        let mut candidates = vec![account_hint];
+        if account_hint != DEFAULT_ACCOUNT_ID {
+            candidates.push(DEFAULT_ACCOUNT_ID);
+        }
+        candidates.extend(
+            self.master_key_manager
+                .account_ids()
+                .await
+                .into_iter()
+                .filter(|id| *id != account_hint),
+        );
+
+        for account_id in candidates {
+            match self
+                .master_key_manager
+                .find_account_utxo_key_index(account_id, spending_key.clone())
+                .await
+            {
+                Ok(index) => return Ok((account_id, index)),
+                Err(OutputManagerError::KeyNotFoundInKeyChain) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(OutputManagerError::KeyNotFoundInKeyChain)
+    }
+}
+
+fn rewind_outputs<TBackend: OutputManagerBackend + 'static>(
+    outputs: Vec<TransactionOutput>,
+    factories: &CryptoFactories,
+    rewind_data: &RewindData,
+) -> Vec<(UnblindedOutput, AccountId)> {
+    outputs
+        .into_iter()
+        .filter_map(|output| {
+            let rewound = output
+                .full_rewind_range_proof(
+                    &factories.range_proof,
+                    &rewind_data.rewind_key,
+                    &rewind_data.rewind_blinding_key,
+                )
+                .ok()?;
+            let account_id = MasterKeyManager::<TBackend>::account_from_proof_message(&rewound.proof_message);
+            let output = UnblindedOutput::new(
+                rewound.committed_value,
+                rewound.blinding_factor.clone(),
+                output.features,
+                output.script,
+                inputs!(PublicKey::from_secret_key(&rewound.blinding_factor)),
+                rewound.blinding_factor,
+                output.sender_offset_public_key,
+                output.metadata_signature,
+            );
+            Some((output, account_id))
+        })
+        .collect()
 }
@@ -25,19 +25,33 @@ use crate::{
     connectivity_service::WalletConnectivityInterface,
     output_manager_service::{
         config::OutputManagerServiceConfig,
-        error::{OutputManagerError, OutputManagerProtocolError, OutputManagerStorageError},
-        handle::{OutputManagerEventSender, OutputManagerRequest, OutputManagerResponse},
+        error::{HtlcError, OutputManagerError, OutputManagerProtocolError, OutputManagerStorageError},
+        handle::{OutputManagerEvent, OutputManagerEventSender, OutputManagerRequest, OutputManagerResponse},
+        htlc::{self, HtlcDetails, HTLC_PREIMAGE_LENGTH},
         recovery::StandardUtxoRecoverer,
         resources::OutputManagerResources,
         storage::{
             database::{OutputManagerBackend, OutputManagerDatabase},
-            models::{DbUnblindedOutput, KnownOneSidedPaymentScript},
+            models::{
+                Account,
+                AccountId,
+                DbUnblindedOutput,
+                HtlcOutput,
+                HtlcRole,
+                HtlcStatus,
+                KnownOneSidedPaymentScript,
+                DEFAULT_ACCOUNT_ID,
+                DEFAULT_ACCOUNT_NAME,
+            },
         },
-        tasks::TxoValidationTask,
+        tasks::{ConsolidationBatch, TxoValidationTask, UtxoConsolidationTask},
         MasterKeyManager,
         TxId,
     },
-    transaction_service::handle::TransactionServiceHandle,
+    transaction_service::{
+        handle::TransactionServiceHandle,
+        offline_signing::{OfflineInput, UnsignedTransaction},
+    },
     types::HashDigest,
 };
 use blake2::Digest;
@@ -47,10 +61,11 @@ use log::*;
 use rand::{rngs::OsRng, RngCore};
 use std::{
     cmp::Ordering,
+    collections::HashMap,
     fmt::{self, Display},
     sync::Arc,
 };
-use tari_common_types::types::{PrivateKey, PublicKey};
+use tari_common_types::types::{Commitment, PrivateKey, PublicKey};
 use tari_comms::types::{CommsPublicKey, CommsSecretKey};
 use tari_core::{
     consensus::ConsensusConstants,
@@ -66,14 +81,19 @@ use tari_core::{
     },
 };
 use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
     inputs,
     keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait, SecretKey},
     script,
     script::TariScript,
-    tari_utilities::{hex::Hex, ByteArray},
+    tari_utilities::{hash::Hashable, hex::Hex, ByteArray},
 };
 use tari_service_framework::reply_channel;
 use tari_shutdown::ShutdownSignal;
+use tokio::{
+    sync::mpsc,
+    time::{self, MissedTickBehavior},
+};
 
 const LOG_TARGET: &str = "wallet::output_manager_service";
 const LOG_TARGET_STRESS: &str = "stress_test::output_manager_service";
@@ -88,6 +108,7 @@ pub struct OutputManagerService<TBackend, TWalletConnectivity> {
         Option<reply_channel::Receiver<OutputManagerRequest, Result<OutputManagerResponse, OutputManagerError>>>,
     base_node_service: BaseNodeServiceHandle,
     last_seen_tip_height: Option<u64>,
+    utxo_consolidation_in_progress: bool,
 }
 
 impl<TBackend, TWalletConnectivity> OutputManagerService<TBackend, TWalletConnectivity>
@@ -135,6 +156,7 @@ where
             request_stream: Some(request_stream),
             base_node_service,
             last_seen_tip_height: None,
+            utxo_consolidation_in_progress: false,
         })
     }
 
@@ -150,6 +172,10 @@ where
 
         let mut base_node_service_event_stream = self.base_node_service.get_event_stream();
 
+        let (consolidation_sender, mut consolidation_receiver) = mpsc::channel(1);
+        let mut utxo_consolidation_interval = time::interval(self.resources.config.utxo_consolidation.interval);
+        utxo_consolidation_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         info!(target: LOG_TARGET, "Output Manager Service started");
         loop {
             tokio::select! {
@@ -159,6 +185,15 @@ where
                         Err(e) => debug!(target: LOG_TARGET, "Lagging read on base node event broadcast channel: {}", e),
                     }
                 },
+                _ = utxo_consolidation_interval.tick(), if self.resources.config.utxo_consolidation.enabled => {
+                    self.start_utxo_consolidation(consolidation_sender.clone()).await;
+                },
+                Some(batches) = consolidation_receiver.recv() => {
+                    self.utxo_consolidation_in_progress = false;
+                    if let Err(e) = self.create_consolidation_transactions(batches).await {
+                        warn!(target: LOG_TARGET, "Error creating UTXO consolidation transactions: {:?}", e);
+                    }
+                },
                 Some(request_context) = request_stream.next() => {
                 trace!(target: LOG_TARGET, "Handling Service API Request");
                     let (request, reply_tx) = request_context.split();
@@ -224,15 +259,56 @@ where
                 lock_height,
                 message,
                 recipient_script,
+                recipient_output_features,
+                account_id,
             )) => self
-                .prepare_transaction_to_send(tx_id, amount, fee_per_gram, lock_height, message, recipient_script)
+                .prepare_transaction_to_send(
+                    account_id,
+                    tx_id,
+                    amount,
+                    fee_per_gram,
+                    lock_height,
+                    message,
+                    recipient_script,
+                    recipient_output_features,
+                )
                 .await
                 .map(OutputManagerResponse::TransactionToSend),
             OutputManagerRequest::CreatePayToSelfTransaction((tx_id, amount, fee_per_gram, lock_height, message)) => {
-                self.create_pay_to_self_transaction(tx_id, amount, fee_per_gram, lock_height, message)
-                    .await
-                    .map(OutputManagerResponse::PayToSelfTransaction)
+                self.create_pay_to_self_transaction(
+                    tx_id,
+                    DEFAULT_ACCOUNT_ID,
+                    DEFAULT_ACCOUNT_ID,
+                    amount,
+                    fee_per_gram,
+                    lock_height,
+                    message,
+                    None,
+                )
+                .await
+                .map(OutputManagerResponse::PayToSelfTransaction)
             },
+            OutputManagerRequest::CreateAccountTransferTransaction((
+                tx_id,
+                from_account_id,
+                to_account_id,
+                amount,
+                fee_per_gram,
+                lock_height,
+                message,
+            )) => self
+                .create_pay_to_self_transaction(
+                    tx_id,
+                    from_account_id,
+                    to_account_id,
+                    amount,
+                    fee_per_gram,
+                    lock_height,
+                    message,
+                    None,
+                )
+                .await
+                .map(OutputManagerResponse::PayToSelfTransaction),
             OutputManagerRequest::FeeEstimate((amount, fee_per_gram, num_kernels, num_outputs)) => self
                 .fee_estimate(amount, fee_per_gram, num_kernels, num_outputs)
                 .await
@@ -327,6 +403,71 @@ where
                 .set_coinbase_abandoned(tx_id, abandoned)
                 .await
                 .map(|_| OutputManagerResponse::CoinbaseAbandonedSet),
+            OutputManagerRequest::PrepareOfflineTransaction((tx_id, amount, fee_per_gram)) => self
+                .prepare_offline_transaction(tx_id, amount, fee_per_gram)
+                .await
+                .map(OutputManagerResponse::OfflineTransactionInputs),
+            OutputManagerRequest::SignOfflineTransaction(unsigned) => self
+                .sign_offline_transaction(*unsigned)
+                .await
+                .map(OutputManagerResponse::TransactionToSend),
+            OutputManagerRequest::ConfirmOfflineTransaction((tx_id, transaction, change_key_index)) => self
+                .confirm_offline_transaction(tx_id, *transaction, change_key_index)
+                .await
+                .map(|_| OutputManagerResponse::OfflineTransactionConfirmed),
+            OutputManagerRequest::GetNextScriptKey => {
+                let index = self.resources.master_key_manager.get_next_key_index().await?;
+                let key = self.resources.master_key_manager.get_script_key_at_index(index).await?;
+                Ok(OutputManagerResponse::ScriptKey((index, key)))
+            },
+            OutputManagerRequest::GetScriptKeyAtIndex(index) => self
+                .resources
+                .master_key_manager
+                .get_script_key_at_index(index)
+                .await
+                .map(|key| OutputManagerResponse::ScriptKey((index, key))),
+            OutputManagerRequest::AddHtlc(htlc) => self
+                .resources
+                .db
+                .add_htlc(*htlc)
+                .await
+                .map(|_| OutputManagerResponse::HtlcAdded)
+                .map_err(OutputManagerError::from),
+            OutputManagerRequest::ImportHtlc((details, recipient_private_key, tx_id, fee_per_gram)) => self
+                .import_htlc(*details, recipient_private_key, tx_id, fee_per_gram)
+                .await
+                .map(|htlc| OutputManagerResponse::HtlcImported(Box::new(htlc))),
+            OutputManagerRequest::GetHtlcs => Ok(OutputManagerResponse::Htlcs(self.resources.db.get_htlcs().await?)),
+            OutputManagerRequest::CreateHtlcPreimage => self
+                .create_htlc_preimage()
+                .await
+                .map(OutputManagerResponse::HtlcPreimage),
+            OutputManagerRequest::AddHtlcPreimage(preimage) => self
+                .add_htlc_preimage(preimage)
+                .await
+                .map(OutputManagerResponse::HtlcPreimageAdded),
+            OutputManagerRequest::CreateHtlcSpendTransaction((commitment, tip_height)) => self
+                .create_htlc_spend_transaction(commitment, tip_height)
+                .await
+                .map(OutputManagerResponse::Transaction),
+            OutputManagerRequest::CreateAccount(name) => self
+                .create_account(name)
+                .await
+                .map(OutputManagerResponse::AccountCreated),
+            OutputManagerRequest::GetAccounts => self.get_accounts().await.map(OutputManagerResponse::Accounts),
+            OutputManagerRequest::GetAccountBalance(account_id) => {
+                let current_tip_for_time_lock_calculation = match self.base_node_service.get_chain_metadata().await {
+                    Ok(metadata) => metadata.map(|m| m.height_of_longest_chain()),
+                    Err(_) => None,
+                };
+                self.get_account_balance(account_id, current_tip_for_time_lock_calculation)
+                    .await
+                    .map(OutputManagerResponse::Balance)
+            },
+            OutputManagerRequest::GetAccountTransactionIds(account_id) => self
+                .fetch_account_tx_ids(account_id)
+                .await
+                .map(OutputManagerResponse::AccountTransactionIds),
         }
     }
 
@@ -383,6 +524,87 @@ where
         Ok(id)
     }
 
+    /// Plan a UTXO consolidation in the background; the planned batches are sent back on `sender` to be created by
+    /// `create_consolidation_transactions`
+    async fn start_utxo_consolidation(&mut self, sender: mpsc::Sender<Vec<ConsolidationBatch>>) {
+        if self.utxo_consolidation_in_progress {
+            debug!(target: LOG_TARGET, "UTXO consolidation is already in progress");
+            return;
+        }
+        let tip_height = match self.base_node_service.get_chain_metadata().await {
+            Ok(metadata) => metadata.map(|cm| cm.height_of_longest_chain()),
+            Err(e) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Could not get chain metadata for UTXO consolidation: {}", e
+                );
+                return;
+            },
+        };
+        let task = UtxoConsolidationTask::new(
+            self.resources.db.clone(),
+            self.resources.connectivity.clone(),
+            self.resources.event_publisher.clone(),
+            self.resources.config.utxo_consolidation.clone(),
+        );
+        self.utxo_consolidation_in_progress = true;
+        tokio::spawn(async move {
+            let batches = task.execute(tip_height).await.unwrap_or_else(|e| {
+                warn!(target: LOG_TARGET, "Error planning UTXO consolidation: {}", e);
+                Vec::new()
+            });
+            let _ = sender.send(batches).await;
+        });
+    }
+
+    /// Create the planned consolidation transactions, each spending the smallest unspent outputs to a single output
+    /// back to this wallet, and hand them to the Transaction Service to be broadcast.
+    async fn create_consolidation_transactions(
+        &mut self,
+        batches: Vec<ConsolidationBatch>,
+    ) -> Result<(), OutputManagerError> {
+        let fee_per_gram = self.resources.config.utxo_consolidation.fee_per_gram;
+        for batch in batches {
+            let tx_id = OsRng.next_u64();
+            let message = "UTXO consolidation".to_string();
+            let (fee, tx) = self
+                .create_pay_to_self_transaction(
+                    tx_id,
+                    DEFAULT_ACCOUNT_ID,
+                    DEFAULT_ACCOUNT_ID,
+                    batch.amount,
+                    fee_per_gram,
+                    None,
+                    message.clone(),
+                    Some(UTXOSelectionStrategy::Smallest),
+                )
+                .await?;
+            info!(
+                target: LOG_TARGET,
+                "Created UTXO consolidation transaction (TxId: {}) spending {} outputs", tx_id, batch.num_inputs
+            );
+            // The Transaction Service may be waiting on this service, so submit without blocking the request loop
+            let mut transaction_service = self.resources.transaction_service.clone();
+            let amount = batch.amount;
+            tokio::spawn(async move {
+                if let Err(e) = transaction_service
+                    .submit_transaction(tx_id, tx, fee, amount, message)
+                    .await
+                {
+                    error!(
+                        target: LOG_TARGET,
+                        "Could not submit UTXO consolidation transaction (TxId: {}): {}", tx_id, e
+                    );
+                }
+            });
+            let _ = self
+                .resources
+                .event_publisher
+                .send(Arc::new(OutputManagerEvent::UtxoConsolidationCreated(tx_id)));
+        }
+        Ok(())
+    }
+
     /// Add an unblinded output to the unspent outputs list
     pub async fn add_output(&mut self, tx_id: Option<TxId>, output: UnblindedOutput) -> Result<(), OutputManagerError> {
         debug!(
@@ -419,6 +641,72 @@ where
         Ok(balance)
     }
 
+    async fn get_account_balance(
+        &self,
+        account_id: AccountId,
+        current_tip_for_time_lock_calculation: Option<u64>,
+    ) -> Result<Balance, OutputManagerError> {
+        self.check_account_exists(account_id).await?;
+        let balance = self
+            .resources
+            .db
+            .get_account_balance(account_id, current_tip_for_time_lock_calculation)
+            .await?;
+        trace!(target: LOG_TARGET, "Account {} balance: {:?}", account_id, balance);
+        Ok(balance)
+    }
+
+    /// Create a new account with its own key chains. Accounts are numbered in order of creation, the default account
+    /// is always account 0.
+    async fn create_account(&mut self, name: String) -> Result<Account, OutputManagerError> {
+        if name == DEFAULT_ACCOUNT_NAME {
+            return Err(OutputManagerStorageError::DuplicateAccount(name).into());
+        }
+        let accounts = self.resources.db.get_accounts().await?;
+        let account = Account {
+            id: accounts.iter().map(|a| a.id).max().unwrap_or(DEFAULT_ACCOUNT_ID) + 1,
+            name,
+            key_index: 0,
+        };
+        self.resources.db.add_account(account.clone()).await?;
+        self.resources.master_key_manager.add_account(&account).await?;
+        info!(
+            target: LOG_TARGET,
+            "Created account `{}` with id {}", account.name, account.id
+        );
+        Ok(account)
+    }
+
+    async fn get_accounts(&self) -> Result<Vec<Account>, OutputManagerError> {
+        let mut accounts = vec![Account {
+            id: DEFAULT_ACCOUNT_ID,
+            name: DEFAULT_ACCOUNT_NAME.to_string(),
+            key_index: self.resources.master_key_manager.current_key_index().await,
+        }];
+        accounts.extend(self.resources.db.get_accounts().await?);
+        Ok(accounts)
+    }
+
+    async fn check_account_exists(&self, account_id: AccountId) -> Result<(), OutputManagerError> {
+        if account_id == DEFAULT_ACCOUNT_ID ||
+            self.resources
+                .db
+                .get_accounts()
+                .await?
+                .iter()
+                .any(|a| a.id == account_id)
+        {
+            Ok(())
+        } else {
+            Err(OutputManagerError::AccountNotFound(account_id.to_string()))
+        }
+    }
+
+    async fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerError> {
+        self.check_account_exists(account_id).await?;
+        Ok(self.resources.db.fetch_account_tx_ids(account_id).await?)
+    }
+
     /// Request a receiver transaction be generated from the supplied Sender Message
     async fn get_recipient_transaction(
         &mut self,
@@ -501,7 +789,7 @@ where
         );
 
         let (utxos, _, _) = self
-            .select_utxos(amount, fee_per_gram, num_outputs as usize, None)
+            .select_utxos(DEFAULT_ACCOUNT_ID, amount, fee_per_gram, num_outputs as usize, None)
             .await?;
         debug!(target: LOG_TARGET, "{} utxos selected.", utxos.len());
 
@@ -511,22 +799,27 @@ where
         Ok(fee)
     }
 
-    /// Prepare a Sender Transaction Protocol for the amount and fee_per_gram specified. If required a change output
-    /// will be produced.
+    /// Prepare a Sender Transaction Protocol for the amount and fee_per_gram specified, funded from the outputs of the
+    /// specified account. If required a change output will be produced for that account. The recipient's output will
+    /// be created with the provided output features, e.g. a maturity that keeps it locked until a future block height.
+    #[allow(clippy::too_many_arguments)]
     pub async fn prepare_transaction_to_send(
         &mut self,
+        account_id: AccountId,
         tx_id: TxId,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         lock_height: Option<u64>,
         message: String,
         recipient_script: TariScript,
+        recipient_output_features: OutputFeatures,
     ) -> Result<SenderTransactionProtocol, OutputManagerError> {
         debug!(
             target: LOG_TARGET,
             "Preparing to send transaction. Amount: {}. Fee per gram: {}. ", amount, fee_per_gram,
         );
-        let (outputs, _, total) = self.select_utxos(amount, fee_per_gram, 1, None).await?;
+        self.check_account_exists(account_id).await?;
+        let (outputs, _, total) = self.select_utxos(account_id, amount, fee_per_gram, 1, None).await?;
 
         let offset = PrivateKey::random(&mut OsRng);
         let nonce = PrivateKey::random(&mut OsRng);
@@ -542,7 +835,7 @@ where
                 0,
                 recipient_script,
                 PrivateKey::random(&mut OsRng),
-                Default::default(),
+                recipient_output_features,
                 PrivateKey::random(&mut OsRng),
             )
             .with_message(message)
@@ -569,10 +862,10 @@ where
             let (spending_key, script_private_key) = self
                 .resources
                 .master_key_manager
-                .get_next_spend_and_script_key()
+                .get_next_account_spend_and_script_key(account_id)
                 .await?;
             builder.with_change_secret(spending_key);
-            builder.with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone());
+            builder.with_rewindable_outputs(self.resources.master_key_manager.account_rewind_data(account_id));
             builder.with_change_script(
                 script!(Nop),
                 inputs!(PublicKey::from_secret_key(&script_private_key)),
@@ -592,10 +885,9 @@ where
                     "There should be a change output metadata signature available".to_string(),
                 )
             })?;
-            change_output.push(DbUnblindedOutput::from_unblinded_output(
-                unblinded_output,
-                &self.resources.factories,
-            )?);
+            let mut output = DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?;
+            output.account_id = account_id;
+            change_output.push(output);
         }
 
         // The Transaction Protocol built successfully so we will pull the unspent outputs out of the unspent list and
@@ -614,6 +906,223 @@ where
         Ok(stp)
     }
 
+    /// Select the inputs for a transaction that will be signed by an offline signer. Only the key indices and public
+    /// data of the inputs are returned. The inputs are fully encumbered straight away because signing can outlive a
+    /// short term encumberance. If change is required a key index is reserved for the change output, which is only
+    /// added once the signed transaction is imported.
+    async fn prepare_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+    ) -> Result<(Vec<OfflineInput>, Option<u64>), OutputManagerError> {
+        debug!(
+            target: LOG_TARGET,
+            "Preparing offline transaction (TxId: {}). Amount: {}. Fee per gram: {}. ", tx_id, amount, fee_per_gram,
+        );
+        // Outputs that were not derived from the wallet's key chain (e.g. coinbases and one-sided payments) cannot be
+        // signed for by the offline signer, so only derivable outputs are considered for selection
+        let unspent = self
+            .resources
+            .db
+            .fetch_sorted_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|o| o.account_id == DEFAULT_ACCOUNT_ID)
+            .collect::<Vec<_>>();
+        let spending_keys = unspent
+            .iter()
+            .map(|o| o.unblinded_output.spending_key.clone())
+            .collect::<Vec<_>>();
+        let found_indices = self
+            .resources
+            .master_key_manager
+            .find_issued_utxo_key_indices(&spending_keys)
+            .await?;
+        let mut key_indices = HashMap::new();
+        let mut candidates = Vec::with_capacity(unspent.len());
+        for (uo, key_index) in unspent.into_iter().zip(found_indices) {
+            match key_index {
+                Some(key_index) => {
+                    key_indices.insert(uo.commitment.to_vec(), key_index);
+                    candidates.push(uo);
+                },
+                None => trace!(
+                    target: LOG_TARGET,
+                    "Output {} is not derived from the key chain and cannot be spent offline",
+                    uo.commitment.to_hex()
+                ),
+            }
+        }
+
+        let (outputs, _, total) = self
+            .select_utxos_from(DEFAULT_ACCOUNT_ID, candidates, amount, fee_per_gram, 1, None)
+            .await?;
+
+        let mut inputs = Vec::with_capacity(outputs.len());
+        for uo in outputs.iter() {
+            let output = &uo.unblinded_output;
+            inputs.push(OfflineInput {
+                key_index: key_indices[&uo.commitment.to_vec()],
+                value: output.value,
+                features: output.features.clone(),
+                script: output.script.clone(),
+                input_data: output.input_data.clone(),
+                sender_offset_public_key: output.sender_offset_public_key.clone(),
+                metadata_signature: output.metadata_signature.clone(),
+            });
+        }
+
+        let fee_without_change = Fee::calculate(fee_per_gram, 1, outputs.len(), 1);
+        let change_key_index = if total > amount + fee_without_change {
+            Some(self.resources.master_key_manager.get_next_key_index().await?)
+        } else {
+            None
+        };
+
+        self.resources.db.encumber_outputs(tx_id, outputs, Vec::new()).await?;
+        self.confirm_encumberance(tx_id).await?;
+
+        debug!(target: LOG_TARGET, "Prepared offline transaction (TxId: {})", tx_id);
+        Ok((inputs, change_key_index))
+    }
+
+    /// Build the Sender Transaction Protocol for a transaction prepared by an online wallet. The keys of the inputs and
+    /// the change output are derived from this wallet's seed, so the inputs do not need to be in the local database.
+    async fn sign_offline_transaction(
+        &mut self,
+        unsigned: UnsignedTransaction,
+    ) -> Result<SenderTransactionProtocol, OutputManagerError> {
+        debug!(
+            target: LOG_TARGET,
+            "Signing offline transaction (TxId: {}) with {} inputs",
+            unsigned.tx_id,
+            unsigned.inputs.len()
+        );
+        let recipient_script = if unsigned.one_sided {
+            script!(PushPubKey(Box::new(unsigned.destination_public_key.clone())))
+        } else {
+            script!(Nop)
+        };
+
+        let mut builder = SenderTransactionProtocol::builder(1);
+        builder
+            .with_lock_height(unsigned.lock_height.unwrap_or(0))
+            .with_fee_per_gram(unsigned.fee_per_gram)
+            .with_offset(PrivateKey::random(&mut OsRng))
+            .with_private_nonce(PrivateKey::random(&mut OsRng))
+            .with_amount(0, unsigned.amount)
+            .with_recipient_data(
+                0,
+                recipient_script,
+                PrivateKey::random(&mut OsRng),
+                Default::default(),
+                PrivateKey::random(&mut OsRng),
+            )
+            .with_message(unsigned.message.clone())
+            .with_prevent_fee_gt_amount(self.resources.config.prevent_fee_gt_amount)
+            .with_tx_id(unsigned.tx_id);
+
+        for input in unsigned.inputs {
+            let (spending_key, script_private_key) = self
+                .resources
+                .master_key_manager
+                .get_spend_and_script_key_at_index(input.key_index)
+                .await?;
+            let output = UnblindedOutput::new(
+                input.value,
+                spending_key,
+                input.features,
+                input.script,
+                input.input_data,
+                script_private_key,
+                input.sender_offset_public_key,
+                input.metadata_signature,
+            );
+            builder.with_input(
+                output.as_transaction_input(&self.resources.factories.commitment)?,
+                output,
+            );
+        }
+
+        if let Some(index) = unsigned.change_key_index {
+            let (spending_key, script_private_key) = self
+                .resources
+                .master_key_manager
+                .get_spend_and_script_key_at_index(index)
+                .await?;
+            builder.with_change_secret(spending_key);
+            builder.with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone());
+            builder.with_change_script(
+                script!(Nop),
+                inputs!(PublicKey::from_secret_key(&script_private_key)),
+                script_private_key,
+            );
+        }
+
+        builder
+            .build::<HashDigest>(&self.resources.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))
+    }
+
+    /// Add the change output of a signed offline transaction as an output to be received. The change output is found
+    /// by rewinding the outputs of the transaction with this wallet's rewind data. The signer may have dropped the
+    /// change output if the change would not have covered its own fee, so a missing change output is not an error. The
+    /// inputs were already fully encumbered when the transaction was prepared.
+    async fn confirm_offline_transaction(
+        &mut self,
+        tx_id: TxId,
+        transaction: Transaction,
+        change_key_index: Option<u64>,
+    ) -> Result<(), OutputManagerError> {
+        if let Some(index) = change_key_index {
+            let (spending_key, script_private_key) = self
+                .resources
+                .master_key_manager
+                .get_spend_and_script_key_at_index(index)
+                .await?;
+            let rewind_data = self.resources.master_key_manager.rewind_data();
+            let change_output = transaction.body.outputs().iter().find_map(|output| {
+                output
+                    .full_rewind_range_proof(
+                        &self.resources.factories.range_proof,
+                        &rewind_data.rewind_key,
+                        &rewind_data.rewind_blinding_key,
+                    )
+                    .ok()
+                    .filter(|rewound| rewound.blinding_factor == spending_key)
+                    .map(|rewound| (rewound.committed_value, output.clone()))
+            });
+            match change_output {
+                Some((value, output)) => {
+                    let unblinded_output = UnblindedOutput::new(
+                        value,
+                        spending_key,
+                        output.features,
+                        output.script,
+                        inputs!(PublicKey::from_secret_key(&script_private_key)),
+                        script_private_key,
+                        output.sender_offset_public_key,
+                        output.metadata_signature,
+                    );
+                    let db_output =
+                        DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?;
+                    self.resources
+                        .db
+                        .add_output_to_be_received(tx_id, db_output, None)
+                        .await?;
+                },
+                None => debug!(
+                    target: LOG_TARGET,
+                    "Offline transaction (TxId: {}) was signed without a change output", tx_id
+                ),
+            }
+        }
+
+        debug!(target: LOG_TARGET, "Confirmed offline transaction (TxId: {})", tx_id);
+        Ok(())
+    }
+
     /// Request a Coinbase transaction for a specific block height. All existing pending transactions with
     /// this blockheight will be cancelled.
     /// The key will be derived from the coinbase specific keychain using the blockheight as an index. The coinbase
@@ -690,15 +1199,25 @@ where
         Ok(tx)
     }
 
+    /// Create a transaction that pays the specified amount from the outputs of one account to a new output of another
+    /// (or the same) account of this wallet
+    #[allow(clippy::too_many_arguments)]
     async fn create_pay_to_self_transaction(
         &mut self,
         tx_id: TxId,
+        from_account_id: AccountId,
+        to_account_id: AccountId,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         lock_height: Option<u64>,
         message: String,
+        strategy: Option<UTXOSelectionStrategy>,
     ) -> Result<(MicroTari, Transaction), OutputManagerError> {
-        let (inputs, _, total) = self.select_utxos(amount, fee_per_gram, 1, None).await?;
+        self.check_account_exists(from_account_id).await?;
+        self.check_account_exists(to_account_id).await?;
+        let (inputs, _, total) = self
+            .select_utxos(from_account_id, amount, fee_per_gram, 1, strategy)
+            .await?;
 
         let offset = PrivateKey::random(&mut OsRng);
         let nonce = PrivateKey::random(&mut OsRng);
@@ -728,7 +1247,7 @@ where
         let (spending_key, script_private_key) = self
             .resources
             .master_key_manager
-            .get_next_spend_and_script_key()
+            .get_next_account_spend_and_script_key(to_account_id)
             .await?;
         let metadata_signature = TransactionOutput::create_final_metadata_signature(
             &amount,
@@ -737,7 +1256,7 @@ where
             &output_features,
             &sender_offset_private_key,
         )?;
-        let utxo = DbUnblindedOutput::from_unblinded_output(
+        let mut utxo = DbUnblindedOutput::from_unblinded_output(
             UnblindedOutput::new(
                 amount,
                 spending_key.clone(),
@@ -750,6 +1269,7 @@ where
             ),
             &self.resources.factories,
         )?;
+        utxo.account_id = to_account_id;
         builder
             .with_output(utxo.unblinded_output.clone(), sender_offset_private_key.clone())
             .map_err(|e| OutputManagerError::BuildError(e.message))?;
@@ -762,10 +1282,12 @@ where
             let (spending_key, script_private_key) = self
                 .resources
                 .master_key_manager
-                .get_next_spend_and_script_key()
+                .get_next_account_spend_and_script_key(from_account_id)
                 .await?;
             builder.with_change_secret(spending_key);
-            builder.with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone());
+            // The rewind data applies to every output, so the proof message marks the receiving account. Recovery finds
+            // the change output of the sending account by searching the key chains of the other accounts.
+            builder.with_rewindable_outputs(self.resources.master_key_manager.account_rewind_data(to_account_id));
             builder.with_change_script(
                 script!(Nop),
                 inputs!(PublicKey::from_secret_key(&script_private_key)),
@@ -784,7 +1306,9 @@ where
                     "There should be a change output metadata signature available".to_string(),
                 )
             })?;
-            let change_output = DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?;
+            let mut change_output =
+                DbUnblindedOutput::from_unblinded_output(unblinded_output, &self.resources.factories)?;
+            change_output.account_id = from_account_id;
 
             outputs.push(change_output);
         }
@@ -818,7 +1342,9 @@ where
             target: LOG_TARGET,
             "Cancelling pending transaction outputs for TxId: {}", tx_id
         );
-        Ok(self.resources.db.cancel_pending_transaction_outputs(tx_id).await?)
+        self.resources.db.cancel_pending_transaction_outputs(tx_id).await?;
+        // If the transaction was spending an HTLC output the HTLC can be spent again
+        Ok(self.resources.db.cancel_htlc_spend(tx_id).await?)
     }
 
     /// Restore the pending transaction encumberance and output for an inbound transaction that was previously
@@ -829,10 +1355,12 @@ where
         Ok(())
     }
 
-    /// Select which unspent transaction outputs to use to send a transaction of the specified amount. Use the specified
-    /// selection strategy to choose the outputs. It also determines if a change output is required.
+    /// Select which unspent transaction outputs of the specified account to use to send a transaction of the specified
+    /// amount. Use the specified selection strategy to choose the outputs. It also determines if a change output is
+    /// required.
     async fn select_utxos(
         &mut self,
+        account_id: AccountId,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         output_count: usize,
@@ -846,13 +1374,34 @@ where
             output_count,
             strategy
         );
+        let uo = self
+            .resources
+            .db
+            .fetch_sorted_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|o| o.account_id == account_id)
+            .collect::<Vec<_>>();
+
+        self.select_utxos_from(account_id, uo, amount, fee_per_gram, output_count, strategy)
+            .await
+    }
+
+    /// Select UTXOs from the provided candidate outputs of an account, which must be sorted by value.
+    async fn select_utxos_from(
+        &mut self,
+        account_id: AccountId,
+        uo: Vec<DbUnblindedOutput>,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        output_count: usize,
+        strategy: Option<UTXOSelectionStrategy>,
+    ) -> Result<(Vec<DbUnblindedOutput>, bool, MicroTari), OutputManagerError> {
         let mut utxos = Vec::new();
         let mut utxos_total_value = MicroTari::from(0);
         let mut fee_without_change = MicroTari::from(0);
         let mut fee_with_change = MicroTari::from(0);
 
-        let uo = self.resources.db.fetch_sorted_unspent_outputs().await?;
-
         // Attempt to get the chain tip height
         let chain_metadata = self.base_node_service.get_chain_metadata().await?;
         let (connected, tip_height) = match &chain_metadata {
@@ -946,7 +1495,9 @@ where
 
         if !perfect_utxo_selection && !enough_spendable {
             let current_tip_for_time_lock_calculation = chain_metadata.map(|cm| cm.height_of_longest_chain());
-            let balance = self.get_balance(current_tip_for_time_lock_calculation).await?;
+            let balance = self
+                .get_account_balance(account_id, current_tip_for_time_lock_calculation)
+                .await?;
             let pending_incoming = balance.pending_incoming_balance;
             if utxos_total_value + pending_incoming >= amount + fee_with_change {
                 return Err(OutputManagerError::FundsPending);
@@ -991,6 +1542,7 @@ where
         let total_split_amount = amount_per_split * split_count as u64;
         let (inputs, require_change_output, utxos_total_value) = self
             .select_utxos(
+                DEFAULT_ACCOUNT_ID,
                 total_split_amount,
                 fee_per_gram,
                 output_count,
@@ -1093,6 +1645,208 @@ where
         Ok((tx_id, tx, fee, utxos_total_value))
     }
 
+    /// Import an HTLC output that was sent to this wallet. The spending key is derived in the same way as for a
+    /// one-sided payment and the commitment is checked against it before the HTLC is stored.
+    async fn import_htlc(
+        &mut self,
+        details: HtlcDetails,
+        recipient_private_key: PrivateKey,
+        tx_id: TxId,
+        fee_per_gram: MicroTari,
+    ) -> Result<HtlcOutput, OutputManagerError> {
+        if PublicKey::from_secret_key(&recipient_private_key) != details.recipient {
+            return Err(HtlcError::NotTheRecipient.into());
+        }
+        let spending_key = PrivateKey::from_bytes(
+            CommsPublicKey::shared_secret(&recipient_private_key, &details.sender_offset_public_key).as_bytes(),
+        )?;
+        if self
+            .resources
+            .factories
+            .commitment
+            .commit_value(&spending_key, details.value.into()) !=
+            details.commitment
+        {
+            return Err(HtlcError::CommitmentMismatch.into());
+        }
+
+        let unblinded_output = UnblindedOutput::new(
+            details.value,
+            spending_key,
+            details.features.clone(),
+            details.script(),
+            htlc::refund_input_data(),
+            recipient_private_key,
+            details.sender_offset_public_key.clone(),
+            details.metadata_signature.clone(),
+        );
+        let hash = unblinded_output
+            .as_transaction_output(&self.resources.factories)?
+            .hash();
+        let preimage = self.resources.db.get_htlc_preimage(details.hash_lock.to_vec()).await?;
+        let htlc = HtlcOutput {
+            commitment: details.commitment.clone(),
+            hash,
+            tx_id,
+            role: HtlcRole::Recipient,
+            status: HtlcStatus::Unconfirmed,
+            unblinded_output,
+            hash_lock: details.hash_lock.to_vec(),
+            preimage,
+            timeout_height: details.timeout_height,
+            counterparty: details.sender,
+            fee_per_gram,
+            mined_height: None,
+            mined_mmr_position: None,
+            spent_in_tx_id: None,
+        };
+        self.resources.db.add_htlc(htlc.clone()).await?;
+        info!(
+            target: LOG_TARGET,
+            "Imported HTLC output {} with timeout height {}",
+            htlc.commitment.to_hex(),
+            htlc.timeout_height
+        );
+        Ok(htlc)
+    }
+
+    async fn create_htlc_preimage(&mut self) -> Result<([u8; 32], Vec<u8>), OutputManagerError> {
+        let mut preimage = vec![0u8; HTLC_PREIMAGE_LENGTH];
+        OsRng.fill_bytes(&mut preimage);
+        let hash_lock = self.add_htlc_preimage(preimage.clone()).await?;
+        Ok((hash_lock, preimage))
+    }
+
+    async fn add_htlc_preimage(&mut self, preimage: Vec<u8>) -> Result<[u8; 32], OutputManagerError> {
+        let hash_lock = htlc::hash_preimage(&preimage)?;
+        self.resources
+            .db
+            .add_htlc_preimage(hash_lock.to_vec(), preimage)
+            .await?;
+        Ok(hash_lock)
+    }
+
+    /// Claim (as the recipient, before the timeout) or refund (as the sender, from the timeout) an HTLC output into a
+    /// new output of this wallet. `tip_height` is the current height of the chain; the transaction is built to be
+    /// valid in the next block.
+    async fn create_htlc_spend_transaction(
+        &mut self,
+        commitment: Commitment,
+        tip_height: u64,
+    ) -> Result<(u64, Transaction, MicroTari, MicroTari), OutputManagerError> {
+        let htlc = self
+            .resources
+            .db
+            .get_htlc(commitment.clone())
+            .await?
+            .ok_or_else(|| HtlcError::HtlcNotFound(commitment.to_hex()))?;
+        if htlc.status != HtlcStatus::Unspent {
+            return Err(HtlcError::NotSpendable(htlc.status.to_string()).into());
+        }
+        let spend_height = tip_height + 1;
+        let (input_data, lock_height) = match htlc.role {
+            HtlcRole::Recipient => {
+                if spend_height >= htlc.timeout_height {
+                    return Err(HtlcError::TimeoutPassed(htlc.timeout_height).into());
+                }
+                let preimage = htlc.preimage.as_ref().ok_or(HtlcError::PreimageUnknown)?;
+                (htlc::claim_input_data(preimage)?, 0)
+            },
+            HtlcRole::Sender => {
+                if spend_height < htlc.timeout_height {
+                    return Err(HtlcError::TimeoutNotReached(htlc.timeout_height).into());
+                }
+                (htlc::refund_input_data(), htlc.timeout_height)
+            },
+        };
+        let mut htlc_output = htlc.unblinded_output.clone();
+        htlc_output.input_data = input_data;
+
+        let fee = Fee::calculate(htlc.fee_per_gram, 1, 1, 1);
+        let output_amount = htlc_output.value.checked_sub(fee).ok_or(HtlcError::ValueTooSmall)?;
+
+        let offset = PrivateKey::random(&mut OsRng);
+        let nonce = PrivateKey::random(&mut OsRng);
+        let mut builder = SenderTransactionProtocol::builder(0);
+        builder
+            .with_lock_height(lock_height)
+            .with_fee_per_gram(htlc.fee_per_gram)
+            .with_offset(offset)
+            .with_private_nonce(nonce)
+            .with_rewindable_outputs(self.resources.master_key_manager.rewind_data().clone())
+            .with_input(
+                htlc_output.as_transaction_input(&self.resources.factories.commitment)?,
+                htlc_output,
+            );
+
+        let (spending_key, script_private_key) = self
+            .resources
+            .master_key_manager
+            .get_next_spend_and_script_key()
+            .await?;
+        let sender_offset_private_key = PrivateKey::random(&mut OsRng);
+        let script = script!(Nop);
+        let output_features = OutputFeatures::default();
+        let metadata_signature = TransactionOutput::create_final_metadata_signature(
+            &output_amount,
+            &spending_key,
+            &script,
+            &output_features,
+            &sender_offset_private_key,
+        )?;
+        let utxo = DbUnblindedOutput::from_unblinded_output(
+            UnblindedOutput::new(
+                output_amount,
+                spending_key,
+                output_features,
+                script,
+                inputs!(PublicKey::from_secret_key(&script_private_key)),
+                script_private_key,
+                PublicKey::from_secret_key(&sender_offset_private_key),
+                metadata_signature,
+            ),
+            &self.resources.factories,
+        )?;
+        builder
+            .with_output(utxo.unblinded_output.clone(), sender_offset_private_key)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+
+        let mut stp = builder
+            .build::<HashDigest>(&self.resources.factories)
+            .map_err(|e| OutputManagerError::BuildError(e.message))?;
+        let tx_id = stp.get_tx_id()?;
+        // The HTLC script checks the height it is executed at, so the transaction is validated at the height of the
+        // block it is expected to be mined in
+        stp.finalize_at_height(
+            KernelFeatures::empty(),
+            &self.resources.factories,
+            None,
+            Some(spend_height),
+        )?;
+        let tx = stp.take_transaction()?;
+
+        self.resources
+            .db
+            .encumber_outputs(tx_id, Vec::new(), vec![utxo])
+            .await?;
+        self.confirm_encumberance(tx_id).await?;
+        self.resources
+            .db
+            .set_htlc_status(commitment, HtlcStatus::Spending, Some(tx_id))
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Created transaction (TxId: {}) to {} HTLC output {}",
+            tx_id,
+            match htlc.role {
+                HtlcRole::Recipient => "claim",
+                HtlcRole::Sender => "refund",
+            },
+            htlc.commitment.to_hex()
+        );
+        Ok((tx_id, tx, fee, htlc.unblinded_output.value))
+    }
+
     /// Persist a one-sided payment script for a Comms Public/Private key. These are the scripts that this wallet knows
     /// to look for when scanning for one-sided payments
     async fn add_known_script(&mut self, known_script: KnownOneSidedPaymentScript) -> Result<(), OutputManagerError> {
@@ -23,7 +23,15 @@
 use crate::output_manager_service::{
     error::OutputManagerStorageError,
     service::Balance,
-    storage::models::{DbUnblindedOutput, KnownOneSidedPaymentScript, OutputStatus},
+    storage::models::{
+        Account,
+        AccountId,
+        DbUnblindedOutput,
+        HtlcOutput,
+        HtlcStatus,
+        KnownOneSidedPaymentScript,
+        OutputStatus,
+    },
     TxId,
 };
 use aes_gcm::Aes256Gcm;
@@ -34,6 +42,7 @@ use std::{
 };
 use tari_common_types::types::{BlindingFactor, Commitment, HashOutput, PrivateKey};
 use tari_core::transactions::transaction::TransactionOutput;
+use tari_crypto::tari_utilities::hex::Hex;
 
 const LOG_TARGET: &str = "wallet::output_manager_service::database";
 
@@ -123,6 +132,32 @@ pub trait OutputManagerBackend: Send + Sync + Clone {
         &self,
         current_tip_for_time_lock_calculation: Option<u64>,
     ) -> Result<Balance, OutputManagerStorageError>;
+    /// Mark an HTLC output as mined at the given height and MMR position
+    fn set_htlc_mined(
+        &self,
+        commitment: &Commitment,
+        mined_height: u64,
+        mmr_position: u64,
+    ) -> Result<(), OutputManagerStorageError>;
+    /// Update the status of an HTLC output and the transaction that spends it
+    fn set_htlc_status(
+        &self,
+        commitment: &Commitment,
+        status: HtlcStatus,
+        spent_in_tx_id: Option<TxId>,
+    ) -> Result<(), OutputManagerStorageError>;
+    /// Return an HTLC output that is being spent by the specified transaction to the unspent state
+    fn cancel_htlc_spend(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError>;
+    /// This method will set the currently stored key index of an account's key chains
+    fn set_account_key_index(&self, account_id: AccountId, index: u64) -> Result<(), OutputManagerStorageError>;
+    /// Return the available, time locked, pending incoming and pending outgoing balance of the outputs of one account
+    fn get_account_balance(
+        &self,
+        account_id: AccountId,
+        current_tip_for_time_lock_calculation: Option<u64>,
+    ) -> Result<Balance, OutputManagerStorageError>;
+    /// Return the ids of the transactions that received or spent outputs of the account
+    fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerStorageError>;
 }
 
 /// Holds the state of the KeyManager being used by the Output Manager Service
@@ -145,6 +180,11 @@ pub enum DbKey {
     InvalidOutputs,
     KnownOneSidedPaymentScripts,
     OutputsByTxIdAndStatus(TxId, OutputStatus),
+    Htlcs,
+    Htlc(Commitment),
+    HtlcPreimage(Vec<u8>),
+    HtlcPreimages,
+    Accounts,
 }
 
 #[derive(Debug)]
@@ -158,6 +198,11 @@ pub enum DbValue {
     KnownOneSidedPaymentScripts(Vec<KnownOneSidedPaymentScript>),
     AnyOutput(Box<DbUnblindedOutput>),
     AnyOutputs(Vec<DbUnblindedOutput>),
+    Htlcs(Vec<HtlcOutput>),
+    Htlc(Box<HtlcOutput>),
+    HtlcPreimage(Vec<u8>),
+    HtlcPreimages(Vec<(Vec<u8>, Vec<u8>)>),
+    Accounts(Vec<Account>),
 }
 
 pub enum DbKeyValuePair {
@@ -166,6 +211,9 @@ pub enum DbKeyValuePair {
     OutputToBeReceived(Commitment, (TxId, Box<DbUnblindedOutput>, Option<u64>)),
     KeyManagerState(KeyManagerState),
     KnownOneSidedPaymentScripts(KnownOneSidedPaymentScript),
+    Htlc(Commitment, Box<HtlcOutput>),
+    HtlcPreimage(Vec<u8>, Vec<u8>),
+    Account(Account),
 }
 
 pub enum WriteOperation {
@@ -187,6 +235,11 @@ where T: OutputManagerBackend + 'static
         Self { db: Arc::new(db) }
     }
 
+    /// The backend of the database, for the operations that are specific to it
+    pub(crate) fn backend(&self) -> &T {
+        &self.db
+    }
+
     pub async fn get_key_manager_state(&self) -> Result<Option<KeyManagerState>, OutputManagerStorageError> {
         let db_clone = self.db.clone();
         tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::KeyManagerState) {
@@ -589,6 +642,184 @@ where T: OutputManagerBackend + 'static
             .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
         Ok(())
     }
+
+    pub async fn add_htlc(&self, htlc: HtlcOutput) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::Htlc(
+                htlc.commitment.clone(),
+                Box::new(htlc),
+            )))
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_htlcs(&self) -> Result<Vec<HtlcOutput>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        let htlcs = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Htlcs) {
+            Ok(None) => log_error(
+                DbKey::Htlcs,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve HTLCs".to_string()),
+            ),
+            Ok(Some(DbValue::Htlcs(htlcs))) => Ok(htlcs),
+            Ok(Some(other)) => unexpected_result(DbKey::Htlcs, other),
+            Err(e) => log_error(DbKey::Htlcs, e),
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(htlcs)
+    }
+
+    pub async fn get_htlc(&self, commitment: Commitment) -> Result<Option<HtlcOutput>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        let htlc = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Htlc(commitment.clone())) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::Htlc(htlc))) => Ok(Some(*htlc)),
+            Ok(Some(other)) => unexpected_result(DbKey::Htlc(commitment), other),
+            Err(e) => log_error(DbKey::Htlc(commitment), e),
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(htlc)
+    }
+
+    pub async fn add_htlc_preimage(
+        &self,
+        hash_lock: Vec<u8>,
+        preimage: Vec<u8>,
+    ) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::HtlcPreimage(
+                hash_lock, preimage,
+            )))
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_htlc_preimage(&self, hash_lock: Vec<u8>) -> Result<Option<Vec<u8>>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        let preimage =
+            tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::HtlcPreimage(hash_lock.clone())) {
+                Ok(None) => Ok(None),
+                Ok(Some(DbValue::HtlcPreimage(preimage))) => Ok(Some(preimage)),
+                Ok(Some(other)) => unexpected_result(DbKey::HtlcPreimage(hash_lock), other),
+                Err(e) => log_error(DbKey::HtlcPreimage(hash_lock), e),
+            })
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(preimage)
+    }
+
+    /// All known HTLC preimages as (hash lock, preimage) pairs, including those of HTLCs that have not been seen yet
+    pub async fn get_htlc_preimages(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        let preimages = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::HtlcPreimages) {
+            Ok(None) => log_error(
+                DbKey::HtlcPreimages,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve HTLC preimages".to_string()),
+            ),
+            Ok(Some(DbValue::HtlcPreimages(preimages))) => Ok(preimages),
+            Ok(Some(other)) => unexpected_result(DbKey::HtlcPreimages, other),
+            Err(e) => log_error(DbKey::HtlcPreimages, e),
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(preimages)
+    }
+
+    pub async fn set_htlc_mined(
+        &self,
+        commitment: Commitment,
+        mined_height: u64,
+        mmr_position: u64,
+    ) -> Result<(), OutputManagerStorageError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || db.set_htlc_mined(&commitment, mined_height, mmr_position))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn set_htlc_status(
+        &self,
+        commitment: Commitment,
+        status: HtlcStatus,
+        spent_in_tx_id: Option<TxId>,
+    ) -> Result<(), OutputManagerStorageError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || db.set_htlc_status(&commitment, status, spent_in_tx_id))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn cancel_htlc_spend(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || db.cancel_htlc_spend(tx_id))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    /// Retrieves the accounts of the wallet, not including the default account, ordered by id
+    pub async fn get_accounts(&self) -> Result<Vec<Account>, OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        let accounts = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::Accounts) {
+            Ok(None) => log_error(
+                DbKey::Accounts,
+                OutputManagerStorageError::UnexpectedResult("Could not retrieve accounts".to_string()),
+            ),
+            Ok(Some(DbValue::Accounts(accounts))) => Ok(accounts),
+            Ok(Some(other)) => unexpected_result(DbKey::Accounts, other),
+            Err(e) => log_error(DbKey::Accounts, e),
+        })
+        .await
+        .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(accounts)
+    }
+
+    pub async fn add_account(&self, account: Account) -> Result<(), OutputManagerStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Insert(DbKeyValuePair::Account(account))))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn set_account_key_index(
+        &self,
+        account_id: AccountId,
+        index: u64,
+    ) -> Result<(), OutputManagerStorageError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || db.set_account_key_index(account_id, index))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_account_balance(
+        &self,
+        account_id: AccountId,
+        current_tip_for_time_lock_calculation: Option<u64>,
+    ) -> Result<Balance, OutputManagerStorageError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || db.get_account_balance(account_id, current_tip_for_time_lock_calculation))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerStorageError> {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || db.fetch_account_tx_ids(account_id))
+            .await
+            .map_err(|err| OutputManagerStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
 }
 
 fn unexpected_result<T>(req: DbKey, res: DbValue) -> Result<T, OutputManagerStorageError> {
@@ -610,6 +841,11 @@ impl Display for DbKey {
             DbKey::KnownOneSidedPaymentScripts => f.write_str("Known claiming scripts"),
             DbKey::AnyOutputByCommitment(_) => f.write_str("AnyOutputByCommitment"),
             DbKey::OutputsByTxIdAndStatus(_, _) => f.write_str("OutputsByTxIdAndStatus"),
+            DbKey::Htlcs => f.write_str("HTLCs"),
+            DbKey::Htlc(c) => f.write_str(&format!("HTLC: {}", c.to_hex())),
+            DbKey::HtlcPreimage(h) => f.write_str(&format!("HTLC preimage: {}", h.to_hex())),
+            DbKey::HtlcPreimages => f.write_str("HTLC preimages"),
+            DbKey::Accounts => f.write_str("Accounts"),
         }
     }
 }
@@ -626,6 +862,11 @@ impl Display for DbValue {
             DbValue::KnownOneSidedPaymentScripts(_) => f.write_str("Known claiming scripts"),
             DbValue::AnyOutput(_) => f.write_str("Any Output"),
             DbValue::AnyOutputs(_) => f.write_str("Any Outputs"),
+            DbValue::Htlcs(_) => f.write_str("HTLCs"),
+            DbValue::Htlc(_) => f.write_str("HTLC"),
+            DbValue::HtlcPreimage(_) => f.write_str("HTLC preimage"),
+            DbValue::HtlcPreimages(_) => f.write_str("HTLC preimages"),
+            DbValue::Accounts(_) => f.write_str("Accounts"),
         }
     }
 }
@@ -20,12 +20,22 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::output_manager_service::error::OutputManagerStorageError;
-use std::cmp::Ordering;
-use tari_common_types::types::{BlockHash, Commitment, HashOutput, PrivateKey};
+use crate::output_manager_service::{error::OutputManagerStorageError, TxId};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    fmt,
+    fmt::{Display, Formatter},
+};
+use tari_common_types::types::{BlockHash, Commitment, HashOutput, PrivateKey, PublicKey};
 use tari_core::{
     tari_utilities::hash::Hashable,
-    transactions::{transaction::UnblindedOutput, transaction_protocol::RewindData, CryptoFactories},
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::UnblindedOutput,
+        transaction_protocol::RewindData,
+        CryptoFactories,
+    },
 };
 use tari_crypto::script::{ExecutionStack, TariScript};
 
@@ -39,6 +49,7 @@ pub struct DbUnblindedOutput {
     pub mined_mmr_position: Option<u64>,
     pub marked_deleted_at_height: Option<u64>,
     pub marked_deleted_in_block: Option<BlockHash>,
+    pub account_id: AccountId,
 }
 
 impl DbUnblindedOutput {
@@ -56,6 +67,7 @@ impl DbUnblindedOutput {
             mined_mmr_position: None,
             marked_deleted_at_height: None,
             marked_deleted_in_block: None,
+            account_id: DEFAULT_ACCOUNT_ID,
         })
     }
 
@@ -74,6 +86,7 @@ impl DbUnblindedOutput {
             mined_mmr_position: None,
             marked_deleted_at_height: None,
             marked_deleted_in_block: None,
+            account_id: DEFAULT_ACCOUNT_ID,
         })
     }
 }
@@ -104,6 +117,23 @@ impl Ord for DbUnblindedOutput {
 
 impl Eq for DbUnblindedOutput {}
 
+/// The identifier of an account, each account has its own UTXO and script key chains and its own balance
+pub type AccountId = u64;
+
+/// The account that holds every output that is not explicitly assigned to another account. It uses the wallet's
+/// original key chains, so a wallet that never creates an account behaves as it did before accounts existed.
+pub const DEFAULT_ACCOUNT_ID: AccountId = 0;
+pub const DEFAULT_ACCOUNT_NAME: &str = "default";
+
+/// A named account within the wallet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub id: AccountId,
+    pub name: String,
+    /// The current index of the account's UTXO and script key chains
+    pub key_index: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct KnownOneSidedPaymentScript {
     pub script_hash: Vec<u8>,
@@ -118,6 +148,71 @@ impl PartialEq for KnownOneSidedPaymentScript {
     }
 }
 
+/// A hash time-locked contract output that this wallet can either claim or refund, see
+/// [htlc](crate::output_manager_service::htlc)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtlcOutput {
+    pub commitment: Commitment,
+    pub hash: HashOutput,
+    /// The transaction that created the HTLC output
+    pub tx_id: TxId,
+    pub role: HtlcRole,
+    pub status: HtlcStatus,
+    /// The output with this wallet's script private key and an empty input stack, which is filled in when spending
+    pub unblinded_output: UnblindedOutput,
+    pub hash_lock: Vec<u8>,
+    /// The preimage of the hash lock, if this wallet knows it
+    pub preimage: Option<Vec<u8>>,
+    pub timeout_height: u64,
+    pub counterparty: PublicKey,
+    /// The fee per gram used when claiming or refunding the HTLC
+    pub fee_per_gram: MicroTari,
+    pub mined_height: Option<u64>,
+    pub mined_mmr_position: Option<u64>,
+    /// The transaction that claims or refunds the HTLC output, if this wallet spent it
+    pub spent_in_tx_id: Option<TxId>,
+}
+
+/// Whether this wallet created an HTLC output (and can refund it) or received it (and can claim it)
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HtlcRole {
+    Sender,
+    Recipient,
+}
+
+impl Display for HtlcRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HtlcRole::Sender => write!(f, "Sender"),
+            HtlcRole::Recipient => write!(f, "Recipient"),
+        }
+    }
+}
+
+/// The status of an HTLC output
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HtlcStatus {
+    /// The HTLC output has not been found on the blockchain yet
+    Unconfirmed,
+    /// The HTLC output has been mined and not been spent
+    Unspent,
+    /// A transaction that claims or refunds the HTLC output has been broadcast
+    Spending,
+    /// The HTLC output has been spent, either by this wallet or the counterparty
+    Spent,
+}
+
+impl Display for HtlcStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HtlcStatus::Unconfirmed => write!(f, "Unconfirmed"),
+            HtlcStatus::Unspent => write!(f, "Unspent"),
+            HtlcStatus::Spending => write!(f, "Spending"),
+            HtlcStatus::Spent => write!(f, "Spent"),
+        }
+    }
+}
+
 /// The status of a given output
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum OutputStatus {
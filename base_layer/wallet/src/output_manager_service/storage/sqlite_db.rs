@@ -23,14 +23,24 @@
 use crate::{
     output_manager_service::{
         error::OutputManagerStorageError,
+        htlc::HTLC_PREIMAGE_LENGTH,
         service::Balance,
         storage::{
             database::{DbKey, DbKeyValuePair, DbValue, KeyManagerState, OutputManagerBackend, WriteOperation},
-            models::{DbUnblindedOutput, KnownOneSidedPaymentScript, OutputStatus},
+            models::{
+                Account,
+                AccountId,
+                DbUnblindedOutput,
+                HtlcOutput,
+                HtlcRole,
+                HtlcStatus,
+                KnownOneSidedPaymentScript,
+                OutputStatus,
+            },
         },
         TxId,
     },
-    schema::{key_manager_states, known_one_sided_payment_scripts, outputs},
+    schema::{accounts, htlc_preimages, htlcs, key_manager_states, known_one_sided_payment_scripts, outputs},
     storage::sqlite_utilities::WalletDbConnection,
     util::{
         diesel_ext::ExpectedRowsExtension,
@@ -70,7 +80,7 @@ const LOG_TARGET: &str = "wallet::output_manager_service::database::sqlite_db";
 #[derive(Clone)]
 pub struct OutputManagerSqliteDatabase {
     database_connection: WalletDbConnection,
-    cipher: Arc<RwLock<Option<Aes256Gcm>>>,
+    pub(crate) cipher: Arc<RwLock<Option<Aes256Gcm>>>,
 }
 
 impl OutputManagerSqliteDatabase {
@@ -140,11 +150,107 @@ impl OutputManagerSqliteDatabase {
                 self.encrypt_if_necessary(&mut script_sql)?;
                 script_sql.commit(&(*conn))?
             },
+            DbKeyValuePair::Htlc(c, htlc) => {
+                if HtlcSql::find(&c.to_vec(), &(*conn)).is_ok() {
+                    return Err(OutputManagerStorageError::DuplicateOutput);
+                }
+                let mut htlc_sql = HtlcSql::try_from(*htlc)?;
+                self.encrypt_if_necessary(&mut htlc_sql)?;
+                htlc_sql.commit(&(*conn))?
+            },
+            DbKeyValuePair::HtlcPreimage(hash_lock, preimage) => {
+                // A preimage can be learnt more than once, e.g. when importing it after it was revealed on the other
+                // chain, so keep the first one
+                if HtlcPreimageSql::find(&hash_lock, &(*conn)).is_ok() {
+                    return Ok(());
+                }
+                let mut preimage_sql = HtlcPreimageSql { hash_lock, preimage };
+                self.encrypt_if_necessary(&mut preimage_sql)?;
+                preimage_sql.commit(&(*conn))?
+            },
+            DbKeyValuePair::Account(account) => {
+                if AccountSql::find_by_name(&account.name, &(*conn)).is_ok() {
+                    return Err(OutputManagerStorageError::DuplicateAccount(account.name));
+                }
+                AccountSql::from(account).commit(&(*conn))?
+            },
+        }
+        Ok(())
+    }
+
+    /// Decrypt an HTLC record and attach the preimage of its hash lock if it is known
+    fn htlc_from_sql(
+        &self,
+        mut htlc: HtlcSql,
+        conn: &SqliteConnection,
+    ) -> Result<HtlcOutput, OutputManagerStorageError> {
+        self.decrypt_if_necessary(&mut htlc)?;
+        let preimage = match HtlcPreimageSql::find(&htlc.hash_lock, conn) {
+            Ok(mut p) => {
+                self.decrypt_if_necessary(&mut p)?;
+                Some(p.preimage)
+            },
+            Err(OutputManagerStorageError::DieselError(DieselError::NotFound)) => None,
+            Err(e) => return Err(e),
+        };
+        let mut htlc = HtlcOutput::try_from(htlc)?;
+        htlc.preimage = preimage;
+        Ok(htlc)
+    }
+
+    /// Re-encrypt every encrypted record from `old_cipher` to `new_cipher`. The caller is responsible for running this
+    /// inside a database transaction and for making sure no backend holding the old cipher is in use.
+    pub(crate) fn reencrypt(
+        conn: &SqliteConnection,
+        old_cipher: &Aes256Gcm,
+        new_cipher: &Aes256Gcm,
+    ) -> Result<(), OutputManagerStorageError> {
+        for mut o in OutputSql::index(conn)? {
+            reencrypt_record(&mut o, old_cipher, new_cipher)?;
+            o.update_encryption(conn)?;
+        }
+
+        match KeyManagerStateSql::get_state(conn) {
+            Ok(mut key_manager_state) => {
+                reencrypt_record(&mut key_manager_state, old_cipher, new_cipher)?;
+                key_manager_state.set_state(conn)?;
+            },
+            Err(OutputManagerStorageError::KeyManagerNotInitialized) => (),
+            Err(e) => return Err(e),
+        }
+
+        for mut script in KnownOneSidedPaymentScriptSql::index(conn)? {
+            reencrypt_record(&mut script, old_cipher, new_cipher)?;
+            script.update_encryption(conn)?;
+        }
+
+        for mut htlc in HtlcSql::index(conn)? {
+            reencrypt_record(&mut htlc, old_cipher, new_cipher)?;
+            htlc.update_encryption(conn)?;
+        }
+
+        for mut preimage in HtlcPreimageSql::index(conn)? {
+            reencrypt_record(&mut preimage, old_cipher, new_cipher)?;
+            preimage.update_encryption(conn)?;
         }
+
         Ok(())
     }
 }
 
+fn reencrypt_record<T: Encryptable<Aes256Gcm>>(
+    record: &mut T,
+    old_cipher: &Aes256Gcm,
+    new_cipher: &Aes256Gcm,
+) -> Result<(), OutputManagerStorageError> {
+    record
+        .decrypt(old_cipher)
+        .map_err(|_| OutputManagerStorageError::AeadError("Decryption Error".to_string()))?;
+    record
+        .encrypt(new_cipher)
+        .map_err(|_| OutputManagerStorageError::AeadError("Encryption Error".to_string()))
+}
+
 impl OutputManagerBackend for OutputManagerSqliteDatabase {
     #[allow(clippy::cognitive_complexity)]
     fn fetch(&self, key: &DbKey) -> Result<Option<DbValue>, OutputManagerStorageError> {
@@ -282,6 +388,37 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                         .collect::<Result<Vec<_>, _>>()?,
                 ))
             },
+            DbKey::Htlcs => Some(DbValue::Htlcs(
+                HtlcSql::index(&(*conn))?
+                    .into_iter()
+                    .map(|h| self.htlc_from_sql(h, &(*conn)))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::Htlc(commitment) => match HtlcSql::find(&commitment.to_vec(), &(*conn)) {
+                Ok(h) => Some(DbValue::Htlc(Box::new(self.htlc_from_sql(h, &(*conn))?))),
+                Err(OutputManagerStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::HtlcPreimage(hash_lock) => match HtlcPreimageSql::find(hash_lock, &(*conn)) {
+                Ok(mut p) => {
+                    self.decrypt_if_necessary(&mut p)?;
+                    Some(DbValue::HtlcPreimage(p.preimage))
+                },
+                Err(OutputManagerStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::HtlcPreimages => Some(DbValue::HtlcPreimages(
+                HtlcPreimageSql::index(&(*conn))?
+                    .into_iter()
+                    .map(|mut p| {
+                        self.decrypt_if_necessary(&mut p)?;
+                        Ok((p.hash_lock, p.preimage))
+                    })
+                    .collect::<Result<Vec<_>, OutputManagerStorageError>>()?,
+            )),
+            DbKey::Accounts => Some(DbValue::Accounts(
+                AccountSql::index(&(*conn))?.into_iter().map(Account::from).collect(),
+            )),
         };
 
         Ok(result)
@@ -362,6 +499,11 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
                 DbKey::TimeLockedUnspentOutputs(_) => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::KnownOneSidedPaymentScripts => return Err(OutputManagerStorageError::OperationNotSupported),
                 DbKey::OutputsByTxIdAndStatus(_, _) => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::Htlcs => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::Htlc(_) => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::HtlcPreimage(_) => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::HtlcPreimages => return Err(OutputManagerStorageError::OperationNotSupported),
+                DbKey::Accounts => return Err(OutputManagerStorageError::OperationNotSupported),
             },
         }
 
@@ -642,6 +784,105 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
         OutputSql::get_balance(current_tip_for_time_lock_calculation, &(*conn))
     }
 
+    fn set_htlc_mined(
+        &self,
+        commitment: &Commitment,
+        mined_height: u64,
+        mmr_position: u64,
+    ) -> Result<(), OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        diesel::update(
+            htlcs::table.filter(
+                htlcs::commitment
+                    .eq(commitment.to_vec())
+                    .and(htlcs::status.eq(HtlcStatus::Unconfirmed as i32)),
+            ),
+        )
+        .set((
+            htlcs::mined_height.eq(mined_height as i64),
+            htlcs::mined_mmr_position.eq(mmr_position as i64),
+            htlcs::status.eq(HtlcStatus::Unspent as i32),
+        ))
+        .execute(&(*conn))
+        .num_rows_affected_or_not_found(1)?;
+
+        Ok(())
+    }
+
+    fn set_htlc_status(
+        &self,
+        commitment: &Commitment,
+        status: HtlcStatus,
+        spent_in_tx_id: Option<TxId>,
+    ) -> Result<(), OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        diesel::update(htlcs::table.filter(htlcs::commitment.eq(commitment.to_vec())))
+            .set((
+                htlcs::status.eq(status as i32),
+                htlcs::spent_in_tx_id.eq(spent_in_tx_id.map(|id| id as i64)),
+            ))
+            .execute(&(*conn))
+            .num_rows_affected_or_not_found(1)?;
+
+        Ok(())
+    }
+
+    fn cancel_htlc_spend(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        // Most transactions do not spend an HTLC so no rows being updated is not an error
+        diesel::update(
+            htlcs::table.filter(
+                htlcs::spent_in_tx_id
+                    .eq(tx_id as i64)
+                    .and(htlcs::status.eq(HtlcStatus::Spending as i32)),
+            ),
+        )
+        .set((
+            htlcs::status.eq(HtlcStatus::Unspent as i32),
+            htlcs::spent_in_tx_id.eq::<Option<i64>>(None),
+        ))
+        .execute(&(*conn))?;
+
+        Ok(())
+    }
+
+    fn set_account_key_index(&self, account_id: AccountId, index: u64) -> Result<(), OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        diesel::update(accounts::table.filter(accounts::id.eq(account_id as i64)))
+            .set(accounts::key_index.eq(index as i64))
+            .execute(&(*conn))
+            .num_rows_affected_or_not_found(1)?;
+
+        Ok(())
+    }
+
+    fn get_account_balance(
+        &self,
+        account_id: AccountId,
+        current_tip_for_time_lock_calculation: Option<u64>,
+    ) -> Result<Balance, OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+
+        OutputSql::get_account_balance(account_id, current_tip_for_time_lock_calculation, &(*conn))
+    }
+
+    fn fetch_account_tx_ids(&self, account_id: AccountId) -> Result<Vec<TxId>, OutputManagerStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let tx_ids = outputs::table
+            .filter(outputs::account_id.eq(account_id as i64))
+            .select((outputs::received_in_tx_id, outputs::spent_in_tx_id))
+            .load::<(Option<i64>, Option<i64>)>(&(*conn))?;
+
+        let mut tx_ids = tx_ids
+            .into_iter()
+            .flat_map(|(received, spent)| received.into_iter().chain(spent))
+            .map(|tx_id| tx_id as TxId)
+            .collect::<Vec<_>>();
+        tx_ids.sort_unstable();
+        tx_ids.dedup();
+        Ok(tx_ids)
+    }
+
     fn cancel_pending_transaction(&self, tx_id: TxId) -> Result<(), OutputManagerStorageError> {
         let conn = self.database_connection.acquire_lock();
 
@@ -808,6 +1049,37 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             script.update_encryption(&conn)?;
         }
 
+        let mut htlcs = HtlcSql::index(&conn)?;
+
+        for htlc in htlcs.iter_mut() {
+            let _ = PrivateKey::from_vec(&htlc.spending_key).map_err(|_| {
+                error!(
+                    target: LOG_TARGET,
+                    "Could not create PrivateKey from stored bytes, They might already be encrypted"
+                );
+                OutputManagerStorageError::AlreadyEncrypted
+            })?;
+            htlc.encrypt(&cipher)
+                .map_err(|_| OutputManagerStorageError::AeadError("Encryption Error".to_string()))?;
+            htlc.update_encryption(&conn)?;
+        }
+
+        let mut preimages = HtlcPreimageSql::index(&conn)?;
+
+        for preimage in preimages.iter_mut() {
+            if preimage.preimage.len() != HTLC_PREIMAGE_LENGTH {
+                error!(
+                    target: LOG_TARGET,
+                    "Stored HTLC preimage has an unexpected length, It might already be encrypted"
+                );
+                return Err(OutputManagerStorageError::AlreadyEncrypted);
+            }
+            preimage
+                .encrypt(&cipher)
+                .map_err(|_| OutputManagerStorageError::AeadError("Encryption Error".to_string()))?;
+            preimage.update_encryption(&conn)?;
+        }
+
         (*current_cipher) = Some(cipher);
 
         Ok(())
@@ -844,6 +1116,23 @@ impl OutputManagerBackend for OutputManagerSqliteDatabase {
             script.update_encryption(&conn)?;
         }
 
+        let mut htlcs = HtlcSql::index(&conn)?;
+
+        for htlc in htlcs.iter_mut() {
+            htlc.decrypt(&cipher)
+                .map_err(|_| OutputManagerStorageError::AeadError("Encryption Error".to_string()))?;
+            htlc.update_encryption(&conn)?;
+        }
+
+        let mut preimages = HtlcPreimageSql::index(&conn)?;
+
+        for preimage in preimages.iter_mut() {
+            preimage
+                .decrypt(&cipher)
+                .map_err(|_| OutputManagerStorageError::AeadError("Encryption Error".to_string()))?;
+            preimage.update_encryption(&conn)?;
+        }
+
         // Now that all the decryption has been completed we can safely remove the cipher fully
         let _ = (*current_cipher).take();
         Ok(())
@@ -891,6 +1180,7 @@ struct NewOutputSql {
     metadata_signature_v_key: Vec<u8>,
     received_in_tx_id: Option<i64>,
     coinbase_block_height: Option<i64>,
+    account_id: i64,
 }
 
 impl NewOutputSql {
@@ -917,6 +1207,7 @@ impl NewOutputSql {
             metadata_signature_u_key: output.unblinded_output.metadata_signature.u().to_vec(),
             metadata_signature_v_key: output.unblinded_output.metadata_signature.v().to_vec(),
             coinbase_block_height: coinbase_block_height.map(|bh| bh as i64),
+            account_id: output.account_id as i64,
         })
     }
 
@@ -967,6 +1258,7 @@ struct OutputSql {
     received_in_tx_id: Option<i64>,
     spent_in_tx_id: Option<i64>,
     coinbase_block_height: Option<i64>,
+    account_id: i64,
 }
 
 impl OutputSql {
@@ -1140,6 +1432,48 @@ impl OutputSql {
         })
     }
 
+    /// Calculate the balance of a single account. This uses the same output statuses as `get_balance`.
+    pub fn get_account_balance(
+        account_id: AccountId,
+        current_tip_for_time_lock_calculation: Option<u64>,
+        conn: &SqliteConnection,
+    ) -> Result<Balance, OutputManagerStorageError> {
+        let outputs = outputs::table
+            .filter(outputs::account_id.eq(account_id as i64))
+            .select((outputs::value, outputs::status, outputs::maturity))
+            .load::<(i64, i32, i64)>(conn)?;
+
+        let mut available_balance = MicroTari::from(0);
+        let mut time_locked_balance = MicroTari::from(0);
+        let mut pending_incoming_balance = MicroTari::from(0);
+        let mut pending_outgoing_balance = MicroTari::from(0);
+        for (value, status, maturity) in outputs {
+            let value = MicroTari::from(value as u64);
+            match OutputStatus::try_from(status)? {
+                OutputStatus::Unspent => {
+                    available_balance += value;
+                    if current_tip_for_time_lock_calculation.map_or(false, |tip| maturity as u64 > tip) {
+                        time_locked_balance += value;
+                    }
+                },
+                OutputStatus::EncumberedToBeReceived |
+                OutputStatus::ShortTermEncumberedToBeReceived |
+                OutputStatus::UnspentMinedUnconfirmed => pending_incoming_balance += value,
+                OutputStatus::EncumberedToBeSpent |
+                OutputStatus::ShortTermEncumberedToBeSpent |
+                OutputStatus::SpentMinedUnconfirmed => pending_outgoing_balance += value,
+                _ => {},
+            }
+        }
+
+        Ok(Balance {
+            available_balance,
+            time_locked_balance: current_tip_for_time_lock_calculation.map(|_| time_locked_balance),
+            pending_incoming_balance,
+            pending_outgoing_balance,
+        })
+    }
+
     pub fn find_by_commitment(
         commitment: &[u8],
         conn: &SqliteConnection,
@@ -1348,6 +1682,7 @@ impl TryFrom<OutputSql> for DbUnblindedOutput {
             mined_mmr_position: o.mined_mmr_position.map(|mp| mp as u64),
             marked_deleted_at_height: o.marked_deleted_at_height.map(|d| d as u64),
             marked_deleted_in_block: o.marked_deleted_in_block,
+            account_id: o.account_id as AccountId,
         })
     }
 }
@@ -1385,6 +1720,7 @@ impl From<OutputSql> for NewOutputSql {
             metadata_signature_v_key: o.metadata_signature_v_key,
             received_in_tx_id: o.received_in_tx_id,
             coinbase_block_height: o.coinbase_block_height,
+            account_id: o.account_id,
         }
     }
 }
@@ -1753,6 +2089,302 @@ impl Encryptable<Aes256Gcm> for KnownOneSidedPaymentScriptSql {
     }
 }
 
+impl TryFrom<i32> for HtlcRole {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(HtlcRole::Sender),
+            1 => Ok(HtlcRole::Recipient),
+            _ => Err(OutputManagerStorageError::ConversionError),
+        }
+    }
+}
+
+impl TryFrom<i32> for HtlcStatus {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(HtlcStatus::Unconfirmed),
+            1 => Ok(HtlcStatus::Unspent),
+            2 => Ok(HtlcStatus::Spending),
+            3 => Ok(HtlcStatus::Spent),
+            _ => Err(OutputManagerStorageError::ConversionError),
+        }
+    }
+}
+
+/// This struct represents an HTLC output in the Sql database. The input data of the output is not stored as it depends
+/// on whether the output is claimed or refunded.
+#[derive(Clone, Debug, Queryable, Insertable, Identifiable, PartialEq)]
+#[table_name = "htlcs"]
+#[primary_key(commitment)]
+struct HtlcSql {
+    commitment: Vec<u8>,
+    hash: Vec<u8>,
+    tx_id: i64,
+    role: i32,
+    status: i32,
+    value: i64,
+    spending_key: Vec<u8>,
+    flags: i32,
+    maturity: i64,
+    script: Vec<u8>,
+    script_private_key: Vec<u8>,
+    sender_offset_public_key: Vec<u8>,
+    metadata_signature_nonce: Vec<u8>,
+    metadata_signature_u_key: Vec<u8>,
+    metadata_signature_v_key: Vec<u8>,
+    hash_lock: Vec<u8>,
+    timeout_height: i64,
+    counterparty: Vec<u8>,
+    fee_per_gram: i64,
+    mined_height: Option<i64>,
+    mined_mmr_position: Option<i64>,
+    spent_in_tx_id: Option<i64>,
+}
+
+/// These are the fields of an HTLC output that are updated when encryption is applied or removed
+#[derive(AsChangeset)]
+#[table_name = "htlcs"]
+struct UpdateHtlcEncryptionSql {
+    spending_key: Vec<u8>,
+    script_private_key: Vec<u8>,
+}
+
+impl HtlcSql {
+    /// Write this struct to the database
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(htlcs::table).values(self.clone()).execute(conn)?;
+        Ok(())
+    }
+
+    /// Find a particular HTLC output by its commitment, if it exists
+    pub fn find(commitment: &[u8], conn: &SqliteConnection) -> Result<HtlcSql, OutputManagerStorageError> {
+        Ok(htlcs::table
+            .filter(htlcs::commitment.eq(commitment))
+            .first::<HtlcSql>(conn)?)
+    }
+
+    /// Return all HTLC outputs
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<HtlcSql>, OutputManagerStorageError> {
+        Ok(htlcs::table.load::<HtlcSql>(conn)?)
+    }
+
+    /// Update the changed fields of this record after encryption/decryption is performed
+    pub fn update_encryption(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::update(htlcs::table.filter(htlcs::commitment.eq(&self.commitment)))
+            .set(UpdateHtlcEncryptionSql {
+                spending_key: self.spending_key.clone(),
+                script_private_key: self.script_private_key.clone(),
+            })
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+}
+
+impl TryFrom<HtlcSql> for HtlcOutput {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(o: HtlcSql) -> Result<Self, Self::Error> {
+        let private_key = |bytes: &[u8]| {
+            PrivateKey::from_bytes(bytes).map_err(|_| {
+                error!(
+                    target: LOG_TARGET,
+                    "Could not create PrivateKey from stored bytes, They might be encrypted"
+                );
+                OutputManagerStorageError::ConversionError
+            })
+        };
+        let unblinded_output = UnblindedOutput::new(
+            MicroTari::from(o.value as u64),
+            private_key(&o.spending_key)?,
+            OutputFeatures {
+                flags: OutputFlags::from_bits(o.flags as u8).ok_or(OutputManagerStorageError::ConversionError)?,
+                maturity: o.maturity as u64,
+            },
+            TariScript::from_bytes(o.script.as_slice())?,
+            ExecutionStack::default(),
+            private_key(&o.script_private_key)?,
+            PublicKey::from_bytes(&o.sender_offset_public_key)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            ComSignature::new(
+                Commitment::from_bytes(&o.metadata_signature_nonce)
+                    .map_err(|_| OutputManagerStorageError::ConversionError)?,
+                private_key(&o.metadata_signature_u_key)?,
+                private_key(&o.metadata_signature_v_key)?,
+            ),
+        );
+
+        Ok(Self {
+            commitment: Commitment::from_bytes(&o.commitment)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            hash: o.hash,
+            tx_id: o.tx_id as u64,
+            role: HtlcRole::try_from(o.role)?,
+            status: HtlcStatus::try_from(o.status)?,
+            unblinded_output,
+            hash_lock: o.hash_lock,
+            preimage: None,
+            timeout_height: o.timeout_height as u64,
+            counterparty: PublicKey::from_bytes(&o.counterparty)
+                .map_err(|_| OutputManagerStorageError::ConversionError)?,
+            fee_per_gram: MicroTari::from(o.fee_per_gram as u64),
+            mined_height: o.mined_height.map(|h| h as u64),
+            mined_mmr_position: o.mined_mmr_position.map(|p| p as u64),
+            spent_in_tx_id: o.spent_in_tx_id.map(|id| id as u64),
+        })
+    }
+}
+
+impl TryFrom<HtlcOutput> for HtlcSql {
+    type Error = OutputManagerStorageError;
+
+    fn try_from(htlc: HtlcOutput) -> Result<Self, Self::Error> {
+        let output = htlc.unblinded_output;
+        Ok(Self {
+            commitment: htlc.commitment.to_vec(),
+            hash: htlc.hash,
+            tx_id: htlc.tx_id as i64,
+            role: htlc.role as i32,
+            status: htlc.status as i32,
+            value: u64::from(output.value) as i64,
+            spending_key: output.spending_key.to_vec(),
+            flags: output.features.flags.bits() as i32,
+            maturity: output.features.maturity as i64,
+            script: output.script.as_bytes(),
+            script_private_key: output.script_private_key.to_vec(),
+            sender_offset_public_key: output.sender_offset_public_key.to_vec(),
+            metadata_signature_nonce: output.metadata_signature.public_nonce().to_vec(),
+            metadata_signature_u_key: output.metadata_signature.u().to_vec(),
+            metadata_signature_v_key: output.metadata_signature.v().to_vec(),
+            hash_lock: htlc.hash_lock,
+            timeout_height: htlc.timeout_height as i64,
+            counterparty: htlc.counterparty.to_vec(),
+            fee_per_gram: u64::from(htlc.fee_per_gram) as i64,
+            mined_height: htlc.mined_height.map(|h| h as i64),
+            mined_mmr_position: htlc.mined_mmr_position.map(|p| p as i64),
+            spent_in_tx_id: htlc.spent_in_tx_id.map(|id| id as i64),
+        })
+    }
+}
+
+impl Encryptable<Aes256Gcm> for HtlcSql {
+    fn encrypt(&mut self, cipher: &Aes256Gcm) -> Result<(), AeadError> {
+        self.spending_key = encrypt_bytes_integral_nonce(cipher, self.spending_key.clone())?;
+        self.script_private_key = encrypt_bytes_integral_nonce(cipher, self.script_private_key.clone())?;
+        Ok(())
+    }
+
+    fn decrypt(&mut self, cipher: &Aes256Gcm) -> Result<(), AeadError> {
+        self.spending_key = decrypt_bytes_integral_nonce(cipher, self.spending_key.clone())?;
+        self.script_private_key = decrypt_bytes_integral_nonce(cipher, self.script_private_key.clone())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Identifiable, PartialEq)]
+#[table_name = "htlc_preimages"]
+#[primary_key(hash_lock)]
+struct HtlcPreimageSql {
+    hash_lock: Vec<u8>,
+    preimage: Vec<u8>,
+}
+
+impl HtlcPreimageSql {
+    /// Write this struct to the database
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(htlc_preimages::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Find the preimage of a hash lock, if it is known
+    pub fn find(hash_lock: &[u8], conn: &SqliteConnection) -> Result<HtlcPreimageSql, OutputManagerStorageError> {
+        Ok(htlc_preimages::table
+            .filter(htlc_preimages::hash_lock.eq(hash_lock))
+            .first::<HtlcPreimageSql>(conn)?)
+    }
+
+    /// Return all known preimages
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<HtlcPreimageSql>, OutputManagerStorageError> {
+        Ok(htlc_preimages::table.load::<HtlcPreimageSql>(conn)?)
+    }
+
+    /// Update the preimage of this record after encryption/decryption is performed
+    pub fn update_encryption(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::update(htlc_preimages::table.filter(htlc_preimages::hash_lock.eq(&self.hash_lock)))
+            .set(htlc_preimages::preimage.eq(&self.preimage))
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+}
+
+impl Encryptable<Aes256Gcm> for HtlcPreimageSql {
+    fn encrypt(&mut self, cipher: &Aes256Gcm) -> Result<(), AeadError> {
+        self.preimage = encrypt_bytes_integral_nonce(cipher, self.preimage.clone())?;
+        Ok(())
+    }
+
+    fn decrypt(&mut self, cipher: &Aes256Gcm) -> Result<(), AeadError> {
+        self.preimage = decrypt_bytes_integral_nonce(cipher, self.preimage.clone())?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, Identifiable, PartialEq)]
+#[table_name = "accounts"]
+struct AccountSql {
+    id: i64,
+    name: String,
+    key_index: i64,
+}
+
+impl AccountSql {
+    /// Write this struct to the database
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), OutputManagerStorageError> {
+        diesel::insert_into(accounts::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find_by_name(name: &str, conn: &SqliteConnection) -> Result<AccountSql, OutputManagerStorageError> {
+        Ok(accounts::table
+            .filter(accounts::name.eq(name))
+            .first::<AccountSql>(conn)?)
+    }
+
+    /// Return all accounts ordered by id
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<AccountSql>, OutputManagerStorageError> {
+        Ok(accounts::table.order(accounts::id.asc()).load::<AccountSql>(conn)?)
+    }
+}
+
+impl From<Account> for AccountSql {
+    fn from(account: Account) -> Self {
+        Self {
+            id: account.id as i64,
+            name: account.name,
+            key_index: account.key_index as i64,
+        }
+    }
+}
+
+impl From<AccountSql> for Account {
+    fn from(account: AccountSql) -> Self {
+        Self {
+            id: account.id as AccountId,
+            name: account.name,
+            key_index: account.key_index as u64,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::convert::TryFrom;
@@ -1763,10 +2395,13 @@ mod test {
     };
     use diesel::{Connection, SqliteConnection};
     use rand::{rngs::OsRng, RngCore};
-    use tari_crypto::{keys::SecretKey, script};
+    use tari_crypto::{
+        keys::{PublicKey as PublicKeyTrait, SecretKey},
+        script,
+    };
     use tempfile::tempdir;
 
-    use tari_common_types::types::{CommitmentFactory, PrivateKey};
+    use tari_common_types::types::{CommitmentFactory, PrivateKey, PublicKey};
     use tari_core::transactions::{
         helpers::{create_unblinded_output, TestParams as TestParamsHelpers},
         tari_amount::MicroTari,
@@ -1777,8 +2412,8 @@ mod test {
 
     use crate::{
         output_manager_service::storage::{
-            database::{DbKey, KeyManagerState, OutputManagerBackend},
-            models::DbUnblindedOutput,
+            database::{DbKey, DbKeyValuePair, DbValue, KeyManagerState, OutputManagerBackend, WriteOperation},
+            models::{DbUnblindedOutput, HtlcOutput, HtlcRole, HtlcStatus},
             sqlite_db::{
                 KeyManagerStateSql,
                 NewKeyManagerStateSql,
@@ -2087,4 +2722,91 @@ mod test {
 
         assert!(db3.fetch(&DbKey::UnspentOutputs).is_ok());
     }
+
+    #[test]
+    fn test_htlc_crud_and_encryption() {
+        let db_name = format!("{}.sqlite3", random::string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+        let factories = CryptoFactories::default();
+        let connection = WalletDbConnection::new(conn, None);
+        let db = OutputManagerSqliteDatabase::new(connection, None);
+
+        let (_, uo) = make_input(MicroTari::from(1000));
+        let uo = DbUnblindedOutput::from_unblinded_output(uo, &factories).unwrap();
+        let hash_lock = vec![7u8; 32];
+        let preimage = vec![9u8; 32];
+        let htlc = HtlcOutput {
+            commitment: uo.commitment.clone(),
+            hash: uo.hash.clone(),
+            tx_id: 1,
+            role: HtlcRole::Recipient,
+            status: HtlcStatus::Unconfirmed,
+            unblinded_output: uo.unblinded_output.clone(),
+            hash_lock: hash_lock.clone(),
+            preimage: None,
+            timeout_height: 100,
+            counterparty: PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng)),
+            fee_per_gram: MicroTari::from(25),
+            mined_height: None,
+            mined_mmr_position: None,
+            spent_in_tx_id: None,
+        };
+        db.write(WriteOperation::Insert(DbKeyValuePair::Htlc(
+            htlc.commitment.clone(),
+            Box::new(htlc.clone()),
+        )))
+        .unwrap();
+        assert!(db
+            .write(WriteOperation::Insert(DbKeyValuePair::Htlc(
+                htlc.commitment.clone(),
+                Box::new(htlc.clone()),
+            )))
+            .is_err());
+
+        let fetch_htlc = |db: &OutputManagerSqliteDatabase| match db.fetch(&DbKey::Htlc(htlc.commitment.clone())) {
+            Ok(Some(DbValue::Htlc(h))) => *h,
+            _ => panic!("HTLC not found"),
+        };
+        let stored = fetch_htlc(&db);
+        assert_eq!(stored.unblinded_output.spending_key, htlc.unblinded_output.spending_key);
+        assert_eq!(stored.status, HtlcStatus::Unconfirmed);
+        assert!(stored.preimage.is_none());
+
+        db.write(WriteOperation::Insert(DbKeyValuePair::HtlcPreimage(
+            hash_lock.clone(),
+            preimage.clone(),
+        )))
+        .unwrap();
+        assert_eq!(fetch_htlc(&db).preimage, Some(preimage.clone()));
+
+        db.set_htlc_mined(&htlc.commitment, 10, 5).unwrap();
+        db.set_htlc_status(&htlc.commitment, HtlcStatus::Spending, Some(2))
+            .unwrap();
+        db.cancel_htlc_spend(3).unwrap();
+        assert_eq!(fetch_htlc(&db).status, HtlcStatus::Spending);
+        db.cancel_htlc_spend(2).unwrap();
+        let stored = fetch_htlc(&db);
+        assert_eq!(stored.status, HtlcStatus::Unspent);
+        assert_eq!(stored.mined_mmr_position, Some(5));
+        assert!(stored.spent_in_tx_id.is_none());
+
+        let key = GenericArray::from_slice(b"an example very very secret key.");
+        let cipher = Aes256Gcm::new(key);
+        db.apply_encryption(cipher).unwrap();
+        let stored = fetch_htlc(&db);
+        assert_eq!(stored.unblinded_output.spending_key, htlc.unblinded_output.spending_key);
+        assert_eq!(stored.preimage, Some(preimage));
+        db.remove_encryption().unwrap();
+        assert_eq!(
+            fetch_htlc(&db).unblinded_output.spending_key,
+            htlc.unblinded_output.spending_key
+        );
+    }
 }
@@ -27,7 +27,7 @@ use crate::{
         handle::{OutputManagerEvent, OutputManagerEventSender},
         storage::{
             database::{OutputManagerBackend, OutputManagerDatabase},
-            models::DbUnblindedOutput,
+            models::{DbUnblindedOutput, HtlcStatus},
         },
     },
 };
@@ -91,8 +91,10 @@ where
 
         self.update_unconfirmed_outputs(&mut base_node_client).await?;
 
-        self.update_spent_outputs(&mut base_node_client, last_mined_header)
+        self.update_spent_outputs(&mut base_node_client, last_mined_header.clone())
             .await?;
+
+        self.update_htlcs(&mut base_node_client, last_mined_header).await?;
         self.publish_event(OutputManagerEvent::TxoValidationSuccess(self.operation_id));
         Ok(self.operation_id)
     }
@@ -250,6 +252,84 @@ where
         Ok(())
     }
 
+    /// HTLC outputs are not kept in the outputs table, so they are tracked separately: unconfirmed HTLCs are marked as
+    /// mined once the base node knows their hash, and mined HTLCs are marked as spent once their MMR position is
+    /// deleted, whether they were spent by this wallet or by the counterparty.
+    async fn update_htlcs(
+        &self,
+        wallet_client: &mut BaseNodeWalletRpcClient,
+        last_mined_header_hash: Option<BlockHash>,
+    ) -> Result<(), OutputManagerProtocolError> {
+        let htlcs = self.db.get_htlcs().await.for_protocol(self.operation_id)?;
+
+        let unconfirmed = htlcs
+            .iter()
+            .filter(|h| h.status == HtlcStatus::Unconfirmed)
+            .collect::<Vec<_>>();
+        for batch in unconfirmed.chunks(self.config.tx_validator_batch_size) {
+            let response = wallet_client
+                .utxo_query(UtxoQueryRequest {
+                    output_hashes: batch.iter().map(|h| h.hash.clone()).collect(),
+                })
+                .await
+                .for_protocol(self.operation_id)?;
+            for returned_output in response.responses.iter() {
+                if let Some(htlc) = batch.iter().find(|h| h.hash == returned_output.output_hash) {
+                    info!(
+                        target: LOG_TARGET,
+                        "Updating HTLC comm:{} as mined at height {}",
+                        htlc.commitment.to_hex(),
+                        returned_output.mined_height
+                    );
+                    self.db
+                        .set_htlc_mined(
+                            htlc.commitment.clone(),
+                            returned_output.mined_height,
+                            returned_output.mmr_position,
+                        )
+                        .await
+                        .for_protocol(self.operation_id)?;
+                }
+            }
+        }
+
+        let mined = htlcs
+            .iter()
+            .filter(|h| h.status == HtlcStatus::Unspent || h.status == HtlcStatus::Spending)
+            .filter(|h| h.mined_mmr_position.is_some())
+            .collect::<Vec<_>>();
+        for batch in mined.chunks(self.config.tx_validator_batch_size) {
+            let response = wallet_client
+                .query_deleted(QueryDeletedRequest {
+                    chain_must_include_header: last_mined_header_hash.clone(),
+                    mmr_positions: batch.iter().filter_map(|h| h.mined_mmr_position).collect(),
+                    include_deleted_block_data: false,
+                })
+                .await
+                .for_protocol(self.operation_id)?;
+            for htlc in batch {
+                if htlc
+                    .mined_mmr_position
+                    .map(|p| response.deleted_positions.contains(&p))
+                    .unwrap_or(false)
+                {
+                    info!(
+                        target: LOG_TARGET,
+                        "Updating HTLC comm:{} as spent at tip height {}",
+                        htlc.commitment.to_hex(),
+                        response.height_of_longest_chain
+                    );
+                    self.db
+                        .set_htlc_status(htlc.commitment.clone(), HtlcStatus::Spent, htlc.spent_in_tx_id)
+                        .await
+                        .for_protocol(self.operation_id)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     // returns the last header found still in the chain
     async fn check_for_reorgs(
         &mut self,
@@ -0,0 +1,239 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    connectivity_service::WalletConnectivityInterface,
+    output_manager_service::{
+        config::UtxoConsolidationConfig,
+        error::OutputManagerError,
+        handle::{OutputManagerEvent, OutputManagerEventSender},
+        storage::{
+            database::{OutputManagerBackend, OutputManagerDatabase},
+            models::DEFAULT_ACCOUNT_ID,
+        },
+    },
+};
+use log::*;
+use serde::Serialize;
+use std::sync::Arc;
+use tari_core::transactions::{fee::Fee, tari_amount::MicroTari};
+
+const LOG_TARGET: &str = "wallet::output_service::utxo_consolidation_task";
+
+/// A single planned consolidation transaction that spends the `num_inputs` smallest spendable outputs into one output
+/// of `amount`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsolidationBatch {
+    pub num_inputs: usize,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+}
+
+/// Decides whether the wallet's smallest outputs should be consolidated right now and plans the pay-to-self
+/// transactions to do so. The transactions themselves are created by the Output Manager Service, which owns the keys.
+pub struct UtxoConsolidationTask<TBackend, TWalletConnectivity> {
+    db: OutputManagerDatabase<TBackend>,
+    connectivity: TWalletConnectivity,
+    event_publisher: OutputManagerEventSender,
+    config: UtxoConsolidationConfig,
+    max_network_fee_per_gram: MicroTari,
+}
+
+impl<TBackend, TWalletConnectivity> UtxoConsolidationTask<TBackend, TWalletConnectivity>
+where
+    TBackend: OutputManagerBackend + 'static,
+    TWalletConnectivity: WalletConnectivityInterface,
+{
+    pub fn new(
+        db: OutputManagerDatabase<TBackend>,
+        connectivity: TWalletConnectivity,
+        event_publisher: OutputManagerEventSender,
+        config: UtxoConsolidationConfig,
+    ) -> Self {
+        let max_network_fee_per_gram = config.max_network_fee_per_gram.unwrap_or(config.fee_per_gram);
+        Self {
+            db,
+            connectivity,
+            event_publisher,
+            config,
+            max_network_fee_per_gram,
+        }
+    }
+
+    /// Returns the consolidation transactions to create. Nothing is returned in dry-run mode, the plan is only logged
+    /// and published.
+    pub async fn execute(mut self, tip_height: Option<u64>) -> Result<Vec<ConsolidationBatch>, OutputManagerError> {
+        // Only outputs that select_utxos would consider can be consolidated, and only the default account is
+        // consolidated so that funds never move between accounts without the user asking
+        let values = self
+            .db
+            .fetch_sorted_unspent_outputs()
+            .await?
+            .into_iter()
+            .filter(|o| o.account_id == DEFAULT_ACCOUNT_ID)
+            .filter(|o| tip_height.map_or(true, |h| o.unblinded_output.features.maturity <= h))
+            .map(|o| o.unblinded_output.value)
+            .collect::<Vec<_>>();
+
+        if values.len() <= self.config.utxo_count_threshold {
+            trace!(
+                target: LOG_TARGET,
+                "{} spendable outputs, consolidation threshold is {}",
+                values.len(),
+                self.config.utxo_count_threshold
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut base_node_client = self
+            .connectivity
+            .obtain_base_node_wallet_rpc_client()
+            .await
+            .ok_or(OutputManagerError::Shutdown)?;
+        let stats = base_node_client.get_mempool_stats().await?;
+        let network_fee_per_gram = MicroTari::from(stats.min_fee_per_gram);
+        if network_fee_per_gram > self.max_network_fee_per_gram {
+            debug!(
+                target: LOG_TARGET,
+                "Postponing UTXO consolidation, the next block needs {} per gram which is above {}",
+                network_fee_per_gram,
+                self.max_network_fee_per_gram
+            );
+            return Ok(Vec::new());
+        }
+
+        let batches = plan_consolidation(&values, &self.config);
+        if batches.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "None of the {} spendable outputs can be consolidated at {} per gram",
+                values.len(),
+                self.config.fee_per_gram
+            );
+            return Ok(batches);
+        }
+
+        for batch in batches.iter() {
+            info!(
+                target: LOG_TARGET,
+                "{}Consolidating {} outputs into {} (fee {})",
+                if self.config.dry_run { "[dry run] " } else { "" },
+                batch.num_inputs,
+                batch.amount,
+                batch.fee
+            );
+        }
+
+        if self.config.dry_run {
+            self.publish_event(OutputManagerEvent::UtxoConsolidationPlanned(batches));
+            return Ok(Vec::new());
+        }
+
+        Ok(batches)
+    }
+
+    fn publish_event(&self, event: OutputManagerEvent) {
+        if let Err(e) = self.event_publisher.send(Arc::new(event)) {
+            debug!(
+                target: LOG_TARGET,
+                "Error sending event because there are no subscribers: {:?}", e
+            );
+        }
+    }
+}
+
+/// Plan the consolidation of the smallest outputs in `values`, which must be sorted in ascending order. Each batch
+/// spends the next smallest outputs so that selecting the smallest outputs for its amount uses exactly those outputs.
+/// Planning stops once the output count is back at the threshold, the transaction limit is reached or the next batch
+/// would not be worth its fee.
+fn plan_consolidation(values: &[MicroTari], config: &UtxoConsolidationConfig) -> Vec<ConsolidationBatch> {
+    let mut batches = Vec::new();
+    let mut utxo_count = values.len();
+    for chunk in values.chunks(config.max_inputs_per_transaction.max(2)) {
+        if batches.len() >= config.max_transactions_per_run || utxo_count <= config.utxo_count_threshold {
+            break;
+        }
+        if chunk.len() < 2 {
+            break;
+        }
+        let total = chunk.iter().fold(MicroTari::from(0), |acc, v| acc + *v);
+        let fee = Fee::calculate(config.fee_per_gram, 1, chunk.len(), 1);
+        // The outputs get larger with every chunk, so if this one is not worth consolidating neither are the rest
+        if total <= fee * 2 {
+            break;
+        }
+        batches.push(ConsolidationBatch {
+            num_inputs: chunk.len(),
+            amount: total - fee,
+            fee,
+        });
+        utxo_count -= chunk.len() - 1;
+    }
+    batches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(utxo_count_threshold: usize, max_inputs_per_transaction: usize) -> UtxoConsolidationConfig {
+        UtxoConsolidationConfig {
+            utxo_count_threshold,
+            max_inputs_per_transaction,
+            max_transactions_per_run: 3,
+            fee_per_gram: MicroTari::from(1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_plans_batches_of_the_smallest_outputs() {
+        let values = (1..=10).map(|v| MicroTari::from(v * 1000)).collect::<Vec<_>>();
+        let batches = plan_consolidation(&values, &config(5, 4));
+        assert_eq!(batches.len(), 2);
+        let fee = Fee::calculate(MicroTari::from(1), 1, 4, 1);
+        assert_eq!(batches[0], ConsolidationBatch {
+            num_inputs: 4,
+            amount: MicroTari::from(10_000) - fee,
+            fee,
+        });
+        assert_eq!(batches[1].num_inputs, 4);
+        assert_eq!(batches[1].amount, MicroTari::from(26_000) - fee);
+    }
+
+    #[test]
+    fn it_respects_the_transaction_limit() {
+        let values = (1..=100).map(|v| MicroTari::from(v * 1000)).collect::<Vec<_>>();
+        let mut config = config(5, 10);
+        config.max_transactions_per_run = 1;
+        let batches = plan_consolidation(&values, &config);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_inputs, 10);
+    }
+
+    #[test]
+    fn it_does_not_consolidate_dust_below_the_fee() {
+        let values = vec![MicroTari::from(1); 20];
+        let batches = plan_consolidation(&values, &config(5, 10));
+        assert!(batches.is_empty());
+    }
+}
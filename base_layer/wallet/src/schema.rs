@@ -1,3 +1,11 @@
+table! {
+    accounts (id) {
+        id -> BigInt,
+        name -> Text,
+        key_index -> BigInt,
+    }
+}
+
 table! {
     client_key_values (key) {
         key -> Text,
@@ -35,6 +43,51 @@ table! {
     }
 }
 
+table! {
+    held_transactions (tx_id) {
+        tx_id -> BigInt,
+        source_public_key -> Binary,
+        amount -> BigInt,
+        message -> Text,
+        sender_message -> Text,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    htlc_preimages (hash_lock) {
+        hash_lock -> Binary,
+        preimage -> Binary,
+    }
+}
+
+table! {
+    htlcs (commitment) {
+        commitment -> Binary,
+        hash -> Binary,
+        tx_id -> BigInt,
+        role -> Integer,
+        status -> Integer,
+        value -> BigInt,
+        spending_key -> Binary,
+        flags -> Integer,
+        maturity -> BigInt,
+        script -> Binary,
+        script_private_key -> Binary,
+        sender_offset_public_key -> Binary,
+        metadata_signature_nonce -> Binary,
+        metadata_signature_u_key -> Binary,
+        metadata_signature_v_key -> Binary,
+        hash_lock -> Binary,
+        timeout_height -> BigInt,
+        counterparty -> Binary,
+        fee_per_gram -> BigInt,
+        mined_height -> Nullable<BigInt>,
+        mined_mmr_position -> Nullable<BigInt>,
+        spent_in_tx_id -> Nullable<BigInt>,
+    }
+}
+
 table! {
     inbound_transactions (tx_id) {
         tx_id -> BigInt,
@@ -110,6 +163,38 @@ table! {
         received_in_tx_id -> Nullable<BigInt>,
         spent_in_tx_id -> Nullable<BigInt>,
         coinbase_block_height -> Nullable<BigInt>,
+        account_id -> BigInt,
+    }
+}
+
+table! {
+    payment_request_payments (tx_id) {
+        tx_id -> BigInt,
+        reference -> BigInt,
+        amount -> BigInt,
+    }
+}
+
+table! {
+    payment_requests (reference) {
+        reference -> BigInt,
+        recipient_public_key -> Binary,
+        amount -> BigInt,
+        message -> Text,
+        expiry_height -> BigInt,
+        signature_nonce -> Binary,
+        signature_key -> Binary,
+        status -> Integer,
+        amount_received -> BigInt,
+        timestamp -> Timestamp,
+    }
+}
+
+table! {
+    scheduled_transactions (tx_id) {
+        tx_id -> BigInt,
+        scheduled_height -> Nullable<BigInt>,
+        scheduled_time -> Nullable<Timestamp>,
     }
 }
 
@@ -121,13 +206,20 @@ table! {
 }
 
 allow_tables_to_appear_in_same_query!(
+    accounts,
     client_key_values,
     completed_transactions,
     contacts,
+    held_transactions,
+    htlc_preimages,
+    htlcs,
     inbound_transactions,
     key_manager_states,
     known_one_sided_payment_scripts,
     outbound_transactions,
     outputs,
+    payment_request_payments,
+    payment_requests,
+    scheduled_transactions,
     wallet_settings,
 );
@@ -0,0 +1,514 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A passphrase encrypted backup of the whole wallet. The backup file is a JSON envelope holding the format version,
+//! the key derivation scheme used for the passphrase, a checksum and the encrypted [WalletBackup].
+
+use crate::{
+    contacts_service::storage::database::{Contact, ContactsBackend, ContactsDatabase},
+    error::WalletBackupError,
+    output_manager_service::storage::{
+        database::{KeyManagerState, OutputManagerBackend, OutputManagerDatabase},
+        models::{Account, AccountId, DbUnblindedOutput, HtlcOutput, KnownOneSidedPaymentScript, DEFAULT_ACCOUNT_ID},
+    },
+    storage::{
+        database::{WalletBackend, WalletDatabase},
+        key_derivation::PassphraseKeyDerivation,
+    },
+    transaction_service::{
+        multisig::{MULTISIG_COORDINATOR_KEY_PREFIX, MULTISIG_SIGNER_KEY_PREFIX},
+        offline_signing::OFFLINE_SIGNING_STATE_KEY_PREFIX,
+        storage::{
+            database::{TransactionBackend, TransactionDatabase},
+            models::{CompletedTransaction, PaymentRequestRecord, PaymentRequestStatus, ScheduledTransaction},
+        },
+    },
+    util::encryption::{decrypt_bytes_integral_nonce, encrypt_bytes_integral_nonce},
+};
+use chrono::{NaiveDateTime, Utc};
+use digest::Digest;
+use log::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+use tari_common_types::types::PrivateKey;
+use tari_comms::types::{CommsPublicKey, CommsSecretKey};
+use tari_core::transactions::{tari_amount::MicroTari, transaction::UnblindedOutput, CryptoFactories};
+use tari_crypto::{
+    common::Blake256,
+    script::{ExecutionStack, TariScript},
+    tari_utilities::hex::{from_hex, Hex},
+};
+
+const LOG_TARGET: &str = "wallet::storage::backup";
+
+/// The current version of the backup format
+pub const WALLET_BACKUP_VERSION: u32 = 2;
+
+/// Client values that are left out of backups. Multisig and offline signing sessions hold secret nonces, which must
+/// never be used for a second signature, so a restored wallet has to start those sessions again.
+const EXCLUDED_CLIENT_VALUE_PREFIXES: [&str; 3] = [
+    MULTISIG_SIGNER_KEY_PREFIX,
+    MULTISIG_COORDINATOR_KEY_PREFIX,
+    OFFLINE_SIGNING_STATE_KEY_PREFIX,
+];
+
+/// The encrypted backup file. Everything but the payload is stored in the clear so that the file describes how to
+/// open it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedWalletBackup {
+    pub version: u32,
+    pub created_at: NaiveDateTime,
+    pub key_derivation: PassphraseKeyDerivation,
+    /// Hex encoded Blake256 hash of the ciphertext, which tells a damaged file apart from a wrong passphrase
+    pub checksum: String,
+    /// Hex encoded ciphertext of the JSON encoded [WalletBackup], with the nonce prepended
+    pub ciphertext: String,
+}
+
+impl EncryptedWalletBackup {
+    pub fn to_json(&self) -> Result<String, WalletBackupError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, WalletBackupError> {
+        let backup: Self = serde_json::from_str(json)?;
+        if backup.version > WALLET_BACKUP_VERSION {
+            return Err(WalletBackupError::UnsupportedVersion(backup.version));
+        }
+        Ok(backup)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), WalletBackupError> {
+        Ok(fs::write(path, self.to_json()?)?)
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, WalletBackupError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Check the integrity of the backup and decrypt it
+    pub fn decrypt(&self, passphrase: &str) -> Result<WalletBackup, WalletBackupError> {
+        let ciphertext = from_hex(&self.ciphertext)?;
+        if Blake256::digest(&ciphertext).to_vec().to_hex() != self.checksum {
+            return Err(WalletBackupError::ChecksumMismatch);
+        }
+
+        let cipher = self.key_derivation.derive_cipher(passphrase)?;
+        let plaintext =
+            decrypt_bytes_integral_nonce(&cipher, ciphertext).map_err(|_| WalletBackupError::IncorrectPassphrase)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+/// The contents of a wallet backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletBackup {
+    pub master_secret_key: CommsSecretKey,
+    pub key_manager_state: Option<BackupKeyManagerState>,
+    /// Client settings such as the custom base node, without the signing sessions in progress
+    pub client_values: BTreeMap<String, String>,
+    pub contacts: Vec<BackupContact>,
+    pub known_one_sided_payment_scripts: Vec<BackupKnownScript>,
+    /// The spendable outputs. Their mined status is not kept, the restored wallet validates them with its base node.
+    pub outputs: Vec<UnblindedOutput>,
+    /// The completed transaction history, including cancelled transactions
+    pub transactions: Vec<CompletedTransaction>,
+    /// The named accounts other than the default account
+    #[serde(default)]
+    pub accounts: Vec<BackupAccount>,
+    /// The account of each of `outputs`, outputs without an entry belong to the default account
+    #[serde(default)]
+    pub output_account_ids: Vec<AccountId>,
+    /// The day the wallet was created, used to start a recovery from the right height
+    #[serde(default)]
+    pub wallet_birthday: Option<u16>,
+    /// The HTLC outputs this wallet can claim or refund
+    #[serde(default)]
+    pub htlcs: Vec<HtlcOutput>,
+    /// The known HTLC preimages as (hash lock, preimage) pairs, including those of HTLCs that have not been seen yet
+    #[serde(default)]
+    pub htlc_preimages: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The payment requests issued by this wallet
+    #[serde(default)]
+    pub payment_requests: Vec<PaymentRequestRecord>,
+    /// The schedules of completed transactions that are held back from broadcast
+    #[serde(default)]
+    pub scheduled_transactions: Vec<ScheduledTransaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupAccount {
+    pub id: AccountId,
+    pub name: String,
+    pub key_index: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupKeyManagerState {
+    pub master_key: PrivateKey,
+    pub branch_seed: String,
+    pub primary_key_index: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupContact {
+    pub alias: String,
+    pub public_key: CommsPublicKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupKnownScript {
+    pub script_hash: Vec<u8>,
+    pub private_key: PrivateKey,
+    pub script: TariScript,
+    pub input: ExecutionStack,
+}
+
+fn is_backed_up_client_value(key: &str) -> bool {
+    !EXCLUDED_CLIENT_VALUE_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+impl WalletBackup {
+    /// Read the backup contents from the wallet databases
+    pub async fn collect<T, U, V, W>(
+        wallet_db: &WalletDatabase<T>,
+        transaction_db: &TransactionDatabase<U>,
+        output_manager_db: &OutputManagerDatabase<V>,
+        contacts_db: &ContactsDatabase<W>,
+    ) -> Result<Self, WalletBackupError>
+    where
+        T: WalletBackend + 'static,
+        U: TransactionBackend + 'static,
+        V: OutputManagerBackend + 'static,
+        W: ContactsBackend + 'static,
+    {
+        let master_secret_key = wallet_db
+            .get_master_secret_key()
+            .await?
+            .ok_or(WalletBackupError::MissingMasterSecretKey)?;
+        let key_manager_state = output_manager_db
+            .get_key_manager_state()
+            .await?
+            .map(|km| BackupKeyManagerState {
+                master_key: km.master_key,
+                branch_seed: km.branch_seed,
+                primary_key_index: km.primary_key_index,
+            });
+
+        let contacts = contacts_db
+            .get_contacts()
+            .await?
+            .into_iter()
+            .map(|c| BackupContact {
+                alias: c.alias,
+                public_key: c.public_key,
+            })
+            .collect();
+        let known_one_sided_payment_scripts = output_manager_db
+            .get_all_known_one_sided_payment_scripts()
+            .await?
+            .into_iter()
+            .map(|s| BackupKnownScript {
+                script_hash: s.script_hash,
+                private_key: s.private_key,
+                script: s.script,
+                input: s.input,
+            })
+            .collect();
+        let (outputs, output_account_ids) = output_manager_db
+            .fetch_sorted_unspent_outputs()
+            .await?
+            .into_iter()
+            .map(|o| (o.unblinded_output, o.account_id))
+            .unzip();
+        let accounts = output_manager_db
+            .get_accounts()
+            .await?
+            .into_iter()
+            .map(|a| BackupAccount {
+                id: a.id,
+                name: a.name,
+                key_index: a.key_index,
+            })
+            .collect();
+
+        let mut transactions = transaction_db
+            .get_completed_transactions()
+            .await?
+            .into_iter()
+            .chain(transaction_db.get_cancelled_completed_transactions().await?)
+            .map(|(_, tx)| tx)
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|tx| tx.timestamp);
+
+        let client_values = wallet_db
+            .get_client_key_values()
+            .await?
+            .into_iter()
+            .filter(|(key, _)| is_backed_up_client_value(key))
+            .collect();
+
+        Ok(Self {
+            master_secret_key,
+            key_manager_state,
+            client_values,
+            contacts,
+            known_one_sided_payment_scripts,
+            outputs,
+            transactions,
+            accounts,
+            output_account_ids,
+            wallet_birthday: wallet_db.get_wallet_birthday().await?,
+            htlcs: output_manager_db.get_htlcs().await?,
+            htlc_preimages: output_manager_db.get_htlc_preimages().await?,
+            payment_requests: transaction_db.get_payment_requests().await?,
+            scheduled_transactions: transaction_db.get_scheduled_transactions().await?,
+        })
+    }
+
+    /// Encrypt the backup under a key derived from the passphrase with a new random salt
+    pub fn encrypt(&self, passphrase: &str) -> Result<EncryptedWalletBackup, WalletBackupError> {
+        let key_derivation = PassphraseKeyDerivation::new_argon2id();
+        let cipher = key_derivation.derive_cipher(passphrase)?;
+        let ciphertext = encrypt_bytes_integral_nonce(&cipher, serde_json::to_vec(self)?)
+            .map_err(|e| WalletBackupError::EncryptionError(e.to_string()))?;
+
+        Ok(EncryptedWalletBackup {
+            version: WALLET_BACKUP_VERSION,
+            created_at: Utc::now().naive_utc(),
+            key_derivation,
+            checksum: Blake256::digest(&ciphertext).to_vec().to_hex(),
+            ciphertext: ciphertext.to_hex(),
+        })
+    }
+
+    /// Write the backup contents into the databases of a new wallet. The outputs are added as unconfirmed so that the
+    /// wallet revalidates them against its base node. The master key is written last, so a restore that fails part of
+    /// the way does not leave behind a database that looks like a complete wallet. Callers should still run this in a
+    /// database transaction that is rolled back on failure, as `restore_wallet_backup` does.
+    pub async fn restore<T, U, V, W>(
+        self,
+        wallet_db: &WalletDatabase<T>,
+        transaction_db: &TransactionDatabase<U>,
+        output_manager_db: &OutputManagerDatabase<V>,
+        contacts_db: &ContactsDatabase<W>,
+        factories: &CryptoFactories,
+    ) -> Result<(), WalletBackupError>
+    where
+        T: WalletBackend + 'static,
+        U: TransactionBackend + 'static,
+        V: OutputManagerBackend + 'static,
+        W: ContactsBackend + 'static,
+    {
+        if wallet_db.get_master_secret_key().await?.is_some() {
+            return Err(WalletBackupError::WalletAlreadyExists);
+        }
+
+        if let Some(birthday) = self.wallet_birthday {
+            wallet_db.set_wallet_birthday(birthday).await?;
+        }
+        if let Some(km) = self.key_manager_state {
+            output_manager_db
+                .set_key_manager_state(KeyManagerState {
+                    master_key: km.master_key,
+                    branch_seed: km.branch_seed,
+                    primary_key_index: km.primary_key_index,
+                })
+                .await?;
+        }
+        for (key, value) in self.client_values {
+            wallet_db.set_client_key_value(key, value).await?;
+        }
+
+        for contact in self.contacts {
+            contacts_db
+                .upsert_contact(Contact {
+                    alias: contact.alias,
+                    public_key: contact.public_key,
+                })
+                .await?;
+        }
+        for script in self.known_one_sided_payment_scripts {
+            output_manager_db
+                .add_known_script(KnownOneSidedPaymentScript {
+                    script_hash: script.script_hash,
+                    private_key: script.private_key,
+                    script: script.script,
+                    input: script.input,
+                })
+                .await?;
+        }
+
+        for account in self.accounts {
+            output_manager_db
+                .add_account(Account {
+                    id: account.id,
+                    name: account.name,
+                    key_index: account.key_index,
+                })
+                .await?;
+        }
+
+        let num_outputs = self.outputs.len();
+        let mut output_account_ids = self.output_account_ids.into_iter();
+        for output in self.outputs {
+            let mut output = DbUnblindedOutput::from_unblinded_output(output, factories)?;
+            output.account_id = output_account_ids.next().unwrap_or(DEFAULT_ACCOUNT_ID);
+            output_manager_db.add_unspent_output(output).await?;
+        }
+        for (hash_lock, preimage) in self.htlc_preimages {
+            output_manager_db.add_htlc_preimage(hash_lock, preimage).await?;
+        }
+        for htlc in self.htlcs {
+            output_manager_db.add_htlc(htlc).await?;
+        }
+
+        let num_transactions = self.transactions.len();
+        let transaction_amounts = self
+            .transactions
+            .iter()
+            .map(|tx| (tx.tx_id, tx.amount))
+            .collect::<HashMap<_, _>>();
+        for tx in self.transactions {
+            transaction_db.insert_completed_transaction(tx.tx_id, tx).await?;
+        }
+        for scheduled_tx in self.scheduled_transactions {
+            transaction_db.add_scheduled_transaction(scheduled_tx).await?;
+        }
+        // The payments are matched to the requests again, which recomputes their status. Requests that had expired are
+        // expired again once the wallet sees the chain tip.
+        for record in self.payment_requests {
+            let reference = record.request.reference;
+            let payments = record.payments;
+            transaction_db
+                .add_payment_request(PaymentRequestRecord {
+                    status: PaymentRequestStatus::Open,
+                    amount_received: MicroTari::from(0),
+                    payments: Vec::new(),
+                    ..record
+                })
+                .await?;
+            for tx_id in payments {
+                if let Some(amount) = transaction_amounts.get(&tx_id) {
+                    transaction_db
+                        .add_payment_request_payment(reference, tx_id, *amount)
+                        .await?;
+                }
+            }
+        }
+        // The wallet is only considered to exist once it has a master key, so it is written last
+        wallet_db.set_master_secret_key(self.master_secret_key).await?;
+
+        info!(
+            target: LOG_TARGET,
+            "Restored wallet backup with {} outputs and {} transactions", num_outputs, num_transactions
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_crypto::keys::SecretKey;
+
+    fn backup() -> WalletBackup {
+        WalletBackup {
+            master_secret_key: CommsSecretKey::random(&mut rand::rngs::OsRng),
+            key_manager_state: None,
+            client_values: vec![("key".to_string(), "value".to_string())].into_iter().collect(),
+            contacts: Vec::new(),
+            known_one_sided_payment_scripts: Vec::new(),
+            outputs: Vec::new(),
+            transactions: Vec::new(),
+            accounts: Vec::new(),
+            output_account_ids: Vec::new(),
+            wallet_birthday: Some(18_900),
+            htlcs: Vec::new(),
+            htlc_preimages: Vec::new(),
+            payment_requests: Vec::new(),
+            scheduled_transactions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn it_round_trips_an_encrypted_backup() {
+        let backup = backup();
+        let json = backup.encrypt("passphrase").unwrap().to_json().unwrap();
+
+        let restored = EncryptedWalletBackup::from_json(&json)
+            .unwrap()
+            .decrypt("passphrase")
+            .unwrap();
+        assert_eq!(restored.master_secret_key, backup.master_secret_key);
+        assert_eq!(restored.client_values, backup.client_values);
+        assert_eq!(restored.wallet_birthday, backup.wallet_birthday);
+    }
+
+    #[test]
+    fn it_detects_a_wrong_passphrase_and_a_damaged_file() {
+        let encrypted = backup().encrypt("passphrase").unwrap();
+        assert!(matches!(
+            encrypted.decrypt("wrong passphrase"),
+            Err(WalletBackupError::IncorrectPassphrase)
+        ));
+
+        let mut damaged = encrypted;
+        let mut ciphertext = from_hex(&damaged.ciphertext).unwrap();
+        ciphertext[20] ^= 0xff;
+        damaged.ciphertext = ciphertext.to_hex();
+        assert!(matches!(
+            damaged.decrypt("passphrase"),
+            Err(WalletBackupError::ChecksumMismatch)
+        ));
+    }
+
+    #[test]
+    fn it_leaves_signing_sessions_out_of_the_client_values() {
+        assert!(is_backed_up_client_value("custom_base_node_public_key"));
+        assert!(!is_backed_up_client_value(&format!("{}1", MULTISIG_SIGNER_KEY_PREFIX)));
+        assert!(!is_backed_up_client_value(&format!(
+            "{}1",
+            MULTISIG_COORDINATOR_KEY_PREFIX
+        )));
+        assert!(!is_backed_up_client_value(&format!(
+            "{}1",
+            OFFLINE_SIGNING_STATE_KEY_PREFIX
+        )));
+    }
+
+    #[test]
+    fn it_rejects_newer_backup_versions() {
+        let mut encrypted = backup().encrypt("passphrase").unwrap();
+        encrypted.version = WALLET_BACKUP_VERSION + 1;
+        assert!(matches!(
+            EncryptedWalletBackup::from_json(&encrypted.to_json().unwrap()),
+            Err(WalletBackupError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -20,17 +20,18 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::error::WalletStorageError;
+use crate::{error::WalletStorageError, storage::key_derivation::PassphraseKeyDerivation};
 use aes_gcm::Aes256Gcm;
 use log::*;
 use std::{
+    collections::HashMap,
     fmt::{Display, Error, Formatter},
     sync::Arc,
 };
 use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     multiaddr::Multiaddr,
-    peer_manager::PeerFeatures,
+    peer_manager::{PeerFeatures, PEER_DATABASE_KEY_BYTES},
     tor::TorIdentity,
     types::{CommsPublicKey, CommsSecretKey},
 };
@@ -47,6 +48,8 @@ pub trait WalletBackend: Send + Sync + Clone {
     fn apply_encryption(&self, cipher: Aes256Gcm) -> Result<(), WalletStorageError>;
     /// Remove encryption from the backend.
     fn remove_encryption(&self) -> Result<(), WalletStorageError>;
+    /// Check that the backend is encrypted with the given cipher
+    fn check_encryption(&self, cipher: Aes256Gcm) -> Result<(), WalletStorageError>;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -56,8 +59,12 @@ pub enum DbKey {
     TorId,
     BaseNodeChainMetadata,
     ClientKey(String),
+    ClientKeyValues,
     MasterSecretKey,
     MasterPublicKey,
+    PassphraseKeyDerivation,
+    WalletBirthday,
+    PeerDatabaseKey,
 }
 
 pub enum DbValue {
@@ -65,10 +72,14 @@ pub enum DbValue {
     CommsFeatures(PeerFeatures),
     TorId(TorIdentity),
     ClientValue(String),
+    ClientKeyValues(HashMap<String, String>),
     ValueCleared,
     BaseNodeChainMetadata(ChainMetadata),
     MasterSecretKey(CommsSecretKey),
     MasterPublicKey(CommsPublicKey),
+    PassphraseKeyDerivation(PassphraseKeyDerivation),
+    WalletBirthday(u16),
+    PeerDatabaseKey([u8; PEER_DATABASE_KEY_BYTES]),
 }
 
 #[derive(Clone)]
@@ -79,6 +90,9 @@ pub enum DbKeyValuePair {
     MasterSecretKey(CommsSecretKey),
     CommsAddress(Multiaddr),
     CommsFeatures(PeerFeatures),
+    PassphraseKeyDerivation(PassphraseKeyDerivation),
+    WalletBirthday(u16),
+    PeerDatabaseKey([u8; PEER_DATABASE_KEY_BYTES]),
 }
 
 pub enum WriteOperation {
@@ -98,6 +112,11 @@ where T: WalletBackend + 'static
         Self { db: Arc::new(db) }
     }
 
+    /// The backend of the database, for the operations that are specific to it
+    pub(crate) fn backend(&self) -> &T {
+        &self.db
+    }
+
     pub async fn get_master_secret_key(&self) -> Result<Option<CommsSecretKey>, WalletStorageError> {
         let db_clone = self.db.clone();
 
@@ -154,6 +173,33 @@ where T: WalletBackend + 'static
         Ok(())
     }
 
+    /// The key that the peer database is encrypted with. It is stored in the wallet database, encrypted with the
+    /// wallet cipher, so that it is unaffected by changing or removing the wallet passphrase.
+    pub async fn get_peer_database_key(&self) -> Result<Option<[u8; PEER_DATABASE_KEY_BYTES]>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PeerDatabaseKey) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::PeerDatabaseKey(k))) => Ok(Some(k)),
+            Ok(Some(other)) => unexpected_result(DbKey::PeerDatabaseKey, other),
+            Err(e) => log_error(DbKey::PeerDatabaseKey, e),
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(c)
+    }
+
+    pub async fn set_peer_database_key(&self, key: [u8; PEER_DATABASE_KEY_BYTES]) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PeerDatabaseKey(key)))
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
     pub async fn get_node_address(&self) -> Result<Option<Multiaddr>, WalletStorageError> {
         let db_clone = self.db.clone();
 
@@ -229,6 +275,66 @@ where T: WalletBackend + 'static
         Ok(())
     }
 
+    pub async fn get_passphrase_key_derivation(&self) -> Result<Option<PassphraseKeyDerivation>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PassphraseKeyDerivation) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::PassphraseKeyDerivation(kdf))) => Ok(Some(kdf)),
+            Ok(Some(other)) => unexpected_result(DbKey::PassphraseKeyDerivation, other),
+            Err(e) => log_error(DbKey::PassphraseKeyDerivation, e),
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(c)
+    }
+
+    pub async fn set_passphrase_key_derivation(&self, kdf: PassphraseKeyDerivation) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PassphraseKeyDerivation(kdf)))
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn clear_passphrase_key_derivation(&self) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Remove(DbKey::PassphraseKeyDerivation)))
+            .await
+            .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    /// The wallet birthday is the number of days since the Unix epoch on which the wallet's master key was created.
+    /// Recovery uses it to skip the part of the chain that cannot contain any of the wallet's outputs.
+    pub async fn get_wallet_birthday(&self) -> Result<Option<u16>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::WalletBirthday) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::WalletBirthday(b))) => Ok(Some(b)),
+            Ok(Some(other)) => unexpected_result(DbKey::WalletBirthday, other),
+            Err(e) => log_error(DbKey::WalletBirthday, e),
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(c)
+    }
+
+    pub async fn set_wallet_birthday(&self, birthday: u16) -> Result<(), WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::WalletBirthday(birthday)))
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
     pub async fn apply_encryption(&self, cipher: Aes256Gcm) -> Result<(), WalletStorageError> {
         let db_clone = self.db.clone();
         tokio::task::spawn_blocking(move || db_clone.apply_encryption(cipher))
@@ -245,6 +351,24 @@ where T: WalletBackend + 'static
             .and_then(|inner_result| inner_result)
     }
 
+    /// Check that the database is encrypted with the given passphrase. Fails with `IncorrectPassword` if the
+    /// passphrase is wrong and with `InvalidEncryptionCipher` if the database is not encrypted.
+    pub async fn verify_passphrase(&self, passphrase: String) -> Result<(), WalletStorageError> {
+        // Wallets encrypted before the key derivation was versioned have no scheme stored
+        let kdf = self
+            .get_passphrase_key_derivation()
+            .await?
+            .unwrap_or(PassphraseKeyDerivation::Blake256);
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let cipher = kdf.derive_cipher(&passphrase)?;
+            db_clone.check_encryption(cipher)
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))
+        .and_then(|inner_result| inner_result)
+    }
+
     pub async fn set_client_key_value(&self, key: String, value: String) -> Result<(), WalletStorageError> {
         let db_clone = self.db.clone();
 
@@ -270,6 +394,20 @@ where T: WalletBackend + 'static
         Ok(c)
     }
 
+    pub async fn get_client_key_values(&self) -> Result<HashMap<String, String>, WalletStorageError> {
+        let db_clone = self.db.clone();
+
+        let c = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::ClientKeyValues) {
+            Ok(None) => Ok(HashMap::new()),
+            Ok(Some(DbValue::ClientKeyValues(values))) => Ok(values),
+            Ok(Some(other)) => unexpected_result(DbKey::ClientKeyValues, other),
+            Err(e) => log_error(DbKey::ClientKeyValues, e),
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(c)
+    }
+
     pub async fn get_client_key_from_str<V>(&self, key: String) -> Result<Option<V>, WalletStorageError>
     where
         V: std::str::FromStr,
@@ -321,7 +459,11 @@ impl Display for DbKey {
             DbKey::CommsFeatures => f.write_str(&"Node features".to_string()),
             DbKey::TorId => f.write_str(&"TorId".to_string()),
             DbKey::ClientKey(k) => f.write_str(&format!("ClientKey: {:?}", k)),
+            DbKey::ClientKeyValues => f.write_str(&"ClientKeyValues".to_string()),
             DbKey::BaseNodeChainMetadata => f.write_str(&"Last seen Chain metadata from base node".to_string()),
+            DbKey::PassphraseKeyDerivation => f.write_str(&"PassphraseKeyDerivation".to_string()),
+            DbKey::WalletBirthday => f.write_str(&"WalletBirthday".to_string()),
+            DbKey::PeerDatabaseKey => f.write_str(&"PeerDatabaseKey".to_string()),
         }
     }
 }
@@ -332,11 +474,15 @@ impl Display for DbValue {
             DbValue::MasterSecretKey(k) => f.write_str(&format!("MasterSecretKey: {:?}", k)),
             DbValue::MasterPublicKey(k) => f.write_str(&format!("MasterPublicKey: {:?}", k)),
             DbValue::ClientValue(v) => f.write_str(&format!("ClientValue: {:?}", v)),
+            DbValue::ClientKeyValues(v) => f.write_str(&format!("ClientKeyValues: {} values", v.len())),
             DbValue::ValueCleared => f.write_str(&"ValueCleared".to_string()),
             DbValue::CommsFeatures(_) => f.write_str(&"Node features".to_string()),
             DbValue::CommsAddress(_) => f.write_str(&"Comms Address".to_string()),
             DbValue::TorId(v) => f.write_str(&format!("Tor ID: {}", v)),
             DbValue::BaseNodeChainMetadata(v) => f.write_str(&format!("Last seen Chain metadata from base node:{}", v)),
+            DbValue::PassphraseKeyDerivation(v) => f.write_str(&format!("Passphrase key derivation: {}", v)),
+            DbValue::WalletBirthday(v) => f.write_str(&format!("Wallet birthday: {}", v)),
+            DbValue::PeerDatabaseKey(_) => f.write_str(&"Peer database key".to_string()),
         }
     }
 }
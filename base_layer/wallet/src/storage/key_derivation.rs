@@ -0,0 +1,173 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{error::WalletStorageError, util::encryption::AES_KEY_BYTES};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, NewAead},
+    Aes256Gcm,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use digest::Digest;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tari_crypto::common::Blake256;
+
+pub const KEY_DERIVATION_SALT_BYTES: usize = 16;
+/// Argon2id parameters for new wallets: 19 MiB of memory, 2 passes and 1 lane
+const ARGON2_MEMORY_COST_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+
+/// The versioned scheme used to derive the database encryption key from the wallet passphrase. The scheme, including
+/// its salt and cost parameters, is stored in the clear alongside the encrypted data.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "version")]
+pub enum PassphraseKeyDerivation {
+    /// A single Blake256 hash of the passphrase. This is only used to open wallets that were encrypted before the key
+    /// derivation was versioned, which have no scheme stored.
+    #[serde(rename = "0")]
+    Blake256,
+    /// The memory-hard Argon2id KDF with a random salt
+    #[serde(rename = "1")]
+    Argon2id {
+        salt: Vec<u8>,
+        memory_cost_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl PassphraseKeyDerivation {
+    /// The scheme used for newly encrypted databases, with a fresh random salt
+    pub fn new_argon2id() -> Self {
+        let mut salt = vec![0u8; KEY_DERIVATION_SALT_BYTES];
+        OsRng.fill_bytes(&mut salt);
+        PassphraseKeyDerivation::Argon2id {
+            salt,
+            memory_cost_kib: ARGON2_MEMORY_COST_KIB,
+            iterations: ARGON2_ITERATIONS,
+            parallelism: ARGON2_PARALLELISM,
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, PassphraseKeyDerivation::Blake256)
+    }
+
+    /// Derive the database cipher from the passphrase using this scheme
+    pub fn derive_cipher(&self, passphrase: &str) -> Result<Aes256Gcm, WalletStorageError> {
+        match self {
+            PassphraseKeyDerivation::Blake256 => {
+                let passphrase_hash = Blake256::new().chain(passphrase.as_bytes()).finalize();
+                let key = GenericArray::from_slice(passphrase_hash.as_slice());
+                Ok(Aes256Gcm::new(key))
+            },
+            PassphraseKeyDerivation::Argon2id {
+                salt,
+                memory_cost_kib,
+                iterations,
+                parallelism,
+            } => {
+                let params = Params::new(*memory_cost_kib, *iterations, *parallelism, Some(AES_KEY_BYTES))
+                    .map_err(|e| WalletStorageError::KeyDerivationError(e.to_string()))?;
+                let mut key = [0u8; AES_KEY_BYTES];
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+                    .map_err(|e| WalletStorageError::KeyDerivationError(e.to_string()))?;
+                Ok(Aes256Gcm::new(GenericArray::from_slice(&key)))
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, WalletStorageError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, WalletStorageError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl fmt::Display for PassphraseKeyDerivation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassphraseKeyDerivation::Blake256 => write!(f, "Blake256 (v0)"),
+            PassphraseKeyDerivation::Argon2id {
+                memory_cost_kib,
+                iterations,
+                parallelism,
+                ..
+            } => write!(
+                f,
+                "Argon2id (v1, m={} KiB, t={}, p={})",
+                memory_cost_kib, iterations, parallelism
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use aes_gcm::aead::Aead;
+
+    fn round_trip(kdf: &PassphraseKeyDerivation, encrypt_with: &str, decrypt_with: &str) -> bool {
+        let nonce = GenericArray::from_slice(&[0u8; 12]);
+        let ciphertext = kdf
+            .derive_cipher(encrypt_with)
+            .unwrap()
+            .encrypt(nonce, b"secret".as_ref())
+            .unwrap();
+        kdf.derive_cipher(decrypt_with)
+            .unwrap()
+            .decrypt(nonce, ciphertext.as_ref())
+            .is_ok()
+    }
+
+    #[test]
+    fn it_derives_the_same_key_from_the_same_passphrase_and_salt() {
+        let kdf = PassphraseKeyDerivation::new_argon2id();
+        assert!(round_trip(&kdf, "passphrase", "passphrase"));
+        assert!(!round_trip(&kdf, "passphrase", "wrong passphrase"));
+
+        let restored = PassphraseKeyDerivation::from_json(&kdf.to_json().unwrap()).unwrap();
+        assert_eq!(restored, kdf);
+        assert!(!restored.is_legacy());
+    }
+
+    #[test]
+    fn it_uses_a_random_salt() {
+        assert_ne!(
+            PassphraseKeyDerivation::new_argon2id(),
+            PassphraseKeyDerivation::new_argon2id()
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_legacy_blake256_derivation() {
+        let kdf = PassphraseKeyDerivation::Blake256;
+        assert!(kdf.is_legacy());
+        assert!(round_trip(&kdf, "passphrase", "passphrase"));
+        assert_eq!(kdf.to_json().unwrap(), r#"{"version":"0"}"#);
+    }
+}
@@ -30,6 +30,8 @@
 //   - After running this, make sure that the diesel update did not change BigInt to Integer in 'schema.rs' (check for
 //     any unwanted changes)
 
+pub mod backup;
 pub mod database;
+pub mod key_derivation;
 pub mod sqlite_db;
 pub mod sqlite_utilities;
@@ -25,6 +25,7 @@ use crate::{
     schema::{client_key_values, wallet_settings},
     storage::{
         database::{DbKey, DbKeyValuePair, DbValue, WalletBackend, WriteOperation},
+        key_derivation::PassphraseKeyDerivation,
         sqlite_utilities::WalletDbConnection,
     },
     util::encryption::{decrypt_bytes_integral_nonce, encrypt_bytes_integral_nonce, Encryptable, AES_NONCE_BYTES},
@@ -37,13 +38,14 @@ use aes_gcm::{
 use diesel::{prelude::*, SqliteConnection};
 use log::*;
 use std::{
+    collections::HashMap,
     str::{from_utf8, FromStr},
     sync::{Arc, RwLock},
 };
 use tari_common_types::chain_metadata::ChainMetadata;
 use tari_comms::{
     multiaddr::Multiaddr,
-    peer_manager::PeerFeatures,
+    peer_manager::{PeerFeatures, PEER_DATABASE_KEY_BYTES},
     tor::TorIdentity,
     types::{CommsPublicKey, CommsSecretKey},
 };
@@ -61,8 +63,8 @@ const LOG_TARGET: &str = "wallet::storage::sqlite_db";
 /// A Sqlite backend for the Output Manager Service. The Backend is accessed via a connection pool to the Sqlite file.
 #[derive(Clone)]
 pub struct WalletSqliteDatabase {
-    database_connection: WalletDbConnection,
-    cipher: Arc<RwLock<Option<Aes256Gcm>>>,
+    pub(crate) database_connection: WalletDbConnection,
+    pub(crate) cipher: Arc<RwLock<Option<Aes256Gcm>>>,
 }
 impl WalletSqliteDatabase {
     pub fn new(database_connection: WalletDbConnection, cipher: Option<Aes256Gcm>) -> Result<Self, WalletStorageError> {
@@ -157,6 +159,15 @@ impl WalletSqliteDatabase {
         }
     }
 
+    fn get_wallet_birthday(&self, conn: &SqliteConnection) -> Result<Option<u16>, WalletStorageError> {
+        if let Some(key_str) = WalletSettingSql::get(DbKey::WalletBirthday.to_string(), conn)? {
+            let birthday = u16::from_str(&key_str).map_err(|e| WalletStorageError::ConversionError(e.to_string()))?;
+            Ok(Some(birthday))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn set_tor_id(&self, tor: TorIdentity, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
         let cipher = acquire_read_lock!(self.cipher);
         let tor_string = tor
@@ -197,6 +208,47 @@ impl WalletSqliteDatabase {
         }
     }
 
+    fn set_peer_database_key(
+        &self,
+        key: &[u8; PEER_DATABASE_KEY_BYTES],
+        conn: &SqliteConnection,
+    ) -> Result<(), WalletStorageError> {
+        let cipher = acquire_read_lock!(self.cipher);
+        let value = match cipher.as_ref() {
+            None => key.to_vec().to_hex(),
+            Some(cipher) => encrypt_bytes_integral_nonce(cipher, key.to_vec())
+                .map_err(|e| WalletStorageError::AeadError(format!("Encryption Error:{}", e.to_string())))?
+                .to_hex(),
+        };
+        WalletSettingSql::new(DbKey::PeerDatabaseKey.to_string(), value).set(conn)?;
+
+        Ok(())
+    }
+
+    fn get_peer_database_key(
+        &self,
+        conn: &SqliteConnection,
+    ) -> Result<Option<[u8; PEER_DATABASE_KEY_BYTES]>, WalletStorageError> {
+        let cipher = acquire_read_lock!(self.cipher);
+        if let Some(key_str) = WalletSettingSql::get(DbKey::PeerDatabaseKey.to_string(), conn)? {
+            let key_bytes = match cipher.as_ref() {
+                None => from_hex(&key_str)?,
+                Some(cipher) => decrypt_bytes_integral_nonce(cipher, from_hex(&key_str)?)
+                    .map_err(|e| WalletStorageError::AeadError(format!("Decryption Error:{}", e.to_string())))?,
+            };
+            if key_bytes.len() != PEER_DATABASE_KEY_BYTES {
+                return Err(WalletStorageError::ConversionError(
+                    "Peer database key has an invalid length".to_string(),
+                ));
+            }
+            let mut key = [0u8; PEER_DATABASE_KEY_BYTES];
+            key.copy_from_slice(&key_bytes);
+            Ok(Some(key))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn set_chain_metadata(&self, chain: ChainMetadata, conn: &SqliteConnection) -> Result<(), WalletStorageError> {
         let bytes = bincode::serialize(&chain).map_err(|e| WalletStorageError::ConversionError(e.to_string()))?;
         WalletSettingSql::new(DbKey::BaseNodeChainMetadata.to_string(), bytes.to_hex()).set(conn)?;
@@ -247,6 +299,15 @@ impl WalletSqliteDatabase {
             DbKeyValuePair::CommsFeatures(cf) => {
                 WalletSettingSql::new(DbKey::CommsFeatures.to_string(), cf.bits().to_string()).set(&conn)?;
             },
+            DbKeyValuePair::PassphraseKeyDerivation(kdf) => {
+                WalletSettingSql::new(DbKey::PassphraseKeyDerivation.to_string(), kdf.to_json()?).set(&conn)?;
+            },
+            DbKeyValuePair::WalletBirthday(b) => {
+                WalletSettingSql::new(DbKey::WalletBirthday.to_string(), b.to_string()).set(&conn)?;
+            },
+            DbKeyValuePair::PeerDatabaseKey(key) => {
+                self.set_peer_database_key(&key, &(*conn))?;
+            },
         }
         Ok(None)
     }
@@ -263,6 +324,9 @@ impl WalletSqliteDatabase {
                     return Ok(Some(DbValue::ValueCleared));
                 }
             },
+            DbKey::ClientKeyValues => {
+                return Err(WalletStorageError::OperationNotSupported);
+            },
             DbKey::CommsFeatures => {
                 return Err(WalletStorageError::OperationNotSupported);
             },
@@ -275,6 +339,16 @@ impl WalletSqliteDatabase {
             DbKey::TorId => {
                 let _ = WalletSettingSql::clear(DbKey::TorId.to_string(), &conn)?;
             },
+            DbKey::PassphraseKeyDerivation => {
+                let _ = WalletSettingSql::clear(DbKey::PassphraseKeyDerivation.to_string(), &conn)?;
+            },
+            DbKey::WalletBirthday => {
+                return Err(WalletStorageError::OperationNotSupported);
+            },
+            // Removing the key would leave the peer database unreadable
+            DbKey::PeerDatabaseKey => {
+                return Err(WalletStorageError::OperationNotSupported);
+            },
         };
         Ok(None)
     }
@@ -300,10 +374,23 @@ impl WalletBackend for WalletSqliteDatabase {
                     Some(DbValue::ClientValue(v.value))
                 },
             },
+            DbKey::ClientKeyValues => {
+                let mut values = HashMap::new();
+                for mut v in ClientKeyValueSql::index(&conn)? {
+                    self.decrypt_if_necessary(&mut v)?;
+                    values.insert(v.key, v.value);
+                }
+                Some(DbValue::ClientKeyValues(values))
+            },
             DbKey::CommsAddress => self.get_comms_address(&conn)?.map(DbValue::CommsAddress),
             DbKey::TorId => self.get_tor_id(&conn)?,
             DbKey::CommsFeatures => self.get_comms_features(&conn)?.map(DbValue::CommsFeatures),
             DbKey::BaseNodeChainMetadata => self.get_chain_metadata(&conn)?.map(DbValue::BaseNodeChainMetadata),
+            DbKey::PassphraseKeyDerivation => {
+                get_passphrase_key_derivation(&conn)?.map(DbValue::PassphraseKeyDerivation)
+            },
+            DbKey::WalletBirthday => self.get_wallet_birthday(&conn)?.map(DbValue::WalletBirthday),
+            DbKey::PeerDatabaseKey => self.get_peer_database_key(&conn)?.map(DbValue::PeerDatabaseKey),
         };
 
         Ok(result)
@@ -351,6 +438,14 @@ impl WalletBackend for WalletSqliteDatabase {
             WalletSettingSql::new(DbKey::TorId.to_string(), ciphertext_integral_nonce.to_hex()).set(&conn)?;
         }
 
+        // Encrypt the peer database key if present
+        let peer_database_key = WalletSettingSql::get(DbKey::PeerDatabaseKey.to_string(), &conn)?;
+        if let Some(v) = peer_database_key {
+            let ciphertext_integral_nonce = encrypt_bytes_integral_nonce(&cipher, from_hex(v.as_str())?)
+                .map_err(|e| WalletStorageError::AeadError(format!("Encryption Error:{}", e.to_string())))?;
+            WalletSettingSql::new(DbKey::PeerDatabaseKey.to_string(), ciphertext_integral_nonce.to_hex()).set(&conn)?;
+        }
+
         (*current_cipher) = Some(cipher);
 
         Ok(())
@@ -395,11 +490,69 @@ impl WalletBackend for WalletSqliteDatabase {
             WalletSettingSql::new(DbKey::TorId.to_string(), tor_string).set(&conn)?;
         }
 
+        // Remove the peer database key encryption if present. The peer database itself stays encrypted with this key.
+        let peer_database_key = WalletSettingSql::get(DbKey::PeerDatabaseKey.to_string(), &conn)?;
+        if let Some(v) = peer_database_key {
+            let decrypted_key_bytes = decrypt_bytes_integral_nonce(&cipher, from_hex(v.as_str())?)
+                .map_err(|e| WalletStorageError::AeadError(format!("Decryption Error:{}", e.to_string())))?;
+            WalletSettingSql::new(DbKey::PeerDatabaseKey.to_string(), decrypted_key_bytes.to_hex()).set(&conn)?;
+        }
+
         // Now that all the decryption has been completed we can safely remove the cipher fully
         let _ = (*current_cipher).take();
 
         Ok(())
     }
+
+    fn check_encryption(&self, cipher: Aes256Gcm) -> Result<(), WalletStorageError> {
+        check_db_encryption_status(&self.database_connection, Some(cipher))
+    }
+}
+
+/// Fetch the scheme used to derive the database cipher from the passphrase, if one was stored
+pub(crate) fn get_passphrase_key_derivation(
+    conn: &SqliteConnection,
+) -> Result<Option<PassphraseKeyDerivation>, WalletStorageError> {
+    WalletSettingSql::get(DbKey::PassphraseKeyDerivation.to_string(), conn)?
+        .map(|json| PassphraseKeyDerivation::from_json(&json))
+        .transpose()
+}
+
+/// Store the scheme used to derive the database cipher from the passphrase
+pub(crate) fn set_passphrase_key_derivation(
+    kdf: &PassphraseKeyDerivation,
+    conn: &SqliteConnection,
+) -> Result<(), WalletStorageError> {
+    WalletSettingSql::new(DbKey::PassphraseKeyDerivation.to_string(), kdf.to_json()?).set(conn)
+}
+
+/// Re-encrypt the encrypted wallet settings and client values from `old_cipher` to `new_cipher`. The caller is
+/// responsible for running this inside a database transaction.
+pub(crate) fn reencrypt_wallet_records(
+    conn: &SqliteConnection,
+    old_cipher: &Aes256Gcm,
+    new_cipher: &Aes256Gcm,
+) -> Result<(), WalletStorageError> {
+    for key in &[DbKey::MasterSecretKey, DbKey::TorId, DbKey::PeerDatabaseKey] {
+        if let Some(v) = WalletSettingSql::get(key.to_string(), conn)? {
+            let plaintext = decrypt_bytes_integral_nonce(old_cipher, from_hex(v.as_str())?)
+                .map_err(|e| WalletStorageError::AeadError(format!("Decryption Error:{}", e.to_string())))?;
+            let ciphertext_integral_nonce = encrypt_bytes_integral_nonce(new_cipher, plaintext)
+                .map_err(|e| WalletStorageError::AeadError(format!("Encryption Error:{}", e.to_string())))?;
+            WalletSettingSql::new(key.to_string(), ciphertext_integral_nonce.to_hex()).set(conn)?;
+        }
+    }
+
+    let mut client_key_values = ClientKeyValueSql::index(conn)?;
+    for ckv in client_key_values.iter_mut() {
+        ckv.decrypt(old_cipher)
+            .map_err(|e| WalletStorageError::AeadError(format!("Decryption Error:{}", e.to_string())))?;
+        ckv.encrypt(new_cipher)
+            .map_err(|e| WalletStorageError::AeadError(format!("Encryption Error:{}", e.to_string())))?;
+        ckv.set(conn)?;
+    }
+
+    Ok(())
 }
 
 /// Confirm if database is encrypted or not and if a cipher is provided confirm the cipher is correct.
@@ -607,8 +760,8 @@ impl Encryptable<Aes256Gcm> for ClientKeyValueSql {
 #[cfg(test)]
 mod test {
     use crate::storage::{
-        database::{DbKey, DbValue, WalletBackend},
-        sqlite_db::{ClientKeyValueSql, WalletSettingSql, WalletSqliteDatabase},
+        database::{DbKey, DbValue, WalletBackend, WriteOperation},
+        sqlite_db::{reencrypt_wallet_records, ClientKeyValueSql, WalletSettingSql, WalletSqliteDatabase},
         sqlite_utilities::run_migration_and_create_sqlite_connection,
     };
     use aes_gcm::{
@@ -616,7 +769,10 @@ mod test {
         Aes256Gcm,
     };
     use rand::{rngs::OsRng, RngCore};
-    use tari_comms::types::{CommsPublicKey, CommsSecretKey};
+    use tari_comms::{
+        peer_manager::PEER_DATABASE_KEY_BYTES,
+        types::{CommsPublicKey, CommsSecretKey},
+    };
     use tari_crypto::{
         keys::{PublicKey, SecretKey},
         tari_utilities::{hex::Hex, ByteArray},
@@ -826,6 +982,57 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_peer_database_key_encryption() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let db_tempdir = tempdir().unwrap();
+        let db_folder = db_tempdir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}/{}", db_folder, db_name);
+        let connection = run_migration_and_create_sqlite_connection(&db_path).unwrap();
+
+        let peer_database_key = [7u8; PEER_DATABASE_KEY_BYTES];
+        let db = WalletSqliteDatabase::new(connection.clone(), None).unwrap();
+        {
+            let conn = connection.acquire_lock();
+            db.set_master_secret_key(&CommsSecretKey::random(&mut OsRng), &conn)
+                .unwrap();
+            db.set_peer_database_key(&peer_database_key, &conn).unwrap();
+        }
+        let read_key = |db: &WalletSqliteDatabase| match db.fetch(&DbKey::PeerDatabaseKey).unwrap().unwrap() {
+            DbValue::PeerDatabaseKey(k) => k,
+            _ => panic!("Should be able to read the peer database key"),
+        };
+        let stored_key = || {
+            let conn = connection.acquire_lock();
+            WalletSettingSql::get(DbKey::PeerDatabaseKey.to_string(), &conn)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(read_key(&db), peer_database_key);
+        assert_eq!(stored_key(), peer_database_key.to_vec().to_hex());
+
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(b"an example very very secret key."));
+        db.apply_encryption(cipher.clone()).unwrap();
+        assert_ne!(stored_key(), peer_database_key.to_vec().to_hex());
+        assert_eq!(read_key(&db), peer_database_key);
+
+        // Changing the passphrase re-encrypts the key rather than losing it
+        let new_cipher = Aes256Gcm::new(GenericArray::from_slice(b"another example very secret key."));
+        {
+            let conn = connection.acquire_lock();
+            reencrypt_wallet_records(&conn, &cipher, &new_cipher).unwrap();
+        }
+        let db = WalletSqliteDatabase::new(connection.clone(), Some(new_cipher)).unwrap();
+        assert_eq!(read_key(&db), peer_database_key);
+
+        db.remove_encryption().unwrap();
+        assert_eq!(stored_key(), peer_database_key.to_vec().to_hex());
+        assert_eq!(read_key(&db), peer_database_key);
+
+        // The key must not be removed, since the peer database would become unreadable
+        assert!(db.write(WriteOperation::Remove(DbKey::PeerDatabaseKey)).is_err());
+    }
+
     #[test]
     fn test_client_key_value_store() {
         let db_name = format!("{}.sqlite3", string(8).as_str());
@@ -21,26 +21,33 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    contacts_service::storage::sqlite_db::ContactsServiceSqliteDatabase,
-    error::WalletStorageError,
-    output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
-    storage::{database::WalletDatabase, sqlite_db::WalletSqliteDatabase},
-    transaction_service::storage::sqlite_db::TransactionServiceSqliteDatabase,
-};
-use aes_gcm::{
-    aead::{generic_array::GenericArray, NewAead},
-    Aes256Gcm,
+    contacts_service::storage::{database::ContactsDatabase, sqlite_db::ContactsServiceSqliteDatabase},
+    error::{WalletBackupError, WalletStorageError},
+    output_manager_service::storage::{database::OutputManagerDatabase, sqlite_db::OutputManagerSqliteDatabase},
+    storage::{
+        backup::EncryptedWalletBackup,
+        database::WalletDatabase,
+        key_derivation::PassphraseKeyDerivation,
+        sqlite_db::{
+            get_passphrase_key_derivation,
+            reencrypt_wallet_records,
+            set_passphrase_key_derivation,
+            WalletSqliteDatabase,
+        },
+    },
+    transaction_service::storage::{database::TransactionDatabase, sqlite_db::TransactionServiceSqliteDatabase},
 };
-use diesel::{Connection, SqliteConnection};
-use digest::Digest;
+use aes_gcm::Aes256Gcm;
+use diesel::{connection::TransactionManager, Connection, SqliteConnection};
 use fs2::FileExt;
 use log::*;
 use std::{
+    fs,
     fs::File,
     path::{Path, PathBuf},
     sync::{Arc, Mutex, MutexGuard},
 };
-use tari_crypto::common::Blake256;
+use tari_core::transactions::CryptoFactories;
 
 const LOG_TARGET: &str = "wallet::storage:sqlite_utilities";
 
@@ -105,6 +112,72 @@ pub async fn partial_wallet_backup<P: AsRef<Path>>(current_db: P, backup_path: P
     Ok(())
 }
 
+/// Restore a full wallet backup into a new, unencrypted wallet database at `db_path`. The integrity of the backup is
+/// checked before the database is created. All records are written in a single database transaction, which is rolled
+/// back if the restore fails, after which the database is removed again.
+pub async fn restore_wallet_backup(
+    db_path: PathBuf,
+    backup: &EncryptedWalletBackup,
+    backup_passphrase: &str,
+    factories: &CryptoFactories,
+) -> Result<(), WalletBackupError> {
+    if db_path.exists() {
+        return Err(WalletBackupError::WalletAlreadyExists);
+    }
+    let contents = backup.decrypt(backup_passphrase)?;
+
+    let connection = run_migration_and_create_sqlite_connection(&db_path)?;
+    let wallet_db = WalletDatabase::new(WalletSqliteDatabase::new(connection.clone(), None)?);
+    let transaction_db = TransactionDatabase::new(TransactionServiceSqliteDatabase::new(connection.clone(), None));
+    let output_manager_db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection.clone(), None));
+    let contacts_db = ContactsDatabase::new(ContactsServiceSqliteDatabase::new(connection.clone()));
+
+    // The backends share this connection, so their writes (and any transactions they open, which become savepoints)
+    // are all part of this transaction
+    {
+        let conn = connection.acquire_lock();
+        conn.transaction_manager()
+            .begin_transaction(&*conn)
+            .map_err(WalletStorageError::from)?;
+    }
+    let result = contents
+        .restore(&wallet_db, &transaction_db, &output_manager_db, &contacts_db, factories)
+        .await;
+    let result = {
+        let conn = connection.acquire_lock();
+        match result {
+            Ok(()) => conn
+                .transaction_manager()
+                .commit_transaction(&*conn)
+                .map_err(|e| WalletStorageError::from(e).into()),
+            Err(e) => {
+                if let Err(rollback_error) = conn.transaction_manager().rollback_transaction(&*conn) {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Could not roll back partially restored wallet backup: {}", rollback_error
+                    );
+                }
+                Err(e)
+            },
+        }
+    };
+
+    if let Err(e) = result {
+        error!(target: LOG_TARGET, "Could not restore wallet backup: {}", e);
+        if let Err(e) = fs::remove_file(&db_path) {
+            warn!(
+                target: LOG_TARGET,
+                "Could not remove partially restored wallet database {}: {}",
+                db_path.display(),
+                e
+            );
+        }
+        return Err(e);
+    }
+
+    Ok(())
+}
+
 pub fn acquire_exclusive_file_lock(db_path: &Path) -> Result<File, WalletStorageError> {
     let lock_file_path = match db_path.file_name() {
         None => {
@@ -149,12 +222,6 @@ pub fn initialize_sqlite_database_backends(
     ),
     WalletStorageError,
 > {
-    let cipher = passphrase.map(|passphrase_str| {
-        let passphrase_hash = Blake256::new().chain(passphrase_str.as_bytes()).finalize();
-        let key = GenericArray::from_slice(passphrase_hash.as_slice());
-        Aes256Gcm::new(key)
-    });
-
     let connection = run_migration_and_create_sqlite_connection(&db_path).map_err(|e| {
         error!(
             target: LOG_TARGET,
@@ -163,6 +230,11 @@ pub fn initialize_sqlite_database_backends(
         e
     })?;
 
+    let cipher = match passphrase {
+        Some(passphrase) => Some(open_database_cipher(&connection, &passphrase)?),
+        None => None,
+    };
+
     let wallet_backend = WalletSqliteDatabase::new(connection.clone(), cipher.clone())?;
     let transaction_backend = TransactionServiceSqliteDatabase::new(connection.clone(), cipher.clone());
     let output_manager_backend = OutputManagerSqliteDatabase::new(connection.clone(), cipher);
@@ -175,3 +247,114 @@ pub fn initialize_sqlite_database_backends(
         contacts_backend,
     ))
 }
+
+/// Derive the database cipher from the passphrase using the key derivation scheme stored in the database. Databases
+/// that were encrypted before the scheme was stored use the legacy Blake256 derivation.
+pub fn derive_database_cipher(
+    connection: &WalletDbConnection,
+    passphrase: &str,
+) -> Result<(Aes256Gcm, PassphraseKeyDerivation), WalletStorageError> {
+    let kdf = get_passphrase_key_derivation(&connection.acquire_lock())?.unwrap_or(PassphraseKeyDerivation::Blake256);
+    let cipher = kdf.derive_cipher(passphrase)?;
+    Ok((cipher, kdf))
+}
+
+/// Derive the cipher for an encrypted database. A database still using the legacy key derivation is migrated to the
+/// current scheme once the passphrase has been confirmed to be correct.
+fn open_database_cipher(connection: &WalletDbConnection, passphrase: &str) -> Result<Aes256Gcm, WalletStorageError> {
+    let (cipher, kdf) = derive_database_cipher(connection, passphrase)?;
+    if !kdf.is_legacy() {
+        return Ok(cipher);
+    }
+
+    // Confirm the passphrase before touching any records
+    let _ = WalletSqliteDatabase::new(connection.clone(), Some(cipher.clone()))?;
+
+    let new_kdf = PassphraseKeyDerivation::new_argon2id();
+    let new_cipher = new_kdf.derive_cipher(passphrase)?;
+    reencrypt_database(connection, &cipher, &new_cipher, &new_kdf)?;
+    info!(
+        target: LOG_TARGET,
+        "Migrated wallet database encryption key derivation from {} to {}", kdf, new_kdf
+    );
+
+    Ok(new_cipher)
+}
+
+/// Change the passphrase of an encrypted wallet database. The wallet must not be running, which the exclusive file
+/// lock on the database enforces.
+pub fn change_passphrase(
+    db_path: PathBuf,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<(), WalletStorageError> {
+    let connection = run_migration_and_create_sqlite_connection(&db_path)?;
+    let _ = change_database_passphrase(&connection, &old_passphrase, &new_passphrase)?;
+    info!(target: LOG_TARGET, "Wallet database passphrase changed");
+
+    Ok(())
+}
+
+/// Change the passphrase of the encrypted database of a running wallet. The backends share the database connection,
+/// and their ciphers are locked until they are switched to the new cipher, so no record is written with the old cipher
+/// once the records have been re-encrypted.
+pub fn change_backends_passphrase(
+    wallet_backend: &WalletSqliteDatabase,
+    transaction_backend: &TransactionServiceSqliteDatabase,
+    output_manager_backend: &OutputManagerSqliteDatabase,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<(), WalletStorageError> {
+    let mut wallet_cipher = acquire_write_lock!(wallet_backend.cipher);
+    let mut transaction_cipher = acquire_write_lock!(transaction_backend.cipher);
+    let mut output_manager_cipher = acquire_write_lock!(output_manager_backend.cipher);
+    if wallet_cipher.is_none() {
+        return Err(WalletStorageError::InvalidEncryptionCipher);
+    }
+
+    let new_cipher = change_database_passphrase(&wallet_backend.database_connection, old_passphrase, new_passphrase)?;
+    *wallet_cipher = Some(new_cipher.clone());
+    *transaction_cipher = Some(new_cipher.clone());
+    *output_manager_cipher = Some(new_cipher);
+    info!(target: LOG_TARGET, "Wallet database passphrase changed");
+
+    Ok(())
+}
+
+/// Re-encrypt the database with a cipher derived from the new passphrase, once the old passphrase has been confirmed,
+/// and return the new cipher
+fn change_database_passphrase(
+    connection: &WalletDbConnection,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<Aes256Gcm, WalletStorageError> {
+    let (old_cipher, _) = derive_database_cipher(connection, old_passphrase)?;
+    // Confirm the old passphrase before touching any records
+    let _ = WalletSqliteDatabase::new(connection.clone(), Some(old_cipher.clone()))?;
+
+    let kdf = PassphraseKeyDerivation::new_argon2id();
+    let new_cipher = kdf.derive_cipher(new_passphrase)?;
+    reencrypt_database(connection, &old_cipher, &new_cipher, &kdf)?;
+
+    Ok(new_cipher)
+}
+
+/// Re-encrypt every encrypted record of the wallet, output manager and transaction databases and store the key
+/// derivation scheme of the new cipher, all in a single database transaction so that a failure leaves the database
+/// readable with the old passphrase. The contacts database holds no encrypted records.
+fn reencrypt_database(
+    connection: &WalletDbConnection,
+    old_cipher: &Aes256Gcm,
+    new_cipher: &Aes256Gcm,
+    kdf: &PassphraseKeyDerivation,
+) -> Result<(), WalletStorageError> {
+    let conn = connection.acquire_lock();
+    conn.transaction::<_, WalletStorageError, _>(|| {
+        reencrypt_wallet_records(&conn, old_cipher, new_cipher)?;
+        OutputManagerSqliteDatabase::reencrypt(&conn, old_cipher, new_cipher)
+            .map_err(|e| WalletStorageError::ReencryptionError(e.to_string()))?;
+        TransactionServiceSqliteDatabase::reencrypt(&conn, old_cipher, new_cipher)
+            .map_err(|e| WalletStorageError::ReencryptionError(e.to_string()))?;
+        set_passphrase_key_derivation(kdf, &conn)
+    })
+}
@@ -0,0 +1,335 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! Acceptance policies decide what happens to a transaction offered to this wallet before a reply is sent to the
+//! sender.
+//!
+//! A policy can accept an offer, reject it or hold it for manual approval. The reply to a held transaction is only
+//! sent once it is approved through the [TransactionServiceHandle](super::handle::TransactionServiceHandle). A
+//! rejected transaction, or one that is not approved in time, is answered with a Transaction Cancelled message so
+//! that the sender can release its inputs.
+
+use crate::{
+    contacts_service::handle::ContactsServiceHandle,
+    output_manager_service::TxId,
+    transaction_service::config::AcceptancePolicyConfig,
+};
+use chrono::NaiveDateTime;
+use log::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::tari_amount::MicroTari;
+
+const LOG_TARGET: &str = "wallet::transaction_service::acceptance_policy";
+
+/// The details of a transaction offered by a sender that are available to an acceptance policy
+#[derive(Clone, Debug, PartialEq)]
+pub struct IncomingTransaction {
+    pub tx_id: TxId,
+    pub source_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub message: String,
+    pub timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AcceptanceDecision {
+    /// Reply to the sender straight away
+    Accept,
+    /// Wait for the transaction to be approved or rejected by the user
+    Hold,
+    /// Decline the transaction for the given reason
+    Reject(String),
+}
+
+impl fmt::Display for AcceptanceDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accept => f.write_str("Accept"),
+            Self::Hold => f.write_str("Hold"),
+            Self::Reject(reason) => write!(f, "Reject ({})", reason),
+        }
+    }
+}
+
+/// Decides whether the transaction service replies to an inbound transaction. The policy is evaluated by the
+/// transaction service itself, so it must not make requests to the transaction service.
+#[async_trait::async_trait]
+pub trait TransactionAcceptancePolicy: Send + Sync {
+    async fn evaluate(&self, transaction: &IncomingTransaction) -> AcceptanceDecision;
+}
+
+/// The default policy, which accepts every inbound transaction
+#[derive(Clone, Debug, Default)]
+pub struct AcceptAllPolicy;
+
+#[async_trait::async_trait]
+impl TransactionAcceptancePolicy for AcceptAllPolicy {
+    async fn evaluate(&self, _transaction: &IncomingTransaction) -> AcceptanceDecision {
+        AcceptanceDecision::Accept
+    }
+}
+
+/// A policy built from the common rules: a minimum amount, only accepting transactions from contacts, a limit on
+/// the number of transactions offered by a sender in a period and holding transactions above an amount for approval.
+/// The rules are checked in that order and the first one that does not accept the transaction decides.
+#[derive(Default)]
+pub struct StandardAcceptancePolicy {
+    minimum_amount: Option<MicroTari>,
+    contacts: Option<ContactsServiceHandle>,
+    rate_limit: Option<(usize, Duration)>,
+    approval_threshold: Option<MicroTari>,
+    reject_all: bool,
+    offers_by_sender: Mutex<HashMap<CommsPublicKey, VecDeque<Instant>>>,
+}
+
+impl StandardAcceptancePolicy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Build the policy from the configured rules. The contacts are required for the contacts only rule, without them
+    /// that rule rejects every transaction.
+    pub fn from_config(config: &AcceptancePolicyConfig, contacts: Option<ContactsServiceHandle>) -> Self {
+        let mut policy = Self::new();
+        if let Some(amount) = config.minimum_amount {
+            policy = policy.with_minimum_amount(amount);
+        }
+        if config.contacts_only {
+            match contacts {
+                Some(contacts) => policy = policy.with_contacts_only(contacts),
+                None => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Only transactions from contacts are accepted, but the contacts service is not available"
+                    );
+                    policy.reject_all = true;
+                },
+            }
+        }
+        if let Some((max_transactions, period)) = config.rate_limit {
+            policy = policy.with_rate_limit(max_transactions, period);
+        }
+        if let Some(amount) = config.approval_threshold {
+            policy = policy.with_approval_threshold(amount);
+        }
+        policy
+    }
+
+    /// Reject transactions for less than `amount`
+    pub fn with_minimum_amount(mut self, amount: MicroTari) -> Self {
+        self.minimum_amount = Some(amount);
+        self
+    }
+
+    /// Reject transactions from senders that are not in the wallet's contacts
+    pub fn with_contacts_only(mut self, contacts: ContactsServiceHandle) -> Self {
+        self.contacts = Some(contacts);
+        self
+    }
+
+    /// Reject transactions from a sender that has already offered `max_transactions` in the last `period`
+    pub fn with_rate_limit(mut self, max_transactions: usize, period: Duration) -> Self {
+        self.rate_limit = Some((max_transactions, period));
+        self
+    }
+
+    /// Hold transactions for at least `amount` for manual approval. A threshold of zero holds every transaction.
+    pub fn with_approval_threshold(mut self, amount: MicroTari) -> Self {
+        self.approval_threshold = Some(amount);
+        self
+    }
+
+    /// Record an offer from the sender and check whether the sender is over the rate limit
+    fn is_rate_limited(&self, source_public_key: &CommsPublicKey) -> bool {
+        let (max_transactions, period) = match self.rate_limit {
+            Some(limit) => limit,
+            None => return false,
+        };
+        let now = Instant::now();
+        let mut offers_by_sender = acquire_lock!(self.offers_by_sender);
+        offers_by_sender.retain(|_, offers| {
+            while offers.front().map(|t| now.duration_since(*t) > period).unwrap_or(false) {
+                offers.pop_front();
+            }
+            !offers.is_empty()
+        });
+        let offers = offers_by_sender.entry(source_public_key.clone()).or_default();
+        if offers.len() >= max_transactions {
+            return true;
+        }
+        offers.push_back(now);
+        false
+    }
+}
+
+#[async_trait::async_trait]
+impl TransactionAcceptancePolicy for StandardAcceptancePolicy {
+    async fn evaluate(&self, transaction: &IncomingTransaction) -> AcceptanceDecision {
+        if self.reject_all {
+            return AcceptanceDecision::Reject("contacts are not available".to_string());
+        }
+        if let Some(minimum_amount) = self.minimum_amount {
+            if transaction.amount < minimum_amount {
+                return AcceptanceDecision::Reject(format!("amount is below the minimum of {}", minimum_amount));
+            }
+        }
+        if let Some(mut contacts) = self.contacts.clone() {
+            if contacts
+                .get_contact(transaction.source_public_key.clone())
+                .await
+                .is_err()
+            {
+                return AcceptanceDecision::Reject("sender is not a contact".to_string());
+            }
+        }
+        if self.is_rate_limited(&transaction.source_public_key) {
+            return AcceptanceDecision::Reject("sender is over the rate limit".to_string());
+        }
+        match self.approval_threshold {
+            Some(threshold) if transaction.amount >= threshold => AcceptanceDecision::Hold,
+            _ => AcceptanceDecision::Accept,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Utc;
+    use rand::rngs::OsRng;
+    use tari_core::transactions::tari_amount::uT;
+    use tari_crypto::keys::PublicKey;
+
+    fn incoming(source_public_key: CommsPublicKey, amount: MicroTari) -> IncomingTransaction {
+        IncomingTransaction {
+            tx_id: 1,
+            source_public_key,
+            amount,
+            message: "".to_string(),
+            timestamp: Utc::now().naive_utc(),
+        }
+    }
+
+    #[tokio::test]
+    async fn it_applies_the_rules_in_order() {
+        let (_, alice) = CommsPublicKey::random_keypair(&mut OsRng);
+        let (_, bob) = CommsPublicKey::random_keypair(&mut OsRng);
+        let policy = StandardAcceptancePolicy::new()
+            .with_minimum_amount(1000 * uT)
+            .with_rate_limit(2, Duration::from_secs(60))
+            .with_approval_threshold(10_000 * uT);
+
+        assert!(matches!(
+            policy.evaluate(&incoming(alice.clone(), 999 * uT)).await,
+            AcceptanceDecision::Reject(_)
+        ));
+        assert_eq!(
+            policy.evaluate(&incoming(alice.clone(), 1000 * uT)).await,
+            AcceptanceDecision::Accept
+        );
+        assert_eq!(
+            policy.evaluate(&incoming(alice.clone(), 10_000 * uT)).await,
+            AcceptanceDecision::Hold
+        );
+        assert!(matches!(
+            policy.evaluate(&incoming(alice, 1000 * uT)).await,
+            AcceptanceDecision::Reject(_)
+        ));
+        // The rate limit applies to each sender separately
+        assert_eq!(
+            policy.evaluate(&incoming(bob, 1000 * uT)).await,
+            AcceptanceDecision::Accept
+        );
+    }
+
+    #[tokio::test]
+    async fn it_forgets_offers_older_than_the_rate_limit_period() {
+        let (_, alice) = CommsPublicKey::random_keypair(&mut OsRng);
+        let policy = StandardAcceptancePolicy::new().with_rate_limit(1, Duration::from_millis(50));
+
+        assert_eq!(
+            policy.evaluate(&incoming(alice.clone(), 1000 * uT)).await,
+            AcceptanceDecision::Accept
+        );
+        assert!(matches!(
+            policy.evaluate(&incoming(alice.clone(), 1000 * uT)).await,
+            AcceptanceDecision::Reject(_)
+        ));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(
+            policy.evaluate(&incoming(alice, 1000 * uT)).await,
+            AcceptanceDecision::Accept
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rejects_everything_when_contacts_only_has_no_contacts() {
+        let (_, alice) = CommsPublicKey::random_keypair(&mut OsRng);
+        let config = AcceptancePolicyConfig {
+            minimum_amount: Some(1000 * uT),
+            contacts_only: true,
+            ..Default::default()
+        };
+        let policy = StandardAcceptancePolicy::from_config(&config, None);
+
+        assert_eq!(
+            policy.evaluate(&incoming(alice, 10_000 * uT)).await,
+            AcceptanceDecision::Reject("contacts are not available".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn it_builds_the_rules_from_config() {
+        let (_, alice) = CommsPublicKey::random_keypair(&mut OsRng);
+        let config = AcceptancePolicyConfig {
+            minimum_amount: Some(1000 * uT),
+            contacts_only: false,
+            rate_limit: Some((2, Duration::from_secs(60))),
+            approval_threshold: Some(10_000 * uT),
+        };
+        assert!(config.has_rules());
+        assert!(!AcceptancePolicyConfig::default().has_rules());
+        let policy = StandardAcceptancePolicy::from_config(&config, None);
+
+        assert!(matches!(
+            policy.evaluate(&incoming(alice.clone(), 999 * uT)).await,
+            AcceptanceDecision::Reject(_)
+        ));
+        assert_eq!(
+            policy.evaluate(&incoming(alice.clone(), 10_000 * uT)).await,
+            AcceptanceDecision::Hold
+        );
+        assert_eq!(
+            policy.evaluate(&incoming(alice.clone(), 1000 * uT)).await,
+            AcceptanceDecision::Accept
+        );
+        assert!(matches!(
+            policy.evaluate(&incoming(alice, 1000 * uT)).await,
+            AcceptanceDecision::Reject(_)
+        ));
+    }
+}
@@ -22,6 +22,7 @@
 
 use log::*;
 use std::{fmt, time::Duration};
+use tari_core::transactions::tari_amount::MicroTari;
 
 const LOG_TARGET: &str = "wallet::transaction_service::config";
 
@@ -40,6 +41,14 @@ pub struct TransactionServiceConfig {
     pub transaction_routing_mechanism: TransactionRoutingMechanism,
     pub transaction_event_channel_size: usize,
     pub transaction_mempool_resubmission_window: Duration,
+    pub scheduled_transaction_check_interval: Duration,
+    /// The rules that decide whether inbound transactions are accepted, rejected or held for approval
+    pub acceptance_policy: AcceptancePolicyConfig,
+    /// An inbound transaction held for approval by the acceptance policy is rejected if it is not approved in time
+    pub held_transaction_approval_timeout: Duration,
+    /// The most inbound transactions that are held for approval at a time. Once it is reached, transactions that
+    /// would be held are rejected instead.
+    pub max_held_transactions: usize,
 }
 
 impl Default for TransactionServiceConfig {
@@ -58,10 +67,37 @@ impl Default for TransactionServiceConfig {
             transaction_routing_mechanism: TransactionRoutingMechanism::default(),
             transaction_event_channel_size: 1000,
             transaction_mempool_resubmission_window: Duration::from_secs(600),
+            scheduled_transaction_check_interval: Duration::from_secs(60),
+            acceptance_policy: AcceptancePolicyConfig::default(),
+            held_transaction_approval_timeout: Duration::from_secs(86400), // 1 Day
+            max_held_transactions: 100,
         }
     }
 }
 
+/// The rules of the standard acceptance policy for inbound transactions. Every inbound transaction is accepted when
+/// no rule is set.
+#[derive(Clone, Debug, Default)]
+pub struct AcceptancePolicyConfig {
+    /// Reject transactions for less than this amount
+    pub minimum_amount: Option<MicroTari>,
+    /// Reject transactions from senders that are not in the wallet's contacts
+    pub contacts_only: bool,
+    /// Reject transactions from a sender that has already offered this many transactions in the period
+    pub rate_limit: Option<(usize, Duration)>,
+    /// Hold transactions for at least this amount for manual approval
+    pub approval_threshold: Option<MicroTari>,
+}
+
+impl AcceptancePolicyConfig {
+    pub fn has_rules(&self) -> bool {
+        self.minimum_amount.is_some() ||
+            self.contacts_only ||
+            self.rate_limit.is_some() ||
+            self.approval_threshold.is_some()
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TransactionRoutingMechanism {
     DirectOnly,
@@ -31,6 +31,10 @@ use serde_json::Error as SerdeJsonError;
 use tari_comms::{connectivity::ConnectivityError, peer_manager::node_id::NodeIdError, protocol::rpc::RpcError};
 use tari_comms_dht::outbound::DhtOutboundError;
 use tari_core::transactions::{transaction::TransactionError, transaction_protocol::TransactionProtocolError};
+use tari_crypto::{
+    range_proof::RangeProofError,
+    signatures::{CommitmentSignatureError, SchnorrSignatureError},
+};
 use tari_p2p::services::liveness::error::LivenessError;
 use tari_service_framework::reply_channel::TransportChannelError;
 use thiserror::Error;
@@ -83,6 +87,10 @@ pub enum TransactionServiceError {
     AttemptedToBroadcastCoinbaseTransaction(TxId),
     #[error("No Base Node public keys are provided for Base chain broadcast and monitoring")]
     NoBaseNodeKeysProvided,
+    #[error("The height of the chain tip has not been received from the base node yet")]
+    ChainTipUnknown,
+    #[error("Time lock error: `{0}`")]
+    TimeLockError(String),
     #[error("Error sending data to Protocol via registered channels")]
     ProtocolChannelError,
     #[error("Transaction detected as rejected by mempool")]
@@ -154,6 +162,110 @@ pub enum TransactionServiceError {
     },
     #[error("Base Node is not synced")]
     BaseNodeNotSynced,
+    #[error("Base Node was contradicted by a majority of the other base nodes")]
+    BaseNodeContradicted,
+    #[error("Offline signing error: `{0}`")]
+    OfflineSigningError(#[from] OfflineSigningError),
+    #[error("Multisig error: `{0}`")]
+    MultisigError(#[from] MultisigError),
+    #[error("Payment request error: `{0}`")]
+    PaymentRequestError(#[from] PaymentRequestError),
+}
+
+#[derive(Debug, Error)]
+pub enum OfflineSigningError {
+    #[error("IO error: `{0}`")]
+    IoError(#[from] std::io::Error),
+    #[error("Serde json error: `{0}`")]
+    SerdeJsonError(#[from] SerdeJsonError),
+    #[error("The offline package does not contain a format version")]
+    MissingVersion,
+    #[error("Unsupported offline package version: `{0}`")]
+    UnsupportedVersion(u64),
+    #[error("No offline signing state found for TxId `{0}`")]
+    SigningStateNotFound(TxId),
+    #[error("No pending offline transaction found for TxId `{0}`")]
+    RelayRecordNotFound(TxId),
+    #[error("The recipient has not replied to offline transaction TxId `{0}` yet")]
+    ReplyNotReceived(TxId),
+    #[error("Expected an offline package of type `{0}`")]
+    UnexpectedPackage(&'static str),
+}
+
+#[derive(Debug, Error)]
+pub enum MultisigError {
+    #[error("IO error: `{0}`")]
+    IoError(#[from] std::io::Error),
+    #[error("Serde json error: `{0}`")]
+    SerdeJsonError(#[from] SerdeJsonError),
+    #[error("The multisig package does not contain a format version")]
+    MissingVersion,
+    #[error("Unsupported multisig package version: `{0}`")]
+    UnsupportedVersion(u64),
+    #[error("Expected a multisig package of type `{0}`")]
+    UnexpectedPackage(&'static str),
+    #[error("Invalid threshold `{threshold}` for `{participants}` participants")]
+    InvalidThreshold { threshold: usize, participants: usize },
+    #[error("The participants of a multisig wallet must be sorted and distinct")]
+    InvalidParticipants,
+    #[error("Too many signer sets `{0}`")]
+    TooManySignerSets(usize),
+    #[error("This wallet is not a participant of the multisig wallet")]
+    NotAParticipant,
+    #[error("Multisig wallet `{0}` not found")]
+    WalletNotFound(u64),
+    #[error("The signers are not a valid set of participants of the multisig wallet")]
+    InvalidSigners,
+    #[error("The output is not locked to the multisig wallet")]
+    NotAMultisigOutput,
+    #[error("The multisig wallet does not have enough unspent funds")]
+    InsufficientFunds,
+    #[error("No multisig signing session found with id `{0}`")]
+    SessionNotFound(TxId),
+    #[error("Not all signers have committed to their nonces for session `{0}`")]
+    CommitmentsOutstanding(TxId),
+    #[error("Not all signers have provided their nonces for session `{0}`")]
+    NoncesOutstanding(TxId),
+    #[error("Invalid spend proposal: `{0}`")]
+    InvalidProposal(String),
+    #[error("Invalid signing request: `{0}`")]
+    InvalidSigningRequest(String),
+    #[error("The nonces do not match the spend proposal or the signer's commitment")]
+    InvalidNonces,
+    #[error("The nonce commitments do not match the signers of the session")]
+    InvalidNonceCommitments,
+    #[error("Invalid partial signature")]
+    InvalidPartialSignature,
+    #[error("Byte array error: `{0}`")]
+    ByteArrayError(#[from] tari_crypto::tari_utilities::ByteArrayError),
+    #[error("Transaction error: `{0}`")]
+    TransactionError(#[from] TransactionError),
+    #[error("Range proof error: `{0}`")]
+    RangeProofError(#[from] RangeProofError),
+    #[error("Signature error: `{0}`")]
+    SignatureError(#[from] SchnorrSignatureError),
+    #[error("Commitment signature error: `{0}`")]
+    CommitmentSignatureError(#[from] CommitmentSignatureError),
+}
+
+#[derive(Debug, Error)]
+pub enum PaymentRequestError {
+    #[error("Bincode error: `{0}`")]
+    BincodeError(#[from] bincode::Error),
+    #[error("Invalid payment request encoding: `{0}`")]
+    InvalidEncoding(String),
+    #[error("Unsupported payment request version: `{0}`")]
+    UnsupportedVersion(u8),
+    #[error("The payment request is for the `{0}` network")]
+    WrongNetwork(String),
+    #[error("The payment request signature is invalid")]
+    InvalidSignature,
+    #[error("The payment request expires at height `{0}` which the chain tip has already reached")]
+    Expired(u64),
+    #[error("Payment request `{0}` not found")]
+    NotFound(String),
+    #[error("Signature error: `{0}`")]
+    SignatureError(#[from] SchnorrSignatureError),
 }
 
 #[derive(Debug, Error)]
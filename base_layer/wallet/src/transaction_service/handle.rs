@@ -21,16 +21,54 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    output_manager_service::TxId,
+    output_manager_service::{
+        htlc::HtlcDetails,
+        storage::models::{AccountId, HtlcOutput},
+        TxId,
+    },
     transaction_service::{
+        acceptance_policy::{IncomingTransaction, TransactionAcceptancePolicy},
         error::TransactionServiceError,
-        storage::models::{CompletedTransaction, InboundTransaction, OutboundTransaction, WalletTransaction},
+        multisig::{
+            MultisigNonceCommitment,
+            MultisigNonceRequest,
+            MultisigNonces,
+            MultisigOutput,
+            MultisigPartialSignatures,
+            MultisigParticipant,
+            MultisigSigningRequest,
+            MultisigSpendProposal,
+            MultisigUpdate,
+            MultisigWallet,
+            MultisigWalletId,
+        },
+        offline_signing::{
+            OfflineRecipientReply,
+            OfflineSenderMessage,
+            OfflineSigningOutcome,
+            OfflineTransactionSummary,
+            SignedTransaction,
+            UnsignedTransaction,
+        },
+        payment_request::{PaymentReference, PaymentRequest},
+        storage::models::{
+            CompletedTransaction,
+            InboundTransaction,
+            OutboundTransaction,
+            PaymentRequestRecord,
+            ScheduledTransaction,
+            TransactionSchedule,
+            WalletTransaction,
+        },
     },
 };
 use aes_gcm::Aes256Gcm;
+use serde::Serialize;
 use std::{collections::HashMap, fmt, sync::Arc};
+use tari_common_types::types::{Commitment, PublicKey};
 use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{tari_amount::MicroTari, transaction::Transaction};
+use tari_crypto::tari_utilities::hex::Hex;
 use tari_service_framework::reply_channel::SenderService;
 use tokio::sync::broadcast;
 use tower::Service;
@@ -47,7 +85,12 @@ pub enum TransactionServiceRequest {
     GetCompletedTransaction(TxId),
     GetAnyTransaction(TxId),
     SendTransaction(CommsPublicKey, MicroTari, MicroTari, String),
+    SendTransactionFromAccount(AccountId, CommsPublicKey, MicroTari, MicroTari, String),
+    TransferBetweenAccounts(AccountId, AccountId, MicroTari, MicroTari, String),
     SendOneSidedTransaction(CommsPublicKey, MicroTari, MicroTari, String),
+    SendTimeLockedTransaction(CommsPublicKey, MicroTari, u64, MicroTari, String),
+    ScheduleTransaction(CommsPublicKey, MicroTari, MicroTari, String, TransactionSchedule),
+    GetScheduledTransactions,
     CancelTransaction(TxId),
     ImportUtxo(MicroTari, CommsPublicKey, String, Option<u64>),
     SubmitCoinSplitTransaction(TxId, Transaction, MicroTari, MicroTari, String),
@@ -61,6 +104,45 @@ pub enum TransactionServiceRequest {
     GetNumConfirmationsRequired,
     SetNumConfirmationsRequired(u64),
     ValidateTransactions,
+    PrepareOfflineTransaction(CommsPublicKey, MicroTari, MicroTari, String, bool),
+    SummarizeOfflineTransaction(Box<UnsignedTransaction>),
+    SignOfflineTransaction(Box<UnsignedTransaction>),
+    RelayOfflineSenderMessage(Box<OfflineSenderMessage>),
+    GetOfflineRecipientReply(TxId),
+    FinalizeOfflineTransaction(Box<OfflineRecipientReply>),
+    SubmitOfflineTransaction(Box<SignedTransaction>),
+    CancelOfflineTransaction(TxId),
+    CreateMultisigParticipant,
+    CreateMultisigWallet(usize, Vec<MultisigParticipant>),
+    ImportMultisigWallet(Box<MultisigWallet>),
+    GetMultisigOutputs(MultisigWalletId),
+    SendToMultisig(MultisigWalletId, MicroTari, MicroTari, String),
+    ImportMultisigUpdate(Box<MultisigUpdate>),
+    ProposeMultisigSpend(
+        MultisigWalletId,
+        Vec<PublicKey>,
+        CommsPublicKey,
+        MicroTari,
+        MicroTari,
+        String,
+    ),
+    CommitMultisigNonces(Box<MultisigSpendProposal>),
+    AddMultisigNonceCommitment(Box<MultisigNonceCommitment>),
+    RevealMultisigNonces(Box<MultisigNonceRequest>),
+    AddMultisigNonces(Box<MultisigNonces>),
+    SignMultisigRequest(Box<MultisigSigningRequest>),
+    AddMultisigPartialSignatures(Box<MultisigPartialSignatures>),
+    CancelMultisigSpend(TxId),
+    SendHtlc(CommsPublicKey, MicroTari, MicroTari, [u8; 32], u64, String),
+    ImportHtlc(Box<HtlcDetails>, MicroTari),
+    SpendHtlc(Commitment, String),
+    CreatePaymentRequest(MicroTari, String, u64),
+    GetPaymentRequests,
+    GetPaymentRequest(PaymentReference),
+    SetAcceptancePolicy(Arc<dyn TransactionAcceptancePolicy>),
+    GetHeldTransactions,
+    ApproveHeldTransaction(TxId),
+    RejectHeldTransaction(TxId),
 }
 
 impl fmt::Display for TransactionServiceRequest {
@@ -74,9 +156,25 @@ impl fmt::Display for TransactionServiceRequest {
             Self::GetCancelledCompletedTransactions => f.write_str("GetCancelledCompletedTransactions"),
             Self::GetCompletedTransaction(t) => f.write_str(&format!("GetCompletedTransaction({})", t)),
             Self::SendTransaction(k, v, _, msg) => f.write_str(&format!("SendTransaction (to {}, {}, {})", k, v, msg)),
+            Self::SendTransactionFromAccount(a, k, v, _, msg) => f.write_str(&format!(
+                "SendTransactionFromAccount (from account {} to {}, {}, {})",
+                a, k, v, msg
+            )),
+            Self::TransferBetweenAccounts(from, to, v, _, msg) => f.write_str(&format!(
+                "TransferBetweenAccounts (from account {} to account {}, {}, {})",
+                from, to, v, msg
+            )),
             Self::SendOneSidedTransaction(k, v, _, msg) => {
                 f.write_str(&format!("SendOneSidedTransaction (to {}, {}, {})", k, v, msg))
             },
+            Self::SendTimeLockedTransaction(k, v, maturity, _, msg) => f.write_str(&format!(
+                "SendTimeLockedTransaction (to {}, {}, maturity {}, {})",
+                k, v, maturity, msg
+            )),
+            Self::ScheduleTransaction(k, v, _, msg, schedule) => {
+                f.write_str(&format!("ScheduleTransaction (to {}, {}, {}, {})", k, v, msg, schedule))
+            },
+            Self::GetScheduledTransactions => f.write_str("GetScheduledTransactions"),
             Self::CancelTransaction(t) => f.write_str(&format!("CancelTransaction ({})", t)),
             Self::ImportUtxo(v, k, msg, maturity) => f.write_str(&format!(
                 "ImportUtxo (from {}, {}, {} with maturity: {})",
@@ -101,6 +199,56 @@ impl fmt::Display for TransactionServiceRequest {
             Self::SetNumConfirmationsRequired(_) => f.write_str("SetNumConfirmationsRequired"),
             Self::GetAnyTransaction(t) => f.write_str(&format!("GetAnyTransaction({})", t)),
             TransactionServiceRequest::ValidateTransactions => f.write_str("ValidateTransactions"),
+            Self::PrepareOfflineTransaction(k, v, _, msg, one_sided) => f.write_str(&format!(
+                "PrepareOfflineTransaction (to {}, {}, {}, one-sided: {})",
+                k, v, msg, one_sided
+            )),
+            Self::SummarizeOfflineTransaction(t) => f.write_str(&format!("SummarizeOfflineTransaction ({})", t.tx_id)),
+            Self::SignOfflineTransaction(t) => f.write_str(&format!("SignOfflineTransaction ({})", t.tx_id)),
+            Self::RelayOfflineSenderMessage(m) => f.write_str(&format!("RelayOfflineSenderMessage ({})", m.tx_id)),
+            Self::GetOfflineRecipientReply(t) => f.write_str(&format!("GetOfflineRecipientReply ({})", t)),
+            Self::FinalizeOfflineTransaction(r) => f.write_str(&format!("FinalizeOfflineTransaction ({})", r.tx_id)),
+            Self::SubmitOfflineTransaction(t) => f.write_str(&format!("SubmitOfflineTransaction ({})", t.tx_id)),
+            Self::CancelOfflineTransaction(t) => f.write_str(&format!("CancelOfflineTransaction ({})", t)),
+            Self::CreateMultisigParticipant => f.write_str("CreateMultisigParticipant"),
+            Self::CreateMultisigWallet(threshold, participants) => f.write_str(&format!(
+                "CreateMultisigWallet ({} of {})",
+                threshold,
+                participants.len()
+            )),
+            Self::ImportMultisigWallet(w) => f.write_str(&format!("ImportMultisigWallet ({})", w.id)),
+            Self::GetMultisigOutputs(w) => f.write_str(&format!("GetMultisigOutputs ({})", w)),
+            Self::SendToMultisig(w, v, _, msg) => f.write_str(&format!("SendToMultisig (to {}, {}, {})", w, v, msg)),
+            Self::ImportMultisigUpdate(u) => f.write_str(&format!("ImportMultisigUpdate ({})", u.tx_id)),
+            Self::ProposeMultisigSpend(w, _, k, v, _, msg) => {
+                f.write_str(&format!("ProposeMultisigSpend (from {} to {}, {}, {})", w, k, v, msg))
+            },
+            Self::CommitMultisigNonces(p) => f.write_str(&format!("CommitMultisigNonces ({})", p.session_id)),
+            Self::AddMultisigNonceCommitment(c) => {
+                f.write_str(&format!("AddMultisigNonceCommitment ({})", c.session_id))
+            },
+            Self::RevealMultisigNonces(r) => f.write_str(&format!("RevealMultisigNonces ({})", r.session_id)),
+            Self::AddMultisigNonces(n) => f.write_str(&format!("AddMultisigNonces ({})", n.session_id)),
+            Self::SignMultisigRequest(r) => f.write_str(&format!("SignMultisigRequest ({})", r.session_id)),
+            Self::AddMultisigPartialSignatures(p) => {
+                f.write_str(&format!("AddMultisigPartialSignatures ({})", p.session_id))
+            },
+            Self::CancelMultisigSpend(t) => f.write_str(&format!("CancelMultisigSpend ({})", t)),
+            Self::SendHtlc(k, v, _, _, timeout, msg) => {
+                f.write_str(&format!("SendHtlc (to {}, {}, timeout {}, {})", k, v, timeout, msg))
+            },
+            Self::ImportHtlc(d, _) => f.write_str(&format!("ImportHtlc ({})", d.commitment.to_hex())),
+            Self::SpendHtlc(c, msg) => f.write_str(&format!("SpendHtlc ({}, {})", c.to_hex(), msg)),
+            Self::CreatePaymentRequest(v, msg, expiry_height) => f.write_str(&format!(
+                "CreatePaymentRequest ({}, {}, expires at {})",
+                v, msg, expiry_height
+            )),
+            Self::GetPaymentRequests => f.write_str("GetPaymentRequests"),
+            Self::GetPaymentRequest(r) => f.write_str(&format!("GetPaymentRequest ({:016x})", r)),
+            Self::SetAcceptancePolicy(_) => f.write_str("SetAcceptancePolicy"),
+            Self::GetHeldTransactions => f.write_str("GetHeldTransactions"),
+            Self::ApproveHeldTransaction(tx_id) => f.write_str(&format!("ApproveHeldTransaction ({})", tx_id)),
+            Self::RejectHeldTransaction(tx_id) => f.write_str(&format!("RejectHeldTransaction ({})", tx_id)),
         }
     }
 }
@@ -128,10 +276,39 @@ pub enum TransactionServiceResponse {
     NumConfirmationsSet,
     ValidationStarted(u64),
     CompletedTransactionValidityChanged,
+    OfflineTransactionPrepared(Box<UnsignedTransaction>),
+    OfflineTransactionSummarized(Box<OfflineTransactionSummary>),
+    OfflineTransactionSigned(OfflineSigningOutcome),
+    OfflineSenderMessageRelayed,
+    OfflineRecipientReply(Box<OfflineRecipientReply>),
+    OfflineTransactionFinalized(Box<SignedTransaction>),
+    MultisigParticipantCreated(MultisigParticipant),
+    MultisigWalletCreated(Box<MultisigWallet>),
+    MultisigWalletImported,
+    MultisigOutputs(Vec<MultisigOutput>),
+    MultisigUpdate(Box<MultisigUpdate>),
+    MultisigUpdateImported(usize),
+    MultisigSpendProposed(Box<MultisigSpendProposal>),
+    MultisigNoncesCommitted(Box<MultisigNonceCommitment>),
+    MultisigNonceRequest(Option<Box<MultisigNonceRequest>>),
+    MultisigNoncesRevealed(Box<MultisigNonces>),
+    MultisigSigningRequest(Option<Box<MultisigSigningRequest>>),
+    MultisigRequestSigned(Box<MultisigPartialSignatures>),
+    MultisigSpendCompleted(Option<Box<MultisigUpdate>>),
+    HtlcSent(TxId, Box<HtlcDetails>),
+    HtlcImported(Box<HtlcOutput>),
+    ScheduledTransactions(Vec<ScheduledTransaction>),
+    PaymentRequestCreated(Box<PaymentRequest>),
+    PaymentRequests(Vec<PaymentRequestRecord>),
+    PaymentRequest(Box<PaymentRequestRecord>),
+    AcceptancePolicySet,
+    HeldTransactions(Vec<IncomingTransaction>),
+    HeldTransactionApproved,
+    HeldTransactionRejected,
 }
 
 /// Events that can be published on the Text Message Service Event Stream
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize)]
 pub enum TransactionEvent {
     MempoolBroadcastTimedOut(TxId),
     ReceivedTransaction(TxId),
@@ -144,6 +321,7 @@ pub enum TransactionEvent {
     TransactionCancelled(TxId),
     TransactionBroadcast(TxId),
     TransactionImported(TxId),
+    TransactionScheduled(TxId),
     TransactionMined {
         tx_id: TxId,
         is_valid: bool,
@@ -160,6 +338,12 @@ pub enum TransactionEvent {
     TransactionValidationFailure(u64),
     TransactionValidationAborted(u64),
     TransactionValidationDelayed(u64),
+    /// A payment was matched to a payment request or the request expired
+    PaymentRequestUpdated(PaymentReference),
+    /// An inbound transaction is waiting for approval before a reply is sent to the sender
+    TransactionHeldForApproval(TxId),
+    /// An inbound transaction was declined by the acceptance policy, by the user or because its approval timed out
+    TransactionRejected(TxId),
     Error(String),
 }
 
@@ -210,6 +394,215 @@ impl TransactionServiceHandle {
         }
     }
 
+    /// Send a transaction that is funded from the outputs of the specified account
+    pub async fn send_transaction_from_account(
+        &mut self,
+        account_id: AccountId,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendTransactionFromAccount(
+                account_id,
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Move funds from one account of this wallet to another with a pay-to-self transaction
+    pub async fn transfer_between_accounts(
+        &mut self,
+        from_account_id: AccountId,
+        to_account_id: AccountId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::TransferBetweenAccounts(
+                from_account_id,
+                to_account_id,
+                amount,
+                fee_per_gram,
+                message,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send a transaction of which the recipient's output has the provided maturity, i.e. the recipient can only
+    /// spend the funds from that block height onwards
+    pub async fn send_time_locked_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        maturity: u64,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendTimeLockedTransaction(
+                dest_pubkey,
+                amount,
+                maturity,
+                fee_per_gram,
+                message,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Negotiate a transaction with the recipient now, but only broadcast it once the schedule is due. Scheduled
+    /// transactions are persisted and will still be broadcast if the wallet is restarted in the meantime.
+    pub async fn schedule_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        schedule: TransactionSchedule,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ScheduleTransaction(
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+                schedule,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_scheduled_transactions(&mut self) -> Result<Vec<ScheduledTransaction>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetScheduledTransactions)
+            .await??
+        {
+            TransactionServiceResponse::ScheduledTransactions(s) => Ok(s),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a payment request for this wallet that expires once the chain tip reaches `expiry_height`
+    pub async fn create_payment_request(
+        &mut self,
+        amount: MicroTari,
+        message: String,
+        expiry_height: u64,
+    ) -> Result<PaymentRequest, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreatePaymentRequest(
+                amount,
+                message,
+                expiry_height,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::PaymentRequestCreated(r) => Ok(*r),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_payment_requests(&mut self) -> Result<Vec<PaymentRequestRecord>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetPaymentRequests)
+            .await??
+        {
+            TransactionServiceResponse::PaymentRequests(r) => Ok(r),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_payment_request(
+        &mut self,
+        reference: PaymentReference,
+    ) -> Result<PaymentRequestRecord, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetPaymentRequest(reference))
+            .await??
+        {
+            TransactionServiceResponse::PaymentRequest(r) => Ok(*r),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Replace the policy that decides whether inbound transactions are accepted, rejected or held for approval
+    pub async fn set_acceptance_policy(
+        &mut self,
+        policy: Arc<dyn TransactionAcceptancePolicy>,
+    ) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SetAcceptancePolicy(policy))
+            .await??
+        {
+            TransactionServiceResponse::AcceptancePolicySet => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_held_transactions(&mut self) -> Result<Vec<IncomingTransaction>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetHeldTransactions)
+            .await??
+        {
+            TransactionServiceResponse::HeldTransactions(txs) => Ok(txs),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Approve a held inbound transaction, which sends the reply to the sender
+    pub async fn approve_held_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ApproveHeldTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::HeldTransactionApproved => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Reject a held inbound transaction, which notifies the sender that it was cancelled
+    pub async fn reject_held_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RejectHeldTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::HeldTransactionRejected => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn send_one_sided_transaction(
         &mut self,
         dest_pubkey: CommsPublicKey,
@@ -501,4 +894,423 @@ impl TransactionServiceHandle {
             _ => Err(TransactionServiceError::UnexpectedApiResponse),
         }
     }
+
+    /// Select and encumber the inputs of a transaction that will be signed by an offline signer
+    pub async fn prepare_offline_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        one_sided: bool,
+    ) -> Result<UnsignedTransaction, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::PrepareOfflineTransaction(
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+                one_sided,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionPrepared(t) => Ok(*t),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Summarize what an offline transaction pays as it would be signed by this wallet, without signing it. This is
+    /// called on the offline signer before signing.
+    pub async fn summarize_offline_transaction(
+        &mut self,
+        unsigned: UnsignedTransaction,
+    ) -> Result<OfflineTransactionSummary, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SummarizeOfflineTransaction(Box::new(
+                unsigned,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionSummarized(s) => Ok(*s),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Sign an offline transaction with the keys of this wallet. This is called on the offline signer.
+    pub async fn sign_offline_transaction(
+        &mut self,
+        unsigned: UnsignedTransaction,
+    ) -> Result<OfflineSigningOutcome, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SignOfflineTransaction(Box::new(unsigned)))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionSigned(o) => Ok(o),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send the sender message of an interactive offline transaction to the recipient
+    pub async fn relay_offline_sender_message(
+        &mut self,
+        sender_message: OfflineSenderMessage,
+    ) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RelayOfflineSenderMessage(Box::new(
+                sender_message,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::OfflineSenderMessageRelayed => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Get the recipient's reply to an interactive offline transaction so it can be passed to the offline signer
+    pub async fn get_offline_recipient_reply(
+        &mut self,
+        tx_id: TxId,
+    ) -> Result<OfflineRecipientReply, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetOfflineRecipientReply(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::OfflineRecipientReply(r) => Ok(*r),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Finalize an interactive offline transaction with the recipient's reply. This is called on the offline signer.
+    pub async fn finalize_offline_transaction(
+        &mut self,
+        reply: OfflineRecipientReply,
+    ) -> Result<SignedTransaction, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::FinalizeOfflineTransaction(Box::new(reply)))
+            .await??
+        {
+            TransactionServiceResponse::OfflineTransactionFinalized(t) => Ok(*t),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Import a signed offline transaction and broadcast it
+    pub async fn submit_offline_transaction(
+        &mut self,
+        signed: SignedTransaction,
+    ) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SubmitOfflineTransaction(Box::new(signed)))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSubmitted => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Release the inputs of an offline transaction and discard any state kept for it
+    pub async fn cancel_offline_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelOfflineTransaction(tx_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Create a new public key share that can be used to set up a multisig wallet
+    pub async fn create_multisig_participant(&mut self) -> Result<MultisigParticipant, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateMultisigParticipant)
+            .await??
+        {
+            TransactionServiceResponse::MultisigParticipantCreated(p) => Ok(p),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Set up a new multisig wallet between the participants. One of the participants must be a key share of this
+    /// wallet. The returned wallet must be imported by the other participants.
+    pub async fn create_multisig_wallet(
+        &mut self,
+        threshold: usize,
+        participants: Vec<MultisigParticipant>,
+    ) -> Result<MultisigWallet, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CreateMultisigWallet(threshold, participants))
+            .await??
+        {
+            TransactionServiceResponse::MultisigWalletCreated(w) => Ok(*w),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Import a multisig wallet that was set up by another participant
+    pub async fn import_multisig_wallet(&mut self, wallet: MultisigWallet) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ImportMultisigWallet(Box::new(wallet)))
+            .await??
+        {
+            TransactionServiceResponse::MultisigWalletImported => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    pub async fn get_multisig_outputs(
+        &mut self,
+        wallet_id: MultisigWalletId,
+    ) -> Result<Vec<MultisigOutput>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::GetMultisigOutputs(wallet_id))
+            .await??
+        {
+            TransactionServiceResponse::MultisigOutputs(o) => Ok(o),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send funds from this wallet to a multisig wallet. The returned update must be imported by the other
+    /// participants.
+    pub async fn send_to_multisig(
+        &mut self,
+        wallet_id: MultisigWalletId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<MultisigUpdate, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendToMultisig(
+                wallet_id,
+                amount,
+                fee_per_gram,
+                message,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::MultisigUpdate(u) => Ok(*u),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Apply an update of the outputs of a multisig wallet. Returns the number of outputs that were added.
+    pub async fn import_multisig_update(&mut self, update: MultisigUpdate) -> Result<usize, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ImportMultisigUpdate(Box::new(update)))
+            .await??
+        {
+            TransactionServiceResponse::MultisigUpdateImported(n) => Ok(n),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Start a signing session, as coordinator, for a one-sided payment from a multisig wallet
+    pub async fn propose_multisig_spend(
+        &mut self,
+        wallet_id: MultisigWalletId,
+        signers: Vec<PublicKey>,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<MultisigSpendProposal, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ProposeMultisigSpend(
+                wallet_id,
+                signers,
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::MultisigSpendProposed(p) => Ok(*p),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Validate a spend proposal, as signer, and commit to the nonces of this wallet
+    pub async fn commit_multisig_nonces(
+        &mut self,
+        proposal: MultisigSpendProposal,
+    ) -> Result<MultisigNonceCommitment, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CommitMultisigNonces(Box::new(proposal)))
+            .await??
+        {
+            TransactionServiceResponse::MultisigNoncesCommitted(c) => Ok(*c),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Add the nonce commitment of a signer, as coordinator. Returns the nonce request once all signers have
+    /// committed.
+    pub async fn add_multisig_nonce_commitment(
+        &mut self,
+        commitment: MultisigNonceCommitment,
+    ) -> Result<Option<MultisigNonceRequest>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::AddMultisigNonceCommitment(Box::new(
+                commitment,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::MultisigNonceRequest(r) => Ok(r.map(|r| *r)),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Reveal the nonces of this wallet, as signer, once all signers have committed
+    pub async fn reveal_multisig_nonces(
+        &mut self,
+        request: MultisigNonceRequest,
+    ) -> Result<MultisigNonces, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::RevealMultisigNonces(Box::new(request)))
+            .await??
+        {
+            TransactionServiceResponse::MultisigNoncesRevealed(n) => Ok(*n),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Add the nonces of a signer, as coordinator. Returns the signing request once all signers have revealed them.
+    pub async fn add_multisig_nonces(
+        &mut self,
+        nonces: MultisigNonces,
+    ) -> Result<Option<MultisigSigningRequest>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::AddMultisigNonces(Box::new(nonces)))
+            .await??
+        {
+            TransactionServiceResponse::MultisigSigningRequest(r) => Ok(r.map(|r| *r)),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Sign a signing request, as signer
+    pub async fn sign_multisig_request(
+        &mut self,
+        request: MultisigSigningRequest,
+    ) -> Result<MultisigPartialSignatures, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SignMultisigRequest(Box::new(request)))
+            .await??
+        {
+            TransactionServiceResponse::MultisigRequestSigned(p) => Ok(*p),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Add the partial signatures of a signer, as coordinator. Once all signers have signed, the transaction is
+    /// broadcast and the update for the other participants is returned.
+    pub async fn add_multisig_partial_signatures(
+        &mut self,
+        partial_signatures: MultisigPartialSignatures,
+    ) -> Result<Option<MultisigUpdate>, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::AddMultisigPartialSignatures(Box::new(
+                partial_signatures,
+            )))
+            .await??
+        {
+            TransactionServiceResponse::MultisigSpendCompleted(u) => Ok(u.map(|u| *u)),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Abandon a signing session, as coordinator, and release its inputs
+    pub async fn cancel_multisig_spend(&mut self, session_id: TxId) -> Result<(), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::CancelMultisigSpend(session_id))
+            .await??
+        {
+            TransactionServiceResponse::TransactionCancelled => Ok(()),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Send an HTLC output to `dest_pubkey` that it can claim with the preimage of `hash_lock` before
+    /// `timeout_height`, after which this wallet can refund it. The returned details must be given to the recipient.
+    pub async fn send_htlc(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        hash_lock: [u8; 32],
+        timeout_height: u64,
+        message: String,
+    ) -> Result<(TxId, HtlcDetails), TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SendHtlc(
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                hash_lock,
+                timeout_height,
+                message,
+            ))
+            .await??
+        {
+            TransactionServiceResponse::HtlcSent(tx_id, d) => Ok((tx_id, *d)),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Import the details of an HTLC output sent to this wallet. It is claimed automatically, using `fee_per_gram`, as
+    /// soon as it is mined and its preimage is known.
+    pub async fn import_htlc(
+        &mut self,
+        details: HtlcDetails,
+        fee_per_gram: MicroTari,
+    ) -> Result<HtlcOutput, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::ImportHtlc(Box::new(details), fee_per_gram))
+            .await??
+        {
+            TransactionServiceResponse::HtlcImported(h) => Ok(*h),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
+
+    /// Claim or refund an HTLC output now, rather than waiting for it to be done automatically
+    pub async fn spend_htlc(
+        &mut self,
+        commitment: Commitment,
+        message: String,
+    ) -> Result<TxId, TransactionServiceError> {
+        match self
+            .handle
+            .call(TransactionServiceRequest::SpendHtlc(commitment, message))
+            .await??
+        {
+            TransactionServiceResponse::TransactionSent(tx_id) => Ok(tx_id),
+            _ => Err(TransactionServiceError::UnexpectedApiResponse),
+        }
+    }
 }
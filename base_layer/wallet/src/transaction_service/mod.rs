@@ -24,9 +24,11 @@ use std::sync::Arc;
 
 use crate::{
     base_node_service::handle::BaseNodeServiceHandle,
+    contacts_service::handle::ContactsServiceHandle,
     output_manager_service::handle::OutputManagerHandle,
     storage::database::{WalletBackend, WalletDatabase},
     transaction_service::{
+        acceptance_policy::{AcceptAllPolicy, StandardAcceptancePolicy, TransactionAcceptancePolicy},
         config::TransactionServiceConfig,
         handle::TransactionServiceHandle,
         service::TransactionService,
@@ -58,9 +60,13 @@ use tari_service_framework::{
     ServiceInitializerContext,
 };
 
+pub mod acceptance_policy;
 pub mod config;
 pub mod error;
 pub mod handle;
+pub mod multisig;
+pub mod offline_signing;
+pub mod payment_request;
 pub mod protocols;
 pub mod service;
 pub mod storage;
@@ -212,6 +218,14 @@ where
             let output_manager_service = handles.expect_handle::<OutputManagerHandle>();
             let connectivity = handles.expect_handle::<WalletConnectivityHandle>();
             let base_node_service_handle = handles.expect_handle::<BaseNodeServiceHandle>();
+            let acceptance_policy: Arc<dyn TransactionAcceptancePolicy> = if config.acceptance_policy.has_rules() {
+                Arc::new(StandardAcceptancePolicy::from_config(
+                    &config.acceptance_policy,
+                    handles.get_handle::<ContactsServiceHandle>(),
+                ))
+            } else {
+                Arc::new(AcceptAllPolicy)
+            };
 
             let result = TransactionService::new(
                 config,
@@ -232,6 +246,7 @@ where
                 handles.get_shutdown_signal(),
                 base_node_service_handle,
             )
+            .with_acceptance_policy(acceptance_policy)
             .start()
             .await;
 
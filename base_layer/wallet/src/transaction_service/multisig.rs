@@ -0,0 +1,1579 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! n-of-m multi-signature outputs.
+//!
+//! A multisig wallet is set up between `m` participants, each of whom contributes a [MultisigParticipant] holding a
+//! fresh public key share. For every set of `n` signers a MuSig style aggregate key is computed and the output script
+//! accepts any one of these aggregate keys as the script key:
+//!
+//! `Dup PushPubKey(K_1) ... PushPubKey(K_c) OrVerify(c)`
+//!
+//! The spending key of each output is derived from a view key that is shared by all participants, so every
+//! participant can recognise, open and build transactions for the outputs while the script signature still requires
+//! the cooperation of a full signer set.
+//!
+//! Spending happens in a signing session run by a coordinator, which must be a participant but need not be a signer:
+//! 1. The coordinator selects the inputs and sends a [MultisigSpendProposal], which commits to the coordinator's
+//!    nonces, to the signers.
+//! 2. Each signer validates the proposal and replies with a [MultisigNonceCommitment] to its nonces.
+//! 3. Once all commitments are collected the coordinator sends them to the signers in a [MultisigNonceRequest].
+//! 4. Each signer replies with its [MultisigNonces], which must match its commitment.
+//! 5. Once all nonces are collected the coordinator builds the outputs and sends a [MultisigSigningRequest].
+//! 6. Each signer checks every nonce against its commitment and replies with [MultisigPartialSignatures], which the
+//!    coordinator aggregates into the final transaction.
+//!
+//! The commitment round follows MuSig1: no nonce is revealed before all of them are fixed, so neither the coordinator
+//! nor another signer can choose its nonces based on those of the others, which would allow a forgery over concurrent
+//! sessions. Every signer also contributes a random share to the sender offset key of each output so that its share of
+//! the script offset does not reveal its private key share. A signer only keeps one open session per multisig wallet.
+//!
+//! All messages are plain serializable types so they can be exchanged as [MultisigPackage] files or over any other
+//! channel.
+
+use crate::{output_manager_service::TxId, transaction_service::error::MultisigError};
+use digest::Digest;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+use tari_common_types::types::{
+    ComSignature,
+    Commitment,
+    HashDigest,
+    MessageHash,
+    PrivateKey,
+    PublicKey,
+    RangeProof,
+    Signature,
+};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{
+    fee::Fee,
+    tari_amount::MicroTari,
+    transaction::{
+        KernelBuilder,
+        OutputFeatures,
+        Transaction,
+        TransactionBuilder,
+        TransactionInput,
+        TransactionOutput,
+        MINIMUM_TRANSACTION_FEE,
+    },
+    transaction_protocol::{build_challenge, RewindData, TransactionMetadata},
+    CryptoFactories,
+};
+use tari_crypto::{
+    commitment::HomomorphicCommitmentFactory,
+    keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait, SecretKey},
+    range_proof::{RangeProofService, REWIND_USER_MESSAGE_LENGTH},
+    script,
+    script::{ExecutionStack, Opcode, StackItem, TariScript},
+    tari_utilities::{hex::Hex, ByteArray},
+};
+
+/// The version of the multisig package file format. This must be incremented when the format changes.
+pub const MULTISIG_PACKAGE_VERSION: u32 = 2;
+/// The maximum number of signer sets, i.e. `m choose n`, that a multisig script may contain
+pub const MAX_MULTISIG_SIGNER_SETS: usize = 64;
+
+/// Client key value prefix for the key index of a public key share created by this wallet
+pub(crate) const MULTISIG_SHARE_KEY_PREFIX: &str = "multisig_share_";
+/// Client key value prefix for a multisig wallet and its outputs
+pub(crate) const MULTISIG_WALLET_KEY_PREFIX: &str = "multisig_wallet_";
+/// Client key value prefix for the open signing session of a multisig wallet on the signer side
+pub(crate) const MULTISIG_SIGNER_KEY_PREFIX: &str = "multisig_signer_";
+/// Client key value prefix for a signing session on the coordinator side
+pub(crate) const MULTISIG_COORDINATOR_KEY_PREFIX: &str = "multisig_coordinator_";
+
+pub type MultisigWalletId = u64;
+
+/// A participant in a multisig wallet
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MultisigParticipant {
+    /// The comms public key of the participant's wallet
+    pub address: CommsPublicKey,
+    /// The public key share that the participant signs with
+    pub public_key: PublicKey,
+}
+
+/// The shared definition of an n-of-m multisig wallet. This includes the private view key, so it must only be shared
+/// with the participants.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigWallet {
+    pub id: MultisigWalletId,
+    pub threshold: usize,
+    pub participants: Vec<MultisigParticipant>,
+    pub view_key: PrivateKey,
+}
+
+impl MultisigWallet {
+    /// Set up a new multisig wallet requiring `threshold` of the `participants` to spend
+    pub fn new(threshold: usize, mut participants: Vec<MultisigParticipant>) -> Result<Self, MultisigError> {
+        participants.sort_by(|a, b| a.public_key.as_bytes().cmp(b.public_key.as_bytes()));
+        let wallet = Self {
+            id: OsRng.next_u64(),
+            threshold,
+            participants,
+            view_key: PrivateKey::random(&mut OsRng),
+        };
+        wallet.validate()?;
+        Ok(wallet)
+    }
+
+    /// Check that the threshold and participants describe a valid multisig wallet
+    pub fn validate(&self) -> Result<(), MultisigError> {
+        let num_participants = self.participants.len();
+        if num_participants < 2 || self.threshold == 0 || self.threshold > num_participants {
+            return Err(MultisigError::InvalidThreshold {
+                threshold: self.threshold,
+                participants: num_participants,
+            });
+        }
+        if self
+            .participants
+            .windows(2)
+            .any(|w| w[0].public_key.as_bytes() >= w[1].public_key.as_bytes())
+        {
+            return Err(MultisigError::InvalidParticipants);
+        }
+        let num_signer_sets = num_combinations(num_participants, self.threshold);
+        if num_signer_sets > MAX_MULTISIG_SIGNER_SETS {
+            return Err(MultisigError::TooManySignerSets(num_signer_sets));
+        }
+        Ok(())
+    }
+
+    pub fn view_public_key(&self) -> PublicKey {
+        PublicKey::from_secret_key(&self.view_key)
+    }
+
+    pub fn is_participant(&self, public_key: &PublicKey) -> bool {
+        self.participants.iter().any(|p| &p.public_key == public_key)
+    }
+
+    /// All the sets of `threshold` participant keys that can spend from this wallet
+    pub fn signer_sets(&self) -> Vec<Vec<PublicKey>> {
+        combinations(self.participants.len(), self.threshold)
+            .into_iter()
+            .map(|set| {
+                set.into_iter()
+                    .map(|i| self.participants[i].public_key.clone())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The script that locks outputs to this wallet
+    pub fn script(&self) -> Result<TariScript, MultisigError> {
+        let signer_sets = self.signer_sets();
+        let num_signer_sets = signer_sets.len() as u8;
+        let mut opcodes = vec![Opcode::Dup];
+        for signers in signer_sets {
+            opcodes.push(Opcode::PushPubKey(Box::new(aggregate_public_key(&signers)?)));
+        }
+        opcodes.push(Opcode::OrVerify(num_signer_sets));
+        Ok(TariScript::new(opcodes))
+    }
+
+    /// Sort and check a set of signers, which must be `threshold` distinct participants of this wallet
+    pub fn sorted_signers(&self, signers: &[PublicKey]) -> Result<Vec<PublicKey>, MultisigError> {
+        let mut sorted = signers.to_vec();
+        sorted.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        sorted.dedup();
+        if sorted.len() != self.threshold || sorted.iter().any(|k| !self.is_participant(k)) {
+            return Err(MultisigError::InvalidSigners);
+        }
+        Ok(sorted)
+    }
+
+    /// The input data that selects the aggregate key of the given signers when spending an output of this wallet
+    pub fn input_data(&self, signers: &[PublicKey]) -> Result<ExecutionStack, MultisigError> {
+        let signers = self.sorted_signers(signers)?;
+        Ok(ExecutionStack::new(vec![StackItem::PublicKey(aggregate_public_key(
+            &signers,
+        )?)]))
+    }
+
+    /// Derive the spending key of an output of this wallet from its sender offset public key
+    pub fn spending_key(&self, sender_offset_public_key: &PublicKey) -> Result<PrivateKey, MultisigError> {
+        Ok(PrivateKey::from_bytes(
+            PublicKey::shared_secret(&self.view_key, sender_offset_public_key).as_bytes(),
+        )?)
+    }
+
+    /// Open an output that is locked to this wallet
+    pub fn rewind_output(
+        &self,
+        output: &TransactionOutput,
+        factories: &CryptoFactories,
+    ) -> Result<MultisigOutput, MultisigError> {
+        if output.script != self.script()? {
+            return Err(MultisigError::NotAMultisigOutput);
+        }
+        let spending_key = self.spending_key(&output.sender_offset_public_key)?;
+        let rewind_data = rewind_data(&spending_key)?;
+        let rewound = output.full_rewind_range_proof(
+            &factories.range_proof,
+            &rewind_data.rewind_key,
+            &rewind_data.rewind_blinding_key,
+        )?;
+        if rewound.blinding_factor != spending_key {
+            return Err(MultisigError::NotAMultisigOutput);
+        }
+        Ok(MultisigOutput {
+            output: output.clone(),
+            value: rewound.committed_value,
+            spending_key,
+            status: MultisigOutputStatus::Unspent,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MultisigOutputStatus {
+    Unspent,
+    PendingSpend(TxId),
+    Spent,
+}
+
+/// An output locked to a multisig wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigOutput {
+    pub output: TransactionOutput,
+    pub value: MicroTari,
+    pub spending_key: PrivateKey,
+    pub status: MultisigOutputStatus,
+}
+
+/// Notifies the participants of outputs that were created for, or spent from, a multisig wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigUpdate {
+    pub wallet_id: MultisigWalletId,
+    pub tx_id: TxId,
+    pub spent: Vec<Commitment>,
+    pub outputs: Vec<TransactionOutput>,
+}
+
+/// An output of a multisig spend. The spending key of the output is derived from a Diffie-Hellman exchange with
+/// `public_key`, which is either the destination of a one-sided payment or the view key of the multisig wallet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigRecipient {
+    pub public_key: PublicKey,
+    pub value: MicroTari,
+    pub script: TariScript,
+}
+
+/// The transaction proposed by the coordinator of a signing session
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigSpendProposal {
+    pub session_id: TxId,
+    pub wallet_id: MultisigWalletId,
+    pub signers: Vec<PublicKey>,
+    pub inputs: Vec<TransactionOutput>,
+    pub recipients: Vec<MultisigRecipient>,
+    pub fee: MicroTari,
+    pub message: String,
+    /// The commitment of the coordinator to its nonces, which are revealed in the signing request
+    pub nonce_commitment: Vec<u8>,
+}
+
+/// The commitment of a signer to its [MultisigNonces]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MultisigNonceCommitment {
+    pub session_id: TxId,
+    pub signer: PublicKey,
+    pub commitment: Vec<u8>,
+}
+
+/// The nonce commitments of all signers, which a signer must hold before it reveals its nonces
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigNonceRequest {
+    pub session_id: TxId,
+    pub wallet_id: MultisigWalletId,
+    pub commitments: Vec<MultisigNonceCommitment>,
+}
+
+/// The public nonces and sender offset key shares of a signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigNonces {
+    pub session_id: TxId,
+    pub signer: PublicKey,
+    /// One nonce per input for the script signatures
+    pub script_nonces: Vec<PublicKey>,
+    /// One share of the sender offset public key per output
+    pub offset_keys: Vec<PublicKey>,
+    /// The Diffie-Hellman share of each output's sender offset key with the recipient's public key
+    pub offset_shared_secrets: Vec<PublicKey>,
+    /// One nonce per output for the metadata signatures
+    pub metadata_nonces: Vec<PublicKey>,
+}
+
+impl MultisigNonces {
+    /// The hash that commits a signer to these nonces
+    pub fn commitment(&self) -> Vec<u8> {
+        let hasher = HashDigest::new()
+            .chain(self.session_id.to_le_bytes())
+            .chain(self.signer.as_bytes());
+        [
+            &self.script_nonces,
+            &self.offset_keys,
+            &self.offset_shared_secrets,
+            &self.metadata_nonces,
+        ]
+        .iter()
+        .fold(hasher, |hasher, keys| {
+            keys.iter()
+                .fold(hasher.chain((keys.len() as u64).to_le_bytes()), |hasher, k| {
+                    hasher.chain(k.as_bytes())
+                })
+        })
+        .finalize()
+        .to_vec()
+    }
+
+    fn matches_proposal(&self, proposal: &MultisigSpendProposal) -> bool {
+        let num_outputs = proposal.recipients.len();
+        self.session_id == proposal.session_id &&
+            self.script_nonces.len() == proposal.inputs.len() &&
+            self.offset_keys.len() == num_outputs &&
+            self.offset_shared_secrets.len() == num_outputs &&
+            self.metadata_nonces.len() == num_outputs
+    }
+}
+
+/// The outputs and aggregate nonces that the signers must sign, along with the nonces they were aggregated from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigSigningRequest {
+    pub session_id: TxId,
+    pub wallet_id: MultisigWalletId,
+    pub outputs: Vec<TransactionOutput>,
+    pub spending_keys: Vec<PrivateKey>,
+    pub script_nonces: Vec<Commitment>,
+    pub metadata_nonces: Vec<Commitment>,
+    /// The nonces of the coordinator, which must match the commitment in the proposal
+    pub coordinator_script_nonces: Vec<Commitment>,
+    pub coordinator_metadata_nonces: Vec<Commitment>,
+    /// The nonces of every signer, which must match the signers' commitments
+    pub nonces: Vec<MultisigNonces>,
+}
+
+impl MultisigSigningRequest {
+    /// Check that the aggregate nonces are made up of the committed nonces of the coordinator and every signer
+    fn verify_nonces(
+        &self,
+        proposal: &MultisigSpendProposal,
+        commitments: &[MultisigNonceCommitment],
+    ) -> Result<(), MultisigError> {
+        if coordinator_nonce_commitment(
+            proposal.session_id,
+            &self.coordinator_script_nonces,
+            &self.coordinator_metadata_nonces,
+        ) != proposal.nonce_commitment ||
+            self.coordinator_script_nonces.len() != proposal.inputs.len() ||
+            self.coordinator_metadata_nonces.len() != proposal.recipients.len()
+        {
+            return Err(MultisigError::InvalidSigningRequest(
+                "Coordinator nonces do not match the proposal".to_string(),
+            ));
+        }
+        if self.nonces.len() != commitments.len() ||
+            commitments.iter().any(|c| {
+                !self
+                    .nonces
+                    .iter()
+                    .any(|n| n.signer == c.signer && n.matches_proposal(proposal) && n.commitment() == c.commitment)
+            })
+        {
+            return Err(MultisigError::InvalidSigningRequest(
+                "Signer nonces do not match their commitments".to_string(),
+            ));
+        }
+        let script_nonces = aggregate_nonces(&self.coordinator_script_nonces, &self.nonces, |n| &n.script_nonces);
+        let metadata_nonces = aggregate_nonces(&self.coordinator_metadata_nonces, &self.nonces, |n| &n.metadata_nonces);
+        if script_nonces != self.script_nonces || metadata_nonces != self.metadata_nonces {
+            return Err(MultisigError::InvalidSigningRequest(
+                "Aggregate nonces do not match the committed nonces".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn script_challenges(
+        &self,
+        wallet: &MultisigWallet,
+        proposal: &MultisigSpendProposal,
+    ) -> Result<Vec<MessageHash>, MultisigError> {
+        let input_data = wallet.input_data(&proposal.signers)?;
+        let script_public_key = aggregate_public_key(&proposal.signers)?;
+        Ok(proposal
+            .inputs
+            .iter()
+            .zip(self.script_nonces.iter())
+            .map(|(input, nonce)| {
+                TransactionInput::build_script_challenge(
+                    nonce,
+                    &input.script,
+                    &input_data,
+                    &script_public_key,
+                    &input.commitment,
+                )
+            })
+            .collect())
+    }
+
+    fn metadata_challenges(&self) -> Vec<MessageHash> {
+        self.outputs
+            .iter()
+            .zip(self.metadata_nonces.iter())
+            .map(|(output, nonce)| {
+                TransactionOutput::build_metadata_signature_challenge(
+                    &output.script,
+                    &output.features,
+                    &output.sender_offset_public_key,
+                    nonce,
+                    &output.commitment,
+                )
+            })
+            .collect()
+    }
+}
+
+/// The partial signatures of a signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigPartialSignatures {
+    pub session_id: TxId,
+    pub signer: PublicKey,
+    pub script_signatures: Vec<PrivateKey>,
+    pub metadata_signatures: Vec<PrivateKey>,
+    pub script_offset: PrivateKey,
+}
+
+/// The private state of a signer during a signing session. This must never leave the signer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigSignerSession {
+    proposal: MultisigSpendProposal,
+    share_public_key: PublicKey,
+    script_nonces: Vec<PrivateKey>,
+    offset_keys: Vec<PrivateKey>,
+    metadata_nonces: Vec<PrivateKey>,
+    nonces: MultisigNonces,
+    commitments: Vec<MultisigNonceCommitment>,
+}
+
+impl MultisigSignerSession {
+    /// Validate a proposal, generate the nonces of this signer and commit to them
+    pub fn new(
+        wallet: &MultisigWallet,
+        share_public_key: PublicKey,
+        proposal: MultisigSpendProposal,
+        factories: &CryptoFactories,
+    ) -> Result<(Self, MultisigNonceCommitment), MultisigError> {
+        validate_proposal(wallet, &share_public_key, &proposal, factories)?;
+
+        let script_nonces = random_keys(proposal.inputs.len());
+        let offset_keys = random_keys(proposal.recipients.len());
+        let metadata_nonces = random_keys(proposal.recipients.len());
+        let nonces = MultisigNonces {
+            session_id: proposal.session_id,
+            signer: share_public_key.clone(),
+            script_nonces: script_nonces.iter().map(PublicKey::from_secret_key).collect(),
+            offset_keys: offset_keys.iter().map(PublicKey::from_secret_key).collect(),
+            offset_shared_secrets: offset_keys
+                .iter()
+                .zip(proposal.recipients.iter())
+                .map(|(k, recipient)| PublicKey::shared_secret(k, &recipient.public_key))
+                .collect(),
+            metadata_nonces: metadata_nonces.iter().map(PublicKey::from_secret_key).collect(),
+        };
+        let commitment = MultisigNonceCommitment {
+            session_id: proposal.session_id,
+            signer: share_public_key.clone(),
+            commitment: nonces.commitment(),
+        };
+
+        Ok((
+            Self {
+                proposal,
+                share_public_key,
+                script_nonces,
+                offset_keys,
+                metadata_nonces,
+                nonces,
+                commitments: Vec::new(),
+            },
+            commitment,
+        ))
+    }
+
+    pub fn proposal(&self) -> &MultisigSpendProposal {
+        &self.proposal
+    }
+
+    /// Reveal the nonces of this signer once the commitments of all signers are known. The commitments are kept to
+    /// check the signing request against, and can not be replaced once the nonces have been revealed.
+    pub fn reveal_nonces(&mut self, request: &MultisigNonceRequest) -> Result<MultisigNonces, MultisigError> {
+        if request.session_id != self.proposal.session_id {
+            return Err(MultisigError::SessionNotFound(request.session_id));
+        }
+        if !self.commitments.is_empty() {
+            if self.commitments != request.commitments {
+                return Err(MultisigError::InvalidNonceCommitments);
+            }
+            return Ok(self.nonces.clone());
+        }
+        let signers = request.commitments.iter().map(|c| c.signer.clone()).collect::<Vec<_>>();
+        let own_commitment = self.nonces.commitment();
+        if signers != self.proposal.signers ||
+            request.commitments.iter().any(|c| c.session_id != request.session_id) ||
+            !request
+                .commitments
+                .iter()
+                .any(|c| c.signer == self.share_public_key && c.commitment == own_commitment)
+        {
+            return Err(MultisigError::InvalidNonceCommitments);
+        }
+        self.commitments = request.commitments.clone();
+        Ok(self.nonces.clone())
+    }
+
+    /// Check the signing request against the proposal and produce the partial signatures of this signer. The session
+    /// is consumed so that its nonces can not be used again.
+    pub fn sign(
+        self,
+        wallet: &MultisigWallet,
+        share_private_key: &PrivateKey,
+        request: &MultisigSigningRequest,
+        factories: &CryptoFactories,
+    ) -> Result<MultisigPartialSignatures, MultisigError> {
+        let proposal = &self.proposal;
+        if request.session_id != proposal.session_id {
+            return Err(MultisigError::SessionNotFound(request.session_id));
+        }
+        if self.commitments.is_empty() {
+            return Err(MultisigError::CommitmentsOutstanding(request.session_id));
+        }
+        if PublicKey::from_secret_key(share_private_key) != self.share_public_key {
+            return Err(MultisigError::NotAParticipant);
+        }
+        if request.outputs.len() != proposal.recipients.len() ||
+            request.spending_keys.len() != proposal.recipients.len() ||
+            request.metadata_nonces.len() != proposal.recipients.len() ||
+            request.script_nonces.len() != proposal.inputs.len()
+        {
+            return Err(MultisigError::InvalidSigningRequest(
+                "Signing request does not match the proposal".to_string(),
+            ));
+        }
+        request.verify_nonces(proposal, &self.commitments)?;
+        for ((output, spending_key), recipient) in request
+            .outputs
+            .iter()
+            .zip(request.spending_keys.iter())
+            .zip(proposal.recipients.iter())
+        {
+            if output.script != recipient.script ||
+                output.features != OutputFeatures::default() ||
+                output.commitment != factories.commitment.commit_value(spending_key, recipient.value.into())
+            {
+                return Err(MultisigError::InvalidSigningRequest(
+                    "Output does not match the proposed recipient".to_string(),
+                ));
+            }
+        }
+
+        let share_key = &key_coefficient(&proposal.signers, &self.share_public_key)? * share_private_key;
+        let script_signatures = request
+            .script_challenges(wallet, proposal)?
+            .iter()
+            .zip(self.script_nonces.iter())
+            .map(|(e, nonce)| partial_signature(&share_key, nonce, e))
+            .collect::<Result<Vec<_>, _>>()?;
+        let metadata_signatures = request
+            .metadata_challenges()
+            .iter()
+            .zip(self.offset_keys.iter().zip(self.metadata_nonces.iter()))
+            .map(|(e, (offset_key, nonce))| partial_signature(offset_key, nonce, e))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Every input has the aggregate key as its script key, while the offset key shares mask this signer's share
+        let script_offset = self.offset_keys.iter().fold(
+            &PrivateKey::from(proposal.inputs.len() as u64) * &share_key,
+            |acc, k| acc - k,
+        );
+
+        Ok(MultisigPartialSignatures {
+            session_id: proposal.session_id,
+            signer: self.share_public_key,
+            script_signatures,
+            metadata_signatures,
+            script_offset,
+        })
+    }
+}
+
+/// The private state of the coordinator during a signing session
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultisigCoordinatorSession {
+    proposal: MultisigSpendProposal,
+    input_values: Vec<MicroTari>,
+    input_spending_keys: Vec<PrivateKey>,
+    sender_offset_keys: Vec<PrivateKey>,
+    script_nonces: Vec<(PrivateKey, PrivateKey)>,
+    metadata_nonces: Vec<(PrivateKey, PrivateKey)>,
+    commitments: Vec<MultisigNonceCommitment>,
+    nonce_request: Option<MultisigNonceRequest>,
+    nonces: Vec<MultisigNonces>,
+    request: Option<MultisigSigningRequest>,
+    partial_signatures: Vec<MultisigPartialSignatures>,
+}
+
+impl MultisigCoordinatorSession {
+    /// Select inputs from the unspent `outputs` of the wallet and propose a one-sided payment of `amount` to
+    /// `destination`, with any change returned to the multisig wallet
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        wallet: &MultisigWallet,
+        outputs: &[MultisigOutput],
+        signers: &[PublicKey],
+        destination: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        factories: &CryptoFactories,
+    ) -> Result<(Self, MultisigSpendProposal), MultisigError> {
+        let signers = wallet.sorted_signers(signers)?;
+
+        let mut candidates = outputs
+            .iter()
+            .filter(|o| o.status == MultisigOutputStatus::Unspent)
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.value.cmp(&a.value));
+
+        let mut inputs = Vec::new();
+        let mut total = MicroTari::from(0);
+        let mut selection = None;
+        for output in candidates {
+            inputs.push(output);
+            total += output.value;
+            let fee_without_change = Fee::calculate(fee_per_gram, 1, inputs.len(), 1);
+            if total == amount + fee_without_change {
+                selection = Some((fee_without_change, MicroTari::from(0)));
+                break;
+            }
+            let fee_with_change = Fee::calculate(fee_per_gram, 1, inputs.len(), 2);
+            if total > amount + fee_with_change {
+                selection = Some((fee_with_change, total - amount - fee_with_change));
+                break;
+            }
+        }
+        let (fee, change) = selection.ok_or(MultisigError::InsufficientFunds)?;
+
+        let mut recipients = vec![MultisigRecipient {
+            public_key: destination.clone(),
+            value: amount,
+            script: script!(PushPubKey(Box::new(destination))),
+        }];
+        if change > MicroTari::from(0) {
+            recipients.push(MultisigRecipient {
+                public_key: wallet.view_public_key(),
+                value: change,
+                script: wallet.script()?,
+            });
+        }
+
+        let mut proposal = MultisigSpendProposal {
+            session_id: OsRng.next_u64(),
+            wallet_id: wallet.id,
+            signers,
+            inputs: inputs.iter().map(|o| o.output.clone()).collect(),
+            recipients,
+            fee,
+            message,
+            nonce_commitment: Vec::new(),
+        };
+        let mut session = Self {
+            input_values: inputs.iter().map(|o| o.value).collect(),
+            input_spending_keys: inputs.iter().map(|o| o.spending_key.clone()).collect(),
+            sender_offset_keys: random_keys(proposal.recipients.len()),
+            script_nonces: random_keys(proposal.inputs.len())
+                .into_iter()
+                .zip(random_keys(proposal.inputs.len()))
+                .collect(),
+            metadata_nonces: random_keys(proposal.recipients.len())
+                .into_iter()
+                .zip(random_keys(proposal.recipients.len()))
+                .collect(),
+            proposal: proposal.clone(),
+            commitments: Vec::new(),
+            nonce_request: None,
+            nonces: Vec::new(),
+            request: None,
+            partial_signatures: Vec::new(),
+        };
+        proposal.nonce_commitment = coordinator_nonce_commitment(
+            proposal.session_id,
+            &public_nonces(&session.script_nonces, factories),
+            &public_nonces(&session.metadata_nonces, factories),
+        );
+        session.proposal = proposal.clone();
+        Ok((session, proposal))
+    }
+
+    pub fn proposal(&self) -> &MultisigSpendProposal {
+        &self.proposal
+    }
+
+    /// The number of signers that still have to reply in the current round
+    pub fn pending_signers(&self) -> usize {
+        let replies = match (&self.nonce_request, &self.request) {
+            (None, _) => self.commitments.len(),
+            (Some(_), None) => self.nonces.len(),
+            (Some(_), Some(_)) => self.partial_signatures.len(),
+        };
+        self.proposal.signers.len() - replies
+    }
+
+    /// Add the nonce commitment of a signer. Once all signers have committed the nonce request is returned.
+    pub fn add_nonce_commitment(
+        &mut self,
+        commitment: MultisigNonceCommitment,
+    ) -> Result<Option<MultisigNonceRequest>, MultisigError> {
+        if commitment.session_id != self.proposal.session_id {
+            return Err(MultisigError::SessionNotFound(commitment.session_id));
+        }
+        if !self.proposal.signers.contains(&commitment.signer) {
+            return Err(MultisigError::InvalidSigners);
+        }
+        if let Some(existing) = self.commitments.iter().find(|c| c.signer == commitment.signer) {
+            if existing != &commitment {
+                return Err(MultisigError::InvalidNonceCommitments);
+            }
+            return Ok(self.nonce_request.clone());
+        }
+        self.commitments.push(commitment);
+        if self.commitments.len() < self.proposal.signers.len() {
+            return Ok(None);
+        }
+
+        // List the commitments in the same order as the signers of the proposal
+        self.commitments
+            .sort_by(|a, b| a.signer.as_bytes().cmp(b.signer.as_bytes()));
+        let request = MultisigNonceRequest {
+            session_id: self.proposal.session_id,
+            wallet_id: self.proposal.wallet_id,
+            commitments: self.commitments.clone(),
+        };
+        self.nonce_request = Some(request.clone());
+        Ok(Some(request))
+    }
+
+    /// Add the nonces of a signer, which must match its commitment. Once the nonces of all signers have been added the
+    /// signing request is returned.
+    pub fn add_nonces(
+        &mut self,
+        nonces: MultisigNonces,
+        factories: &CryptoFactories,
+    ) -> Result<Option<MultisigSigningRequest>, MultisigError> {
+        if nonces.session_id != self.proposal.session_id {
+            return Err(MultisigError::SessionNotFound(nonces.session_id));
+        }
+        if self.nonce_request.is_none() {
+            return Err(MultisigError::CommitmentsOutstanding(nonces.session_id));
+        }
+        let commitment = self
+            .commitments
+            .iter()
+            .find(|c| c.signer == nonces.signer)
+            .ok_or(MultisigError::InvalidSigners)?;
+        if self.request.is_some() || self.nonces.iter().any(|n| n.signer == nonces.signer) {
+            return Ok(self.request.clone());
+        }
+        if !nonces.matches_proposal(&self.proposal) || nonces.commitment() != commitment.commitment {
+            return Err(MultisigError::InvalidNonces);
+        }
+        self.nonces.push(nonces);
+        if self.nonces.len() < self.proposal.signers.len() {
+            return Ok(None);
+        }
+
+        let num_outputs = self.proposal.recipients.len();
+        let mut outputs = Vec::with_capacity(num_outputs);
+        let mut spending_keys = Vec::with_capacity(num_outputs);
+        for (j, recipient) in self.proposal.recipients.iter().enumerate() {
+            let coordinator_offset_key = &self.sender_offset_keys[j];
+            let sender_offset_public_key = self
+                .nonces
+                .iter()
+                .fold(PublicKey::from_secret_key(coordinator_offset_key), |acc, n| {
+                    &acc + &n.offset_keys[j]
+                });
+            let shared_secret = self.nonces.iter().fold(
+                PublicKey::shared_secret(coordinator_offset_key, &recipient.public_key),
+                |acc, n| &acc + &n.offset_shared_secrets[j],
+            );
+            let spending_key = PrivateKey::from_bytes(shared_secret.as_bytes())?;
+            let rewind_data = rewind_data(&spending_key)?;
+            let proof = factories.range_proof.construct_proof_with_rewind_key(
+                &spending_key,
+                recipient.value.into(),
+                &rewind_data.rewind_key,
+                &rewind_data.rewind_blinding_key,
+                &rewind_data.proof_message,
+            )?;
+            outputs.push(TransactionOutput::new(
+                OutputFeatures::default(),
+                factories.commitment.commit_value(&spending_key, recipient.value.into()),
+                RangeProof::from_bytes(&proof)?,
+                recipient.script.clone(),
+                sender_offset_public_key,
+                ComSignature::default(),
+            ));
+            spending_keys.push(spending_key);
+        }
+        let coordinator_script_nonces = public_nonces(&self.script_nonces, factories);
+        let coordinator_metadata_nonces = public_nonces(&self.metadata_nonces, factories);
+
+        let request = MultisigSigningRequest {
+            session_id: self.proposal.session_id,
+            wallet_id: self.proposal.wallet_id,
+            outputs,
+            spending_keys,
+            script_nonces: aggregate_nonces(&coordinator_script_nonces, &self.nonces, |n| &n.script_nonces),
+            metadata_nonces: aggregate_nonces(&coordinator_metadata_nonces, &self.nonces, |n| &n.metadata_nonces),
+            coordinator_script_nonces,
+            coordinator_metadata_nonces,
+            nonces: self.nonces.clone(),
+        };
+        self.request = Some(request.clone());
+        Ok(Some(request))
+    }
+
+    /// Add the partial signatures of a signer. Once the signatures of all signers have been added the final
+    /// transaction is returned.
+    pub fn add_partial_signatures(
+        &mut self,
+        wallet: &MultisigWallet,
+        partial_signatures: MultisigPartialSignatures,
+        factories: &CryptoFactories,
+    ) -> Result<Option<Transaction>, MultisigError> {
+        let request = self
+            .request
+            .clone()
+            .ok_or(MultisigError::NoncesOutstanding(self.proposal.session_id))?;
+        if partial_signatures.session_id != self.proposal.session_id {
+            return Err(MultisigError::SessionNotFound(partial_signatures.session_id));
+        }
+        let nonces = self
+            .nonces
+            .iter()
+            .find(|n| n.signer == partial_signatures.signer)
+            .ok_or(MultisigError::InvalidSigners)?;
+        if self
+            .partial_signatures
+            .iter()
+            .any(|p| p.signer == partial_signatures.signer)
+        {
+            return Ok(None);
+        }
+
+        let script_challenges = request.script_challenges(wallet, &self.proposal)?;
+        let metadata_challenges = request.metadata_challenges();
+        if partial_signatures.script_signatures.len() != script_challenges.len() ||
+            partial_signatures.metadata_signatures.len() != metadata_challenges.len()
+        {
+            return Err(MultisigError::InvalidPartialSignature);
+        }
+        let share_public_key = PublicKey::shared_secret(
+            &key_coefficient(&self.proposal.signers, &partial_signatures.signer)?,
+            &partial_signatures.signer,
+        );
+        for (l, e) in script_challenges.iter().enumerate() {
+            let signature = Signature::new(
+                nonces.script_nonces[l].clone(),
+                partial_signatures.script_signatures[l].clone(),
+            );
+            if !signature.verify_challenge(&share_public_key, e) {
+                return Err(MultisigError::InvalidPartialSignature);
+            }
+        }
+        for (j, e) in metadata_challenges.iter().enumerate() {
+            let signature = Signature::new(
+                nonces.metadata_nonces[j].clone(),
+                partial_signatures.metadata_signatures[j].clone(),
+            );
+            if !signature.verify_challenge(&nonces.offset_keys[j], e) {
+                return Err(MultisigError::InvalidPartialSignature);
+            }
+        }
+
+        self.partial_signatures.push(partial_signatures);
+        if self.partial_signatures.len() < self.proposal.signers.len() {
+            return Ok(None);
+        }
+        self.build_transaction(wallet, &request, &script_challenges, &metadata_challenges, factories)
+            .map(Some)
+    }
+
+    fn build_transaction(
+        &self,
+        wallet: &MultisigWallet,
+        request: &MultisigSigningRequest,
+        script_challenges: &[MessageHash],
+        metadata_challenges: &[MessageHash],
+        factories: &CryptoFactories,
+    ) -> Result<Transaction, MultisigError> {
+        let input_data = wallet.input_data(&self.proposal.signers)?;
+        let mut builder = TransactionBuilder::new();
+
+        let mut input_keys = PrivateKey::default();
+        for (l, input) in self.proposal.inputs.iter().enumerate() {
+            let (nonce_a, nonce_b) = &self.script_nonces[l];
+            let spending_key = &self.input_spending_keys[l];
+            let signature = ComSignature::sign(
+                PrivateKey::from(self.input_values[l].as_u64()),
+                spending_key.clone(),
+                nonce_a.clone(),
+                nonce_b.clone(),
+                &script_challenges[l],
+                &factories.commitment,
+            )?;
+            let u = self
+                .partial_signatures
+                .iter()
+                .fold(signature.u().clone(), |acc, p| &acc + &p.script_signatures[l]);
+            builder.add_input(TransactionInput::new(
+                input.features.clone(),
+                input.commitment.clone(),
+                input.script.clone(),
+                input_data.clone(),
+                ComSignature::new(request.script_nonces[l].clone(), u, signature.v().clone()),
+                input.sender_offset_public_key.clone(),
+            ));
+            input_keys = input_keys + spending_key.clone();
+        }
+
+        let mut output_keys = PrivateKey::default();
+        let mut coordinator_offset_keys = PrivateKey::default();
+        for (j, output) in request.outputs.iter().enumerate() {
+            let (nonce_a, nonce_b) = &self.metadata_nonces[j];
+            let spending_key = &request.spending_keys[j];
+            let signature = ComSignature::sign(
+                PrivateKey::from(self.proposal.recipients[j].value.as_u64()),
+                spending_key + &self.sender_offset_keys[j],
+                nonce_a.clone(),
+                nonce_b.clone(),
+                &metadata_challenges[j],
+                &factories.commitment,
+            )?;
+            let u = self
+                .partial_signatures
+                .iter()
+                .fold(signature.u().clone(), |acc, p| &acc + &p.metadata_signatures[j]);
+            let mut output = output.clone();
+            output.metadata_signature = ComSignature::new(request.metadata_nonces[j].clone(), u, signature.v().clone());
+            output.verify_metadata_signature()?;
+            builder.add_output(output);
+            output_keys = output_keys + spending_key.clone();
+            coordinator_offset_keys = coordinator_offset_keys + self.sender_offset_keys[j].clone();
+        }
+
+        let script_offset = self
+            .partial_signatures
+            .iter()
+            .fold(PrivateKey::default(), |acc, p| &acc + &p.script_offset) -
+            coordinator_offset_keys;
+
+        let offset = PrivateKey::random(&mut OsRng);
+        let excess = output_keys - input_keys - offset.clone();
+        let nonce = PrivateKey::random(&mut OsRng);
+        let metadata = TransactionMetadata {
+            fee: self.proposal.fee,
+            lock_height: 0,
+        };
+        let challenge = build_challenge(&PublicKey::from_secret_key(&nonce), &metadata);
+        let excess_sig = Signature::sign(excess.clone(), nonce, &challenge)?;
+        let kernel = KernelBuilder::new()
+            .with_fee(metadata.fee)
+            .with_lock_height(metadata.lock_height)
+            .with_excess(&Commitment::from_public_key(&PublicKey::from_secret_key(&excess)))
+            .with_signature(&excess_sig)
+            .build()?;
+
+        builder.add_offset(offset);
+        builder.add_script_offset(script_offset);
+        builder.with_kernel(kernel);
+        Ok(builder.build(factories)?)
+    }
+}
+
+/// The packages that are exchanged between the participants of a multisig wallet
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum MultisigPackage {
+    Participant(MultisigParticipant),
+    Wallet(MultisigWallet),
+    Update(MultisigUpdate),
+    SpendProposal(MultisigSpendProposal),
+    NonceCommitment(MultisigNonceCommitment),
+    NonceRequest(MultisigNonceRequest),
+    Nonces(MultisigNonces),
+    SigningRequest(MultisigSigningRequest),
+    PartialSignatures(MultisigPartialSignatures),
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedPackage {
+    version: u32,
+    package: MultisigPackage,
+}
+
+impl MultisigPackage {
+    pub fn to_json(&self) -> Result<String, MultisigError> {
+        Ok(serde_json::to_string_pretty(&VersionedPackage {
+            version: MULTISIG_PACKAGE_VERSION,
+            package: self.clone(),
+        })?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, MultisigError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or(MultisigError::MissingVersion)?;
+        if version != u64::from(MULTISIG_PACKAGE_VERSION) {
+            return Err(MultisigError::UnsupportedVersion(version));
+        }
+        let versioned: VersionedPackage = serde_json::from_value(value)?;
+        Ok(versioned.package)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), MultisigError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, MultisigError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+/// The key state of a multisig wallet held by one of its participants
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct MultisigWalletRecord {
+    pub wallet: MultisigWallet,
+    pub share_public_key: PublicKey,
+    pub share_key_index: u64,
+    pub outputs: Vec<MultisigOutput>,
+}
+
+impl MultisigWalletRecord {
+    /// Apply an update to the outputs of this wallet. Returns the number of outputs that were added.
+    pub fn apply_update(
+        &mut self,
+        update: &MultisigUpdate,
+        factories: &CryptoFactories,
+    ) -> Result<usize, MultisigError> {
+        for output in self.outputs.iter_mut() {
+            if update.spent.contains(&output.output.commitment) {
+                output.status = MultisigOutputStatus::Spent;
+            }
+        }
+        let mut added = 0;
+        for output in &update.outputs {
+            if self.outputs.iter().any(|o| o.output.commitment == output.commitment) {
+                continue;
+            }
+            match self.wallet.rewind_output(output, factories) {
+                Ok(multisig_output) => {
+                    self.outputs.push(multisig_output);
+                    added += 1;
+                },
+                Err(MultisigError::NotAMultisigOutput) => {},
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(added)
+    }
+
+    /// The total value of the unspent outputs of this wallet
+    pub fn balance(&self) -> MicroTari {
+        self.outputs
+            .iter()
+            .filter(|o| o.status == MultisigOutputStatus::Unspent)
+            .fold(MicroTari::from(0), |acc, o| acc + o.value)
+    }
+}
+
+pub(crate) fn share_key(public_key: &PublicKey) -> String {
+    format!("{}{}", MULTISIG_SHARE_KEY_PREFIX, public_key.to_hex())
+}
+
+pub(crate) fn wallet_key(wallet_id: MultisigWalletId) -> String {
+    format!("{}{}", MULTISIG_WALLET_KEY_PREFIX, wallet_id)
+}
+
+pub(crate) fn signer_key(wallet_id: MultisigWalletId) -> String {
+    format!("{}{}", MULTISIG_SIGNER_KEY_PREFIX, wallet_id)
+}
+
+pub(crate) fn coordinator_key(session_id: TxId) -> String {
+    format!("{}{}", MULTISIG_COORDINATOR_KEY_PREFIX, session_id)
+}
+
+/// Check a proposal before committing to nonces for it. Every recipient must either be a one-sided payment or change
+/// returned to the multisig wallet, and the inputs must be outputs of the wallet that balance the transaction.
+fn validate_proposal(
+    wallet: &MultisigWallet,
+    share_public_key: &PublicKey,
+    proposal: &MultisigSpendProposal,
+    factories: &CryptoFactories,
+) -> Result<(), MultisigError> {
+    if proposal.wallet_id != wallet.id {
+        return Err(MultisigError::InvalidProposal(
+            "Proposal is for another wallet".to_string(),
+        ));
+    }
+    if wallet.sorted_signers(&proposal.signers)? != proposal.signers || !proposal.signers.contains(share_public_key) {
+        return Err(MultisigError::InvalidSigners);
+    }
+    if proposal.inputs.is_empty() || proposal.recipients.is_empty() {
+        return Err(MultisigError::InvalidProposal(
+            "Proposal has no inputs or outputs".to_string(),
+        ));
+    }
+    if proposal.fee < MINIMUM_TRANSACTION_FEE {
+        return Err(MultisigError::InvalidProposal(
+            "Fee is less than the minimum".to_string(),
+        ));
+    }
+
+    let mut total_in = MicroTari::from(0);
+    for input in &proposal.inputs {
+        total_in += wallet.rewind_output(input, factories)?.value;
+    }
+    let multisig_script = wallet.script()?;
+    let mut total_out = proposal.fee;
+    for recipient in &proposal.recipients {
+        let valid_recipient = if recipient.script == multisig_script {
+            recipient.public_key == wallet.view_public_key()
+        } else {
+            recipient.script == script!(PushPubKey(Box::new(recipient.public_key.clone())))
+        };
+        if !valid_recipient {
+            return Err(MultisigError::InvalidProposal(
+                "Unsupported recipient script".to_string(),
+            ));
+        }
+        total_out += recipient.value;
+    }
+    if total_in != total_out {
+        return Err(MultisigError::InvalidProposal(
+            "Inputs do not balance the outputs and fee".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The MuSig key coefficient of `public_key` in the set of `signers`, which protects against rogue key attacks
+fn key_coefficient(signers: &[PublicKey], public_key: &PublicKey) -> Result<PrivateKey, MultisigError> {
+    let hash = signers
+        .iter()
+        .fold(HashDigest::new(), |hasher, k| hasher.chain(k.as_bytes()))
+        .chain(public_key.as_bytes())
+        .finalize()
+        .to_vec();
+    Ok(PrivateKey::from_bytes(&hash)?)
+}
+
+/// The aggregate public key of a sorted set of signers
+fn aggregate_public_key(signers: &[PublicKey]) -> Result<PublicKey, MultisigError> {
+    signers.iter().try_fold(PublicKey::default(), |acc, k| {
+        Ok(&acc + &PublicKey::shared_secret(&key_coefficient(signers, k)?, k))
+    })
+}
+
+/// The hash that commits the coordinator to its public script and metadata nonces
+fn coordinator_nonce_commitment(
+    session_id: TxId,
+    script_nonces: &[Commitment],
+    metadata_nonces: &[Commitment],
+) -> Vec<u8> {
+    let hasher = HashDigest::new().chain(session_id.to_le_bytes());
+    [script_nonces, metadata_nonces]
+        .iter()
+        .fold(hasher, |hasher, nonces| {
+            nonces
+                .iter()
+                .fold(hasher.chain((nonces.len() as u64).to_le_bytes()), |hasher, n| {
+                    hasher.chain(n.as_bytes())
+                })
+        })
+        .finalize()
+        .to_vec()
+}
+
+fn public_nonces(nonces: &[(PrivateKey, PrivateKey)], factories: &CryptoFactories) -> Vec<Commitment> {
+    nonces
+        .iter()
+        .map(|(nonce_a, nonce_b)| factories.commitment.commit(nonce_b, nonce_a))
+        .collect()
+}
+
+/// Add the nonces selected from every signer to the corresponding nonces of the coordinator
+fn aggregate_nonces<F>(
+    coordinator_nonces: &[Commitment],
+    nonces: &[MultisigNonces],
+    signer_nonces: F,
+) -> Vec<Commitment>
+where
+    F: Fn(&MultisigNonces) -> &Vec<PublicKey>,
+{
+    coordinator_nonces
+        .iter()
+        .enumerate()
+        .map(|(i, nonce)| nonces.iter().fold(nonce.clone(), |acc, n| &acc + &signer_nonces(n)[i]))
+        .collect()
+}
+
+fn partial_signature(secret: &PrivateKey, nonce: &PrivateKey, challenge: &[u8]) -> Result<PrivateKey, MultisigError> {
+    Ok(Signature::sign(secret.clone(), nonce.clone(), challenge)?
+        .get_signature()
+        .clone())
+}
+
+fn rewind_data(spending_key: &PrivateKey) -> Result<RewindData, MultisigError> {
+    let rewind_key = PrivateKey::from_bytes(&hash_secret_key(spending_key))?;
+    let rewind_blinding_key = PrivateKey::from_bytes(&hash_secret_key(&rewind_key))?;
+    Ok(RewindData {
+        rewind_key,
+        rewind_blinding_key,
+        proof_message: [0u8; REWIND_USER_MESSAGE_LENGTH],
+    })
+}
+
+fn hash_secret_key(key: &PrivateKey) -> Vec<u8> {
+    HashDigest::new().chain(key.as_bytes()).finalize().to_vec()
+}
+
+fn random_keys(n: usize) -> Vec<PrivateKey> {
+    (0..n).map(|_| PrivateKey::random(&mut OsRng)).collect()
+}
+
+fn num_combinations(n: usize, k: usize) -> usize {
+    (0..k).fold(1usize, |acc, i| acc.saturating_mul(n - i) / (i + 1))
+}
+
+/// All the sorted `k` element subsets of the indices `0..n`
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut result = Vec::new();
+    let mut current = Vec::with_capacity(k);
+    fn recurse(start: usize, n: usize, k: usize, current: &mut Vec<usize>, result: &mut Vec<Vec<usize>>) {
+        if current.len() == k {
+            result.push(current.clone());
+            return;
+        }
+        for i in start..n {
+            current.push(i);
+            recurse(i + 1, n, k, current, result);
+            current.pop();
+        }
+    }
+    recurse(0, n, k, &mut current, &mut result);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transaction_service::{
+        error::MultisigError,
+        multisig::{
+            rewind_data,
+            MultisigCoordinatorSession,
+            MultisigNonceRequest,
+            MultisigOutput,
+            MultisigParticipant,
+            MultisigSignerSession,
+            MultisigUpdate,
+            MultisigWallet,
+            MultisigWalletRecord,
+        },
+    };
+    use rand::rngs::OsRng;
+    use tari_common_types::types::{PrivateKey, PublicKey};
+    use tari_core::transactions::{
+        tari_amount::{uT, MicroTari},
+        transaction::{OutputFeatures, TransactionOutput, UnblindedOutput},
+        CryptoFactories,
+    };
+    use tari_crypto::{
+        keys::{PublicKey as PublicKeyTrait, SecretKey},
+        script::ExecutionStack,
+    };
+
+    fn create_participants(n: usize) -> (Vec<PrivateKey>, Vec<MultisigParticipant>) {
+        let keys = (0..n).map(|_| PrivateKey::random(&mut OsRng)).collect::<Vec<_>>();
+        let participants = keys
+            .iter()
+            .map(|k| MultisigParticipant {
+                address: PublicKey::random_keypair(&mut OsRng).1,
+                public_key: PublicKey::from_secret_key(k),
+            })
+            .collect();
+        (keys, participants)
+    }
+
+    fn create_multisig_output(
+        wallet: &MultisigWallet,
+        value: MicroTari,
+        factories: &CryptoFactories,
+    ) -> TransactionOutput {
+        let (sender_offset_private_key, sender_offset_public_key) = PublicKey::random_keypair(&mut OsRng);
+        let spending_key = wallet.spending_key(&sender_offset_public_key).unwrap();
+        let script = wallet.script().unwrap();
+        let metadata_signature = TransactionOutput::create_final_metadata_signature(
+            &value,
+            &spending_key,
+            &script,
+            &OutputFeatures::default(),
+            &sender_offset_private_key,
+        )
+        .unwrap();
+        UnblindedOutput::new(
+            value,
+            spending_key.clone(),
+            OutputFeatures::default(),
+            script,
+            ExecutionStack::new(vec![]),
+            PrivateKey::random(&mut OsRng),
+            sender_offset_public_key,
+            metadata_signature,
+        )
+        .as_rewindable_transaction_output(factories, &rewind_data(&spending_key).unwrap())
+        .unwrap()
+    }
+
+    #[test]
+    fn test_invalid_multisig_wallets() {
+        let (_, participants) = create_participants(3);
+        assert!(matches!(
+            MultisigWallet::new(4, participants.clone()),
+            Err(MultisigError::InvalidThreshold { .. })
+        ));
+        assert!(matches!(
+            MultisigWallet::new(0, participants.clone()),
+            Err(MultisigError::InvalidThreshold { .. })
+        ));
+        let duplicated = vec![participants[0].clone(), participants[0].clone()];
+        assert!(matches!(
+            MultisigWallet::new(1, duplicated),
+            Err(MultisigError::InvalidParticipants)
+        ));
+        let (_, participants) = create_participants(10);
+        assert!(matches!(
+            MultisigWallet::new(5, participants),
+            Err(MultisigError::TooManySignerSets(252))
+        ));
+    }
+
+    #[test]
+    fn test_multisig_nonce_commitments() {
+        let factories = CryptoFactories::default();
+        let (_, participants) = create_participants(2);
+        let wallet = MultisigWallet::new(2, participants.clone()).unwrap();
+        let outputs = vec![wallet
+            .rewind_output(
+                &create_multisig_output(&wallet, MicroTari::from(100_000), &factories),
+                &factories,
+            )
+            .unwrap()];
+        let signer_keys = participants.iter().map(|p| p.public_key.clone()).collect::<Vec<_>>();
+        let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+        let (mut coordinator, proposal) = MultisigCoordinatorSession::new(
+            &wallet,
+            &outputs,
+            &signer_keys,
+            destination,
+            MicroTari::from(40_000),
+            25 * uT,
+            "Escrow release".to_string(),
+            &factories,
+        )
+        .unwrap();
+
+        let (mut first, first_commitment) =
+            MultisigSignerSession::new(&wallet, signer_keys[0].clone(), proposal.clone(), &factories).unwrap();
+        let (_, second_commitment) =
+            MultisigSignerSession::new(&wallet, signer_keys[1].clone(), proposal, &factories).unwrap();
+
+        // Nonces can not be added before every signer has committed
+        let nonce_request = MultisigNonceRequest {
+            session_id: first_commitment.session_id,
+            wallet_id: wallet.id,
+            commitments: vec![first_commitment.clone()],
+        };
+        assert!(matches!(
+            first.reveal_nonces(&nonce_request),
+            Err(MultisigError::InvalidNonceCommitments)
+        ));
+        assert!(coordinator
+            .add_nonce_commitment(first_commitment.clone())
+            .unwrap()
+            .is_none());
+        let mut replaced = first_commitment.clone();
+        replaced.commitment = vec![0u8; 32];
+        assert!(matches!(
+            coordinator.add_nonce_commitment(replaced),
+            Err(MultisigError::InvalidNonceCommitments)
+        ));
+        let nonce_request = coordinator.add_nonce_commitment(second_commitment).unwrap().unwrap();
+        let nonces = first.reveal_nonces(&nonce_request).unwrap();
+
+        // Once revealed the nonces can not be bound to other commitments
+        let mut other_request = nonce_request.clone();
+        other_request.commitments[0].commitment = vec![0u8; 32];
+        assert!(matches!(
+            first.reveal_nonces(&other_request),
+            Err(MultisigError::InvalidNonceCommitments)
+        ));
+        assert_eq!(
+            first.reveal_nonces(&nonce_request).unwrap().commitment(),
+            nonces.commitment()
+        );
+    }
+
+    #[test]
+    fn test_multisig_spend() {
+        let factories = CryptoFactories::default();
+        let (keys, participants) = create_participants(3);
+        let wallet = MultisigWallet::new(2, participants.clone()).unwrap();
+        assert_eq!(wallet.signer_sets().len(), 3);
+
+        let mut record = MultisigWalletRecord {
+            wallet: wallet.clone(),
+            share_public_key: participants[0].public_key.clone(),
+            share_key_index: 0,
+            outputs: Vec::new(),
+        };
+        let funding = MultisigUpdate {
+            wallet_id: wallet.id,
+            tx_id: 1,
+            spent: Vec::new(),
+            outputs: vec![create_multisig_output(&wallet, MicroTari::from(100_000), &factories)],
+        };
+        assert_eq!(record.apply_update(&funding, &factories).unwrap(), 1);
+        assert_eq!(record.apply_update(&funding, &factories).unwrap(), 0);
+        assert_eq!(record.balance(), MicroTari::from(100_000));
+
+        // Only the first and last participants sign
+        let signers = vec![(&keys[0], &participants[0]), (&keys[2], &participants[2])];
+        let signer_keys = signers.iter().map(|(_, p)| p.public_key.clone()).collect::<Vec<_>>();
+        let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+        let (mut coordinator, proposal) = MultisigCoordinatorSession::new(
+            &wallet,
+            &record.outputs,
+            &signer_keys,
+            destination,
+            MicroTari::from(40_000),
+            25 * uT,
+            "Escrow release".to_string(),
+            &factories,
+        )
+        .unwrap();
+        assert_eq!(proposal.recipients.len(), 2);
+
+        // A participant that was not chosen as signer can not commit to the proposal
+        assert!(matches!(
+            MultisigSignerSession::new(
+                &wallet,
+                participants[1].public_key.clone(),
+                proposal.clone(),
+                &factories
+            ),
+            Err(MultisigError::InvalidSigners)
+        ));
+
+        let mut sessions = Vec::new();
+        let mut nonce_request = None;
+        for (_, participant) in &signers {
+            let (session, commitment) =
+                MultisigSignerSession::new(&wallet, participant.public_key.clone(), proposal.clone(), &factories)
+                    .unwrap();
+            sessions.push(session);
+            nonce_request = coordinator.add_nonce_commitment(commitment).unwrap();
+        }
+        let nonce_request = nonce_request.unwrap();
+        assert_eq!(coordinator.pending_signers(), 2);
+
+        let mut request = None;
+        for session in sessions.iter_mut() {
+            let nonces = session.reveal_nonces(&nonce_request).unwrap();
+            // Nonces that do not match the signer's commitment are rejected
+            let mut tampered = nonces.clone();
+            tampered.script_nonces[0] = PublicKey::random_keypair(&mut OsRng).1;
+            assert!(matches!(
+                coordinator.add_nonces(tampered, &factories),
+                Err(MultisigError::InvalidNonces)
+            ));
+            request = coordinator.add_nonces(nonces, &factories).unwrap();
+        }
+        let request = request.unwrap();
+
+        // A signer refuses a request whose aggregate nonces are not made up of the committed nonces
+        let mut tampered = request.clone();
+        tampered.script_nonces[0] = tampered.metadata_nonces[0].clone();
+        assert!(matches!(
+            sessions[0].clone().sign(&wallet, signers[0].0, &tampered, &factories),
+            Err(MultisigError::InvalidSigningRequest(_))
+        ));
+
+        let mut transaction = None;
+        for (session, (key, _)) in sessions.into_iter().zip(signers.iter()) {
+            let partial_signatures = session.sign(&wallet, key, &request, &factories).unwrap();
+            transaction = coordinator
+                .add_partial_signatures(&wallet, partial_signatures, &factories)
+                .unwrap();
+        }
+        let transaction = transaction.unwrap();
+        assert_eq!(transaction.body.inputs().len(), 1);
+        assert_eq!(transaction.body.outputs().len(), 2);
+
+        let update = MultisigUpdate {
+            wallet_id: wallet.id,
+            tx_id: proposal.session_id,
+            spent: proposal.inputs.iter().map(|i| i.commitment.clone()).collect(),
+            outputs: transaction.body.outputs().clone(),
+        };
+        assert_eq!(record.apply_update(&update, &factories).unwrap(), 1);
+        let change = record
+            .outputs
+            .iter()
+            .filter(|o| o.output.commitment != funding.outputs[0].commitment)
+            .collect::<Vec<&MultisigOutput>>();
+        assert_eq!(change.len(), 1);
+        assert_eq!(record.balance(), change[0].value);
+        assert_eq!(
+            change[0].value + MicroTari::from(40_000) + proposal.fee,
+            MicroTari::from(100_000)
+        );
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Types used to sign a transaction on an air-gapped machine.
+//!
+//! The workflow has the following steps:
+//! 1. The online wallet selects and encumbers inputs and writes an [UnsignedTransaction] package. Inputs are described
+//!    by their key indices and public data only.
+//! 2. The offline signer, which holds the seed, derives the keys and builds the transaction. A one-sided payment is
+//!    finalized immediately into a [SignedTransaction]. An interactive payment produces an [OfflineSenderMessage] and
+//!    the signer keeps the sender protocol state until the recipient replies.
+//! 3. For interactive payments the online wallet relays the sender message to the recipient and exports the
+//!    [OfflineRecipientReply] once it arrives. The offline signer uses it to finalize into a [SignedTransaction].
+//! 4. The online wallet imports the [SignedTransaction], tracks the change output and broadcasts the transaction.
+
+use crate::{output_manager_service::TxId, transaction_service::error::OfflineSigningError};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Error, Formatter},
+    fs,
+    path::Path,
+};
+use tari_common_types::types::{ComSignature, PublicKey};
+use tari_comms::types::CommsPublicKey;
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::{OutputFeatures, Transaction},
+    transaction_protocol::{recipient::RecipientSignedMessage, sender::SingleRoundSenderData},
+    SenderTransactionProtocol,
+};
+use tari_crypto::script::{ExecutionStack, TariScript};
+
+/// The version of the offline package file format. This must be incremented when the format changes.
+pub const OFFLINE_PACKAGE_VERSION: u32 = 1;
+
+/// Client key value prefix for the sender protocol state held by the offline signer
+pub(crate) const OFFLINE_SIGNING_STATE_KEY_PREFIX: &str = "offline_signing_state_";
+/// Client key value prefix for the state of an interactive offline transaction held by the online wallet
+pub(crate) const OFFLINE_RELAY_KEY_PREFIX: &str = "offline_relay_";
+
+/// An input to be spent by an offline transaction. The private keys are not included, the signer derives them from
+/// `key_index`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfflineInput {
+    pub key_index: u64,
+    pub value: MicroTari,
+    pub features: OutputFeatures,
+    pub script: TariScript,
+    pub input_data: ExecutionStack,
+    pub sender_offset_public_key: PublicKey,
+    pub metadata_signature: ComSignature,
+}
+
+/// A transaction prepared by the online wallet that still needs to be signed by the offline signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    pub tx_id: TxId,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee_per_gram: MicroTari,
+    pub lock_height: Option<u64>,
+    pub message: String,
+    pub one_sided: bool,
+    pub inputs: Vec<OfflineInput>,
+    pub change_key_index: Option<u64>,
+}
+
+/// The sender half of an interactive offline transaction that the online wallet must relay to the recipient
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfflineSenderMessage {
+    pub tx_id: TxId,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub message: String,
+    pub change_key_index: Option<u64>,
+    pub sender_message: SingleRoundSenderData,
+}
+
+/// The recipient's reply to an interactive offline transaction, exported by the online wallet for the signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfflineRecipientReply {
+    pub tx_id: TxId,
+    pub reply: RecipientSignedMessage,
+}
+
+/// A fully signed transaction that is ready to be imported into the online wallet and broadcast
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    pub tx_id: TxId,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+    pub message: String,
+    pub one_sided: bool,
+    pub change_key_index: Option<u64>,
+    pub transaction: Transaction,
+}
+
+/// What an [UnsignedTransaction] pays, as it would be signed. The package is prepared by the online wallet, so the
+/// offline signer shows this to be checked before anything is signed.
+#[derive(Clone, Debug)]
+pub struct OfflineTransactionSummary {
+    pub tx_id: TxId,
+    pub destination_public_key: CommsPublicKey,
+    pub amount: MicroTari,
+    pub fee: MicroTari,
+    pub change: MicroTari,
+    pub total_inputs: MicroTari,
+    pub lock_height: Option<u64>,
+    pub one_sided: bool,
+    pub message: String,
+}
+
+impl Display for OfflineTransactionSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        writeln!(f, "Transaction:  {}", self.tx_id)?;
+        writeln!(f, "Recipient:    {}", self.destination_public_key)?;
+        writeln!(f, "Amount:       {}", self.amount)?;
+        writeln!(f, "Fee:          {}", self.fee)?;
+        writeln!(f, "Change:       {}", self.change)?;
+        writeln!(f, "Inputs:       {}", self.total_inputs)?;
+        writeln!(
+            f,
+            "Type:         {}",
+            if self.one_sided { "one-sided" } else { "negotiated" }
+        )?;
+        if let Some(height) = self.lock_height {
+            writeln!(f, "Lock height:  {}", height)?;
+        }
+        write!(f, "Message:      {}", self.message)
+    }
+}
+
+/// The result of signing an [UnsignedTransaction]
+#[derive(Clone, Debug)]
+pub enum OfflineSigningOutcome {
+    /// A one-sided transaction was signed and finalized
+    Signed(Box<SignedTransaction>),
+    /// An interactive transaction is waiting on the reply of the recipient
+    AwaitingReply(Box<OfflineSenderMessage>),
+}
+
+/// State kept by the offline signer while an interactive offline transaction is waiting on the recipient. This holds
+/// the private nonce and offset of the transaction so it must never leave the signer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct OfflineSigningState {
+    pub sender_message: OfflineSenderMessage,
+    pub protocol: SenderTransactionProtocol,
+}
+
+/// State kept by the online wallet while an interactive offline transaction is waiting on the recipient
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct OfflineRelayRecord {
+    pub destination_public_key: CommsPublicKey,
+    pub reply: Option<RecipientSignedMessage>,
+}
+
+/// The packages that can be exchanged between the online wallet and the offline signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum OfflinePackage {
+    Unsigned(UnsignedTransaction),
+    SenderMessage(OfflineSenderMessage),
+    RecipientReply(OfflineRecipientReply),
+    Signed(SignedTransaction),
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedPackage {
+    version: u32,
+    package: OfflinePackage,
+}
+
+impl OfflinePackage {
+    pub fn tx_id(&self) -> TxId {
+        match self {
+            OfflinePackage::Unsigned(p) => p.tx_id,
+            OfflinePackage::SenderMessage(p) => p.tx_id,
+            OfflinePackage::RecipientReply(p) => p.tx_id,
+            OfflinePackage::Signed(p) => p.tx_id,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, OfflineSigningError> {
+        Ok(serde_json::to_string_pretty(&VersionedPackage {
+            version: OFFLINE_PACKAGE_VERSION,
+            package: self.clone(),
+        })?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, OfflineSigningError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or(OfflineSigningError::MissingVersion)?;
+        if version != u64::from(OFFLINE_PACKAGE_VERSION) {
+            return Err(OfflineSigningError::UnsupportedVersion(version));
+        }
+        let versioned: VersionedPackage = serde_json::from_value(value)?;
+        Ok(versioned.package)
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), OfflineSigningError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self, OfflineSigningError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl From<OfflineSigningOutcome> for OfflinePackage {
+    fn from(outcome: OfflineSigningOutcome) -> Self {
+        match outcome {
+            OfflineSigningOutcome::Signed(p) => OfflinePackage::Signed(*p),
+            OfflineSigningOutcome::AwaitingReply(p) => OfflinePackage::SenderMessage(*p),
+        }
+    }
+}
+
+pub(crate) fn signing_state_key(tx_id: TxId) -> String {
+    format!("{}{}", OFFLINE_SIGNING_STATE_KEY_PREFIX, tx_id)
+}
+
+pub(crate) fn relay_key(tx_id: TxId) -> String {
+    format!("{}{}", OFFLINE_RELAY_KEY_PREFIX, tx_id)
+}
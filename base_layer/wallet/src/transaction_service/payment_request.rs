@@ -0,0 +1,307 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+//! Payment requests let a recipient ask for a payment without waiting for the sender to reach out first.
+//!
+//! A [PaymentRequest] names the recipient, the amount, a message and the block height at which it expires. It has a
+//! random reference and is signed by the recipient's node identity, so a sender can check that it was not tampered
+//! with. It is shared as a compact hex string or a QR-friendly `tari://` URI.
+//!
+//! The sender includes the reference in the message of the transaction paying the request, see
+//! [PaymentRequest::payment_message]. The recipient's wallet matches incoming transactions to its open requests using
+//! the reference once they are mined, and takes the payment off the request again if the transaction is reorged out or
+//! cancelled. Imported outputs, such as one-sided payments that do not carry the sender's message, are matched by the
+//! outstanding amount instead.
+
+use crate::{
+    output_manager_service::TxId,
+    transaction_service::{
+        error::{PaymentRequestError, TransactionStorageError},
+        storage::{
+            database::{TransactionBackend, TransactionDatabase},
+            models::PaymentRequestRecord,
+        },
+    },
+};
+use digest::Digest;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use tari_common_types::types::Signature;
+use tari_comms::{
+    peer_manager::NodeIdentity,
+    types::{Challenge, CommsPublicKey},
+    utils::signature::sign_challenge,
+};
+use tari_core::transactions::tari_amount::MicroTari;
+use tari_crypto::tari_utilities::{hex::Hex, ByteArray};
+
+/// The version of the payment request encoding. This must be incremented when the format changes.
+pub const PAYMENT_REQUEST_VERSION: u8 = 1;
+
+/// The tag that precedes the reference of a payment request in the message of a transaction paying it
+pub const PAYMENT_REFERENCE_TAG: &str = "pr:";
+
+const PAYMENT_REQUEST_URI_PATH: &str = "payment_request";
+const PAYMENT_REQUEST_DOMAIN: &[u8] = b"tari_wallet_payment_request";
+
+/// The unique reference of a payment request
+pub type PaymentReference = u64;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PaymentRequest {
+    pub reference: PaymentReference,
+    pub recipient: CommsPublicKey,
+    pub amount: MicroTari,
+    pub message: String,
+    /// The request can no longer be paid once the chain tip reaches this height
+    pub expiry_height: u64,
+    /// The signature of the recipient's node identity over the other fields
+    pub signature: Signature,
+}
+
+impl PaymentRequest {
+    /// Create a payment request to be paid to the node identity, signed with its secret key
+    pub fn new(
+        node_identity: &NodeIdentity,
+        amount: MicroTari,
+        message: String,
+        expiry_height: u64,
+    ) -> Result<Self, PaymentRequestError> {
+        let reference = OsRng.next_u64();
+        let recipient = node_identity.public_key().clone();
+        let challenge = Self::challenge(reference, &recipient, amount, &message, expiry_height);
+        let signature = sign_challenge(&mut OsRng, node_identity.secret_key().clone(), challenge)?;
+        Ok(Self {
+            reference,
+            recipient,
+            amount,
+            message,
+            expiry_height,
+            signature,
+        })
+    }
+
+    /// Returns true if the request was signed by its recipient
+    pub fn is_valid(&self) -> bool {
+        let challenge = Self::challenge(
+            self.reference,
+            &self.recipient,
+            self.amount,
+            &self.message,
+            self.expiry_height,
+        );
+        self.signature.verify_challenge(&self.recipient, &challenge.finalize())
+    }
+
+    pub fn is_expired(&self, tip_height: u64) -> bool {
+        tip_height >= self.expiry_height
+    }
+
+    /// The reference formatted the way it is shown to users and included in payment messages
+    pub fn reference_hex(&self) -> String {
+        format_payment_reference(self.reference)
+    }
+
+    /// The message a sender should use for the transaction paying this request
+    pub fn payment_message(&self) -> String {
+        if self.message.is_empty() {
+            format!("[{}{}]", PAYMENT_REFERENCE_TAG, self.reference_hex())
+        } else {
+            format!("{} [{}{}]", self.message, PAYMENT_REFERENCE_TAG, self.reference_hex())
+        }
+    }
+
+    /// Encode the request as a hex string
+    pub fn to_compact_string(&self) -> Result<String, PaymentRequestError> {
+        let mut bytes = vec![PAYMENT_REQUEST_VERSION];
+        bytes.extend(bincode::serialize(self)?);
+        Ok(bytes.to_hex())
+    }
+
+    /// Decode a request encoded with [PaymentRequest::to_compact_string]. The signature of the request is checked.
+    pub fn from_compact_string(s: &str) -> Result<Self, PaymentRequestError> {
+        let bytes = Vec::<u8>::from_hex(s.trim()).map_err(|e| PaymentRequestError::InvalidEncoding(e.to_string()))?;
+        match bytes.first() {
+            Some(&PAYMENT_REQUEST_VERSION) => {},
+            Some(v) => return Err(PaymentRequestError::UnsupportedVersion(*v)),
+            None => {
+                return Err(PaymentRequestError::InvalidEncoding(
+                    "Empty payment request".to_string(),
+                ))
+            },
+        }
+        let request: PaymentRequest = bincode::deserialize(&bytes[1..])?;
+        if !request.is_valid() {
+            return Err(PaymentRequestError::InvalidSignature);
+        }
+        Ok(request)
+    }
+
+    /// Encode the request as a URI for the given network, e.g. to be shown as a QR code
+    pub fn to_uri(&self, network: &str) -> Result<String, PaymentRequestError> {
+        Ok(format!(
+            "tari://{}/{}/{}",
+            network,
+            PAYMENT_REQUEST_URI_PATH,
+            self.to_compact_string()?
+        ))
+    }
+
+    /// Decode a request URI created with [PaymentRequest::to_uri]. Requests for another network are rejected.
+    pub fn from_uri(uri: &str, network: &str) -> Result<Self, PaymentRequestError> {
+        let rest = uri
+            .trim()
+            .strip_prefix("tari://")
+            .ok_or_else(|| PaymentRequestError::InvalidEncoding("Not a tari:// URI".to_string()))?;
+        let parts = rest.split('/').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [uri_network, PAYMENT_REQUEST_URI_PATH, request] => {
+                if !uri_network.eq_ignore_ascii_case(network) {
+                    return Err(PaymentRequestError::WrongNetwork(uri_network.to_string()));
+                }
+                Self::from_compact_string(request)
+            },
+            _ => Err(PaymentRequestError::InvalidEncoding(
+                "Not a payment request URI".to_string(),
+            )),
+        }
+    }
+
+    fn challenge(
+        reference: PaymentReference,
+        recipient: &CommsPublicKey,
+        amount: MicroTari,
+        message: &str,
+        expiry_height: u64,
+    ) -> Challenge {
+        Challenge::new()
+            .chain(PAYMENT_REQUEST_DOMAIN)
+            .chain(reference.to_le_bytes())
+            .chain(recipient.as_bytes())
+            .chain(u64::from(amount).to_le_bytes())
+            .chain(expiry_height.to_le_bytes())
+            .chain(message.as_bytes())
+    }
+}
+
+pub fn format_payment_reference(reference: PaymentReference) -> String {
+    format!("{:016x}", reference)
+}
+
+pub fn parse_payment_reference(s: &str) -> Result<PaymentReference, PaymentRequestError> {
+    PaymentReference::from_str_radix(s.trim(), 16).map_err(|e| PaymentRequestError::InvalidEncoding(e.to_string()))
+}
+
+/// Find the reference of a payment request in the message of a transaction paying it
+pub fn find_payment_reference(message: &str) -> Option<PaymentReference> {
+    message.match_indices(PAYMENT_REFERENCE_TAG).find_map(|(i, _)| {
+        let start = i + PAYMENT_REFERENCE_TAG.len();
+        message
+            .get(start..start + 16)
+            .and_then(|s| PaymentReference::from_str_radix(s, 16).ok())
+    })
+}
+
+/// Credit a payment to the open payment request it pays and return the updated request. The reference in the message of
+/// the transaction is used if there is one. Otherwise, if `match_by_amount` is set, the oldest open request with
+/// exactly the payment's amount outstanding is chosen. Requests that expired at or before `height` are not matched.
+pub(crate) async fn match_payment_request<TBackend: TransactionBackend + 'static>(
+    db: &TransactionDatabase<TBackend>,
+    tx_id: TxId,
+    amount: MicroTari,
+    message: &str,
+    height: Option<u64>,
+    match_by_amount: bool,
+) -> Result<Option<PaymentRequestRecord>, TransactionStorageError> {
+    let requests = db.get_payment_requests().await?;
+    if requests.iter().any(|r| r.payments.contains(&tx_id)) {
+        return Ok(None);
+    }
+    let mut open_requests = requests
+        .into_iter()
+        .filter(|r| r.is_open() && !height.map(|h| r.request.is_expired(h)).unwrap_or(false));
+    let request = match find_payment_reference(message) {
+        Some(reference) => open_requests.find(|r| r.request.reference == reference),
+        None if match_by_amount => open_requests.find(|r| r.amount_outstanding() == amount),
+        None => None,
+    };
+    match request {
+        Some(r) => Ok(Some(
+            db.add_payment_request_payment(r.request.reference, tx_id, amount)
+                .await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_comms::{peer_manager::PeerFeatures, test_utils::node_identity::build_node_identity};
+
+    fn create_request() -> PaymentRequest {
+        let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+        PaymentRequest::new(&node_identity, MicroTari::from(12_345), "Invoice 42".to_string(), 1000).unwrap()
+    }
+
+    #[test]
+    fn it_encodes_and_decodes_a_signed_request() {
+        let request = create_request();
+        assert!(request.is_valid());
+
+        let decoded = PaymentRequest::from_compact_string(&request.to_compact_string().unwrap()).unwrap();
+        assert_eq!(decoded, request);
+
+        let uri = request.to_uri("stibbons").unwrap();
+        assert!(uri.starts_with("tari://stibbons/payment_request/"));
+        assert_eq!(PaymentRequest::from_uri(&uri, "stibbons").unwrap(), request);
+        assert!(matches!(
+            PaymentRequest::from_uri(&uri, "weatherwax"),
+            Err(PaymentRequestError::WrongNetwork(_))
+        ));
+    }
+
+    #[test]
+    fn it_rejects_a_tampered_request() {
+        let mut request = create_request();
+        request.amount = MicroTari::from(1);
+        assert!(!request.is_valid());
+        assert!(matches!(
+            PaymentRequest::from_compact_string(&request.to_compact_string().unwrap()),
+            Err(PaymentRequestError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn it_finds_the_reference_in_a_payment_message() {
+        let request = create_request();
+        assert_eq!(
+            find_payment_reference(&request.payment_message()),
+            Some(request.reference)
+        );
+        assert_eq!(find_payment_reference("Thanks for the coffee"), None);
+        assert_eq!(find_payment_reference("pr:123"), None);
+        assert_eq!(
+            parse_payment_reference(&request.reference_hex()).unwrap(),
+            request.reference
+        );
+    }
+}
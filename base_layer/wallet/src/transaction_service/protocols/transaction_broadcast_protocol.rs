@@ -409,6 +409,21 @@ where
                 "Failed to Cancel TxId: {} after failed sending attempt with error {:?}", self.tx_id, e
             );
         }
+        match self.resources.db.remove_payment_request_payment(self.tx_id).await {
+            Ok(Some(record)) => {
+                let _ = self
+                    .resources
+                    .event_publisher
+                    .send(Arc::new(TransactionEvent::PaymentRequestUpdated(
+                        record.request.reference,
+                    )));
+            },
+            Ok(None) => {},
+            Err(e) => warn!(
+                target: LOG_TARGET,
+                "Failed to remove TxId: {} from its payment request with error {:?}", self.tx_id, e
+            ),
+        }
     }
 }
 
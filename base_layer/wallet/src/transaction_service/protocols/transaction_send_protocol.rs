@@ -22,6 +22,7 @@
 
 use crate::{
     connectivity_service::WalletConnectivityInterface,
+    output_manager_service::storage::models::AccountId,
     transaction_service::{
         config::TransactionRoutingMechanism,
         error::{TransactionServiceError, TransactionServiceProtocolError},
@@ -49,7 +50,7 @@ use tari_comms_dht::{
 };
 use tari_core::transactions::{
     tari_amount::MicroTari,
-    transaction::KernelFeatures,
+    transaction::{KernelFeatures, OutputFeatures},
     transaction_protocol::{proto, recipient::RecipientSignedMessage, sender::SingleRoundSenderData},
     SenderTransactionProtocol,
 };
@@ -75,6 +76,9 @@ pub struct TransactionSendProtocol<TBackend, TWalletConnectivity> {
     amount: MicroTari,
     fee_per_gram: MicroTari,
     message: String,
+    lock_height: Option<u64>,
+    recipient_output_features: OutputFeatures,
+    account_id: AccountId,
     service_request_reply_channel: Option<oneshot::Sender<Result<TransactionServiceResponse, TransactionServiceError>>>,
     stage: TransactionSendProtocolStage,
     resources: TransactionServiceResources<TBackend, TWalletConnectivity>,
@@ -97,6 +101,9 @@ where
         amount: MicroTari,
         fee_per_gram: MicroTari,
         message: String,
+        lock_height: Option<u64>,
+        recipient_output_features: OutputFeatures,
+        account_id: AccountId,
         service_request_reply_channel: Option<
             oneshot::Sender<Result<TransactionServiceResponse, TransactionServiceError>>,
         >,
@@ -111,6 +118,9 @@ where
             amount,
             fee_per_gram,
             message,
+            lock_height,
+            recipient_output_features,
+            account_id,
             service_request_reply_channel,
             stage,
         }
@@ -155,13 +165,15 @@ where
         match self
             .resources
             .output_manager_service
-            .prepare_transaction_to_send(
+            .prepare_account_transaction_to_send(
+                self.account_id,
                 self.id,
                 self.amount,
                 self.fee_per_gram,
-                None,
+                self.lock_height,
                 self.message.clone(),
                 script!(Nop),
+                self.recipient_output_features.clone(),
             )
             .await
         {
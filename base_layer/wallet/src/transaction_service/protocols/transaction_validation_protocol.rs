@@ -27,9 +27,10 @@ use crate::{
         config::TransactionServiceConfig,
         error::{TransactionServiceError, TransactionServiceProtocolError, TransactionServiceProtocolErrorExt},
         handle::{TransactionEvent, TransactionEventSender},
+        payment_request::match_payment_request,
         storage::{
             database::{TransactionBackend, TransactionDatabase},
-            models::{CompletedTransaction, TransactionStatus},
+            models::{CompletedTransaction, TransactionDirection, TransactionStatus},
         },
     },
 };
@@ -117,6 +118,17 @@ where
                 mined.len(),
                 unmined.len()
             );
+            if self.is_contradicted_by_other_base_nodes(&unmined).await {
+                warn!(
+                    target: LOG_TARGET,
+                    "A majority of the other base nodes report transactions as mined that the current base node does \
+                     not, aborting transaction validation"
+                );
+                if let Some(node_id) = self.connectivity.get_current_base_node_id() {
+                    self.connectivity.report_base_node_disputed(node_id).await;
+                }
+                return Err(TransactionServiceError::BaseNodeContradicted).for_protocol(self.operation_id);
+            }
             for (tx, mined_height, mined_in_block, num_confirmations) in &mined {
                 info!(target: LOG_TARGET, "Updating transaction {} as mined", tx.tx_id);
                 self.update_transaction_as_mined(tx, mined_in_block, *mined_height, *num_confirmations)
@@ -308,6 +320,62 @@ where
         ))
     }
 
+    /// Ask the other base nodes in the pool about the transactions the current base node reports as not mined. A
+    /// single base node could otherwise hide mined transactions from the wallet. Returns true if a majority of the
+    /// other base nodes that answered report at least one of them as mined.
+    async fn is_contradicted_by_other_base_nodes(&mut self, unmined: &[CompletedTransaction]) -> bool {
+        let signatures = unmined
+            .iter()
+            .filter_map(|tx| tx.transaction.first_kernel_excess_sig())
+            .cloned()
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            return false;
+        }
+        let clients = self.connectivity.obtain_cross_check_wallet_rpc_clients().await;
+        if clients.is_empty() {
+            return false;
+        }
+
+        let mut num_answered = 0usize;
+        let mut num_mined = 0usize;
+        for (node_id, mut client) in clients {
+            let response = match client
+                .transaction_batch_query(SignaturesProto {
+                    sigs: signatures.iter().map(|s| SignatureProto::from(s.clone())).collect(),
+                })
+                .await
+            {
+                Ok(r) => r,
+                Err(e) => {
+                    debug!(target: LOG_TARGET, "Base node {} cross-check failed: {}", node_id, e);
+                    continue;
+                },
+            };
+            if !response.is_synced {
+                debug!(
+                    target: LOG_TARGET,
+                    "Base node {} is not synced, ignoring its answer", node_id
+                );
+                continue;
+            }
+            num_answered += 1;
+            let is_any_mined = response.responses.into_iter().any(|r| {
+                TxQueryBatchResponse::try_from(r)
+                    .map(|r| r.location == TxLocation::Mined && signatures.contains(&r.signature))
+                    .unwrap_or(false)
+            });
+            if is_any_mined {
+                debug!(
+                    target: LOG_TARGET,
+                    "Base node {} reports transactions as mined that the current base node does not", node_id
+                );
+                num_mined += 1;
+            }
+        }
+        num_mined > 0 && num_mined * 2 > num_answered
+    }
+
     async fn get_base_node_block_at_height(
         &mut self,
         height: u64,
@@ -378,11 +446,56 @@ where
                     "Could not mark coinbase output for TxId: {} as not abandoned: {}", tx.tx_id, e
                 );
             };
+        } else if tx.direction == TransactionDirection::Inbound {
+            self.match_payment_request(tx, mined_height).await;
         }
 
         Ok(())
     }
 
+    /// Credit a mined incoming transaction to the payment request referenced in its message
+    async fn match_payment_request(&mut self, tx: &CompletedTransaction, mined_height: u64) {
+        match match_payment_request(&self.db, tx.tx_id, tx.amount, &tx.message, Some(mined_height), false).await {
+            Ok(Some(record)) => {
+                info!(
+                    target: LOG_TARGET,
+                    "TxId: {} paid {} of payment request {}, which is now {}",
+                    tx.tx_id,
+                    tx.amount,
+                    record.request.reference_hex(),
+                    record.status
+                );
+                self.publish_event(TransactionEvent::PaymentRequestUpdated(record.request.reference));
+            },
+            Ok(None) => {},
+            Err(e) => warn!(
+                target: LOG_TARGET,
+                "Error matching TxId: {} to a payment request: {:?}", tx.tx_id, e
+            ),
+        }
+    }
+
+    /// Take the payment of a transaction that is no longer mined off the payment request it was matched to
+    async fn unmatch_payment_request(&mut self, tx: &CompletedTransaction) {
+        match self.db.remove_payment_request_payment(tx.tx_id).await {
+            Ok(Some(record)) => {
+                info!(
+                    target: LOG_TARGET,
+                    "TxId: {} is no longer mined, payment request {} is now {}",
+                    tx.tx_id,
+                    record.request.reference_hex(),
+                    record.status
+                );
+                self.publish_event(TransactionEvent::PaymentRequestUpdated(record.request.reference));
+            },
+            Ok(None) => {},
+            Err(e) => warn!(
+                target: LOG_TARGET,
+                "Error removing TxId: {} from its payment request: {:?}", tx.tx_id, e
+            ),
+        }
+    }
+
     #[allow(clippy::ptr_arg)]
     async fn update_coinbase_as_abandoned(
         &mut self,
@@ -431,6 +544,8 @@ where
                     "Could not mark coinbase output for TxId: {} as not abandoned: {}", tx.tx_id, e
                 );
             };
+        } else {
+            self.unmatch_payment_request(tx).await;
         }
 
         self.publish_event(TransactionEvent::TransactionBroadcast(tx.tx_id));
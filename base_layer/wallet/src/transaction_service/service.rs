@@ -23,12 +23,60 @@
 use crate::{
     base_node_service::handle::{BaseNodeEvent, BaseNodeServiceHandle},
     connectivity_service::WalletConnectivityInterface,
-    output_manager_service::{handle::OutputManagerHandle, TxId},
+    output_manager_service::{
+        error::{HtlcError, OutputManagerError, OutputManagerStorageError},
+        handle::OutputManagerHandle,
+        htlc::{self, HtlcDetails},
+        storage::models::{AccountId, HtlcOutput, HtlcRole, HtlcStatus, DEFAULT_ACCOUNT_ID},
+        TxId,
+    },
     storage::database::{WalletBackend, WalletDatabase},
     transaction_service::{
+        acceptance_policy::{AcceptAllPolicy, AcceptanceDecision, IncomingTransaction, TransactionAcceptancePolicy},
         config::TransactionServiceConfig,
-        error::{TransactionServiceError, TransactionServiceProtocolError},
+        error::{
+            MultisigError,
+            OfflineSigningError,
+            PaymentRequestError,
+            TransactionServiceError,
+            TransactionServiceProtocolError,
+            TransactionStorageError,
+        },
         handle::{TransactionEvent, TransactionEventSender, TransactionServiceRequest, TransactionServiceResponse},
+        multisig::{
+            coordinator_key,
+            share_key,
+            signer_key,
+            wallet_key,
+            MultisigCoordinatorSession,
+            MultisigNonceCommitment,
+            MultisigNonceRequest,
+            MultisigNonces,
+            MultisigOutput,
+            MultisigOutputStatus,
+            MultisigPartialSignatures,
+            MultisigParticipant,
+            MultisigSignerSession,
+            MultisigSigningRequest,
+            MultisigSpendProposal,
+            MultisigUpdate,
+            MultisigWallet,
+            MultisigWalletId,
+            MultisigWalletRecord,
+        },
+        offline_signing::{
+            relay_key,
+            signing_state_key,
+            OfflineRecipientReply,
+            OfflineRelayRecord,
+            OfflineSenderMessage,
+            OfflineSigningOutcome,
+            OfflineSigningState,
+            OfflineTransactionSummary,
+            SignedTransaction,
+            UnsignedTransaction,
+        },
+        payment_request::{format_payment_reference, match_payment_request, PaymentReference, PaymentRequest},
         protocols::{
             transaction_broadcast_protocol::TransactionBroadcastProtocol,
             transaction_receive_protocol::{TransactionReceiveProtocol, TransactionReceiveProtocolStage},
@@ -37,12 +85,22 @@ use crate::{
         },
         storage::{
             database::{TransactionBackend, TransactionDatabase},
-            models::{CompletedTransaction, TransactionDirection, TransactionStatus},
+            models::{
+                CompletedTransaction,
+                HeldTransaction,
+                PaymentRequestRecord,
+                ScheduledTransaction,
+                TransactionDirection,
+                TransactionSchedule,
+                TransactionStatus,
+                WalletTransaction,
+            },
         },
         tasks::{
             send_finalized_transaction::send_finalized_transaction_message,
             send_transaction_cancelled::send_transaction_cancelled_message,
             send_transaction_reply::send_transaction_reply,
+            send_transaction_sender_message::send_transaction_sender_message,
         },
     },
     types::HashDigest,
@@ -54,13 +112,14 @@ use digest::Digest;
 use futures::{pin_mut, stream::FuturesUnordered, Stream, StreamExt};
 use log::*;
 use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     convert::TryInto,
     sync::Arc,
     time::{Duration, Instant},
 };
-use tari_common_types::types::PrivateKey;
+use tari_common_types::types::{Commitment, PrivateKey, PublicKey};
 use tari_comms::{peer_manager::NodeIdentity, types::CommsPublicKey};
 use tari_comms_dht::outbound::OutboundMessageRequester;
 use tari_core::{
@@ -68,7 +127,7 @@ use tari_core::{
     proto::base_node as base_node_proto,
     transactions::{
         tari_amount::MicroTari,
-        transaction::{KernelFeatures, OutputFeatures, Transaction},
+        transaction::{KernelFeatures, OutputFeatures, Transaction, UnblindedOutput},
         transaction_protocol::{
             proto,
             recipient::RecipientSignedMessage,
@@ -77,15 +136,22 @@ use tari_core::{
         },
         CryptoFactories,
         ReceiverTransactionProtocol,
+        SenderTransactionProtocol,
     },
 };
-use tari_crypto::{keys::DiffieHellmanSharedSecret, script, tari_utilities::ByteArray};
+use tari_crypto::{
+    keys::{DiffieHellmanSharedSecret, PublicKey as PublicKeyTrait},
+    script,
+    script::TariScript,
+    tari_utilities::{hex::Hex, ByteArray, Hashable},
+};
 use tari_p2p::domain_message::DomainMessage;
 use tari_service_framework::{reply_channel, reply_channel::Receiver};
 use tari_shutdown::ShutdownSignal;
 use tokio::{
     sync::{mpsc, mpsc::Sender, oneshot},
     task::JoinHandle,
+    time::{self, MissedTickBehavior},
 };
 
 const LOG_TARGET: &str = "wallet::transaction_service::service";
@@ -137,6 +203,7 @@ pub struct TransactionService<
     wallet_db: WalletDatabase<TWalletBackend>,
     base_node_service: BaseNodeServiceHandle,
     last_seen_tip_height: Option<u64>,
+    acceptance_policy: Arc<dyn TransactionAcceptancePolicy>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -236,9 +303,16 @@ where
             base_node_service,
             wallet_db,
             last_seen_tip_height: None,
+            acceptance_policy: Arc::new(AcceptAllPolicy),
         }
     }
 
+    /// Use the given policy to decide whether inbound transactions are accepted, instead of accepting all of them
+    pub fn with_acceptance_policy(mut self, policy: Arc<dyn TransactionAcceptancePolicy>) -> Self {
+        self.acceptance_policy = policy;
+        self
+    }
+
     #[warn(unreachable_code)]
     pub async fn start(mut self) -> Result<(), TransactionServiceError> {
         let request_stream = self
@@ -298,13 +372,20 @@ where
 
         let mut base_node_service_event_stream = self.base_node_service.get_event_stream();
 
+        let mut scheduled_transaction_interval = time::interval(self.config.scheduled_transaction_check_interval);
+        scheduled_transaction_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         info!(target: LOG_TARGET, "Transaction Service started");
         loop {
             tokio::select! {
                 // Base Node Monitoring Service event
                 event = base_node_service_event_stream.recv() => {
                     match event {
-                        Ok(msg) => self.handle_base_node_service_event(msg, &mut transaction_validation_protocol_handles).await,
+                        Ok(msg) => self.handle_base_node_service_event(
+                            msg,
+                            &mut transaction_validation_protocol_handles,
+                            &mut transaction_broadcast_protocol_handles,
+                        ).await,
                         Err(e) => debug!(target: LOG_TARGET, "Lagging read on base node event broadcast channel: {}", e),
                     };
                 },
@@ -493,6 +574,19 @@ where
                         Ok(join_result_inner) => self.complete_transaction_validation_protocol(join_result_inner, &mut transaction_broadcast_protocol_handles,).await,
                         Err(e) => error!(target: LOG_TARGET, "Error resolving Transaction Validation protocol: {:?}", e),
                     };
+                }
+                _ = scheduled_transaction_interval.tick() => {
+                    let _ = self
+                        .broadcast_due_scheduled_transactions(&mut transaction_broadcast_protocol_handles)
+                        .await
+                        .map_err(|e| {
+                            warn!(target: LOG_TARGET, "Error broadcasting scheduled transactions: {:?}", e);
+                            e
+                        });
+                    let _ = self.reject_timed_out_held_transactions().await.map_err(|e| {
+                        warn!(target: LOG_TARGET, "Error rejecting timed out held transactions: {:?}", e);
+                        e
+                    });
                 }
                  _ = shutdown.wait() => {
                     info!(target: LOG_TARGET, "Transaction service shutting down because it received the shutdown signal");
@@ -527,10 +621,93 @@ where
             TransactionServiceRequest::SendTransaction(dest_pubkey, amount, fee_per_gram, message) => {
                 let rp = reply_channel.take().expect("Cannot be missing");
                 self.send_transaction(
+                    DEFAULT_ACCOUNT_ID,
+                    dest_pubkey,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    OutputFeatures::default(),
+                    None,
+                    send_transaction_join_handles,
+                    transaction_broadcast_join_handles,
+                    rp,
+                )
+                .await?;
+                return Ok(());
+            },
+            TransactionServiceRequest::SendTransactionFromAccount(
+                account_id,
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+            ) => {
+                let rp = reply_channel.take().expect("Cannot be missing");
+                self.send_transaction(
+                    account_id,
+                    dest_pubkey,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    OutputFeatures::default(),
+                    None,
+                    send_transaction_join_handles,
+                    transaction_broadcast_join_handles,
+                    rp,
+                )
+                .await?;
+                return Ok(());
+            },
+            TransactionServiceRequest::TransferBetweenAccounts(
+                from_account_id,
+                to_account_id,
+                amount,
+                fee_per_gram,
+                message,
+            ) => self
+                .transfer_between_accounts(
+                    from_account_id,
+                    to_account_id,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    transaction_broadcast_join_handles,
+                )
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::SendTimeLockedTransaction(
+                dest_pubkey,
+                amount,
+                maturity,
+                fee_per_gram,
+                message,
+            ) => {
+                let rp = reply_channel.take().expect("Cannot be missing");
+                self.send_transaction(
+                    DEFAULT_ACCOUNT_ID,
+                    dest_pubkey,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    OutputFeatures::with_maturity(maturity),
+                    None,
+                    send_transaction_join_handles,
+                    transaction_broadcast_join_handles,
+                    rp,
+                )
+                .await?;
+                return Ok(());
+            },
+            TransactionServiceRequest::ScheduleTransaction(dest_pubkey, amount, fee_per_gram, message, schedule) => {
+                let rp = reply_channel.take().expect("Cannot be missing");
+                self.send_transaction(
+                    DEFAULT_ACCOUNT_ID,
                     dest_pubkey,
                     amount,
                     fee_per_gram,
                     message,
+                    OutputFeatures::default(),
+                    Some(schedule),
                     send_transaction_join_handles,
                     transaction_broadcast_join_handles,
                     rp,
@@ -538,6 +715,9 @@ where
                 .await?;
                 return Ok(());
             },
+            TransactionServiceRequest::GetScheduledTransactions => Ok(
+                TransactionServiceResponse::ScheduledTransactions(self.db.get_scheduled_transactions().await?),
+            ),
             TransactionServiceRequest::SendOneSidedTransaction(dest_pubkey, amount, fee_per_gram, message) => self
                 .send_one_sided_transaction(
                     dest_pubkey,
@@ -643,6 +823,173 @@ where
                 .start_transaction_validation_protocol(transaction_validation_join_handles)
                 .await
                 .map(TransactionServiceResponse::ValidationStarted),
+            TransactionServiceRequest::PrepareOfflineTransaction(
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+                one_sided,
+            ) => self
+                .prepare_offline_transaction(dest_pubkey, amount, fee_per_gram, message, one_sided)
+                .await
+                .map(|t| TransactionServiceResponse::OfflineTransactionPrepared(Box::new(t))),
+            TransactionServiceRequest::SummarizeOfflineTransaction(unsigned) => self
+                .summarize_offline_transaction(*unsigned)
+                .await
+                .map(|s| TransactionServiceResponse::OfflineTransactionSummarized(Box::new(s))),
+            TransactionServiceRequest::SignOfflineTransaction(unsigned) => self
+                .sign_offline_transaction(*unsigned)
+                .await
+                .map(TransactionServiceResponse::OfflineTransactionSigned),
+            TransactionServiceRequest::RelayOfflineSenderMessage(sender_message) => self
+                .relay_offline_sender_message(*sender_message)
+                .await
+                .map(|_| TransactionServiceResponse::OfflineSenderMessageRelayed),
+            TransactionServiceRequest::GetOfflineRecipientReply(tx_id) => self
+                .get_offline_recipient_reply(tx_id)
+                .await
+                .map(|r| TransactionServiceResponse::OfflineRecipientReply(Box::new(r))),
+            TransactionServiceRequest::FinalizeOfflineTransaction(reply) => self
+                .finalize_offline_transaction(*reply)
+                .await
+                .map(|t| TransactionServiceResponse::OfflineTransactionFinalized(Box::new(t))),
+            TransactionServiceRequest::SubmitOfflineTransaction(signed) => self
+                .submit_offline_transaction(*signed, transaction_broadcast_join_handles)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionSubmitted),
+            TransactionServiceRequest::CancelOfflineTransaction(tx_id) => self
+                .cancel_offline_transaction(tx_id)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionCancelled),
+            TransactionServiceRequest::CreateMultisigParticipant => self
+                .create_multisig_participant()
+                .await
+                .map(TransactionServiceResponse::MultisigParticipantCreated),
+            TransactionServiceRequest::CreateMultisigWallet(threshold, participants) => self
+                .create_multisig_wallet(threshold, participants)
+                .await
+                .map(|w| TransactionServiceResponse::MultisigWalletCreated(Box::new(w))),
+            TransactionServiceRequest::ImportMultisigWallet(wallet) => self
+                .import_multisig_wallet(*wallet)
+                .await
+                .map(|_| TransactionServiceResponse::MultisigWalletImported),
+            TransactionServiceRequest::GetMultisigOutputs(wallet_id) => self
+                .get_multisig_outputs(wallet_id)
+                .await
+                .map(TransactionServiceResponse::MultisigOutputs),
+            TransactionServiceRequest::SendToMultisig(wallet_id, amount, fee_per_gram, message) => self
+                .send_to_multisig(
+                    wallet_id,
+                    amount,
+                    fee_per_gram,
+                    message,
+                    transaction_broadcast_join_handles,
+                )
+                .await
+                .map(|u| TransactionServiceResponse::MultisigUpdate(Box::new(u))),
+            TransactionServiceRequest::ImportMultisigUpdate(update) => self
+                .import_multisig_update(*update)
+                .await
+                .map(TransactionServiceResponse::MultisigUpdateImported),
+            TransactionServiceRequest::ProposeMultisigSpend(
+                wallet_id,
+                signers,
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                message,
+            ) => self
+                .propose_multisig_spend(wallet_id, signers, dest_pubkey, amount, fee_per_gram, message)
+                .await
+                .map(|p| TransactionServiceResponse::MultisigSpendProposed(Box::new(p))),
+            TransactionServiceRequest::CommitMultisigNonces(proposal) => self
+                .commit_multisig_nonces(*proposal)
+                .await
+                .map(|c| TransactionServiceResponse::MultisigNoncesCommitted(Box::new(c))),
+            TransactionServiceRequest::AddMultisigNonceCommitment(commitment) => self
+                .add_multisig_nonce_commitment(*commitment)
+                .await
+                .map(|r| TransactionServiceResponse::MultisigNonceRequest(r.map(Box::new))),
+            TransactionServiceRequest::RevealMultisigNonces(request) => self
+                .reveal_multisig_nonces(*request)
+                .await
+                .map(|n| TransactionServiceResponse::MultisigNoncesRevealed(Box::new(n))),
+            TransactionServiceRequest::AddMultisigNonces(nonces) => self
+                .add_multisig_nonces(*nonces)
+                .await
+                .map(|r| TransactionServiceResponse::MultisigSigningRequest(r.map(Box::new))),
+            TransactionServiceRequest::SignMultisigRequest(request) => self
+                .sign_multisig_request(*request)
+                .await
+                .map(|p| TransactionServiceResponse::MultisigRequestSigned(Box::new(p))),
+            TransactionServiceRequest::AddMultisigPartialSignatures(partial_signatures) => self
+                .add_multisig_partial_signatures(*partial_signatures, transaction_broadcast_join_handles)
+                .await
+                .map(|u| TransactionServiceResponse::MultisigSpendCompleted(u.map(Box::new))),
+            TransactionServiceRequest::CancelMultisigSpend(session_id) => self
+                .cancel_multisig_spend(session_id)
+                .await
+                .map(|_| TransactionServiceResponse::TransactionCancelled),
+            TransactionServiceRequest::SendHtlc(
+                dest_pubkey,
+                amount,
+                fee_per_gram,
+                hash_lock,
+                timeout_height,
+                message,
+            ) => self
+                .send_htlc(
+                    dest_pubkey,
+                    amount,
+                    fee_per_gram,
+                    hash_lock,
+                    timeout_height,
+                    message,
+                    transaction_broadcast_join_handles,
+                )
+                .await
+                .map(|(tx_id, d)| TransactionServiceResponse::HtlcSent(tx_id, Box::new(d))),
+            TransactionServiceRequest::ImportHtlc(details, fee_per_gram) => self
+                .import_htlc(*details, fee_per_gram)
+                .await
+                .map(|h| TransactionServiceResponse::HtlcImported(Box::new(h))),
+            TransactionServiceRequest::SpendHtlc(commitment, message) => self
+                .spend_htlc(commitment, message, transaction_broadcast_join_handles)
+                .await
+                .map(TransactionServiceResponse::TransactionSent),
+            TransactionServiceRequest::CreatePaymentRequest(amount, message, expiry_height) => self
+                .create_payment_request(amount, message, expiry_height)
+                .await
+                .map(|r| TransactionServiceResponse::PaymentRequestCreated(Box::new(r))),
+            TransactionServiceRequest::GetPaymentRequests => Ok(TransactionServiceResponse::PaymentRequests(
+                self.db.get_payment_requests().await?,
+            )),
+            TransactionServiceRequest::GetPaymentRequest(reference) => self
+                .db
+                .get_payment_request(reference)
+                .await?
+                .ok_or_else(|| PaymentRequestError::NotFound(format_payment_reference(reference)).into())
+                .map(|r| TransactionServiceResponse::PaymentRequest(Box::new(r))),
+            TransactionServiceRequest::SetAcceptancePolicy(policy) => {
+                self.acceptance_policy = policy;
+                Ok(TransactionServiceResponse::AcceptancePolicySet)
+            },
+            TransactionServiceRequest::GetHeldTransactions => Ok(TransactionServiceResponse::HeldTransactions(
+                self.db
+                    .get_held_transactions()
+                    .await?
+                    .into_iter()
+                    .map(|h| h.transaction)
+                    .collect(),
+            )),
+            TransactionServiceRequest::ApproveHeldTransaction(tx_id) => self
+                .approve_held_transaction(tx_id, receive_transaction_join_handles)
+                .await
+                .map(|_| TransactionServiceResponse::HeldTransactionApproved),
+            TransactionServiceRequest::RejectHeldTransaction(tx_id) => self
+                .reject_held_transaction(tx_id, "rejected by the user")
+                .await
+                .map(|_| TransactionServiceResponse::HeldTransactionRejected),
         };
 
         // If the individual handlers did not already send the API response then do it here.
@@ -661,6 +1008,9 @@ where
         transaction_validation_join_handles: &mut FuturesUnordered<
             JoinHandle<Result<u64, TransactionServiceProtocolError>>,
         >,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
     ) {
         match (*event).clone() {
             BaseNodeEvent::BaseNodeStateChanged(state) => {
@@ -680,21 +1030,52 @@ where
                         });
                 }
                 self.last_seen_tip_height = state.chain_metadata.map(|cm| cm.height_of_longest_chain());
+                if trigger_validation {
+                    if let Some(tip_height) = self.last_seen_tip_height {
+                        let _ = self
+                            .spend_due_htlcs(tip_height, transaction_broadcast_join_handles)
+                            .await
+                            .map_err(|e| {
+                                warn!(target: LOG_TARGET, "Error claiming or refunding HTLCs: {:?}", e);
+                                e
+                            });
+                    }
+                    let _ = self
+                        .broadcast_due_scheduled_transactions(transaction_broadcast_join_handles)
+                        .await
+                        .map_err(|e| {
+                            warn!(target: LOG_TARGET, "Error broadcasting scheduled transactions: {:?}", e);
+                            e
+                        });
+                    if let Some(tip_height) = self.last_seen_tip_height {
+                        let _ = self.expire_payment_requests(tip_height).await.map_err(|e| {
+                            warn!(target: LOG_TARGET, "Error expiring payment requests: {:?}", e);
+                            e
+                        });
+                    }
+                }
             },
         }
     }
 
     /// Sends a new transaction to a recipient
     /// # Arguments
+    /// 'account_id': The account whose outputs fund the transaction
     /// 'dest_pubkey': The Comms pubkey of the recipient node
     /// 'amount': The amount of Tari to send to the recipient
     /// 'fee_per_gram': The amount of fee per transaction gram to be included in transaction
+    /// 'recipient_output_features': The features of the recipient's output, e.g. its maturity
+    /// 'schedule': If provided the completed transaction is held until the schedule is due before it is broadcast
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_transaction(
         &mut self,
+        account_id: AccountId,
         dest_pubkey: CommsPublicKey,
         amount: MicroTari,
         fee_per_gram: MicroTari,
         message: String,
+        recipient_output_features: OutputFeatures,
+        schedule: Option<TransactionSchedule>,
         join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
         transaction_broadcast_join_handles: &mut FuturesUnordered<
             JoinHandle<Result<u64, TransactionServiceProtocolError>>,
@@ -702,6 +1083,7 @@ where
         reply_channel: oneshot::Sender<Result<TransactionServiceResponse, TransactionServiceError>>,
     ) -> Result<(), TransactionServiceError> {
         let tx_id = OsRng.next_u64();
+        let lock_height = schedule.and_then(|s| s.lock_height());
 
         // If we're paying ourselves, let's complete and submit the transaction immediately
         if self.node_identity.public_key() == &dest_pubkey {
@@ -709,32 +1091,36 @@ where
                 target: LOG_TARGET,
                 "Received transaction with spend-to-self transaction"
             );
+            if recipient_output_features.maturity > 0 {
+                return Err(TransactionServiceError::TimeLockError(
+                    "Time-locked spend-to-self transactions not supported".to_string(),
+                ));
+            }
+            if let Some(schedule) = schedule {
+                self.db
+                    .add_scheduled_transaction(ScheduledTransaction::new(tx_id, schedule))
+                    .await?;
+            }
 
             let (fee, transaction) = self
                 .output_manager_service
-                .create_pay_to_self_transaction(tx_id, amount, fee_per_gram, None, message.clone())
-                .await?;
-
-            // Notify that the transaction was successfully resolved.
-            let _ = self
-                .event_publisher
-                .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
-
-            self.submit_transaction(
-                transaction_broadcast_join_handles,
-                CompletedTransaction::new(
+                .create_account_transfer_transaction(
                     tx_id,
-                    self.node_identity.public_key().clone(),
-                    self.node_identity.public_key().clone(),
+                    account_id,
+                    DEFAULT_ACCOUNT_ID,
                     amount,
-                    fee,
-                    transaction,
-                    TransactionStatus::Completed,
-                    message,
-                    Utc::now().naive_utc(),
-                    TransactionDirection::Inbound,
-                    None,
-                ),
+                    fee_per_gram,
+                    lock_height,
+                    message.clone(),
+                )
+                .await?;
+            self.complete_transaction_to_self(
+                tx_id,
+                amount,
+                fee,
+                transaction,
+                message,
+                transaction_broadcast_join_handles,
             )
             .await?;
 
@@ -748,6 +1134,14 @@ where
             return Ok(());
         }
 
+        // The schedule is stored before the transaction is negotiated so that the transaction is held back when it
+        // completes, even if that is after a restart
+        if let Some(schedule) = schedule {
+            self.db
+                .add_scheduled_transaction(ScheduledTransaction::new(tx_id, schedule))
+                .await?;
+        }
+
         let (tx_reply_sender, tx_reply_receiver) = mpsc::channel(100);
         let (cancellation_sender, cancellation_receiver) = oneshot::channel();
         self.pending_transaction_reply_senders.insert(tx_id, tx_reply_sender);
@@ -763,6 +1157,9 @@ where
             amount,
             fee_per_gram,
             message,
+            lock_height,
+            recipient_output_features,
+            account_id,
             Some(reply_channel),
             TransactionSendProtocolStage::Initial,
         );
@@ -773,6 +1170,80 @@ where
         Ok(())
     }
 
+    /// Moves funds between two accounts of this wallet. The transaction is completed and submitted immediately.
+    pub async fn transfer_between_accounts(
+        &mut self,
+        from_account_id: AccountId,
+        to_account_id: AccountId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let tx_id = OsRng.next_u64();
+        let (fee, transaction) = self
+            .output_manager_service
+            .create_account_transfer_transaction(
+                tx_id,
+                from_account_id,
+                to_account_id,
+                amount,
+                fee_per_gram,
+                None,
+                message.clone(),
+            )
+            .await?;
+        self.complete_transaction_to_self(
+            tx_id,
+            amount,
+            fee,
+            transaction,
+            message,
+            transaction_broadcast_join_handles,
+        )
+        .await?;
+
+        Ok(tx_id)
+    }
+
+    /// Record a transaction that pays this wallet from its own outputs as completed and submit it for broadcast
+    async fn complete_transaction_to_self(
+        &mut self,
+        tx_id: TxId,
+        amount: MicroTari,
+        fee: MicroTari,
+        transaction: Transaction,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(), TransactionServiceError> {
+        // Notify that the transaction was successfully resolved.
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
+
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                tx_id,
+                self.node_identity.public_key().clone(),
+                self.node_identity.public_key().clone(),
+                amount,
+                fee,
+                transaction,
+                TransactionStatus::Completed,
+                message,
+                Utc::now().naive_utc(),
+                TransactionDirection::Inbound,
+                None,
+            ),
+        )
+        .await
+    }
+
     /// Sends a one side payment transaction to a recipient
     /// # Arguments
     /// 'dest_pubkey': The Comms pubkey of the recipient node
@@ -795,6 +1266,33 @@ where
             ));
         }
 
+        let script = script!(PushPubKey(Box::new(dest_pubkey.clone())));
+        let (tx_id, _, _) = self
+            .send_one_sided_to_script(
+                dest_pubkey,
+                script,
+                amount,
+                fee_per_gram,
+                message,
+                transaction_broadcast_join_handles,
+            )
+            .await?;
+        Ok(tx_id)
+    }
+
+    /// Send a one-sided payment to an output locked with `script`, of which the spending key is derived from a
+    /// Diffie-Hellman shared secret with `dest_pubkey`
+    async fn send_one_sided_to_script(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        script: TariScript,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(TxId, Transaction, PrivateKey), TransactionServiceError> {
         let tx_id = OsRng.next_u64();
 
         // Prepare sender part of the transaction
@@ -806,7 +1304,8 @@ where
                 fee_per_gram,
                 None,
                 message.clone(),
-                script!(PushPubKey(Box::new(dest_pubkey.clone()))),
+                script,
+                OutputFeatures::default(),
             )
             .await?;
 
@@ -821,18 +1320,62 @@ where
             .await
             .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
 
-        // Prepare receiver part of the transaction
+        let spending_key = self.finalize_one_sided_transaction(tx_id, &mut stp, &dest_pubkey)?;
 
-        // Diffie-Hellman shared secret `k_Ob * K_Sb = K_Ob * k_Sb` results in a public key, which is converted to
-        // bytes to enable conversion into a private key to be used as the spending key
-        let sender_offset_private_key = stp
-            .get_recipient_sender_offset_private_key(0)
-            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
-        // TODO: Add a standardized Diffie-Hellman method to the tari_crypto library that will return a private key,
-        // TODO: then come back and use it here.
-        let spending_key = PrivateKey::from_bytes(
-            CommsPublicKey::shared_secret(&sender_offset_private_key.clone(), &dest_pubkey.clone()).as_bytes(),
-        )
+        // This event being sent is important, but not critical to the protocol being successful. Send only fails if
+        // there are no subscribers.
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
+
+        // Broadcast one-sided transaction
+
+        let tx = stp
+            .get_transaction()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        let fee = stp
+            .get_fee_amount()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                tx_id,
+                self.resources.node_identity.public_key().clone(),
+                dest_pubkey,
+                amount,
+                fee,
+                tx.clone(),
+                TransactionStatus::Completed,
+                message,
+                Utc::now().naive_utc(),
+                TransactionDirection::Outbound,
+                None,
+            ),
+        )
+        .await?;
+
+        Ok((tx_id, tx, spending_key))
+    }
+
+    /// Complete the receiver part of a one-sided transaction on behalf of the recipient and finalize the sender
+    /// protocol. The recipient's spending key is derived from a Diffie-Hellman shared secret so that only the recipient
+    /// can spend the output. The spending key is returned for outputs that the sender can also spend, such as HTLCs.
+    fn finalize_one_sided_transaction(
+        &self,
+        tx_id: TxId,
+        stp: &mut SenderTransactionProtocol,
+        dest_pubkey: &CommsPublicKey,
+    ) -> Result<PrivateKey, TransactionServiceError> {
+        // Diffie-Hellman shared secret `k_Ob * K_Sb = K_Ob * k_Sb` results in a public key, which is converted to
+        // bytes to enable conversion into a private key to be used as the spending key
+        let sender_offset_private_key = stp
+            .get_recipient_sender_offset_private_key(0)
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        // TODO: Add a standardized Diffie-Hellman method to the tari_crypto library that will return a private key,
+        // TODO: then come back and use it here.
+        let spending_key = PrivateKey::from_bytes(
+            CommsPublicKey::shared_secret(&sender_offset_private_key.clone(), &dest_pubkey.clone()).as_bytes(),
+        )
         .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
 
         let sender_message = TransactionSenderMessage::new_single_round_message(stp.get_single_round_message()?);
@@ -847,7 +1390,7 @@ where
         let rtp = ReceiverTransactionProtocol::new_with_rewindable_output(
             sender_message,
             PrivateKey::random(&mut OsRng),
-            spending_key,
+            spending_key.clone(),
             OutputFeatures::default(),
             &self.resources.factories,
             &rewind_data,
@@ -871,40 +1414,7 @@ where
                 TransactionServiceProtocolError::new(tx_id, e.into())
             })?;
         info!(target: LOG_TARGET, "Finalized one-side transaction TxId: {}", tx_id);
-
-        // This event being sent is important, but not critical to the protocol being successful. Send only fails if
-        // there are no subscribers.
-        let _ = self
-            .event_publisher
-            .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
-
-        // Broadcast one-sided transaction
-
-        let tx = stp
-            .get_transaction()
-            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
-        let fee = stp
-            .get_fee_amount()
-            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
-        self.submit_transaction(
-            transaction_broadcast_join_handles,
-            CompletedTransaction::new(
-                tx_id,
-                self.resources.node_identity.public_key().clone(),
-                dest_pubkey.clone(),
-                amount,
-                fee,
-                tx.clone(),
-                TransactionStatus::Completed,
-                message.clone(),
-                Utc::now().naive_utc(),
-                TransactionDirection::Outbound,
-                None,
-            ),
-        )
-        .await?;
-
-        Ok(tx_id)
+        Ok(spending_key)
     }
 
     /// Accept the public reply from a recipient and apply the reply to the relevant transaction protocol
@@ -1027,7 +1537,12 @@ where
 
         // Is this a new Transaction Reply for an existing pending transaction?
         let sender = match self.pending_transaction_reply_senders.get_mut(&tx_id) {
-            None => return Err(TransactionServiceError::TransactionDoesNotExistError),
+            // The reply could be for an interactive transaction that is being signed offline
+            None => {
+                return self
+                    .accept_offline_recipient_reply(source_pubkey, recipient_reply)
+                    .await
+            },
             Some(s) => s,
         };
 
@@ -1094,15 +1609,24 @@ where
         }
     }
 
-    /// Cancel a pending transaction
+    /// Cancel a pending transaction, or a completed transaction that is being held until its schedule is due
     async fn cancel_pending_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
-        self.db.cancel_pending_transaction(tx_id).await.map_err(|e| {
-            warn!(
-                target: LOG_TARGET,
-                "Pending Transaction does not exist and could not be cancelled: {:?}", e
-            );
-            e
-        })?;
+        let scheduled_tx = self.db.get_scheduled_transaction(tx_id).await?;
+        match self.db.get_completed_transaction(tx_id).await {
+            Ok(tx) if scheduled_tx.is_some() && tx.status == TransactionStatus::Scheduled => {
+                self.db.cancel_completed_transaction(tx_id).await?
+            },
+            _ => self.db.cancel_pending_transaction(tx_id).await.map_err(|e| {
+                warn!(
+                    target: LOG_TARGET,
+                    "Pending Transaction does not exist and could not be cancelled: {:?}", e
+                );
+                e
+            })?,
+        }
+        if scheduled_tx.is_some() {
+            self.db.remove_scheduled_transaction(tx_id).await?;
+        }
 
         self.output_manager_service.cancel_transaction(tx_id).await?;
 
@@ -1168,6 +1692,23 @@ where
             }
         }
 
+        // A recipient whose acceptance policy rejected our transaction tells us so instead of replying
+        if let Ok(outbound_tx) = self.db.get_pending_outbound_transaction(tx_id).await {
+            if outbound_tx.destination_public_key == source_pubkey {
+                info!(
+                    target: LOG_TARGET,
+                    "Transaction (TxId: {}) was rejected by the recipient", tx_id
+                );
+                self.cancel_pending_transaction(tx_id).await?;
+            } else {
+                trace!(
+                    target: LOG_TARGET,
+                    "Received a Transaction Cancelled (TxId: {}) message from an unknown source, ignoring",
+                    tx_id
+                );
+            }
+        }
+
         Ok(())
     }
 
@@ -1198,6 +1739,9 @@ where
                     tx.fee,
                     tx.message,
                     None,
+                    OutputFeatures::default(),
+                    DEFAULT_ACCOUNT_ID,
+                    None,
                     TransactionSendProtocolStage::WaitForReply,
                 );
 
@@ -1310,31 +1854,180 @@ where
                 return Err(TransactionServiceError::RepeatedMessageError);
             }
 
-            let (tx_finalized_sender, tx_finalized_receiver) = mpsc::channel(100);
-            let (cancellation_sender, cancellation_receiver) = oneshot::channel();
-            self.finalized_transaction_senders
-                .insert(data.tx_id, tx_finalized_sender);
-            self.receiver_transaction_cancellation_senders
-                .insert(data.tx_id, cancellation_sender);
+            // Check if this transaction is already waiting for approval
+            if let Some(held) = self.db.get_held_transaction(data.tx_id).await? {
+                if held.transaction.source_public_key != source_pubkey {
+                    return Err(TransactionServiceError::InvalidSourcePublicKey);
+                }
+                trace!(
+                    target: LOG_TARGET,
+                    "A repeated Transaction (TxId: {}) has been received while it is held for approval. Ignoring.",
+                    data.tx_id
+                );
+                return Ok(());
+            }
 
-            let protocol = TransactionReceiveProtocol::new(
-                data.tx_id,
-                source_pubkey,
-                sender_message,
-                TransactionReceiveProtocolStage::Initial,
-                self.resources.clone(),
-                tx_finalized_receiver,
-                cancellation_receiver,
-            );
+            let transaction = IncomingTransaction {
+                tx_id: data.tx_id,
+                source_public_key: source_pubkey.clone(),
+                amount: data.amount,
+                message: data.message.clone(),
+                timestamp: Utc::now().naive_utc(),
+            };
+            let decision = self.acceptance_policy.evaluate(&transaction).await;
+            match decision {
+                AcceptanceDecision::Accept => (),
+                AcceptanceDecision::Hold => {
+                    if self.db.get_held_transactions().await?.len() >= self.config.max_held_transactions {
+                        self.send_transaction_rejection(&transaction, "too many transactions are held for approval");
+                        return Ok(());
+                    }
+                    info!(
+                        target: LOG_TARGET,
+                        "Transaction (TxId: {}) of {} from {} is held for approval",
+                        data.tx_id,
+                        data.amount,
+                        source_pubkey
+                    );
+                    self.db
+                        .add_held_transaction(HeldTransaction {
+                            transaction,
+                            sender_message,
+                        })
+                        .await?;
+                    let _ = self
+                        .event_publisher
+                        .send(Arc::new(TransactionEvent::TransactionHeldForApproval(data.tx_id)))
+                        .map_err(|e| {
+                            trace!(
+                                target: LOG_TARGET,
+                                "Error sending event, usually because there are no subscribers: {:?}",
+                                e
+                            );
+                            e
+                        });
+                    return Ok(());
+                },
+                AcceptanceDecision::Reject(reason) => {
+                    self.send_transaction_rejection(&transaction, &reason);
+                    return Ok(());
+                },
+            }
 
-            let join_handle = tokio::spawn(protocol.execute());
-            join_handles.push(join_handle);
+            self.start_receive_transaction_protocol(data.tx_id, source_pubkey, sender_message, join_handles);
             Ok(())
         } else {
             Err(TransactionServiceError::InvalidStateError)
         }
     }
 
+    fn start_receive_transaction_protocol(
+        &mut self,
+        tx_id: TxId,
+        source_pubkey: CommsPublicKey,
+        sender_message: TransactionSenderMessage,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) {
+        let (tx_finalized_sender, tx_finalized_receiver) = mpsc::channel(100);
+        let (cancellation_sender, cancellation_receiver) = oneshot::channel();
+        self.finalized_transaction_senders.insert(tx_id, tx_finalized_sender);
+        self.receiver_transaction_cancellation_senders
+            .insert(tx_id, cancellation_sender);
+
+        let protocol = TransactionReceiveProtocol::new(
+            tx_id,
+            source_pubkey,
+            sender_message,
+            TransactionReceiveProtocolStage::Initial,
+            self.resources.clone(),
+            tx_finalized_receiver,
+            cancellation_receiver,
+        );
+
+        let join_handle = tokio::spawn(protocol.execute());
+        join_handles.push(join_handle);
+    }
+
+    /// Send the reply for a transaction that was held for approval
+    async fn approve_held_transaction(
+        &mut self,
+        tx_id: TxId,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<(), TransactionServiceError> {
+        let held = self.remove_held_transaction(tx_id).await?;
+        info!(target: LOG_TARGET, "Held Transaction (TxId: {}) approved", tx_id);
+        self.start_receive_transaction_protocol(
+            tx_id,
+            held.transaction.source_public_key,
+            held.sender_message,
+            join_handles,
+        );
+        Ok(())
+    }
+
+    async fn reject_held_transaction(&mut self, tx_id: TxId, reason: &str) -> Result<(), TransactionServiceError> {
+        let held = self.remove_held_transaction(tx_id).await?;
+        self.send_transaction_rejection(&held.transaction, reason);
+        Ok(())
+    }
+
+    async fn remove_held_transaction(&mut self, tx_id: TxId) -> Result<HeldTransaction, TransactionServiceError> {
+        if self.db.get_held_transaction(tx_id).await?.is_none() {
+            return Err(TransactionServiceError::TransactionDoesNotExistError);
+        }
+        Ok(self.db.remove_held_transaction(tx_id).await?)
+    }
+
+    async fn reject_timed_out_held_transactions(&mut self) -> Result<(), TransactionServiceError> {
+        let timeout = self.config.held_transaction_approval_timeout;
+        let now = Utc::now().naive_utc();
+        let timed_out = self
+            .db
+            .get_held_transactions()
+            .await?
+            .into_iter()
+            .filter(|h| {
+                now.signed_duration_since(h.transaction.timestamp)
+                    .to_std()
+                    .map(|elapsed| elapsed >= timeout)
+                    .unwrap_or(false)
+            })
+            .map(|h| h.transaction.tx_id)
+            .collect::<Vec<_>>();
+        for tx_id in timed_out {
+            self.reject_held_transaction(tx_id, "approval timed out").await?;
+        }
+        Ok(())
+    }
+
+    /// Tell the sender that their transaction was not accepted so that they can cancel it
+    fn send_transaction_rejection(&self, transaction: &IncomingTransaction, reason: &str) {
+        info!(
+            target: LOG_TARGET,
+            "Transaction (TxId: {}) of {} from {} rejected: {}",
+            transaction.tx_id,
+            transaction.amount,
+            transaction.source_public_key,
+            reason
+        );
+        tokio::spawn(send_transaction_cancelled_message(
+            transaction.tx_id,
+            transaction.source_public_key.clone(),
+            self.resources.outbound_message_service.clone(),
+        ));
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionRejected(transaction.tx_id)))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+    }
+
     /// Accept the public reply from a recipient and apply the reply to the relevant transaction protocol
     /// # Arguments
     /// 'recipient_reply' - The public response from a recipient with data required to complete the transaction
@@ -1629,13 +2322,44 @@ where
         Ok(())
     }
 
-    /// Start to protocol to Broadcast the specified Completed Transaction to the Base Node.
+    /// Start to protocol to Broadcast the specified Completed Transaction to the Base Node. If the transaction is
+    /// scheduled and the schedule is not yet due it is held with the `Scheduled` status instead.
     async fn broadcast_completed_transaction(
         &mut self,
-        completed_tx: CompletedTransaction,
+        mut completed_tx: CompletedTransaction,
         join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
     ) -> Result<(), TransactionServiceError> {
         let tx_id = completed_tx.tx_id;
+        if let Some(scheduled_tx) = self.db.get_scheduled_transaction(tx_id).await? {
+            if !scheduled_tx
+                .schedule
+                .is_due(self.last_seen_tip_height, Utc::now().naive_utc())
+            {
+                if completed_tx.status == TransactionStatus::Completed {
+                    self.db.set_completed_transaction_scheduled(tx_id, true).await?;
+                    info!(
+                        target: LOG_TARGET,
+                        "Transaction (TxId: {}) is scheduled to be broadcast {}", tx_id, scheduled_tx.schedule
+                    );
+                    let _ = self
+                        .event_publisher
+                        .send(Arc::new(TransactionEvent::TransactionScheduled(tx_id)));
+                }
+                return Ok(());
+            }
+            // The status is released before the schedule is removed, so that a transaction is never left `Scheduled`
+            // without a schedule that will release it
+            if completed_tx.status == TransactionStatus::Scheduled {
+                self.db.set_completed_transaction_scheduled(tx_id, false).await?;
+                completed_tx.status = TransactionStatus::Completed;
+            }
+            self.db.remove_scheduled_transaction(tx_id).await?;
+            info!(
+                target: LOG_TARGET,
+                "Scheduled Transaction (TxId: {}) is due to be broadcast", tx_id
+            );
+        }
+
         if !(completed_tx.status == TransactionStatus::Completed ||
             completed_tx.status == TransactionStatus::Broadcast ||
             completed_tx.status == TransactionStatus::MinedUnconfirmed) ||
@@ -1673,6 +2397,43 @@ where
         Ok(())
     }
 
+    /// Release the scheduled transactions of which the schedule is due for broadcast. Schedules of which the
+    /// transaction was cancelled or never created are removed.
+    async fn broadcast_due_scheduled_transactions(
+        &mut self,
+        join_handles: &mut FuturesUnordered<JoinHandle<Result<u64, TransactionServiceProtocolError>>>,
+    ) -> Result<(), TransactionServiceError> {
+        let now = Utc::now().naive_utc();
+        let scheduled_txs = self.db.get_scheduled_transactions().await?;
+        for scheduled_tx in scheduled_txs
+            .into_iter()
+            .filter(|s| s.schedule.is_due(self.last_seen_tip_height, now))
+        {
+            let tx_id = scheduled_tx.tx_id;
+            match self.db.get_any_transaction(tx_id).await? {
+                // Still being negotiated, it will be broadcast as soon as it is completed
+                Some(WalletTransaction::PendingOutbound(_)) | Some(WalletTransaction::PendingInbound(_)) => (),
+                Some(WalletTransaction::Completed(tx)) if !tx.cancelled => {
+                    if let Err(e) = self.broadcast_completed_transaction(tx, join_handles).await {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Could not broadcast scheduled Transaction (TxId: {}): {:?}", tx_id, e
+                        );
+                    }
+                },
+                _ => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Removing schedule of cancelled or missing Transaction (TxId: {})", tx_id
+                    );
+                    self.db.remove_scheduled_transaction(tx_id).await?;
+                },
+            }
+        }
+
+        Ok(())
+    }
+
     /// Go through all completed transactions that have not yet been broadcast and broadcast all of them to the base
     /// node.
     async fn broadcast_all_completed_transactions(
@@ -1773,10 +2534,17 @@ where
                 value,
                 source_public_key,
                 self.node_identity.public_key().clone(),
-                message,
+                message.clone(),
                 maturity,
             )
             .await?;
+        let _ = self.match_imported_payment(tx_id, value, &message).await.map_err(|e| {
+            warn!(
+                target: LOG_TARGET,
+                "Error matching TxId: {} to a payment request: {:?}", tx_id, e
+            );
+            e
+        });
         let _ = self
             .event_publisher
             .send(Arc::new(TransactionEvent::TransactionImported(tx_id)))
@@ -1847,6 +2615,1021 @@ where
         Ok(())
     }
 
+    /// Select and encumber the inputs of a transaction that will be signed by an offline signer. The returned package
+    /// contains no private keys.
+    pub async fn prepare_offline_transaction(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        one_sided: bool,
+    ) -> Result<UnsignedTransaction, TransactionServiceError> {
+        if one_sided && self.node_identity.public_key() == &dest_pubkey {
+            warn!(target: LOG_TARGET, "One-sided spend-to-self transactions not supported");
+            return Err(TransactionServiceError::OneSidedTransactionError(
+                "One-sided spend-to-self transactions not supported".to_string(),
+            ));
+        }
+
+        let tx_id = OsRng.next_u64();
+        let (inputs, change_key_index) = self
+            .output_manager_service
+            .prepare_offline_transaction(tx_id, amount, fee_per_gram)
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Prepared offline transaction (TxId: {}) spending {} inputs",
+            tx_id,
+            inputs.len()
+        );
+
+        Ok(UnsignedTransaction {
+            tx_id,
+            destination_public_key: dest_pubkey,
+            amount,
+            fee_per_gram,
+            lock_height: None,
+            message,
+            one_sided,
+            inputs,
+            change_key_index,
+        })
+    }
+
+    /// Sign a transaction prepared by an online wallet. This is called on the offline signer. A one-sided transaction
+    /// is finalized straight away. For an interactive transaction the sender protocol is stored until the recipient's
+    /// reply is provided to `finalize_offline_transaction`.
+    /// Build an offline transaction without keeping any signing state, to report the fee and change it would have
+    pub async fn summarize_offline_transaction(
+        &mut self,
+        unsigned: UnsignedTransaction,
+    ) -> Result<OfflineTransactionSummary, TransactionServiceError> {
+        let tx_id = unsigned.tx_id;
+        let stp = self
+            .output_manager_service
+            .sign_offline_transaction(unsigned.clone())
+            .await?;
+        let fee = stp
+            .get_fee_amount()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        let change = stp
+            .get_change_amount()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+
+        Ok(OfflineTransactionSummary {
+            tx_id,
+            destination_public_key: unsigned.destination_public_key,
+            amount: unsigned.amount,
+            fee,
+            change,
+            total_inputs: unsigned.inputs.iter().map(|i| i.value).sum(),
+            lock_height: unsigned.lock_height,
+            one_sided: unsigned.one_sided,
+            message: unsigned.message,
+        })
+    }
+
+    pub async fn sign_offline_transaction(
+        &mut self,
+        unsigned: UnsignedTransaction,
+    ) -> Result<OfflineSigningOutcome, TransactionServiceError> {
+        let tx_id = unsigned.tx_id;
+        let mut stp = self
+            .output_manager_service
+            .sign_offline_transaction(unsigned.clone())
+            .await?;
+        let sender_message = stp
+            .build_single_round_message()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+
+        if !unsigned.one_sided {
+            let sender_message = OfflineSenderMessage {
+                tx_id,
+                destination_public_key: unsigned.destination_public_key,
+                amount: unsigned.amount,
+                message: unsigned.message,
+                change_key_index: unsigned.change_key_index,
+                sender_message,
+            };
+            let state = OfflineSigningState {
+                sender_message: sender_message.clone(),
+                protocol: stp,
+            };
+            self.wallet_db
+                .set_client_key_value(
+                    signing_state_key(tx_id),
+                    serde_json::to_string(&state).map_err(OfflineSigningError::from)?,
+                )
+                .await?;
+            info!(
+                target: LOG_TARGET,
+                "Offline transaction (TxId: {}) signed, waiting for the recipient's reply", tx_id
+            );
+            return Ok(OfflineSigningOutcome::AwaitingReply(Box::new(sender_message)));
+        }
+
+        self.finalize_one_sided_transaction(tx_id, &mut stp, &unsigned.destination_public_key)?;
+        let fee = stp
+            .get_fee_amount()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        let transaction = stp
+            .take_transaction()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+
+        Ok(OfflineSigningOutcome::Signed(Box::new(SignedTransaction {
+            tx_id,
+            destination_public_key: unsigned.destination_public_key,
+            amount: unsigned.amount,
+            fee,
+            message: unsigned.message,
+            one_sided: true,
+            change_key_index: unsigned.change_key_index,
+            transaction,
+        })))
+    }
+
+    /// Send the sender message of an interactive offline transaction to the recipient. The recipient's reply will be
+    /// stored until it is exported for the offline signer.
+    pub async fn relay_offline_sender_message(
+        &mut self,
+        sender_message: OfflineSenderMessage,
+    ) -> Result<(), TransactionServiceError> {
+        let tx_id = sender_message.tx_id;
+        // Keep a reply that has already been received if the message is being relayed again
+        let record = match self.get_offline_relay_record(tx_id).await? {
+            Some(record) if record.destination_public_key == sender_message.destination_public_key => record,
+            _ => OfflineRelayRecord {
+                destination_public_key: sender_message.destination_public_key.clone(),
+                reply: None,
+            },
+        };
+        self.set_offline_relay_record(tx_id, &record).await?;
+
+        tokio::spawn(send_transaction_sender_message(
+            tx_id,
+            sender_message.sender_message,
+            sender_message.destination_public_key,
+            self.resources.outbound_message_service.clone(),
+            self.resources.config.direct_send_timeout,
+            self.resources.config.transaction_routing_mechanism,
+        ));
+        info!(
+            target: LOG_TARGET,
+            "Relaying offline transaction (TxId: {}) to the recipient", tx_id
+        );
+        Ok(())
+    }
+
+    /// Store a recipient's reply for an interactive offline transaction
+    async fn accept_offline_recipient_reply(
+        &mut self,
+        source_pubkey: CommsPublicKey,
+        recipient_reply: RecipientSignedMessage,
+    ) -> Result<(), TransactionServiceError> {
+        let tx_id = recipient_reply.tx_id;
+        let mut record = match self.get_offline_relay_record(tx_id).await? {
+            None => return Err(TransactionServiceError::TransactionDoesNotExistError),
+            Some(r) => r,
+        };
+        if record.destination_public_key != source_pubkey {
+            return Err(TransactionServiceError::InvalidSourcePublicKey);
+        }
+        if record.reply.is_some() {
+            trace!(
+                target: LOG_TARGET,
+                "A repeated Transaction Reply (TxId: {}) has been received for an offline transaction. Ignoring.",
+                tx_id
+            );
+            return Ok(());
+        }
+
+        record.reply = Some(recipient_reply);
+        self.set_offline_relay_record(tx_id, &record).await?;
+        info!(
+            target: LOG_TARGET,
+            "Received the recipient's reply for offline transaction (TxId: {})", tx_id
+        );
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::ReceivedTransactionReply(tx_id)))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+        Ok(())
+    }
+
+    /// Get the recipient's reply to an interactive offline transaction so that it can be passed to the offline signer
+    pub async fn get_offline_recipient_reply(
+        &self,
+        tx_id: TxId,
+    ) -> Result<OfflineRecipientReply, TransactionServiceError> {
+        let record = self
+            .get_offline_relay_record(tx_id)
+            .await?
+            .ok_or(OfflineSigningError::RelayRecordNotFound(tx_id))?;
+        let reply = record.reply.ok_or(OfflineSigningError::ReplyNotReceived(tx_id))?;
+        Ok(OfflineRecipientReply { tx_id, reply })
+    }
+
+    /// Finalize an interactive offline transaction with the recipient's reply. This is called on the offline signer.
+    /// The stored sender protocol is removed once the transaction is finalized.
+    pub async fn finalize_offline_transaction(
+        &mut self,
+        reply: OfflineRecipientReply,
+    ) -> Result<SignedTransaction, TransactionServiceError> {
+        let tx_id = reply.tx_id;
+        let state = self
+            .wallet_db
+            .get_client_key_value(signing_state_key(tx_id))
+            .await?
+            .ok_or(OfflineSigningError::SigningStateNotFound(tx_id))?;
+        let OfflineSigningState {
+            sender_message,
+            protocol: mut stp,
+        } = serde_json::from_str(&state).map_err(OfflineSigningError::from)?;
+
+        stp.add_single_recipient_info(reply.reply, &self.resources.factories.range_proof)
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        stp.finalize(KernelFeatures::empty(), &self.resources.factories)
+            .map_err(|e| {
+                error!(
+                    target: LOG_TARGET,
+                    "Offline transaction (TxId: {}) could not be finalized. Failure error: {:?}", tx_id, e,
+                );
+                TransactionServiceProtocolError::new(tx_id, e.into())
+            })?;
+        let fee = stp
+            .get_fee_amount()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+        let transaction = stp
+            .take_transaction()
+            .map_err(|e| TransactionServiceProtocolError::new(tx_id, e.into()))?;
+
+        self.wallet_db.clear_client_value(signing_state_key(tx_id)).await?;
+        info!(target: LOG_TARGET, "Finalized offline transaction TxId: {}", tx_id);
+
+        Ok(SignedTransaction {
+            tx_id,
+            destination_public_key: sender_message.destination_public_key,
+            amount: sender_message.amount,
+            fee,
+            message: sender_message.message,
+            one_sided: false,
+            change_key_index: sender_message.change_key_index,
+            transaction,
+        })
+    }
+
+    /// Import a signed offline transaction, track its change output and start broadcasting it. The recipient of an
+    /// interactive transaction is sent the finalized transaction.
+    pub async fn submit_offline_transaction(
+        &mut self,
+        signed: SignedTransaction,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(), TransactionServiceError> {
+        let tx_id = signed.tx_id;
+        // A repeated submission must not release the inputs of a transaction that is already being broadcast
+        if self.db.transaction_exists(tx_id).await? {
+            return Err(TransactionStorageError::TransactionAlreadyExists.into());
+        }
+        let destination_public_key = signed.destination_public_key.clone();
+        let finalized_transaction = if signed.one_sided {
+            None
+        } else {
+            Some(signed.transaction.clone())
+        };
+
+        // The inputs were fully encumbered when the transaction was prepared, so they must be released if the
+        // transaction cannot be submitted or they would stay locked until the transaction is cancelled manually
+        if let Err(e) = self
+            .confirm_and_submit_offline_transaction(signed, transaction_broadcast_join_handles)
+            .await
+        {
+            warn!(
+                target: LOG_TARGET,
+                "Could not submit offline transaction TxId: {}, releasing its inputs: {}", tx_id, e
+            );
+            if let Err(cancel_err) = self.output_manager_service.cancel_transaction(tx_id).await {
+                error!(
+                    target: LOG_TARGET,
+                    "Could not release the inputs of offline transaction TxId: {}: {}", tx_id, cancel_err
+                );
+            }
+            return Err(e);
+        }
+
+        if let Some(transaction) = finalized_transaction {
+            tokio::spawn(send_finalized_transaction_message(
+                tx_id,
+                transaction,
+                destination_public_key,
+                self.resources.outbound_message_service.clone(),
+                self.resources.config.direct_send_timeout,
+                self.resources.config.transaction_routing_mechanism,
+            ));
+            self.wallet_db.clear_client_value(relay_key(tx_id)).await?;
+        }
+
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(tx_id)));
+        info!(target: LOG_TARGET, "Submitted offline transaction TxId: {}", tx_id);
+        Ok(())
+    }
+
+    async fn confirm_and_submit_offline_transaction(
+        &mut self,
+        signed: SignedTransaction,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(), TransactionServiceError> {
+        let tx_id = signed.tx_id;
+        self.output_manager_service
+            .confirm_offline_transaction(tx_id, signed.transaction.clone(), signed.change_key_index)
+            .await?;
+
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                tx_id,
+                self.resources.node_identity.public_key().clone(),
+                signed.destination_public_key,
+                signed.amount,
+                signed.fee,
+                signed.transaction,
+                TransactionStatus::Completed,
+                signed.message,
+                Utc::now().naive_utc(),
+                TransactionDirection::Outbound,
+                None,
+            ),
+        )
+        .await
+    }
+
+    /// Release the encumbered inputs of an offline transaction and discard any state stored for it
+    async fn cancel_offline_transaction(&mut self, tx_id: TxId) -> Result<(), TransactionServiceError> {
+        let cleared_signing_state = self.wallet_db.clear_client_value(signing_state_key(tx_id)).await?;
+        let cleared_relay_record = self.wallet_db.clear_client_value(relay_key(tx_id)).await?;
+
+        // The offline signer does not encumber any outputs so there is nothing to cancel in the output manager
+        match self.output_manager_service.cancel_transaction(tx_id).await {
+            Ok(_) => {},
+            Err(OutputManagerError::OutputManagerStorageError(OutputManagerStorageError::ValueNotFound))
+                if cleared_signing_state || cleared_relay_record => {},
+            Err(e) => return Err(e.into()),
+        }
+
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionCancelled(tx_id)));
+        info!(target: LOG_TARGET, "Cancelled offline transaction TxId: {}", tx_id);
+        Ok(())
+    }
+
+    async fn get_offline_relay_record(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<OfflineRelayRecord>, TransactionServiceError> {
+        match self.wallet_db.get_client_key_value(relay_key(tx_id)).await? {
+            None => Ok(None),
+            Some(value) => Ok(Some(serde_json::from_str(&value).map_err(OfflineSigningError::from)?)),
+        }
+    }
+
+    async fn set_offline_relay_record(
+        &self,
+        tx_id: TxId,
+        record: &OfflineRelayRecord,
+    ) -> Result<(), TransactionServiceError> {
+        self.wallet_db
+            .set_client_key_value(
+                relay_key(tx_id),
+                serde_json::to_string(record).map_err(OfflineSigningError::from)?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Create a new key share, derived from the script key chain of this wallet, with which this wallet can
+    /// participate in a multisig wallet
+    async fn create_multisig_participant(&mut self) -> Result<MultisigParticipant, TransactionServiceError> {
+        let (index, private_key) = self.output_manager_service.get_next_script_key().await?;
+        let public_key = PublicKey::from_secret_key(&private_key);
+        self.set_multisig_value(share_key(&public_key), &index).await?;
+        Ok(MultisigParticipant {
+            address: self.node_identity.public_key().clone(),
+            public_key,
+        })
+    }
+
+    async fn create_multisig_wallet(
+        &mut self,
+        threshold: usize,
+        participants: Vec<MultisigParticipant>,
+    ) -> Result<MultisigWallet, TransactionServiceError> {
+        let wallet = MultisigWallet::new(threshold, participants)?;
+        self.import_multisig_wallet(wallet.clone()).await?;
+        info!(
+            target: LOG_TARGET,
+            "Created {}-of-{} multisig wallet {}",
+            wallet.threshold,
+            wallet.participants.len(),
+            wallet.id
+        );
+        Ok(wallet)
+    }
+
+    /// Store a multisig wallet of which one of the key shares of this wallet is a participant. Outputs that are already
+    /// known for the wallet are kept.
+    async fn import_multisig_wallet(&mut self, wallet: MultisigWallet) -> Result<(), TransactionServiceError> {
+        wallet.validate()?;
+        let mut share = None;
+        for participant in &wallet.participants {
+            if let Some(index) = self
+                .get_multisig_value::<u64>(share_key(&participant.public_key))
+                .await?
+            {
+                share = Some((participant.public_key.clone(), index));
+                break;
+            }
+        }
+        let (share_public_key, share_key_index) = share.ok_or(MultisigError::NotAParticipant)?;
+        let outputs = match self
+            .get_multisig_value::<MultisigWalletRecord>(wallet_key(wallet.id))
+            .await?
+        {
+            Some(record) => record.outputs,
+            None => Vec::new(),
+        };
+        let record = MultisigWalletRecord {
+            wallet,
+            share_public_key,
+            share_key_index,
+            outputs,
+        };
+        self.set_multisig_value(wallet_key(record.wallet.id), &record).await
+    }
+
+    async fn get_multisig_outputs(
+        &self,
+        wallet_id: MultisigWalletId,
+    ) -> Result<Vec<MultisigOutput>, TransactionServiceError> {
+        Ok(self.get_multisig_record(wallet_id).await?.outputs)
+    }
+
+    /// Send a one-sided payment from this wallet to a multisig wallet. The returned update lets the other participants
+    /// discover the new output.
+    async fn send_to_multisig(
+        &mut self,
+        wallet_id: MultisigWalletId,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<MultisigUpdate, TransactionServiceError> {
+        let mut record = self.get_multisig_record(wallet_id).await?;
+        let script = record.wallet.script()?;
+        let (tx_id, tx, _) = self
+            .send_one_sided_to_script(
+                record.wallet.view_public_key(),
+                script.clone(),
+                amount,
+                fee_per_gram,
+                message,
+                transaction_broadcast_join_handles,
+            )
+            .await?;
+
+        let update = MultisigUpdate {
+            wallet_id,
+            tx_id,
+            spent: Vec::new(),
+            outputs: tx
+                .body
+                .outputs()
+                .iter()
+                .filter(|o| o.script == script)
+                .cloned()
+                .collect(),
+        };
+        record.apply_update(&update, &self.resources.factories)?;
+        self.set_multisig_value(wallet_key(wallet_id), &record).await?;
+        Ok(update)
+    }
+
+    async fn import_multisig_update(&mut self, update: MultisigUpdate) -> Result<usize, TransactionServiceError> {
+        let mut record = self.get_multisig_record(update.wallet_id).await?;
+        let added = record.apply_update(&update, &self.resources.factories)?;
+        self.set_multisig_value(wallet_key(update.wallet_id), &record).await?;
+        info!(
+            target: LOG_TARGET,
+            "Imported update (TxId: {}) adding {} outputs to multisig wallet {}", update.tx_id, added, update.wallet_id
+        );
+        Ok(added)
+    }
+
+    /// Start a signing session as the coordinator of a payment from a multisig wallet. The selected inputs are
+    /// encumbered until the session completes or is cancelled.
+    async fn propose_multisig_spend(
+        &mut self,
+        wallet_id: MultisigWalletId,
+        signers: Vec<PublicKey>,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        message: String,
+    ) -> Result<MultisigSpendProposal, TransactionServiceError> {
+        let mut record = self.get_multisig_record(wallet_id).await?;
+        let (session, proposal) = MultisigCoordinatorSession::new(
+            &record.wallet,
+            &record.outputs,
+            &signers,
+            dest_pubkey,
+            amount,
+            fee_per_gram,
+            message,
+            &self.resources.factories,
+        )?;
+        for output in record.outputs.iter_mut() {
+            if proposal.inputs.iter().any(|i| i.commitment == output.output.commitment) {
+                output.status = MultisigOutputStatus::PendingSpend(proposal.session_id);
+            }
+        }
+        self.set_multisig_value(coordinator_key(proposal.session_id), &session)
+            .await?;
+        self.set_multisig_value(wallet_key(wallet_id), &record).await?;
+        info!(
+            target: LOG_TARGET,
+            "Proposed spend (TxId: {}) from multisig wallet {}", proposal.session_id, wallet_id
+        );
+        Ok(proposal)
+    }
+
+    /// Commit to the nonces of this wallet for a spend proposal. Only one signing session is kept per multisig wallet,
+    /// so committing to a new proposal abandons the nonces of any previous one.
+    async fn commit_multisig_nonces(
+        &mut self,
+        proposal: MultisigSpendProposal,
+    ) -> Result<MultisigNonceCommitment, TransactionServiceError> {
+        let record = self.get_multisig_record(proposal.wallet_id).await?;
+        let wallet_id = proposal.wallet_id;
+        let (session, commitment) = MultisigSignerSession::new(
+            &record.wallet,
+            record.share_public_key.clone(),
+            proposal,
+            &self.resources.factories,
+        )?;
+        self.set_multisig_value(signer_key(wallet_id), &session).await?;
+        Ok(commitment)
+    }
+
+    async fn add_multisig_nonce_commitment(
+        &mut self,
+        commitment: MultisigNonceCommitment,
+    ) -> Result<Option<MultisigNonceRequest>, TransactionServiceError> {
+        let session_id = commitment.session_id;
+        let mut session = self.get_multisig_coordinator_session(session_id).await?;
+        let request = session.add_nonce_commitment(commitment)?;
+        self.set_multisig_value(coordinator_key(session_id), &session).await?;
+        Ok(request)
+    }
+
+    /// Reveal the nonces of this wallet once the commitments of all signers are known. The commitments are stored with
+    /// the signing session so that the signing request can be checked against them.
+    async fn reveal_multisig_nonces(
+        &mut self,
+        request: MultisigNonceRequest,
+    ) -> Result<MultisigNonces, TransactionServiceError> {
+        let mut session = self
+            .get_multisig_value::<MultisigSignerSession>(signer_key(request.wallet_id))
+            .await?
+            .filter(|s| s.proposal().session_id == request.session_id)
+            .ok_or(MultisigError::SessionNotFound(request.session_id))?;
+        let nonces = session.reveal_nonces(&request)?;
+        self.set_multisig_value(signer_key(request.wallet_id), &session).await?;
+        Ok(nonces)
+    }
+
+    async fn add_multisig_nonces(
+        &mut self,
+        nonces: MultisigNonces,
+    ) -> Result<Option<MultisigSigningRequest>, TransactionServiceError> {
+        let session_id = nonces.session_id;
+        let mut session = self.get_multisig_coordinator_session(session_id).await?;
+        let request = session.add_nonces(nonces, &self.resources.factories)?;
+        self.set_multisig_value(coordinator_key(session_id), &session).await?;
+        Ok(request)
+    }
+
+    /// Produce the partial signatures of this wallet for a signing request. The signing session is discarded before
+    /// signing so that its nonces can never be used twice.
+    async fn sign_multisig_request(
+        &mut self,
+        request: MultisigSigningRequest,
+    ) -> Result<MultisigPartialSignatures, TransactionServiceError> {
+        let record = self.get_multisig_record(request.wallet_id).await?;
+        let session = self
+            .get_multisig_value::<MultisigSignerSession>(signer_key(request.wallet_id))
+            .await?
+            .filter(|s| s.proposal().session_id == request.session_id)
+            .ok_or(MultisigError::SessionNotFound(request.session_id))?;
+        self.wallet_db.clear_client_value(signer_key(request.wallet_id)).await?;
+
+        let share_private_key = self
+            .output_manager_service
+            .get_script_key_at_index(record.share_key_index)
+            .await?;
+        let partial_signatures =
+            session.sign(&record.wallet, &share_private_key, &request, &self.resources.factories)?;
+        Ok(partial_signatures)
+    }
+
+    /// Add the partial signatures of a signer. Once all the signers have signed, the transaction is submitted and the
+    /// update for the other participants is returned.
+    async fn add_multisig_partial_signatures(
+        &mut self,
+        partial_signatures: MultisigPartialSignatures,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<Option<MultisigUpdate>, TransactionServiceError> {
+        let session_id = partial_signatures.session_id;
+        let mut session = self.get_multisig_coordinator_session(session_id).await?;
+        let proposal = session.proposal().clone();
+        let mut record = self.get_multisig_record(proposal.wallet_id).await?;
+        let tx = match session.add_partial_signatures(&record.wallet, partial_signatures, &self.resources.factories)? {
+            Some(tx) => tx,
+            None => {
+                self.set_multisig_value(coordinator_key(session_id), &session).await?;
+                return Ok(None);
+            },
+        };
+
+        let script = record.wallet.script()?;
+        let update = MultisigUpdate {
+            wallet_id: proposal.wallet_id,
+            tx_id: session_id,
+            spent: proposal.inputs.iter().map(|i| i.commitment.clone()).collect(),
+            outputs: tx
+                .body
+                .outputs()
+                .iter()
+                .filter(|o| o.script == script)
+                .cloned()
+                .collect(),
+        };
+        record.apply_update(&update, &self.resources.factories)?;
+        self.set_multisig_value(wallet_key(proposal.wallet_id), &record).await?;
+        self.wallet_db.clear_client_value(coordinator_key(session_id)).await?;
+
+        let recipient = &proposal.recipients[0];
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                session_id,
+                self.resources.node_identity.public_key().clone(),
+                recipient.public_key.clone(),
+                recipient.value,
+                proposal.fee,
+                tx,
+                TransactionStatus::Completed,
+                proposal.message.clone(),
+                Utc::now().naive_utc(),
+                TransactionDirection::Outbound,
+                None,
+            ),
+        )
+        .await?;
+
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::TransactionCompletedImmediately(session_id)));
+        info!(
+            target: LOG_TARGET,
+            "Submitted multisig transaction TxId: {} from wallet {}", session_id, proposal.wallet_id
+        );
+        Ok(Some(update))
+    }
+
+    /// Abandon a signing session as the coordinator and release the inputs it encumbered
+    async fn cancel_multisig_spend(&mut self, session_id: TxId) -> Result<(), TransactionServiceError> {
+        let session = self.get_multisig_coordinator_session(session_id).await?;
+        let wallet_id = session.proposal().wallet_id;
+        let mut record = self.get_multisig_record(wallet_id).await?;
+        for output in record.outputs.iter_mut() {
+            if output.status == MultisigOutputStatus::PendingSpend(session_id) {
+                output.status = MultisigOutputStatus::Unspent;
+            }
+        }
+        self.set_multisig_value(wallet_key(wallet_id), &record).await?;
+        self.wallet_db.clear_client_value(coordinator_key(session_id)).await?;
+        info!(target: LOG_TARGET, "Cancelled multisig spend TxId: {}", session_id);
+        Ok(())
+    }
+
+    async fn get_multisig_record(
+        &self,
+        wallet_id: MultisigWalletId,
+    ) -> Result<MultisigWalletRecord, TransactionServiceError> {
+        self.get_multisig_value(wallet_key(wallet_id))
+            .await?
+            .ok_or_else(|| MultisigError::WalletNotFound(wallet_id).into())
+    }
+
+    async fn get_multisig_coordinator_session(
+        &self,
+        session_id: TxId,
+    ) -> Result<MultisigCoordinatorSession, TransactionServiceError> {
+        self.get_multisig_value(coordinator_key(session_id))
+            .await?
+            .ok_or_else(|| MultisigError::SessionNotFound(session_id).into())
+    }
+
+    async fn get_multisig_value<T: DeserializeOwned>(&self, key: String) -> Result<Option<T>, TransactionServiceError> {
+        match self.wallet_db.get_client_key_value(key).await? {
+            None => Ok(None),
+            Some(value) => Ok(Some(serde_json::from_str(&value).map_err(MultisigError::from)?)),
+        }
+    }
+
+    async fn set_multisig_value<T: Serialize>(&self, key: String, value: &T) -> Result<(), TransactionServiceError> {
+        self.wallet_db
+            .set_client_key_value(key, serde_json::to_string(value).map_err(MultisigError::from)?)
+            .await?;
+        Ok(())
+    }
+
+    /// Send an HTLC output to `dest_pubkey`. The output is sent as a one-sided payment locked with the HTLC script,
+    /// and recorded in the output manager so that it can be refunded after the timeout.
+    #[allow(clippy::too_many_arguments)]
+    async fn send_htlc(
+        &mut self,
+        dest_pubkey: CommsPublicKey,
+        amount: MicroTari,
+        fee_per_gram: MicroTari,
+        hash_lock: [u8; 32],
+        timeout_height: u64,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(TxId, HtlcDetails), TransactionServiceError> {
+        if self.node_identity.public_key() == &dest_pubkey {
+            return Err(TransactionServiceError::OneSidedTransactionError(
+                "HTLCs cannot be sent to this wallet".to_string(),
+            ));
+        }
+        let tip_height = self
+            .last_seen_tip_height
+            .ok_or(TransactionServiceError::ChainTipUnknown)?;
+        if timeout_height <= tip_height + 1 {
+            return Err(OutputManagerError::from(HtlcError::TimeoutPassed(timeout_height)).into());
+        }
+
+        let sender = self.node_identity.public_key().clone();
+        let script = htlc::htlc_script(&hash_lock, timeout_height, &dest_pubkey, &sender);
+        let (tx_id, tx, spending_key) = self
+            .send_one_sided_to_script(
+                dest_pubkey.clone(),
+                script.clone(),
+                amount,
+                fee_per_gram,
+                message,
+                transaction_broadcast_join_handles,
+            )
+            .await?;
+        let output = tx.body.outputs().iter().find(|o| o.script == script).ok_or_else(|| {
+            TransactionServiceError::OneSidedTransactionError("HTLC output not found in transaction".to_string())
+        })?;
+
+        let htlc = HtlcOutput {
+            commitment: output.commitment.clone(),
+            hash: output.hash(),
+            tx_id,
+            role: HtlcRole::Sender,
+            status: HtlcStatus::Unconfirmed,
+            unblinded_output: UnblindedOutput::new(
+                amount,
+                spending_key,
+                output.features.clone(),
+                script,
+                htlc::refund_input_data(),
+                self.node_identity.secret_key().clone(),
+                output.sender_offset_public_key.clone(),
+                output.metadata_signature.clone(),
+            ),
+            hash_lock: hash_lock.to_vec(),
+            preimage: None,
+            timeout_height,
+            counterparty: dest_pubkey,
+            fee_per_gram,
+            mined_height: None,
+            mined_mmr_position: None,
+            spent_in_tx_id: None,
+        };
+        let details = HtlcDetails::from_sent_htlc(&htlc, sender).map_err(OutputManagerError::from)?;
+        self.output_manager_service.add_htlc(htlc).await?;
+        info!(
+            target: LOG_TARGET,
+            "Sent HTLC output {} (TxId: {}) with timeout height {}",
+            details.commitment.to_hex(),
+            tx_id,
+            timeout_height
+        );
+        Ok((tx_id, details))
+    }
+
+    async fn import_htlc(
+        &mut self,
+        details: HtlcDetails,
+        fee_per_gram: MicroTari,
+    ) -> Result<HtlcOutput, TransactionServiceError> {
+        let htlc = self
+            .output_manager_service
+            .import_htlc(
+                details,
+                self.node_identity.secret_key().clone(),
+                OsRng.next_u64(),
+                fee_per_gram,
+            )
+            .await?;
+        Ok(htlc)
+    }
+
+    /// Claim or refund an HTLC output and broadcast the spending transaction
+    async fn spend_htlc(
+        &mut self,
+        commitment: Commitment,
+        message: String,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<TxId, TransactionServiceError> {
+        let tip_height = self
+            .last_seen_tip_height
+            .ok_or(TransactionServiceError::ChainTipUnknown)?;
+        let (tx_id, tx, fee, amount) = self
+            .output_manager_service
+            .create_htlc_spend_transaction(commitment, tip_height)
+            .await?;
+        self.submit_transaction(
+            transaction_broadcast_join_handles,
+            CompletedTransaction::new(
+                tx_id,
+                self.node_identity.public_key().clone(),
+                self.node_identity.public_key().clone(),
+                amount,
+                fee,
+                tx,
+                TransactionStatus::Completed,
+                message,
+                Utc::now().naive_utc(),
+                TransactionDirection::Inbound,
+                None,
+            ),
+        )
+        .await?;
+        Ok(tx_id)
+    }
+
+    /// Claim the mined HTLCs sent to this wallet of which the preimage is known, and refund the mined HTLCs sent by
+    /// this wallet that have timed out
+    async fn spend_due_htlcs(
+        &mut self,
+        tip_height: u64,
+        transaction_broadcast_join_handles: &mut FuturesUnordered<
+            JoinHandle<Result<u64, TransactionServiceProtocolError>>,
+        >,
+    ) -> Result<(), TransactionServiceError> {
+        let htlcs = self.output_manager_service.get_htlcs().await?;
+        let spend_height = tip_height + 1;
+        for htlc in htlcs.into_iter().filter(|h| h.status == HtlcStatus::Unspent) {
+            let (due, message) = match htlc.role {
+                HtlcRole::Recipient => (
+                    htlc.preimage.is_some() && spend_height < htlc.timeout_height,
+                    "Claimed HTLC",
+                ),
+                HtlcRole::Sender => (spend_height >= htlc.timeout_height, "Refunded HTLC"),
+            };
+            if !due {
+                continue;
+            }
+            match self
+                .spend_htlc(
+                    htlc.commitment.clone(),
+                    message.to_string(),
+                    transaction_broadcast_join_handles,
+                )
+                .await
+            {
+                Ok(tx_id) => info!(
+                    target: LOG_TARGET,
+                    "{} {} in TxId: {}",
+                    message,
+                    htlc.commitment.to_hex(),
+                    tx_id
+                ),
+                Err(e) => warn!(
+                    target: LOG_TARGET,
+                    "Could not spend HTLC {}: {:?}",
+                    htlc.commitment.to_hex(),
+                    e
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_payment_request(
+        &mut self,
+        amount: MicroTari,
+        message: String,
+        expiry_height: u64,
+    ) -> Result<PaymentRequest, TransactionServiceError> {
+        if let Some(tip_height) = self.last_seen_tip_height {
+            if tip_height >= expiry_height {
+                return Err(PaymentRequestError::Expired(expiry_height).into());
+            }
+        }
+        let request = PaymentRequest::new(&self.node_identity, amount, message, expiry_height)?;
+        self.db
+            .add_payment_request(PaymentRequestRecord::new(request.clone()))
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Created payment request {} for {} expiring at height {}",
+            request.reference_hex(),
+            amount,
+            expiry_height
+        );
+        Ok(request)
+    }
+
+    /// Match an imported output to an open payment request. Imported outputs are already mined, and as one-sided
+    /// payments do not carry the sender's message they may also be matched by the amount outstanding. Transactions
+    /// received from a sender are matched once they are mined, by the validation protocol.
+    async fn match_imported_payment(
+        &mut self,
+        tx_id: TxId,
+        amount: MicroTari,
+        message: &str,
+    ) -> Result<(), TransactionServiceError> {
+        let record =
+            match match_payment_request(&self.db, tx_id, amount, message, self.last_seen_tip_height, true).await? {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+        info!(
+            target: LOG_TARGET,
+            "TxId: {} paid {} of payment request {}, which is now {}",
+            tx_id,
+            amount,
+            record.request.reference_hex(),
+            record.status
+        );
+        self.publish_payment_request_updated(record.request.reference);
+        Ok(())
+    }
+
+    async fn expire_payment_requests(&mut self, tip_height: u64) -> Result<(), TransactionServiceError> {
+        for reference in self.db.expire_payment_requests(tip_height).await? {
+            info!(
+                target: LOG_TARGET,
+                "Payment request {} expired at height {}",
+                format_payment_reference(reference),
+                tip_height
+            );
+            self.publish_payment_request_updated(reference);
+        }
+        Ok(())
+    }
+
+    fn publish_payment_request_updated(&self, reference: PaymentReference) {
+        let _ = self
+            .event_publisher
+            .send(Arc::new(TransactionEvent::PaymentRequestUpdated(reference)))
+            .map_err(|e| {
+                trace!(
+                    target: LOG_TARGET,
+                    "Error sending event, usually because there are no subscribers: {:?}",
+                    e
+                );
+                e
+            });
+    }
+
     async fn generate_coinbase_transaction(
         &mut self,
         reward: MicroTari,
@@ -24,10 +24,14 @@ use crate::{
     output_manager_service::TxId,
     transaction_service::{
         error::TransactionStorageError,
+        payment_request::{format_payment_reference, PaymentReference},
         storage::models::{
             CompletedTransaction,
+            HeldTransaction,
             InboundTransaction,
             OutboundTransaction,
+            PaymentRequestRecord,
+            ScheduledTransaction,
             TransactionDirection,
             TransactionStatus,
         },
@@ -130,6 +134,27 @@ pub trait TransactionBackend: Send + Sync + Clone {
 
     /// Clears the mined block and height of a transaction
     fn set_transaction_as_unmined(&self, tx_id: TxId) -> Result<(), TransactionStorageError>;
+
+    /// Move a completed transaction between the `Completed` and `Scheduled` statuses
+    fn set_completed_transaction_scheduled(&self, tx_id: TxId, scheduled: bool) -> Result<(), TransactionStorageError>;
+
+    /// Record a payment received for a payment request and update the status of the request, which is returned
+    fn add_payment_request_payment(
+        &self,
+        reference: PaymentReference,
+        tx_id: TxId,
+        amount: MicroTari,
+    ) -> Result<PaymentRequestRecord, TransactionStorageError>;
+
+    /// Remove the payment made by the transaction from the payment request it was matched to and update the status of
+    /// the request, which is returned. Returns `None` if the transaction was not matched to a request.
+    fn remove_payment_request_payment(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<PaymentRequestRecord>, TransactionStorageError>;
+
+    /// Mark the open payment requests that expire at or below the tip height as expired, returning their references
+    fn expire_payment_requests(&self, tip_height: u64) -> Result<Vec<PaymentReference>, TransactionStorageError>;
 }
 
 #[derive(Clone, PartialEq)]
@@ -146,6 +171,12 @@ pub enum DbKey {
     CancelledPendingOutboundTransaction(TxId),
     CancelledPendingInboundTransaction(TxId),
     AnyTransaction(TxId),
+    ScheduledTransaction(TxId),
+    ScheduledTransactions,
+    PaymentRequest(PaymentReference),
+    PaymentRequests,
+    HeldTransaction(TxId),
+    HeldTransactions,
 }
 
 impl fmt::Debug for DbKey {
@@ -197,6 +228,24 @@ impl fmt::Debug for DbKey {
             AnyTransaction(tx_id) => {
                 write!(f, "AnyTransaction ({}u64, {}i64)", tx_id, *tx_id as i64)
             },
+            ScheduledTransaction(tx_id) => {
+                write!(f, "ScheduledTransaction ({}u64, {}i64)", tx_id, *tx_id as i64)
+            },
+            ScheduledTransactions => {
+                write!(f, "ScheduledTransactions")
+            },
+            PaymentRequest(reference) => {
+                write!(f, "PaymentRequest ({})", format_payment_reference(*reference))
+            },
+            PaymentRequests => {
+                write!(f, "PaymentRequests")
+            },
+            HeldTransaction(tx_id) => {
+                write!(f, "HeldTransaction ({}u64, {}i64)", tx_id, *tx_id as i64)
+            },
+            HeldTransactions => {
+                write!(f, "HeldTransactions")
+            },
         }
     }
 }
@@ -210,12 +259,21 @@ pub enum DbValue {
     PendingInboundTransactions(HashMap<TxId, InboundTransaction>),
     CompletedTransactions(HashMap<TxId, CompletedTransaction>),
     WalletTransaction(Box<WalletTransaction>),
+    ScheduledTransaction(Box<ScheduledTransaction>),
+    ScheduledTransactions(Vec<ScheduledTransaction>),
+    PaymentRequest(Box<PaymentRequestRecord>),
+    PaymentRequests(Vec<PaymentRequestRecord>),
+    HeldTransaction(Box<HeldTransaction>),
+    HeldTransactions(Vec<HeldTransaction>),
 }
 
 pub enum DbKeyValuePair {
     PendingOutboundTransaction(TxId, Box<OutboundTransaction>),
     PendingInboundTransaction(TxId, Box<InboundTransaction>),
     CompletedTransaction(TxId, Box<CompletedTransaction>),
+    ScheduledTransaction(TxId, Box<ScheduledTransaction>),
+    PaymentRequest(PaymentReference, Box<PaymentRequestRecord>),
+    HeldTransaction(TxId, Box<HeldTransaction>),
 }
 
 pub enum WriteOperation {
@@ -237,6 +295,11 @@ where T: TransactionBackend + 'static
         Self { db: Arc::new(db) }
     }
 
+    /// The backend of the database, for the operations that are specific to it
+    pub(crate) fn backend(&self) -> &T {
+        &self.db
+    }
+
     pub async fn add_pending_inbound_transaction(
         &self,
         tx_id: TxId,
@@ -782,6 +845,215 @@ where T: TransactionBackend + 'static
         .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
         Ok(())
     }
+
+    /// Hold a transaction until the provided schedule is due. A transaction that is completed while it is scheduled
+    /// is given the `Scheduled` status instead of being broadcast.
+    pub async fn add_scheduled_transaction(
+        &self,
+        scheduled_transaction: ScheduledTransaction,
+    ) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::ScheduledTransaction(
+                scheduled_transaction.tx_id,
+                Box::new(scheduled_transaction),
+            )))
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_scheduled_transaction(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<ScheduledTransaction>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let key = DbKey::ScheduledTransaction(tx_id);
+        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&key) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::ScheduledTransaction(s))) => Ok(Some(*s)),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    pub async fn get_scheduled_transactions(&self) -> Result<Vec<ScheduledTransaction>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::ScheduledTransactions) {
+            Ok(None) => log_error(
+                DbKey::ScheduledTransactions,
+                TransactionStorageError::UnexpectedResult("Could not retrieve scheduled transactions".to_string()),
+            ),
+            Ok(Some(DbValue::ScheduledTransactions(s))) => Ok(s),
+            Ok(Some(other)) => unexpected_result(DbKey::ScheduledTransactions, other),
+            Err(e) => log_error(DbKey::ScheduledTransactions, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    pub async fn remove_scheduled_transaction(&self, tx_id: TxId) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.write(WriteOperation::Remove(DbKey::ScheduledTransaction(tx_id))))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn set_completed_transaction_scheduled(
+        &self,
+        tx_id: TxId,
+        scheduled: bool,
+    ) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.set_completed_transaction_scheduled(tx_id, scheduled))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn add_payment_request(&self, record: PaymentRequestRecord) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::PaymentRequest(
+                record.request.reference,
+                Box::new(record),
+            )))
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_payment_request(
+        &self,
+        reference: PaymentReference,
+    ) -> Result<Option<PaymentRequestRecord>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let key = DbKey::PaymentRequest(reference);
+        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&key) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::PaymentRequest(r))) => Ok(Some(*r)),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    pub async fn get_payment_requests(&self) -> Result<Vec<PaymentRequestRecord>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::PaymentRequests) {
+            Ok(None) => log_error(
+                DbKey::PaymentRequests,
+                TransactionStorageError::UnexpectedResult("Could not retrieve payment requests".to_string()),
+            ),
+            Ok(Some(DbValue::PaymentRequests(r))) => Ok(r),
+            Ok(Some(other)) => unexpected_result(DbKey::PaymentRequests, other),
+            Err(e) => log_error(DbKey::PaymentRequests, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    pub async fn add_payment_request_payment(
+        &self,
+        reference: PaymentReference,
+        tx_id: TxId,
+        amount: MicroTari,
+    ) -> Result<PaymentRequestRecord, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.add_payment_request_payment(reference, tx_id, amount))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    pub async fn remove_payment_request_payment(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<PaymentRequestRecord>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.remove_payment_request_payment(tx_id))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
+
+    /// Store an inbound transaction that is held for approval, so that it is still held after a restart
+    pub async fn add_held_transaction(&self, held_transaction: HeldTransaction) -> Result<(), TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            db_clone.write(WriteOperation::Insert(DbKeyValuePair::HeldTransaction(
+                held_transaction.transaction.tx_id,
+                Box::new(held_transaction),
+            )))
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+
+    pub async fn get_held_transaction(&self, tx_id: TxId) -> Result<Option<HeldTransaction>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let key = DbKey::HeldTransaction(tx_id);
+        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&key) {
+            Ok(None) => Ok(None),
+            Ok(Some(DbValue::HeldTransaction(h))) => Ok(Some(*h)),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    /// The inbound transactions that are held for approval, oldest first
+    pub async fn get_held_transactions(&self) -> Result<Vec<HeldTransaction>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let t = tokio::task::spawn_blocking(move || match db_clone.fetch(&DbKey::HeldTransactions) {
+            Ok(None) => log_error(
+                DbKey::HeldTransactions,
+                TransactionStorageError::UnexpectedResult("Could not retrieve held transactions".to_string()),
+            ),
+            Ok(Some(DbValue::HeldTransactions(h))) => Ok(h),
+            Ok(Some(other)) => unexpected_result(DbKey::HeldTransactions, other),
+            Err(e) => log_error(DbKey::HeldTransactions, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    /// Remove a held transaction, returning it
+    pub async fn remove_held_transaction(&self, tx_id: TxId) -> Result<HeldTransaction, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        let key = DbKey::HeldTransaction(tx_id);
+        let t = tokio::task::spawn_blocking(move || match db_clone.write(WriteOperation::Remove(key.clone())) {
+            Ok(None) => Err(TransactionStorageError::ValueNotFound(key)),
+            Ok(Some(DbValue::HeldTransaction(h))) => Ok(*h),
+            Ok(Some(other)) => unexpected_result(key, other),
+            Err(e) => log_error(key, e),
+        })
+        .await
+        .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(t)
+    }
+
+    pub async fn expire_payment_requests(
+        &self,
+        tip_height: u64,
+    ) -> Result<Vec<PaymentReference>, TransactionStorageError> {
+        let db_clone = self.db.clone();
+        tokio::task::spawn_blocking(move || db_clone.expire_payment_requests(tip_height))
+            .await
+            .map_err(|err| TransactionStorageError::BlockingTaskSpawnError(err.to_string()))?
+    }
 }
 
 impl Display for DbKey {
@@ -808,6 +1080,12 @@ impl Display for DbKey {
                 f.write_str(&"Cancelled Pending Inbound Transaction".to_string())
             },
             DbKey::AnyTransaction(_) => f.write_str(&"Any Transaction".to_string()),
+            DbKey::ScheduledTransaction(_) => f.write_str(&"Scheduled Transaction".to_string()),
+            DbKey::ScheduledTransactions => f.write_str(&"All Scheduled Transactions".to_string()),
+            DbKey::PaymentRequest(_) => f.write_str(&"Payment Request".to_string()),
+            DbKey::PaymentRequests => f.write_str(&"All Payment Requests".to_string()),
+            DbKey::HeldTransaction(_) => f.write_str(&"Held Transaction".to_string()),
+            DbKey::HeldTransactions => f.write_str(&"All Held Transactions".to_string()),
         }
     }
 }
@@ -822,6 +1100,12 @@ impl Display for DbValue {
             DbValue::PendingInboundTransactions(_) => f.write_str(&"All Pending Inbound Transactions".to_string()),
             DbValue::CompletedTransactions(_) => f.write_str(&"All Complete Transactions".to_string()),
             DbValue::WalletTransaction(_) => f.write_str(&"Any Wallet Transaction".to_string()),
+            DbValue::ScheduledTransaction(_) => f.write_str(&"Scheduled Transaction".to_string()),
+            DbValue::ScheduledTransactions(_) => f.write_str(&"All Scheduled Transactions".to_string()),
+            DbValue::PaymentRequest(_) => f.write_str(&"Payment Request".to_string()),
+            DbValue::PaymentRequests(_) => f.write_str(&"All Payment Requests".to_string()),
+            DbValue::HeldTransaction(_) => f.write_str(&"Held Transaction".to_string()),
+            DbValue::HeldTransactions(_) => f.write_str(&"All Held Transactions".to_string()),
         }
     }
 }
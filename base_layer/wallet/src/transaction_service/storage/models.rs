@@ -20,8 +20,15 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{output_manager_service::TxId, transaction_service::error::TransactionStorageError};
-use chrono::NaiveDateTime;
+use crate::{
+    output_manager_service::TxId,
+    transaction_service::{
+        acceptance_policy::IncomingTransaction,
+        error::TransactionStorageError,
+        payment_request::PaymentRequest,
+    },
+};
+use chrono::{NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{
     convert::TryFrom,
@@ -32,6 +39,7 @@ use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::{
     tari_amount::MicroTari,
     transaction::Transaction,
+    transaction_protocol::sender::TransactionSenderMessage,
     ReceiverTransactionProtocol,
     SenderTransactionProtocol,
 };
@@ -53,6 +61,9 @@ pub enum TransactionStatus {
     Coinbase,
     /// This transaction is mined and confirmed at the current base node's height
     MinedConfirmed,
+    /// This transaction has been completed between the parties and is held by the wallet until its scheduled height
+    /// or time, after which it will be broadcast to the base layer network.
+    Scheduled,
 }
 
 impl TryFrom<i32> for TransactionStatus {
@@ -67,6 +78,7 @@ impl TryFrom<i32> for TransactionStatus {
             4 => Ok(TransactionStatus::Pending),
             5 => Ok(TransactionStatus::Coinbase),
             6 => Ok(TransactionStatus::MinedConfirmed),
+            7 => Ok(TransactionStatus::Scheduled),
             _ => Err(TransactionStorageError::ConversionError(
                 "Invalid TransactionStatus".to_string(),
             )),
@@ -91,6 +103,7 @@ impl Display for TransactionStatus {
             TransactionStatus::Imported => write!(f, "Imported"),
             TransactionStatus::Pending => write!(f, "Pending"),
             TransactionStatus::Coinbase => write!(f, "Coinbase"),
+            TransactionStatus::Scheduled => write!(f, "Scheduled"),
         }
     }
 }
@@ -279,6 +292,136 @@ impl Display for TransactionDirection {
     }
 }
 
+/// The trigger at which a scheduled transaction is released for broadcast
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TransactionSchedule {
+    /// Broadcast the transaction as soon as it can be mined at this block height
+    Height(u64),
+    /// Broadcast the transaction once this time (UTC) has passed
+    Time(NaiveDateTime),
+}
+
+impl TransactionSchedule {
+    /// Returns true if the transaction should be broadcast given the current chain tip and time. A height schedule is
+    /// due once the next block will be at or above the scheduled height.
+    pub fn is_due(&self, tip_height: Option<u64>, now: NaiveDateTime) -> bool {
+        match self {
+            TransactionSchedule::Height(height) => tip_height.map(|tip| tip + 1 >= *height).unwrap_or(false),
+            TransactionSchedule::Time(time) => now >= *time,
+        }
+    }
+
+    /// The kernel lock height that ensures the transaction cannot be mined before its schedule
+    pub fn lock_height(&self) -> Option<u64> {
+        match self {
+            TransactionSchedule::Height(height) => Some(*height),
+            TransactionSchedule::Time(_) => None,
+        }
+    }
+}
+
+impl Display for TransactionSchedule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            TransactionSchedule::Height(height) => write!(f, "at height {}", height),
+            TransactionSchedule::Time(time) => write!(f, "at {}", time),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledTransaction {
+    pub tx_id: TxId,
+    pub schedule: TransactionSchedule,
+}
+
+impl ScheduledTransaction {
+    pub fn new(tx_id: TxId, schedule: TransactionSchedule) -> Self {
+        Self { tx_id, schedule }
+    }
+}
+
+/// An inbound transaction that the acceptance policy is holding for approval. No reply has been sent for it yet.
+#[derive(Debug, Clone)]
+pub struct HeldTransaction {
+    pub transaction: IncomingTransaction,
+    pub sender_message: TransactionSenderMessage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PaymentRequestStatus {
+    /// No payment has been received for the request yet
+    Open,
+    /// Payments for part of the requested amount have been received
+    PartlyPaid,
+    /// Payments for the full requested amount have been received
+    Paid,
+    /// The chain tip reached the expiry height before the request was paid in full
+    Expired,
+}
+
+impl TryFrom<i32> for PaymentRequestStatus {
+    type Error = TransactionStorageError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PaymentRequestStatus::Open),
+            1 => Ok(PaymentRequestStatus::PartlyPaid),
+            2 => Ok(PaymentRequestStatus::Paid),
+            3 => Ok(PaymentRequestStatus::Expired),
+            _ => Err(TransactionStorageError::ConversionError(
+                "Invalid PaymentRequestStatus".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for PaymentRequestStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            PaymentRequestStatus::Open => write!(f, "Open"),
+            PaymentRequestStatus::PartlyPaid => write!(f, "Partly paid"),
+            PaymentRequestStatus::Paid => write!(f, "Paid"),
+            PaymentRequestStatus::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+/// A payment request created by this wallet along with the payments received for it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRequestRecord {
+    pub request: PaymentRequest,
+    pub status: PaymentRequestStatus,
+    pub amount_received: MicroTari,
+    /// The transactions matched to the request
+    pub payments: Vec<TxId>,
+    pub timestamp: NaiveDateTime,
+}
+
+impl PaymentRequestRecord {
+    pub fn new(request: PaymentRequest) -> Self {
+        Self {
+            request,
+            status: PaymentRequestStatus::Open,
+            amount_received: MicroTari::from(0),
+            payments: Vec::new(),
+            timestamp: Utc::now().naive_utc(),
+        }
+    }
+
+    /// Returns true if the request can still be paid
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self.status,
+            PaymentRequestStatus::Open | PaymentRequestStatus::PartlyPaid
+        )
+    }
+
+    pub fn amount_outstanding(&self) -> MicroTari {
+        self.request.amount.saturating_sub(self.amount_received)
+    }
+}
+
 impl From<CompletedTransaction> for InboundTransaction {
     fn from(ct: CompletedTransaction) -> Self {
         Self {
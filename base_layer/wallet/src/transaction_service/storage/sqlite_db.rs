@@ -22,17 +22,32 @@
 
 use crate::{
     output_manager_service::TxId,
-    schema::{completed_transactions, inbound_transactions, outbound_transactions},
+    schema::{
+        completed_transactions,
+        held_transactions,
+        inbound_transactions,
+        outbound_transactions,
+        payment_request_payments,
+        payment_requests,
+        scheduled_transactions,
+    },
     storage::sqlite_utilities::WalletDbConnection,
     transaction_service::{
+        acceptance_policy::IncomingTransaction,
         error::TransactionStorageError,
+        payment_request::{PaymentReference, PaymentRequest},
         storage::{
             database::{DbKey, DbKeyValuePair, DbValue, TransactionBackend, WriteOperation},
             models::{
                 CompletedTransaction,
+                HeldTransaction,
                 InboundTransaction,
                 OutboundTransaction,
+                PaymentRequestRecord,
+                PaymentRequestStatus,
+                ScheduledTransaction,
                 TransactionDirection,
+                TransactionSchedule,
                 TransactionStatus,
                 WalletTransaction,
             },
@@ -53,7 +68,7 @@ use std::{
     str::from_utf8,
     sync::{Arc, MutexGuard, RwLock},
 };
-use tari_common_types::types::{BlockHash, PublicKey};
+use tari_common_types::types::{BlockHash, PrivateKey, PublicKey, Signature};
 use tari_comms::types::CommsPublicKey;
 use tari_core::transactions::tari_amount::MicroTari;
 use tari_crypto::tari_utilities::{
@@ -67,7 +82,7 @@ const LOG_TARGET: &str = "wallet::transaction_service::database::sqlite_db";
 #[derive(Clone)]
 pub struct TransactionServiceSqliteDatabase {
     database_connection: WalletDbConnection,
-    cipher: Arc<RwLock<Option<Aes256Gcm>>>,
+    pub(crate) cipher: Arc<RwLock<Option<Aes256Gcm>>>,
 }
 
 impl TransactionServiceSqliteDatabase {
@@ -106,6 +121,24 @@ impl TransactionServiceSqliteDatabase {
 
                 c.commit(&(*conn))?;
             },
+            DbKeyValuePair::ScheduledTransaction(k, v) => {
+                if ScheduledTransactionSql::find(k, &(*conn)).is_ok() {
+                    return Err(TransactionStorageError::DuplicateOutput);
+                }
+                ScheduledTransactionSql::from(*v).commit(&(*conn))?;
+            },
+            DbKeyValuePair::PaymentRequest(k, v) => {
+                if PaymentRequestSql::find(k, &(*conn)).is_ok() {
+                    return Err(TransactionStorageError::DuplicateOutput);
+                }
+                PaymentRequestSql::from(*v).commit(&(*conn))?;
+            },
+            DbKeyValuePair::HeldTransaction(k, v) => {
+                if HeldTransactionSql::find(k, &(*conn)).is_ok() {
+                    return Err(TransactionStorageError::DuplicateOutput);
+                }
+                HeldTransactionSql::try_from(*v)?.commit(&(*conn))?;
+            },
         }
         Ok(())
     }
@@ -193,6 +226,32 @@ impl TransactionServiceSqliteDatabase {
                 }
             },
             DbKey::AnyTransaction(_) => Err(TransactionStorageError::OperationNotSupported),
+            DbKey::ScheduledTransaction(k) => match ScheduledTransactionSql::find(k, &(*conn)) {
+                Ok(v) => {
+                    v.delete(&(*conn))?;
+                    Ok(Some(DbValue::ScheduledTransaction(Box::new(
+                        ScheduledTransaction::try_from(v)?,
+                    ))))
+                },
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                    Err(TransactionStorageError::ValueNotFound(DbKey::ScheduledTransaction(k)))
+                },
+                Err(e) => Err(e),
+            },
+            DbKey::ScheduledTransactions => Err(TransactionStorageError::OperationNotSupported),
+            DbKey::PaymentRequest(_) => Err(TransactionStorageError::OperationNotSupported),
+            DbKey::PaymentRequests => Err(TransactionStorageError::OperationNotSupported),
+            DbKey::HeldTransaction(k) => match HeldTransactionSql::find(k, &(*conn)) {
+                Ok(v) => {
+                    v.delete(&(*conn))?;
+                    Ok(Some(DbValue::HeldTransaction(Box::new(HeldTransaction::try_from(v)?))))
+                },
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                    Err(TransactionStorageError::ValueNotFound(DbKey::HeldTransaction(k)))
+                },
+                Err(e) => Err(e),
+            },
+            DbKey::HeldTransactions => Err(TransactionStorageError::OperationNotSupported),
         }
     }
 
@@ -213,6 +272,44 @@ impl TransactionServiceSqliteDatabase {
         }
         Ok(())
     }
+
+    /// Re-encrypt every stored transaction from `old_cipher` to `new_cipher`. The caller is responsible for running
+    /// this inside a database transaction and for making sure no backend holding the old cipher is in use.
+    pub(crate) fn reencrypt(
+        conn: &SqliteConnection,
+        old_cipher: &Aes256Gcm,
+        new_cipher: &Aes256Gcm,
+    ) -> Result<(), TransactionStorageError> {
+        for mut tx in InboundTransactionSql::index(conn)? {
+            reencrypt_record(&mut tx, old_cipher, new_cipher)?;
+            tx.update_encryption(conn)?;
+        }
+
+        for mut tx in OutboundTransactionSql::index(conn)? {
+            reencrypt_record(&mut tx, old_cipher, new_cipher)?;
+            tx.update_encryption(conn)?;
+        }
+
+        for mut tx in CompletedTransactionSql::index(conn)? {
+            reencrypt_record(&mut tx, old_cipher, new_cipher)?;
+            tx.update_encryption(conn)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn reencrypt_record<T: Encryptable<Aes256Gcm>>(
+    record: &mut T,
+    old_cipher: &Aes256Gcm,
+    new_cipher: &Aes256Gcm,
+) -> Result<(), TransactionStorageError> {
+    record
+        .decrypt(old_cipher)
+        .map_err(|_| TransactionStorageError::AeadError("Decryption Error".to_string()))?;
+    record
+        .encrypt(new_cipher)
+        .map_err(|_| TransactionStorageError::AeadError("Encryption Error".to_string()))
 }
 
 impl TransactionBackend for TransactionServiceSqliteDatabase {
@@ -369,6 +466,41 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     Err(e) => return Err(e),
                 }
             },
+            DbKey::ScheduledTransaction(t) => match ScheduledTransactionSql::find(*t, &(*conn)) {
+                Ok(s) => Some(DbValue::ScheduledTransaction(Box::new(ScheduledTransaction::try_from(
+                    s,
+                )?))),
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::ScheduledTransactions => Some(DbValue::ScheduledTransactions(
+                ScheduledTransactionSql::index(&(*conn))?
+                    .into_iter()
+                    .map(ScheduledTransaction::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::PaymentRequest(r) => match PaymentRequestSql::find(*r, &(*conn)) {
+                Ok(p) => Some(DbValue::PaymentRequest(Box::new(p.into_record(&(*conn))?))),
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::PaymentRequests => Some(DbValue::PaymentRequests(
+                PaymentRequestSql::index(&(*conn))?
+                    .into_iter()
+                    .map(|p| p.into_record(&(*conn)))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            DbKey::HeldTransaction(t) => match HeldTransactionSql::find(*t, &(*conn)) {
+                Ok(h) => Some(DbValue::HeldTransaction(Box::new(HeldTransaction::try_from(h)?))),
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => None,
+                Err(e) => return Err(e),
+            },
+            DbKey::HeldTransactions => Some(DbValue::HeldTransactions(
+                HeldTransactionSql::index(&(*conn))?
+                    .into_iter()
+                    .map(HeldTransaction::try_from)
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
         };
 
         Ok(result)
@@ -402,6 +534,12 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     InboundTransactionSql::find(*k, &(*conn)).is_ok() ||
                     OutboundTransactionSql::find(*k, &(*conn)).is_ok()
             },
+            DbKey::ScheduledTransaction(k) => ScheduledTransactionSql::find(*k, &(*conn)).is_ok(),
+            DbKey::ScheduledTransactions => false,
+            DbKey::PaymentRequest(r) => PaymentRequestSql::find(*r, &(*conn)).is_ok(),
+            DbKey::PaymentRequests => false,
+            DbKey::HeldTransaction(k) => HeldTransactionSql::find(*k, &(*conn)).is_ok(),
+            DbKey::HeldTransactions => false,
         };
 
         Ok(result)
@@ -831,6 +969,7 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
                     .is_null()
                     .or(completed_transactions::status.eq(TransactionStatus::MinedUnconfirmed as i32)),
             )
+            .filter(completed_transactions::status.ne(TransactionStatus::Scheduled as i32))
             .filter(completed_transactions::cancelled.eq(false as i32))
             .order_by(completed_transactions::tx_id)
             .load::<CompletedTransactionSql>(&*conn)?;
@@ -859,6 +998,117 @@ impl TransactionBackend for TransactionServiceSqliteDatabase {
         };
         Ok(())
     }
+
+    fn set_completed_transaction_scheduled(&self, tx_id: u64, scheduled: bool) -> Result<(), TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let (from, to) = if scheduled {
+            (TransactionStatus::Completed, TransactionStatus::Scheduled)
+        } else {
+            (TransactionStatus::Scheduled, TransactionStatus::Completed)
+        };
+
+        match CompletedTransactionSql::find_by_cancelled(tx_id, false, &(*conn)) {
+            Ok(v) => {
+                if TransactionStatus::try_from(v.status)? != from {
+                    return Err(TransactionStorageError::UnexpectedResult(format!(
+                        "Transaction has status {} and cannot be changed to {}",
+                        TransactionStatus::try_from(v.status)?,
+                        to
+                    )));
+                }
+                v.update(
+                    UpdateCompletedTransactionSql {
+                        status: Some(to as i32),
+                        ..Default::default()
+                    },
+                    &(*conn),
+                )?;
+            },
+            Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                return Err(TransactionStorageError::ValueNotFound(DbKey::CompletedTransaction(
+                    tx_id,
+                )));
+            },
+            Err(e) => return Err(e),
+        };
+        Ok(())
+    }
+
+    fn add_payment_request_payment(
+        &self,
+        reference: PaymentReference,
+        tx_id: TxId,
+        amount: MicroTari,
+    ) -> Result<PaymentRequestRecord, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        conn.transaction::<_, TransactionStorageError, _>(|| {
+            let request = match PaymentRequestSql::find(reference, &(*conn)) {
+                Ok(r) => r,
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => {
+                    return Err(TransactionStorageError::ValueNotFound(DbKey::PaymentRequest(reference)));
+                },
+                Err(e) => return Err(e),
+            };
+            PaymentRequestPaymentSql {
+                tx_id: tx_id as i64,
+                reference: reference as i64,
+                amount: u64::from(amount) as i64,
+            }
+            .commit(&(*conn))?;
+
+            let amount_received = request.amount_received + u64::from(amount) as i64;
+            let status = if amount_received >= request.amount {
+                PaymentRequestStatus::Paid
+            } else {
+                PaymentRequestStatus::PartlyPaid
+            };
+            request.update(status, amount_received, &(*conn))?;
+            PaymentRequestSql::find(reference, &(*conn))?.into_record(&(*conn))
+        })
+    }
+
+    fn remove_payment_request_payment(
+        &self,
+        tx_id: TxId,
+    ) -> Result<Option<PaymentRequestRecord>, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        conn.transaction::<_, TransactionStorageError, _>(|| {
+            let payment = match PaymentRequestPaymentSql::find(tx_id, &(*conn)) {
+                Ok(p) => p,
+                Err(TransactionStorageError::DieselError(DieselError::NotFound)) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+            payment.delete(&(*conn))?;
+
+            let request = PaymentRequestSql::find(payment.reference as u64, &(*conn))?;
+            let amount_received = (request.amount_received - payment.amount).max(0);
+            let status = match PaymentRequestStatus::try_from(request.status)? {
+                PaymentRequestStatus::Expired => PaymentRequestStatus::Expired,
+                _ if amount_received >= request.amount => PaymentRequestStatus::Paid,
+                _ if amount_received > 0 => PaymentRequestStatus::PartlyPaid,
+                _ => PaymentRequestStatus::Open,
+            };
+            request.update(status, amount_received, &(*conn))?;
+            Ok(Some(
+                PaymentRequestSql::find(payment.reference as u64, &(*conn))?.into_record(&(*conn))?,
+            ))
+        })
+    }
+
+    fn expire_payment_requests(&self, tip_height: u64) -> Result<Vec<PaymentReference>, TransactionStorageError> {
+        let conn = self.database_connection.acquire_lock();
+        let expired = payment_requests::table
+            .filter(payment_requests::expiry_height.le(tip_height as i64))
+            .filter(payment_requests::status.eq_any(vec![
+                PaymentRequestStatus::Open as i32,
+                PaymentRequestStatus::PartlyPaid as i32,
+            ]))
+            .load::<PaymentRequestSql>(&(*conn))?;
+        for request in &expired {
+            request.update(PaymentRequestStatus::Expired, request.amount_received, &(*conn))?;
+        }
+        Ok(expired.into_iter().map(|r| r.reference as u64).collect())
+    }
 }
 
 #[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
@@ -1488,6 +1738,278 @@ pub struct UpdateCompletedTransactionSql {
     mined_in_block: Option<Option<Vec<u8>>>,
 }
 
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "scheduled_transactions"]
+struct ScheduledTransactionSql {
+    tx_id: i64,
+    scheduled_height: Option<i64>,
+    scheduled_time: Option<NaiveDateTime>,
+}
+
+impl ScheduledTransactionSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(scheduled_transactions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<ScheduledTransactionSql>, TransactionStorageError> {
+        Ok(scheduled_transactions::table
+            .order_by(scheduled_transactions::tx_id)
+            .load::<ScheduledTransactionSql>(conn)?)
+    }
+
+    pub fn find(tx_id: TxId, conn: &SqliteConnection) -> Result<ScheduledTransactionSql, TransactionStorageError> {
+        Ok(scheduled_transactions::table
+            .filter(scheduled_transactions::tx_id.eq(tx_id as i64))
+            .first::<ScheduledTransactionSql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::delete(scheduled_transactions::table.filter(scheduled_transactions::tx_id.eq(&self.tx_id)))
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+}
+
+impl From<ScheduledTransaction> for ScheduledTransactionSql {
+    fn from(s: ScheduledTransaction) -> Self {
+        let (scheduled_height, scheduled_time) = match s.schedule {
+            TransactionSchedule::Height(height) => (Some(height as i64), None),
+            TransactionSchedule::Time(time) => (None, Some(time)),
+        };
+        Self {
+            tx_id: s.tx_id as i64,
+            scheduled_height,
+            scheduled_time,
+        }
+    }
+}
+
+impl TryFrom<ScheduledTransactionSql> for ScheduledTransaction {
+    type Error = TransactionStorageError;
+
+    fn try_from(s: ScheduledTransactionSql) -> Result<Self, Self::Error> {
+        let schedule = match (s.scheduled_height, s.scheduled_time) {
+            (Some(height), None) => TransactionSchedule::Height(height as u64),
+            (None, Some(time)) => TransactionSchedule::Time(time),
+            _ => {
+                return Err(TransactionStorageError::ConversionError(
+                    "Scheduled transaction must have either a height or a time".to_string(),
+                ))
+            },
+        };
+        Ok(Self::new(s.tx_id as u64, schedule))
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "payment_requests"]
+struct PaymentRequestSql {
+    reference: i64,
+    recipient_public_key: Vec<u8>,
+    amount: i64,
+    message: String,
+    expiry_height: i64,
+    signature_nonce: Vec<u8>,
+    signature_key: Vec<u8>,
+    status: i32,
+    amount_received: i64,
+    timestamp: NaiveDateTime,
+}
+
+impl PaymentRequestSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(payment_requests::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<PaymentRequestSql>, TransactionStorageError> {
+        Ok(payment_requests::table
+            .order_by(payment_requests::timestamp)
+            .load::<PaymentRequestSql>(conn)?)
+    }
+
+    pub fn find(
+        reference: PaymentReference,
+        conn: &SqliteConnection,
+    ) -> Result<PaymentRequestSql, TransactionStorageError> {
+        Ok(payment_requests::table
+            .filter(payment_requests::reference.eq(reference as i64))
+            .first::<PaymentRequestSql>(conn)?)
+    }
+
+    pub fn update(
+        &self,
+        status: PaymentRequestStatus,
+        amount_received: i64,
+        conn: &SqliteConnection,
+    ) -> Result<(), TransactionStorageError> {
+        diesel::update(payment_requests::table.filter(payment_requests::reference.eq(&self.reference)))
+            .set((
+                payment_requests::status.eq(status as i32),
+                payment_requests::amount_received.eq(amount_received),
+            ))
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+
+    /// Convert into a [PaymentRequestRecord], loading the payments received for the request
+    pub fn into_record(self, conn: &SqliteConnection) -> Result<PaymentRequestRecord, TransactionStorageError> {
+        let payments = payment_request_payments::table
+            .filter(payment_request_payments::reference.eq(self.reference))
+            .order_by(payment_request_payments::tx_id)
+            .select(payment_request_payments::tx_id)
+            .load::<i64>(conn)?;
+        let recipient = PublicKey::from_vec(&self.recipient_public_key)
+            .map_err(|_| TransactionStorageError::ConversionError("Invalid recipient public key".to_string()))?;
+        let signature = Signature::new(
+            PublicKey::from_vec(&self.signature_nonce)
+                .map_err(|_| TransactionStorageError::ConversionError("Invalid signature nonce".to_string()))?,
+            PrivateKey::from_vec(&self.signature_key)
+                .map_err(|_| TransactionStorageError::ConversionError("Invalid signature key".to_string()))?,
+        );
+        Ok(PaymentRequestRecord {
+            request: PaymentRequest {
+                reference: self.reference as u64,
+                recipient,
+                amount: MicroTari::from(self.amount as u64),
+                message: self.message,
+                expiry_height: self.expiry_height as u64,
+                signature,
+            },
+            status: PaymentRequestStatus::try_from(self.status)?,
+            amount_received: MicroTari::from(self.amount_received as u64),
+            payments: payments.into_iter().map(|tx_id| tx_id as u64).collect(),
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+impl From<PaymentRequestRecord> for PaymentRequestSql {
+    fn from(r: PaymentRequestRecord) -> Self {
+        Self {
+            reference: r.request.reference as i64,
+            recipient_public_key: r.request.recipient.to_vec(),
+            amount: u64::from(r.request.amount) as i64,
+            message: r.request.message,
+            expiry_height: r.request.expiry_height as i64,
+            signature_nonce: r.request.signature.get_public_nonce().to_vec(),
+            signature_key: r.request.signature.get_signature().to_vec(),
+            status: r.status as i32,
+            amount_received: u64::from(r.amount_received) as i64,
+            timestamp: r.timestamp,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "payment_request_payments"]
+struct PaymentRequestPaymentSql {
+    tx_id: i64,
+    reference: i64,
+    amount: i64,
+}
+
+impl PaymentRequestPaymentSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(payment_request_payments::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn find(tx_id: TxId, conn: &SqliteConnection) -> Result<PaymentRequestPaymentSql, TransactionStorageError> {
+        Ok(payment_request_payments::table
+            .filter(payment_request_payments::tx_id.eq(tx_id as i64))
+            .first::<PaymentRequestPaymentSql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::delete(payment_request_payments::table.filter(payment_request_payments::tx_id.eq(&self.tx_id)))
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Queryable, Insertable, PartialEq)]
+#[table_name = "held_transactions"]
+struct HeldTransactionSql {
+    tx_id: i64,
+    source_public_key: Vec<u8>,
+    amount: i64,
+    message: String,
+    sender_message: String,
+    timestamp: NaiveDateTime,
+}
+
+impl HeldTransactionSql {
+    pub fn commit(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::insert_into(held_transactions::table)
+            .values(self.clone())
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn index(conn: &SqliteConnection) -> Result<Vec<HeldTransactionSql>, TransactionStorageError> {
+        Ok(held_transactions::table
+            .order_by(held_transactions::timestamp)
+            .load::<HeldTransactionSql>(conn)?)
+    }
+
+    pub fn find(tx_id: TxId, conn: &SqliteConnection) -> Result<HeldTransactionSql, TransactionStorageError> {
+        Ok(held_transactions::table
+            .filter(held_transactions::tx_id.eq(tx_id as i64))
+            .first::<HeldTransactionSql>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &SqliteConnection) -> Result<(), TransactionStorageError> {
+        diesel::delete(held_transactions::table.filter(held_transactions::tx_id.eq(&self.tx_id)))
+            .execute(conn)
+            .num_rows_affected_or_not_found(1)?;
+        Ok(())
+    }
+}
+
+impl TryFrom<HeldTransaction> for HeldTransactionSql {
+    type Error = TransactionStorageError;
+
+    fn try_from(h: HeldTransaction) -> Result<Self, Self::Error> {
+        Ok(Self {
+            tx_id: h.transaction.tx_id as i64,
+            source_public_key: h.transaction.source_public_key.to_vec(),
+            amount: u64::from(h.transaction.amount) as i64,
+            message: h.transaction.message,
+            sender_message: serde_json::to_string(&h.sender_message)?,
+            timestamp: h.transaction.timestamp,
+        })
+    }
+}
+
+impl TryFrom<HeldTransactionSql> for HeldTransaction {
+    type Error = TransactionStorageError;
+
+    fn try_from(h: HeldTransactionSql) -> Result<Self, Self::Error> {
+        Ok(Self {
+            transaction: IncomingTransaction {
+                tx_id: h.tx_id as u64,
+                source_public_key: PublicKey::from_vec(&h.source_public_key)
+                    .map_err(|_| TransactionStorageError::ConversionError("Invalid source public key".to_string()))?,
+                amount: MicroTari::from(h.amount as u64),
+                message: h.message,
+                timestamp: h.timestamp,
+            },
+            sender_message: serde_json::from_str(&h.sender_message)?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::convert::TryFrom;
@@ -1520,20 +2042,24 @@ mod test {
 
     use crate::{
         storage::sqlite_utilities::WalletDbConnection,
-        transaction_service::storage::{
-            database::{DbKey, TransactionBackend},
-            models::{
-                CompletedTransaction,
-                InboundTransaction,
-                OutboundTransaction,
-                TransactionDirection,
-                TransactionStatus,
-            },
-            sqlite_db::{
-                CompletedTransactionSql,
-                InboundTransactionSql,
-                OutboundTransactionSql,
-                TransactionServiceSqliteDatabase,
+        transaction_service::{
+            acceptance_policy::IncomingTransaction,
+            storage::{
+                database::{DbKey, DbKeyValuePair, DbValue, TransactionBackend, WriteOperation},
+                models::{
+                    CompletedTransaction,
+                    HeldTransaction,
+                    InboundTransaction,
+                    OutboundTransaction,
+                    TransactionDirection,
+                    TransactionStatus,
+                },
+                sqlite_db::{
+                    CompletedTransactionSql,
+                    InboundTransactionSql,
+                    OutboundTransactionSql,
+                    TransactionServiceSqliteDatabase,
+                },
             },
         },
         util::encryption::Encryptable,
@@ -2132,4 +2658,73 @@ mod test {
         assert!(db3.fetch(&DbKey::PendingOutboundTransactions).is_ok());
         assert!(db3.fetch(&DbKey::CompletedTransactions).is_ok());
     }
+
+    #[test]
+    fn test_held_transactions() {
+        let db_name = format!("{}.sqlite3", string(8).as_str());
+        let temp_dir = tempdir().unwrap();
+        let db_folder = temp_dir.path().to_str().unwrap().to_string();
+        let db_path = format!("{}{}", db_folder, db_name);
+
+        embed_migrations!("./migrations");
+        let conn = SqliteConnection::establish(&db_path).unwrap_or_else(|_| panic!("Error connecting to {}", db_path));
+
+        embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).expect("Migration failed");
+        let db = TransactionServiceSqliteDatabase::new(WalletDbConnection::new(conn, None), None);
+
+        let source_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+        for tx_id in 1..=2 {
+            db.write(WriteOperation::Insert(DbKeyValuePair::HeldTransaction(
+                tx_id,
+                Box::new(HeldTransaction {
+                    transaction: IncomingTransaction {
+                        tx_id,
+                        source_public_key: source_public_key.clone(),
+                        amount: MicroTari::from(10_000 * tx_id),
+                        message: "Yo!".to_string(),
+                        timestamp: Utc::now().naive_utc(),
+                    },
+                    sender_message: TransactionSenderMessage::None,
+                }),
+            )))
+            .unwrap();
+        }
+        assert!(db
+            .write(WriteOperation::Insert(DbKeyValuePair::HeldTransaction(
+                1,
+                Box::new(HeldTransaction {
+                    transaction: IncomingTransaction {
+                        tx_id: 1,
+                        source_public_key: source_public_key.clone(),
+                        amount: MicroTari::from(1),
+                        message: "Again".to_string(),
+                        timestamp: Utc::now().naive_utc(),
+                    },
+                    sender_message: TransactionSenderMessage::None,
+                }),
+            )))
+            .is_err());
+
+        match db.fetch(&DbKey::HeldTransaction(2)).unwrap() {
+            Some(DbValue::HeldTransaction(held)) => {
+                assert_eq!(held.transaction.source_public_key, source_public_key);
+                assert_eq!(held.transaction.amount, MicroTari::from(20_000));
+                assert!(matches!(held.sender_message, TransactionSenderMessage::None));
+            },
+            _ => panic!("Held transaction not found"),
+        }
+        match db.fetch(&DbKey::HeldTransactions).unwrap() {
+            Some(DbValue::HeldTransactions(held)) => {
+                assert_eq!(held.iter().map(|h| h.transaction.tx_id).collect::<Vec<_>>(), vec![1, 2]);
+            },
+            _ => panic!("Held transactions not found"),
+        }
+
+        match db.write(WriteOperation::Remove(DbKey::HeldTransaction(1))).unwrap() {
+            Some(DbValue::HeldTransaction(held)) => assert_eq!(held.transaction.tx_id, 1),
+            _ => panic!("Held transaction not removed"),
+        }
+        assert!(!db.contains(&DbKey::HeldTransaction(1)).unwrap());
+        assert!(db.write(WriteOperation::Remove(DbKey::HeldTransaction(1))).is_err());
+    }
 }
@@ -23,4 +23,5 @@
 pub mod send_finalized_transaction;
 pub mod send_transaction_cancelled;
 pub mod send_transaction_reply;
+pub mod send_transaction_sender_message;
 pub mod wait_on_dial;
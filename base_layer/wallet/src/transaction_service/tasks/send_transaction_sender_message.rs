@@ -0,0 +1,172 @@
+// Copyright 2019. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    output_manager_service::TxId,
+    transaction_service::{
+        config::TransactionRoutingMechanism,
+        error::TransactionServiceError,
+        tasks::wait_on_dial::wait_on_dial,
+    },
+};
+use log::*;
+use std::time::Duration;
+use tari_comms::{peer_manager::NodeId, types::CommsPublicKey};
+use tari_comms_dht::{
+    domain_message::OutboundDomainMessage,
+    outbound::{OutboundEncryption, OutboundMessageRequester, SendMessageResponse},
+};
+use tari_core::transactions::transaction_protocol::{proto, sender::SingleRoundSenderData};
+use tari_p2p::tari_message::TariMessageType;
+
+const LOG_TARGET: &str = "wallet::transaction_service::tasks::send_transaction_sender_message";
+
+/// A task to send the sender message of a transaction that was built outside of a `TransactionSendProtocol`, such as
+/// an offline signed transaction, to the recipient either directly, via Store-and-forward or both as per config
+/// setting.
+pub async fn send_transaction_sender_message(
+    tx_id: TxId,
+    sender_message: SingleRoundSenderData,
+    destination_public_key: CommsPublicKey,
+    mut outbound_message_service: OutboundMessageRequester,
+    direct_send_timeout: Duration,
+    transaction_routing_mechanism: TransactionRoutingMechanism,
+) -> Result<(), TransactionServiceError> {
+    let proto_message = proto::TransactionSenderMessage::single(sender_message.into());
+    let mut store_and_forward_send_result = false;
+    let mut direct_send_result = false;
+
+    if transaction_routing_mechanism != TransactionRoutingMechanism::StoreAndForwardOnly {
+        match outbound_message_service
+            .send_direct(
+                destination_public_key.clone(),
+                OutboundDomainMessage::new(TariMessageType::SenderPartialTransaction, proto_message.clone()),
+            )
+            .await
+        {
+            Ok(SendMessageResponse::Queued(send_states)) => {
+                direct_send_result = wait_on_dial(
+                    send_states,
+                    tx_id,
+                    destination_public_key.clone(),
+                    "Transaction",
+                    direct_send_timeout,
+                )
+                .await;
+            },
+            Ok(SendMessageResponse::Failed(err)) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Transaction Send Direct for TxID {} failed: {}", tx_id, err
+                );
+            },
+            Ok(SendMessageResponse::PendingDiscovery(rx)) => match rx.await {
+                Ok(SendMessageResponse::Queued(send_states)) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Discovery of {} completed for TxID: {}", destination_public_key, tx_id
+                    );
+                    direct_send_result = wait_on_dial(
+                        send_states,
+                        tx_id,
+                        destination_public_key.clone(),
+                        "Transaction",
+                        direct_send_timeout,
+                    )
+                    .await;
+                },
+                Err(e) => {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Error waiting for Discovery while sending message to TxId: {} {:?}", tx_id, e
+                    );
+                },
+                _ => warn!(
+                    target: LOG_TARGET,
+                    "Empty response received waiting for Discovery to complete TxId: {}", tx_id
+                ),
+            },
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Direct Transaction Send failed: {:?}", e);
+            },
+        }
+        info!(
+            target: LOG_TARGET,
+            "Direct Send result was {} for TxId: {} to recipient with Public Key: {}",
+            direct_send_result,
+            tx_id,
+            destination_public_key,
+        );
+    }
+
+    // Send a Store and Forward (SAF) regardless of the direct send result, as a direct send can be reported as
+    // successful while the recipient is offline.
+    if transaction_routing_mechanism != TransactionRoutingMechanism::DirectOnly {
+        store_and_forward_send_result = send_transaction_sender_message_store_and_forward(
+            tx_id,
+            destination_public_key,
+            proto_message,
+            &mut outbound_message_service,
+        )
+        .await?;
+    }
+
+    if !direct_send_result && !store_and_forward_send_result {
+        return Err(TransactionServiceError::OutboundSendFailure);
+    }
+    Ok(())
+}
+
+async fn send_transaction_sender_message_store_and_forward(
+    tx_id: TxId,
+    destination_pubkey: CommsPublicKey,
+    msg: proto::TransactionSenderMessage,
+    outbound_message_service: &mut OutboundMessageRequester,
+) -> Result<bool, TransactionServiceError> {
+    match outbound_message_service
+        .closest_broadcast(
+            NodeId::from_public_key(&destination_pubkey),
+            OutboundEncryption::EncryptFor(Box::new(destination_pubkey.clone())),
+            vec![],
+            OutboundDomainMessage::new(TariMessageType::SenderPartialTransaction, msg),
+        )
+        .await
+    {
+        Ok(send_states) => {
+            info!(
+                target: LOG_TARGET,
+                "Sending Transaction (TxId: {}) to Neighbours for Store and Forward successful with Message Tags: {:?}",
+                tx_id,
+                send_states.to_tags(),
+            );
+        },
+        Err(e) => {
+            warn!(
+                target: LOG_TARGET,
+                "Sending Transaction (TxId: {}) to neighbours for Store and Forward failed: {:?}", tx_id, e
+            );
+            return Ok(false);
+        },
+    };
+
+    Ok(true)
+}
@@ -0,0 +1,68 @@
+// Copyright 2020. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! A wallet birthday is the number of days since the Unix epoch on which the wallet's master key was created. It is
+//! kept beside the seed words, the 24 words only have room for the key itself.
+
+use chrono::{NaiveDate, Utc};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The birthday of a wallet created today
+pub fn current_wallet_birthday() -> u16 {
+    birthday_from_date(Utc::today().naive_utc()).unwrap_or(u16::MAX)
+}
+
+/// Returns the birthday for the given date, or None if the date is before the Unix epoch or too far in the future
+pub fn birthday_from_date(date: NaiveDate) -> Option<u16> {
+    let days = date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1)).num_days();
+    if days < 0 || days > i64::from(u16::MAX) {
+        return None;
+    }
+    Some(days as u16)
+}
+
+pub fn birthday_to_date(birthday: u16) -> NaiveDate {
+    NaiveDate::from_ymd(1970, 1, 1) + chrono::Duration::days(i64::from(birthday))
+}
+
+/// The Unix timestamp of the start of the birthday
+pub fn birthday_to_unix_timestamp(birthday: u16) -> u64 {
+    (i64::from(birthday) * SECONDS_PER_DAY) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_converts_between_dates_and_birthdays() {
+        let date = NaiveDate::from_ymd(2021, 11, 8);
+        let birthday = birthday_from_date(date).unwrap();
+        assert_eq!(birthday, 18_939);
+        assert_eq!(birthday_to_date(birthday), date);
+        assert_eq!(birthday_to_unix_timestamp(birthday), 1_636_329_600);
+
+        assert_eq!(birthday_from_date(NaiveDate::from_ymd(1969, 12, 31)), None);
+        assert_eq!(birthday_from_date(NaiveDate::from_ymd(2200, 1, 1)), None);
+    }
+}
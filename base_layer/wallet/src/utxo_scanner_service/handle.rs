@@ -20,12 +20,13 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use serde::Serialize;
 use std::time::Duration;
 use tari_comms::peer_manager::NodeId;
 use tari_core::transactions::tari_amount::MicroTari;
 use tokio::sync::broadcast;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum UtxoScannerEvent {
     ConnectingToBaseNode(NodeId),
     ConnectedToBaseNode(NodeId, Duration),
@@ -40,10 +41,11 @@ pub enum UtxoScannerEvent {
         retry_limit: usize,
         error: String,
     },
-    /// Progress of the recovery process (current_block, current_chain_height)
+    /// Progress of the recovery process (current_block, current_chain_height, estimated time remaining)
     Progress {
         current_block: u64,
         current_chain_height: u64,
+        time_remaining: Option<Duration>,
     },
     /// Completed Recovery (Number scanned, Num of Recovered outputs, Value of recovered outputs, Time taken)
     Completed {
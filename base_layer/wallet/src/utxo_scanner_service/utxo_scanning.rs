@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
+    cmp,
     convert::TryFrom,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -67,6 +68,7 @@ use crate::{
         sqlite_db::WalletSqliteDatabase,
     },
     transaction_service::handle::TransactionServiceHandle,
+    util::birthday::{birthday_to_date, birthday_to_unix_timestamp},
     utxo_scanner_service::{error::UtxoScannerError, handle::UtxoScannerEvent},
     WalletSqlite,
 };
@@ -78,6 +80,13 @@ pub const LOG_TARGET: &str = "wallet::utxo_scanning";
 pub const RECOVERY_KEY: &str = "recovery_data";
 const SCANNING_KEY: &str = "scanning_data";
 
+/// The number of blocks that are scanned before the scanning progress is saved
+const BLOCK_BATCH_SIZE: u64 = 500;
+/// The number of outputs that are rewound together
+const UTXO_CHUNK_SIZE: usize = 100;
+/// Blocks mined this many days before the wallet birthday are also scanned, to allow for clock differences
+const BIRTHDAY_SAFETY_MARGIN_DAYS: u16 = 1;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum UtxoScannerMode {
     Recovery,
@@ -215,17 +224,15 @@ where TBackend: WalletBackend + 'static
 impl<TBackend> UtxoScannerTask<TBackend>
 where TBackend: WalletBackend + 'static
 {
-    async fn finalize(
-        &self,
-        total_scanned: u64,
-        final_utxo_pos: u64,
-        elapsed: Duration,
-    ) -> Result<(), UtxoScannerError> {
+    async fn finalize(&self, total_scanned: u64, elapsed: Duration) -> Result<(), UtxoScannerError> {
         let metadata = self.get_metadata().await?.unwrap_or_default();
-        self.publish_event(UtxoScannerEvent::Progress {
-            current_block: final_utxo_pos,
-            current_chain_height: final_utxo_pos,
-        });
+        if let Some(height) = metadata.height {
+            self.publish_event(UtxoScannerEvent::Progress {
+                current_block: height,
+                current_chain_height: height,
+                time_remaining: Some(Duration::from_secs(0)),
+            });
+        }
         self.publish_event(UtxoScannerEvent::Completed {
             number_scanned: total_scanned,
             number_received: metadata.number_of_utxos,
@@ -277,7 +284,7 @@ where TBackend: WalletBackend + 'static
         let timer = Instant::now();
         let mut total_scanned = 0u64;
         loop {
-            let start_index = self.get_start_utxo_mmr_pos(&mut client).await?;
+            let (start_height, start_index) = self.get_start_scan_position(&mut client).await?;
             let tip_header = self.get_chain_tip_header(&mut client).await?;
             let output_mmr_size = tip_header.output_mmr_size;
             if !self.run_flag.load(Ordering::Relaxed) {
@@ -286,30 +293,29 @@ where TBackend: WalletBackend + 'static
             }
             debug!(
                 target: LOG_TARGET,
-                "Scanning UTXO's (start_index = {}, output_mmr_size = {}, height = {}, tip_hash = {})",
+                "Scanning UTXO's (start_height = {}, start_index = {}, output_mmr_size = {}, height = {}, tip_hash = \
+                 {})",
+                start_height,
                 start_index,
                 output_mmr_size,
                 tip_header.height,
                 tip_header.hash().to_hex()
             );
-            // start_index could be greater than output_mmr_size if we switch to a new peer that is behind the original
-            // peer. In the common case, we wait for start index.
-            if start_index >= output_mmr_size - 1 {
+            // start_height could be greater than the tip height if we switch to a new peer that is behind the original
+            // peer. In the common case, we wait for new blocks.
+            if start_height > tip_header.height || start_index >= output_mmr_size {
                 debug!(
                     target: LOG_TARGET,
-                    "Scanning complete UTXO #{} in {:.2?}",
-                    start_index,
+                    "Scanning complete to height #{} in {:.2?}",
+                    start_height.saturating_sub(1),
                     timer.elapsed()
                 );
                 return Ok((total_scanned, start_index, timer.elapsed()));
             }
 
-            let num_scanned = self.scan_utxos(&mut client, start_index, tip_header).await?;
-            if num_scanned == 0 {
-                return Err(UtxoScannerError::UtxoScanningError(
-                    "Peer returned 0 UTXOs to scan".to_string(),
-                ));
-            }
+            let num_scanned = self
+                .scan_utxos(&mut client, start_height, start_index, tip_header)
+                .await?;
             debug!(
                 target: LOG_TARGET,
                 "Scanning round completed UTXO #{} in {:.2?} ({} scanned)",
@@ -318,9 +324,7 @@ where TBackend: WalletBackend + 'static
                 num_scanned
             );
 
-            // let num_scanned = 0;
             total_scanned += num_scanned;
-            // return Ok((total_scanned, start_index, timer.elapsed()));
         }
     }
 
@@ -333,15 +337,93 @@ where TBackend: WalletBackend + 'static
         Ok(end_header)
     }
 
-    async fn get_start_utxo_mmr_pos(&self, client: &mut BaseNodeSyncRpcClient) -> Result<u64, UtxoScannerError> {
-        let metadata = self.get_metadata().await?.unwrap_or_default();
+    async fn get_header_by_height(
+        &self,
+        client: &mut BaseNodeSyncRpcClient,
+        height: u64,
+    ) -> Result<BlockHeader, UtxoScannerError> {
+        let header = client.get_header_by_height(height).await?;
+        BlockHeader::try_from(header).map_err(|_| UtxoScannerError::ConversionError)
+    }
+
+    /// Binary search the chain up to `tip_height` for the first block whose header matches `predicate`. The predicate
+    /// must be false for a prefix of the chain and true for the rest of it. Returns `tip_height + 1` if no header
+    /// matches.
+    async fn find_first_height<F>(
+        &self,
+        client: &mut BaseNodeSyncRpcClient,
+        tip_height: u64,
+        predicate: F,
+    ) -> Result<u64, UtxoScannerError>
+    where
+        F: Fn(&BlockHeader) -> bool,
+    {
+        let mut low = 0;
+        let mut high = tip_height + 1;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let header = self.get_header_by_height(client, mid).await?;
+            if predicate(&header) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Returns the height and output MMR index to start scanning from, along with the header of the block before it,
+    /// based on the wallet birthday. Wallets without a birthday are scanned from the genesis block.
+    async fn get_birthday_scan_position(
+        &self,
+        client: &mut BaseNodeSyncRpcClient,
+    ) -> Result<(u64, u64, Option<BlockHeader>), UtxoScannerError> {
+        let birthday = match self.resources.db.get_wallet_birthday().await? {
+            Some(birthday) => birthday,
+            None => return Ok((0, 0, None)),
+        };
+        // Block timestamps are only roughly increasing, the safety margin covers the blocks that are out of order
+        let start_timestamp = birthday_to_unix_timestamp(birthday.saturating_sub(BIRTHDAY_SAFETY_MARGIN_DAYS));
+        let tip_height = client.get_chain_metadata().await?.height_of_longest_chain();
+        let height = self
+            .find_first_height(client, tip_height, |header| {
+                header.timestamp.as_u64() >= start_timestamp
+            })
+            .await?;
+        if height == 0 {
+            return Ok((0, 0, None));
+        }
+
+        let prev_header = self.get_header_by_height(client, height - 1).await?;
+        info!(
+            target: LOG_TARGET,
+            "Wallet birthday is {}, skipping the first {} block(s)",
+            birthday_to_date(birthday),
+            height
+        );
+        Ok((height, prev_header.output_mmr_size, Some(prev_header)))
+    }
+
+    /// Returns the height and output MMR index to continue scanning from
+    async fn get_start_scan_position(
+        &self,
+        client: &mut BaseNodeSyncRpcClient,
+    ) -> Result<(u64, u64), UtxoScannerError> {
+        let mut metadata = self.get_metadata().await?.unwrap_or_default();
         if metadata.height_hash.is_empty() {
+            let (height, start_index, prev_header) = self.get_birthday_scan_position(client).await?;
+            if let Some(header) = prev_header {
+                metadata.height_hash = header.hash();
+                metadata.height = Some(header.height);
+                metadata.utxo_index = header.output_mmr_size.saturating_sub(1);
+            }
             // Set a value in here so that if the recovery fails on the genesis block the client will know a
             // recover was started. Important on Console wallet that otherwise makes this decision based on the
             // presence of the data file
             self.set_metadata(metadata).await?;
-            return Ok(0);
+            return Ok((height, start_index));
         }
+        let start_index = metadata.utxo_index + 1;
         // if it's none, we return 0 above.
         let request = FindChainSplitRequest {
             block_hashes: vec![metadata.height_hash],
@@ -349,104 +431,153 @@ where TBackend: WalletBackend + 'static
         };
         // this returns the index of the vec of hashes we sent it, that is the last hash it knows of.
         match client.find_chain_split(request).await {
-            Ok(_) => Ok(metadata.utxo_index + 1),
+            Ok(_) => match metadata.height {
+                Some(height) => Ok((height + 1, start_index)),
+                None => {
+                    // Progress saved by an older version of the scanner only has the output index
+                    let tip_height = client.get_chain_metadata().await?.height_of_longest_chain();
+                    let height = self
+                        .find_first_height(client, tip_height, |header| header.output_mmr_size > start_index)
+                        .await?;
+                    Ok((height, start_index))
+                },
+            },
             Err(RpcError::RequestFailed(err)) if err.status_code().is_not_found() => {
                 warn!(target: LOG_TARGET, "Reorg detected: {}", err);
                 // The node does not know of the last hash we scanned, thus we had a chain split.
-                // We now start at 0 again.
-                Ok(0)
+                // We now start at the wallet birthday again.
+                let (height, start_index, _) = self.get_birthday_scan_position(client).await?;
+                Ok((height, start_index))
             },
             Err(err) => Err(err.into()),
         }
     }
 
+    /// Scan the chain from `start_height` to the tip in batches of blocks. The header at the end of each batch gives
+    /// the output MMR range of the batch, so batches without outputs are skipped without downloading anything and the
+    /// progress can be saved at a block boundary.
     async fn scan_utxos(
         &mut self,
         client: &mut BaseNodeSyncRpcClient,
+        start_height: u64,
         start_mmr_leaf_index: u64,
-        end_header: BlockHeader,
+        tip_header: BlockHeader,
     ) -> Result<u64, UtxoScannerError> {
         debug!(
             target: LOG_TARGET,
-            "Scanning UTXO's from #{} to #{} (height {})",
+            "Scanning UTXO's from #{} to #{} (height {} to {})",
             start_mmr_leaf_index,
-            end_header.output_mmr_size,
-            end_header.height
+            tip_header.output_mmr_size,
+            start_height,
+            tip_header.height
         );
 
-        let end_header_hash = end_header.hash();
-        let end_header_size = end_header.output_mmr_size;
+        let timer = Instant::now();
+        let mut total_scanned = 0u64;
+        let mut height = start_height;
+        let mut start_index = start_mmr_leaf_index;
+
+        self.publish_event(UtxoScannerEvent::Progress {
+            current_block: start_height.saturating_sub(1),
+            current_chain_height: tip_header.height,
+            time_remaining: None,
+        });
+        while height <= tip_header.height {
+            let batch_end_height = cmp::min(height + BLOCK_BATCH_SIZE - 1, tip_header.height);
+            let batch_end_header = if batch_end_height == tip_header.height {
+                tip_header.clone()
+            } else {
+                self.get_header_by_height(client, batch_end_height).await?
+            };
+            let batch_end_hash = batch_end_header.hash();
+
+            let (num_scanned, num_recovered, amount) = if batch_end_header.output_mmr_size > start_index {
+                self.scan_output_range(client, start_index, batch_end_hash.clone())
+                    .await?
+            } else {
+                trace!(
+                    target: LOG_TARGET,
+                    "Skipping blocks {} to {}, they have no outputs",
+                    height,
+                    batch_end_height
+                );
+                (0, 0, MicroTari::from(0))
+            };
+            total_scanned += num_scanned;
+            if !self.run_flag.load(Ordering::Relaxed) {
+                // The batch was not scanned completely, so its progress is not saved
+                return Ok(total_scanned);
+            }
+
+            self.update_scanning_progress_in_db(&batch_end_header, amount, num_recovered)
+                .await?;
+            start_index = batch_end_header.output_mmr_size;
+            height = batch_end_height + 1;
+
+            self.publish_event(UtxoScannerEvent::Progress {
+                current_block: batch_end_height,
+                current_chain_height: tip_header.height,
+                time_remaining: estimate_time_remaining(
+                    timer.elapsed(),
+                    start_index - start_mmr_leaf_index,
+                    tip_header.output_mmr_size.saturating_sub(start_index),
+                ),
+            });
+        }
+        Ok(total_scanned)
+    }
+
+    /// Download and scan the unspent outputs from `start_mmr_leaf_index` up to the end of the block with the given
+    /// hash. Returns the number of outputs scanned and the number and value of the outputs that were recovered.
+    async fn scan_output_range(
+        &mut self,
+        client: &mut BaseNodeSyncRpcClient,
+        start_mmr_leaf_index: u64,
+        end_header_hash: HashOutput,
+    ) -> Result<(u64, u64, MicroTari), UtxoScannerError> {
         let mut num_recovered = 0u64;
         let mut total_amount = MicroTari::from(0);
         let mut total_scanned = 0;
 
-        self.publish_event(UtxoScannerEvent::Progress {
-            current_block: start_mmr_leaf_index,
-            current_chain_height: (end_header_size - 1),
-        });
         let request = SyncUtxosRequest {
             start: start_mmr_leaf_index,
-            end_header_hash: end_header_hash.clone(),
+            end_header_hash,
             include_pruned_utxos: false,
             include_deleted_bitmaps: false,
         };
 
         let utxo_stream = client.sync_utxos(request).await?;
-        // We download in chunks just because rewind_outputs works with multiple outputs (and could parallelized
-        // rewinding)
-        let mut utxo_stream = utxo_stream.chunks(10);
+        // We download in chunks so that each chunk can be rewound in parallel
+        let mut utxo_stream = utxo_stream.chunks(UTXO_CHUNK_SIZE);
         let mut last_utxo_index = 0u64;
-        let mut iteration_count = 0u64;
         while let Some(response) = utxo_stream.next().await {
             if !self.run_flag.load(Ordering::Relaxed) {
                 // if running is set to false, we know its been canceled upstream so lets exit the loop
-                return Ok(total_scanned as u64);
+                break;
             }
             let (outputs, utxo_index) = convert_response_to_transaction_outputs(response, last_utxo_index)?;
             last_utxo_index = utxo_index;
             total_scanned += outputs.len();
-            iteration_count += 1;
             let found_outputs = self.scan_for_outputs(outputs).await?;
 
-            // Reduce the number of db hits by only persisting progress every N iterations
-            const COMMIT_EVERY_N: u64 = 100;
-            if iteration_count % COMMIT_EVERY_N == 0 || last_utxo_index >= end_header_size - 1 {
-                self.publish_event(UtxoScannerEvent::Progress {
-                    current_block: last_utxo_index,
-                    current_chain_height: (end_header_size - 1),
-                });
-                self.update_scanning_progress_in_db(
-                    last_utxo_index,
-                    total_amount,
-                    num_recovered,
-                    end_header_hash.clone(),
-                )
-                .await?;
-            }
             let (count, amount) = self.import_utxos_to_transaction_service(found_outputs).await?;
             num_recovered = num_recovered.saturating_add(count);
             total_amount += amount;
         }
-        self.update_scanning_progress_in_db(last_utxo_index, total_amount, num_recovered, end_header_hash)
-            .await?;
-        self.publish_event(UtxoScannerEvent::Progress {
-            current_block: (end_header_size - 1),
-            current_chain_height: (end_header_size - 1),
-        });
-        Ok(total_scanned as u64)
+        Ok((total_scanned as u64, num_recovered, total_amount))
     }
 
     async fn update_scanning_progress_in_db(
         &self,
-        last_utxo_index: u64,
+        last_scanned_header: &BlockHeader,
         total_amount: MicroTari,
         num_recovered: u64,
-        end_header_hash: Vec<u8>,
     ) -> Result<(), UtxoScannerError> {
         let mut meta_data = self.get_metadata().await?.unwrap_or_default();
-        meta_data.height_hash = end_header_hash;
+        meta_data.height_hash = last_scanned_header.hash();
+        meta_data.height = Some(last_scanned_header.height);
         meta_data.number_of_utxos += num_recovered;
-        meta_data.utxo_index = last_utxo_index;
+        meta_data.utxo_index = last_scanned_header.output_mmr_size.saturating_sub(1);
         meta_data.total_amount += total_amount;
 
         self.set_metadata(meta_data).await?;
@@ -586,7 +717,7 @@ where TBackend: WalletBackend + 'static
                 Some(peer) => match self.attempt_sync(peer.clone()).await {
                     Ok((total_scanned, final_utxo_pos, elapsed)) => {
                         debug!(target: LOG_TARGET, "Scanned to UTXO #{}", final_utxo_pos);
-                        self.finalize(total_scanned, final_utxo_pos, elapsed).await?;
+                        self.finalize(total_scanned, elapsed).await?;
                         return Ok(());
                     },
                     Err(e) => {
@@ -779,10 +910,39 @@ fn convert_response_to_transaction_outputs(
     Ok((outputs, current_utxo_index))
 }
 
+/// Estimate the time left from the rate at which outputs have been scanned so far
+fn estimate_time_remaining(elapsed: Duration, outputs_scanned: u64, outputs_remaining: u64) -> Option<Duration> {
+    if outputs_scanned == 0 {
+        return None;
+    }
+    Some(elapsed.mul_f64(outputs_remaining as f64 / outputs_scanned as f64))
+}
+
 #[derive(Default, Serialize, Deserialize)]
 struct ScanningMetadata {
     pub total_amount: MicroTari,
     pub number_of_utxos: u64,
     pub utxo_index: u64,
     pub height_hash: HashOutput,
+    /// The height of the block with `height_hash`, the last block that was scanned completely
+    #[serde(default)]
+    pub height: Option<u64>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_estimates_the_time_remaining() {
+        assert_eq!(estimate_time_remaining(Duration::from_secs(10), 0, 100), None);
+        assert_eq!(
+            estimate_time_remaining(Duration::from_secs(10), 100, 300),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            estimate_time_remaining(Duration::from_secs(10), 100, 0),
+            Some(Duration::from_secs(0))
+        );
+    }
 }
@@ -20,12 +20,8 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::{marker::PhantomData, sync::Arc};
+use std::sync::Arc;
 
-use aes_gcm::{
-    aead::{generic_array::GenericArray, NewAead},
-    Aes256Gcm,
-};
 use digest::Digest;
 use log::*;
 use rand::rngs::OsRng;
@@ -68,24 +64,43 @@ use tari_shutdown::ShutdownSignal;
 use crate::{
     base_node_service::{handle::BaseNodeServiceHandle, BaseNodeServiceInitializer},
     config::{WalletConfig, KEY_MANAGER_COMMS_SECRET_KEY_BRANCH_KEY},
-    connectivity_service::{WalletConnectivityHandle, WalletConnectivityInitializer, WalletConnectivityInterface},
-    contacts_service::{handle::ContactsServiceHandle, storage::database::ContactsBackend, ContactsServiceInitializer},
-    error::WalletError,
+    connectivity_service::{
+        BaseNodeHealth,
+        WalletConnectivityHandle,
+        WalletConnectivityInitializer,
+        WalletConnectivityInterface,
+    },
+    contacts_service::{
+        handle::ContactsServiceHandle,
+        storage::database::{ContactsBackend, ContactsDatabase},
+        ContactsServiceInitializer,
+    },
+    error::{WalletError, WalletStorageError},
     output_manager_service::{
         error::OutputManagerError,
         handle::OutputManagerHandle,
-        storage::{database::OutputManagerBackend, models::KnownOneSidedPaymentScript},
+        storage::{
+            database::{OutputManagerBackend, OutputManagerDatabase},
+            models::KnownOneSidedPaymentScript,
+        },
         OutputManagerServiceInitializer,
         TxId,
     },
-    storage::database::{WalletBackend, WalletDatabase},
+    storage::{
+        backup::{EncryptedWalletBackup, WalletBackup},
+        database::{WalletBackend, WalletDatabase},
+        key_derivation::PassphraseKeyDerivation,
+        sqlite_utilities::change_backends_passphrase,
+    },
     transaction_service::{
         handle::TransactionServiceHandle,
-        storage::database::TransactionBackend,
+        storage::database::{TransactionBackend, TransactionDatabase},
         TransactionServiceInitializer,
     },
     types::KeyDigest,
+    util::birthday::current_wallet_birthday,
     utxo_scanner_service::{handle::UtxoScannerHandle, UtxoScannerServiceInitializer},
+    WalletSqlite,
 };
 
 const LOG_TARGET: &str = "wallet";
@@ -106,9 +121,9 @@ pub struct Wallet<T, U, V, W> {
     pub updater_service: Option<SoftwareUpdaterHandle>,
     pub db: WalletDatabase<T>,
     pub factories: CryptoFactories,
-    _u: PhantomData<U>,
-    _v: PhantomData<V>,
-    _w: PhantomData<W>,
+    transaction_db: TransactionDatabase<U>,
+    output_manager_db: OutputManagerDatabase<V>,
+    contacts_db: ContactsDatabase<W>,
 }
 
 impl<T, U, V, W> Wallet<T, U, V, W>
@@ -141,6 +156,9 @@ where
         comms_config.node_identity = node_identity.clone();
 
         let bn_service_db = wallet_database.clone();
+        let transaction_db = TransactionDatabase::new(transaction_backend.clone());
+        let output_manager_db = OutputManagerDatabase::new(output_manager_backend.clone());
+        let contacts_db = ContactsDatabase::new(contacts_backend.clone());
 
         let factories = config.clone().factories;
         let (publisher, subscription_factory) = pubsub_connector(config.buffer_size, config.rate_limit);
@@ -259,9 +277,9 @@ where
             wallet_connectivity,
             db: wallet_database,
             factories,
-            _u: PhantomData,
-            _v: PhantomData,
-            _w: PhantomData,
+            transaction_db,
+            output_manager_db,
+            contacts_db,
         })
     }
 
@@ -304,6 +322,21 @@ where
         self.wallet_connectivity.get_current_base_node_peer()
     }
 
+    /// Set the base nodes the wallet fails over to when the current base node is unreachable or lags behind the
+    /// others, in order of preference
+    pub async fn set_base_node_pool(&mut self, peers: Vec<Peer>) -> Result<(), WalletError> {
+        for peer in &peers {
+            self.comms.peer_manager().add_peer(peer.clone()).await?;
+        }
+        self.wallet_connectivity.set_base_node_pool(peers).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_base_node_pool_status(&mut self) -> Result<Vec<BaseNodeHealth>, WalletError> {
+        Ok(self.wallet_connectivity.get_base_node_pool_status().await?)
+    }
+
     pub async fn check_for_update(&self) -> Option<String> {
         let mut updater = self.updater_service.clone().unwrap();
         debug!(
@@ -479,11 +512,13 @@ where
     /// in which case this will fail.
     pub async fn apply_encryption(&mut self, passphrase: String) -> Result<(), WalletError> {
         debug!(target: LOG_TARGET, "Applying wallet encryption.");
-        let passphrase_hash = Blake256::new().chain(passphrase.as_bytes()).finalize();
-        let key = GenericArray::from_slice(passphrase_hash.as_slice());
-        let cipher = Aes256Gcm::new(key);
+        let kdf = PassphraseKeyDerivation::new_argon2id();
+        let cipher = kdf.derive_cipher(&passphrase)?;
 
         self.db.apply_encryption(cipher.clone()).await?;
+        // The wallet backend refuses to encrypt an already encrypted database, so only store the scheme once it has
+        // accepted the new cipher
+        self.db.set_passphrase_key_derivation(kdf).await?;
         self.output_manager_service.apply_encryption(cipher.clone()).await?;
         self.transaction_service.apply_encryption(cipher).await?;
         Ok(())
@@ -495,9 +530,27 @@ where
         self.db.remove_encryption().await?;
         self.output_manager_service.remove_encryption().await?;
         self.transaction_service.remove_encryption().await?;
+        self.db.clear_passphrase_key_derivation().await?;
         Ok(())
     }
 
+    /// Check that the wallet is encrypted with the given passphrase
+    pub async fn verify_passphrase(&self, passphrase: String) -> Result<(), WalletError> {
+        Ok(self.db.verify_passphrase(passphrase).await?)
+    }
+
+    /// Create a full backup of the wallet encrypted with the given passphrase, see [WalletBackup] for its contents
+    pub async fn create_backup(&self, passphrase: String) -> Result<EncryptedWalletBackup, WalletError> {
+        let backup = WalletBackup::collect(
+            &self.db,
+            &self.transaction_db,
+            &self.output_manager_db,
+            &self.contacts_db,
+        )
+        .await?;
+        Ok(backup.encrypt(&passphrase)?)
+    }
+
     /// Utility function to find out if there is data in the database indicating that there is an incomplete recovery
     /// process in progress
     pub async fn is_recovery_in_progress(&self) -> Result<bool, WalletError> {
@@ -506,6 +559,29 @@ where
     }
 }
 
+impl WalletSqlite {
+    /// Encrypt the wallet database with a new passphrase, once the current passphrase has been confirmed. All the
+    /// records are re-encrypted in a single database transaction, so a failure leaves the wallet readable with the
+    /// current passphrase.
+    pub async fn change_passphrase(&self, passphrase: String, new_passphrase: String) -> Result<(), WalletError> {
+        let wallet_backend = self.db.backend().clone();
+        let transaction_backend = self.transaction_db.backend().clone();
+        let output_manager_backend = self.output_manager_db.backend().clone();
+        tokio::task::spawn_blocking(move || {
+            change_backends_passphrase(
+                &wallet_backend,
+                &transaction_backend,
+                &output_manager_backend,
+                &passphrase,
+                &new_passphrase,
+            )
+        })
+        .await
+        .map_err(|err| WalletStorageError::BlockingTaskSpawnError(err.to_string()))??;
+        Ok(())
+    }
+}
+
 async fn read_or_create_master_secret_key<T: WalletBackend + 'static>(
     recovery_master_key: Option<CommsSecretKey>,
     db: &mut WalletDatabase<T>,
@@ -517,6 +593,7 @@ async fn read_or_create_master_secret_key<T: WalletBackend + 'static>(
             None => {
                 let secret_key = CommsSecretKey::random(&mut OsRng);
                 db.set_master_secret_key(secret_key.clone()).await?;
+                db.set_wallet_birthday(current_wallet_birthday()).await?;
                 secret_key
             },
             Some(secret_key) => secret_key,
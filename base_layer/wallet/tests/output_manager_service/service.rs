@@ -335,6 +335,7 @@ async fn test_utxo_selection_no_chain_metadata() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap_err();
@@ -360,6 +361,7 @@ async fn test_utxo_selection_no_chain_metadata() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -429,6 +431,7 @@ async fn test_utxo_selection_with_chain_metadata() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap_err();
@@ -481,6 +484,7 @@ async fn test_utxo_selection_with_chain_metadata() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -505,6 +509,7 @@ async fn test_utxo_selection_with_chain_metadata() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -547,6 +552,7 @@ async fn send_not_enough_funds() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
     {
@@ -591,6 +597,7 @@ async fn send_no_change() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -637,6 +644,7 @@ async fn send_not_enough_for_change() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
     {
@@ -671,6 +679,7 @@ async fn cancel_transaction() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -746,6 +755,7 @@ async fn test_get_balance() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -790,6 +800,7 @@ async fn sending_transaction_with_short_term_clear() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -815,6 +826,7 @@ async fn sending_transaction_with_short_term_clear() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         )
         .await
         .unwrap();
@@ -830,6 +842,111 @@ async fn sending_transaction_with_short_term_clear() {
     assert_eq!(balance.pending_outgoing_balance, available_balance);
 }
 
+#[tokio::test]
+async fn accounts_have_separate_balances_and_history() {
+    let factories = CryptoFactories::default();
+    let (connection, _tempdir) = get_temp_sqlite_database_connection();
+    let backend = OutputManagerSqliteDatabase::new(connection, None);
+    let (mut oms, _, _shutdown, _, _, _, _, _) = setup_output_manager_service(backend.clone(), true).await;
+
+    let output_val = 10_000 * uT;
+    let (_ti, uo) = make_input(&mut OsRng.clone(), output_val, &factories.commitment);
+    oms.add_output(uo).await.unwrap();
+    let (_ti, uo) = make_input(&mut OsRng.clone(), output_val, &factories.commitment);
+    oms.add_output(uo).await.unwrap();
+
+    let account = oms.create_account("savings".to_string()).await.unwrap();
+    assert_eq!(account.id, 1);
+    assert!(matches!(
+        oms.create_account("savings".to_string()).await,
+        Err(OutputManagerError::OutputManagerStorageError(
+            OutputManagerStorageError::DuplicateAccount(_)
+        ))
+    ));
+    assert!(oms.create_account("default".to_string()).await.is_err());
+    let accounts = oms.get_accounts().await.unwrap();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].name, "default");
+    assert_eq!(accounts[1], account);
+
+    // The new account has nothing to spend
+    let balance = oms.get_account_balance(account.id).await.unwrap();
+    assert_eq!(balance.available_balance, MicroTari::from(0));
+    assert!(matches!(
+        oms.prepare_account_transaction_to_send(
+            account.id,
+            OsRng.next_u64(),
+            MicroTari::from(1000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+            OutputFeatures::default(),
+        )
+        .await,
+        Err(OutputManagerError::NotEnoughFunds)
+    ));
+
+    let transfer_value = 5_000 * uT;
+    let tx_id = OsRng.next_u64();
+    let (_fee, tx) = oms
+        .create_account_transfer_transaction(
+            tx_id,
+            0,
+            account.id,
+            transfer_value,
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(tx.body.inputs().len(), 1);
+
+    let balance = oms.get_account_balance(account.id).await.unwrap();
+    assert_eq!(balance.pending_incoming_balance, transfer_value);
+    assert_eq!(balance.pending_outgoing_balance, MicroTari::from(0));
+    let balance = oms.get_account_balance(0).await.unwrap();
+    assert_eq!(balance.available_balance, output_val);
+    assert_eq!(balance.pending_outgoing_balance, output_val);
+    // The wallet balance still covers every account
+    let balance = oms.get_balance().await.unwrap();
+    assert_eq!(
+        balance.pending_incoming_balance,
+        oms.get_account_balance(0).await.unwrap().pending_incoming_balance + transfer_value
+    );
+
+    // Funds that are still arriving in the account cannot be spent yet
+    assert!(matches!(
+        oms.prepare_account_transaction_to_send(
+            account.id,
+            OsRng.next_u64(),
+            MicroTari::from(1000),
+            MicroTari::from(20),
+            None,
+            "".to_string(),
+            script!(Nop),
+            OutputFeatures::default(),
+        )
+        .await,
+        Err(OutputManagerError::FundsPending)
+    ));
+
+    assert_eq!(oms.get_account_transaction_ids(account.id).await.unwrap(), vec![tx_id]);
+    assert_eq!(oms.get_account_transaction_ids(0).await.unwrap(), vec![tx_id]);
+    assert!(matches!(
+        oms.get_account_balance(42).await,
+        Err(OutputManagerError::AccountNotFound(_))
+    ));
+
+    // Accounts and their key chains are loaded again after a restart
+    drop(oms);
+    let (mut oms, _, _shutdown, _, _, _, _, _) = setup_output_manager_service(backend, true).await;
+    assert_eq!(oms.get_accounts().await.unwrap().len(), 2);
+    let balance = oms.get_account_balance(account.id).await.unwrap();
+    assert_eq!(balance.pending_incoming_balance, transfer_value);
+}
+
 #[tokio::test]
 async fn coin_split_with_change() {
     let factories = CryptoFactories::default();
@@ -1053,6 +1170,7 @@ async fn test_txo_validation() {
         None,
         "".to_string(),
         script!(Nop),
+        OutputFeatures::default(),
     )
     .await
     .unwrap();
@@ -99,6 +99,7 @@ pub struct BaseNodeWalletRpcMockState {
     synced: Arc<Mutex<bool>>,
     utxos: Arc<Mutex<Vec<TransactionOutput>>>,
     blocks: Arc<Mutex<HashMap<u64, BlockHeader>>>,
+    mempool_stats_response: Arc<Mutex<proto::mempool::StatsResponse>>,
 }
 
 #[allow(clippy::mutex_atomic)]
@@ -158,6 +159,13 @@ impl BaseNodeWalletRpcMockState {
             synced: Arc::new(Mutex::new(true)),
             utxos: Arc::new(Mutex::new(Vec::new())),
             blocks: Arc::new(Mutex::new(Default::default())),
+            mempool_stats_response: Arc::new(Mutex::new(proto::mempool::StatsResponse {
+                total_txs: 0,
+                unconfirmed_txs: 0,
+                reorg_txs: 0,
+                total_weight: 0,
+                min_fee_per_gram: 0,
+            })),
         }
     }
 
@@ -166,6 +174,11 @@ impl BaseNodeWalletRpcMockState {
         *lock = response;
     }
 
+    pub fn set_mempool_stats_response(&self, response: proto::mempool::StatsResponse) {
+        let mut lock = acquire_lock!(self.mempool_stats_response);
+        *lock = response;
+    }
+
     pub fn set_submit_transaction_response(&self, response: TxSubmissionResponse) {
         let mut lock = acquire_lock!(self.submit_transaction_response);
         *lock = response;
@@ -643,6 +656,11 @@ impl BaseNodeWalletService for BaseNodeWalletRpcMockService {
             Err(RpcStatus::not_found("Header not found"))
         }
     }
+
+    async fn get_mempool_stats(&self, _: Request<()>) -> Result<Response<proto::mempool::StatsResponse>, RpcStatus> {
+        let lock = acquire_lock!(self.state.mempool_stats_response);
+        Ok(Response::new(lock.clone()))
+    }
 }
 
 #[cfg(test)]
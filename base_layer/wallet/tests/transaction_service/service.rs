@@ -95,6 +95,7 @@ use tari_crypto::{
     script,
     script::{ExecutionStack, TariScript},
 };
+use tari_key_manager::key_manager::KeyManager;
 use tari_p2p::{comms_connector::pubsub_connector, domain_message::DomainMessage, Network};
 use tari_service_framework::{reply_channel, RegisterHandle, StackBuilder};
 use tari_shutdown::{Shutdown, ShutdownSignal};
@@ -104,6 +105,7 @@ use tari_wallet::{
         config::BaseNodeServiceConfig,
         handle::{BaseNodeEvent, BaseNodeServiceHandle},
         mock_base_node_service::MockBaseNodeService,
+        service::BaseNodeState,
         BaseNodeServiceInitializer,
     },
     connectivity_service::{
@@ -131,9 +133,12 @@ use tari_wallet::{
     },
     test_utils::make_wallet_database_connection,
     transaction_service::{
-        config::TransactionServiceConfig,
-        error::TransactionServiceError,
+        acceptance_policy::StandardAcceptancePolicy,
+        config::{TransactionRoutingMechanism, TransactionServiceConfig},
+        error::{MultisigError, OfflineSigningError, TransactionServiceError},
         handle::{TransactionEvent, TransactionServiceHandle},
+        multisig::{MultisigOutputStatus, MultisigPackage},
+        offline_signing::{OfflinePackage, OfflineSigningOutcome},
         service::TransactionService,
         storage::{
             database::{DbKeyValuePair, TransactionBackend, TransactionDatabase, WriteOperation},
@@ -141,7 +146,9 @@ use tari_wallet::{
                 CompletedTransaction,
                 InboundTransaction,
                 OutboundTransaction,
+                PaymentRequestStatus,
                 TransactionDirection,
+                TransactionSchedule,
                 TransactionStatus,
             },
             sqlite_db::TransactionServiceSqliteDatabase,
@@ -1409,6 +1416,7 @@ fn finalize_tx_with_incorrect_pubkey() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         ))
         .unwrap();
     let msg = stp.build_single_round_message().unwrap();
@@ -1539,6 +1547,7 @@ fn finalize_tx_with_missing_output() {
             None,
             "".to_string(),
             script!(Nop),
+            OutputFeatures::default(),
         ))
         .unwrap();
     let msg = stp.build_single_round_message().unwrap();
@@ -1946,6 +1955,386 @@ fn test_set_num_confirmations() {
     }
 }
 
+#[test]
+fn test_payment_request_matching() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let (connection, _temp_dir) = make_wallet_database_connection(None);
+
+    let (mut ts, _, _, _, _, _, _, _, _shutdown, _, _, _, _, _, _rpc_server_connection) =
+        setup_transaction_service_no_comms(&mut runtime, factories, connection, None);
+    let mut event_stream = ts.get_event_stream();
+
+    let request = runtime
+        .block_on(ts.create_payment_request(MicroTari::from(5000), "Invoice 1".to_string(), 1000))
+        .unwrap();
+    let other_request = runtime
+        .block_on(ts.create_payment_request(MicroTari::from(3000), "Invoice 2".to_string(), 1000))
+        .unwrap();
+    assert!(request.is_valid());
+    assert_eq!(runtime.block_on(ts.get_payment_requests()).unwrap().len(), 2);
+
+    // A payment carrying the reference is matched even if it does not cover the full amount
+    let source_public_key = PublicKey::from_secret_key(&PrivateKey::random(&mut OsRng));
+    let tx_id = runtime
+        .block_on(ts.import_utxo(
+            MicroTari::from(3000),
+            source_public_key.clone(),
+            request.payment_message(),
+            None,
+        ))
+        .unwrap();
+    let record = runtime.block_on(ts.get_payment_request(request.reference)).unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::PartlyPaid);
+    assert_eq!(record.payments, vec![tx_id]);
+    assert_eq!(
+        runtime
+            .block_on(ts.get_payment_request(other_request.reference))
+            .unwrap()
+            .status,
+        PaymentRequestStatus::Open
+    );
+
+    // A payment without a reference is matched to the oldest open request with that amount outstanding
+    runtime
+        .block_on(ts.import_utxo(
+            MicroTari::from(2000),
+            source_public_key.clone(),
+            "One-sided payment".to_string(),
+            None,
+        ))
+        .unwrap();
+    let record = runtime.block_on(ts.get_payment_request(request.reference)).unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::Paid);
+    assert_eq!(record.amount_received, MicroTari::from(5000));
+
+    runtime
+        .block_on(ts.import_utxo(
+            MicroTari::from(1234),
+            source_public_key,
+            "Unrelated payment".to_string(),
+            None,
+        ))
+        .unwrap();
+    assert_eq!(
+        runtime
+            .block_on(ts.get_payment_request(other_request.reference))
+            .unwrap()
+            .status,
+        PaymentRequestStatus::Open
+    );
+
+    runtime.block_on(async move {
+        let delay = sleep(Duration::from_secs(10));
+        tokio::pin!(delay);
+        let mut updates = 0;
+        loop {
+            tokio::select! {
+                event = event_stream.recv() => {
+                    if let TransactionEvent::PaymentRequestUpdated(reference) = &*event.unwrap() {
+                        assert_eq!(*reference, request.reference);
+                        updates += 1;
+                        if updates >= 2 {
+                            break;
+                        }
+                    }
+                },
+                () = &mut delay => {
+                    break;
+                },
+            }
+        }
+        assert_eq!(updates, 2);
+    });
+}
+
+#[test]
+fn test_incoming_transaction_acceptance_policy() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let alice_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+
+    let (alice_connection, _tempdir) = make_wallet_database_connection(None);
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _,
+        _,
+        _,
+        _,
+        mut alice_tx_cancelled_sender,
+        _shutdown,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _rpc_server_connection,
+    ) = setup_transaction_service_no_comms(
+        &mut runtime,
+        factories.clone(),
+        alice_connection,
+        Some(TransactionServiceConfig {
+            transaction_routing_mechanism: TransactionRoutingMechanism::DirectOnly,
+            ..Default::default()
+        }),
+    );
+
+    let (bob_connection, _tempdir) = make_wallet_database_connection(None);
+    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _shutdown, _, _, _, _, _, _rpc) =
+        setup_transaction_service_no_comms(
+            &mut runtime,
+            factories.clone(),
+            bob_connection,
+            Some(TransactionServiceConfig {
+                transaction_routing_mechanism: TransactionRoutingMechanism::DirectOnly,
+                ..Default::default()
+            }),
+        );
+    let mut bob_event_stream = bob_ts.get_event_stream();
+    runtime
+        .block_on(
+            bob_ts.set_acceptance_policy(Arc::new(
+                StandardAcceptancePolicy::new()
+                    .with_minimum_amount(5000 * uT)
+                    .with_approval_threshold(10000 * uT),
+            )),
+        )
+        .unwrap();
+
+    for _ in 0..2 {
+        let (_utxo, uo) = make_input(&mut OsRng, 250000 * uT, &factories.commitment);
+        runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+    }
+    let mut sender_messages = Vec::new();
+    for amount in &[10000 * uT, 1000 * uT] {
+        let tx_id = runtime
+            .block_on(alice_ts.send_transaction(
+                bob_node_identity.public_key().clone(),
+                *amount,
+                100 * uT,
+                "Testing Message".to_string(),
+            ))
+            .unwrap();
+        alice_outbound_service
+            .wait_call_count(1, Duration::from_secs(30))
+            .expect("Alice call wait");
+        let call = alice_outbound_service.pop_call().unwrap();
+        sender_messages.push((tx_id, try_decode_sender_message(call.1.to_vec()).unwrap()));
+    }
+    let (held_tx_id, held_message) = sender_messages.remove(0);
+    let (rejected_tx_id, rejected_message) = sender_messages.remove(0);
+
+    // The large transaction is held and Bob does not reply to it yet
+    runtime
+        .block_on(bob_tx_sender.send(create_dummy_message(
+            held_message.into(),
+            alice_node_identity.public_key(),
+        )))
+        .unwrap();
+    runtime.block_on(async {
+        let delay = sleep(Duration::from_secs(30));
+        tokio::pin!(delay);
+        loop {
+            tokio::select! {
+                event = bob_event_stream.recv() => {
+                    if let TransactionEvent::TransactionHeldForApproval(tx_id) = &*event.unwrap() {
+                        assert_eq!(*tx_id, held_tx_id);
+                        break;
+                    }
+                },
+                () = &mut delay => {
+                    panic!("Transaction was not held for approval");
+                },
+            }
+        }
+    });
+    assert_eq!(bob_outbound_service.call_count(), 0);
+    let held = runtime.block_on(bob_ts.get_held_transactions()).unwrap();
+    assert_eq!(held.len(), 1);
+    assert_eq!(held[0].tx_id, held_tx_id);
+    assert_eq!(held[0].amount, 10000 * uT);
+
+    // The small transaction is rejected and Alice cancels it when she receives the rejection
+    runtime
+        .block_on(bob_tx_sender.send(create_dummy_message(
+            rejected_message.into(),
+            alice_node_identity.public_key(),
+        )))
+        .unwrap();
+    // The rejection is sent both directly and via store and forward
+    bob_outbound_service
+        .wait_call_count(2, Duration::from_secs(30))
+        .expect("Bob call wait 1");
+    let call = bob_outbound_service.take_calls().remove(0);
+    let rejection = try_decode_transaction_cancelled_message(call.1.to_vec()).unwrap();
+    assert_eq!(rejection.tx_id, rejected_tx_id);
+
+    runtime
+        .block_on(alice_tx_cancelled_sender.send(create_dummy_message(rejection, bob_node_identity.public_key())))
+        .unwrap();
+    runtime.block_on(async {
+        let delay = sleep(Duration::from_secs(30));
+        tokio::pin!(delay);
+        loop {
+            tokio::select! {
+                _ = sleep(Duration::from_millis(100)) => {
+                    let pending = alice_ts.get_pending_outbound_transactions().await.unwrap();
+                    if !pending.contains_key(&rejected_tx_id) {
+                        assert!(pending.contains_key(&held_tx_id));
+                        break;
+                    }
+                },
+                () = &mut delay => {
+                    panic!("Rejected transaction was not cancelled by the sender");
+                },
+            }
+        }
+    });
+
+    // Approving the held transaction sends the reply
+    runtime.block_on(bob_ts.approve_held_transaction(held_tx_id)).unwrap();
+    bob_outbound_service
+        .wait_call_count(1, Duration::from_secs(30))
+        .expect("Bob call wait 2");
+    let call = bob_outbound_service.pop_call().unwrap();
+    let reply = try_decode_transaction_reply_message(call.1.to_vec()).unwrap();
+    assert_eq!(reply.tx_id, held_tx_id);
+    assert!(runtime.block_on(bob_ts.get_held_transactions()).unwrap().is_empty());
+    assert!(runtime.block_on(bob_ts.approve_held_transaction(held_tx_id)).is_err());
+}
+
+#[test]
+fn test_held_transactions_are_capped() {
+    let factories = CryptoFactories::default();
+    let mut runtime = Runtime::new().unwrap();
+
+    let alice_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+
+    let (alice_connection, _tempdir) = make_wallet_database_connection(None);
+    let (
+        mut alice_ts,
+        mut alice_output_manager,
+        alice_outbound_service,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _shutdown,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _rpc_server_connection,
+    ) = setup_transaction_service_no_comms(
+        &mut runtime,
+        factories.clone(),
+        alice_connection,
+        Some(TransactionServiceConfig {
+            transaction_routing_mechanism: TransactionRoutingMechanism::DirectOnly,
+            ..Default::default()
+        }),
+    );
+
+    let (bob_connection, _tempdir) = make_wallet_database_connection(None);
+    let (mut bob_ts, _, bob_outbound_service, mut bob_tx_sender, _, _, _, _, _shutdown, _, _, _, _, _, _rpc) =
+        setup_transaction_service_no_comms(
+            &mut runtime,
+            factories.clone(),
+            bob_connection,
+            Some(TransactionServiceConfig {
+                transaction_routing_mechanism: TransactionRoutingMechanism::DirectOnly,
+                max_held_transactions: 1,
+                ..Default::default()
+            }),
+        );
+    let mut bob_event_stream = bob_ts.get_event_stream();
+    runtime
+        .block_on(bob_ts.set_acceptance_policy(Arc::new(
+            StandardAcceptancePolicy::new().with_approval_threshold(0 * uT),
+        )))
+        .unwrap();
+
+    for _ in 0..2 {
+        let (_utxo, uo) = make_input(&mut OsRng, 250000 * uT, &factories.commitment);
+        runtime.block_on(alice_output_manager.add_output(uo)).unwrap();
+    }
+    let mut sender_messages = Vec::new();
+    for _ in 0..2 {
+        let tx_id = runtime
+            .block_on(alice_ts.send_transaction(
+                bob_node_identity.public_key().clone(),
+                10000 * uT,
+                100 * uT,
+                "Testing Message".to_string(),
+            ))
+            .unwrap();
+        alice_outbound_service
+            .wait_call_count(1, Duration::from_secs(30))
+            .expect("Alice call wait");
+        let call = alice_outbound_service.pop_call().unwrap();
+        sender_messages.push((tx_id, try_decode_sender_message(call.1.to_vec()).unwrap()));
+    }
+    let (held_tx_id, held_message) = sender_messages.remove(0);
+    let (rejected_tx_id, rejected_message) = sender_messages.remove(0);
+
+    runtime
+        .block_on(bob_tx_sender.send(create_dummy_message(
+            held_message.into(),
+            alice_node_identity.public_key(),
+        )))
+        .unwrap();
+    runtime.block_on(async {
+        let delay = sleep(Duration::from_secs(30));
+        tokio::pin!(delay);
+        loop {
+            tokio::select! {
+                event = bob_event_stream.recv() => {
+                    if let TransactionEvent::TransactionHeldForApproval(tx_id) = &*event.unwrap() {
+                        assert_eq!(*tx_id, held_tx_id);
+                        break;
+                    }
+                },
+                () = &mut delay => {
+                    panic!("Transaction was not held for approval");
+                },
+            }
+        }
+    });
+
+    // Once the cap is reached further transactions are rejected instead of held
+    runtime
+        .block_on(bob_tx_sender.send(create_dummy_message(
+            rejected_message.into(),
+            alice_node_identity.public_key(),
+        )))
+        .unwrap();
+    bob_outbound_service
+        .wait_call_count(2, Duration::from_secs(30))
+        .expect("Bob call wait");
+    let call = bob_outbound_service.take_calls().remove(0);
+    let rejection = try_decode_transaction_cancelled_message(call.1.to_vec()).unwrap();
+    assert_eq!(rejection.tx_id, rejected_tx_id);
+
+    let held = runtime.block_on(bob_ts.get_held_transactions()).unwrap();
+    assert_eq!(held.len(), 1);
+    assert_eq!(held[0].tx_id, held_tx_id);
+
+    runtime.block_on(bob_ts.reject_held_transaction(held_tx_id)).unwrap();
+    assert!(runtime.block_on(bob_ts.get_held_transactions()).unwrap().is_empty());
+}
+
 #[test]
 #[ignore = "test is flaky"]
 fn test_transaction_cancellation() {
@@ -5173,3 +5562,955 @@ fn dont_broadcast_invalid_transactions() {
         runtime.block_on(rpc_service_state.wait_pop_submit_transaction_calls(1, Duration::from_secs(5)));
     assert!(tx_submit_calls.is_err(), "Should be no calls made");
 }
+
+/// Add an output to the wallet whose keys are derived from the wallet's key chain, so that an offline signer holding
+/// the same seed can sign for it
+async fn add_key_chain_output(oms: &mut OutputManagerHandle, value: MicroTari) {
+    let (index, script_private_key) = oms.get_next_script_key().await.unwrap();
+    let key_manager = KeyManager::<PrivateKey, Blake256>::from(CommsSecretKey::default(), "".to_string(), 0);
+    let mut params = TestParamsHelpers::new();
+    params.spend_key = key_manager.derive_key(index).unwrap().k;
+    params.script_private_key = script_private_key;
+    oms.add_output(create_unblinded_output(
+        script!(Nop),
+        OutputFeatures::default(),
+        params,
+        value,
+    ))
+    .await
+    .unwrap();
+}
+
+#[test]
+fn offline_signing_one_sided_round_trip() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let (online_connection, _online_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut online_ts,
+        mut online_oms,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _online_shutdown,
+        _online_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _online_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), online_connection, None);
+    // Both services are created with the same master key so the signer can derive the keys of the online wallet
+    let (signer_connection, _signer_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut signer_ts,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _signer_shutdown,
+        _signer_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _signer_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), signer_connection, None);
+
+    runtime.block_on(async move {
+        let derivable_value = MicroTari::from(10_000);
+        add_key_chain_output(&mut online_oms, derivable_value).await;
+        // The smaller output would be selected first but it cannot be signed for offline
+        let foreign_value = MicroTari::from(6_000);
+        let (_utxo, foreign_output) = make_input(&mut OsRng, foreign_value, &factories.commitment);
+        online_oms.add_output(foreign_output).await.unwrap();
+
+        let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+        let amount = MicroTari::from(5_000);
+        let unsigned = online_ts
+            .prepare_offline_transaction(destination, amount, 5.into(), "offline".to_string(), true)
+            .await
+            .unwrap();
+        let tx_id = unsigned.tx_id;
+        assert_eq!(unsigned.inputs.len(), 1);
+        assert_eq!(unsigned.inputs[0].value, derivable_value);
+        assert!(unsigned.change_key_index.is_some());
+
+        let json = OfflinePackage::Unsigned(unsigned).to_json().unwrap();
+        let unsigned = match OfflinePackage::from_json(&json).unwrap() {
+            OfflinePackage::Unsigned(unsigned) => unsigned,
+            _ => panic!("Expected an unsigned package"),
+        };
+
+        // The signer can check what the package pays before signing it
+        let summary = signer_ts.summarize_offline_transaction(unsigned.clone()).await.unwrap();
+        assert_eq!(summary.tx_id, tx_id);
+        assert_eq!(summary.destination_public_key, unsigned.destination_public_key);
+        assert_eq!(summary.amount, amount);
+        assert_eq!(summary.total_inputs, derivable_value);
+        assert_eq!(summary.change, derivable_value - amount - summary.fee);
+        assert!(summary.one_sided);
+
+        let signed = match signer_ts.sign_offline_transaction(unsigned).await.unwrap() {
+            OfflineSigningOutcome::Signed(signed) => *signed,
+            OfflineSigningOutcome::AwaitingReply(_) => panic!("A one-sided transaction should be signed immediately"),
+        };
+        assert_eq!(signed.fee, summary.fee);
+        online_ts.submit_offline_transaction(signed.clone()).await.unwrap();
+
+        let completed_tx = online_ts.get_completed_transaction(tx_id).await.unwrap();
+        assert_eq!(completed_tx.amount, amount);
+        let balance = online_oms.get_balance().await.unwrap();
+        assert_eq!(balance.available_balance, foreign_value);
+        assert_eq!(balance.pending_incoming_balance, derivable_value - amount - signed.fee);
+
+        // Submitting the transaction again must not release its inputs
+        assert!(online_ts.submit_offline_transaction(signed).await.is_err());
+        let balance = online_oms.get_balance().await.unwrap();
+        assert_eq!(balance.available_balance, foreign_value);
+        assert_eq!(balance.pending_outgoing_balance, derivable_value);
+    });
+}
+
+#[test]
+fn offline_signing_interactive_round_trip() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let (online_connection, _online_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut online_ts,
+        mut online_oms,
+        online_outbound_service,
+        _,
+        mut online_tx_ack_sender,
+        _,
+        _,
+        _,
+        _online_shutdown,
+        _online_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _online_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), online_connection, None);
+    let (signer_connection, _signer_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut signer_ts,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _signer_shutdown,
+        _signer_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _signer_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), signer_connection, None);
+
+    let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+    let amount = MicroTari::from(5_000);
+    let sender_message = runtime.block_on(async {
+        add_key_chain_output(&mut online_oms, MicroTari::from(10_000)).await;
+        let unsigned = online_ts
+            .prepare_offline_transaction(destination.clone(), amount, 5.into(), "offline".to_string(), false)
+            .await
+            .unwrap();
+        let sender_message = match signer_ts.sign_offline_transaction(unsigned).await.unwrap() {
+            OfflineSigningOutcome::AwaitingReply(sender_message) => *sender_message,
+            OfflineSigningOutcome::Signed(_) => panic!("An interactive transaction needs the recipient's reply"),
+        };
+        online_ts
+            .relay_offline_sender_message(sender_message.clone())
+            .await
+            .unwrap();
+        sender_message
+    });
+    let tx_id = sender_message.tx_id;
+    online_outbound_service
+        .wait_call_count(1, Duration::from_secs(60))
+        .unwrap();
+
+    let recipient = TestParams::new(&mut OsRng);
+    let rtp = ReceiverTransactionProtocol::new(
+        TransactionSenderMessage::Single(Box::new(sender_message.sender_message)),
+        recipient.nonce,
+        recipient.spend_key,
+        OutputFeatures::default(),
+        &factories,
+    );
+    let reply = rtp.get_signed_data().unwrap().clone();
+
+    runtime.block_on(async move {
+        // A reply that is not from the recipient is ignored
+        let (_, impostor) = PublicKey::random_keypair(&mut OsRng);
+        online_tx_ack_sender
+            .send(create_dummy_message(reply.clone().into(), &impostor))
+            .await
+            .unwrap();
+        online_tx_ack_sender
+            .send(create_dummy_message(reply.clone().into(), &destination))
+            .await
+            .unwrap();
+
+        let mut exported = None;
+        for _ in 0..50 {
+            if let Ok(r) = online_ts.get_offline_recipient_reply(tx_id).await {
+                exported = Some(r);
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        let exported = exported.expect("The recipient's reply should have been stored");
+        assert_eq!(exported.reply.public_spend_key, reply.public_spend_key);
+
+        let mut tampered = exported.clone();
+        let (_, tampered_key) = PublicKey::random_keypair(&mut OsRng);
+        tampered.reply.public_spend_key = tampered_key;
+        assert!(signer_ts.finalize_offline_transaction(tampered).await.is_err());
+
+        let mut mismatched = exported.clone();
+        mismatched.tx_id = tx_id.wrapping_add(1);
+        assert!(matches!(
+            signer_ts.finalize_offline_transaction(mismatched).await,
+            Err(TransactionServiceError::OfflineSigningError(
+                OfflineSigningError::SigningStateNotFound(_)
+            ))
+        ));
+
+        // The signing state is kept when finalizing fails so the genuine reply can still be used
+        let signed = signer_ts.finalize_offline_transaction(exported.clone()).await.unwrap();
+        assert_eq!(signed.amount, amount);
+        assert!(matches!(
+            signer_ts.finalize_offline_transaction(exported).await,
+            Err(TransactionServiceError::OfflineSigningError(
+                OfflineSigningError::SigningStateNotFound(_)
+            ))
+        ));
+
+        online_ts.submit_offline_transaction(signed).await.unwrap();
+        let completed_tx = online_ts.get_completed_transaction(tx_id).await.unwrap();
+        assert_eq!(completed_tx.destination_public_key, destination);
+    });
+}
+
+#[test]
+fn offline_signing_cancel_and_no_change() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let (online_connection, _online_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut online_ts,
+        mut online_oms,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _online_shutdown,
+        _online_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _online_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), online_connection, None);
+    let (signer_connection, _signer_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut signer_ts,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _signer_shutdown,
+        _signer_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _signer_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories, signer_connection, None);
+
+    runtime.block_on(async move {
+        let value = MicroTari::from(10_000);
+        let fee_per_gram = MicroTari::from(5);
+        add_key_chain_output(&mut online_oms, value).await;
+        let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+
+        let unsigned = online_ts
+            .prepare_offline_transaction(
+                destination.clone(),
+                MicroTari::from(5_000),
+                fee_per_gram,
+                "offline".to_string(),
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            online_oms.get_balance().await.unwrap().available_balance,
+            MicroTari::from(0)
+        );
+
+        online_ts.cancel_offline_transaction(unsigned.tx_id).await.unwrap();
+        assert_eq!(online_oms.get_balance().await.unwrap().available_balance, value);
+
+        // The change left over is less than the fee of a change output, so the signer drops the change output even
+        // though a change key was reserved
+        let amount = value - Fee::calculate(fee_per_gram, 1, 1, 1) - MicroTari::from(1);
+        let unsigned = online_ts
+            .prepare_offline_transaction(destination, amount, fee_per_gram, "offline".to_string(), true)
+            .await
+            .unwrap();
+        assert!(unsigned.change_key_index.is_some());
+        let signed = match signer_ts.sign_offline_transaction(unsigned).await.unwrap() {
+            OfflineSigningOutcome::Signed(signed) => *signed,
+            OfflineSigningOutcome::AwaitingReply(_) => panic!("A one-sided transaction should be signed immediately"),
+        };
+        assert_eq!(signed.transaction.body.outputs().len(), 1);
+
+        online_ts.submit_offline_transaction(signed).await.unwrap();
+        let balance = online_oms.get_balance().await.unwrap();
+        assert_eq!(balance.available_balance, MicroTari::from(0));
+        assert_eq!(balance.pending_incoming_balance, MicroTari::from(0));
+        assert_eq!(balance.pending_outgoing_balance, value);
+    });
+}
+
+#[test]
+fn multisig_spend_through_transaction_service() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let (coordinator_connection, _coordinator_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut coordinator_ts,
+        mut coordinator_oms,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _coordinator_shutdown,
+        _coordinator_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _coordinator_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), coordinator_connection, None);
+    let (signer_connection, _signer_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut signer_ts,
+        mut signer_oms,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _signer_shutdown,
+        _signer_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _signer_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), signer_connection, None);
+
+    runtime.block_on(async move {
+        // Both services are created with the same master key, so the signer skips the script key that the coordinator
+        // uses as its key share
+        let coordinator = coordinator_ts.create_multisig_participant().await.unwrap();
+        signer_oms.get_next_script_key().await.unwrap();
+        let signer = signer_ts.create_multisig_participant().await.unwrap();
+        assert_ne!(coordinator.public_key, signer.public_key);
+
+        let wallet = coordinator_ts
+            .create_multisig_wallet(2, vec![coordinator.clone(), signer.clone()])
+            .await
+            .unwrap();
+        signer_ts.import_multisig_wallet(wallet.clone()).await.unwrap();
+
+        let (_utxo, funds) = make_input(&mut OsRng, MicroTari::from(200_000), &factories.commitment);
+        coordinator_oms.add_output(funds).await.unwrap();
+        let funding = coordinator_ts
+            .send_to_multisig(wallet.id, MicroTari::from(100_000), 5.into(), "funding".to_string())
+            .await
+            .unwrap();
+        assert_eq!(funding.outputs.len(), 1);
+        assert_eq!(signer_ts.import_multisig_update(funding).await.unwrap(), 1);
+
+        let signers = vec![coordinator.public_key.clone(), signer.public_key.clone()];
+        let (_, destination) = PublicKey::random_keypair(&mut OsRng);
+        let amount = MicroTari::from(40_000);
+        let proposal = coordinator_ts
+            .propose_multisig_spend(
+                wallet.id,
+                signers,
+                destination.clone(),
+                amount,
+                5.into(),
+                "Escrow release".to_string(),
+            )
+            .await
+            .unwrap();
+        let session_id = proposal.session_id;
+        let outputs = coordinator_ts.get_multisig_outputs(wallet.id).await.unwrap();
+        assert_eq!(outputs[0].status, MultisigOutputStatus::PendingSpend(session_id));
+
+        let coordinator_commitment = coordinator_ts.commit_multisig_nonces(proposal.clone()).await.unwrap();
+        let signer_commitment = signer_ts.commit_multisig_nonces(proposal).await.unwrap();
+
+        // No nonce request is produced, and no nonces are accepted, until every signer has committed
+        assert!(coordinator_ts
+            .add_multisig_nonce_commitment(coordinator_commitment)
+            .await
+            .unwrap()
+            .is_none());
+        let json = MultisigPackage::NonceCommitment(signer_commitment).to_json().unwrap();
+        let signer_commitment = match MultisigPackage::from_json(&json).unwrap() {
+            MultisigPackage::NonceCommitment(commitment) => commitment,
+            _ => panic!("Expected a nonce commitment package"),
+        };
+        let nonce_request = coordinator_ts
+            .add_multisig_nonce_commitment(signer_commitment)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let coordinator_nonces = coordinator_ts
+            .reveal_multisig_nonces(nonce_request.clone())
+            .await
+            .unwrap();
+        let signer_nonces = signer_ts.reveal_multisig_nonces(nonce_request.clone()).await.unwrap();
+
+        // A signer does not accept a different set of commitments once its nonces have been revealed
+        let mut replaced = nonce_request;
+        replaced.commitments[0].commitment = vec![0u8; 32];
+        assert!(matches!(
+            signer_ts.reveal_multisig_nonces(replaced).await,
+            Err(TransactionServiceError::MultisigError(
+                MultisigError::InvalidNonceCommitments
+            ))
+        ));
+
+        // Nonces that differ from the committed ones are rejected
+        let mut tampered = signer_nonces.clone();
+        tampered.metadata_nonces[0] = PublicKey::random_keypair(&mut OsRng).1;
+        assert!(matches!(
+            coordinator_ts.add_multisig_nonces(tampered).await,
+            Err(TransactionServiceError::MultisigError(MultisigError::InvalidNonces))
+        ));
+
+        assert!(coordinator_ts
+            .add_multisig_nonces(coordinator_nonces)
+            .await
+            .unwrap()
+            .is_none());
+        let request = coordinator_ts
+            .add_multisig_nonces(signer_nonces)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let coordinator_signatures = coordinator_ts.sign_multisig_request(request.clone()).await.unwrap();
+        let signer_signatures = signer_ts.sign_multisig_request(request.clone()).await.unwrap();
+        // The signing session is consumed, so the nonces can not be used for a second signature
+        assert!(matches!(
+            signer_ts.sign_multisig_request(request).await,
+            Err(TransactionServiceError::MultisigError(MultisigError::SessionNotFound(
+                _
+            )))
+        ));
+
+        assert!(coordinator_ts
+            .add_multisig_partial_signatures(coordinator_signatures)
+            .await
+            .unwrap()
+            .is_none());
+        let update = coordinator_ts
+            .add_multisig_partial_signatures(signer_signatures)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.tx_id, session_id);
+        assert_eq!(update.outputs.len(), 1);
+
+        let completed_tx = coordinator_ts.get_completed_transaction(session_id).await.unwrap();
+        assert_eq!(completed_tx.destination_public_key, destination);
+        assert_eq!(completed_tx.amount, amount);
+        assert_eq!(completed_tx.transaction.body.inputs().len(), 1);
+
+        assert_eq!(signer_ts.import_multisig_update(update).await.unwrap(), 1);
+        let outputs = signer_ts.get_multisig_outputs(wallet.id).await.unwrap();
+        assert_eq!(outputs[0].status, MultisigOutputStatus::Spent);
+        assert_eq!(outputs[1].status, MultisigOutputStatus::Unspent);
+        assert_eq!(outputs[1].value + amount + completed_tx.fee, MicroTari::from(100_000));
+    });
+}
+
+/// Pass the sender message of Alice's latest transaction to Bob and Bob's reply back to Alice, returning the sender
+/// message
+async fn negotiate_with_bob(
+    alice_outbound_service: &OutboundServiceMockState,
+    alice_tx_ack_sender: &mut Sender<DomainMessage<proto::RecipientSignedMessage>>,
+    alice_public_key: &PublicKey,
+    bob_outbound_service: &OutboundServiceMockState,
+    bob_tx_sender: &mut Sender<DomainMessage<proto::TransactionSenderMessage>>,
+    bob_public_key: &PublicKey,
+) -> TransactionSenderMessage {
+    alice_outbound_service
+        .wait_call_count(2, Duration::from_secs(60))
+        .expect("Alice call wait");
+    let _ = alice_outbound_service.pop_call().unwrap();
+    let (_, body) = alice_outbound_service.pop_call().unwrap();
+    let tx_sender_msg = try_decode_sender_message(body.to_vec()).unwrap();
+
+    bob_tx_sender
+        .send(create_dummy_message(tx_sender_msg.clone().into(), alice_public_key))
+        .await
+        .unwrap();
+    bob_outbound_service
+        .wait_call_count(2, Duration::from_secs(60))
+        .expect("Bob call wait");
+    let _ = bob_outbound_service.pop_call().unwrap();
+    let (_, body) = bob_outbound_service.pop_call().unwrap();
+    let tx_reply_msg = try_decode_transaction_reply_message(body.to_vec()).unwrap();
+
+    alice_tx_ack_sender
+        .send(create_dummy_message(tx_reply_msg.into(), bob_public_key))
+        .await
+        .unwrap();
+    tx_sender_msg
+}
+
+fn base_node_state_at_height(height: u64) -> Arc<BaseNodeEvent> {
+    Arc::new(BaseNodeEvent::BaseNodeStateChanged(BaseNodeState {
+        chain_metadata: Some(ChainMetadata::new(height, Vec::new(), 0, 0, 0)),
+        is_synced: Some(true),
+        ..Default::default()
+    }))
+}
+
+#[test]
+fn scheduled_transaction_is_broadcast_at_its_height_after_a_restart() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let alice_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+
+    let (alice_connection, _alice_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut alice_ts,
+        mut alice_oms,
+        alice_outbound_service,
+        _,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        mut alice_shutdown,
+        _alice_mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        _,
+        mut alice_connectivity,
+        _alice_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_connection.clone(), None);
+    alice_connectivity.set_base_node(server_node_identity.to_peer());
+
+    let (bob_connection, _bob_temp_dir) = make_wallet_database_connection(None);
+    let (
+        _bob_ts,
+        _bob_oms,
+        bob_outbound_service,
+        mut bob_tx_sender,
+        _,
+        _,
+        _,
+        _,
+        _bob_shutdown,
+        _bob_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _bob_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), bob_connection, None);
+
+    let (_utxo, uo) = make_input(&mut OsRng, MicroTari(250_000), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo)).unwrap();
+
+    let mut alice_event_stream = alice_ts.get_event_stream();
+    let tx_id = runtime
+        .block_on(alice_ts.schedule_transaction(
+            bob_node_identity.public_key().clone(),
+            MicroTari(10_000),
+            MicroTari(100),
+            "Scheduled".to_string(),
+            TransactionSchedule::Height(10),
+        ))
+        .unwrap();
+    runtime.block_on(negotiate_with_bob(
+        &alice_outbound_service,
+        &mut alice_tx_ack_sender,
+        alice_node_identity.public_key(),
+        &bob_outbound_service,
+        &mut bob_tx_sender,
+        bob_node_identity.public_key(),
+    ));
+
+    runtime.block_on(async {
+        let delay = sleep(Duration::from_secs(60));
+        tokio::pin!(delay);
+        let mut scheduled = false;
+        loop {
+            tokio::select! {
+                event = alice_event_stream.recv() => {
+                    if let TransactionEvent::TransactionScheduled(id) = &*event.unwrap() {
+                        if *id == tx_id {
+                            scheduled = true;
+                            break;
+                        }
+                    }
+                },
+                () = &mut delay => {
+                    break;
+                },
+            }
+        }
+        assert!(scheduled);
+    });
+
+    // The completed transaction is held back and can only be mined from the scheduled height
+    let completed_tx = runtime.block_on(alice_ts.get_completed_transaction(tx_id)).unwrap();
+    assert_eq!(completed_tx.status, TransactionStatus::Scheduled);
+    assert_eq!(completed_tx.transaction.body.kernels()[0].lock_height, 10);
+    assert!(runtime
+        .block_on(rpc_service_state.wait_pop_submit_transaction_calls(1, Duration::from_secs(5)))
+        .is_err());
+
+    alice_shutdown.trigger();
+    drop(alice_ts);
+
+    // The schedule is kept in the database across a restart
+    let (
+        mut alice_ts,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _,
+        _alice_shutdown,
+        _alice_mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        base_node_event_publisher,
+        mut alice_connectivity,
+        _alice_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories, alice_connection, None);
+    alice_connectivity.set_base_node(server_node_identity.to_peer());
+    runtime.block_on(alice_ts.restart_broadcast_protocols()).unwrap();
+
+    let scheduled_txs = runtime.block_on(alice_ts.get_scheduled_transactions()).unwrap();
+    assert_eq!(scheduled_txs.len(), 1);
+    assert_eq!(scheduled_txs[0].tx_id, tx_id);
+    assert_eq!(scheduled_txs[0].schedule, TransactionSchedule::Height(10));
+    assert_eq!(
+        runtime
+            .block_on(alice_ts.get_completed_transaction(tx_id))
+            .unwrap()
+            .status,
+        TransactionStatus::Scheduled
+    );
+
+    // The next block after this tip is still below the scheduled height
+    base_node_event_publisher.send(base_node_state_at_height(8)).unwrap();
+    assert!(runtime
+        .block_on(rpc_service_state.wait_pop_submit_transaction_calls(1, Duration::from_secs(5)))
+        .is_err());
+
+    // The next block can include the transaction, so it is released and broadcast
+    base_node_event_publisher.send(base_node_state_at_height(9)).unwrap();
+    let submissions = runtime
+        .block_on(rpc_service_state.wait_pop_submit_transaction_calls(1, Duration::from_secs(30)))
+        .expect("Should receive a tx submission");
+    assert_eq!(submissions[0].body.kernels()[0].lock_height, 10);
+    assert!(runtime
+        .block_on(alice_ts.get_scheduled_transactions())
+        .unwrap()
+        .is_empty());
+    assert_ne!(
+        runtime
+            .block_on(alice_ts.get_completed_transaction(tx_id))
+            .unwrap()
+            .status,
+        TransactionStatus::Scheduled
+    );
+}
+
+#[test]
+fn scheduled_transaction_is_broadcast_at_its_time() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let alice_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+
+    let (alice_connection, _alice_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut alice_ts,
+        mut alice_oms,
+        alice_outbound_service,
+        _,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        _alice_shutdown,
+        _alice_mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        _,
+        mut alice_connectivity,
+        _alice_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(
+        &mut runtime,
+        factories.clone(),
+        alice_connection,
+        Some(TransactionServiceConfig {
+            scheduled_transaction_check_interval: Duration::from_secs(1),
+            ..Default::default()
+        }),
+    );
+    alice_connectivity.set_base_node(server_node_identity.to_peer());
+
+    let (bob_connection, _bob_temp_dir) = make_wallet_database_connection(None);
+    let (
+        _bob_ts,
+        _bob_oms,
+        bob_outbound_service,
+        mut bob_tx_sender,
+        _,
+        _,
+        _,
+        _,
+        _bob_shutdown,
+        _bob_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _bob_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), bob_connection, None);
+
+    let (_utxo, uo) = make_input(&mut OsRng, MicroTari(250_000), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo)).unwrap();
+
+    let mut alice_event_stream = alice_ts.get_event_stream();
+    let broadcast_time = Utc::now().naive_utc() + ChronoDuration::seconds(10);
+    let tx_id = runtime
+        .block_on(alice_ts.schedule_transaction(
+            bob_node_identity.public_key().clone(),
+            MicroTari(10_000),
+            MicroTari(100),
+            "Scheduled".to_string(),
+            TransactionSchedule::Time(broadcast_time),
+        ))
+        .unwrap();
+    runtime.block_on(negotiate_with_bob(
+        &alice_outbound_service,
+        &mut alice_tx_ack_sender,
+        alice_node_identity.public_key(),
+        &bob_outbound_service,
+        &mut bob_tx_sender,
+        bob_node_identity.public_key(),
+    ));
+    runtime.block_on(async {
+        let delay = sleep(Duration::from_secs(60));
+        tokio::pin!(delay);
+        let mut scheduled = false;
+        loop {
+            tokio::select! {
+                event = alice_event_stream.recv() => {
+                    if let TransactionEvent::TransactionScheduled(id) = &*event.unwrap() {
+                        if *id == tx_id {
+                            scheduled = true;
+                            break;
+                        }
+                    }
+                },
+                () = &mut delay => {
+                    break;
+                },
+            }
+        }
+        assert!(scheduled);
+    });
+
+    // A time schedule does not lock the kernel
+    let completed_tx = runtime.block_on(alice_ts.get_completed_transaction(tx_id)).unwrap();
+    assert_eq!(completed_tx.status, TransactionStatus::Scheduled);
+    assert_eq!(completed_tx.transaction.body.kernels()[0].lock_height, 0);
+
+    let submissions = runtime
+        .block_on(rpc_service_state.wait_pop_submit_transaction_calls(1, Duration::from_secs(60)))
+        .expect("Should receive a tx submission");
+    assert!(Utc::now().naive_utc() >= broadcast_time);
+    assert_eq!(
+        submissions[0].body.kernels()[0].excess,
+        completed_tx.transaction.body.kernels()[0].excess
+    );
+    assert!(runtime
+        .block_on(alice_ts.get_scheduled_transactions())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn time_locked_transaction_sets_the_recipient_output_maturity() {
+    let mut runtime = create_runtime();
+    let factories = CryptoFactories::default();
+
+    let alice_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+    let bob_node_identity =
+        NodeIdentity::random(&mut OsRng, get_next_memory_address(), PeerFeatures::COMMUNICATION_NODE);
+
+    let (alice_connection, _alice_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut alice_ts,
+        mut alice_oms,
+        alice_outbound_service,
+        _,
+        mut alice_tx_ack_sender,
+        _,
+        _,
+        _,
+        _alice_shutdown,
+        _alice_mock_rpc_server,
+        server_node_identity,
+        _,
+        _,
+        mut alice_connectivity,
+        _alice_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), alice_connection, None);
+    alice_connectivity.set_base_node(server_node_identity.to_peer());
+
+    let (bob_connection, _bob_temp_dir) = make_wallet_database_connection(None);
+    let (
+        mut bob_ts,
+        _bob_oms,
+        bob_outbound_service,
+        mut bob_tx_sender,
+        _,
+        _,
+        _,
+        _,
+        _bob_shutdown,
+        _bob_mock_rpc_server,
+        _,
+        _,
+        _,
+        _,
+        _bob_rpc_server_connection,
+    ) = setup_transaction_service_no_comms(&mut runtime, factories.clone(), bob_connection, None);
+
+    let (_utxo, uo) = make_input(&mut OsRng, MicroTari(250_000), &factories.commitment);
+    runtime.block_on(alice_oms.add_output(uo)).unwrap();
+
+    let mut alice_event_stream = alice_ts.get_event_stream();
+    let maturity = 1_000;
+    let tx_id = runtime
+        .block_on(alice_ts.send_time_locked_transaction(
+            bob_node_identity.public_key().clone(),
+            MicroTari(10_000),
+            maturity,
+            MicroTari(100),
+            "Time-locked".to_string(),
+        ))
+        .unwrap();
+    let tx_sender_msg = runtime.block_on(negotiate_with_bob(
+        &alice_outbound_service,
+        &mut alice_tx_ack_sender,
+        alice_node_identity.public_key(),
+        &bob_outbound_service,
+        &mut bob_tx_sender,
+        bob_node_identity.public_key(),
+    ));
+
+    // The recipient is asked to create its output with the maturity
+    match tx_sender_msg {
+        TransactionSenderMessage::Single(data) => assert_eq!(data.features.maturity, maturity),
+        _ => panic!("Transaction is the not a single rounder sender variant"),
+    }
+
+    runtime.block_on(async {
+        let delay = sleep(Duration::from_secs(60));
+        tokio::pin!(delay);
+        let mut received = false;
+        loop {
+            tokio::select! {
+                event = alice_event_stream.recv() => {
+                    if let TransactionEvent::ReceivedTransactionReply(id) = &*event.unwrap() {
+                        if *id == tx_id {
+                            received = true;
+                            break;
+                        }
+                    }
+                },
+                () = &mut delay => {
+                    break;
+                },
+            }
+        }
+        assert!(received);
+    });
+
+    // Only the recipient's output in the final transaction is time-locked, Alice's change is not
+    let completed_tx = runtime.block_on(alice_ts.get_completed_transaction(tx_id)).unwrap();
+    let maturities = completed_tx
+        .transaction
+        .body
+        .outputs()
+        .iter()
+        .map(|o| o.features.maturity)
+        .collect::<Vec<_>>();
+    assert_eq!(maturities.len(), 2);
+    assert_eq!(maturities.iter().filter(|m| **m == maturity).count(), 1);
+    assert_eq!(maturities.iter().filter(|m| **m == 0).count(), 1);
+
+    let bob_pending_tx = runtime
+        .block_on(bob_ts.get_pending_inbound_transactions())
+        .unwrap()
+        .remove(&tx_id)
+        .expect("Bob should have the pending transaction");
+    assert_eq!(bob_pending_tx.amount, MicroTari(10_000));
+}
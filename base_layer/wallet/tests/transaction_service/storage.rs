@@ -24,7 +24,7 @@ use aes_gcm::{
     aead::{generic_array::GenericArray, NewAead},
     Aes256Gcm,
 };
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use rand::rngs::OsRng;
 use tari_crypto::{
     keys::{PublicKey as PublicKeyTrait, SecretKey as SecretKeyTrait},
@@ -35,6 +35,7 @@ use tempfile::tempdir;
 use tokio::runtime::Runtime;
 
 use tari_common_types::types::{HashDigest, PrivateKey, PublicKey};
+use tari_comms::{peer_manager::PeerFeatures, test_utils::node_identity::build_node_identity};
 use tari_core::transactions::{
     helpers::{create_unblinded_output, TestParams},
     tari_amount::{uT, MicroTari},
@@ -47,17 +48,24 @@ use tari_core::transactions::{
 use tari_test_utils::random;
 use tari_wallet::{
     storage::sqlite_utilities::run_migration_and_create_sqlite_connection,
-    transaction_service::storage::{
-        database::{TransactionBackend, TransactionDatabase},
-        models::{
-            CompletedTransaction,
-            InboundTransaction,
-            OutboundTransaction,
-            TransactionDirection,
-            TransactionStatus,
-            WalletTransaction,
+    transaction_service::{
+        payment_request::PaymentRequest,
+        storage::{
+            database::{TransactionBackend, TransactionDatabase},
+            models::{
+                CompletedTransaction,
+                InboundTransaction,
+                OutboundTransaction,
+                PaymentRequestRecord,
+                PaymentRequestStatus,
+                ScheduledTransaction,
+                TransactionDirection,
+                TransactionSchedule,
+                TransactionStatus,
+                WalletTransaction,
+            },
+            sqlite_db::TransactionServiceSqliteDatabase,
         },
-        sqlite_db::TransactionServiceSqliteDatabase,
     },
 };
 pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
@@ -545,6 +553,127 @@ pub fn test_db_backend<T: TransactionBackend + 'static>(backend: T) {
 
     let unmined_txs = runtime.block_on(db.fetch_unconfirmed_transactions()).unwrap();
     assert_eq!(unmined_txs.len(), 5);
+
+    let scheduled_tx_id = 9999;
+    runtime
+        .block_on(db.insert_completed_transaction(scheduled_tx_id, CompletedTransaction {
+            tx_id: scheduled_tx_id,
+            status: TransactionStatus::Completed,
+            ..completed_txs[0].clone()
+        }))
+        .unwrap();
+    assert!(runtime
+        .block_on(db.get_scheduled_transaction(scheduled_tx_id))
+        .unwrap()
+        .is_none());
+    let scheduled_tx = ScheduledTransaction::new(scheduled_tx_id, TransactionSchedule::Height(1000));
+    runtime
+        .block_on(db.add_scheduled_transaction(scheduled_tx.clone()))
+        .unwrap();
+    assert!(runtime
+        .block_on(db.add_scheduled_transaction(scheduled_tx.clone()))
+        .is_err());
+    let scheduled_at_time = ScheduledTransaction::new(
+        completed_txs[1].tx_id,
+        TransactionSchedule::Time(NaiveDateTime::from_timestamp(1_700_000_000, 0)),
+    );
+    runtime
+        .block_on(db.add_scheduled_transaction(scheduled_at_time.clone()))
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(db.get_scheduled_transaction(scheduled_tx_id)).unwrap(),
+        Some(scheduled_tx)
+    );
+    assert_eq!(runtime.block_on(db.get_scheduled_transactions()).unwrap().len(), 2);
+
+    assert!(runtime
+        .block_on(db.set_completed_transaction_scheduled(scheduled_tx_id, false))
+        .is_err());
+    runtime
+        .block_on(db.set_completed_transaction_scheduled(scheduled_tx_id, true))
+        .unwrap();
+    assert_eq!(
+        runtime
+            .block_on(db.get_completed_transaction(scheduled_tx_id))
+            .unwrap()
+            .status,
+        TransactionStatus::Scheduled
+    );
+    // Scheduled transactions have not been broadcast so there is no need to look for them on chain
+    let unmined_txs = runtime.block_on(db.fetch_unconfirmed_transactions()).unwrap();
+    assert_eq!(unmined_txs.len(), 5);
+
+    runtime
+        .block_on(db.set_completed_transaction_scheduled(scheduled_tx_id, false))
+        .unwrap();
+    runtime
+        .block_on(db.remove_scheduled_transaction(scheduled_tx_id))
+        .unwrap();
+    assert!(runtime
+        .block_on(db.remove_scheduled_transaction(scheduled_tx_id))
+        .is_err());
+    assert_eq!(runtime.block_on(db.get_scheduled_transactions()).unwrap(), vec![
+        scheduled_at_time
+    ]);
+
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let request = PaymentRequest::new(&node_identity, MicroTari::from(1000), "Invoice".to_string(), 500).unwrap();
+    let expiring_request = PaymentRequest::new(&node_identity, MicroTari::from(2000), String::new(), 100).unwrap();
+    runtime
+        .block_on(db.add_payment_request(PaymentRequestRecord::new(request.clone())))
+        .unwrap();
+    runtime
+        .block_on(db.add_payment_request(PaymentRequestRecord::new(expiring_request.clone())))
+        .unwrap();
+    assert!(runtime
+        .block_on(db.add_payment_request(PaymentRequestRecord::new(request.clone())))
+        .is_err());
+    let record = runtime
+        .block_on(db.get_payment_request(request.reference))
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.request, request);
+    assert!(record.request.is_valid());
+    assert_eq!(record.status, PaymentRequestStatus::Open);
+
+    let record = runtime
+        .block_on(db.add_payment_request_payment(request.reference, 1, MicroTari::from(400)))
+        .unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::PartlyPaid);
+    assert_eq!(record.amount_outstanding(), MicroTari::from(600));
+    let record = runtime
+        .block_on(db.add_payment_request_payment(request.reference, 2, MicroTari::from(600)))
+        .unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::Paid);
+    assert_eq!(record.payments, vec![1, 2]);
+    assert!(!record.is_open());
+
+    let record = runtime.block_on(db.remove_payment_request_payment(2)).unwrap().unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::PartlyPaid);
+    assert_eq!(record.amount_received, MicroTari::from(400));
+    assert_eq!(record.payments, vec![1]);
+    assert!(runtime
+        .block_on(db.remove_payment_request_payment(2))
+        .unwrap()
+        .is_none());
+    let record = runtime.block_on(db.remove_payment_request_payment(1)).unwrap().unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::Open);
+    assert!(record.payments.is_empty());
+
+    assert_eq!(runtime.block_on(db.expire_payment_requests(100)).unwrap(), vec![
+        expiring_request.reference
+    ]);
+    assert!(runtime.block_on(db.expire_payment_requests(1000)).unwrap().is_empty());
+    let records = runtime.block_on(db.get_payment_requests()).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+        records
+            .iter()
+            .find(|r| r.request.reference == expiring_request.reference)
+            .unwrap()
+            .status,
+        PaymentRequestStatus::Expired
+    );
 }
 
 #[test]
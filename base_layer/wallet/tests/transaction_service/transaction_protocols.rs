@@ -73,6 +73,7 @@ use tari_wallet::{
         config::TransactionServiceConfig,
         error::{TransactionServiceError, TransactionServiceProtocolError},
         handle::{TransactionEvent, TransactionEventReceiver, TransactionEventSender},
+        payment_request::PaymentRequest,
         protocols::{
             transaction_broadcast_protocol::TransactionBroadcastProtocol,
             transaction_validation_protocol::TransactionValidationProtocol,
@@ -80,7 +81,13 @@ use tari_wallet::{
         service::TransactionServiceResources,
         storage::{
             database::TransactionDatabase,
-            models::{CompletedTransaction, TransactionDirection, TransactionStatus},
+            models::{
+                CompletedTransaction,
+                PaymentRequestRecord,
+                PaymentRequestStatus,
+                TransactionDirection,
+                TransactionStatus,
+            },
             sqlite_db::TransactionServiceSqliteDatabase,
         },
     },
@@ -889,6 +896,127 @@ async fn tx_validation_protocol_tx_becomes_mined_unconfirmed_then_confirmed() {
     assert_eq!(completed_txs.get(&2).unwrap().confirmations.unwrap(), 4);
 }
 
+/// Test that a received transaction is matched to the payment request in its message once it is mined, and is taken off
+/// the request again when it is reorged out
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn tx_validation_protocol_matches_payment_requests_when_mined() {
+    let (
+        resources,
+        _outbound_mock_state,
+        _mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        _shutdown,
+        _temp_dir,
+        _transaction_event_receiver,
+        wallet_connectivity,
+    ) = setup(TxProtocolTestConfig::WithConnection).await;
+    wallet_connectivity.notify_base_node_set(server_node_identity.to_peer());
+
+    let request = PaymentRequest::new(&resources.node_identity, 3 * T, "Invoice".to_string(), 100).unwrap();
+    resources
+        .db
+        .add_payment_request(PaymentRequestRecord::new(request.clone()))
+        .await
+        .unwrap();
+
+    // The first transaction references the request, the second only has the amount outstanding after the first
+    let factories = CryptoFactories::default();
+    let mut responses = Vec::new();
+    for (tx_id, amount, message) in [(1, 1 * T, request.payment_message()), (2, 2 * T, "Thanks".to_string())] {
+        let (_utxo, uo) = make_input(&mut OsRng, 10 * amount, &factories.commitment);
+        let (txs, _uou) = schema_to_transaction(&[txn_schema!(from: vec![uo], to: vec![amount])]);
+        let tx = (*txs[0]).clone();
+        responses.push(TxQueryBatchResponseProto {
+            signature: Some(SignatureProto::from(tx.first_kernel_excess_sig().unwrap().clone())),
+            location: TxLocationProto::from(TxLocation::Mined) as i32,
+            block_hash: Some([1u8; 16].to_vec()),
+            confirmations: 0,
+            block_height: 1,
+        });
+        let completed_tx = CompletedTransaction::new(
+            tx_id,
+            CommsPublicKey::default(),
+            resources.node_identity.public_key().clone(),
+            amount,
+            200 * uT,
+            tx,
+            TransactionStatus::Broadcast,
+            message,
+            Utc::now().naive_local(),
+            TransactionDirection::Inbound,
+            None,
+        );
+        resources
+            .db
+            .insert_completed_transaction(tx_id, completed_tx)
+            .await
+            .unwrap();
+    }
+
+    // Finalized transactions do not pay the request before they are mined
+    let record = resources
+        .db
+        .get_payment_request(request.reference)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::Open);
+
+    rpc_service_state.set_transaction_query_batch_responses(TxQueryBatchResponsesProto {
+        responses,
+        is_synced: true,
+        tip_hash: Some([1u8; 16].to_vec()),
+        height_of_longest_chain: 1,
+    });
+    let protocol = TransactionValidationProtocol::new(
+        1,
+        resources.db.clone(),
+        wallet_connectivity.clone(),
+        resources.config.clone(),
+        resources.event_publisher.clone(),
+        resources.output_manager_service.clone(),
+    );
+    assert!(task::spawn(protocol.execute()).await.unwrap().is_ok());
+
+    let record = resources
+        .db
+        .get_payment_request(request.reference)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::PartlyPaid);
+    assert_eq!(record.amount_received, 1 * T);
+    assert_eq!(record.payments, vec![1]);
+
+    // The block is no longer in the chain, so the payment is taken off the request
+    rpc_service_state.set_transaction_query_batch_responses(TxQueryBatchResponsesProto {
+        responses: vec![],
+        is_synced: true,
+        tip_hash: Some([1u8; 16].to_vec()),
+        height_of_longest_chain: 1,
+    });
+    let protocol = TransactionValidationProtocol::new(
+        2,
+        resources.db.clone(),
+        wallet_connectivity.clone(),
+        resources.config.clone(),
+        resources.event_publisher.clone(),
+        resources.output_manager_service.clone(),
+    );
+    assert!(task::spawn(protocol.execute()).await.unwrap().is_ok());
+
+    let record = resources
+        .db
+        .get_payment_request(request.reference)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(record.status, PaymentRequestStatus::Open);
+    assert!(record.payments.is_empty());
+}
+
 /// Test that validation detects transactions becoming mined unconfirmed and then confirmed with some going back to
 /// completed
 #[tokio::test]
@@ -1170,3 +1298,118 @@ async fn tx_validation_protocol_reorg() {
     assert!(!completed_txs.get(&6).unwrap().valid);
     assert_eq!(completed_txs.get(&7).unwrap().status, TransactionStatus::Coinbase);
 }
+
+/// Test that validation does not trust the current base node when a majority of the other base nodes report a
+/// transaction as mined that it reports as not mined
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn tx_validation_protocol_cross_checks_unmined_transactions() {
+    let (
+        resources,
+        _outbound_mock_state,
+        _mock_rpc_server,
+        server_node_identity,
+        rpc_service_state,
+        _shutdown,
+        _temp_dir,
+        _transaction_event_receiver,
+        wallet_connectivity,
+    ) = setup(TxProtocolTestConfig::WithConnection).await;
+    add_transaction_to_database(
+        1,
+        1 * T,
+        true,
+        Some(TransactionStatus::Broadcast),
+        None,
+        resources.db.clone(),
+    )
+    .await;
+    let tx1 = resources.db.get_completed_transaction(1).await.unwrap();
+    let signature = Some(SignatureProto::from(
+        tx1.transaction.first_kernel_excess_sig().unwrap().clone(),
+    ));
+
+    rpc_service_state.set_transaction_query_batch_responses(TxQueryBatchResponsesProto {
+        responses: vec![TxQueryBatchResponseProto {
+            signature: signature.clone(),
+            location: TxLocationProto::from(TxLocation::NotStored) as i32,
+            block_hash: None,
+            confirmations: 0,
+            block_height: 0,
+        }],
+        is_synced: true,
+        tip_hash: Some([5u8; 16].to_vec()),
+        height_of_longest_chain: 5,
+    });
+
+    // Setup the other base node in the pool, which has the transaction mined
+    let other_node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let service = BaseNodeWalletRpcMockService::new();
+    let other_rpc_service_state = service.get_state();
+    let other_server = BaseNodeWalletRpcServer::new(service);
+    let protocol_name = other_server.as_protocol_name();
+    let mut other_mock_server = MockRpcServer::new(other_server, other_node_identity.clone());
+    other_mock_server.serve();
+    let mut connection = other_mock_server
+        .create_connection(other_node_identity.to_peer(), protocol_name.into())
+        .await;
+    wallet_connectivity.set_cross_check_wallet_rpc_clients(vec![(
+        other_node_identity.node_id().clone(),
+        connect_rpc_client(&mut connection).await,
+    )]);
+    other_rpc_service_state.set_transaction_query_batch_responses(TxQueryBatchResponsesProto {
+        responses: vec![TxQueryBatchResponseProto {
+            signature: signature.clone(),
+            location: TxLocationProto::from(TxLocation::Mined) as i32,
+            block_hash: Some([5u8; 16].to_vec()),
+            confirmations: 1,
+            block_height: 5,
+        }],
+        is_synced: true,
+        tip_hash: Some([5u8; 16].to_vec()),
+        height_of_longest_chain: 5,
+    });
+
+    wallet_connectivity.notify_base_node_set(server_node_identity.to_peer());
+
+    let protocol = TransactionValidationProtocol::new(
+        1,
+        resources.db.clone(),
+        wallet_connectivity.clone(),
+        resources.config.clone(),
+        resources.event_publisher.clone(),
+        resources.output_manager_service.clone(),
+    );
+    let result = task::spawn(protocol.execute()).await.unwrap();
+    assert!(matches!(
+        result,
+        Err(TransactionServiceProtocolError {
+            id: 1,
+            error: TransactionServiceError::BaseNodeContradicted,
+        })
+    ));
+    assert_eq!(wallet_connectivity.get_disputed_base_nodes(), vec![
+        server_node_identity.node_id().clone()
+    ]);
+    let completed_tx = resources.db.get_completed_transaction(1).await.unwrap();
+    assert_eq!(completed_tx.status, TransactionStatus::Broadcast);
+
+    // When the other base node agrees that the transaction is not mined the current base node is trusted
+    other_rpc_service_state.set_transaction_query_batch_responses(TxQueryBatchResponsesProto {
+        responses: vec![],
+        is_synced: true,
+        tip_hash: Some([5u8; 16].to_vec()),
+        height_of_longest_chain: 5,
+    });
+    let protocol = TransactionValidationProtocol::new(
+        2,
+        resources.db.clone(),
+        wallet_connectivity.clone(),
+        resources.config.clone(),
+        resources.event_publisher.clone(),
+        resources.output_manager_service.clone(),
+    );
+    let result = task::spawn(protocol.execute()).await.unwrap();
+    assert!(result.is_ok());
+    assert_eq!(wallet_connectivity.get_disputed_base_nodes().len(), 1);
+}
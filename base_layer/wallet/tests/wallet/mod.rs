@@ -22,14 +22,8 @@
 
 use std::{panic, path::Path, sync::Arc, time::Duration};
 
-use aes_gcm::{
-    aead::{generic_array::GenericArray, NewAead},
-    Aes256Gcm,
-};
-use digest::Digest;
 use rand::rngs::OsRng;
 use tari_crypto::{
-    common::Blake256,
     inputs,
     keys::{PublicKey as PublicKeyTrait, SecretKey},
     script,
@@ -43,7 +37,8 @@ use tari_common_types::{
 };
 use tari_comms::{
     multiaddr::Multiaddr,
-    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags},
+    peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerFlags, PEER_DATABASE_KEY_BYTES},
+    reachability::ReachabilityConfig,
     types::{CommsPublicKey, CommsSecretKey},
 };
 use tari_comms_dht::DhtConfig;
@@ -59,13 +54,21 @@ use tari_test_utils::random;
 use tari_wallet::{
     contacts_service::storage::{database::Contact, sqlite_db::ContactsServiceSqliteDatabase},
     error::{WalletError, WalletStorageError},
-    output_manager_service::storage::sqlite_db::OutputManagerSqliteDatabase,
+    output_manager_service::storage::{
+        database::{OutputManagerBackend, OutputManagerDatabase},
+        sqlite_db::OutputManagerSqliteDatabase,
+    },
     storage::{
+        backup::WalletBackup,
         database::{DbKeyValuePair, WalletBackend, WalletDatabase, WriteOperation},
+        key_derivation::PassphraseKeyDerivation,
         sqlite_db::WalletSqliteDatabase,
         sqlite_utilities::{
+            change_passphrase,
+            derive_database_cipher,
             initialize_sqlite_database_backends,
             partial_wallet_backup,
+            restore_wallet_backup,
             run_migration_and_create_sqlite_connection,
         },
     },
@@ -73,7 +76,7 @@ use tari_wallet::{
     transaction_service::{
         config::TransactionServiceConfig,
         handle::TransactionEvent,
-        storage::sqlite_db::TransactionServiceSqliteDatabase,
+        storage::{database::TransactionBackend, sqlite_db::TransactionServiceSqliteDatabase},
     },
     Wallet,
     WalletConfig,
@@ -112,8 +115,14 @@ async fn create_wallet(
             listener_address: node_identity.public_address(),
         },
         auxilary_tcp_listener_address: None,
+        // Wallets are not expected to be reachable by other peers
+        reachability: ReachabilityConfig {
+            is_self_check_enabled: false,
+            ..Default::default()
+        },
         datastore_path: data_path.to_path_buf(),
         peer_database_name: random::string(8),
+        peer_database_cipher: None,
         max_concurrent_inbound_tasks: 100,
         outbound_buffer_size: 100,
         dht: DhtConfig {
@@ -124,6 +133,8 @@ async fn create_wallet(
         },
         allow_test_addresses: true,
         listener_liveness_allowlist_cidrs: Vec::new(),
+        firewall_rules: Default::default(),
+        bandwidth: Default::default(),
         listener_liveness_max_sessions: 0,
         user_agent: "tari/test-wallet".to_string(),
         dns_seeds_name_server: DEFAULT_DNS_NAME_SERVER.parse().unwrap(),
@@ -321,11 +332,8 @@ async fn test_wallet() {
         panic!("Should not be able to instantiate encrypted wallet without cipher");
     }
 
-    let passphrase_hash = Blake256::new()
-        .chain("wrong passphrase".to_string().as_bytes())
-        .finalize();
-    let key = GenericArray::from_slice(passphrase_hash.as_slice());
-    let cipher = Aes256Gcm::new(key);
+    let (cipher, kdf) = derive_database_cipher(&connection, "wrong passphrase").unwrap();
+    assert!(!kdf.is_legacy());
     let result = WalletSqliteDatabase::new(connection.clone(), Some(cipher));
 
     if let Err(err) = result {
@@ -334,11 +342,7 @@ async fn test_wallet() {
         panic!("Should not be able to instantiate encrypted wallet without cipher");
     }
 
-    let passphrase_hash = Blake256::new()
-        .chain("It's turtles all the way down".to_string().as_bytes())
-        .finalize();
-    let key = GenericArray::from_slice(passphrase_hash.as_slice());
-    let cipher = Aes256Gcm::new(key);
+    let (cipher, _) = derive_database_cipher(&connection, "It's turtles all the way down").unwrap();
     let db = WalletSqliteDatabase::new(connection, Some(cipher)).expect("Should be able to instantiate db with cipher");
     drop(db);
 
@@ -353,6 +357,32 @@ async fn test_wallet() {
     )
     .await
     .unwrap();
+    let peer_database_key = [7u8; PEER_DATABASE_KEY_BYTES];
+    alice_wallet.db.set_peer_database_key(peer_database_key).await.unwrap();
+
+    // The passphrase can only be changed by confirming the current one
+    match alice_wallet
+        .change_passphrase("wrong passphrase".to_string(), "Turtles all the way up".to_string())
+        .await
+    {
+        Err(WalletError::WalletStorageError(WalletStorageError::IncorrectPassword)) => {},
+        _ => panic!("Should not be able to change the passphrase without the current one"),
+    }
+    alice_wallet
+        .change_passphrase(
+            "It's turtles all the way down".to_string(),
+            "Turtles all the way up".to_string(),
+        )
+        .await
+        .unwrap();
+    alice_wallet
+        .verify_passphrase("Turtles all the way up".to_string())
+        .await
+        .unwrap();
+    assert!(alice_wallet
+        .verify_passphrase("It's turtles all the way down".to_string())
+        .await
+        .is_err());
 
     alice_wallet.remove_encryption().await.unwrap();
 
@@ -365,6 +395,9 @@ async fn test_wallet() {
         "Should be able to instantiate db with
     cipher",
     );
+    // The peer database key survives changing the passphrase and removing the encryption
+    let db = WalletDatabase::new(db);
+    assert_eq!(db.get_peer_database_key().await.unwrap(), Some(peer_database_key));
     drop(db);
 
     // Test the partial db backup in this test so that we can work with the data generated during the test
@@ -470,6 +503,97 @@ async fn test_do_not_overwrite_master_key() {
     .unwrap();
 }
 
+#[tokio::test]
+async fn test_passphrase_key_derivation_migration_and_change() {
+    let factories = CryptoFactories::default();
+    let dir = tempdir().unwrap();
+    let wallet_path = dir.path().join("wallet_db").with_extension("sqlite3");
+
+    let mut shutdown = Shutdown::new();
+    let wallet = create_wallet(
+        dir.path(),
+        "wallet_db",
+        factories.clone(),
+        shutdown.to_signal(),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    shutdown.trigger();
+    wallet.wait_until_shutdown().await;
+
+    // Encrypt the database the way wallets did before the key derivation was versioned
+    let legacy_cipher = PassphraseKeyDerivation::Blake256
+        .derive_cipher("old passphrase")
+        .unwrap();
+    let (wallet_backend, transaction_backend, output_manager_backend, _) =
+        initialize_sqlite_database_backends(wallet_path.clone(), None).unwrap();
+    wallet_backend.apply_encryption(legacy_cipher.clone()).unwrap();
+    output_manager_backend.apply_encryption(legacy_cipher.clone()).unwrap();
+    transaction_backend.apply_encryption(legacy_cipher).unwrap();
+    drop((wallet_backend, transaction_backend, output_manager_backend));
+
+    // Opening it with the passphrase migrates it to the current key derivation
+    let (wallet_backend, transaction_backend, output_manager_backend, _) =
+        initialize_sqlite_database_backends(wallet_path.clone(), Some("old passphrase".to_string())).unwrap();
+    let peer_database_key = [7u8; PEER_DATABASE_KEY_BYTES];
+    WalletDatabase::new(wallet_backend)
+        .set_peer_database_key(peer_database_key)
+        .await
+        .unwrap();
+    drop((transaction_backend, output_manager_backend));
+    let connection = run_migration_and_create_sqlite_connection(&wallet_path).unwrap();
+    let (_, kdf) = derive_database_cipher(&connection, "old passphrase").unwrap();
+    assert!(!kdf.is_legacy());
+    drop(connection);
+
+    change_passphrase(
+        wallet_path.clone(),
+        "old passphrase".to_string(),
+        "new passphrase".to_string(),
+    )
+    .unwrap();
+
+    match change_passphrase(
+        wallet_path.clone(),
+        "old passphrase".to_string(),
+        "another passphrase".to_string(),
+    ) {
+        Err(WalletStorageError::IncorrectPassword) => {},
+        _ => panic!("Should not be able to change the passphrase with the wrong old passphrase"),
+    }
+
+    match initialize_sqlite_database_backends(wallet_path.clone(), Some("old passphrase".to_string())) {
+        Err(WalletStorageError::IncorrectPassword) => {},
+        _ => panic!("Should not be able to open the wallet with the old passphrase"),
+    }
+
+    // The peer database key is re-encrypted with the new passphrase
+    let (wallet_backend, _, _, _) =
+        initialize_sqlite_database_backends(wallet_path, Some("new passphrase".to_string())).unwrap();
+    let wallet_db = WalletDatabase::new(wallet_backend);
+    assert_eq!(
+        wallet_db.get_peer_database_key().await.unwrap(),
+        Some(peer_database_key)
+    );
+    drop(wallet_db);
+
+    let mut shutdown = Shutdown::new();
+    let wallet = create_wallet(
+        dir.path(),
+        "wallet_db",
+        factories,
+        shutdown.to_signal(),
+        Some("new passphrase".to_string()),
+        None,
+    )
+    .await
+    .unwrap();
+    shutdown.trigger();
+    wallet.wait_until_shutdown().await;
+}
+
 #[tokio::test]
 async fn test_sign_message() {
     let factories = CryptoFactories::default();
@@ -693,13 +817,21 @@ async fn test_import_utxo() {
             tor_socks_config: None,
         },
         auxilary_tcp_listener_address: None,
+        // Wallets are not expected to be reachable by other peers
+        reachability: ReachabilityConfig {
+            is_self_check_enabled: false,
+            ..Default::default()
+        },
         datastore_path: temp_dir.path().to_path_buf(),
         peer_database_name: random::string(8),
+        peer_database_cipher: None,
         max_concurrent_inbound_tasks: 100,
         outbound_buffer_size: 100,
         dht: Default::default(),
         allow_test_addresses: true,
         listener_liveness_allowlist_cidrs: Vec::new(),
+        firewall_rules: Default::default(),
+        bandwidth: Default::default(),
         listener_liveness_max_sessions: 0,
         user_agent: "tari/test-wallet".to_string(),
         dns_seeds_name_server: DEFAULT_DNS_NAME_SERVER.parse().unwrap(),
@@ -789,3 +921,62 @@ fn test_db_file_locking() {
 
     assert!(run_migration_and_create_sqlite_connection(&wallet_path).is_ok());
 }
+
+#[tokio::test]
+async fn test_restore_wallet_backup() {
+    let factories = CryptoFactories::default();
+    let db_tempdir = tempdir().unwrap();
+    let (_utxo, output) = make_input(&mut OsRng, MicroTari::from(1000), &factories.commitment);
+    let backup = WalletBackup {
+        master_secret_key: CommsSecretKey::random(&mut OsRng),
+        key_manager_state: None,
+        client_values: vec![("key".to_string(), "value".to_string())].into_iter().collect(),
+        contacts: Vec::new(),
+        known_one_sided_payment_scripts: Vec::new(),
+        outputs: vec![output.clone()],
+        transactions: Vec::new(),
+        accounts: Vec::new(),
+        output_account_ids: Vec::new(),
+        wallet_birthday: None,
+        htlcs: Vec::new(),
+        htlc_preimages: Vec::new(),
+        payment_requests: Vec::new(),
+        scheduled_transactions: Vec::new(),
+    };
+
+    // The duplicate output fails the restore after the other records have been written
+    let mut broken_backup = backup.clone();
+    broken_backup.outputs.push(output);
+    let broken_path = db_tempdir.path().join("broken").with_extension("sqlite3");
+    assert!(restore_wallet_backup(
+        broken_path.clone(),
+        &broken_backup.encrypt("passphrase").unwrap(),
+        "passphrase",
+        &factories
+    )
+    .await
+    .is_err());
+    assert!(!broken_path.exists());
+
+    let wallet_path = db_tempdir.path().join("restored").with_extension("sqlite3");
+    restore_wallet_backup(
+        wallet_path.clone(),
+        &backup.encrypt("passphrase").unwrap(),
+        "passphrase",
+        &factories,
+    )
+    .await
+    .unwrap();
+    let connection = run_migration_and_create_sqlite_connection(&wallet_path).unwrap();
+    let wallet_db = WalletDatabase::new(WalletSqliteDatabase::new(connection.clone(), None).unwrap());
+    assert_eq!(
+        wallet_db.get_master_secret_key().await.unwrap(),
+        Some(backup.master_secret_key)
+    );
+    assert_eq!(
+        wallet_db.get_client_key_value("key".to_string()).await.unwrap(),
+        Some("value".to_string())
+    );
+    let output_manager_db = OutputManagerDatabase::new(OutputManagerSqliteDatabase::new(connection, None));
+    assert_eq!(output_manager_db.fetch_sorted_unspent_outputs().await.unwrap().len(), 1);
+}
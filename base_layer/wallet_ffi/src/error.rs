@@ -29,7 +29,7 @@ use tari_crypto::{
 use tari_key_manager::mnemonic::MnemonicError;
 use tari_wallet::{
     contacts_service::error::{ContactsServiceError, ContactsServiceStorageError},
-    error::{WalletError, WalletStorageError},
+    error::{WalletBackupError, WalletError, WalletStorageError},
     output_manager_service::error::{OutputManagerError, OutputManagerStorageError},
     transaction_service::error::{TransactionServiceError, TransactionStorageError},
 };
@@ -274,6 +274,23 @@ impl From<WalletError> for LibWalletError {
                 code: 428,
                 message: format!("{:?}", w),
             },
+            // Wallet Backup Errors
+            WalletError::WalletBackupError(WalletBackupError::UnsupportedVersion(_)) => Self {
+                code: 429,
+                message: format!("{:?}", w),
+            },
+            WalletError::WalletBackupError(WalletBackupError::ChecksumMismatch) => Self {
+                code: 430,
+                message: format!("{:?}", w),
+            },
+            WalletError::WalletBackupError(WalletBackupError::IncorrectPassphrase) => Self {
+                code: 431,
+                message: format!("{:?}", w),
+            },
+            WalletError::WalletBackupError(WalletBackupError::WalletAlreadyExists) => Self {
+                code: 432,
+                message: format!("{:?}", w),
+            },
             // This is the catch all error code. Any error that is not explicitly mapped above will be given this code
             _ => Self {
                 code: 999,
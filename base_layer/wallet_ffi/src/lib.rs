@@ -151,6 +151,7 @@ use tari_common_types::{
 use tari_comms::{
     multiaddr::Multiaddr,
     peer_manager::{NodeIdentity, PeerFeatures},
+    reachability::ReachabilityConfig,
     socks,
     tor,
     transports::MemoryTransport,
@@ -167,9 +168,10 @@ use tari_wallet::{
     contacts_service::storage::database::Contact,
     error::{WalletError, WalletStorageError},
     storage::{
+        backup::EncryptedWalletBackup,
         database::WalletDatabase,
         sqlite_db::WalletSqliteDatabase,
-        sqlite_utilities::{initialize_sqlite_database_backends, partial_wallet_backup},
+        sqlite_utilities::{initialize_sqlite_database_backends, partial_wallet_backup, restore_wallet_backup},
     },
     transaction_service::{
         config::TransactionServiceConfig,
@@ -1628,6 +1630,7 @@ pub unsafe extern "C" fn completed_transaction_get_source_public_key(
 /// |   4 | Pending             |
 /// |   5 | Coinbase            |
 /// |   6 | MinedConfirmed      |
+/// |   7 | Scheduled           |
 ///
 /// # Safety
 /// None
@@ -2424,6 +2427,7 @@ pub unsafe extern "C" fn transport_tor_create(
         tor_proxy_bypass_addresses: vec![],
         // Prefer performance
         tor_proxy_bypass_for_outbound_tcp: true,
+        client_auth: vec![],
     };
     let transport = TariTransportType::Tor(tor_config);
 
@@ -2588,8 +2592,13 @@ pub unsafe extern "C" fn comms_config_create(
                         node_identity: Arc::new(ni),
                         transport_type: (*transport_type).clone(),
                         auxilary_tcp_listener_address: None,
+                        reachability: ReachabilityConfig {
+                            is_self_check_enabled: false,
+                            ..Default::default()
+                        },
                         datastore_path,
                         peer_database_name: database_name_string,
+                        peer_database_cipher: None,
                         max_concurrent_inbound_tasks: 100,
                         outbound_buffer_size: 100,
                         dht: DhtConfig {
@@ -2603,6 +2612,8 @@ pub unsafe extern "C" fn comms_config_create(
                         //       docstring for more info.
                         allow_test_addresses: true,
                         listener_liveness_allowlist_cidrs: Vec::new(),
+                        firewall_rules: Default::default(),
+                        bandwidth: Default::default(),
                         listener_liveness_max_sessions: 0,
                         user_agent: format!("tari/wallet/{}", env!("CARGO_PKG_VERSION")),
                         dns_seeds_name_server: "1.1.1.1:53".parse().unwrap(),
@@ -3612,6 +3623,7 @@ pub unsafe extern "C" fn wallet_get_completed_transactions(
                 .values()
                 .filter(|ct| ct.status != TransactionStatus::Completed)
                 .filter(|ct| ct.status != TransactionStatus::Broadcast)
+                .filter(|ct| ct.status != TransactionStatus::Scheduled)
             {
                 completed.push(tx.clone());
             }
@@ -3740,7 +3752,11 @@ pub unsafe extern "C" fn wallet_get_pending_outbound_transactions(
                 // list here in the FFI interface
                 for ct in completed_txs
                     .values()
-                    .filter(|ct| ct.status == TransactionStatus::Completed || ct.status == TransactionStatus::Broadcast)
+                    .filter(|ct| {
+                        ct.status == TransactionStatus::Completed ||
+                            ct.status == TransactionStatus::Broadcast ||
+                            ct.status == TransactionStatus::Scheduled
+                    })
                     .filter(|ct| ct.direction == TransactionDirection::Outbound)
                 {
                     pending.push(OutboundTransaction::from(ct.clone()));
@@ -3879,7 +3895,10 @@ pub unsafe extern "C" fn wallet_get_completed_transaction_by_id(
     match completed_transactions {
         Ok(completed_transactions) => {
             if let Some(tx) = completed_transactions.get(&transaction_id) {
-                if tx.status != TransactionStatus::Completed && tx.status != TransactionStatus::Broadcast {
+                if tx.status != TransactionStatus::Completed &&
+                    tx.status != TransactionStatus::Broadcast &&
+                    tx.status != TransactionStatus::Scheduled
+                {
                     let completed = tx.clone();
                     return Box::into_raw(Box::new(completed));
                 }
@@ -4009,7 +4028,9 @@ pub unsafe extern "C" fn wallet_get_pending_outbound_transaction_by_id(
     match completed_transactions {
         Ok(completed_transactions) => {
             if let Some(tx) = completed_transactions.get(&transaction_id) {
-                if (tx.status == TransactionStatus::Broadcast || tx.status == TransactionStatus::Completed) &&
+                if (tx.status == TransactionStatus::Broadcast ||
+                    tx.status == TransactionStatus::Completed ||
+                    tx.status == TransactionStatus::Scheduled) &&
                     tx.direction == TransactionDirection::Outbound
                 {
                     let completed = tx.clone();
@@ -4651,6 +4672,159 @@ pub unsafe extern "C" fn wallet_remove_encryption(wallet: *mut TariWallet, error
     }
 }
 
+/// Write an encrypted backup of the whole wallet to a file. The backup holds the master seed, known one-sided payment
+/// scripts, contacts, transaction history, unspent outputs and client key values, and is encrypted with its own
+/// passphrase, independent of the database encryption.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer
+/// `backup_path` - The pointer to a Utf8 string of the file path the backup will be written to
+/// `backup_passphrase` - The pointer to a Utf8 string of the passphrase used to encrypt the backup
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Return a boolean value indicating the operation's success or failure. The error_ptr will hold the error
+/// code if there was a failure
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_create_backup(
+    wallet: *mut TariWallet,
+    backup_path: *const c_char,
+    backup_passphrase: *const c_char,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if backup_path.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("backup_path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if backup_passphrase.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("backup_passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let path = PathBuf::from(
+        CStr::from_ptr(backup_path)
+            .to_str()
+            .expect("A non-null backup path should be able to be converted to string"),
+    );
+    let pf = CStr::from_ptr(backup_passphrase)
+        .to_str()
+        .expect("A non-null backup passphrase should be able to be converted to string")
+        .to_owned();
+
+    let result = (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.create_backup(pf))
+        .and_then(|backup| backup.write_to_file(path).map_err(WalletError::from));
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(e).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
+/// Restore a wallet from an encrypted backup file created with `wallet_create_backup`. This must be called before
+/// `wallet_create` with the same config, and there must not already be a wallet database at the configured datastore
+/// path. The restored outputs are unconfirmed until the first TXO validation completes after `wallet_create`.
+///
+/// ## Arguments
+/// `config` - The TariCommsConfig pointer that will be passed to `wallet_create`
+/// `backup_path` - The pointer to a Utf8 string of the path of the backup file
+/// `backup_passphrase` - The pointer to a Utf8 string of the passphrase the backup was encrypted with
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Return a boolean value indicating the operation's success or failure. The error_ptr will hold the error
+/// code if there was a failure
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_restore_backup(
+    config: *mut TariCommsConfig,
+    backup_path: *const c_char,
+    backup_passphrase: *const c_char,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+    if config.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("config".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if backup_path.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("backup_path".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    if backup_passphrase.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("backup_passphrase".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    let path = PathBuf::from(
+        CStr::from_ptr(backup_path)
+            .to_str()
+            .expect("A non-null backup path should be able to be converted to string"),
+    );
+    let pf = CStr::from_ptr(backup_passphrase)
+        .to_str()
+        .expect("A non-null backup passphrase should be able to be converted to string");
+
+    let runtime = match Runtime::new() {
+        Ok(r) => r,
+        Err(e) => {
+            error = LibWalletError::from(InterfaceError::TokioError(e.to_string())).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            return false;
+        },
+    };
+
+    let sql_database_path = (*config)
+        .datastore_path
+        .join((*config).peer_database_name.clone())
+        .with_extension("sqlite3");
+
+    let result = EncryptedWalletBackup::read_from_file(path).and_then(|backup| {
+        runtime.block_on(restore_wallet_backup(
+            sql_database_path,
+            &backup,
+            pf,
+            &CryptoFactories::default(),
+        ))
+    });
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::from(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
 /// Set a Key Value in the Wallet storage used for Client Key Value store
 ///
 /// ## Arguments
@@ -4859,6 +5033,82 @@ pub unsafe extern "C" fn wallet_is_recovery_in_progress(wallet: *mut TariWallet,
     }
 }
 
+/// Gets the wallet birthday, the number of days since the Unix epoch on which the wallet was created. It should be
+/// stored along with the seed words so that a recovery can skip the blocks that were mined before the wallet existed.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `c_ushort` - Returns the wallet birthday, 0 is returned if the wallet has no birthday or an error occurred.
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_get_birthday(wallet: *mut TariWallet, error_out: *mut c_int) -> c_ushort {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return 0;
+    }
+
+    match (*wallet).runtime.block_on((*wallet).wallet.db.get_wallet_birthday()) {
+        Ok(birthday) => birthday.unwrap_or_default(),
+        Err(e) => {
+            error = LibWalletError::from(WalletError::WalletStorageError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            0
+        },
+    }
+}
+
+/// Sets the wallet birthday of a wallet that is being recovered, so that the recovery starts at the blocks mined
+/// around that day instead of the genesis block. This must be called before `wallet_start_recovery`.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `birthday` - The wallet birthday as returned by `wallet_get_birthday` for the original wallet
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+///
+/// # Safety
+/// None
+#[no_mangle]
+pub unsafe extern "C" fn wallet_set_birthday(
+    wallet: *mut TariWallet,
+    birthday: c_ushort,
+    error_out: *mut c_int,
+) -> bool {
+    let mut error = 0;
+    ptr::swap(error_out, &mut error as *mut c_int);
+
+    if wallet.is_null() {
+        error = LibWalletError::from(InterfaceError::NullError("wallet".to_string())).code;
+        ptr::swap(error_out, &mut error as *mut c_int);
+        return false;
+    }
+
+    match (*wallet)
+        .runtime
+        .block_on((*wallet).wallet.db.set_wallet_birthday(birthday))
+    {
+        Ok(_) => true,
+        Err(e) => {
+            error = LibWalletError::from(WalletError::WalletStorageError(e)).code;
+            ptr::swap(error_out, &mut error as *mut c_int);
+            false
+        },
+    }
+}
+
 /// Starts the Wallet recovery process.
 ///
 /// ## Arguments
@@ -4882,7 +5132,7 @@ pub unsafe extern "C" fn wallet_is_recovery_in_progress(wallet: *mut TariWallet,
 ///     - ConnectingToBaseNode, 0, 0
 ///     - ConnectedToBaseNode, 0, 1
 ///     - ConnectionToBaseNodeFailed, number of retries, retry limit
-///     - Progress, current block, total number of blocks
+///     - Progress, current block height, chain tip height
 ///     - Completed, total number of UTXO's scanned, MicroTari recovered,
 ///     - ScanningRoundFailed, number of retries, retry limit
 ///     - RecoveryFailed, 0, 0
@@ -89,11 +89,15 @@ pub async fn recovery_event_monitoring(
             Ok(UtxoScannerEvent::Progress {
                 current_block: current,
                 current_chain_height: total,
+                time_remaining,
             }) => {
                 unsafe {
                     (recovery_progress_callback)(RecoveryEvent::Progress as u8, current, total);
                 }
-                info!(target: LOG_TARGET, "Recovery progress: {}/{}", current, total);
+                info!(
+                    target: LOG_TARGET,
+                    "Recovery progress: {}/{} (time remaining: {:.0?})", current, total, time_remaining
+                );
             },
             Ok(UtxoScannerEvent::Completed {
                 number_scanned: num_scanned,
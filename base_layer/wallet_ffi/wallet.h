@@ -572,6 +572,13 @@ void wallet_apply_encryption(struct TariWallet *wallet, const char *passphrase,
 // be removed. If it is not encrypted then this function will still succeed to make the operation idempotent
 void wallet_remove_encryption(struct TariWallet *wallet, int *error_out);
 
+// Write an encrypted backup of the whole wallet to the file at backup_path, protected by backup_passphrase
+bool wallet_create_backup(struct TariWallet *wallet, const char *backup_path, const char *backup_passphrase, int *error_out);
+
+// Restore a wallet from an encrypted backup file. Must be called before wallet_create with the same config and when no
+// wallet database exists at the configured datastore path
+bool wallet_restore_backup(struct TariCommsConfig *config, const char *backup_path, const char *backup_passphrase, int *error_out);
+
 /// Set a Key Value in the Wallet storage used for Client Key Value store
 ///
 /// ## Arguments
@@ -636,6 +643,37 @@ bool wallet_clear_value(struct TariWallet *wallet, const char *key, int *error_o
 /// None
 bool wallet_is_recovery_in_progress(struct TariWallet *wallet, int *error_out);
 
+/// Gets the wallet birthday, the number of days since the Unix epoch on which the wallet was created. It should be
+/// stored along with the seed words so that a recovery can skip the blocks that were mined before the wallet existed.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `unsigned short` - Returns the wallet birthday, 0 is returned if the wallet has no birthday or an error occurred.
+///
+/// # Safety
+/// None
+unsigned short wallet_get_birthday(struct TariWallet *wallet, int *error_out);
+
+/// Sets the wallet birthday of a wallet that is being recovered, so that the recovery starts at the blocks mined
+/// around that day instead of the genesis block. This must be called before `wallet_start_recovery`.
+///
+/// ## Arguments
+/// `wallet` - The TariWallet pointer.
+/// `birthday` - The wallet birthday as returned by `wallet_get_birthday` for the original wallet
+/// `error_out` - Pointer to an int which will be modified to an error code should one occur, may not be null. Functions
+/// as an out parameter.
+///
+/// ## Returns
+/// `bool` - Returns if successful or not
+///
+/// # Safety
+/// None
+bool wallet_set_birthday(struct TariWallet *wallet, unsigned short birthday, int *error_out);
+
 /// Starts the Wallet recovery process.
 ///
 /// ## Arguments
@@ -659,7 +697,7 @@ bool wallet_is_recovery_in_progress(struct TariWallet *wallet, int *error_out);
 ///     - ConnectingToBaseNode, 0, 0
 ///     - ConnectedToBaseNode, 0, 1
 ///     - ConnectionToBaseNodeFailed, number of retries, retry limit
-///     - Progress, current block, total number of blocks
+///     - Progress, current block height, chain tip height
 ///     - Completed, total number of UTXO's scanned, MicroTari recovered,
 ///     - ScanningRoundFailed, number of retries, retry limit
 ///     - RecoveryFailed, 0, 0
@@ -41,6 +41,19 @@ function Client(address) {
     "getNetworkStatus",
     "cancelTransaction",
     "checkForUpdates",
+    "getContacts",
+    "upsertContact",
+    "removeContact",
+    "getSeedWords",
+    "setBaseNode",
+    "applyEncryption",
+    "removeEncryption",
+    "changePassphrase",
+    "signMessage",
+    "verifyMessageSignature",
+    "getUnspentOutputs",
+    "startRecovery",
+    "getRecoveryStatus",
   ];
 
   this.waitForReady = (...args) => {
@@ -128,9 +128,16 @@ pub struct ConfigBootstrap {
     /// Supply the optional wallet seed words for recovery on the command line
     #[structopt(long, alias = "seed_words")]
     pub seed_words: Option<String>,
+    /// Supply the optional wallet birthday (YYYY-MM-DD) shown with the seed words, so that recovery can skip older
+    /// blocks
+    #[structopt(long, alias = "wallet_birthday")]
+    pub wallet_birthday: Option<String>,
     /// Supply the optional file name to save the wallet seed words into
     #[structopt(long, aliases = &["seed_words_file_name", "seed-words-file"], parse(from_os_str))]
     pub seed_words_file_name: Option<PathBuf>,
+    /// Restore the console wallet from an encrypted wallet backup file
+    #[structopt(long, alias = "restore_backup", parse(from_os_str))]
+    pub restore_backup: Option<PathBuf>,
     /// Wallet notify script
     #[structopt(long, alias = "notify")]
     pub wallet_notify: Option<PathBuf>,
@@ -178,7 +185,9 @@ impl Default for ConfigBootstrap {
             change_password: false,
             recovery: false,
             seed_words: None,
+            wallet_birthday: None,
             seed_words_file_name: None,
+            restore_backup: None,
             wallet_notify: None,
             command_mode_auto_exit: false,
             mine_until_height: None,
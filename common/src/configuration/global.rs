@@ -32,7 +32,7 @@ use std::{
     convert::TryInto,
     fmt,
     fmt::{Display, Formatter},
-    net::SocketAddr,
+    net::{Ipv4Addr, SocketAddr},
     num::{NonZeroU16, TryFromIntError},
     path::PathBuf,
     str::FromStr,
@@ -62,7 +62,19 @@ pub struct GlobalConfig {
     pub allow_test_addresses: bool,
     pub listnener_liveness_max_sessions: usize,
     pub listener_liveness_allowlist_cidrs: Vec<String>,
+    pub firewall_allow_cidrs: Vec<String>,
+    pub firewall_deny_cidrs: Vec<String>,
+    pub firewall_max_connections_per_ip: Option<usize>,
+    pub firewall_max_connections_per_subnet: Option<usize>,
+    pub firewall_max_handshake_failures: usize,
+    pub firewall_handshake_failure_block_period: Duration,
+    pub bandwidth_max_upload_rate: Option<usize>,
+    pub bandwidth_max_download_rate: Option<usize>,
+    pub bandwidth_protocol_limits: Vec<String>,
     pub rpc_max_simultaneous_sessions: Option<usize>,
+    pub rpc_max_sessions_per_peer: Option<usize>,
+    pub rpc_peer_request_budget: Option<usize>,
+    pub rpc_recording_file: Option<PathBuf>,
     pub data_dir: PathBuf,
     pub db_type: DatabaseType,
     pub db_config: LMDBConfig,
@@ -73,9 +85,16 @@ pub struct GlobalConfig {
     pub core_threads: Option<usize>,
     pub base_node_identity_file: PathBuf,
     pub public_address: Multiaddr,
+    pub reachability_check_enabled: bool,
+    pub reachability_check_interval: Duration,
+    pub reachability_port_mapping_enabled: bool,
+    pub reachability_port_mapping_gateway: Option<Ipv4Addr>,
+    pub reachability_tor_fallback_address: Option<Multiaddr>,
     pub grpc_enabled: bool,
     pub grpc_base_node_address: SocketAddr,
     pub grpc_console_wallet_address: SocketAddr,
+    pub base_node_metrics_server_address: Option<SocketAddr>,
+    pub console_wallet_metrics_server_address: Option<SocketAddr>,
     pub peer_seeds: Vec<String>,
     pub dns_seeds: Vec<String>,
     pub dns_seeds_name_server: SocketAddr,
@@ -117,6 +136,22 @@ pub struct GlobalConfig {
     pub wallet_base_node_service_request_max_age: u64,
     pub wallet_balance_enquiry_cooldown_period: u64,
     pub prevent_fee_gt_amount: bool,
+    pub wallet_utxo_consolidation_enabled: bool,
+    pub wallet_utxo_consolidation_dry_run: bool,
+    pub wallet_utxo_consolidation_interval: Duration,
+    pub wallet_utxo_consolidation_threshold: usize,
+    pub wallet_utxo_consolidation_max_inputs: usize,
+    pub wallet_utxo_consolidation_max_transactions: usize,
+    pub wallet_utxo_consolidation_fee_per_gram: u64,
+    pub wallet_utxo_consolidation_max_network_fee_per_gram: Option<u64>,
+    pub wallet_acceptance_min_amount: Option<u64>,
+    pub wallet_acceptance_contacts_only: bool,
+    pub wallet_acceptance_rate_limit: Option<usize>,
+    pub wallet_acceptance_rate_limit_period: Duration,
+    pub wallet_acceptance_approval_threshold: Option<u64>,
+    pub wallet_held_transaction_approval_timeout: Duration,
+    pub wallet_max_held_transactions: usize,
+    pub wallet_grpc_seed_words_enabled: bool,
     pub monerod_url: String,
     pub monerod_username: String,
     pub monerod_password: String,
@@ -128,6 +163,9 @@ pub struct GlobalConfig {
     pub wait_for_initial_sync_at_startup: bool,
     pub max_randomx_vms: usize,
     pub console_wallet_notify_file: Option<PathBuf>,
+    pub console_wallet_webhooks: Vec<String>,
+    pub console_wallet_webhook_secret: Option<String>,
+    pub console_wallet_webhook_max_retries: u32,
     pub auto_ping_interval: u64,
     pub blocks_behind_before_considered_lagging: u64,
     pub flood_ban_max_msg_count: usize,
@@ -332,6 +370,34 @@ fn convert_node_config(
                 .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
         })?;
 
+    // Reachability
+    let key = config_string("base_node", net_str, "reachability_check_enabled");
+    let reachability_check_enabled = optional(cfg.get_bool(&key))?.unwrap_or(true);
+
+    let key = config_string("base_node", net_str, "reachability_check_interval");
+    let reachability_check_interval = optional(cfg.get_int(&key))?
+        .map(|seconds| Duration::from_secs(seconds as u64))
+        .unwrap_or_else(|| Duration::from_secs(30 * 60));
+
+    let key = config_string("base_node", net_str, "reachability_port_mapping_enabled");
+    let reachability_port_mapping_enabled = optional(cfg.get_bool(&key))?.unwrap_or(false);
+
+    let key = config_string("base_node", net_str, "reachability_port_mapping_gateway");
+    let reachability_port_mapping_gateway = optional(cfg.get_str(&key))?
+        .map(|addr| {
+            addr.parse::<Ipv4Addr>()
+                .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
+        })
+        .transpose()?;
+
+    let key = config_string("base_node", net_str, "reachability_tor_fallback_address");
+    let reachability_tor_fallback_address = optional(cfg.get_str(&key))?
+        .map(|addr| {
+            addr.parse::<Multiaddr>()
+                .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
+        })
+        .transpose()?;
+
     // GPRC enabled
     let key = config_string("base_node", net_str, "grpc_enabled");
     let grpc_enabled = cfg
@@ -356,6 +422,14 @@ fn convert_node_config(
                 .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
         })?;
 
+    let key = config_string("base_node", net_str, "metrics_server_address");
+    let base_node_metrics_server_address = optional(cfg.get_str(&key))?
+        .map(|addr| {
+            addr.parse::<SocketAddr>()
+                .map_err(|e| ConfigurationError::new(&key, &e.to_string()))
+        })
+        .transpose()?;
+
     // Peer and DNS seeds
     let key = config_string("base_node", net_str, "peer_seeds");
     // Peer seeds can be an array or a comma separated list (e.g. in an ENVVAR)
@@ -491,6 +565,58 @@ fn convert_node_config(
         .get_bool(key)
         .map_err(|e| ConfigurationError::new(key, &e.to_string()))?;
 
+    let key = "wallet.utxo_consolidation_enabled";
+    let wallet_utxo_consolidation_enabled = optional(cfg.get_bool(key))?.unwrap_or(false);
+
+    let key = "wallet.utxo_consolidation_dry_run";
+    let wallet_utxo_consolidation_dry_run = optional(cfg.get_bool(key))?.unwrap_or(false);
+
+    let key = "wallet.utxo_consolidation_interval";
+    let wallet_utxo_consolidation_interval =
+        Duration::from_secs(optional(cfg.get_int(key))?.map(|i| i as u64).unwrap_or(60 * 60));
+
+    let key = "wallet.utxo_consolidation_threshold";
+    let wallet_utxo_consolidation_threshold = optional(cfg.get_int(key))?.unwrap_or(500) as usize;
+
+    let key = "wallet.utxo_consolidation_max_inputs";
+    let wallet_utxo_consolidation_max_inputs = optional(cfg.get_int(key))?.unwrap_or(100) as usize;
+
+    let key = "wallet.utxo_consolidation_max_transactions";
+    let wallet_utxo_consolidation_max_transactions = optional(cfg.get_int(key))?.unwrap_or(1) as usize;
+
+    let key = "wallet.utxo_consolidation_fee_per_gram";
+    let wallet_utxo_consolidation_fee_per_gram = optional(cfg.get_int(key))?.map(|i| i as u64).unwrap_or(5);
+
+    let key = "wallet.utxo_consolidation_max_network_fee_per_gram";
+    let wallet_utxo_consolidation_max_network_fee_per_gram = optional(cfg.get_int(key))?.map(|i| i as u64);
+
+    // Incoming transaction acceptance policy
+    let key = "wallet.acceptance_min_amount";
+    let wallet_acceptance_min_amount = optional(cfg.get_int(key))?.map(|i| i as u64);
+
+    let key = "wallet.acceptance_contacts_only";
+    let wallet_acceptance_contacts_only = optional(cfg.get_bool(key))?.unwrap_or(false);
+
+    let key = "wallet.acceptance_rate_limit";
+    let wallet_acceptance_rate_limit = optional(cfg.get_int(key))?.map(|i| i as usize);
+
+    let key = "wallet.acceptance_rate_limit_period";
+    let wallet_acceptance_rate_limit_period =
+        Duration::from_secs(optional(cfg.get_int(key))?.map(|i| i as u64).unwrap_or(60 * 60));
+
+    let key = "wallet.acceptance_approval_threshold";
+    let wallet_acceptance_approval_threshold = optional(cfg.get_int(key))?.map(|i| i as u64);
+
+    let key = "wallet.held_transaction_approval_timeout";
+    let wallet_held_transaction_approval_timeout =
+        Duration::from_secs(optional(cfg.get_int(key))?.map(|i| i as u64).unwrap_or(24 * 60 * 60));
+
+    let key = "wallet.max_held_transactions";
+    let wallet_max_held_transactions = optional(cfg.get_int(key))?.unwrap_or(100) as usize;
+
+    let key = "wallet.grpc_seed_words_enabled";
+    let wallet_grpc_seed_words_enabled = optional(cfg.get_bool(key))?.unwrap_or(false);
+
     let key = "wallet.transaction_routing_mechanism";
     let transaction_routing_mechanism =
         optional(cfg.get_str(key))?.unwrap_or_else(|| "DirectAndStoreAndForward".to_string());
@@ -517,6 +643,34 @@ fn convert_node_config(
     let key = "wallet.notify";
     let console_wallet_notify_file = optional(cfg.get_str(key))?.map(PathBuf::from);
 
+    let key = "wallet.webhooks";
+    // Webhooks can be an array or a comma separated list (e.g. in an ENVVAR)
+    let console_wallet_webhooks = match cfg.get_array(key) {
+        Ok(urls) => urls.into_iter().map(|v| v.into_str().unwrap()).collect(),
+        Err(..) => match cfg.get_str(key) {
+            Ok(s) => s
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect(),
+            Err(..) => vec![],
+        },
+    };
+
+    let key = "wallet.webhook_secret";
+    let console_wallet_webhook_secret = optional(cfg.get_str(key))?;
+
+    let key = "wallet.webhook_max_retries";
+    let console_wallet_webhook_max_retries = optional(cfg.get_int(key))?.map(|i| i as u32).unwrap_or(15);
+
+    let key = "wallet.metrics_server_address";
+    let console_wallet_metrics_server_address = optional(cfg.get_str(key))?
+        .map(|addr| {
+            addr.parse::<SocketAddr>()
+                .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        })
+        .transpose()?;
+
     let key = "wallet.base_node_service_refresh_interval";
     let wallet_base_node_service_refresh_interval = cfg
         .get_int(key)
@@ -551,6 +705,90 @@ fn convert_node_config(
         .map(|values| values.iter().map(ToString::to_string).collect())
         .unwrap_or_else(|_| vec!["127.0.0.1/32".to_string()]);
 
+    // Inbound connection firewall
+    let key = "common.firewall_allow_cidrs";
+    let firewall_allow_cidrs = optional(cfg.get_array(key))?
+        .map(|values| values.iter().map(ToString::to_string).collect())
+        .unwrap_or_default();
+
+    let key = "common.firewall_deny_cidrs";
+    let firewall_deny_cidrs = optional(cfg.get_array(key))?
+        .map(|values| values.iter().map(ToString::to_string).collect())
+        .unwrap_or_default();
+
+    let key = "common.firewall_max_connections_per_ip";
+    let firewall_max_connections_per_ip = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            -1 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for firewall_max_connections_per_ip", v),
+            )),
+        })?;
+
+    let key = "common.firewall_max_connections_per_subnet";
+    let firewall_max_connections_per_subnet = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            -1 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for firewall_max_connections_per_subnet", v),
+            )),
+        })?;
+
+    let key = "common.firewall_max_handshake_failures";
+    let firewall_max_handshake_failures = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?
+        .try_into()
+        .map_err(|e: TryFromIntError| ConfigurationError::new(key, &e.to_string()))?;
+
+    let key = "common.firewall_handshake_failure_block_period";
+    let firewall_handshake_failure_block_period = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))?
+        .try_into()
+        .map(Duration::from_secs)
+        .map_err(|e: TryFromIntError| ConfigurationError::new(key, &e.to_string()))?;
+
+    // Bandwidth limits (configured in KiB/s)
+    let key = "common.bandwidth_max_upload_rate";
+    let bandwidth_max_upload_rate = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            0 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize * 1024)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for bandwidth_max_upload_rate", v),
+            )),
+        })?;
+
+    let key = "common.bandwidth_max_download_rate";
+    let bandwidth_max_download_rate = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            0 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize * 1024)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for bandwidth_max_download_rate", v),
+            )),
+        })?;
+
+    let key = "common.bandwidth_protocol_limits";
+    let bandwidth_protocol_limits = optional(cfg.get_array(key))?
+        .map(|values| values.iter().map(ToString::to_string).collect())
+        .unwrap_or_default();
+
     let key = "common.rpc_max_simultaneous_sessions";
     let rpc_max_simultaneous_sessions = cfg
         .get_int(key)
@@ -564,6 +802,35 @@ fn convert_node_config(
             )),
         })?;
 
+    let key = "common.rpc_max_sessions_per_peer";
+    let rpc_max_sessions_per_peer = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            -1 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for rpc_max_sessions_per_peer", v),
+            )),
+        })?;
+
+    let key = "common.rpc_peer_request_budget";
+    let rpc_peer_request_budget = cfg
+        .get_int(key)
+        .map_err(|e| ConfigurationError::new(key, &e.to_string()))
+        .and_then(|v| match v {
+            -1 => Ok(None),
+            n if n.is_positive() => Ok(Some(n as usize)),
+            v => Err(ConfigurationError::new(
+                key,
+                &format!("invalid value {} for rpc_peer_request_budget", v),
+            )),
+        })?;
+
+    let key = "common.rpc_recording_file";
+    let rpc_recording_file = optional(cfg.get_str(key))?.map(PathBuf::from);
+
     let key = "common.buffer_size_base_node";
     let buffer_size_base_node = cfg
         .get_int(key)
@@ -710,7 +977,19 @@ fn convert_node_config(
         allow_test_addresses,
         listnener_liveness_max_sessions: liveness_max_sessions,
         listener_liveness_allowlist_cidrs: liveness_allowlist_cidrs,
+        firewall_allow_cidrs,
+        firewall_deny_cidrs,
+        firewall_max_connections_per_ip,
+        firewall_max_connections_per_subnet,
+        firewall_max_handshake_failures,
+        firewall_handshake_failure_block_period,
+        bandwidth_max_upload_rate,
+        bandwidth_max_download_rate,
+        bandwidth_protocol_limits,
         rpc_max_simultaneous_sessions,
+        rpc_max_sessions_per_peer,
+        rpc_peer_request_budget,
+        rpc_recording_file,
         data_dir,
         db_type,
         db_config,
@@ -721,9 +1000,16 @@ fn convert_node_config(
         core_threads,
         base_node_identity_file,
         public_address,
+        reachability_check_enabled,
+        reachability_check_interval,
+        reachability_port_mapping_enabled,
+        reachability_port_mapping_gateway,
+        reachability_tor_fallback_address,
         grpc_enabled,
         grpc_base_node_address,
         grpc_console_wallet_address,
+        base_node_metrics_server_address,
+        console_wallet_metrics_server_address,
         peer_seeds,
         dns_seeds,
         dns_seeds_name_server,
@@ -765,6 +1051,22 @@ fn convert_node_config(
         wallet_base_node_service_request_max_age,
         wallet_balance_enquiry_cooldown_period,
         prevent_fee_gt_amount,
+        wallet_utxo_consolidation_enabled,
+        wallet_utxo_consolidation_dry_run,
+        wallet_utxo_consolidation_interval,
+        wallet_utxo_consolidation_threshold,
+        wallet_utxo_consolidation_max_inputs,
+        wallet_utxo_consolidation_max_transactions,
+        wallet_utxo_consolidation_fee_per_gram,
+        wallet_utxo_consolidation_max_network_fee_per_gram,
+        wallet_acceptance_min_amount,
+        wallet_acceptance_contacts_only,
+        wallet_acceptance_rate_limit,
+        wallet_acceptance_rate_limit_period,
+        wallet_acceptance_approval_threshold,
+        wallet_held_transaction_approval_timeout,
+        wallet_max_held_transactions,
+        wallet_grpc_seed_words_enabled,
         proxy_host_address,
         transcoder_host_address,
         proxy_submit_to_origin,
@@ -776,6 +1078,9 @@ fn convert_node_config(
         wait_for_initial_sync_at_startup,
         max_randomx_vms,
         console_wallet_notify_file,
+        console_wallet_webhooks,
+        console_wallet_webhook_secret,
+        console_wallet_webhook_max_retries,
         auto_ping_interval,
         blocks_behind_before_considered_lagging,
         flood_ban_max_msg_count,
@@ -902,6 +1207,16 @@ fn network_transport_config(
             let key = config_string(app_str, network, "tor_proxy_bypass_for_outbound_tcp");
             let tor_proxy_bypass_for_outbound_tcp = optional(cfg.get_bool(&key))?.unwrap_or(false);
 
+            let key = config_string(app_str, network, "tor_client_auth");
+            let tor_client_auth = optional(cfg.get_array(&key))?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|v| {
+                    v.into_str()
+                        .map_err(|err| ConfigurationError::new(&key, &err.to_string()))
+                })
+                .collect::<Result<_, _>>()?;
+
             Ok(CommsTransport::TorHiddenService {
                 control_server_address,
                 auth,
@@ -910,6 +1225,7 @@ fn network_transport_config(
                 onion_port,
                 tor_proxy_bypass_addresses,
                 tor_proxy_bypass_for_outbound_tcp,
+                tor_client_auth,
             })
         },
         "socks5" => {
@@ -931,6 +1247,12 @@ fn network_transport_config(
                 auth,
             })
         },
+        "quic" => {
+            let key = config_string(app_str, network, "quic_listener_address");
+            let listener_address = get_conf_multiaddr(&key)?;
+
+            Ok(CommsTransport::Quic { listener_address })
+        },
         t => Err(ConfigurationError::new(
             &transport_key,
             &format!("Invalid transport type '{}'", t),
@@ -1051,6 +1373,9 @@ pub enum CommsTransport {
         onion_port: NonZeroU16,
         tor_proxy_bypass_addresses: Vec<Multiaddr>,
         tor_proxy_bypass_for_outbound_tcp: bool,
+        /// Base32-encoded x25519 public keys of the clients that are authorized to connect to the hidden service. If
+        /// empty, any client may connect.
+        tor_client_auth: Vec<String>,
     },
     /// Use a SOCKS5 proxy transport. This transport recognises any addresses supported by the proxy.
     Socks5 {
@@ -1058,4 +1383,7 @@ pub enum CommsTransport {
         auth: SocksAuthentication,
         listener_address: Multiaddr,
     },
+    /// Use the QUIC transport to join the Tari network. This transport can only communicate with QUIC addresses
+    /// (e.g. /ip4/1.2.3.4/udp/18189/quic). Requires the node to be built with the `quic` feature.
+    Quic { listener_address: Multiaddr },
 }
@@ -58,7 +58,17 @@ pub fn default_config(bootstrap: &ConfigBootstrap) -> Config {
     cfg.set_default("common.message_cache_ttl", 1440).unwrap();
     cfg.set_default("common.peer_allowlist", Vec::<String>::new()).unwrap();
     cfg.set_default("common.rpc_max_simultaneous_sessions", 1000).unwrap();
+    cfg.set_default("common.rpc_max_sessions_per_peer", 10).unwrap();
+    cfg.set_default("common.rpc_peer_request_budget", 1000).unwrap();
     cfg.set_default("common.liveness_max_sessions", 0).unwrap();
+    cfg.set_default("common.firewall_max_connections_per_ip", -1).unwrap();
+    cfg.set_default("common.firewall_max_connections_per_subnet", -1)
+        .unwrap();
+    cfg.set_default("common.firewall_max_handshake_failures", 5).unwrap();
+    cfg.set_default("common.firewall_handshake_failure_block_period", 1800)
+        .unwrap();
+    cfg.set_default("common.bandwidth_max_upload_rate", 0).unwrap();
+    cfg.set_default("common.bandwidth_max_download_rate", 0).unwrap();
     cfg.set_default("common.denylist_ban_period", 1440).unwrap();
     cfg.set_default("common.buffer_size_base_node", 1_500).unwrap();
     cfg.set_default("common.buffer_size_console_wallet", 50_000).unwrap();
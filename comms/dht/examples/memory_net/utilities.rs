@@ -648,6 +648,9 @@ fn connection_manager_logger(
                     node_name
                 );
             },
+            event => {
+                println!("'{}' {}", node_name, event);
+            },
         }
         event
     }
@@ -96,12 +96,13 @@ pub struct DhtConfig {
     pub connectivity_random_pool_refresh: Duration,
     /// Network discovery config
     pub network_discovery: NetworkDiscoveryConfig,
-    /// Length of time to ban a peer if the peer misbehaves at the DHT-level.
+    /// Length of time to ban a peer that sends or propagates a message with a forged or missing origin signature.
+    /// Other DHT-level offences are reported against the peer's reputation.
     /// Default: 6 hrs
     pub ban_duration: Duration,
     /// This allows the use of test addresses in the network.
     pub allow_test_addresses: bool,
-    /// The maximum number of messages over `flood_ban_timespan` to allow before banning the peer (for `ban_duration`)
+    /// The maximum number of messages over `flood_ban_timespan` to allow before reporting the peer for flooding
     /// Default: 1000 messages
     pub flood_ban_max_msg_count: usize,
     /// The timespan over which to calculate the max message rate.
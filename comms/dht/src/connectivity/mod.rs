@@ -31,7 +31,7 @@ use log::*;
 use std::{sync::Arc, time::Instant};
 use tari_comms::{
     connectivity::{ConnectivityError, ConnectivityEvent, ConnectivityEventRx, ConnectivityRequester},
-    peer_manager::{node_id::NodeDistance, NodeId, PeerManagerError, PeerQuery, PeerQuerySortBy},
+    peer_manager::{node_id::NodeDistance, Misbehaviour, NodeId, PeerManagerError, PeerQuery, PeerQuerySortBy},
     NodeIdentity,
     PeerConnection,
     PeerManager,
@@ -154,7 +154,7 @@ impl DhtConnectivity {
                     if let Err(err) = self.refresh_random_pool_if_required().await {
                         debug!(target: LOG_TARGET, "Error refreshing random peer pool: {:?}", err);
                     }
-                    if let Err(err) = self.check_and_ban_flooding_peers().await {
+                    if let Err(err) = self.check_and_report_flooding_peers().await {
                         debug!(target: LOG_TARGET, "Error checking for peer flooding: {:?}", err);
                     }
                     self.log_status();
@@ -234,7 +234,7 @@ impl DhtConnectivity {
         Ok(())
     }
 
-    async fn check_and_ban_flooding_peers(&mut self) -> Result<(), DhtConnectivityError> {
+    async fn check_and_report_flooding_peers(&mut self) -> Result<(), DhtConnectivityError> {
         let nodes = self
             .metrics_collector
             .get_message_rates_exceeding(self.config.flood_ban_max_msg_count, self.config.flood_ban_timespan)
@@ -243,13 +243,13 @@ impl DhtConnectivity {
         for (peer, mps) in nodes {
             warn!(
                 target: LOG_TARGET,
-                "Banning peer `{}` because of flooding. Message rate: {:.2}m/s", peer, mps
+                "Reporting peer `{}` for flooding. Message rate: {:.2}m/s", peer, mps
             );
             self.connectivity
-                .ban_peer_until(
+                .report_misbehaviour(
                     peer,
-                    self.config.ban_duration,
-                    "Exceeded maximum message rate".to_string(),
+                    Misbehaviour::MessageFlood,
+                    format!("Exceeded maximum message rate ({:.2}m/s)", mps),
                 )
                 .await?;
         }
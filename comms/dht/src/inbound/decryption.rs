@@ -34,7 +34,7 @@ use std::{sync::Arc, task::Poll, time::Duration};
 use tari_comms::{
     connectivity::ConnectivityRequester,
     message::EnvelopeBody,
-    peer_manager::NodeIdentity,
+    peer_manager::{Misbehaviour, NodeIdentity},
     pipeline::PipelineError,
     types::{Challenge, CommsPublicKey},
     utils::signature,
@@ -170,10 +170,13 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
             Err(err @ EphemeralKeyNotProvided) |
             Err(err @ OriginMacInvalidSignature) => {
                 // This message should not have been propagated, or has been manipulated in some way. Ban the source of
-                // this message.
+                // this message, whatever its reputation, and record the offence against its reputation.
                 connectivity
                     .ban_peer_until(source.node_id.clone(), ban_duration, err.to_string())
                     .await?;
+                connectivity
+                    .report_misbehaviour(source.node_id.clone(), Misbehaviour::InvalidSignature, err.to_string())
+                    .await?;
                 Err(err.into())
             },
             Err(EnvelopeBodyDecodeFailed) => {
@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{inbound::DhtInboundMessage, proto::envelope::DhtEnvelope};
+use crate::{inbound::DhtInboundMessage, metrics, proto::envelope::DhtEnvelope};
 use futures::{future::BoxFuture, task::Context};
 use log::*;
 use prost::Message;
@@ -86,6 +86,7 @@ where
 
                     let inbound_msg =
                         DhtInboundMessage::new(tag, dht_envelope.header.try_into()?, source_peer, dht_envelope.body);
+                    metrics::inbound_messages(inbound_msg.dht_header.message_type).inc();
                     trace!(
                         target: LOG_TARGET,
                         "Deserialization succeeded. Passing message {} onto next service (Trace: {})",
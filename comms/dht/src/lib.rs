@@ -155,6 +155,7 @@ pub use dedup::DedupLayer;
 
 mod filter;
 mod logging_middleware;
+mod metrics;
 mod proto;
 mod rpc;
 mod schema;
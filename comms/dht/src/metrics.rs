@@ -0,0 +1,87 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::envelope::DhtMessageType;
+use once_cell::sync::Lazy;
+use tari_metrics::{IntCounter, IntCounterVec};
+
+pub fn inbound_messages(message_type: DhtMessageType) -> IntCounter {
+    static METER: Lazy<IntCounterVec> = Lazy::new(|| {
+        tari_metrics::register_int_counter_vec(
+            "comms_dht_inbound_messages",
+            "Number of inbound DHT messages by DHT message type",
+            &["message_type"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[&message_type.to_string()])
+}
+
+pub fn saf_messages_stored() -> IntCounter {
+    static METER: Lazy<IntCounter> = Lazy::new(|| {
+        tari_metrics::register_int_counter(
+            "comms_dht_saf_messages_stored",
+            "Number of messages stored on behalf of other peers",
+        )
+        .unwrap()
+    });
+
+    METER.clone()
+}
+
+pub fn saf_requests_handled() -> IntCounter {
+    static METER: Lazy<IntCounter> = Lazy::new(|| {
+        tari_metrics::register_int_counter(
+            "comms_dht_saf_requests_handled",
+            "Number of stored message requests received from peers",
+        )
+        .unwrap()
+    });
+
+    METER.clone()
+}
+
+pub fn saf_messages_sent() -> IntCounter {
+    static METER: Lazy<IntCounter> = Lazy::new(|| {
+        tari_metrics::register_int_counter(
+            "comms_dht_saf_messages_sent",
+            "Number of stored messages sent to peers in response to requests",
+        )
+        .unwrap()
+    });
+
+    METER.clone()
+}
+
+pub fn saf_messages_received(accepted: bool) -> IntCounter {
+    static METER: Lazy<IntCounterVec> = Lazy::new(|| {
+        tari_metrics::register_int_counter_vec(
+            "comms_dht_saf_messages_received",
+            "Number of stored messages received from peers by whether they were accepted",
+            &["accepted"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[if accepted { "true" } else { "false" }])
+}
@@ -26,6 +26,7 @@ use crate::{
     crypt,
     envelope::{timestamp_to_datetime, DhtMessageFlags, DhtMessageHeader, NodeDestination},
     inbound::{DecryptedDhtMessage, DhtInboundMessage},
+    metrics,
     outbound::{OutboundMessageRequester, SendMessageParams},
     proto::{
         envelope::{DhtMessageType, OriginMac},
@@ -182,6 +183,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         let retrieve_msgs = msg
             .decode_part::<StoredMessagesRequest>(0)?
             .ok_or(StoreAndForwardError::InvalidEnvelopeBody)?;
+        metrics::saf_requests_handled().inc();
 
         let source_pubkey = Box::new(message.source_peer.public_key.clone());
         let source_node_id = Box::new(message.source_peer.node_id.clone());
@@ -214,6 +216,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
                 request_id: retrieve_msgs.request_id,
                 response_type: resp_type as i32,
             };
+            let num_messages = stored_messages.messages().len();
 
             debug!(
                 target: LOG_TARGET,
@@ -236,6 +239,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
                 .await
             {
                 Ok(_) => {
+                    metrics::saf_messages_sent().inc_by(num_messages as u64);
                     if let Some(threshold) = since {
                         debug!(
                             target: LOG_TARGET,
@@ -301,6 +305,7 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError>
         let successful_msgs_iter = results
             .into_iter()
             .map(|result| {
+                metrics::saf_messages_received(result.is_ok()).inc();
                 match &result {
                     Ok(msg) => {
                         trace!(target: LOG_TARGET, "Recv SAF message: {}", msg);
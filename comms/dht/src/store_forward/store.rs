@@ -23,6 +23,7 @@
 use super::StoreAndForwardRequester;
 use crate::{
     inbound::DecryptedDhtMessage,
+    metrics,
     store_forward::{
         database::NewStoredMessage,
         error::StoreAndForwardError,
@@ -442,7 +443,11 @@ where S: Service<DecryptedDhtMessage, Response = (), Error = PipelineError> + Se
 
         let stored_message =
             NewStoredMessage::try_construct(message, priority).ok_or(StoreAndForwardError::InvalidStoreMessage)?;
-        self.saf_requester.insert_message(stored_message).await
+        let is_stored = self.saf_requester.insert_message(stored_message).await?;
+        if is_stored {
+            metrics::saf_messages_stored().inc();
+        }
+        Ok(is_stored)
     }
 }
 
@@ -0,0 +1,215 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::protocol::ProtocolId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::AddAssign,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+};
+
+/// The number of bytes sent and received
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BandwidthTotals {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl BandwidthTotals {
+    pub fn new(bytes_sent: u64, bytes_received: u64) -> Self {
+        Self {
+            bytes_sent,
+            bytes_received,
+        }
+    }
+
+    /// The total number of bytes sent and received
+    pub fn total(&self) -> u64 {
+        self.bytes_sent.saturating_add(self.bytes_received)
+    }
+}
+
+impl AddAssign for BandwidthTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.bytes_sent = self.bytes_sent.saturating_add(other.bytes_sent);
+        self.bytes_received = self.bytes_received.saturating_add(other.bytes_received);
+    }
+}
+
+impl fmt::Display for BandwidthTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sent: {} bytes, received: {} bytes",
+            self.bytes_sent, self.bytes_received
+        )
+    }
+}
+
+/// Counts bytes sent and received. Clones of this counter share the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthCounter {
+    bytes_sent: Arc<AtomicU64>,
+    bytes_received: Arc<AtomicU64>,
+}
+
+impl BandwidthCounter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn record_sent(&self, num_bytes: usize) {
+        self.bytes_sent.fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_received(&self, num_bytes: usize) {
+        self.bytes_received.fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn totals(&self) -> BandwidthTotals {
+        BandwidthTotals::new(
+            self.bytes_sent.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Bandwidth totals for a connection (or all connections to a peer) and for each protocol that was negotiated on it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BandwidthStats {
+    pub total: BandwidthTotals,
+    pub protocols: HashMap<ProtocolId, BandwidthTotals>,
+}
+
+impl BandwidthStats {
+    /// Adds the totals in `other` to these stats
+    pub fn merge(&mut self, other: &BandwidthStats) {
+        self.total += other.total;
+        for (protocol, totals) in &other.protocols {
+            *self.protocols.entry(protocol.clone()).or_default() += *totals;
+        }
+    }
+
+    /// Returns the protocol totals, most bytes transferred first
+    pub fn protocols_by_usage(&self) -> Vec<(&ProtocolId, &BandwidthTotals)> {
+        let mut protocols = self.protocols.iter().collect::<Vec<_>>();
+        protocols.sort_by(|(_, a), (_, b)| b.total().cmp(&a.total()));
+        protocols
+    }
+}
+
+/// Byte counters for a multiplexed connection and for each protocol negotiated on its substreams. Clones share the
+/// same counters.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionBandwidth {
+    total: BandwidthCounter,
+    protocols: Arc<Mutex<HashMap<ProtocolId, BandwidthCounter>>>,
+}
+
+impl ConnectionBandwidth {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the counter for all bytes transferred on the connection
+    pub fn total(&self) -> &BandwidthCounter {
+        &self.total
+    }
+
+    /// Returns the counter for the given protocol, creating it if necessary
+    pub fn protocol_counter(&self, protocol: &ProtocolId) -> BandwidthCounter {
+        self.protocols
+            .lock()
+            .unwrap()
+            .entry(protocol.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Returns a snapshot of the connection and protocol totals
+    pub fn stats(&self) -> BandwidthStats {
+        let protocols = self
+            .protocols
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(protocol, counter)| (protocol.clone(), counter.totals()))
+            .collect();
+        BandwidthStats {
+            total: self.total.totals(),
+            protocols,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_counts_connection_and_protocol_bytes() {
+        let bandwidth = ConnectionBandwidth::new();
+        let protocol = ProtocolId::from_static(b"t/test/1");
+        let counter = bandwidth.protocol_counter(&protocol);
+        counter.record_sent(10);
+        counter.record_received(5);
+        bandwidth.total().record_sent(12);
+        bandwidth.total().record_received(5);
+        // Clones share the same counter
+        bandwidth.protocol_counter(&protocol).record_sent(1);
+
+        let stats = bandwidth.stats();
+        assert_eq!(stats.total, BandwidthTotals::new(12, 5));
+        assert_eq!(stats.protocols[&protocol], BandwidthTotals::new(11, 5));
+    }
+
+    #[test]
+    fn it_merges_stats() {
+        let a = ProtocolId::from_static(b"t/a/1");
+        let b = ProtocolId::from_static(b"t/b/1");
+        let mut stats = BandwidthStats {
+            total: BandwidthTotals::new(10, 10),
+            protocols: vec![(a.clone(), BandwidthTotals::new(10, 10))].into_iter().collect(),
+        };
+        stats.merge(&BandwidthStats {
+            total: BandwidthTotals::new(100, 200),
+            protocols: vec![
+                (a.clone(), BandwidthTotals::new(0, 10)),
+                (b.clone(), BandwidthTotals::new(100, 190)),
+            ]
+            .into_iter()
+            .collect(),
+        });
+
+        assert_eq!(stats.total, BandwidthTotals::new(110, 210));
+        assert_eq!(stats.protocols[&a], BandwidthTotals::new(10, 20));
+        let by_usage = stats.protocols_by_usage();
+        assert_eq!(by_usage[0].0, &b);
+        assert_eq!(by_usage[1].0, &a);
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{protocol::ProtocolId, rate_limit::TokenBucket};
+use std::{
+    cmp,
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{self, Sleep};
+
+/// Rates are limited per second
+const RESTOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Upload and download rate limits in bytes per second. `None` (or zero) is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimit {
+    pub max_upload_rate: Option<usize>,
+    pub max_download_rate: Option<usize>,
+}
+
+impl BandwidthLimit {
+    pub fn new(max_upload_rate: Option<usize>, max_download_rate: Option<usize>) -> Self {
+        Self {
+            max_upload_rate,
+            max_download_rate,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Default::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthConfig {
+    /// The limits for all connections combined. Default: unlimited
+    pub global: BandwidthLimit,
+    /// The limits for each protocol. A protocol limit applies to all substreams of the protocol on all connections
+    /// combined, in addition to the global limit. Default: none
+    pub protocols: HashMap<ProtocolId, BandwidthLimit>,
+}
+
+impl BandwidthConfig {
+    pub fn is_unlimited(&self) -> bool {
+        is_unlimited(&self.global) && self.protocols.values().all(is_unlimited)
+    }
+}
+
+fn is_unlimited(limit: &BandwidthLimit) -> bool {
+    limit.max_upload_rate.filter(|r| *r > 0).is_none() && limit.max_download_rate.filter(|r| *r > 0).is_none()
+}
+
+type SharedBucket = Arc<Mutex<TokenBucket>>;
+
+#[derive(Debug, Clone, Default)]
+struct Buckets {
+    upload: Option<SharedBucket>,
+    download: Option<SharedBucket>,
+}
+
+impl Buckets {
+    fn new(limit: &BandwidthLimit) -> Self {
+        let new_bucket = |rate: usize| Arc::new(Mutex::new(TokenBucket::new(rate, RESTOCK_INTERVAL)));
+        Self {
+            upload: limit.max_upload_rate.filter(|r| *r > 0).map(new_bucket),
+            download: limit.max_download_rate.filter(|r| *r > 0).map(new_bucket),
+        }
+    }
+}
+
+/// Enforces the limits given in a [BandwidthConfig](self::BandwidthConfig). Clones share the same limits, so that a
+/// single limiter applies to all connections.
+#[derive(Debug, Clone, Default)]
+pub struct BandwidthLimiter {
+    global: Buckets,
+    protocols: Arc<HashMap<ProtocolId, Buckets>>,
+}
+
+impl BandwidthLimiter {
+    pub fn new(config: &BandwidthConfig) -> Self {
+        Self {
+            global: Buckets::new(&config.global),
+            protocols: Arc::new(
+                config
+                    .protocols
+                    .iter()
+                    .filter(|(_, limit)| !is_unlimited(limit))
+                    .map(|(protocol, limit)| (protocol.clone(), Buckets::new(limit)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// A limiter that does not limit bandwidth
+    pub fn unlimited() -> Self {
+        Default::default()
+    }
+
+    /// Returns a throttle for a substream that enforces the global limits and the limits for the given protocol
+    pub(crate) fn throttle_for(&self, protocol: &ProtocolId) -> SubstreamThrottle {
+        let protocol = self.protocols.get(protocol);
+        SubstreamThrottle {
+            upload: self
+                .global
+                .upload
+                .iter()
+                .chain(protocol.and_then(|b| b.upload.as_ref()))
+                .cloned()
+                .collect(),
+            download: self
+                .global
+                .download
+                .iter()
+                .chain(protocol.and_then(|b| b.download.as_ref()))
+                .cloned()
+                .collect(),
+            delay: None,
+        }
+    }
+}
+
+/// Throttles the reads and writes of a single substream
+#[derive(Debug, Default)]
+pub(crate) struct SubstreamThrottle {
+    upload: Vec<SharedBucket>,
+    download: Vec<SharedBucket>,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl SubstreamThrottle {
+    /// Returns the number of bytes, up to `num_bytes`, that may be written now. If the upload limit has been
+    /// reached, Pending is returned and the task is woken once the limit is restocked.
+    pub fn poll_upload_capacity(&mut self, cx: &mut Context<'_>, num_bytes: usize) -> Poll<usize> {
+        poll_capacity(&self.upload, &mut self.delay, cx, num_bytes)
+    }
+
+    /// Returns the number of bytes, up to `num_bytes`, that may be read now. If the download limit has been
+    /// reached, Pending is returned and the task is woken once the limit is restocked.
+    pub fn poll_download_capacity(&mut self, cx: &mut Context<'_>, num_bytes: usize) -> Poll<usize> {
+        poll_capacity(&self.download, &mut self.delay, cx, num_bytes)
+    }
+
+    pub fn record_sent(&self, num_bytes: usize) {
+        consume(&self.upload, num_bytes);
+    }
+
+    pub fn record_received(&self, num_bytes: usize) {
+        consume(&self.download, num_bytes);
+    }
+}
+
+fn poll_capacity(
+    buckets: &[SharedBucket],
+    delay: &mut Option<Pin<Box<Sleep>>>,
+    cx: &mut Context<'_>,
+    num_bytes: usize,
+) -> Poll<usize> {
+    if let Some(sleep) = delay.as_mut() {
+        futures::ready!(sleep.as_mut().poll(cx));
+        *delay = None;
+    }
+
+    let mut capacity = num_bytes;
+    let mut wait_for = Duration::from_secs(0);
+    for bucket in buckets {
+        let mut bucket = bucket.lock().unwrap();
+        let available = bucket.available();
+        if available == 0 {
+            wait_for = cmp::max(wait_for, bucket.time_until_restock());
+        }
+        capacity = cmp::min(capacity, available);
+    }
+
+    if capacity > 0 || num_bytes == 0 {
+        return Poll::Ready(capacity);
+    }
+
+    let mut sleep = Box::pin(time::sleep(wait_for));
+    // Poll the sleep so that the task is woken once the limit is restocked
+    if sleep.as_mut().poll(cx).is_ready() {
+        cx.waker().wake_by_ref();
+    } else {
+        *delay = Some(sleep);
+    }
+    Poll::Pending
+}
+
+fn consume(buckets: &[SharedBucket], num_bytes: usize) {
+    // Bytes that have already been transferred are taken even if the limit was reached concurrently by another
+    // substream, so the limit may briefly be exceeded by a single read or write per substream
+    for bucket in buckets {
+        bucket.lock().unwrap().take_up_to(num_bytes);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::runtime;
+    use futures::future::poll_fn;
+    use std::time::Instant;
+
+    fn protocol() -> ProtocolId {
+        ProtocolId::from_static(b"t/test/1")
+    }
+
+    #[runtime::test]
+    async fn it_does_not_limit_by_default() {
+        let limiter = BandwidthLimiter::unlimited();
+        let mut throttle = limiter.throttle_for(&protocol());
+        let capacity = poll_fn(|cx| throttle.poll_upload_capacity(cx, 1024 * 1024)).await;
+        assert_eq!(capacity, 1024 * 1024);
+        throttle.record_sent(capacity);
+        let capacity = poll_fn(|cx| throttle.poll_download_capacity(cx, 1024 * 1024)).await;
+        assert_eq!(capacity, 1024 * 1024);
+    }
+
+    #[runtime::test]
+    async fn it_limits_global_and_protocol_rates() {
+        let config = BandwidthConfig {
+            global: BandwidthLimit::new(Some(100), None),
+            protocols: vec![(protocol(), BandwidthLimit::new(Some(0), Some(10)))]
+                .into_iter()
+                .collect(),
+        };
+        assert!(!config.is_unlimited());
+        let limiter = BandwidthLimiter::new(&config);
+
+        let mut throttle = limiter.throttle_for(&protocol());
+        let mut other_throttle = limiter.throttle_for(&ProtocolId::from_static(b"t/other/1"));
+
+        // The protocol download limit applies, the zero upload limit is unlimited
+        assert_eq!(poll_fn(|cx| throttle.poll_download_capacity(cx, 50)).await, 10);
+        assert_eq!(poll_fn(|cx| other_throttle.poll_download_capacity(cx, 50)).await, 50);
+
+        // The global upload limit is shared between all substreams
+        assert_eq!(poll_fn(|cx| throttle.poll_upload_capacity(cx, 60)).await, 60);
+        throttle.record_sent(60);
+        assert_eq!(poll_fn(|cx| other_throttle.poll_upload_capacity(cx, 60)).await, 40);
+        other_throttle.record_sent(40);
+
+        // The limit is reached, the next write waits for the restock
+        let timer = Instant::now();
+        assert_eq!(poll_fn(|cx| throttle.poll_upload_capacity(cx, 60)).await, 60);
+        assert!(timer.elapsed() >= Duration::from_millis(900));
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{limiter::SubstreamThrottle, BandwidthCounter, BandwidthLimiter, ConnectionBandwidth};
+use crate::protocol::ProtocolId;
+use std::task::{Context, Poll};
+
+/// Counts the bytes transferred on a substream towards its connection and negotiated protocol, and throttles the
+/// substream according to the bandwidth limits for the protocol.
+#[derive(Debug)]
+pub(crate) struct SubstreamMeter {
+    connection: ConnectionBandwidth,
+    protocol: Option<BandwidthCounter>,
+    throttle: SubstreamThrottle,
+}
+
+impl SubstreamMeter {
+    pub fn new(connection: ConnectionBandwidth) -> Self {
+        Self {
+            connection,
+            protocol: None,
+            throttle: Default::default(),
+        }
+    }
+
+    /// Set the protocol negotiated on the substream. Bytes transferred before this is called (i.e. protocol
+    /// negotiation) only count towards the connection totals and are not throttled.
+    pub fn set_protocol(&mut self, protocol: &ProtocolId, limiter: &BandwidthLimiter) {
+        self.protocol = Some(self.connection.protocol_counter(protocol));
+        self.throttle = limiter.throttle_for(protocol);
+    }
+
+    pub fn poll_upload_capacity(&mut self, cx: &mut Context<'_>, num_bytes: usize) -> Poll<usize> {
+        self.throttle.poll_upload_capacity(cx, num_bytes)
+    }
+
+    pub fn poll_download_capacity(&mut self, cx: &mut Context<'_>, num_bytes: usize) -> Poll<usize> {
+        self.throttle.poll_download_capacity(cx, num_bytes)
+    }
+
+    pub fn record_sent(&self, num_bytes: usize) {
+        if num_bytes == 0 {
+            return;
+        }
+        self.connection.total().record_sent(num_bytes);
+        if let Some(counter) = self.protocol.as_ref() {
+            counter.record_sent(num_bytes);
+        }
+        self.throttle.record_sent(num_bytes);
+    }
+
+    pub fn record_received(&self, num_bytes: usize) {
+        if num_bytes == 0 {
+            return;
+        }
+        self.connection.total().record_received(num_bytes);
+        if let Some(counter) = self.protocol.as_ref() {
+            counter.record_received(num_bytes);
+        }
+        self.throttle.record_received(num_bytes);
+    }
+}
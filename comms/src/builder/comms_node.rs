@@ -20,7 +20,7 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{CommsBuilderError, CommsShutdown};
+use super::{consts, CommsBuilderError, CommsShutdown};
 use crate::{
     connection_manager::{
         ConnectionManager,
@@ -40,7 +40,9 @@ use crate::{
         ProtocolId,
         ProtocolNotificationTx,
         Protocols,
+        REACHABILITY_PROTOCOL,
     },
+    reachability::{ReachabilityReport, ReachabilityRequest, ReachabilityRequester, ReachabilityService},
     tor,
     transports::Transport,
     CommsBuilder,
@@ -51,7 +53,7 @@ use std::{iter, sync::Arc};
 use tari_shutdown::ShutdownSignal;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::{broadcast, mpsc},
+    sync::{broadcast, mpsc, watch},
 };
 
 const LOG_TARGET: &str = "comms::node";
@@ -64,6 +66,9 @@ pub struct UnspawnedCommsNode {
     pub(super) connection_manager_requester: ConnectionManagerRequester,
     pub(super) connectivity_requester: ConnectivityRequester,
     pub(super) connectivity_rx: mpsc::Receiver<ConnectivityRequest>,
+    pub(super) reachability_requester: ReachabilityRequester,
+    pub(super) reachability_request_rx: mpsc::Receiver<ReachabilityRequest>,
+    pub(super) reachability_report_tx: watch::Sender<ReachabilityReport>,
     pub(super) peer_manager: Arc<PeerManager>,
     pub(super) protocol_extensions: ProtocolExtensions,
     pub(super) protocols: Protocols<Substream>,
@@ -130,6 +135,9 @@ impl UnspawnedCommsNode {
             mut connection_manager_requester,
             connectivity_requester,
             connectivity_rx,
+            reachability_requester,
+            reachability_request_rx,
+            reachability_report_tx,
             node_identity,
             shutdown_signal,
             peer_manager,
@@ -142,6 +150,7 @@ impl UnspawnedCommsNode {
             hidden_service_ctl,
             connection_manager_config,
             connectivity_config,
+            reachability_config,
             ..
         } = builder;
 
@@ -169,6 +178,10 @@ impl UnspawnedCommsNode {
         );
         protocol_extensions.install_all(&mut ext_context)?;
 
+        let (reachability_proto_tx, reachability_proto_rx) =
+            mpsc::channel(consts::REACHABILITY_PROTOCOL_EVENTS_BUFFER_SIZE);
+        ext_context.add_protocol(&[REACHABILITY_PROTOCOL.clone()], reachability_proto_tx);
+
         //---------------------------------- Connection Manager --------------------------------------------//
 
         let noise_config = NoiseConfig::new(node_identity.clone());
@@ -219,10 +232,28 @@ impl UnspawnedCommsNode {
             node_identity.public_address()
         );
 
+        //---------------------------------- Reachability --------------------------------------------//
+        // Spawned once listening so that the bound listener address is known for port mapping and the public address
+        // includes the hidden service address if one was created.
+        ReachabilityService {
+            config: reachability_config,
+            node_identity: node_identity.clone(),
+            peer_manager: peer_manager.clone(),
+            connectivity: connectivity_requester.clone(),
+            connection_manager: connection_manager_requester.clone(),
+            listener_address: listening_info.bind_address().clone(),
+            request_rx: reachability_request_rx,
+            protocol_rx: reachability_proto_rx,
+            report_tx: reachability_report_tx,
+            shutdown_signal: shutdown_signal.clone(),
+        }
+        .spawn();
+
         Ok(CommsNode {
             shutdown_signal,
             connection_manager_requester,
             connectivity_requester,
+            reachability_requester,
             listening_info,
             node_identity,
             peer_manager,
@@ -264,6 +295,8 @@ pub struct CommsNode {
     connection_manager_requester: ConnectionManagerRequester,
     /// Requester for the ConnectivityManager
     connectivity_requester: ConnectivityRequester,
+    /// Requester for the reachability service
+    reachability_requester: ReachabilityRequester,
     /// Node identity for this node
     node_identity: Arc<NodeIdentity>,
     /// Shared PeerManager instance
@@ -317,6 +350,16 @@ impl CommsNode {
         self.connectivity_requester.clone()
     }
 
+    /// Return a handle that is used to call the connection manager.
+    pub fn connection_manager(&self) -> ConnectionManagerRequester {
+        self.connection_manager_requester.clone()
+    }
+
+    /// Return a handle that is used to call the reachability service.
+    pub fn reachability(&self) -> ReachabilityRequester {
+        self.reachability_requester.clone()
+    }
+
     /// Returns a new `ShutdownSignal`
     pub fn shutdown_signal(&self) -> ShutdownSignal {
         self.shutdown_signal.clone()
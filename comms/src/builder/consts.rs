@@ -30,3 +30,7 @@ pub const CONNECTION_MANAGER_REQUEST_BUFFER_SIZE: usize = 10;
 /// Connection manager events buffer size. The size should allow more than enough "time" for slow subscribers to read
 /// the events while not being wasteful.
 pub const CONNECTION_MANAGER_EVENTS_BUFFER_SIZE: usize = 30;
+/// Buffer size for actor requests to the reachability service.
+pub const REACHABILITY_REQUEST_BUFFER_SIZE: usize = 10;
+/// Buffer size for notifications that a peer wants to speak the reachability protocol.
+pub const REACHABILITY_PROTOCOL_EVENTS_BUFFER_SIZE: usize = 10;
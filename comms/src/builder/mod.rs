@@ -43,17 +43,19 @@ mod tests;
 
 use crate::{
     backoff::{Backoff, BoxedBackoff, ExponentialBackoff},
-    connection_manager::{ConnectionManagerConfig, ConnectionManagerRequester},
+    bandwidth::BandwidthConfig,
+    connection_manager::{ConnectionManagerConfig, ConnectionManagerRequester, FirewallRules},
     connectivity::{ConnectivityConfig, ConnectivityRequester},
     multiaddr::Multiaddr,
     peer_manager::{NodeIdentity, PeerManager},
     protocol::{NodeNetworkInfo, ProtocolExtensions},
+    reachability::{ReachabilityConfig, ReachabilityReport, ReachabilityRequester},
     tor,
     types::CommsDatabase,
 };
 use std::{fs::File, sync::Arc};
 use tari_shutdown::ShutdownSignal;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::{broadcast, mpsc, watch};
 
 /// The `CommsBuilder` provides a simple builder API for getting Tari comms p2p messaging up and running.
 pub struct CommsBuilder {
@@ -64,6 +66,7 @@ pub struct CommsBuilder {
     hidden_service_ctl: Option<tor::HiddenServiceController>,
     connection_manager_config: ConnectionManagerConfig,
     connectivity_config: ConnectivityConfig,
+    reachability_config: ReachabilityConfig,
 
     shutdown_signal: Option<ShutdownSignal>,
 }
@@ -78,6 +81,7 @@ impl Default for CommsBuilder {
             hidden_service_ctl: None,
             connection_manager_config: ConnectionManagerConfig::default(),
             connectivity_config: ConnectivityConfig::default(),
+            reachability_config: ReachabilityConfig::default(),
             shutdown_signal: None,
         }
     }
@@ -159,6 +163,19 @@ impl CommsBuilder {
         self
     }
 
+    /// Set the firewall rules for inbound connections. The rules can be changed at runtime using
+    /// [ConnectionManagerRequester::set_firewall_rules].
+    pub fn with_firewall_rules(mut self, rules: FirewallRules) -> Self {
+        self.connection_manager_config.firewall_rules = rules;
+        self
+    }
+
+    /// Set the upload and download rate limits for all connections and for individual protocols
+    pub fn with_bandwidth_config(mut self, config: BandwidthConfig) -> Self {
+        self.connection_manager_config.bandwidth = config;
+        self
+    }
+
     /// The maximum number of connection tasks that will be spawned at the same time. Once this limit is reached, peers
     /// attempting to connect will have to wait for another connection attempt to complete.
     pub fn with_max_simultaneous_inbound_connects(mut self, max_simultaneous_inbound_connects: usize) -> Self {
@@ -185,7 +202,15 @@ impl CommsBuilder {
         self
     }
 
-    /// Set the peer storage database to use.
+    /// Set the configuration for the reachability service, which checks that this node's public address can be
+    /// reached by other peers.
+    pub fn with_reachability_config(mut self, config: ReachabilityConfig) -> Self {
+        self.reachability_config = config;
+        self
+    }
+
+    /// Set the peer storage database to use. Peer records are encrypted at rest if the database has a cipher (see
+    /// [LMDBWrapper::with_cipher](tari_storage::LMDBWrapper::with_cipher)).
     pub fn with_peer_storage(mut self, peer_storage: CommsDatabase, file_lock: Option<File>) -> Self {
         self.peer_storage = Some(peer_storage);
         self.peer_storage_file_lock = file_lock;
@@ -207,7 +232,10 @@ impl CommsBuilder {
             Some(storage) => {
                 // TODO: Peer manager should be refactored to be backend agnostic
                 #[cfg(not(test))]
-                PeerManager::migrate_lmdb(&storage.inner())?;
+                match storage.cipher() {
+                    Some(cipher) => PeerManager::migrate_lmdb_encrypted(storage.inner(), cipher)?,
+                    None => PeerManager::migrate_lmdb(&storage.inner())?,
+                }
 
                 let peer_manager = PeerManager::new(storage, file_lock).map_err(CommsBuilderError::PeerManagerError)?;
                 Ok(Arc::new(peer_manager))
@@ -237,6 +265,11 @@ impl CommsBuilder {
         let (event_tx, _) = broadcast::channel(consts::CONNECTIVITY_MANAGER_EVENTS_BUFFER_SIZE);
         let connectivity_requester = ConnectivityRequester::new(connectivity_tx, event_tx);
 
+        //---------------------------------- Reachability --------------------------------------------//
+        let (reachability_tx, reachability_request_rx) = mpsc::channel(consts::REACHABILITY_REQUEST_BUFFER_SIZE);
+        let (reachability_report_tx, reachability_report_rx) = watch::channel(ReachabilityReport::default());
+        let reachability_requester = ReachabilityRequester::new(reachability_tx, reachability_report_rx);
+
         Ok(UnspawnedCommsNode {
             protocols: Default::default(),
             node_identity,
@@ -246,6 +279,9 @@ impl CommsBuilder {
             builder: self,
             connectivity_requester,
             connectivity_rx,
+            reachability_requester,
+            reachability_request_rx,
+            reachability_report_tx,
             peer_manager,
             protocol_extensions: ProtocolExtensions::new(),
         })
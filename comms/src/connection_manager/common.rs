@@ -200,12 +200,7 @@ fn validate_address(addr: &Multiaddr, allow_test_addrs: bool) -> Result<(), Conn
 
     match proto {
         Protocol::Dns4(_) | Protocol::Dns6(_) | Protocol::Dnsaddr(_) => {
-            let tcp = addr_iter.next().ok_or_else(|| {
-                ConnectionManagerError::InvalidMultiaddr("Address does not include a TCP port".to_string())
-            })?;
-
-            validate_tcp_port(tcp)?;
-
+            validate_transport_port(&mut addr_iter)?;
             expect_end_of_address(addr_iter)
         },
 
@@ -224,11 +219,7 @@ fn validate_address(addr: &Multiaddr, allow_test_addrs: bool) -> Result<(), Conn
             ))
         },
         Protocol::Ip4(_) | Protocol::Ip6(_) => {
-            let tcp = addr_iter.next().ok_or_else(|| {
-                ConnectionManagerError::InvalidMultiaddr("Address does not include a TCP port".to_string())
-            })?;
-
-            validate_tcp_port(tcp)?;
+            validate_transport_port(&mut addr_iter)?;
             expect_end_of_address(addr_iter)
         },
         Protocol::Memory(0) => Err(ConnectionManagerError::InvalidMultiaddr(
@@ -253,6 +244,32 @@ fn validate_address(addr: &Multiaddr, allow_test_addrs: bool) -> Result<(), Conn
     }
 }
 
+/// Validates that the next address components are either a TCP port or, when the `quic` feature is enabled, a UDP
+/// port followed by QUIC
+fn validate_transport_port(addr_iter: &mut multiaddr::Iter<'_>) -> Result<(), ConnectionManagerError> {
+    let transport = addr_iter.next().ok_or_else(|| {
+        ConnectionManagerError::InvalidMultiaddr("Address does not include a TCP or UDP port".to_string())
+    })?;
+
+    match transport {
+        Protocol::Udp(0) => Err(ConnectionManagerError::InvalidMultiaddr(
+            "Cannot connect to a zero UDP port".to_string(),
+        )),
+        #[cfg(feature = "quic")]
+        Protocol::Udp(_) => match addr_iter.next() {
+            Some(Protocol::Quic) => Ok(()),
+            _ => Err(ConnectionManagerError::InvalidMultiaddr(
+                "UDP addresses must use the QUIC protocol".to_string(),
+            )),
+        },
+        #[cfg(not(feature = "quic"))]
+        Protocol::Udp(_) => Err(ConnectionManagerError::InvalidMultiaddr(
+            "UDP/QUIC addresses are not supported by this build".to_string(),
+        )),
+        tcp => validate_tcp_port(tcp),
+    }
+}
+
 fn validate_tcp_port(expected_tcp: Protocol) -> Result<(), ConnectionManagerError> {
     match expected_tcp {
         Protocol::Tcp(0) => Err(ConnectionManagerError::InvalidMultiaddr(
@@ -285,6 +302,9 @@ mod test {
 
         let invalid = &[
             multiaddr!(Ip4([127, 0, 0, 1]), Tcp(1u16)),
+            multiaddr!(Ip4([127, 0, 0, 1]), Udp(1u16), Quic),
+            multiaddr!(Ip4([172, 0, 0, 1]), Udp(1u16)),
+            multiaddr!(Ip4([172, 0, 0, 1]), Udp(0u16), Quic),
             multiaddr!(Ip4([169, 254, 0, 1]), Tcp(1u16)),
             multiaddr!(Ip4([172, 0, 0, 1])),
             "/onion/aaimaq4ygg2iegci:1234/http".parse().unwrap(),
@@ -316,6 +336,7 @@ mod test {
 
         let invalid = &[
             multiaddr!(Ip4([172, 0, 0, 1])),
+            multiaddr!(Ip4([127, 0, 0, 1]), Udp(1u16), Tcp(1u16)),
             "/onion/aaimaq4ygg2iegci:1234/http".parse().unwrap(),
             multiaddr!(Dnsaddr("mike-magic-nodes.com")),
             multiaddr!(Memory(0u64)),
@@ -326,4 +347,26 @@ mod test {
             validate_address(addr, true).unwrap_err();
         }
     }
+
+    #[test]
+    fn validate_address_quic() {
+        let quic_addrs = [
+            multiaddr!(Ip4([172, 0, 0, 1]), Udp(1u16), Quic),
+            multiaddr!(Ip6([172, 0, 0, 1, 1, 1, 1, 1]), Udp(1u16), Quic),
+        ];
+
+        #[cfg(feature = "quic")]
+        {
+            validate_peer_addresses(&quic_addrs, false).unwrap();
+            validate_address(&multiaddr!(Ip4([127, 0, 0, 1]), Udp(1u16), Quic), true).unwrap();
+        }
+
+        #[cfg(not(feature = "quic"))]
+        {
+            for addr in &quic_addrs {
+                validate_address(addr, false).unwrap_err();
+            }
+            validate_address(&multiaddr!(Ip4([127, 0, 0, 1]), Udp(1u16), Quic), true).unwrap_err();
+        }
+    }
 }
@@ -23,6 +23,7 @@
 use super::{error::ConnectionManagerError, peer_connection::PeerConnection, types::ConnectionDirection};
 use crate::{
     backoff::Backoff,
+    bandwidth::BandwidthLimiter,
     connection_manager::{
         common,
         dial_state::DialState,
@@ -30,7 +31,6 @@ use crate::{
         peer_connection,
     },
     multiaddr::Multiaddr,
-    multiplexing::Yamux,
     noise::{NoiseConfig, NoiseSocket},
     peer_manager::{NodeId, NodeIdentity, Peer, PeerFeatures, PeerManager},
     protocol::ProtocolId,
@@ -70,6 +70,13 @@ pub(crate) enum DialerRequest {
         Option<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>,
     ),
     CancelPendingDial(NodeId),
+    /// Dial the given address and authenticate the expected public key using the noise handshake. The connection is
+    /// closed immediately afterwards.
+    ProbeAddress {
+        public_key: CommsPublicKey,
+        address: Multiaddr,
+        reply_tx: oneshot::Sender<Result<(), ConnectionManagerError>>,
+    },
 }
 
 pub struct Dialer<TTransport, TBackoff> {
@@ -85,6 +92,7 @@ pub struct Dialer<TTransport, TBackoff> {
     shutdown: Option<ShutdownSignal>,
     pending_dial_requests: HashMap<NodeId, Vec<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>>,
     our_supported_protocols: Vec<ProtocolId>,
+    bandwidth_limiter: BandwidthLimiter,
 }
 
 impl<TTransport, TBackoff> Dialer<TTransport, TBackoff>
@@ -103,6 +111,7 @@ where
         backoff: TBackoff,
         request_rx: mpsc::Receiver<DialerRequest>,
         conn_man_notifier: mpsc::Sender<ConnectionManagerEvent>,
+        bandwidth_limiter: BandwidthLimiter,
         shutdown: ShutdownSignal,
     ) -> Self {
         Self {
@@ -118,6 +127,7 @@ where
             shutdown: Some(shutdown),
             pending_dial_requests: Default::default(),
             our_supported_protocols: Vec::new(),
+            bandwidth_limiter,
         }
     }
 
@@ -169,9 +179,63 @@ where
                     let _ = s.trigger();
                 }
             },
+            ProbeAddress {
+                public_key,
+                address,
+                reply_tx,
+            } => {
+                self.handle_probe_address_request(public_key, address, reply_tx);
+            },
         }
     }
 
+    fn handle_probe_address_request(
+        &self,
+        public_key: CommsPublicKey,
+        address: Multiaddr,
+        reply_tx: oneshot::Sender<Result<(), ConnectionManagerError>>,
+    ) {
+        let transport = self.transport.clone();
+        let noise_config = self.noise_config.clone();
+        let network_byte = self.config.network_info.network_byte;
+        runtime::current().spawn(async move {
+            let result = Self::probe_address(&transport, &noise_config, network_byte, &public_key, &address).await;
+            debug!(
+                target: LOG_TARGET,
+                "Probe of address '{}' completed with result {:?}", address, result
+            );
+            let _ = reply_tx.send(result);
+        });
+    }
+
+    async fn probe_address(
+        transport: &TTransport,
+        noise_config: &NoiseConfig,
+        network_byte: u8,
+        public_key: &CommsPublicKey,
+        address: &Multiaddr,
+    ) -> Result<(), ConnectionManagerError> {
+        let mut socket = transport
+            .dial(address.clone())
+            .await
+            .map_err(|err| ConnectionManagerError::TransportError(err.to_string()))?;
+
+        socket
+            .write(&[network_byte])
+            .await
+            .map_err(|_| ConnectionManagerError::WireFormatSendFailed)?;
+
+        let noise_socket = time::timeout(
+            Duration::from_secs(40),
+            noise_config.upgrade_socket(socket, ConnectionDirection::Outbound),
+        )
+        .await
+        .map_err(|_| ConnectionManagerError::NoiseProtocolTimeout)??;
+
+        Self::check_authenticated_public_key(&noise_socket, public_key)?;
+        Ok(())
+    }
+
     fn is_pending_dial(&self, node_id: &NodeId) -> bool {
         self.cancel_signals.contains_key(node_id)
     }
@@ -282,6 +346,7 @@ where
         let supported_protocols = self.our_supported_protocols.clone();
         let noise_config = self.noise_config.clone();
         let config = self.config.clone();
+        let bandwidth_limiter = self.bandwidth_limiter.clone();
 
         let span = span!(Level::TRACE, "handle_dial_peer_request_inner1");
         let dial_fut = async move {
@@ -309,6 +374,7 @@ where
                         conn_man_notifier,
                         supported_protocols,
                         &config,
+                        bandwidth_limiter,
                         cancel_signal,
                     )
                     .await;
@@ -339,7 +405,7 @@ where
     }
 
     #[allow(clippy::too_many_arguments)]
-    #[tracing::instrument(skip(peer_manager, socket, conn_man_notifier, config, cancel_signal))]
+    #[tracing::instrument(skip(peer_manager, socket, conn_man_notifier, config, bandwidth_limiter, cancel_signal))]
     async fn perform_socket_upgrade_procedure(
         peer_manager: Arc<PeerManager>,
         node_identity: Arc<NodeIdentity>,
@@ -349,10 +415,11 @@ where
         conn_man_notifier: mpsc::Sender<ConnectionManagerEvent>,
         our_supported_protocols: Vec<ProtocolId>,
         config: &ConnectionManagerConfig,
+        bandwidth_limiter: BandwidthLimiter,
         cancel_signal: ShutdownSignal,
     ) -> Result<PeerConnection, ConnectionManagerError> {
         static CONNECTION_DIRECTION: ConnectionDirection = ConnectionDirection::Outbound;
-        let mut muxer = Yamux::upgrade_connection(socket, CONNECTION_DIRECTION)
+        let mut muxer = TTransport::multiplex(socket, CONNECTION_DIRECTION)
             .await
             .map_err(|err| ConnectionManagerError::YamuxUpgradeFailure(err.to_string()))?;
 
@@ -420,6 +487,8 @@ where
             conn_man_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            None,
+            bandwidth_limiter,
         )
     }
 
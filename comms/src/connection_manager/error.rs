@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    connection_manager::PeerConnectionRequest,
+    connection_manager::{FirewallRejection, PeerConnectionRequest},
     noise,
     peer_manager::PeerManagerError,
     protocol::{IdentityProtocolError, ProtocolError},
@@ -81,6 +81,8 @@ pub enum ConnectionManagerError {
     NoiseProtocolTimeout,
     #[error("Listener oneshot cancelled")]
     ListenerOneshotCancelled,
+    #[error("Inbound connection rejected by the firewall: {0}")]
+    InboundConnectionRejected(FirewallRejection),
 }
 
 impl From<yamux::ConnectionError> for ConnectionManagerError {
@@ -0,0 +1,404 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Inbound connection firewall.
+//!
+//! The [FirewallRules] are checked by the listener for every inbound comms connection (liveness sessions have their
+//! own allowlist). An [InboundFirewall] keeps track of the number of connections from each IP address and subnet, as
+//! well as failed noise handshakes. Addresses that repeatedly fail the handshake are blocked for a period of time.
+//!
+//! Loopback addresses are exempt from connection limits and automatic blocking, because inbound tor connections are
+//! forwarded to the listener from the local tor proxy. The allow and deny lists still apply to them.
+
+use crate::{multiaddr::Multiaddr, peer_manager::PeerFeatures};
+use multiaddr::Protocol;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Rules applied to inbound comms connections. These can be replaced at runtime using
+/// [ConnectionManagerRequester::set_firewall_rules](super::ConnectionManagerRequester::set_firewall_rules).
+#[derive(Debug, Clone)]
+pub struct FirewallRules {
+    /// If not empty, only connections from addresses in these CIDR blocks are accepted. Default: empty (allow all)
+    pub allow_cidrs: Vec<cidr::AnyIpCidr>,
+    /// Connections from addresses in these CIDR blocks are rejected. This takes precedence over `allow_cidrs`.
+    /// Default: empty
+    pub deny_cidrs: Vec<cidr::AnyIpCidr>,
+    /// The maximum number of inbound connections from a single IP address. Default: None (unlimited)
+    pub max_connections_per_ip: Option<usize>,
+    /// The maximum number of inbound connections from a single subnet. Default: None (unlimited)
+    pub max_connections_per_subnet: Option<usize>,
+    /// The prefix length used to group IPv4 addresses into subnets. Default: 24
+    pub ipv4_subnet_prefix_len: u8,
+    /// The prefix length used to group IPv6 addresses into subnets. Default: 64
+    pub ipv6_subnet_prefix_len: u8,
+    /// Features that a peer must have for its inbound connection to be accepted. Default: NONE
+    pub required_features: PeerFeatures,
+    /// The number of failed handshakes within `handshake_failure_window` after which an address is blocked. Zero
+    /// disables automatic blocking. Default: 5
+    pub max_handshake_failures: usize,
+    /// The period in which failed handshakes are counted. Default: 5 minutes
+    pub handshake_failure_window: Duration,
+    /// The length of time an address is blocked for after too many failed handshakes. Default: 30 minutes
+    pub handshake_failure_block_duration: Duration,
+}
+
+impl Default for FirewallRules {
+    fn default() -> Self {
+        Self {
+            allow_cidrs: Vec::new(),
+            deny_cidrs: Vec::new(),
+            max_connections_per_ip: None,
+            max_connections_per_subnet: None,
+            ipv4_subnet_prefix_len: 24,
+            ipv6_subnet_prefix_len: 64,
+            required_features: PeerFeatures::NONE,
+            max_handshake_failures: 5,
+            handshake_failure_window: Duration::from_secs(5 * 60),
+            handshake_failure_block_duration: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+impl FirewallRules {
+    fn subnet_of(&self, ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(ip) => {
+                let prefix_len = u32::from(self.ipv4_subnet_prefix_len.min(32));
+                let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+            },
+            IpAddr::V6(ip) => {
+                let prefix_len = u32::from(self.ipv6_subnet_prefix_len.min(128));
+                let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+            },
+        }
+    }
+}
+
+/// The reason that the firewall rejected an inbound connection
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FirewallRejection {
+    #[error("Address is in the deny list")]
+    Denied,
+    #[error("Address is not in the allow list")]
+    NotAllowed,
+    #[error("Maximum number of connections ({0}) from the address reached")]
+    TooManyConnectionsFromAddress(usize),
+    #[error("Maximum number of connections ({0}) from the subnet reached")]
+    TooManyConnectionsFromSubnet(usize),
+    #[error("Peer does not have the required features {0:?}")]
+    MissingRequiredFeatures(PeerFeatures),
+    #[error("Address is blocked for another {0:.0?} after repeated handshake failures")]
+    TemporarilyBlocked(Duration),
+}
+
+/// Shared firewall state used by the listeners. Cloning is cheap and all clones refer to the same state.
+#[derive(Clone)]
+pub(crate) struct InboundFirewall {
+    state: Arc<Mutex<FirewallState>>,
+}
+
+impl InboundFirewall {
+    pub fn new(rules: FirewallRules) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(FirewallState {
+                rules,
+                connections_per_ip: HashMap::new(),
+                connections_per_subnet: HashMap::new(),
+                handshake_failures: HashMap::new(),
+                blocked: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn rules(&self) -> FirewallRules {
+        acquire_lock!(self.state).rules.clone()
+    }
+
+    /// Replace the current rules. Existing connections are not affected, however they are counted towards the new
+    /// connection limits. Addresses that are currently blocked remain blocked.
+    pub fn set_rules(&self, rules: FirewallRules) {
+        acquire_lock!(self.state).rules = rules;
+    }
+
+    /// Checks an inbound connection from the given address against the rules. If accepted, the connection is counted
+    /// towards the connection limits for its IP address and subnet until the returned permit is dropped. Addresses
+    /// that are not IP addresses (e.g. memory addresses) are always accepted.
+    pub fn check_address(&self, address: &Multiaddr) -> Result<FirewallPermit, FirewallRejection> {
+        let ip = match ip_of(address) {
+            Some(ip) => ip,
+            None => return Ok(FirewallPermit { inner: None }),
+        };
+
+        let mut state = acquire_lock!(self.state);
+        let rules = &state.rules;
+        if rules.deny_cidrs.iter().any(|cidr| cidr.contains(&ip)) {
+            return Err(FirewallRejection::Denied);
+        }
+        if !rules.allow_cidrs.is_empty() && !rules.allow_cidrs.iter().any(|cidr| cidr.contains(&ip)) {
+            return Err(FirewallRejection::NotAllowed);
+        }
+
+        let subnet = rules.subnet_of(ip);
+        if !ip.is_loopback() {
+            let now = Instant::now();
+            match state.blocked.get(&ip).copied() {
+                Some(until) if until > now => return Err(FirewallRejection::TemporarilyBlocked(until - now)),
+                Some(_) => {
+                    state.blocked.remove(&ip);
+                },
+                None => {},
+            }
+
+            if let Some(max) = state.rules.max_connections_per_ip {
+                if state.connections_per_ip.get(&ip).copied().unwrap_or(0) >= max {
+                    return Err(FirewallRejection::TooManyConnectionsFromAddress(max));
+                }
+            }
+            if let Some(max) = state.rules.max_connections_per_subnet {
+                if state.connections_per_subnet.get(&subnet).copied().unwrap_or(0) >= max {
+                    return Err(FirewallRejection::TooManyConnectionsFromSubnet(max));
+                }
+            }
+        }
+
+        *state.connections_per_ip.entry(ip).or_default() += 1;
+        *state.connections_per_subnet.entry(subnet).or_default() += 1;
+
+        Ok(FirewallPermit {
+            inner: Some(PermitInner {
+                state: self.state.clone(),
+                ip,
+                subnet,
+            }),
+        })
+    }
+
+    /// Checks that the features of an authenticated peer include the required features
+    pub fn check_features(&self, features: PeerFeatures) -> Result<(), FirewallRejection> {
+        let required_features = acquire_lock!(self.state).rules.required_features;
+        if features.contains(required_features) {
+            Ok(())
+        } else {
+            Err(FirewallRejection::MissingRequiredFeatures(required_features))
+        }
+    }
+
+    /// Records a failed handshake from the given address. If this results in the address being blocked, the IP
+    /// address and the duration of the block are returned.
+    pub fn record_handshake_failure(&self, address: &Multiaddr) -> Option<(IpAddr, Duration)> {
+        let ip = ip_of(address).filter(|ip| !ip.is_loopback())?;
+        let mut state = acquire_lock!(self.state);
+        let max_failures = state.rules.max_handshake_failures;
+        if max_failures == 0 {
+            return None;
+        }
+
+        let now = Instant::now();
+        state.prune(now);
+        let window = state.rules.handshake_failure_window;
+        let failures = state.handshake_failures.entry(ip).or_default();
+        failures.push_back(now);
+        while failures.front().filter(|t| now.duration_since(**t) > window).is_some() {
+            failures.pop_front();
+        }
+        if failures.len() < max_failures {
+            return None;
+        }
+
+        state.handshake_failures.remove(&ip);
+        let duration = state.rules.handshake_failure_block_duration;
+        state.blocked.insert(ip, now + duration);
+        Some((ip, duration))
+    }
+}
+
+struct FirewallState {
+    rules: FirewallRules,
+    connections_per_ip: HashMap<IpAddr, usize>,
+    connections_per_subnet: HashMap<IpAddr, usize>,
+    handshake_failures: HashMap<IpAddr, VecDeque<Instant>>,
+    blocked: HashMap<IpAddr, Instant>,
+}
+
+impl FirewallState {
+    /// Removes expired blocks and handshake failures that are outside of the window
+    fn prune(&mut self, now: Instant) {
+        let window = self.rules.handshake_failure_window;
+        self.blocked.retain(|_, until| *until > now);
+        self.handshake_failures
+            .retain(|_, failures| failures.back().filter(|t| now.duration_since(**t) <= window).is_some());
+    }
+
+    fn release(&mut self, ip: IpAddr, subnet: IpAddr) {
+        decrement(&mut self.connections_per_ip, ip);
+        decrement(&mut self.connections_per_subnet, subnet);
+    }
+}
+
+fn decrement(counts: &mut HashMap<IpAddr, usize>, key: IpAddr) {
+    if let Some(count) = counts.get_mut(&key) {
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            counts.remove(&key);
+        }
+    }
+}
+
+/// Counts an accepted inbound connection towards the firewall connection limits until dropped
+pub(crate) struct FirewallPermit {
+    inner: Option<PermitInner>,
+}
+
+struct PermitInner {
+    state: Arc<Mutex<FirewallState>>,
+    ip: IpAddr,
+    subnet: IpAddr,
+}
+
+impl fmt::Debug for FirewallPermit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FirewallPermit")
+            .field("ip", &self.inner.as_ref().map(|inner| inner.ip))
+            .finish()
+    }
+}
+
+impl Drop for FirewallPermit {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            acquire_lock!(inner.state).release(inner.ip, inner.subnet);
+        }
+    }
+}
+
+fn ip_of(address: &Multiaddr) -> Option<IpAddr> {
+    match address.iter().next()? {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::cidr::parse_cidrs;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn it_applies_the_allow_and_deny_lists() {
+        let firewall = InboundFirewall::new(FirewallRules {
+            allow_cidrs: parse_cidrs(&["10.0.0.0/8"]).unwrap(),
+            deny_cidrs: parse_cidrs(&["10.1.0.0/16"]).unwrap(),
+            ..Default::default()
+        });
+
+        assert!(firewall.check_address(&addr("/ip4/10.2.3.4/tcp/1234")).is_ok());
+        assert_eq!(
+            firewall.check_address(&addr("/ip4/10.1.3.4/tcp/1234")).unwrap_err(),
+            FirewallRejection::Denied
+        );
+        assert_eq!(
+            firewall.check_address(&addr("/ip4/11.2.3.4/tcp/1234")).unwrap_err(),
+            FirewallRejection::NotAllowed
+        );
+        assert!(firewall.check_address(&addr("/memory/123")).is_ok());
+    }
+
+    #[test]
+    fn it_limits_connections_per_ip_and_subnet() {
+        let firewall = InboundFirewall::new(FirewallRules {
+            max_connections_per_ip: Some(1),
+            max_connections_per_subnet: Some(2),
+            ..Default::default()
+        });
+
+        let permit = firewall.check_address(&addr("/ip4/1.2.3.4/tcp/1234")).unwrap();
+        assert_eq!(
+            firewall.check_address(&addr("/ip4/1.2.3.4/tcp/1235")).unwrap_err(),
+            FirewallRejection::TooManyConnectionsFromAddress(1)
+        );
+        let _permit2 = firewall.check_address(&addr("/ip4/1.2.3.5/tcp/1234")).unwrap();
+        assert_eq!(
+            firewall.check_address(&addr("/ip4/1.2.3.6/tcp/1234")).unwrap_err(),
+            FirewallRejection::TooManyConnectionsFromSubnet(2)
+        );
+        assert!(firewall.check_address(&addr("/ip4/1.2.4.6/tcp/1234")).is_ok());
+
+        drop(permit);
+        assert!(firewall.check_address(&addr("/ip4/1.2.3.4/tcp/1234")).is_ok());
+        // Loopback is exempt from limits
+        let _permits = (0..3)
+            .map(|_| firewall.check_address(&addr("/ip4/127.0.0.1/tcp/1234")).unwrap())
+            .collect::<Vec<_>>();
+    }
+
+    #[test]
+    fn it_blocks_addresses_after_repeated_handshake_failures() {
+        let firewall = InboundFirewall::new(FirewallRules {
+            max_handshake_failures: 2,
+            ..Default::default()
+        });
+
+        let address = addr("/ip4/1.2.3.4/tcp/1234");
+        assert!(firewall.record_handshake_failure(&address).is_none());
+        let (ip, _) = firewall.record_handshake_failure(&address).unwrap();
+        assert_eq!(ip, "1.2.3.4".parse::<IpAddr>().unwrap());
+        assert!(matches!(
+            firewall.check_address(&addr("/ip4/1.2.3.4/tcp/999")).unwrap_err(),
+            FirewallRejection::TemporarilyBlocked(_)
+        ));
+        assert!(firewall
+            .record_handshake_failure(&addr("/ip4/127.0.0.1/tcp/1"))
+            .is_none());
+        assert!(firewall
+            .record_handshake_failure(&addr("/ip4/127.0.0.1/tcp/1"))
+            .is_none());
+    }
+
+    #[test]
+    fn it_checks_required_features() {
+        let firewall = InboundFirewall::new(Default::default());
+        assert!(firewall.check_features(PeerFeatures::COMMUNICATION_CLIENT).is_ok());
+        firewall.set_rules(FirewallRules {
+            required_features: PeerFeatures::COMMUNICATION_NODE,
+            ..Default::default()
+        });
+        assert_eq!(
+            firewall.check_features(PeerFeatures::COMMUNICATION_CLIENT).unwrap_err(),
+            FirewallRejection::MissingRequiredFeatures(PeerFeatures::COMMUNICATION_NODE)
+        );
+        assert!(firewall.check_features(PeerFeatures::COMMUNICATION_NODE).is_ok());
+    }
+}
@@ -23,19 +23,20 @@
 use super::{
     common,
     error::ConnectionManagerError,
+    firewall::{FirewallPermit, InboundFirewall},
     peer_connection::{self, PeerConnection},
     types::ConnectionDirection,
     ConnectionManagerConfig,
     ConnectionManagerEvent,
 };
 use crate::{
+    bandwidth::BandwidthLimiter,
     bounded_executor::BoundedExecutor,
     connection_manager::{
         liveness::LivenessSession,
         wire_mode::{WireMode, LIVENESS_WIRE_MODE},
     },
     multiaddr::Multiaddr,
-    multiplexing::Yamux,
     noise::NoiseConfig,
     peer_manager::{NodeIdentity, PeerFeatures},
     protocol::ProtocolId,
@@ -81,6 +82,8 @@ pub struct PeerListener<TTransport> {
     node_identity: Arc<NodeIdentity>,
     our_supported_protocols: Vec<ProtocolId>,
     liveness_session_count: Arc<AtomicUsize>,
+    firewall: InboundFirewall,
+    bandwidth_limiter: BandwidthLimiter,
     on_listening: OneshotTrigger<Result<Multiaddr, ConnectionManagerError>>,
 }
 
@@ -98,6 +101,8 @@ where
         conn_man_notifier: mpsc::Sender<ConnectionManagerEvent>,
         peer_manager: Arc<PeerManager>,
         node_identity: Arc<NodeIdentity>,
+        firewall: InboundFirewall,
+        bandwidth_limiter: BandwidthLimiter,
         shutdown_signal: ShutdownSignal,
     ) -> Self {
         Self {
@@ -111,6 +116,8 @@ where
             our_supported_protocols: Vec::new(),
             bounded_executor: BoundedExecutor::from_current(config.max_simultaneous_inbound_connects),
             liveness_session_count: Arc::new(AtomicUsize::new(config.liveness_max_sessions)),
+            firewall,
+            bandwidth_limiter,
             config,
             on_listening: oneshot_trigger::channel(),
         }
@@ -237,11 +244,32 @@ where
         let our_supported_protocols = self.our_supported_protocols.clone();
         let liveness_session_count = self.liveness_session_count.clone();
         let shutdown_signal = self.shutdown_signal.clone();
+        let firewall = self.firewall.clone();
+        let bandwidth_limiter = self.bandwidth_limiter.clone();
 
         let span = span!(Level::TRACE, "connection_mann::listener::inbound_task",);
         let inbound_fut = async move {
             match Self::read_wire_format(&mut socket, config.time_to_first_byte).await {
                 Ok(WireMode::Comms(byte)) if byte == config.network_info.network_byte => {
+                    let firewall_permit = match firewall.check_address(&peer_addr) {
+                        Ok(permit) => permit,
+                        Err(rejection) => {
+                            debug!(
+                                target: LOG_TARGET,
+                                "Firewall rejected inbound connection from '{}' because '{}'", peer_addr, rejection
+                            );
+                            let _ = socket.shutdown().await;
+                            log_if_error!(
+                                target: LOG_TARGET,
+                                conn_man_notifier
+                                    .send(ConnectionManagerEvent::InboundConnectionRejected(peer_addr, rejection))
+                                    .await,
+                                "Failed to publish event because '{error}'",
+                            );
+                            return;
+                        },
+                    };
+
                     let this_node_id_str = node_identity.node_id().short_str();
                     let result = Self::perform_socket_upgrade_procedure(
                         node_identity,
@@ -249,9 +277,12 @@ where
                         noise_config.clone(),
                         conn_man_notifier.clone(),
                         socket,
-                        peer_addr,
+                        peer_addr.clone(),
                         our_supported_protocols,
                         &config,
+                        &firewall,
+                        firewall_permit,
+                        bandwidth_limiter,
                     )
                     .await;
 
@@ -265,6 +296,22 @@ where
                                 "Failed to publish event because '{error}'",
                             );
                         },
+                        Err(ConnectionManagerError::InboundConnectionRejected(rejection)) => {
+                            debug!(
+                                target: LOG_TARGET,
+                                "[ThisNode={}] Firewall rejected inbound connection from '{}' because '{}'",
+                                this_node_id_str,
+                                peer_addr,
+                                rejection
+                            );
+                            log_if_error!(
+                                target: LOG_TARGET,
+                                conn_man_notifier
+                                    .send(ConnectionManagerEvent::InboundConnectionRejected(peer_addr, rejection))
+                                    .await,
+                                "Failed to publish event because '{error}'",
+                            );
+                        },
                         Err(err) => {
                             debug!(
                                 target: LOG_TARGET,
@@ -272,6 +319,24 @@ where
                                 this_node_id_str,
                                 err
                             );
+                            if Self::is_handshake_failure(&err) {
+                                if let Some((ip, duration)) = firewall.record_handshake_failure(&peer_addr) {
+                                    warn!(
+                                        target: LOG_TARGET,
+                                        "Blocking inbound connections from {} for {:.0?} after repeated handshake \
+                                         failures",
+                                        ip,
+                                        duration
+                                    );
+                                    log_if_error!(
+                                        target: LOG_TARGET,
+                                        conn_man_notifier
+                                            .send(ConnectionManagerEvent::InboundAddressBlocked(ip, duration))
+                                            .await,
+                                        "Failed to publish event because '{error}'",
+                                    );
+                                }
+                            }
                             log_if_error!(
                                 target: LOG_TARGET,
                                 conn_man_notifier
@@ -334,6 +399,11 @@ where
         self.bounded_executor.spawn(inbound_fut).await;
     }
 
+    fn is_handshake_failure(err: &ConnectionManagerError) -> bool {
+        use ConnectionManagerError::*;
+        matches!(err, NoiseError(_) | NoiseProtocolTimeout | InvalidStaticPublicKey)
+    }
+
     async fn remote_public_key_from_socket(socket: TTransport::Output, noise_config: NoiseConfig) -> String {
         let public_key: Option<CommsPublicKey> = match time::timeout(
             Duration::from_secs(30),
@@ -370,6 +440,9 @@ where
         peer_addr: Multiaddr,
         our_supported_protocols: Vec<ProtocolId>,
         config: &ConnectionManagerConfig,
+        firewall: &InboundFirewall,
+        firewall_permit: FirewallPermit,
+        bandwidth_limiter: BandwidthLimiter,
     ) -> Result<PeerConnection, ConnectionManagerError> {
         static CONNECTION_DIRECTION: ConnectionDirection = ConnectionDirection::Inbound;
         debug!(
@@ -391,7 +464,7 @@ where
         // Check if we know the peer and if it is banned
         let known_peer = common::find_unbanned_peer(&peer_manager, &authenticated_public_key).await?;
 
-        let mut muxer = Yamux::upgrade_connection(noise_socket, CONNECTION_DIRECTION)
+        let mut muxer = TTransport::multiplex(noise_socket, CONNECTION_DIRECTION)
             .await
             .map_err(|err| ConnectionManagerError::YamuxUpgradeFailure(err.to_string()))?;
 
@@ -419,6 +492,10 @@ where
         );
         trace!(target: LOG_TARGET, "{:?}", peer_identity);
 
+        firewall
+            .check_features(features)
+            .map_err(ConnectionManagerError::InboundConnectionRejected)?;
+
         let (peer_node_id, their_supported_protocols) = common::validate_and_add_peer_from_peer_identity(
             &peer_manager,
             known_peer,
@@ -445,6 +522,8 @@ where
             conn_man_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            Some(firewall_permit),
+            bandwidth_limiter,
         )
     }
 
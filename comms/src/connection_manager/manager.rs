@@ -23,12 +23,14 @@
 use super::{
     dialer::{Dialer, DialerRequest},
     error::ConnectionManagerError,
+    firewall::{FirewallRejection, FirewallRules, InboundFirewall},
     listener::PeerListener,
     peer_connection::PeerConnection,
     requester::ConnectionManagerRequest,
 };
 use crate::{
     backoff::Backoff,
+    bandwidth::{BandwidthConfig, BandwidthLimiter, BandwidthStats},
     multiplexing::Substream,
     noise::NoiseConfig,
     peer_manager::{NodeId, NodeIdentity},
@@ -38,7 +40,7 @@ use crate::{
 };
 use log::*;
 use multiaddr::Multiaddr;
-use std::{fmt, sync::Arc};
+use std::{fmt, net::IpAddr, sync::Arc};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use time::Duration;
 use tokio::{
@@ -61,6 +63,14 @@ pub enum ConnectionManagerEvent {
     PeerDisconnected(NodeId),
     PeerConnectFailed(NodeId, ConnectionManagerError),
     PeerInboundConnectFailed(ConnectionManagerError),
+    /// A peer connection has closed. This is published for every connection, including connections that were closed
+    /// silently, and contains the bandwidth used by the connection.
+    PeerConnectionClosed(NodeId, BandwidthStats),
+
+    // Firewall
+    InboundConnectionRejected(Multiaddr, FirewallRejection),
+    InboundAddressBlocked(IpAddr, Duration),
+    FirewallRulesUpdated,
 
     // Substreams
     NewInboundSubstream(NodeId, ProtocolId, Substream),
@@ -74,6 +84,12 @@ impl fmt::Display for ConnectionManagerEvent {
             PeerDisconnected(node_id) => write!(f, "PeerDisconnected({})", node_id.short_str()),
             PeerConnectFailed(node_id, err) => write!(f, "PeerConnectFailed({}, {:?})", node_id.short_str(), err),
             PeerInboundConnectFailed(err) => write!(f, "PeerInboundConnectFailed({:?})", err),
+            PeerConnectionClosed(node_id, bandwidth) => {
+                write!(f, "PeerConnectionClosed({}, {})", node_id.short_str(), bandwidth.total)
+            },
+            InboundConnectionRejected(addr, reason) => write!(f, "InboundConnectionRejected({}, {})", addr, reason),
+            InboundAddressBlocked(ip, duration) => write!(f, "InboundAddressBlocked({}, {:.0?})", ip, duration),
+            FirewallRulesUpdated => write!(f, "FirewallRulesUpdated"),
             NewInboundSubstream(node_id, protocol, _) => write!(
                 f,
                 "NewInboundSubstream({}, {}, Stream)",
@@ -108,6 +124,11 @@ pub struct ConnectionManagerConfig {
     /// If set, an additional TCP-only p2p listener will be started. This is useful for local wallet connections.
     /// Default: None (disabled)
     pub auxilary_tcp_listener_address: Option<Multiaddr>,
+    /// Firewall rules for inbound comms connections. Default: allow all, with automatic blocking of addresses that
+    /// repeatedly fail the noise handshake
+    pub firewall_rules: FirewallRules,
+    /// Upload and download rate limits for all connections and for individual protocols. Default: unlimited
+    pub bandwidth: BandwidthConfig,
 }
 
 impl Default for ConnectionManagerConfig {
@@ -131,6 +152,8 @@ impl Default for ConnectionManagerConfig {
             time_to_first_byte: Duration::from_secs(45),
             liveness_cidr_allowlist: vec![cidr::AnyIpCidr::V4("127.0.0.1/32".parse().unwrap())],
             auxilary_tcp_listener_address: None,
+            firewall_rules: Default::default(),
+            bandwidth: Default::default(),
         }
     }
 }
@@ -164,6 +187,7 @@ pub struct ConnectionManager<TTransport, TBackoff> {
     protocols: Protocols<Substream>,
     listener_info: Option<ListenerInfo>,
     listening_notifiers: Vec<oneshot::Sender<ListenerInfo>>,
+    firewall: InboundFirewall,
     connection_manager_events_tx: broadcast::Sender<Arc<ConnectionManagerEvent>>,
     complete_trigger: Shutdown,
 }
@@ -188,6 +212,8 @@ where
     ) -> Self {
         let (internal_event_tx, internal_event_rx) = mpsc::channel(EVENT_CHANNEL_SIZE);
         let (dialer_tx, dialer_rx) = mpsc::channel(DIALER_REQUEST_CHANNEL_SIZE);
+        let firewall = InboundFirewall::new(config.firewall_rules.clone());
+        let bandwidth_limiter = BandwidthLimiter::new(&config.bandwidth);
 
         let listener = PeerListener::new(
             config.clone(),
@@ -197,6 +223,8 @@ where
             internal_event_tx.clone(),
             peer_manager.clone(),
             node_identity.clone(),
+            firewall.clone(),
+            bandwidth_limiter.clone(),
             shutdown_signal.clone(),
         );
 
@@ -209,6 +237,8 @@ where
                 internal_event_tx.clone(),
                 peer_manager.clone(),
                 node_identity.clone(),
+                firewall.clone(),
+                bandwidth_limiter.clone(),
                 shutdown_signal.clone(),
             )
         });
@@ -222,6 +252,7 @@ where
             backoff,
             dialer_rx,
             internal_event_tx,
+            bandwidth_limiter,
             shutdown_signal.clone(),
         );
 
@@ -237,6 +268,7 @@ where
             listener_info: None,
             aux_listener,
             listening_notifiers: Vec::new(),
+            firewall,
             connection_manager_events_tx,
             complete_trigger: Shutdown::new(),
         }
@@ -368,6 +400,27 @@ where
                     );
                 }
             },
+            ProbeAddress {
+                public_key,
+                address,
+                reply_tx,
+            } => {
+                self.send_dialer_request(DialerRequest::ProbeAddress {
+                    public_key,
+                    address,
+                    reply_tx,
+                })
+                .await;
+            },
+            SetFirewallRules(rules) => {
+                info!(target: LOG_TARGET, "Firewall rules updated");
+                debug!(target: LOG_TARGET, "New firewall rules: {:?}", rules);
+                self.firewall.set_rules(rules);
+                self.publish_event(ConnectionManagerEvent::FirewallRulesUpdated);
+            },
+            GetFirewallRules(reply) => {
+                let _ = reply.send(self.firewall.rules());
+            },
             NotifyListening(reply) => match self.listener_info.as_ref() {
                 Some(info) => {
                     let _ = reply.send(info.clone());
@@ -423,6 +476,24 @@ where
                 }
             },
 
+            PeerConnectionClosed(node_id, bandwidth) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Connection to peer '{}' closed ({})",
+                    node_id.short_str(),
+                    bandwidth.total
+                );
+                if let Err(err) = self.peer_manager.record_bandwidth(&node_id, &bandwidth).await {
+                    warn!(
+                        target: LOG_TARGET,
+                        "Failed to record bandwidth for peer '{}' because '{}'",
+                        node_id.short_str(),
+                        err
+                    );
+                }
+                self.publish_event(PeerConnectionClosed(node_id, bandwidth));
+            },
+
             event => {
                 self.publish_event(event);
             },
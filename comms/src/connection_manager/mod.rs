@@ -39,6 +39,10 @@ pub use manager::{ConnectionManager, ConnectionManagerConfig, ConnectionManagerE
 mod error;
 pub use error::{ConnectionManagerError, PeerConnectionError};
 
+mod firewall;
+pub(crate) use firewall::InboundFirewall;
+pub use firewall::{FirewallRejection, FirewallRules};
+
 mod peer_connection;
 pub use peer_connection::{ConnectionId, NegotiatedSubstream, PeerConnection, PeerConnectionRequest};
 
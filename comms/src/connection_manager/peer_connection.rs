@@ -33,10 +33,12 @@ use crate::protocol::rpc::{
 
 use super::{
     error::{ConnectionManagerError, PeerConnectionError},
+    firewall::FirewallPermit,
     manager::ConnectionManagerEvent,
     types::ConnectionDirection,
 };
 use crate::{
+    bandwidth::{BandwidthLimiter, BandwidthStats, ConnectionBandwidth},
     framing,
     framing::CanonicalFraming,
     multiplexing::{Control, IncomingSubstreams, Substream, Yamux},
@@ -76,6 +78,8 @@ pub fn create(
     event_notifier: mpsc::Sender<ConnectionManagerEvent>,
     our_supported_protocols: Vec<ProtocolId>,
     their_supported_protocols: Vec<ProtocolId>,
+    firewall_permit: Option<FirewallPermit>,
+    bandwidth_limiter: BandwidthLimiter,
 ) -> Result<PeerConnection, ConnectionManagerError> {
     trace!(
         target: LOG_TARGET,
@@ -86,6 +90,7 @@ pub fn create(
     let (peer_tx, peer_rx) = mpsc::channel(1);
     let id = ID_COUNTER.fetch_add(1, Ordering::Relaxed); // Monotonic
     let substream_counter = connection.substream_counter();
+    let bandwidth = connection.bandwidth();
     let peer_conn = PeerConnection::new(
         id,
         peer_tx,
@@ -94,6 +99,7 @@ pub fn create(
         peer_addr,
         direction,
         substream_counter,
+        bandwidth.clone(),
     );
     let peer_actor = PeerConnectionActor::new(
         id,
@@ -104,6 +110,9 @@ pub fn create(
         event_notifier,
         our_supported_protocols,
         their_supported_protocols,
+        firewall_permit,
+        bandwidth,
+        bandwidth_limiter,
     );
     runtime::current().spawn(peer_actor.run());
 
@@ -135,10 +144,12 @@ pub struct PeerConnection {
     direction: ConnectionDirection,
     started_at: Instant,
     substream_counter: AtomicRefCounter,
+    bandwidth: ConnectionBandwidth,
     handle_counter: Arc<()>,
 }
 
 impl PeerConnection {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         id: ConnectionId,
         request_tx: mpsc::Sender<PeerConnectionRequest>,
@@ -147,6 +158,7 @@ impl PeerConnection {
         address: Multiaddr,
         direction: ConnectionDirection,
         substream_counter: AtomicRefCounter,
+        bandwidth: ConnectionBandwidth,
     ) -> Self {
         Self {
             id,
@@ -157,6 +169,7 @@ impl PeerConnection {
             direction,
             started_at: Instant::now(),
             substream_counter,
+            bandwidth,
             handle_counter: Arc::new(()),
         }
     }
@@ -197,6 +210,11 @@ impl PeerConnection {
         Arc::strong_count(&self.handle_counter)
     }
 
+    /// Returns the bytes sent and received on this connection, in total and for each protocol
+    pub fn bandwidth_stats(&self) -> BandwidthStats {
+        self.bandwidth.stats()
+    }
+
     #[tracing::instrument("peer_connection::open_substream", skip(self))]
     pub async fn open_substream(
         &mut self,
@@ -244,7 +262,11 @@ impl PeerConnection {
             self.peer_node_id
         );
         let framed = self.open_framed_substream(&protocol, RPC_MAX_FRAME_SIZE).await?;
-        builder.with_protocol_id(protocol).connect(framed).await
+        builder
+            .with_protocol_id(protocol)
+            .with_node_id(self.peer_node_id.clone())
+            .connect(framed)
+            .await
     }
 
     /// Creates a new RpcClientPool that can be shared between tasks. The client pool will lazily establish up to
@@ -288,14 +310,16 @@ impl fmt::Display for PeerConnection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "Id: {}, Node ID: {}, Direction: {}, Peer Address: {}, Age: {:.0?}, #Substreams: {}, #Refs: {}",
+            "Id: {}, Node ID: {}, Direction: {}, Peer Address: {}, Age: {:.0?}, #Substreams: {}, #Refs: {}, \
+             Bandwidth: ({})",
             self.id,
             self.peer_node_id.short_str(),
             self.direction,
             self.address,
             self.age(),
             self.substream_count(),
-            self.handle_count()
+            self.handle_count(),
+            self.bandwidth.total().totals(),
         )
     }
 }
@@ -317,6 +341,10 @@ struct PeerConnectionActor {
     event_notifier: mpsc::Sender<ConnectionManagerEvent>,
     our_supported_protocols: Vec<ProtocolId>,
     their_supported_protocols: Vec<ProtocolId>,
+    // Held for the lifetime of an inbound connection so that it is counted towards the firewall connection limits
+    _firewall_permit: Option<FirewallPermit>,
+    bandwidth: ConnectionBandwidth,
+    bandwidth_limiter: BandwidthLimiter,
 }
 
 impl PeerConnectionActor {
@@ -330,6 +358,9 @@ impl PeerConnectionActor {
         event_notifier: mpsc::Sender<ConnectionManagerEvent>,
         our_supported_protocols: Vec<ProtocolId>,
         their_supported_protocols: Vec<ProtocolId>,
+        firewall_permit: Option<FirewallPermit>,
+        bandwidth: ConnectionBandwidth,
+        bandwidth_limiter: BandwidthLimiter,
     ) -> Self {
         Self {
             id,
@@ -341,6 +372,9 @@ impl PeerConnectionActor {
             event_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            _firewall_permit: firewall_permit,
+            bandwidth,
+            bandwidth_limiter,
         }
     }
 
@@ -389,6 +423,12 @@ impl PeerConnectionActor {
             );
         }
         self.request_rx.close();
+
+        self.notify_event(ConnectionManagerEvent::PeerConnectionClosed(
+            self.peer_node_id.clone(),
+            self.bandwidth.stats(),
+        ))
+        .await;
     }
 
     async fn handle_request(&mut self, request: PeerConnectionRequest) {
@@ -427,6 +467,7 @@ impl PeerConnectionActor {
         let selected_protocol = ProtocolNegotiation::new(&mut stream)
             .negotiate_protocol_inbound(&self.our_supported_protocols)
             .await?;
+        stream.set_protocol(&selected_protocol, &self.bandwidth_limiter);
 
         self.notify_event(ConnectionManagerEvent::NewInboundSubstream(
             self.peer_node_id.clone(),
@@ -463,6 +504,7 @@ impl PeerConnectionActor {
             let fut = negotiation.negotiate_protocol_outbound(&selected_protocols);
             time::timeout(PROTOCOL_NEGOTIATION_TIMEOUT, fut).await??
         };
+        stream.set_protocol(&selected_protocol, &self.bandwidth_limiter);
 
         Ok(NegotiatedSubstream::new(selected_protocol, stream))
     }
@@ -22,8 +22,13 @@
 
 use super::{error::ConnectionManagerError, peer_connection::PeerConnection};
 use crate::{
-    connection_manager::manager::{ConnectionManagerEvent, ListenerInfo},
+    connection_manager::{
+        manager::{ConnectionManagerEvent, ListenerInfo},
+        FirewallRules,
+    },
+    multiaddr::Multiaddr,
     peer_manager::NodeId,
+    types::CommsPublicKey,
 };
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, oneshot};
@@ -41,6 +46,17 @@ pub enum ConnectionManagerRequest {
     CancelDial(NodeId),
     /// Register a oneshot to get triggered when the node is listening, or has failed to listen
     NotifyListening(oneshot::Sender<ListenerInfo>),
+    /// Dial an address and check that the node on the other end authenticates as the given public key. The
+    /// connection is not kept.
+    ProbeAddress {
+        public_key: CommsPublicKey,
+        address: Multiaddr,
+        reply_tx: oneshot::Sender<Result<(), ConnectionManagerError>>,
+    },
+    /// Replace the inbound firewall rules
+    SetFirewallRules(FirewallRules),
+    /// Get the current inbound firewall rules
+    GetFirewallRules(oneshot::Sender<FirewallRules>),
 }
 
 /// Responsible for constructing requests to the ConnectionManagerService
@@ -117,6 +133,47 @@ impl ConnectionManagerRequester {
         Ok(())
     }
 
+    /// Dial the given address and check that the node listening on it authenticates as `public_key`. This is used to
+    /// check that an address is reachable without establishing a peer connection.
+    pub async fn probe_address(
+        &mut self,
+        public_key: CommsPublicKey,
+        address: Multiaddr,
+    ) -> Result<(), ConnectionManagerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectionManagerRequest::ProbeAddress {
+                public_key,
+                address,
+                reply_tx,
+            })
+            .await
+            .map_err(|_| ConnectionManagerError::SendToActorFailed)?;
+        reply_rx
+            .await
+            .map_err(|_| ConnectionManagerError::ActorRequestCanceled)?
+    }
+
+    /// Replace the rules used by the inbound connection firewall. The new rules apply to all subsequent inbound
+    /// connections. A `FirewallRulesUpdated` event is published once the rules have been applied.
+    pub async fn set_firewall_rules(&mut self, rules: FirewallRules) -> Result<(), ConnectionManagerError> {
+        self.sender
+            .send(ConnectionManagerRequest::SetFirewallRules(rules))
+            .await
+            .map_err(|_| ConnectionManagerError::SendToActorFailed)?;
+        Ok(())
+    }
+
+    /// Returns the rules currently used by the inbound connection firewall
+    pub async fn get_firewall_rules(&mut self) -> Result<FirewallRules, ConnectionManagerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectionManagerRequest::GetFirewallRules(reply_tx))
+            .await
+            .map_err(|_| ConnectionManagerError::SendToActorFailed)?;
+        reply_rx.await.map_err(|_| ConnectionManagerError::ActorRequestCanceled)
+    }
+
     /// Return the ListenerInfo for the configured listener once the listener(s) are bound to the socket.
     ///
     /// This is useful when using "assigned port" addresses, such as /ip4/0.0.0.0/tcp/0 or /memory/0 for listening and
@@ -22,12 +22,16 @@
 
 use crate::{
     backoff::ConstantBackoff,
+    bandwidth::BandwidthLimiter,
     connection_manager::{
         dialer::{Dialer, DialerRequest},
         listener::PeerListener,
         manager::ConnectionManagerEvent,
         ConnectionManagerConfig,
         ConnectionManagerError,
+        FirewallRejection,
+        FirewallRules,
+        InboundFirewall,
     },
     noise::NoiseConfig,
     peer_manager::PeerFeatures,
@@ -61,6 +65,8 @@ async fn listen() -> Result<(), Box<dyn Error>> {
         event_tx,
         peer_manager,
         node_identity,
+        InboundFirewall::new(Default::default()),
+        BandwidthLimiter::unlimited(),
         shutdown.to_signal(),
     );
 
@@ -96,6 +102,8 @@ async fn smoke() {
         event_tx.clone(),
         peer_manager1.clone(),
         node_identity1.clone(),
+        InboundFirewall::new(Default::default()),
+        BandwidthLimiter::unlimited(),
         shutdown.to_signal(),
     );
     listener.set_supported_protocols(supported_protocols.clone());
@@ -116,6 +124,7 @@ async fn smoke() {
         ConstantBackoff::new(Duration::from_millis(100)),
         request_rx,
         event_tx,
+        BandwidthLimiter::unlimited(),
         shutdown.to_signal(),
     );
     dialer.set_supported_protocols(supported_protocols.clone());
@@ -160,6 +169,11 @@ async fn smoke() {
         assert_eq!(buf, *b"HELLO");
     }
 
+    // Bytes sent before the protocol was negotiated only count towards the connection total
+    let bandwidth = outbound_peer_conn.bandwidth_stats();
+    assert_eq!(bandwidth.protocols[&expected_proto].bytes_sent, 5);
+    assert!(bandwidth.total.bytes_sent > 5);
+
     conn1.disconnect().await.unwrap();
 
     shutdown.trigger();
@@ -192,6 +206,8 @@ async fn banned() {
         event_tx.clone(),
         peer_manager1.clone(),
         node_identity1.clone(),
+        InboundFirewall::new(Default::default()),
+        BandwidthLimiter::unlimited(),
         shutdown.to_signal(),
     );
     listener.set_supported_protocols(supported_protocols.clone());
@@ -217,6 +233,7 @@ async fn banned() {
         ConstantBackoff::new(Duration::from_millis(100)),
         request_rx,
         event_tx,
+        BandwidthLimiter::unlimited(),
         shutdown.to_signal(),
     );
     dialer.set_supported_protocols(supported_protocols);
@@ -245,3 +262,144 @@ async fn banned() {
 
     timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
 }
+
+#[runtime::test]
+async fn firewall_rejects_peer_without_required_features() {
+    let (event_tx, mut event_rx) = mpsc::channel(10);
+    let mut shutdown = Shutdown::new();
+
+    let node_identity1 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let firewall = InboundFirewall::new(FirewallRules {
+        required_features: PeerFeatures::COMMUNICATION_NODE,
+        ..Default::default()
+    });
+    let listener = PeerListener::new(
+        Default::default(),
+        "/memory/0".parse().unwrap(),
+        MemoryTransport,
+        NoiseConfig::new(node_identity1.clone()),
+        event_tx,
+        build_peer_manager(),
+        node_identity1.clone(),
+        firewall,
+        BandwidthLimiter::unlimited(),
+        shutdown.to_signal(),
+    );
+    let address = listener.listen().await.unwrap();
+
+    let node_identity2 = build_node_identity(PeerFeatures::COMMUNICATION_CLIENT);
+    let (dialer_event_tx, _dialer_event_rx) = mpsc::channel(10);
+    let (request_tx, request_rx) = mpsc::channel(1);
+    let dialer = Dialer::new(
+        ConnectionManagerConfig::default(),
+        node_identity2.clone(),
+        build_peer_manager(),
+        MemoryTransport,
+        NoiseConfig::new(node_identity2.clone()),
+        ConstantBackoff::new(Duration::from_millis(100)),
+        request_rx,
+        dialer_event_tx,
+        BandwidthLimiter::unlimited(),
+        shutdown.to_signal(),
+    );
+    let dialer_fut = runtime::current().spawn(dialer.run());
+
+    let mut peer = node_identity1.to_peer();
+    peer.addresses = vec![address].into();
+    peer.set_id_for_test(1);
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    request_tx
+        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx)))
+        .await
+        .unwrap();
+    // The dial may or may not complete before the listener closes the connection
+    let _ = reply_rx.await.unwrap();
+
+    unpack_enum!(ConnectionManagerEvent::InboundConnectionRejected(_addr, rejection) = event_rx.recv().await.unwrap());
+    assert_eq!(
+        rejection,
+        FirewallRejection::MissingRequiredFeatures(PeerFeatures::COMMUNICATION_NODE)
+    );
+
+    shutdown.trigger();
+
+    timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
+}
+
+#[cfg(feature = "quic")]
+#[runtime::test]
+async fn smoke_quic() {
+    use crate::transports::QuicTransport;
+
+    let (event_tx, mut event_rx) = mpsc::channel(10);
+    let mut shutdown = Shutdown::new();
+
+    let node_identity1 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let expected_proto = ProtocolId::from_static(b"/tari/test-proto");
+    let supported_protocols = vec![expected_proto.clone()];
+    let mut listener = PeerListener::new(
+        Default::default(),
+        "/ip4/127.0.0.1/udp/0/quic".parse().unwrap(),
+        QuicTransport::default(),
+        NoiseConfig::new(node_identity1.clone()),
+        event_tx.clone(),
+        build_peer_manager(),
+        node_identity1.clone(),
+        InboundFirewall::new(Default::default()),
+        BandwidthLimiter::unlimited(),
+        shutdown.to_signal(),
+    );
+    listener.set_supported_protocols(supported_protocols.clone());
+    let address = listener.listen().await.unwrap();
+    unpack_enum!(Protocol::Quic = address.iter().last().unwrap());
+
+    let node_identity2 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let (request_tx, request_rx) = mpsc::channel(1);
+    let mut dialer = Dialer::new(
+        ConnectionManagerConfig::default(),
+        node_identity2.clone(),
+        build_peer_manager(),
+        QuicTransport::default(),
+        NoiseConfig::new(node_identity2.clone()),
+        ConstantBackoff::new(Duration::from_millis(100)),
+        request_rx,
+        event_tx,
+        BandwidthLimiter::unlimited(),
+        shutdown.to_signal(),
+    );
+    dialer.set_supported_protocols(supported_protocols);
+    let dialer_fut = runtime::current().spawn(dialer.run());
+
+    let mut peer = node_identity1.to_peer();
+    peer.addresses = vec![address].into();
+    peer.set_id_for_test(1);
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    request_tx
+        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx)))
+        .await
+        .unwrap();
+    let mut outbound_peer_conn = reply_rx.await.unwrap().unwrap();
+
+    let mut out_stream = outbound_peer_conn.open_substream(&expected_proto).await.unwrap();
+    out_stream.stream.write_all(b"HELLO").await.unwrap();
+    out_stream.stream.flush().await.unwrap();
+
+    unpack_enum!(ConnectionManagerEvent::PeerConnected(_conn1) = event_rx.recv().await.unwrap());
+    unpack_enum!(ConnectionManagerEvent::PeerConnected(_conn2) = event_rx.recv().await.unwrap());
+
+    unpack_enum!(
+        ConnectionManagerEvent::NewInboundSubstream(node_id, proto, in_stream) = event_rx.recv().await.unwrap()
+    );
+    assert_eq!(&node_id, node_identity2.node_id());
+    assert_eq!(proto, expected_proto);
+    let mut buf = [0u8; 5];
+    in_stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, *b"HELLO");
+
+    outbound_peer_conn.disconnect().await.unwrap();
+    shutdown.trigger();
+
+    timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
+}
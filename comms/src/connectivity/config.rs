@@ -20,6 +20,7 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::peer_manager::{PeerCompactionConfig, ReputationConfig};
 use std::time::Duration;
 
 #[derive(Debug, Clone, Copy)]
@@ -41,6 +42,10 @@ pub struct ConnectivityConfig {
     /// The length of time to wait before disconnecting a connection that failed tie breaking.
     /// Default: 1s
     pub connection_tie_break_linger: Duration,
+    /// Peer reputation scoring and ban thresholds
+    pub reputation: ReputationConfig,
+    /// Periodic eviction of long-inactive, low-value peers from the peer database
+    pub peer_compaction: PeerCompactionConfig,
 }
 
 impl Default for ConnectivityConfig {
@@ -52,6 +57,8 @@ impl Default for ConnectivityConfig {
             is_connection_reaping_enabled: true,
             max_failures_mark_offline: 2,
             connection_tie_break_linger: Duration::from_secs(2),
+            reputation: Default::default(),
+            peer_compaction: Default::default(),
         }
     }
 }
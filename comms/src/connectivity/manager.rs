@@ -24,8 +24,9 @@ use super::{
     connection_pool::{ConnectionPool, ConnectionStatus},
     connection_stats::PeerConnectionStats,
     error::ConnectivityError,
+    metrics,
     requester::{ConnectivityEvent, ConnectivityRequest},
-    selection::ConnectivitySelection,
+    selection::{ConnectivitySelection, ReputationScores},
 };
 use crate::{
     connection_manager::{
@@ -35,7 +36,7 @@ use crate::{
         ConnectionManagerRequester,
     },
     connectivity::ConnectivityEventTx,
-    peer_manager::NodeId,
+    peer_manager::{GoodBehaviour, Misbehaviour, NodeId, PeerManagerError, PeerReputation, ReputationChange},
     runtime::task,
     utils::datetime::format_duration,
     NodeIdentity,
@@ -88,6 +89,7 @@ impl ConnectivityManager {
             peer_manager: self.peer_manager.clone(),
             event_tx: self.event_tx,
             connection_stats: HashMap::new(),
+            reputations: HashMap::new(),
             node_identity: self.node_identity,
             pool: ConnectionPool::new(),
             shutdown_signal: self.shutdown_signal,
@@ -143,6 +145,7 @@ struct ConnectivityManagerActor {
     peer_manager: Arc<PeerManager>,
     event_tx: ConnectivityEventTx,
     connection_stats: HashMap<NodeId, PeerConnectionStats>,
+    reputations: HashMap<NodeId, PeerReputation>,
     pool: ConnectionPool,
     shutdown_signal: ShutdownSignal,
 }
@@ -168,6 +171,16 @@ impl ConnectivityManagerActor {
         );
         ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
+        let compaction_interval = self.config.peer_compaction.interval;
+        let mut compaction_ticker = time::interval_at(
+            Instant::now()
+                .checked_add(compaction_interval)
+                .expect("peer_compaction.interval cause overflow")
+                .into(),
+            compaction_interval,
+        );
+        compaction_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
         self.publish_event(ConnectivityEvent::ConnectivityStateInitialized);
 
         loop {
@@ -190,6 +203,12 @@ impl ConnectivityManagerActor {
                     }
                 },
 
+                _ = compaction_ticker.tick(), if self.config.peer_compaction.is_enabled => {
+                    if let Err(err) = self.compact_peers().await {
+                        error!(target: LOG_TARGET, "Error when compacting the peer database: {:?}", err);
+                    }
+                },
+
                 _ = self.shutdown_signal.wait() => {
                     info!(target: LOG_TARGET, "ConnectivityManager is shutting down because it received the shutdown signal");
                     self.disconnect_all().await;
@@ -269,6 +288,27 @@ impl ConnectivityManagerActor {
                     error!(target: LOG_TARGET, "Error when banning peer: {:?}", err);
                 }
             },
+            ReportMisbehaviour(node_id, misbehaviour, reason) => {
+                if let Err(err) = self.report_misbehaviour(&node_id, misbehaviour, reason).await {
+                    error!(target: LOG_TARGET, "Error when reporting peer misbehaviour: {:?}", err);
+                }
+            },
+            CreditPeer(node_id, behaviour) => {
+                if let Err(err) = self.credit_peer(&node_id, behaviour).await {
+                    error!(target: LOG_TARGET, "Error when crediting peer: {:?}", err);
+                }
+            },
+            GetReputationScores(reply) => {
+                let _ = reply.send(
+                    self.peer_manager
+                        .reputation_scores(&self.config.reputation)
+                        .await
+                        .map_err(Into::into),
+                );
+            },
+            CompactPeers(reply) => {
+                let _ = reply.send(self.compact_peers().await);
+            },
             GetActiveConnections(reply) => {
                 let _ = reply.send(
                     self.pool
@@ -306,6 +346,29 @@ impl ConnectivityManagerActor {
         }
     }
 
+    async fn compact_peers(&mut self) -> Result<usize, ConnectivityError> {
+        // Peers in the connection pool are in use and are never evicted
+        let exclude_peers = self
+            .pool
+            .all()
+            .into_iter()
+            .map(|state| state.node_id().clone())
+            .collect::<Vec<_>>();
+        let num_evicted = self
+            .peer_manager
+            .compact(&self.config.peer_compaction, &self.config.reputation, &exclude_peers)
+            .await?;
+        if num_evicted > 0 {
+            info!(
+                target: LOG_TARGET,
+                "Peer database compaction evicted {} peer(s). {} peer(s) remain.",
+                num_evicted,
+                self.peer_manager.count().await
+            );
+        }
+        Ok(num_evicted)
+    }
+
     async fn refresh_connection_pool(&mut self) -> Result<(), ConnectivityError> {
         debug!(
             target: LOG_TARGET,
@@ -357,6 +420,10 @@ impl ConnectivityManagerActor {
             state.status() == ConnectionStatus::Failed || state.status() == ConnectionStatus::Disconnected
         });
 
+        for state in &cleared_states {
+            self.reputations.remove(state.node_id());
+        }
+
         if !cleared_states.is_empty() {
             debug!(
                 target: LOG_TARGET,
@@ -381,7 +448,12 @@ impl ConnectivityManagerActor {
             self.pool.count_connected_nodes()
         );
 
-        let conns = selection.select(&self.pool);
+        let scores = self
+            .reputations
+            .iter()
+            .map(|(node_id, reputation)| (node_id.clone(), reputation.score(&self.config.reputation)))
+            .collect::<ReputationScores>();
+        let conns = selection.select(&self.pool, &scores);
         debug!(target: LOG_TARGET, "Selected {} connections(s)", conns.len());
 
         Ok(conns.into_iter().cloned().collect())
@@ -526,9 +598,25 @@ impl ConnectivityManagerActor {
                     target: LOG_TARGET,
                     "Connection to peer '{}' failed because '{:?}'", node_id, err
                 );
+                metrics::dial_failures().inc();
                 self.handle_peer_connection_failure(node_id).await?;
                 (&*node_id, ConnectionStatus::Failed, None)
             },
+            InboundConnectionRejected(addr, reason) => {
+                self.publish_event(ConnectivityEvent::InboundConnectionRejected(
+                    addr.clone(),
+                    reason.clone(),
+                ));
+                return Ok(());
+            },
+            InboundAddressBlocked(ip, duration) => {
+                self.publish_event(ConnectivityEvent::InboundAddressBlocked(*ip, *duration));
+                return Ok(());
+            },
+            FirewallRulesUpdated => {
+                self.publish_event(ConnectivityEvent::FirewallRulesUpdated);
+                return Ok(());
+            },
             _ => return Ok(()),
         };
 
@@ -549,6 +637,9 @@ impl ConnectivityManagerActor {
         match (old_status, new_status) {
             (_, Connected) => {
                 self.mark_peer_succeeded(node_id.clone());
+                if let Ok(peer) = self.peer_manager.find_by_node_id(&node_id).await {
+                    self.reputations.insert(node_id.clone(), peer.reputation);
+                }
                 match self.pool.get_connection(&node_id).cloned() {
                     Some(conn) => {
                         self.publish_event(ConnectivityEvent::PeerConnected(conn));
@@ -621,6 +712,8 @@ impl ConnectivityManagerActor {
             target: LOG_TARGET,
             "#min_peers = {}, #nodes = {}, #clients = {}", min_peers, num_connected_nodes, num_connected_clients
         );
+        metrics::connections("node").set(num_connected_nodes as i64);
+        metrics::connections("client").set(num_connected_clients as i64);
 
         match num_connected_nodes {
             n if n >= min_peers => {
@@ -705,7 +798,75 @@ impl ConnectivityManagerActor {
         );
 
         self.peer_manager.ban_peer_by_node_id(node_id, duration, reason).await?;
+        self.disconnect_banned_peer(node_id).await
+    }
+
+    async fn report_misbehaviour(
+        &mut self,
+        node_id: &NodeId,
+        misbehaviour: Misbehaviour,
+        reason: String,
+    ) -> Result<(), ConnectivityError> {
+        debug!(
+            target: LOG_TARGET,
+            "Peer {} reported for misbehaviour '{}': {}", node_id, misbehaviour, reason
+        );
+        let change = match self
+            .peer_manager
+            .report_misbehaviour(node_id, misbehaviour, reason, &self.config.reputation)
+            .await
+        {
+            Ok(change) => change,
+            Err(PeerManagerError::PeerNotFoundError) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Ignoring misbehaviour report for unknown peer {}", node_id
+                );
+                return Ok(());
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        self.update_reputation(node_id, change).await
+    }
+
+    async fn credit_peer(&mut self, node_id: &NodeId, behaviour: GoodBehaviour) -> Result<(), ConnectivityError> {
+        let change = match self
+            .peer_manager
+            .credit_peer(node_id, behaviour, &self.config.reputation)
+            .await
+        {
+            Ok(change) => change,
+            Err(PeerManagerError::PeerNotFoundError) => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+
+        self.update_reputation(node_id, change).await
+    }
+
+    async fn update_reputation(&mut self, node_id: &NodeId, change: ReputationChange) -> Result<(), ConnectivityError> {
+        debug!(
+            target: LOG_TARGET,
+            "Reputation score of peer {} is now {:.1}", node_id, change.score
+        );
+        if self.pool.contains(node_id) {
+            self.reputations.insert(node_id.clone(), change.reputation);
+        }
+
+        if let Some(duration) = change.banned_for {
+            info!(
+                target: LOG_TARGET,
+                "Banned peer {} for {} because its reputation score fell to {:.1}",
+                node_id,
+                format_duration(duration),
+                change.score
+            );
+            self.disconnect_banned_peer(node_id).await?;
+        }
+        Ok(())
+    }
 
+    async fn disconnect_banned_peer(&mut self, node_id: &NodeId) -> Result<(), ConnectivityError> {
         self.publish_event(ConnectivityEvent::PeerBanned(node_id.clone()));
 
         if let Some(conn) = self.pool.get_connection_mut(node_id) {
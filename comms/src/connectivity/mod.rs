@@ -30,6 +30,8 @@ mod connection_pool;
 mod error;
 pub use error::ConnectivityError;
 
+mod metrics;
+
 mod manager;
 pub(crate) use manager::ConnectivityManager;
 pub use manager::ConnectivityStatus;
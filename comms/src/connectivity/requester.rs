@@ -27,14 +27,16 @@ use super::{
     ConnectivitySelection,
 };
 use crate::{
-    connection_manager::{ConnectionDirection, ConnectionManagerError},
-    peer_manager::NodeId,
+    connection_manager::{ConnectionDirection, ConnectionManagerError, FirewallRejection},
+    multiaddr::Multiaddr,
+    peer_manager::{GoodBehaviour, Misbehaviour, NodeId, PeerScore},
     PeerConnection,
 };
 use futures::{future, stream::FuturesUnordered, Stream};
 use log::*;
 use std::{
     fmt,
+    net::IpAddr,
     time::{Duration, Instant},
 };
 use tokio::{
@@ -57,6 +59,10 @@ pub enum ConnectivityEvent {
     PeerOffline(NodeId),
     PeerConnectionWillClose(NodeId, ConnectionDirection),
 
+    InboundConnectionRejected(Multiaddr, FirewallRejection),
+    InboundAddressBlocked(IpAddr, Duration),
+    FirewallRulesUpdated,
+
     ConnectivityStateInitialized,
     ConnectivityStateOnline(usize),
     ConnectivityStateDegraded(usize),
@@ -75,6 +81,9 @@ impl fmt::Display for ConnectivityEvent {
             PeerConnectionWillClose(node_id, direction) => {
                 write!(f, "PeerConnectionWillClose({}, {})", node_id, direction)
             },
+            InboundConnectionRejected(addr, reason) => write!(f, "InboundConnectionRejected({}, {})", addr, reason),
+            InboundAddressBlocked(ip, duration) => write!(f, "InboundAddressBlocked({}, {:.0?})", ip, duration),
+            FirewallRulesUpdated => write!(f, "FirewallRulesUpdated"),
             ConnectivityStateInitialized => write!(f, "ConnectivityStateInitialized"),
             ConnectivityStateOnline(n) => write!(f, "ConnectivityStateOnline({})", n),
             ConnectivityStateDegraded(n) => write!(f, "ConnectivityStateDegraded({})", n),
@@ -100,6 +109,10 @@ pub enum ConnectivityRequest {
     GetAllConnectionStates(oneshot::Sender<Vec<PeerConnectionState>>),
     GetActiveConnections(oneshot::Sender<Vec<PeerConnection>>),
     BanPeer(NodeId, Duration, String),
+    ReportMisbehaviour(NodeId, Misbehaviour, String),
+    CreditPeer(NodeId, GoodBehaviour),
+    GetReputationScores(oneshot::Sender<Result<Vec<PeerScore>, ConnectivityError>>),
+    CompactPeers(oneshot::Sender<Result<usize, ConnectivityError>>),
 }
 
 #[derive(Debug, Clone)]
@@ -246,6 +259,50 @@ impl ConnectivityRequester {
             .await
     }
 
+    /// Report misbehaviour by a peer. The peer's reputation score is reduced and, if the score falls to the ban
+    /// threshold, the peer is banned and disconnected.
+    pub async fn report_misbehaviour(
+        &mut self,
+        node_id: NodeId,
+        misbehaviour: Misbehaviour,
+        reason: String,
+    ) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::ReportMisbehaviour(node_id, misbehaviour, reason))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
+    }
+
+    /// Credit a peer for good behaviour, increasing its reputation score
+    pub async fn credit_peer(&mut self, node_id: NodeId, behaviour: GoodBehaviour) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::CreditPeer(node_id, behaviour))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
+    }
+
+    /// Returns the current reputation scores of all scored peers, highest score first
+    pub async fn get_reputation_scores(&mut self) -> Result<Vec<PeerScore>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetReputationScores(reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)?
+    }
+
+    /// Evict long-inactive, low-value peers from the peer database now. Returns the number of evicted peers.
+    pub async fn compact_peers(&mut self) -> Result<usize, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::CompactPeers(reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)?
+    }
+
     pub async fn wait_started(&mut self) -> Result<(), ConnectivityError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.sender
@@ -23,7 +23,13 @@
 use super::connection_pool::ConnectionPool;
 use crate::{connectivity::connection_pool::ConnectionStatus, peer_manager::NodeId, PeerConnection};
 use rand::{rngs::OsRng, seq::SliceRandom};
-use std::{fmt, fmt::Display};
+use std::{cmp::Ordering, collections::HashMap, fmt, fmt::Display};
+
+/// The score difference that doubles the likelihood of a peer being randomly selected
+const SCORE_WEIGHT_DOUBLING: f64 = 25.0;
+
+/// Reputation scores keyed by node id. Peers without an entry have a score of zero.
+pub type ReputationScores = HashMap<NodeId, f64>;
 
 #[derive(Debug, Clone)]
 pub struct ConnectivitySelection {
@@ -36,6 +42,7 @@ enum SelectionMode {
     AllNodes,
     RandomNodes(usize),
     ClosestTo(Box<NodeId>, usize),
+    HighestScoring(usize),
 }
 
 impl ConnectivitySelection {
@@ -46,6 +53,7 @@ impl ConnectivitySelection {
         }
     }
 
+    /// Select `n` random peer connections. Peers with higher reputation scores are more likely to be selected.
     pub fn random_nodes(n: usize, exclude: Vec<NodeId>) -> Self {
         Self {
             selection_mode: SelectionMode::RandomNodes(n),
@@ -53,6 +61,14 @@ impl ConnectivitySelection {
         }
     }
 
+    /// Select the `n` peer connections with the highest reputation scores
+    pub fn highest_scoring(n: usize, exclude: Vec<NodeId>) -> Self {
+        Self {
+            selection_mode: SelectionMode::HighestScoring(n),
+            excluded_peers: exclude,
+        }
+    }
+
     /// Select `n` peer connections ordered by closeness to `node_id`
     pub fn closest_to(node_id: NodeId, n: usize, exclude: Vec<NodeId>) -> Self {
         Self {
@@ -62,11 +78,16 @@ impl ConnectivitySelection {
     }
 
     /// Select peers from the pool according to the ConnectivitySelection
-    pub fn select<'a>(&self, pool: &'a ConnectionPool) -> Vec<&'a PeerConnection> {
+    pub fn select<'a>(&self, pool: &'a ConnectionPool, scores: &ReputationScores) -> Vec<&'a PeerConnection> {
         use SelectionMode::*;
         match &self.selection_mode {
             AllNodes => select_connected_nodes(pool, &self.excluded_peers),
-            RandomNodes(n) => select_random_nodes(pool, *n, &self.excluded_peers),
+            RandomNodes(n) => select_random_nodes(pool, *n, &self.excluded_peers, scores),
+            HighestScoring(n) => {
+                let mut connections = select_highest_scoring(pool, scores, &self.excluded_peers);
+                connections.truncate(*n);
+                connections
+            },
             ClosestTo(dest_node_id, n) => {
                 let mut connections = select_closest(pool, dest_node_id, &self.excluded_peers);
                 connections.truncate(*n);
@@ -100,9 +121,40 @@ pub fn select_closest<'a>(pool: &'a ConnectionPool, node_id: &NodeId, exclude: &
     nodes
 }
 
-pub fn select_random_nodes<'a>(pool: &'a ConnectionPool, n: usize, exclude: &[NodeId]) -> Vec<&'a PeerConnection> {
+pub fn select_highest_scoring<'a>(
+    pool: &'a ConnectionPool,
+    scores: &ReputationScores,
+    exclude: &[NodeId],
+) -> Vec<&'a PeerConnection> {
+    let mut nodes = select_connected_nodes(pool, exclude);
+
+    nodes.sort_by(|a, b| {
+        let score_a = score_of(scores, a.peer_node_id());
+        let score_b = score_of(scores, b.peer_node_id());
+        score_b.partial_cmp(&score_a).unwrap_or(Ordering::Equal)
+    });
+
+    nodes
+}
+
+pub fn select_random_nodes<'a>(
+    pool: &'a ConnectionPool,
+    n: usize,
+    exclude: &[NodeId],
+    scores: &ReputationScores,
+) -> Vec<&'a PeerConnection> {
     let nodes = select_connected_nodes(pool, exclude);
-    nodes.choose_multiple(&mut OsRng, n).cloned().collect()
+    match nodes.choose_multiple_weighted(&mut OsRng, n.min(nodes.len()), |conn| {
+        2f64.powf(score_of(scores, conn.peer_node_id()) / SCORE_WEIGHT_DOUBLING)
+    }) {
+        Ok(selected) => selected.cloned().collect(),
+        // Weights are always positive and finite, this is a fallback to uniform selection in case that changes
+        Err(_) => nodes.choose_multiple(&mut OsRng, n).cloned().collect(),
+    }
+}
+
+fn score_of(scores: &ReputationScores, node_id: &NodeId) -> f64 {
+    scores.get(node_id).copied().unwrap_or(0.0)
 }
 
 impl Display for ConnectivitySelection {
@@ -123,6 +175,7 @@ impl Display for SelectionMode {
             AllNodes => write!(f, "AllNodes"),
             RandomNodes(n) => write!(f, "RandomNodes({})", n),
             ClosestTo(node_id, n) => write!(f, "ClosestTo({}, {})", node_id, n),
+            HighestScoring(n) => write!(f, "HighestScoring({})", n),
         }
     }
 }
@@ -154,11 +207,11 @@ mod test {
     #[test]
     fn select_random() {
         let (pool, _receivers) = create_pool_with_connections(10);
-        let conns = select_random_nodes(&pool, 500, &[]);
+        let conns = select_random_nodes(&pool, 500, &[], &HashMap::new());
         assert_eq!(conns.len(), 10);
 
         let first_node = conns.first().unwrap().peer_node_id().clone();
-        let conns = select_random_nodes(&pool, 10, &[first_node.clone()]);
+        let conns = select_random_nodes(&pool, 10, &[first_node.clone()], &HashMap::new());
         assert_eq!(conns.len(), 9);
         assert!(conns.iter().all(|c| c.peer_node_id() != &first_node));
     }
@@ -185,4 +238,35 @@ mod test {
         let conns = select_closest(&pool, node_identity.node_id(), &[]);
         assert!(conns.is_empty());
     }
+
+    #[test]
+    fn select_random_prefers_high_scores() {
+        let (pool, _receivers) = create_pool_with_connections(10);
+        let mut scores = ReputationScores::new();
+        let preferred = pool.all().first().unwrap().node_id().clone();
+        scores.insert(preferred.clone(), 100.0);
+        for state in pool.all().iter().skip(1) {
+            scores.insert(state.node_id().clone(), -1000.0);
+        }
+
+        let conns = select_random_nodes(&pool, 1, &[], &scores);
+        assert_eq!(conns.len(), 1);
+        assert_eq!(conns[0].peer_node_id(), &preferred);
+    }
+
+    #[test]
+    fn select_highest_scoring_ordering() {
+        let (pool, _receivers) = create_pool_with_connections(10);
+        let scores = pool
+            .all()
+            .into_iter()
+            .enumerate()
+            .map(|(i, state)| (state.node_id().clone(), i as f64))
+            .collect::<ReputationScores>();
+
+        let conns = ConnectivitySelection::highest_scoring(3, vec![]).select(&pool, &scores);
+        assert_eq!(conns.len(), 3);
+        let selected_scores = conns.iter().map(|c| scores[c.peer_node_id()]).collect::<Vec<_>>();
+        assert_eq!(selected_scores, vec![9.0, 8.0, 7.0]);
+    }
 }
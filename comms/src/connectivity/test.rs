@@ -27,9 +27,9 @@ use super::{
     selection::ConnectivitySelection,
 };
 use crate::{
-    connection_manager::{ConnectionManagerError, ConnectionManagerEvent},
+    connection_manager::{ConnectionManagerError, ConnectionManagerEvent, FirewallRejection},
     connectivity::ConnectivityEventRx,
-    peer_manager::{Peer, PeerFeatures},
+    peer_manager::{GoodBehaviour, Misbehaviour, Peer, PeerFeatures},
     runtime,
     runtime::task,
     test_utils::{
@@ -135,6 +135,32 @@ async fn connecting_peers() {
     }
 }
 
+#[runtime::test]
+async fn firewall_events() {
+    let (_connectivity, mut event_stream, _node_identity, _peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+
+    let mut events = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateInitialized = events.remove(0));
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::InboundConnectionRejected(
+        "/ip4/1.2.3.4/tcp/1234".parse().unwrap(),
+        FirewallRejection::Denied,
+    ));
+    cm_mock_state.publish_event(ConnectionManagerEvent::InboundAddressBlocked(
+        "1.2.3.4".parse().unwrap(),
+        Duration::from_secs(60),
+    ));
+    cm_mock_state.publish_event(ConnectionManagerEvent::FirewallRulesUpdated);
+
+    let mut events = collect_try_recv!(event_stream, take = 3, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::InboundConnectionRejected(_addr, reason) = events.remove(0));
+    assert_eq!(reason, FirewallRejection::Denied);
+    unpack_enum!(ConnectivityEvent::InboundAddressBlocked(_ip, duration) = events.remove(0));
+    assert_eq!(duration, Duration::from_secs(60));
+    unpack_enum!(ConnectivityEvent::FirewallRulesUpdated = events.remove(0));
+}
+
 #[runtime::test]
 async fn online_then_offline() {
     let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
@@ -276,6 +302,58 @@ async fn ban_peer() {
     assert!(conn.is_none());
 }
 
+#[runtime::test]
+async fn misbehaviour_reputation_ban() {
+    let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: 1,
+            ..Default::default()
+        });
+    let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+
+    let mut events = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateInitialized = events.remove(0));
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    let mut events = collect_try_recv!(event_stream, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerConnected(_conn) = events.remove(0));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateOnline(_n) = events.remove(0));
+
+    connectivity
+        .credit_peer(peer.node_id.clone(), GoodBehaviour::SuccessfulSync)
+        .await
+        .unwrap();
+    connectivity
+        .report_misbehaviour(peer.node_id.clone(), Misbehaviour::InvalidData, "bad block".to_string())
+        .await
+        .unwrap();
+
+    let scores = connectivity.get_reputation_scores().await.unwrap();
+    assert_eq!(scores.len(), 1);
+    assert_eq!(scores[0].node_id, peer.node_id);
+    assert!(scores[0].score < -39.0 && scores[0].score > -41.0);
+    assert!(!scores[0].is_banned);
+
+    connectivity
+        .report_misbehaviour(peer.node_id.clone(), Misbehaviour::InvalidSignature, "".to_string())
+        .await
+        .unwrap();
+
+    let event = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10))
+        .pop()
+        .unwrap();
+    unpack_enum!(ConnectivityEvent::PeerBanned(node_id) = event);
+    assert_eq!(node_id, peer.node_id);
+
+    let peer = peer_manager.find_by_node_id(&peer.node_id).await.unwrap();
+    assert!(peer.is_banned());
+    assert_eq!(peer.reputation.num_bans(), 1);
+
+    let conn = connectivity.get_connection(peer.node_id.clone()).await.unwrap();
+    assert!(conn.is_none());
+}
+
 #[runtime::test]
 async fn peer_selection() {
     let config = ConnectivityConfig {
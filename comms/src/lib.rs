@@ -22,6 +22,8 @@ mod macros;
 mod builder;
 pub use builder::{CommsBuilder, CommsBuilderError, CommsNode, UnspawnedCommsNode};
 
+pub mod bandwidth;
+
 pub mod connection_manager;
 pub use connection_manager::{validate_peer_addresses, PeerConnection, PeerConnectionError};
 
@@ -34,6 +36,8 @@ pub mod framing;
 
 pub mod rate_limit;
 
+pub mod reachability;
+
 mod multiplexing;
 pub use multiplexing::Substream;
 
@@ -22,3 +22,6 @@
 
 mod yamux;
 pub use self::yamux::{ConnectionError, Control, IncomingSubstreams, Substream, Yamux};
+
+#[cfg(feature = "quic")]
+pub(crate) mod quic;
@@ -0,0 +1,215 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Multiplexing for QUIC connections. QUIC streams are natively multiplexed, so rather than layering yamux over the
+//! connection, each QUIC bidirectional stream is exposed as a [Substream](super::Substream).
+//!
+//! The peer is authenticated by the noise handshake on the first stream of the connection. Because the TLS
+//! certificates used by QUIC are self-signed and not verified, both sides additionally exchange keying material
+//! exported from the TLS session over the authenticated noise channel. A mismatch means that the noise handshake was
+//! relayed over a different QUIC connection and the connection is rejected.
+
+use super::{yamux::SubstreamInner, Control, IncomingSubstreams, Yamux};
+use crate::{
+    bandwidth::ConnectionBandwidth,
+    connection_manager::ConnectionDirection,
+    noise::NoiseSocket,
+    runtime,
+    stream_id,
+    transports::QuicSocket,
+    utils::atomic_ref_counter::AtomicRefCounter,
+};
+use futures::StreamExt;
+use log::*;
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tari_shutdown::{Shutdown, ShutdownSignal};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    sync::mpsc,
+};
+use yamux::ConnectionError;
+
+const LOG_TARGET: &str = "comms::multiplexing::quic";
+
+const CHANNEL_BINDING_LABEL: &[u8] = b"EXPORTER-tari-comms-quic-channel-binding";
+const CHANNEL_BINDING_LEN: usize = 32;
+
+/// Upgrade a QUIC connection, authenticated by noise, to a multiplexed connection
+pub(crate) async fn upgrade_connection(
+    mut socket: NoiseSocket<QuicSocket>,
+    direction: ConnectionDirection,
+) -> io::Result<Yamux> {
+    verify_channel_binding(&mut socket).await?;
+
+    // The first stream is only used for the handshake, all subsequent streams are substreams
+    let (connection, incoming) = socket.into_inner().into_parts();
+    debug!(
+        target: LOG_TARGET,
+        "{} QUIC connection to '{}' upgraded",
+        direction,
+        connection.remote_address()
+    );
+
+    let substream_counter = AtomicRefCounter::new();
+    let bandwidth = ConnectionBandwidth::new();
+    let control = Control::new_quic(QuicControl { connection }, substream_counter.clone(), bandwidth.clone());
+
+    let shutdown = Shutdown::new();
+    let (incoming_tx, incoming_rx) = mpsc::channel(10);
+    let worker = IncomingWorker {
+        control: control.clone(),
+        incoming,
+        sender: incoming_tx,
+        shutdown_signal: shutdown.to_signal(),
+    };
+    runtime::task::spawn(worker.run());
+    let incoming = IncomingSubstreams::new(incoming_rx, substream_counter.clone(), bandwidth.clone(), shutdown);
+
+    Ok(Yamux::from_parts(control, incoming, substream_counter, bandwidth))
+}
+
+async fn verify_channel_binding(socket: &mut NoiseSocket<QuicSocket>) -> io::Result<()> {
+    let mut local = [0u8; CHANNEL_BINDING_LEN];
+    socket
+        .get_ref()
+        .connection()
+        .export_keying_material(&mut local, CHANNEL_BINDING_LABEL, &[])
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "Failed to export QUIC keying material"))?;
+    socket.write_all(&local).await?;
+    socket.flush().await?;
+
+    let mut remote = [0u8; CHANNEL_BINDING_LEN];
+    socket.read_exact(&mut remote).await?;
+    if local != remote {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "QUIC channel binding does not match the authenticated noise session",
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct QuicControl {
+    connection: quinn::Connection,
+}
+
+impl QuicControl {
+    pub async fn open_stream(&mut self) -> Result<QuicStream, ConnectionError> {
+        let (send, recv) = self
+            .connection
+            .open_bi()
+            .await
+            .map_err(|err| ConnectionError::Io(err.into()))?;
+        Ok(QuicStream { send, recv })
+    }
+
+    pub fn close(&self) {
+        self.connection.close(0u32.into(), b"");
+    }
+}
+
+/// A QUIC bidirectional stream
+#[derive(Debug)]
+pub(super) struct QuicStream {
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+impl QuicStream {
+    pub fn stream_id(&self) -> stream_id::Id {
+        // QUIC stream indexes are allocated sequentially per direction, the index will not exceed u32::MAX in practice
+        stream_id::Id::new(self.send.id().index() as u32)
+    }
+}
+
+impl AsyncRead for QuicStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.recv).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_shutdown(cx)
+    }
+}
+
+struct IncomingWorker {
+    control: Control,
+    incoming: quinn::IncomingBiStreams,
+    sender: mpsc::Sender<SubstreamInner>,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl IncomingWorker {
+    pub async fn run(mut self) {
+        loop {
+            tokio::select! {
+                biased;
+
+                _ = self.shutdown_signal.wait() => {
+                    debug!(target: LOG_TARGET, "QUIC connection shutdown");
+                    let _ = self.control.close().await;
+                    break;
+                }
+
+                result = self.incoming.next() => {
+                    match result {
+                        Some(Ok((send, recv))) => {
+                            let stream = SubstreamInner::Quic(QuicStream { send, recv });
+                            if self.sender.send(stream).await.is_err() {
+                                debug!(
+                                    target: LOG_TARGET,
+                                    "Incoming QUIC stream task is shutting down because the internal stream sender \
+                                     channel was closed"
+                                );
+                                break;
+                            }
+                        },
+                        Some(Err(err)) => {
+                            debug!(target: LOG_TARGET, "QUIC connection closed: {}", err);
+                            break;
+                        },
+                        None => {
+                            debug!(target: LOG_TARGET, "Incoming QUIC streams completed. IncomingWorker exiting");
+                            break;
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
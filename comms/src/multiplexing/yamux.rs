@@ -20,15 +20,19 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+#[cfg(feature = "quic")]
+use super::quic::{QuicControl, QuicStream};
 use crate::{
+    bandwidth::{BandwidthLimiter, ConnectionBandwidth, SubstreamMeter},
     connection_manager::ConnectionDirection,
+    protocol::ProtocolId,
     runtime,
     stream_id,
     stream_id::StreamId,
     utils::atomic_ref_counter::{AtomicRefCounter, AtomicRefCounterGuard},
 };
 use futures::{task::Context, Stream};
-use std::{future::Future, io, pin::Pin, task::Poll};
+use std::{io, pin::Pin, task::Poll};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
@@ -47,6 +51,7 @@ pub struct Yamux {
     control: Control,
     incoming: IncomingSubstreams,
     substream_counter: AtomicRefCounter,
+    bandwidth: ConnectionBandwidth,
 }
 
 const MAX_BUFFER_SIZE: u32 = 8 * 1024 * 1024; // 8MiB
@@ -70,14 +75,16 @@ impl Yamux {
         config.set_receive_window(RECEIVE_WINDOW);
 
         let substream_counter = AtomicRefCounter::new();
+        let bandwidth = ConnectionBandwidth::new();
         let connection = yamux::Connection::new(socket.compat(), config, mode);
-        let control = Control::new(connection.control(), substream_counter.clone());
-        let incoming = Self::spawn_incoming_stream_worker(connection, substream_counter.clone());
+        let control = Control::new(connection.control(), substream_counter.clone(), bandwidth.clone());
+        let incoming = Self::spawn_incoming_stream_worker(connection, substream_counter.clone(), bandwidth.clone());
 
         Ok(Self {
             control,
             incoming,
             substream_counter,
+            bandwidth,
         })
     }
 
@@ -86,6 +93,7 @@ impl Yamux {
     fn spawn_incoming_stream_worker<TSocket>(
         connection: yamux::Connection<TSocket>,
         counter: AtomicRefCounter,
+        bandwidth: ConnectionBandwidth,
     ) -> IncomingSubstreams
     where
         TSocket: futures::AsyncRead + futures::AsyncWrite + Unpin + Send + 'static,
@@ -94,7 +102,7 @@ impl Yamux {
         let (incoming_tx, incoming_rx) = mpsc::channel(10);
         let incoming = IncomingWorker::new(connection, incoming_tx, shutdown.to_signal());
         runtime::task::spawn(incoming.run());
-        IncomingSubstreams::new(incoming_rx, counter, shutdown)
+        IncomingSubstreams::new(incoming_rx, counter, bandwidth, shutdown)
     }
 
     /// Get the yamux control struct
@@ -121,19 +129,63 @@ impl Yamux {
     pub(crate) fn substream_counter(&self) -> AtomicRefCounter {
         self.substream_counter.clone()
     }
+
+    /// Return the bandwidth counters for this connection
+    pub(crate) fn bandwidth(&self) -> ConnectionBandwidth {
+        self.bandwidth.clone()
+    }
+
+    /// Create a multiplexed connection from a control and incoming substreams that are not provided by yamux (e.g.
+    /// QUIC streams)
+    #[cfg(feature = "quic")]
+    pub(super) fn from_parts(
+        control: Control,
+        incoming: IncomingSubstreams,
+        substream_counter: AtomicRefCounter,
+        bandwidth: ConnectionBandwidth,
+    ) -> Self {
+        Self {
+            control,
+            incoming,
+            substream_counter,
+            bandwidth,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Control {
-    inner: yamux::Control,
+    inner: ControlInner,
     substream_counter: AtomicRefCounter,
+    bandwidth: ConnectionBandwidth,
+}
+
+#[derive(Clone)]
+enum ControlInner {
+    Yamux(yamux::Control),
+    #[cfg(feature = "quic")]
+    Quic(QuicControl),
 }
 
 impl Control {
-    pub fn new(inner: yamux::Control, substream_counter: AtomicRefCounter) -> Self {
+    pub fn new(inner: yamux::Control, substream_counter: AtomicRefCounter, bandwidth: ConnectionBandwidth) -> Self {
         Self {
-            inner,
+            inner: ControlInner::Yamux(inner),
+            substream_counter,
+            bandwidth,
+        }
+    }
+
+    #[cfg(feature = "quic")]
+    pub(super) fn new_quic(
+        inner: QuicControl,
+        substream_counter: AtomicRefCounter,
+        bandwidth: ConnectionBandwidth,
+    ) -> Self {
+        Self {
+            inner: ControlInner::Quic(inner),
             substream_counter,
+            bandwidth,
         }
     }
 
@@ -141,16 +193,28 @@ impl Control {
     pub async fn open_stream(&mut self) -> Result<Substream, ConnectionError> {
         // Ensure that this counts as used while the substream is being opened
         let counter_guard = self.substream_counter.new_guard();
-        let stream = self.inner.open_stream().await?;
+        let stream = match &mut self.inner {
+            ControlInner::Yamux(control) => SubstreamInner::Yamux(control.open_stream().await?.compat()),
+            #[cfg(feature = "quic")]
+            ControlInner::Quic(control) => SubstreamInner::Quic(control.open_stream().await?),
+        };
         Ok(Substream {
-            stream: stream.compat(),
+            stream,
             counter_guard,
+            meter: SubstreamMeter::new(self.bandwidth.clone()),
         })
     }
 
     /// Close the connection.
-    pub fn close(&mut self) -> impl Future<Output = Result<(), ConnectionError>> + '_ {
-        self.inner.close()
+    pub async fn close(&mut self) -> Result<(), ConnectionError> {
+        match &mut self.inner {
+            ControlInner::Yamux(control) => control.close().await,
+            #[cfg(feature = "quic")]
+            ControlInner::Quic(control) => {
+                control.close();
+                Ok(())
+            },
+        }
     }
 
     pub fn substream_count(&self) -> usize {
@@ -160,23 +224,30 @@ impl Control {
     pub(crate) fn substream_counter(&self) -> AtomicRefCounter {
         self.substream_counter.clone()
     }
+
+    pub(crate) fn bandwidth(&self) -> ConnectionBandwidth {
+        self.bandwidth.clone()
+    }
 }
 
 pub struct IncomingSubstreams {
-    inner: mpsc::Receiver<yamux::Stream>,
+    inner: mpsc::Receiver<SubstreamInner>,
     substream_counter: AtomicRefCounter,
+    bandwidth: ConnectionBandwidth,
     shutdown: Shutdown,
 }
 
 impl IncomingSubstreams {
-    pub(self) fn new(
-        inner: mpsc::Receiver<yamux::Stream>,
+    pub(super) fn new(
+        inner: mpsc::Receiver<SubstreamInner>,
         substream_counter: AtomicRefCounter,
+        bandwidth: ConnectionBandwidth,
         shutdown: Shutdown,
     ) -> Self {
         Self {
             inner,
             substream_counter,
+            bandwidth,
             shutdown,
         }
     }
@@ -192,8 +263,9 @@ impl Stream for IncomingSubstreams {
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         match futures::ready!(Pin::new(&mut self.inner).poll_recv(cx)) {
             Some(stream) => Poll::Ready(Some(Substream {
-                stream: stream.compat(),
+                stream,
                 counter_guard: self.substream_counter.new_guard(),
+                meter: SubstreamMeter::new(self.bandwidth.clone()),
             })),
             None => Poll::Ready(None),
         }
@@ -208,25 +280,62 @@ impl Drop for IncomingSubstreams {
 
 #[derive(Debug)]
 pub struct Substream {
-    stream: Compat<yamux::Stream>,
+    stream: SubstreamInner,
     counter_guard: AtomicRefCounterGuard,
+    meter: SubstreamMeter,
+}
+
+impl Substream {
+    /// Count the bytes subsequently transferred on this substream towards the given protocol and apply the bandwidth
+    /// limits for the protocol
+    pub(crate) fn set_protocol(&mut self, protocol: &ProtocolId, limiter: &BandwidthLimiter) {
+        self.meter.set_protocol(protocol, limiter);
+    }
+}
+
+#[derive(Debug)]
+pub(super) enum SubstreamInner {
+    Yamux(Compat<yamux::Stream>),
+    #[cfg(feature = "quic")]
+    Quic(QuicStream),
 }
 
 impl StreamId for Substream {
     fn stream_id(&self) -> stream_id::Id {
-        self.stream.get_ref().id().into()
+        match &self.stream {
+            SubstreamInner::Yamux(stream) => stream.get_ref().id().into(),
+            #[cfg(feature = "quic")]
+            SubstreamInner::Quic(stream) => stream.stream_id(),
+        }
     }
 }
 
 impl tokio::io::AsyncRead for Substream {
     fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.stream).poll_read(cx, buf)
+        let this = &mut *self;
+        let capacity = futures::ready!(this.meter.poll_download_capacity(cx, buf.remaining()));
+        let filled_len = buf.filled().len();
+        if capacity < buf.remaining() {
+            // Read into a smaller buffer so that no more than the throttled capacity is read
+            let mut limited_buf = vec![0u8; capacity];
+            let mut limited = ReadBuf::new(&mut limited_buf);
+            futures::ready!(Pin::new(&mut this.stream).poll_read(cx, &mut limited))?;
+            buf.put_slice(limited.filled());
+        } else {
+            futures::ready!(Pin::new(&mut this.stream).poll_read(cx, buf))?;
+        }
+        this.meter.record_received(buf.filled().len() - filled_len);
+        Poll::Ready(Ok(()))
     }
 }
 
 impl tokio::io::AsyncWrite for Substream {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.stream).poll_write(cx, buf)
+        let this = &mut *self;
+        let capacity = futures::ready!(this.meter.poll_upload_capacity(cx, buf.len()));
+        let num_written = futures::ready!(Pin::new(&mut this.stream).poll_write(cx, &buf[..capacity]))?;
+        this.meter.record_sent(num_written);
+        Poll::Ready(Ok(num_written))
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
@@ -238,6 +347,42 @@ impl tokio::io::AsyncWrite for Substream {
     }
 }
 
+impl tokio::io::AsyncRead for SubstreamInner {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            SubstreamInner::Yamux(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(feature = "quic")]
+            SubstreamInner::Quic(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl tokio::io::AsyncWrite for SubstreamInner {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            SubstreamInner::Yamux(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(feature = "quic")]
+            SubstreamInner::Quic(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            SubstreamInner::Yamux(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(feature = "quic")]
+            SubstreamInner::Quic(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            SubstreamInner::Yamux(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(feature = "quic")]
+            SubstreamInner::Quic(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
 impl From<yamux::StreamId> for stream_id::Id {
     fn from(id: yamux::StreamId) -> Self {
         stream_id::Id::new(id.val())
@@ -246,7 +391,7 @@ impl From<yamux::StreamId> for stream_id::Id {
 
 struct IncomingWorker<TSocket> {
     connection: yamux::Connection<TSocket>,
-    sender: mpsc::Sender<yamux::Stream>,
+    sender: mpsc::Sender<SubstreamInner>,
     shutdown_signal: ShutdownSignal,
 }
 
@@ -255,7 +400,7 @@ where TSocket: futures::AsyncRead + futures::AsyncWrite + Unpin + Send + 'static
 {
     pub fn new(
         connection: yamux::Connection<TSocket>,
-        sender: mpsc::Sender<yamux::Stream>,
+        sender: mpsc::Sender<SubstreamInner>,
         shutdown_signal: ShutdownSignal,
     ) -> Self {
         Self {
@@ -287,7 +432,7 @@ where TSocket: futures::AsyncRead + futures::AsyncWrite + Unpin + Send + 'static
                      match result {
                         Ok(Some(stream)) => {
                             event!(Level::TRACE, "yamux::incoming_worker::new_stream {}", stream);
-                            if self.sender.send(stream).await.is_err() {
+                            if self.sender.send(SubstreamInner::Yamux(stream.compat())).await.is_err() {
                                 debug!(
                                     target: LOG_TARGET,
                                     "{} Incoming peer substream task is shutting down because the internal stream sender channel \
@@ -332,9 +477,11 @@ where TSocket: futures::AsyncRead + futures::AsyncWrite + Unpin + Send + 'static
 #[cfg(test)]
 mod test {
     use crate::{
+        bandwidth::{BandwidthConfig, BandwidthLimit, BandwidthLimiter},
         connection_manager::ConnectionDirection,
         memsocket::MemorySocket,
         multiplexing::yamux::Yamux,
+        protocol::ProtocolId,
         runtime,
         runtime::task,
     };
@@ -413,6 +560,49 @@ mod test {
         assert_eq!(listener.substream_count(), 0);
     }
 
+    #[runtime::test]
+    async fn bandwidth_accounting() -> io::Result<()> {
+        let (dialer, listener) = MemorySocket::new_pair();
+        let protocol = ProtocolId::from_static(b"t/test/1");
+        let msg = b"Oathbringer";
+
+        let dialer = Yamux::upgrade_connection(dialer, ConnectionDirection::Outbound).await?;
+        let mut dialer_control = dialer.get_yamux_control();
+        let dialer_bandwidth = dialer.bandwidth();
+
+        let limiter = BandwidthLimiter::new(&BandwidthConfig {
+            global: BandwidthLimit::new(Some(1024), Some(1024)),
+            ..Default::default()
+        });
+        let out_protocol = protocol.clone();
+        task::spawn(async move {
+            let mut substream = dialer_control.open_stream().await.unwrap();
+            substream.set_protocol(&out_protocol, &limiter);
+            substream.write_all(msg).await.unwrap();
+            substream.flush().await.unwrap();
+            substream.shutdown().await.unwrap();
+        });
+
+        let listener = Yamux::upgrade_connection(listener, ConnectionDirection::Inbound).await?;
+        let listener_bandwidth = listener.bandwidth();
+        let mut incoming = listener.into_incoming();
+        let mut substream = incoming.next().await.unwrap();
+        substream.set_protocol(&protocol, &BandwidthLimiter::unlimited());
+
+        let mut buf = Vec::new();
+        substream.read_to_end(&mut buf).await?;
+        assert_eq!(buf, msg);
+
+        let stats = dialer_bandwidth.stats();
+        assert_eq!(stats.total.bytes_sent, msg.len() as u64);
+        assert_eq!(stats.protocols[&protocol].bytes_sent, msg.len() as u64);
+        let stats = listener_bandwidth.stats();
+        assert_eq!(stats.total.bytes_received, msg.len() as u64);
+        assert_eq!(stats.protocols[&protocol].bytes_received, msg.len() as u64);
+
+        Ok(())
+    }
+
     #[runtime::test]
     async fn close() -> io::Result<()> {
         let (dialer, listener) = MemorySocket::new_pair();
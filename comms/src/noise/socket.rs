@@ -156,6 +156,17 @@ impl<TSocket> NoiseSocket<TSocket> {
         self.get_remote_static()
             .and_then(|s| CommsPublicKey::from_bytes(s).ok())
     }
+
+    /// Returns a reference to the underlying socket
+    pub fn get_ref(&self) -> &TSocket {
+        &self.socket
+    }
+
+    /// Consumes the NoiseSocket and returns the underlying socket. Any buffered data that has not been read is
+    /// discarded.
+    pub fn into_inner(self) -> TSocket {
+        self.socket
+    }
 }
 
 fn poll_write_all<TSocket>(
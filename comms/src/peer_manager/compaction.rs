@@ -0,0 +1,79 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::peer_manager::{Peer, PeerFlags, ReputationConfig};
+use chrono::NaiveDateTime;
+use std::time::Duration;
+
+/// Configuration for the periodic compaction of the peer database. Compaction evicts long-inactive, low-value peers
+/// so that the peer database does not grow without bound.
+#[derive(Debug, Clone, Copy)]
+pub struct PeerCompactionConfig {
+    /// True if periodic compaction is enabled, otherwise false (default: true)
+    pub is_enabled: bool,
+    /// The interval between compactions.
+    /// Default: 6 hours
+    pub interval: Duration,
+    /// Peers that have not connected, or been added, within this period may be evicted.
+    /// Default: 7 days
+    pub min_inactive_age: Duration,
+    /// Peers with a reputation score at or above this value are never evicted.
+    /// Default: 1
+    pub min_retained_score: f64,
+}
+
+impl PeerCompactionConfig {
+    /// Returns true if the peer may be evicted from the peer database. Seed peers, banned peers (so that the ban is not
+    /// forgotten), peers with a good reputation and recently active peers are always retained.
+    pub fn is_evictable(&self, peer: &Peer, reputation: &ReputationConfig, now: NaiveDateTime) -> bool {
+        if peer.flags.contains(PeerFlags::SEED) || peer.is_banned() {
+            return false;
+        }
+
+        if peer.reputation.score_at(now, reputation.decay_half_life) >= self.min_retained_score {
+            return false;
+        }
+
+        let last_active = peer
+            .connection_stats
+            .last_connected_at
+            .map(|last_connected_at| last_connected_at.max(peer.added_at))
+            .unwrap_or(peer.added_at);
+
+        (now - last_active)
+            .to_std()
+            .map(|inactive_for| inactive_for >= self.min_inactive_age)
+            // last_active is in the future
+            .unwrap_or(false)
+    }
+}
+
+impl Default for PeerCompactionConfig {
+    fn default() -> Self {
+        Self {
+            is_enabled: true,
+            interval: Duration::from_secs(6 * 60 * 60),
+            min_inactive_age: Duration::from_secs(7 * 24 * 60 * 60),
+            min_retained_score: 1.0,
+        }
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Peer database encryption
+//!
+//! The peer database may be encrypted at rest by providing a [ValueCipher](tari_storage::ValueCipher) to the
+//! [LMDBWrapper](tari_storage::LMDBWrapper) passed to the comms builder. Only the peer records are encrypted, the
+//! peer keys and the migration version remain in the clear.
+//!
+//! Existing plaintext records are encrypted in place when the database is first opened with a cipher. Because
+//! migrations operate on plaintext records, an encrypted database is decrypted in place before a pending migration is
+//! run and re-encrypted afterwards. Records that can neither be read nor decrypted (e.g. they were encrypted with a
+//! previous key) are removed, since peers can always be rediscovered.
+
+use crate::peer_manager::{migrations::MIGRATION_VERSION_KEY, Peer, PeerId, PeerManagerError};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    Aes256Gcm,
+};
+use log::*;
+use rand::{rngs::OsRng, RngCore};
+use std::sync::Arc;
+use tari_storage::{
+    lmdb_store::LMDBDatabase,
+    IterationResult,
+    KeyValStoreError,
+    KeyValueStore,
+    LMDBWrapper,
+    ValueCipher,
+};
+
+const LOG_TARGET: &str = "comms::peer_manager::encryption";
+
+const NONCE_BYTES: usize = 12;
+pub const PEER_DATABASE_KEY_BYTES: usize = 32;
+
+/// AES-256-GCM cipher for peer records. A random nonce is generated for each record and prepended to the ciphertext.
+#[derive(Clone)]
+pub struct PeerDatabaseCipher {
+    cipher: Aes256Gcm,
+}
+
+impl PeerDatabaseCipher {
+    pub fn new(cipher: Aes256Gcm) -> Self {
+        Self { cipher }
+    }
+
+    pub fn from_key(key: &[u8; PEER_DATABASE_KEY_BYTES]) -> Self {
+        Self::new(Aes256Gcm::new(GenericArray::from_slice(key)))
+    }
+}
+
+impl ValueCipher for PeerDatabaseCipher {
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, KeyValStoreError> {
+        let mut nonce = [0u8; NONCE_BYTES];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(GenericArray::from_slice(&nonce), plaintext)
+            .map_err(|err| KeyValStoreError::EncryptionError(err.to_string()))?;
+        let mut buf = Vec::with_capacity(NONCE_BYTES + ciphertext.len());
+        buf.extend_from_slice(&nonce);
+        buf.extend(ciphertext);
+        Ok(buf)
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, KeyValStoreError> {
+        if ciphertext.len() < NONCE_BYTES {
+            return Err(KeyValStoreError::DecryptionError(
+                "Ciphertext is shorter than the nonce".to_string(),
+            ));
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_BYTES);
+        self.cipher
+            .decrypt(GenericArray::from_slice(nonce), ciphertext)
+            .map_err(|err| KeyValStoreError::DecryptionError(err.to_string()))
+    }
+}
+
+/// Encrypts all plaintext peer records in place, returning the number of records that were encrypted. Records that
+/// are already encrypted with the given cipher are left as is.
+pub fn encrypt_records(database: Arc<LMDBDatabase>, cipher: Arc<dyn ValueCipher>) -> Result<usize, PeerManagerError> {
+    let plaintext = LMDBWrapper::<PeerId, Peer>::new(database.clone());
+    let encrypted = LMDBWrapper::<PeerId, Peer>::new(database.clone()).with_cipher(cipher);
+    let mut num_encrypted = 0;
+    for key in record_keys(&database)? {
+        match read_record(&plaintext, &encrypted, &key) {
+            Some(RecordState::Plaintext(peer)) => {
+                encrypted.insert(key, peer)?;
+                num_encrypted += 1;
+            },
+            Some(RecordState::Unreadable(err)) => remove_unreadable(&database, key, err)?,
+            Some(RecordState::Encrypted(_)) | None => {},
+        }
+    }
+    debug!(target: LOG_TARGET, "Encrypted {} peer record(s)", num_encrypted);
+    Ok(num_encrypted)
+}
+
+/// Decrypts all encrypted peer records in place, returning the number of records that were decrypted.
+pub fn decrypt_records(database: Arc<LMDBDatabase>, cipher: Arc<dyn ValueCipher>) -> Result<usize, PeerManagerError> {
+    let plaintext = LMDBWrapper::<PeerId, Peer>::new(database.clone());
+    let encrypted = LMDBWrapper::<PeerId, Peer>::new(database.clone()).with_cipher(cipher);
+    let mut num_decrypted = 0;
+    for key in record_keys(&database)? {
+        match read_record(&plaintext, &encrypted, &key) {
+            Some(RecordState::Encrypted(peer)) => {
+                plaintext.insert(key, peer)?;
+                num_decrypted += 1;
+            },
+            Some(RecordState::Unreadable(err)) => remove_unreadable(&database, key, err)?,
+            Some(RecordState::Plaintext(_)) | None => {},
+        }
+    }
+    debug!(target: LOG_TARGET, "Decrypted {} peer record(s)", num_decrypted);
+    Ok(num_decrypted)
+}
+
+enum RecordState {
+    Plaintext(Peer),
+    Encrypted(Peer),
+    /// The record is neither plaintext nor decryptable with the cipher, most likely because it was encrypted with a
+    /// different key
+    Unreadable(KeyValStoreError),
+}
+
+fn remove_unreadable(database: &LMDBDatabase, key: PeerId, err: KeyValStoreError) -> Result<(), PeerManagerError> {
+    warn!(
+        target: LOG_TARGET,
+        "Removing peer record that could not be decrypted ({}). Was the peer database encrypted with a different key?",
+        err
+    );
+    database.remove(&key).map_err(KeyValStoreError::from)?;
+    Ok(())
+}
+
+fn record_keys(database: &LMDBDatabase) -> Result<Vec<PeerId>, PeerManagerError> {
+    let mut keys = Vec::new();
+    // Deserializing the value as () reads none of the value bytes, so this yields every key regardless of whether the
+    // record is encrypted
+    database
+        .for_each::<PeerId, (), _>(|pair| {
+            if let Ok((key, _)) = pair {
+                if key != MIGRATION_VERSION_KEY {
+                    keys.push(key);
+                }
+            }
+            IterationResult::Continue
+        })
+        .map_err(KeyValStoreError::from)?;
+    Ok(keys)
+}
+
+fn read_record(
+    plaintext: &LMDBWrapper<PeerId, Peer>,
+    encrypted: &LMDBWrapper<PeerId, Peer>,
+    key: &PeerId,
+) -> Option<RecordState> {
+    // The cipher is authenticated so a record that decrypts is definitely encrypted. Check that first.
+    let decrypt_err = match encrypted.get(key) {
+        Ok(peer) => return peer.map(RecordState::Encrypted),
+        Err(err) => err,
+    };
+    match plaintext.get(key) {
+        Ok(peer) => peer.map(RecordState::Plaintext),
+        Err(_) => Some(RecordState::Unreadable(decrypt_err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        net_address::MultiaddressesWithStats,
+        peer_manager::{NodeId, PeerFeatures, PeerFlags, PeerManager},
+    };
+    use multiaddr::Multiaddr;
+    use tari_crypto::{keys::PublicKey, ristretto::RistrettoPublicKey};
+    use tari_storage::lmdb_store::{LMDBBuilder, LMDBConfig};
+    use tari_test_utils::{paths::create_temporary_data_path, random};
+
+    fn create_database() -> Arc<LMDBDatabase> {
+        let database_name = random::string(8);
+        let datastore = LMDBBuilder::new()
+            .set_path(create_temporary_data_path())
+            .set_env_config(LMDBConfig::default())
+            .set_max_number_of_databases(1)
+            .add_database(&database_name, lmdb_zero::db::CREATE)
+            .build()
+            .unwrap();
+        Arc::new(datastore.get_handle(&database_name).unwrap())
+    }
+
+    fn create_test_peer() -> Peer {
+        let (_sk, pk) = RistrettoPublicKey::random_keypair(&mut OsRng);
+        let node_id = NodeId::from_key(&pk);
+        Peer::new(
+            pk,
+            node_id,
+            MultiaddressesWithStats::from("/ip4/1.2.3.4/tcp/8000".parse::<Multiaddr>().unwrap()),
+            PeerFlags::default(),
+            PeerFeatures::COMMUNICATION_NODE,
+            Default::default(),
+            Default::default(),
+        )
+    }
+
+    fn create_cipher(key: u8) -> Arc<dyn ValueCipher> {
+        Arc::new(PeerDatabaseCipher::from_key(&[key; PEER_DATABASE_KEY_BYTES]))
+    }
+
+    #[test]
+    fn it_encrypts_and_decrypts() {
+        let cipher = PeerDatabaseCipher::from_key(&[1u8; PEER_DATABASE_KEY_BYTES]);
+        let ciphertext = cipher.encrypt(b"peer").unwrap();
+        assert_ne!(&ciphertext[NONCE_BYTES..], b"peer");
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), b"peer");
+        // A fresh nonce is used each time
+        assert_ne!(cipher.encrypt(b"peer").unwrap(), ciphertext);
+
+        let wrong_key = PeerDatabaseCipher::from_key(&[2u8; PEER_DATABASE_KEY_BYTES]);
+        assert!(wrong_key.decrypt(&ciphertext).is_err());
+        assert!(cipher.decrypt(&ciphertext[..NONCE_BYTES - 1]).is_err());
+    }
+
+    #[test]
+    fn it_encrypts_and_decrypts_records_in_place() {
+        let database = create_database();
+        let cipher = create_cipher(1);
+        let plaintext = LMDBWrapper::<PeerId, Peer>::new(database.clone());
+        let encrypted = LMDBWrapper::<PeerId, Peer>::new(database.clone()).with_cipher(cipher.clone());
+        let wrong_key = LMDBWrapper::<PeerId, Peer>::new(database.clone()).with_cipher(create_cipher(2));
+        let peers = (0..3).map(|_| create_test_peer()).collect::<Vec<_>>();
+        for (key, peer) in peers.iter().enumerate() {
+            plaintext.insert(key as PeerId, peer.clone()).unwrap();
+        }
+
+        assert_eq!(encrypt_records(database.clone(), cipher.clone()).unwrap(), 3);
+        // Records that are already encrypted are left as is
+        assert_eq!(encrypt_records(database.clone(), cipher.clone()).unwrap(), 0);
+        for (key, peer) in peers.iter().enumerate() {
+            let key = key as PeerId;
+            assert_eq!(encrypted.get(&key).unwrap().unwrap().node_id, peer.node_id);
+            assert!(wrong_key.get(&key).is_err());
+        }
+
+        assert_eq!(decrypt_records(database.clone(), cipher.clone()).unwrap(), 3);
+        assert_eq!(decrypt_records(database, cipher).unwrap(), 0);
+        for (key, peer) in peers.iter().enumerate() {
+            let key = key as PeerId;
+            assert_eq!(plaintext.get(&key).unwrap().unwrap().node_id, peer.node_id);
+            assert!(wrong_key.get(&key).is_err());
+        }
+    }
+
+    #[test]
+    fn it_removes_records_encrypted_with_another_key() {
+        let database = create_database();
+        let plaintext = LMDBWrapper::<PeerId, Peer>::new(database.clone());
+        plaintext.insert(1, create_test_peer()).unwrap();
+        assert_eq!(encrypt_records(database.clone(), create_cipher(1)).unwrap(), 1);
+
+        assert_eq!(encrypt_records(database.clone(), create_cipher(2)).unwrap(), 0);
+        assert_eq!(database.len().unwrap(), 0);
+
+        plaintext.insert(1, create_test_peer()).unwrap();
+        assert_eq!(encrypt_records(database.clone(), create_cipher(1)).unwrap(), 1);
+        assert_eq!(decrypt_records(database.clone(), create_cipher(2)).unwrap(), 0);
+        assert_eq!(database.len().unwrap(), 0);
+    }
+
+    #[test]
+    fn it_migrates_an_encrypted_database() {
+        let database = create_database();
+        let cipher = create_cipher(1);
+        let encrypted = LMDBWrapper::<PeerId, Peer>::new(database.clone()).with_cipher(cipher.clone());
+        // An empty database is set to the latest migration version
+        PeerManager::migrate_lmdb(&database).unwrap();
+        let latest_version = database.get::<_, u32>(&MIGRATION_VERSION_KEY).unwrap().unwrap();
+
+        let peer = create_test_peer();
+        LMDBWrapper::<PeerId, Peer>::new(database.clone())
+            .insert(1, peer.clone())
+            .unwrap();
+        PeerManager::migrate_lmdb_encrypted(database.clone(), cipher.clone()).unwrap();
+        assert_eq!(encrypted.get(&1).unwrap().unwrap().node_id, peer.node_id);
+
+        // A pending migration is run on the decrypted records, which are encrypted again afterwards
+        database.insert(&MIGRATION_VERSION_KEY, &(latest_version - 1)).unwrap();
+        PeerManager::migrate_lmdb_encrypted(database.clone(), cipher).unwrap();
+        assert_eq!(
+            database.get::<_, u32>(&MIGRATION_VERSION_KEY).unwrap(),
+            Some(latest_version)
+        );
+        assert_eq!(encrypted.get(&1).unwrap().unwrap().node_id, peer.node_id);
+        assert_eq!(database.len().unwrap(), 2);
+    }
+}
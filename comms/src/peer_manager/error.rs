@@ -34,6 +34,8 @@ pub enum PeerManagerError {
     DatabaseError(#[from] KeyValStoreError),
     #[error("An error occurred while migrating the database: {0}")]
     MigrationError(String),
+    #[error("Unsupported peer export version {0}")]
+    UnsupportedExportVersion(u32),
 }
 
 impl PeerManagerError {
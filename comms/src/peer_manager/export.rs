@@ -0,0 +1,87 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags},
+    types::CommsPublicKey,
+};
+use chrono::{NaiveDateTime, Utc};
+use multiaddr::Multiaddr;
+use serde::{Deserialize, Serialize};
+
+/// The current version of the peer export format
+pub const PEER_EXPORT_VERSION: u32 = 1;
+
+/// A portable list of peers that can be used to seed the peer database of another node. Only the information required
+/// to contact a peer is exported. Connection statistics, bans and reputation are local to a node and are not exported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerExport {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub peers: Vec<ExportedPeer>,
+}
+
+impl PeerExport {
+    pub fn new(peers: Vec<ExportedPeer>) -> Self {
+        Self {
+            version: PEER_EXPORT_VERSION,
+            exported_at: Utc::now().naive_utc(),
+            peers,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedPeer {
+    pub public_key: CommsPublicKey,
+    pub addresses: Vec<Multiaddr>,
+    pub features: PeerFeatures,
+    #[serde(default)]
+    pub user_agent: String,
+}
+
+impl From<&Peer> for ExportedPeer {
+    fn from(peer: &Peer) -> Self {
+        Self {
+            public_key: peer.public_key.clone(),
+            addresses: peer.addresses.iter().cloned().collect(),
+            features: peer.features,
+            user_agent: peer.user_agent.clone(),
+        }
+    }
+}
+
+impl From<ExportedPeer> for Peer {
+    fn from(peer: ExportedPeer) -> Self {
+        // The NodeId is always derived from the public key rather than trusted from the export
+        let node_id = NodeId::from_public_key(&peer.public_key);
+        Peer::new(
+            peer.public_key,
+            node_id,
+            peer.addresses.into(),
+            PeerFlags::NONE,
+            peer.features,
+            Default::default(),
+            peer.user_agent,
+        )
+    }
+}
@@ -21,24 +21,33 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    bandwidth::BandwidthStats,
     peer_manager::{
+        encryption,
         migrations,
         node_id::{NodeDistance, NodeId},
         peer::{Peer, PeerFlags},
         peer_id::PeerId,
         peer_storage::PeerStorage,
+        reputation::{GoodBehaviour, Misbehaviour, PeerScore, ReputationChange, ReputationConfig},
         wrapper::KeyValueWrapper,
+        PeerCompactionConfig,
+        PeerExport,
         PeerFeatures,
         PeerManagerError,
         PeerQuery,
+        PEER_EXPORT_VERSION,
     },
     types::{CommsDatabase, CommsPublicKey},
 };
+use log::*;
 use multiaddr::Multiaddr;
-use std::{fmt, fs::File, time::Duration};
-use tari_storage::{lmdb_store::LMDBDatabase, IterationResult};
+use std::{cmp::Ordering, fmt, fs::File, sync::Arc, time::Duration};
+use tari_storage::{lmdb_store::LMDBDatabase, IterationResult, ValueCipher};
 use tokio::sync::RwLock;
 
+const LOG_TARGET: &str = "comms::peer_manager::manager";
+
 /// The PeerManager consist of a routing table of previously discovered peers.
 /// It also provides functionality to add, find and delete peers.
 pub struct PeerManager {
@@ -61,6 +70,25 @@ impl PeerManager {
         migrations::migrate(database).map_err(|err| PeerManagerError::MigrationError(err.to_string()))
     }
 
+    /// Migrate an encrypted peer database and encrypt any plaintext records. Encrypted records are decrypted in place
+    /// if a migration is pending, since migrations operate on plaintext records.
+    pub fn migrate_lmdb_encrypted(
+        database: Arc<LMDBDatabase>,
+        cipher: Arc<dyn ValueCipher>,
+    ) -> Result<(), PeerManagerError> {
+        let is_migration_required = migrations::is_migration_required(&database)
+            .map_err(|err| PeerManagerError::MigrationError(err.to_string()))?;
+        if is_migration_required {
+            encryption::decrypt_records(database.clone(), cipher.clone())?;
+            Self::migrate_lmdb(&database)?;
+        }
+        let num_encrypted = encryption::encrypt_records(database, cipher)?;
+        if num_encrypted > 0 {
+            info!(target: LOG_TARGET, "Encrypted {} peer record(s)", num_encrypted);
+        }
+        Ok(())
+    }
+
     pub async fn count(&self) -> usize {
         self.peer_storage.read().await.count()
     }
@@ -255,6 +283,82 @@ impl PeerManager {
             .ban_peer_by_node_id(node_id, duration, reason)
     }
 
+    /// Reduces the reputation score of the peer by the penalty for the given misbehaviour. The peer is banned if its
+    /// score falls to the ban threshold.
+    pub async fn report_misbehaviour(
+        &self,
+        node_id: &NodeId,
+        misbehaviour: Misbehaviour,
+        reason: String,
+        config: &ReputationConfig,
+    ) -> Result<ReputationChange, PeerManagerError> {
+        self.peer_storage.write().await.adjust_reputation(
+            node_id,
+            -misbehaviour.penalty(),
+            format!("{}: {}", misbehaviour, reason),
+            config,
+        )
+    }
+
+    /// Increases the reputation score of the peer by the credit for the given good behaviour
+    pub async fn credit_peer(
+        &self,
+        node_id: &NodeId,
+        behaviour: GoodBehaviour,
+        config: &ReputationConfig,
+    ) -> Result<ReputationChange, PeerManagerError> {
+        self.peer_storage
+            .write()
+            .await
+            .adjust_reputation(node_id, behaviour.credit(), behaviour.to_string(), config)
+    }
+
+    /// Returns the current reputation scores of all peers that have been scored, highest score first
+    pub async fn reputation_scores(&self, config: &ReputationConfig) -> Result<Vec<PeerScore>, PeerManagerError> {
+        let query = PeerQuery::new().select_where(|peer| peer.reputation.has_history());
+        let mut scores = self
+            .perform_query(query)
+            .await?
+            .iter()
+            .map(|peer| PeerScore::from_peer(peer, config))
+            .collect::<Vec<_>>();
+        scores.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        Ok(scores)
+    }
+
+    /// Evicts long-inactive, low-value peers from the peer database, excluding the given peers. Returns the number of
+    /// evicted peers.
+    pub async fn compact(
+        &self,
+        config: &PeerCompactionConfig,
+        reputation: &ReputationConfig,
+        exclude_peers: &[NodeId],
+    ) -> Result<usize, PeerManagerError> {
+        self.peer_storage
+            .write()
+            .await
+            .compact(config, reputation, exclude_peers)
+    }
+
+    /// Exports all peers that are not banned to the portable peer export format
+    pub async fn export_peers(&self) -> Result<PeerExport, PeerManagerError> {
+        let peers = self.peer_storage.read().await.export_peers()?;
+        Ok(PeerExport::new(peers))
+    }
+
+    /// Imports peers from the portable peer export format. Returns the number of new peers.
+    pub async fn import_peers(&self, export: PeerExport) -> Result<usize, PeerManagerError> {
+        if export.version > PEER_EXPORT_VERSION {
+            return Err(PeerManagerError::UnsupportedExportVersion(export.version));
+        }
+        self.peer_storage.write().await.import_peers(export.peers)
+    }
+
+    /// Adds the bandwidth used by a closed connection to the bandwidth totals of the peer
+    pub async fn record_bandwidth(&self, node_id: &NodeId, bandwidth: &BandwidthStats) -> Result<(), PeerManagerError> {
+        self.peer_storage.write().await.record_bandwidth(node_id, bandwidth)
+    }
+
     /// Changes the offline flag bit of the peer. Return the previous offline state.
     pub async fn set_offline(&self, node_id: &NodeId, is_offline: bool) -> Result<bool, PeerManagerError> {
         self.peer_storage.write().await.set_offline(node_id, is_offline)
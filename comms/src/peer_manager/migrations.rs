@@ -23,6 +23,8 @@
 mod v1;
 mod v2;
 mod v3;
+mod v4;
+mod v5;
 
 use log::*;
 use tari_storage::lmdb_store::{LMDBDatabase, LMDBError};
@@ -31,12 +33,27 @@ const LOG_TARGET: &str = "comms::peer_manager::migrations";
 
 pub(super) const MIGRATION_VERSION_KEY: u64 = std::u64::MAX;
 
-pub fn migrate(database: &LMDBDatabase) -> Result<(), LMDBError> {
-    let migrations = vec![
+fn migrations() -> Vec<Box<dyn Migration<LMDBDatabase, Error = LMDBError>>> {
+    vec![
         v1::MigrationV1.boxed(),
         v2::MigrationV2.boxed(),
         v3::MigrationV3.boxed(),
-    ];
+        v4::MigrationV4.boxed(),
+        v5::MigrationV5.boxed(),
+    ]
+}
+
+/// Returns true if the database contains records that have not been migrated to the latest version
+pub fn is_migration_required(database: &LMDBDatabase) -> Result<bool, LMDBError> {
+    if database.len()? == 0 {
+        return Ok(false);
+    }
+    let version = database.get::<_, u32>(&MIGRATION_VERSION_KEY)?.unwrap_or(0);
+    Ok(version != migrations().len() as u32)
+}
+
+pub fn migrate(database: &LMDBDatabase) -> Result<(), LMDBError> {
+    let migrations = migrations();
 
     // If the database is empty there is nothing to migrate, so set it to the latest version
     if database.len()? == 0 {
@@ -70,11 +70,20 @@
 //! let returned_peer = peer_manager.find_by_node_id(&node_id).unwrap();
 //! ```
 
+mod compaction;
+pub use compaction::PeerCompactionConfig;
+
 mod connection_stats;
 
 mod error;
 pub use error::PeerManagerError;
 
+mod encryption;
+pub use encryption::{PeerDatabaseCipher, PEER_DATABASE_KEY_BYTES};
+
+mod export;
+pub use export::{ExportedPeer, PeerExport, PEER_EXPORT_VERSION};
+
 pub mod node_id;
 pub use node_id::NodeId;
 
@@ -99,6 +108,9 @@ pub use peer_query::{PeerQuery, PeerQuerySortBy};
 mod peer_storage;
 pub use peer_storage::PeerStorage;
 
+mod reputation;
+pub use reputation::{GoodBehaviour, Misbehaviour, PeerReputation, PeerScore, ReputationChange, ReputationConfig};
+
 mod migrations;
 
 mod wrapper;
@@ -24,9 +24,11 @@ use super::{
     connection_stats::PeerConnectionStats,
     node_id::{deserialize_node_id_from_hex, NodeId},
     peer_id::PeerId,
+    reputation::PeerReputation,
     PeerFeatures,
 };
 use crate::{
+    bandwidth::BandwidthStats,
     net_address::MultiaddressesWithStats,
     protocol::ProtocolId,
     types::CommsPublicKey,
@@ -48,6 +50,8 @@ bitflags! {
     #[derive(Default, Deserialize, Serialize)]
     pub struct PeerFlags: u8 {
         const NONE = 0x00;
+        /// The peer was added from the configured seed peers
+        const SEED = 0x01;
     }
 }
 
@@ -91,6 +95,10 @@ pub struct Peer {
     /// Metadata field. This field is for use by upstream clients to record extra info about a peer.
     /// We use a hashmap here so that we can use more than one "info set"
     pub metadata: HashMap<u8, Vec<u8>>,
+    /// Reputation score of the peer, adjusted by reported misbehaviour and good behaviour
+    pub reputation: PeerReputation,
+    /// Total bytes sent to and received from the peer over all closed connections, in total and for each protocol
+    pub bandwidth: BandwidthStats,
 }
 
 impl Peer {
@@ -119,6 +127,8 @@ impl Peer {
             supported_protocols,
             user_agent,
             metadata: HashMap::new(),
+            reputation: Default::default(),
+            bandwidth: Default::default(),
         }
     }
 
@@ -21,10 +21,14 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    bandwidth::BandwidthStats,
     peer_manager::{
         node_id::{NodeDistance, NodeId},
         peer::{Peer, PeerFlags},
         peer_id::{generate_peer_key, PeerId},
+        reputation::{ReputationChange, ReputationConfig},
+        ExportedPeer,
+        PeerCompactionConfig,
         PeerFeatures,
         PeerManagerError,
         PeerQuery,
@@ -32,10 +36,14 @@ use crate::{
     protocol::ProtocolId,
     types::{CommsDatabase, CommsPublicKey},
 };
+use chrono::Utc;
 use log::*;
 use multiaddr::Multiaddr;
 use rand::{rngs::OsRng, seq::SliceRandom};
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 use tari_crypto::tari_utilities::ByteArray;
 use tari_storage::{IterationResult, KeyValueStore};
 
@@ -69,6 +77,19 @@ where DS: KeyValueStore<PeerId, Peer>
             })
             .map_err(PeerManagerError::DatabaseError)?;
 
+        let num_unreadable = database
+            .size()
+            .map_err(PeerManagerError::DatabaseError)?
+            .saturating_sub(total_entries);
+        if num_unreadable > 0 {
+            warn!(
+                target: LOG_TARGET,
+                "{} peer record(s) could not be read. The records may be corrupt, or the peer database is encrypted \
+                 and the correct cipher was not provided.",
+                num_unreadable
+            );
+        }
+
         trace!(
             target: LOG_TARGET,
             "Peer storage is initialized. {} total entries.",
@@ -487,6 +508,62 @@ where DS: KeyValueStore<PeerId, Peer>
         Ok(node_id)
     }
 
+    /// Adds `delta` to the reputation score of the peer. If the score falls to the ban threshold, the peer is banned
+    /// for the escalating ban duration given by the config. Peers that are already banned are not banned again.
+    pub fn adjust_reputation(
+        &mut self,
+        node_id: &NodeId,
+        delta: f64,
+        reason: String,
+        config: &ReputationConfig,
+    ) -> Result<ReputationChange, PeerManagerError> {
+        let peer_key = *self
+            .node_id_index
+            .get(node_id)
+            .ok_or(PeerManagerError::PeerNotFoundError)?;
+        let mut peer: Peer = self
+            .peer_db
+            .get(&peer_key)
+            .map_err(PeerManagerError::DatabaseError)?
+            .expect("node_id_index is out of sync with peer db");
+        let now = Utc::now().naive_utc();
+        let can_ban = !peer.is_banned();
+        let banned_for = peer.reputation.adjust(delta, config, now, can_ban);
+        let score = peer.reputation.score_at(now, config.decay_half_life);
+        if let Some(duration) = banned_for {
+            peer.ban_for(
+                duration,
+                format!("Reputation score fell to {:.1}. Last offence: {}", score, reason),
+            );
+        }
+        let reputation = peer.reputation.clone();
+        self.peer_db
+            .insert(peer_key, peer)
+            .map_err(PeerManagerError::DatabaseError)?;
+        Ok(ReputationChange {
+            reputation,
+            score,
+            banned_for,
+        })
+    }
+
+    /// Adds the bandwidth used by a closed connection to the bandwidth totals of the peer
+    pub fn record_bandwidth(&mut self, node_id: &NodeId, bandwidth: &BandwidthStats) -> Result<(), PeerManagerError> {
+        let peer_key = *self
+            .node_id_index
+            .get(node_id)
+            .ok_or(PeerManagerError::PeerNotFoundError)?;
+        let mut peer: Peer = self
+            .peer_db
+            .get(&peer_key)
+            .map_err(PeerManagerError::DatabaseError)?
+            .expect("node_id_index is out of sync with peer db");
+        peer.bandwidth.merge(bandwidth);
+        self.peer_db
+            .insert(peer_key, peer)
+            .map_err(PeerManagerError::DatabaseError)
+    }
+
     /// Changes the OFFLINE flag bit of the peer.
     pub fn set_offline(&mut self, node_id: &NodeId, offline: bool) -> Result<bool, PeerManagerError> {
         let peer_key = *self
@@ -546,6 +623,75 @@ where DS: KeyValueStore<PeerId, Peer>
             .map_err(PeerManagerError::DatabaseError)?;
         Ok(result)
     }
+
+    /// Evicts peers that the compaction config considers evictable, excluding the given peers. Returns the number of
+    /// evicted peers.
+    pub fn compact(
+        &mut self,
+        config: &PeerCompactionConfig,
+        reputation: &ReputationConfig,
+        exclude_peers: &[NodeId],
+    ) -> Result<usize, PeerManagerError> {
+        let now = Utc::now().naive_utc();
+        let evicted = self
+            .peer_db
+            .filter(|(_, peer)| !exclude_peers.contains(&peer.node_id) && config.is_evictable(peer, reputation, now))
+            .map_err(PeerManagerError::DatabaseError)?
+            .into_iter()
+            .map(|(peer_key, _)| peer_key)
+            .collect::<HashSet<_>>();
+
+        for peer_key in &evicted {
+            self.peer_db.delete(peer_key).map_err(PeerManagerError::DatabaseError)?;
+        }
+        self.public_key_index.retain(|_, peer_key| !evicted.contains(peer_key));
+        self.node_id_index.retain(|_, peer_key| !evicted.contains(peer_key));
+
+        Ok(evicted.len())
+    }
+
+    /// Returns all peers that are not banned and have at least one address in the portable export format
+    pub fn export_peers(&self) -> Result<Vec<ExportedPeer>, PeerManagerError> {
+        let mut peers = Vec::new();
+        self.peer_db.for_each_ok(|(_, peer)| {
+            if !peer.is_banned() && !peer.addresses.is_empty() {
+                peers.push(ExportedPeer::from(&peer));
+            }
+            IterationResult::Continue
+        })?;
+        Ok(peers)
+    }
+
+    /// Imports peers from the portable export format. The addresses of peers that already exist are merged into the
+    /// existing peer, leaving its local state (bans, reputation etc.) intact. Returns the number of new peers.
+    pub fn import_peers(&mut self, peers: Vec<ExportedPeer>) -> Result<usize, PeerManagerError> {
+        let mut num_added = 0;
+        for exported in peers {
+            if exported.addresses.is_empty() {
+                continue;
+            }
+            match self.public_key_index.get(&exported.public_key).copied() {
+                Some(peer_key) => {
+                    let mut peer: Peer = self
+                        .peer_db
+                        .get(&peer_key)
+                        .map_err(PeerManagerError::DatabaseError)?
+                        .expect("public_key_index is out of sync with peer db");
+                    for address in &exported.addresses {
+                        peer.addresses.add_net_address(address);
+                    }
+                    self.peer_db
+                        .insert(peer_key, peer)
+                        .map_err(PeerManagerError::DatabaseError)?;
+                },
+                None => {
+                    self.add_peer(exported.into())?;
+                    num_added += 1;
+                },
+            }
+        }
+        Ok(num_added)
+    }
 }
 
 #[allow(clippy::from_over_into)]
@@ -855,4 +1001,125 @@ mod test {
         let is_in_region = peer_storage.in_network_region(far_node, &main_peer_node_id, 3).unwrap();
         assert!(!is_in_region);
     }
+
+    #[test]
+    fn adjust_reputation_bans_peer() {
+        let mut peer_storage = PeerStorage::new_indexed(HashmapDatabase::new()).unwrap();
+        let peer = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, false);
+        peer_storage.add_peer(peer.clone()).unwrap();
+        let config = ReputationConfig::default();
+
+        let change = peer_storage
+            .adjust_reputation(&peer.node_id, -60.0, "first".to_string(), &config)
+            .unwrap();
+        assert!(change.banned_for.is_none());
+        assert!(!peer_storage.find_by_node_id(&peer.node_id).unwrap().is_banned());
+
+        let change = peer_storage
+            .adjust_reputation(&peer.node_id, -60.0, "second".to_string(), &config)
+            .unwrap();
+        assert_eq!(change.banned_for, Some(config.ban_duration));
+        let peer = peer_storage.find_by_node_id(&peer.node_id).unwrap();
+        assert!(peer.is_banned());
+        assert!(peer.reason_banned().contains("second"));
+        assert_eq!(peer.reputation.num_bans(), 1);
+
+        // Already banned, so the ban is not extended
+        let change = peer_storage
+            .adjust_reputation(&peer.node_id, -60.0, "third".to_string(), &config)
+            .unwrap();
+        assert!(change.banned_for.is_none());
+        assert_eq!(change.reputation.num_bans(), 1);
+    }
+
+    #[test]
+    fn record_bandwidth_accumulates_totals() {
+        use crate::bandwidth::BandwidthTotals;
+        let mut peer_storage = PeerStorage::new_indexed(HashmapDatabase::new()).unwrap();
+        let peer = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, false);
+        peer_storage.add_peer(peer.clone()).unwrap();
+        let protocol = ProtocolId::from_static(b"t/test/1");
+        let bandwidth = BandwidthStats {
+            total: BandwidthTotals::new(100, 50),
+            protocols: vec![(protocol.clone(), BandwidthTotals::new(80, 40))]
+                .into_iter()
+                .collect(),
+        };
+
+        peer_storage.record_bandwidth(&peer.node_id, &bandwidth).unwrap();
+        peer_storage.record_bandwidth(&peer.node_id, &bandwidth).unwrap();
+
+        let peer = peer_storage.find_by_node_id(&peer.node_id).unwrap();
+        assert_eq!(peer.bandwidth.total, BandwidthTotals::new(200, 100));
+        assert_eq!(peer.bandwidth.protocols[&protocol], BandwidthTotals::new(160, 80));
+    }
+
+    #[test]
+    fn compact_evicts_inactive_peers() {
+        let mut peer_storage = PeerStorage::new_indexed(HashmapDatabase::new()).unwrap();
+        let config = PeerCompactionConfig::default();
+        let reputation = ReputationConfig::default();
+        let inactive_since = Utc::now().naive_utc() - chrono::Duration::days(30);
+
+        let mut inactive = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, true);
+        inactive.added_at = inactive_since;
+        let mut excluded = inactive.clone();
+        excluded.public_key = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, true).public_key;
+        excluded.node_id = NodeId::from_public_key(&excluded.public_key);
+        let mut seed = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, true);
+        seed.added_at = inactive_since;
+        seed.flags = PeerFlags::SEED;
+        let mut banned = create_test_peer(PeerFeatures::COMMUNICATION_NODE, true, false);
+        banned.added_at = inactive_since;
+        let mut reconnected = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, false);
+        reconnected.added_at = inactive_since;
+        reconnected.connection_stats.set_connection_success();
+        let recent = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, false);
+
+        for peer in [&inactive, &excluded, &seed, &banned, &reconnected, &recent] {
+            peer_storage.add_peer(peer.clone()).unwrap();
+        }
+
+        let num_evicted = peer_storage
+            .compact(&config, &reputation, &[excluded.node_id.clone()])
+            .unwrap();
+        assert_eq!(num_evicted, 1);
+        assert_eq!(peer_storage.count(), 5);
+        assert!(!peer_storage.exists_node_id(&inactive.node_id));
+        assert!(!peer_storage.exists(&inactive.public_key));
+        assert!(peer_storage.find_by_node_id(&excluded.node_id).is_ok());
+
+        // A good reputation keeps an otherwise evictable peer
+        peer_storage
+            .adjust_reputation(&excluded.node_id, 50.0, "".to_string(), &reputation)
+            .unwrap();
+        assert_eq!(peer_storage.compact(&config, &reputation, &[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn export_import_peers() {
+        let mut peer_storage = PeerStorage::new_indexed(HashmapDatabase::new()).unwrap();
+        let peer = create_test_peer(PeerFeatures::COMMUNICATION_NODE, false, false);
+        let banned = create_test_peer(PeerFeatures::COMMUNICATION_NODE, true, false);
+        peer_storage.add_peer(peer.clone()).unwrap();
+        peer_storage.add_peer(banned).unwrap();
+
+        let exported = peer_storage.export_peers().unwrap();
+        assert_eq!(exported, vec![ExportedPeer::from(&peer)]);
+
+        let mut other_storage = PeerStorage::new_indexed(HashmapDatabase::new()).unwrap();
+        assert_eq!(other_storage.import_peers(exported.clone()).unwrap(), 1);
+        let imported = other_storage.find_by_public_key(&peer.public_key).unwrap();
+        assert_eq!(imported.node_id, peer.node_id);
+        assert_eq!(imported.addresses, peer.addresses);
+
+        // Importing again merges addresses into the existing peer
+        let mut exported = exported;
+        let new_address = "/ip4/5.6.7.8/tcp/8000".parse::<Multiaddr>().unwrap();
+        exported[0].addresses = vec![new_address.clone()];
+        assert_eq!(other_storage.import_peers(exported).unwrap(), 0);
+        let imported = other_storage.find_by_public_key(&peer.public_key).unwrap();
+        assert_eq!(imported.addresses.len(), 2);
+        assert!(imported.addresses.iter().any(|a| *a == new_address));
+    }
 }
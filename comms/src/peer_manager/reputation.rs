@@ -0,0 +1,357 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Peer reputation
+//!
+//! Services report typed [Misbehaviour](self::Misbehaviour) (and [GoodBehaviour](self::GoodBehaviour)) against a
+//! peer instead of banning it outright. Each report adjusts the peer's reputation score by the weight of the event.
+//! Scores decay exponentially towards zero so that old offences (and old credit) are eventually forgotten. Once a
+//! score falls to the ban threshold, the peer is banned for a duration that doubles with every subsequent ban.
+//! Offences that an honest peer cannot commit, such as sending consensus-invalid data or forged signatures, take the
+//! score straight to the ban threshold regardless of any credit the peer has built up.
+
+use super::{NodeId, Peer};
+use crate::types::CommsPublicKey;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fmt, time::Duration};
+
+/// Offences that may be reported against a peer, ordered roughly by severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Misbehaviour {
+    /// The peer did not respond in time
+    Unresponsive,
+    /// The peer sent a message that does not conform to the protocol
+    ProtocolViolation,
+    /// The peer sent data that failed validation
+    InvalidData,
+    /// The peer sent a header or block that breaks the consensus rules
+    ConsensusViolation,
+    /// The peer misrepresented the state of its chain
+    ChainMisrepresentation,
+    /// The peer sent or propagated a message with an invalid signature
+    InvalidSignature,
+    /// The peer exceeded the maximum permitted message rate
+    MessageFlood,
+}
+
+impl Misbehaviour {
+    /// The amount by which the peer's reputation score is reduced for this offence. The penalty of offences that
+    /// [always ban](Self::always_bans) is infinite.
+    pub fn penalty(self) -> f64 {
+        use Misbehaviour::*;
+        match self {
+            Unresponsive => 10.0,
+            ProtocolViolation => 25.0,
+            InvalidData => 50.0,
+            MessageFlood => 100.0,
+            ConsensusViolation | ChainMisrepresentation | InvalidSignature => f64::INFINITY,
+        }
+    }
+
+    /// Returns true if the offence takes the peer's score to the ban threshold however high it was
+    pub fn always_bans(self) -> bool {
+        self.penalty().is_infinite()
+    }
+}
+
+impl fmt::Display for Misbehaviour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Misbehaviour::*;
+        match self {
+            Unresponsive => write!(f, "Unresponsive"),
+            ProtocolViolation => write!(f, "Protocol violation"),
+            InvalidData => write!(f, "Invalid data"),
+            ConsensusViolation => write!(f, "Consensus violation"),
+            ChainMisrepresentation => write!(f, "Chain misrepresentation"),
+            InvalidSignature => write!(f, "Invalid signature"),
+            MessageFlood => write!(f, "Message flood"),
+        }
+    }
+}
+
+/// Behaviour for which a peer may be credited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GoodBehaviour {
+    /// The peer provided data that allowed this node to complete a sync
+    SuccessfulSync,
+}
+
+impl GoodBehaviour {
+    /// The amount by which the peer's reputation score is increased
+    pub fn credit(self) -> f64 {
+        use GoodBehaviour::*;
+        match self {
+            SuccessfulSync => 10.0,
+        }
+    }
+}
+
+impl fmt::Display for GoodBehaviour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use GoodBehaviour::*;
+        match self {
+            SuccessfulSync => write!(f, "Successful sync"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReputationConfig {
+    /// A peer is banned once its score falls to this value.
+    /// Default: -100
+    pub ban_threshold: f64,
+    /// The maximum score a peer can accumulate through good behaviour.
+    /// Default: 100
+    pub max_score: f64,
+    /// The time it takes for a score to decay to half of its value. A zero duration disables decay.
+    /// Default: 2 hours
+    pub decay_half_life: Duration,
+    /// The duration of the first ban. Each subsequent ban of the same peer doubles this duration.
+    /// Default: 1 hour
+    pub ban_duration: Duration,
+    /// The maximum ban duration.
+    /// Default: 24 hours
+    pub max_ban_duration: Duration,
+}
+
+impl ReputationConfig {
+    /// Returns the ban duration for the `num_bans`th ban of a peer
+    pub fn ban_duration_for(&self, num_bans: u32) -> Duration {
+        let factor = 2u32.saturating_pow(num_bans.saturating_sub(1));
+        self.ban_duration
+            .checked_mul(factor)
+            .unwrap_or(self.max_ban_duration)
+            .min(self.max_ban_duration)
+    }
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            ban_threshold: -100.0,
+            max_score: 100.0,
+            decay_half_life: Duration::from_secs(2 * 60 * 60),
+            ban_duration: Duration::from_secs(60 * 60),
+            max_ban_duration: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// The persisted reputation of a peer.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PeerReputation {
+    score: f64,
+    updated_at: Option<NaiveDateTime>,
+    num_bans: u32,
+}
+
+// The score is never NaN, so equality is total
+impl Eq for PeerReputation {}
+
+impl PeerReputation {
+    /// Returns the decayed score at the given time
+    pub fn score_at(&self, now: NaiveDateTime, decay_half_life: Duration) -> f64 {
+        let updated_at = match self.updated_at {
+            Some(updated_at) => updated_at,
+            None => return self.score,
+        };
+        if decay_half_life.as_millis() == 0 {
+            return self.score;
+        }
+        let elapsed_ms = (now - updated_at).num_milliseconds().max(0) as f64;
+        self.score * 0.5f64.powf(elapsed_ms / decay_half_life.as_millis() as f64)
+    }
+
+    /// Returns the current decayed score
+    pub fn score(&self, config: &ReputationConfig) -> f64 {
+        self.score_at(Utc::now().naive_utc(), config.decay_half_life)
+    }
+
+    /// The number of times this peer has been banned due to its reputation
+    pub fn num_bans(&self) -> u32 {
+        self.num_bans
+    }
+
+    /// The last time the score was adjusted, or None if the peer has never been scored
+    pub fn updated_at(&self) -> Option<&NaiveDateTime> {
+        self.updated_at.as_ref()
+    }
+
+    /// Returns true if the score has ever been adjusted
+    pub fn has_history(&self) -> bool {
+        self.updated_at.is_some()
+    }
+
+    /// Adds `delta` to the decayed score. If the score falls to the ban threshold and `can_ban` is true, the ban count
+    /// is incremented and the ban duration is returned.
+    pub(super) fn adjust(
+        &mut self,
+        delta: f64,
+        config: &ReputationConfig,
+        now: NaiveDateTime,
+        can_ban: bool,
+    ) -> Option<Duration> {
+        let score = self.score_at(now, config.decay_half_life) + delta;
+        self.score = score.max(config.ban_threshold).min(config.max_score);
+        self.updated_at = Some(now);
+        if can_ban && self.score <= config.ban_threshold {
+            self.num_bans = self.num_bans.saturating_add(1);
+            return Some(config.ban_duration_for(self.num_bans));
+        }
+        None
+    }
+}
+
+/// The result of adjusting a peer's reputation
+#[derive(Debug, Clone)]
+pub struct ReputationChange {
+    /// The peer's reputation after the adjustment
+    pub reputation: PeerReputation,
+    /// The score after the adjustment
+    pub score: f64,
+    /// Set to the ban duration if the adjustment caused the peer to be banned
+    pub banned_for: Option<Duration>,
+}
+
+/// A snapshot of a peer's reputation score
+#[derive(Debug, Clone)]
+pub struct PeerScore {
+    pub node_id: NodeId,
+    pub public_key: CommsPublicKey,
+    pub score: f64,
+    pub num_bans: u32,
+    pub is_banned: bool,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl PeerScore {
+    pub fn from_peer(peer: &Peer, config: &ReputationConfig) -> Self {
+        Self {
+            node_id: peer.node_id.clone(),
+            public_key: peer.public_key.clone(),
+            score: peer.reputation.score(config),
+            num_bans: peer.reputation.num_bans(),
+            is_banned: peer.is_banned(),
+            updated_at: peer.reputation.updated_at().copied(),
+        }
+    }
+}
+
+impl fmt::Display for PeerScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} score = {:.1}, bans = {}{}",
+            self.node_id,
+            self.score,
+            self.num_bans,
+            if self.is_banned { " (BANNED)" } else { "" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn now() -> NaiveDateTime {
+        Utc::now().naive_utc()
+    }
+
+    #[test]
+    fn it_decays_towards_zero() {
+        let config = ReputationConfig::default();
+        let mut reputation = PeerReputation::default();
+        let t0 = now();
+        reputation.adjust(-40.0, &config, t0, true);
+        assert!((reputation.score_at(t0, config.decay_half_life) + 40.0).abs() < 0.001);
+        let t1 = t0 + ChronoDuration::from_std(config.decay_half_life).unwrap();
+        assert!((reputation.score_at(t1, config.decay_half_life) + 20.0).abs() < 0.001);
+
+        reputation.adjust(10.0, &config, t1, true);
+        assert!((reputation.score_at(t1, config.decay_half_life) + 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn it_bans_when_threshold_reached() {
+        let config = ReputationConfig::default();
+        let mut reputation = PeerReputation::default();
+        let t0 = now();
+        assert!(reputation
+            .adjust(-Misbehaviour::InvalidData.penalty(), &config, t0, true)
+            .is_none());
+        let banned_for = reputation.adjust(-Misbehaviour::InvalidData.penalty(), &config, t0, true);
+        assert_eq!(banned_for, Some(config.ban_duration));
+        assert_eq!(reputation.num_bans(), 1);
+        assert!((reputation.score_at(t0, config.decay_half_life) - config.ban_threshold).abs() < 0.001);
+
+        let banned_for = reputation.adjust(-Misbehaviour::Unresponsive.penalty(), &config, t0, true);
+        assert_eq!(banned_for, Some(config.ban_duration * 2));
+        assert_eq!(reputation.num_bans(), 2);
+
+        assert!(reputation
+            .adjust(-Misbehaviour::MessageFlood.penalty(), &config, t0, false)
+            .is_none());
+        assert_eq!(reputation.num_bans(), 2);
+    }
+
+    #[test]
+    fn it_always_bans_for_severe_offences() {
+        let config = ReputationConfig::default();
+        let mut reputation = PeerReputation::default();
+        let t0 = now();
+        for _ in 0..20 {
+            reputation.adjust(GoodBehaviour::SuccessfulSync.credit(), &config, t0, true);
+        }
+        assert!(Misbehaviour::InvalidSignature.always_bans());
+        assert!(!Misbehaviour::MessageFlood.always_bans());
+        let banned_for = reputation.adjust(-Misbehaviour::InvalidSignature.penalty(), &config, t0, true);
+        assert_eq!(banned_for, Some(config.ban_duration));
+        assert!((reputation.score_at(t0, config.decay_half_life) - config.ban_threshold).abs() < 0.001);
+
+        // The score stays finite, so it decays and can be credited again
+        let t1 = t0 + ChronoDuration::from_std(config.decay_half_life).unwrap();
+        assert!((reputation.score_at(t1, config.decay_half_life) - config.ban_threshold / 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn it_caps_credit() {
+        let config = ReputationConfig::default();
+        let mut reputation = PeerReputation::default();
+        let t0 = now();
+        for _ in 0..20 {
+            reputation.adjust(GoodBehaviour::SuccessfulSync.credit(), &config, t0, true);
+        }
+        assert!((reputation.score_at(t0, config.decay_half_life) - config.max_score).abs() < 0.001);
+    }
+
+    #[test]
+    fn ban_duration_is_capped() {
+        let config = ReputationConfig::default();
+        assert_eq!(config.ban_duration_for(1), config.ban_duration);
+        assert_eq!(config.ban_duration_for(3), config.ban_duration * 4);
+        assert_eq!(config.ban_duration_for(10), config.max_ban_duration);
+        assert_eq!(config.ban_duration_for(u32::MAX), config.max_ban_duration);
+    }
+}
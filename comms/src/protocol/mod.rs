@@ -35,6 +35,16 @@ pub use negotiation::ProtocolNegotiation;
 mod network_info;
 pub use network_info::NodeNetworkInfo;
 
+mod reachability;
+pub use reachability::{
+    request_dial_back,
+    respond_to_dial_back,
+    DialBackResult,
+    ReachabilityProtocolError,
+    MAX_DIAL_BACK_ADDRESSES,
+    REACHABILITY_PROTOCOL,
+};
+
 mod protocols;
 pub use protocols::{ProtocolEvent, ProtocolNotification, ProtocolNotificationRx, ProtocolNotificationTx, Protocols};
 
@@ -0,0 +1,259 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Reachability protocol
+//!
+//! A small request/response protocol that asks a connected peer to dial back a set of addresses. The peer dials
+//! each address, authenticates the requesting node over the noise handshake and reports which addresses could be
+//! reached. This allows a node to discover whether the public addresses it advertises are actually reachable (e.g.
+//! when running behind NAT).
+
+use crate::{framing, message::MessageExt, multiaddr::Multiaddr, proto::reachability as proto, protocol::ProtocolId};
+use futures::{future, SinkExt, StreamExt};
+use log::*;
+use prost::Message;
+use std::{convert::TryFrom, future::Future, io, time::Duration};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time,
+};
+
+pub static REACHABILITY_PROTOCOL: ProtocolId = ProtocolId::from_static(b"t/reachability/1.0");
+const LOG_TARGET: &str = "comms::protocol::reachability";
+
+/// The maximum number of addresses that a peer may ask this node to dial back in a single request
+pub const MAX_DIAL_BACK_ADDRESSES: usize = 4;
+const MAX_FRAME_SIZE: usize = 4 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The result of a dial back attempt for a single address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DialBackResult {
+    pub address: Multiaddr,
+    pub is_reachable: bool,
+    pub error: Option<String>,
+}
+
+impl DialBackResult {
+    pub fn reachable(address: Multiaddr) -> Self {
+        Self {
+            address,
+            is_reachable: true,
+            error: None,
+        }
+    }
+
+    pub fn unreachable<T: ToString>(address: Multiaddr, error: T) -> Self {
+        Self {
+            address,
+            is_reachable: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Ask the peer on the other end of the socket to dial back the given addresses. The socket must already be negotiated
+/// to speak the [REACHABILITY_PROTOCOL](self::REACHABILITY_PROTOCOL). Results for addresses that were not requested
+/// are discarded.
+pub async fn request_dial_back<TSocket>(
+    socket: TSocket,
+    addresses: &[Multiaddr],
+    timeout: Duration,
+) -> Result<Vec<DialBackResult>, ReachabilityProtocolError>
+where
+    TSocket: AsyncRead + AsyncWrite + Unpin,
+{
+    if addresses.len() > MAX_DIAL_BACK_ADDRESSES {
+        return Err(ReachabilityProtocolError::TooManyAddresses {
+            requested: addresses.len(),
+            max: MAX_DIAL_BACK_ADDRESSES,
+        });
+    }
+
+    let mut framed = framing::canonical(socket, MAX_FRAME_SIZE);
+    let msg = proto::DialBackRequest {
+        addresses: addresses.iter().map(|a| a.to_vec()).collect(),
+    };
+    framed.send(msg.to_encoded_bytes().into()).await?;
+
+    let msg_bytes = time::timeout(timeout, framed.next())
+        .await?
+        .ok_or(ReachabilityProtocolError::PeerUnexpectedCloseConnection)??;
+    let response = proto::DialBackResponse::decode(msg_bytes)?;
+
+    let results = response
+        .results
+        .into_iter()
+        .filter_map(|result| {
+            let address = Multiaddr::try_from(result.address).ok()?;
+            if !addresses.contains(&address) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Peer returned a dial back result for address '{}' that was not requested", address
+                );
+                return None;
+            }
+            if result.is_reachable {
+                Some(DialBackResult::reachable(address))
+            } else {
+                Some(DialBackResult::unreachable(address, result.error))
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Respond to a dial back request from the peer on the other end of the socket. `dial_back` is called concurrently for
+/// each requested address and should return Ok if the address was successfully dialed and authenticated. Returns the
+/// number of addresses that were dialed.
+pub async fn respond_to_dial_back<TSocket, F, Fut>(
+    socket: TSocket,
+    mut dial_back: F,
+) -> Result<usize, ReachabilityProtocolError>
+where
+    TSocket: AsyncRead + AsyncWrite + Unpin,
+    F: FnMut(Multiaddr) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let mut framed = framing::canonical(socket, MAX_FRAME_SIZE);
+    let msg_bytes = time::timeout(REQUEST_TIMEOUT, framed.next())
+        .await?
+        .ok_or(ReachabilityProtocolError::PeerUnexpectedCloseConnection)??;
+    let request = proto::DialBackRequest::decode(msg_bytes)?;
+
+    if request.addresses.len() > MAX_DIAL_BACK_ADDRESSES {
+        return Err(ReachabilityProtocolError::TooManyAddresses {
+            requested: request.addresses.len(),
+            max: MAX_DIAL_BACK_ADDRESSES,
+        });
+    }
+
+    let num_addresses = request.addresses.len();
+    let dials = request.addresses.into_iter().map(|bytes| {
+        let dial = Multiaddr::try_from(bytes.clone()).ok().map(&mut dial_back);
+        async move {
+            let result = match dial {
+                Some(dial) => dial.await,
+                None => Err("Invalid address".to_string()),
+            };
+            proto::DialBackResult {
+                address: bytes,
+                is_reachable: result.is_ok(),
+                error: result.err().unwrap_or_default(),
+            }
+        }
+    });
+    let results = future::join_all(dials).await;
+
+    let msg = proto::DialBackResponse { results };
+    framed.send(msg.to_encoded_bytes().into()).await?;
+    framed.close().await?;
+
+    Ok(num_addresses)
+}
+
+#[derive(Debug, Error, Clone)]
+pub enum ReachabilityProtocolError {
+    #[error("IoError: {0}")]
+    IoError(String),
+    #[error("ProtobufDecodeError: {0}")]
+    ProtobufDecodeError(String),
+    #[error("Peer unexpectedly closed the connection")]
+    PeerUnexpectedCloseConnection,
+    #[error("Timeout waiting for the peer to respond")]
+    Timeout,
+    #[error("Too many addresses requested ({requested}). The maximum is {max}")]
+    TooManyAddresses { requested: usize, max: usize },
+}
+
+impl From<time::error::Elapsed> for ReachabilityProtocolError {
+    fn from(_: time::error::Elapsed) -> Self {
+        ReachabilityProtocolError::Timeout
+    }
+}
+
+impl From<io::Error> for ReachabilityProtocolError {
+    fn from(err: io::Error) -> Self {
+        ReachabilityProtocolError::IoError(err.to_string())
+    }
+}
+
+impl From<prost::DecodeError> for ReachabilityProtocolError {
+    fn from(err: prost::DecodeError) -> Self {
+        ReachabilityProtocolError::ProtobufDecodeError(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{memsocket::MemorySocket, runtime};
+
+    #[runtime::test]
+    async fn dial_back_exchange() {
+        let (requester, responder) = MemorySocket::new_pair();
+        let reachable: Multiaddr = "/ip4/1.2.3.4/tcp/18189".parse().unwrap();
+        let unreachable: Multiaddr = "/ip4/1.2.3.4/tcp/18190".parse().unwrap();
+
+        let expected = reachable.clone();
+        let (results, num_dialed) = future::join(
+            request_dial_back(
+                requester,
+                &[reachable.clone(), unreachable.clone()],
+                Duration::from_secs(5),
+            ),
+            respond_to_dial_back(responder, |addr| {
+                let is_expected = addr == expected;
+                async move {
+                    if is_expected {
+                        Ok(())
+                    } else {
+                        Err("Connection refused".to_string())
+                    }
+                }
+            }),
+        )
+        .await;
+
+        assert_eq!(num_dialed.unwrap(), 2);
+        let results = results.unwrap();
+        assert_eq!(results, vec![
+            DialBackResult::reachable(reachable),
+            DialBackResult::unreachable(unreachable, "Connection refused"),
+        ]);
+    }
+
+    #[runtime::test]
+    async fn too_many_addresses() {
+        let (requester, _responder) = MemorySocket::new_pair();
+        let addresses = (0..=MAX_DIAL_BACK_ADDRESSES)
+            .map(|i| format!("/ip4/1.2.3.4/tcp/{}", 18000 + i).parse().unwrap())
+            .collect::<Vec<Multiaddr>>();
+
+        let err = request_dial_back(requester, &addresses, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReachabilityProtocolError::TooManyAddresses { .. }));
+    }
+}
@@ -24,12 +24,14 @@ use super::message::RpcMethod;
 use crate::{
     framing::CanonicalFraming,
     message::MessageExt,
+    peer_manager::NodeId,
     proto,
     protocol::{
         rpc,
         rpc::{
             body::ClientStreaming,
             message::{BaseRequest, RpcMessageFlags},
+            recording::{RecordingSession, RecordingSide, RpcRecorder},
             Handshake,
             NamedProtocolService,
             Response,
@@ -55,6 +57,7 @@ use log::*;
 use prost::Message;
 use std::{
     borrow::Cow,
+    cmp,
     convert::TryFrom,
     fmt,
     future::Future,
@@ -90,6 +93,18 @@ impl RpcClient {
         framed: CanonicalFraming<TSubstream>,
         protocol_name: ProtocolId,
     ) -> Result<Self, RpcError>
+    where
+        TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static,
+    {
+        Self::connect_with_recording(config, framed, protocol_name, None).await
+    }
+
+    async fn connect_with_recording<TSubstream>(
+        config: RpcClientConfig,
+        framed: CanonicalFraming<TSubstream>,
+        protocol_name: ProtocolId,
+        recording: Option<RecordingSession>,
+    ) -> Result<Self, RpcError>
     where
         TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static,
     {
@@ -103,9 +118,17 @@ impl RpcClient {
             let span = span!(Level::TRACE, "start_rpc_worker");
             span.follows_from(tracing_id);
 
-            RpcClientWorker::new(config, request_rx, framed, ready_tx, protocol_name, shutdown_signal)
-                .run()
-                .instrument(span)
+            RpcClientWorker::new(
+                config,
+                request_rx,
+                framed,
+                ready_tx,
+                protocol_name,
+                shutdown_signal,
+                recording,
+            )
+            .run()
+            .instrument(span)
         });
         ready_rx
             .await
@@ -164,7 +187,7 @@ impl RpcClient {
         self.connector.send_ping()
     }
 
-    async fn call_inner(
+    pub(super) async fn call_inner(
         &mut self,
         request: BaseRequest<Bytes>,
     ) -> Result<mpsc::Receiver<Result<Response<Bytes>, RpcStatus>>, RpcError> {
@@ -184,6 +207,8 @@ impl fmt::Debug for RpcClient {
 pub struct RpcClientBuilder<TClient> {
     config: RpcClientConfig,
     protocol_id: Option<ProtocolId>,
+    node_id: Option<NodeId>,
+    recorder: Option<RpcRecorder>,
     _client: PhantomData<TClient>,
 }
 
@@ -192,6 +217,8 @@ impl<TClient> Default for RpcClientBuilder<TClient> {
         Self {
             config: Default::default(),
             protocol_id: None,
+            node_id: None,
+            recorder: None,
             _client: PhantomData,
         }
     }
@@ -231,11 +258,39 @@ impl<TClient> RpcClientBuilder<TClient> {
         self
     }
 
+    /// Set the maximum number of times a request is retried when the server responds that it is busy. A busy response
+    /// is returned to the caller once the retries are exhausted.
+    /// Default: 3
+    pub fn with_max_busy_retries(mut self, max_retries: usize) -> Self {
+        self.config.max_busy_retries = max_retries;
+        self
+    }
+
+    /// Set the maximum length of time to wait before retrying a request after a busy response, regardless of the
+    /// retry delay requested by the server.
+    /// Default: 30 seconds
+    pub fn with_max_busy_retry_delay(mut self, delay: Duration) -> Self {
+        self.config.max_busy_retry_delay = delay;
+        self
+    }
+
     /// Set the protocol ID associated with this client. This is used for logging purposes only.
     pub fn with_protocol_id(mut self, protocol_id: ProtocolId) -> Self {
         self.protocol_id = Some(protocol_id);
         self
     }
+
+    /// Set the node ID of the peer that this client connects to. This is used for recording purposes only.
+    pub fn with_node_id(mut self, node_id: NodeId) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
+
+    /// Records the request and response frames of the session using the given recorder
+    pub fn with_recorder(mut self, recorder: RpcRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
 }
 
 impl<TClient> RpcClientBuilder<TClient>
@@ -244,16 +299,18 @@ where TClient: From<RpcClient> + NamedProtocolService
     /// Negotiates and establishes a session to the peer's RPC service
     pub async fn connect<TSubstream>(self, framed: CanonicalFraming<TSubstream>) -> Result<TClient, RpcError>
     where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static {
-        RpcClient::connect(
-            self.config,
-            framed,
-            self.protocol_id
-                .as_ref()
-                .cloned()
-                .unwrap_or_else(|| ProtocolId::from_static(TClient::PROTOCOL_NAME)),
-        )
-        .await
-        .map(Into::into)
+        let protocol_id = self
+            .protocol_id
+            .as_ref()
+            .cloned()
+            .unwrap_or_else(|| ProtocolId::from_static(TClient::PROTOCOL_NAME));
+        let recording = self
+            .recorder
+            .as_ref()
+            .map(|recorder| recorder.start_session(RecordingSide::Client, self.node_id.as_ref(), &protocol_id));
+        RpcClient::connect_with_recording(self.config, framed, protocol_id, recording)
+            .await
+            .map(Into::into)
     }
 }
 
@@ -262,6 +319,8 @@ pub struct RpcClientConfig {
     pub deadline: Option<Duration>,
     pub deadline_grace_period: Duration,
     pub handshake_timeout: Duration,
+    pub max_busy_retries: usize,
+    pub max_busy_retry_delay: Duration,
 }
 
 impl RpcClientConfig {
@@ -274,6 +333,13 @@ impl RpcClientConfig {
     pub fn handshake_timeout(&self) -> Duration {
         self.handshake_timeout
     }
+
+    /// Returns the delay before the given retry attempt (starting at 0) of a request that received a busy response.
+    /// The delay requested by the server is used if provided, otherwise the delay increases exponentially.
+    pub fn busy_retry_delay(&self, attempt: usize, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after.unwrap_or_else(|| Duration::from_secs(1 << cmp::min(attempt, 16)));
+        cmp::min(delay, self.max_busy_retry_delay)
+    }
 }
 
 impl Default for RpcClientConfig {
@@ -282,6 +348,8 @@ impl Default for RpcClientConfig {
             deadline: Some(Duration::from_secs(120)),
             deadline_grace_period: Duration::from_secs(60),
             handshake_timeout: Duration::from_secs(90),
+            max_busy_retries: 3,
+            max_busy_retry_delay: Duration::from_secs(30),
         }
     }
 }
@@ -372,6 +440,7 @@ struct RpcClientWorker<TSubstream> {
     last_request_latency: Option<Duration>,
     protocol_id: ProtocolId,
     shutdown_signal: ShutdownSignal,
+    recording: Option<RecordingSession>,
 }
 
 impl<TSubstream> RpcClientWorker<TSubstream>
@@ -384,6 +453,7 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
         ready_tx: oneshot::Sender<Result<(), RpcError>>,
         protocol_id: ProtocolId,
         shutdown_signal: ShutdownSignal,
+        recording: Option<RecordingSession>,
     ) -> Self {
         Self {
             config,
@@ -394,6 +464,7 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
             last_request_latency: None,
             protocol_id,
             shutdown_signal,
+            recording,
         }
     }
 
@@ -556,9 +627,9 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
         request: BaseRequest<Bytes>,
         reply: oneshot::Sender<mpsc::Receiver<Result<Response<Bytes>, RpcStatus>>>,
     ) -> Result<(), RpcError> {
-        let request_id = self.next_request_id();
+        let mut request_id = self.next_request_id();
         let method = request.method.into();
-        let req = proto::rpc::RpcRequest {
+        let mut req = proto::rpc::RpcRequest {
             request_id: request_id as u32,
             method,
             deadline: self.config.deadline.map(|t| t.as_secs()).unwrap_or(0),
@@ -596,12 +667,13 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
             // can exit early
         }
 
-        if let Err(err) = self.send_request(req).await {
+        if let Err(err) = self.send_request(req.clone()).await {
             warn!(target: LOG_TARGET, "{}", err);
             let _ = response_tx.send(Err(err.into()));
             return Ok(());
         }
 
+        let mut num_busy_retries = 0;
         loop {
             let resp = match self.read_response(request_id).await {
                 Ok(resp) => {
@@ -678,6 +750,34 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
                         break;
                     }
                 },
+                Ok(Err(err))
+                    if err.status_code().is_busy() &&
+                        num_busy_retries < self.config.max_busy_retries &&
+                        !response_tx.is_closed() =>
+                {
+                    let delay = self.config.busy_retry_delay(num_busy_retries, err.retry_after());
+                    num_busy_retries += 1;
+                    debug!(
+                        target: LOG_TARGET,
+                        "(stream={}) Remote service is busy ({}). Retrying request {} (method={}) in {:.0?} (attempt \
+                         {} of {})",
+                        self.stream_id(),
+                        err,
+                        request_id,
+                        method,
+                        delay,
+                        num_busy_retries,
+                        self.config.max_busy_retries
+                    );
+                    time::sleep(delay).await;
+                    request_id = self.next_request_id();
+                    req.request_id = request_id as u32;
+                    if let Err(err) = self.send_request(req.clone()).await {
+                        warn!(target: LOG_TARGET, "{}", err);
+                        let _ = response_tx.send(Err(err.into())).await;
+                        break;
+                    }
+                }
                 Ok(Err(err)) => {
                     debug!(target: LOG_TARGET, "Remote service returned error: {}", err);
                     if !response_tx.is_closed() {
@@ -708,6 +808,9 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
                 expected: rpc::max_request_size(),
             });
         }
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record_request(&req);
+        }
         self.framed.send(payload.into()).await?;
         Ok(())
     }
@@ -715,6 +818,9 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
     async fn read_response(&mut self, request_id: u16) -> Result<proto::rpc::RpcResponse, RpcError> {
         let mut reader = RpcResponseReader::new(&mut self.framed, self.config, request_id);
         let resp = reader.read_response().await?;
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record_response(&resp);
+        }
         Ok(resp)
     }
 
@@ -266,6 +266,7 @@ impl RpcResponse {
             request_id: self.request_id,
             status: self.status as u32,
             flags: self.flags.bits().into(),
+            retry_after_ms: 0,
             payload: self.payload.to_vec(),
         }
     }
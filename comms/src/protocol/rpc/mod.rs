@@ -51,6 +51,7 @@ const fn max_response_payload_size() -> usize {
     // - 3 u32 fields, VarInt(u32::MAX) is 5 bytes
     // - 1 length varint for the payload, allow for 5 bytes to be safe (max_payload_size being technically too small is
     //   fine, being too large isn't)
+    // retry_after_ms is not included because it is zero (and therefore not encoded) for responses that carry a payload
     const MAX_HEADER_SIZE: usize = 4 + 4 * 5;
     max_response_size() - MAX_HEADER_SIZE
 }
@@ -61,7 +62,7 @@ pub use body::{Body, ClientStreaming, IntoBody, Streaming};
 mod context;
 
 mod server;
-pub use server::{mock, NamedProtocolService, RpcServer, RpcServerError, RpcServerHandle};
+pub use server::{mock, NamedProtocolService, RpcMethodLimits, RpcServer, RpcServerError, RpcServerHandle};
 
 mod client;
 pub use client::{RpcClient, RpcClientBuilder, RpcClientConfig};
@@ -85,6 +86,8 @@ pub use status::{RpcStatus, RpcStatusCode};
 
 mod not_found;
 
+pub mod recording;
+
 // Re-exports used to keep things orderly in the #[tari_rpc] proc macro
 pub mod __macro_reexports {
     pub use crate::{
@@ -0,0 +1,136 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{recorder::is_final_response, RpcRecordingError};
+use crate::{
+    peer_manager::NodeId,
+    proto,
+    protocol::{
+        rpc::{message::RpcMessageFlags, RpcStatusCode},
+        ProtocolId,
+    },
+    Bytes,
+};
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tari_crypto::tari_utilities::ByteArray;
+
+/// The side of the RPC session on which a frame was captured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingSide {
+    Server,
+    Client,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordedFrameKind {
+    Request,
+    Response,
+}
+
+/// A request or response frame read from an RPC recording
+#[derive(Debug, Clone)]
+pub struct RpcRecordedFrame {
+    pub timestamp: SystemTime,
+    pub side: RecordingSide,
+    pub kind: RecordedFrameKind,
+    pub session_id: u64,
+    pub peer: Option<NodeId>,
+    pub protocol: ProtocolId,
+    pub request_id: u32,
+    pub method: u32,
+    pub deadline: Duration,
+    pub status: RpcStatusCode,
+    pub flags: RpcMessageFlags,
+    pub payload_size: usize,
+    /// The recorded payload. This is empty for responses if the recorder was not configured to record response
+    /// payloads.
+    pub payload: Bytes,
+    pub elapsed: Option<Duration>,
+}
+
+impl RpcRecordedFrame {
+    pub fn is_request(&self) -> bool {
+        self.kind == RecordedFrameKind::Request
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.kind == RecordedFrameKind::Response
+    }
+
+    /// Returns true if this is a ping or ping response frame
+    pub fn is_ack(&self) -> bool {
+        self.flags.contains(RpcMessageFlags::ACK)
+    }
+
+    /// Returns true if this is the last response frame for the request
+    pub fn is_final_response(&self) -> bool {
+        self.is_response() && is_final_response(self.side, self.flags, self.status as u32)
+    }
+}
+
+impl TryFrom<proto::rpc::RpcRecordedFrame> for RpcRecordedFrame {
+    type Error = RpcRecordingError;
+
+    fn try_from(frame: proto::rpc::RpcRecordedFrame) -> Result<Self, Self::Error> {
+        use proto::rpc::rpc_recorded_frame::{Kind, Side};
+        let side = match Side::from_i32(frame.side) {
+            Some(Side::Server) => RecordingSide::Server,
+            Some(Side::Client) => RecordingSide::Client,
+            None => return Err(RpcRecordingError::InvalidFrame(format!("invalid side {}", frame.side))),
+        };
+        let kind = match Kind::from_i32(frame.kind) {
+            Some(Kind::Request) => RecordedFrameKind::Request,
+            Some(Kind::Response) => RecordedFrameKind::Response,
+            None => return Err(RpcRecordingError::InvalidFrame(format!("invalid kind {}", frame.kind))),
+        };
+        let peer = if frame.peer_node_id.is_empty() {
+            None
+        } else {
+            Some(
+                NodeId::from_bytes(&frame.peer_node_id)
+                    .map_err(|err| RpcRecordingError::InvalidFrame(format!("invalid peer node id: {}", err)))?,
+            )
+        };
+
+        Ok(Self {
+            timestamp: UNIX_EPOCH + Duration::from_micros(frame.timestamp),
+            side,
+            kind,
+            session_id: frame.session_id,
+            peer,
+            protocol: frame.protocol.into(),
+            request_id: frame.request_id,
+            method: frame.method,
+            deadline: Duration::from_secs(frame.deadline),
+            status: frame.status.into(),
+            flags: RpcMessageFlags::from_bits_truncate(frame.flags as u8),
+            payload_size: frame.payload_size as usize,
+            payload: frame.payload.into(),
+            elapsed: Some(frame.elapsed)
+                .filter(|_| kind == RecordedFrameKind::Response)
+                .map(Duration::from_micros),
+        })
+    }
+}
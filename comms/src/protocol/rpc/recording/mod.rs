@@ -0,0 +1,44 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # RPC recording
+//!
+//! An optional [RpcRecorder] captures the request and response frames of RPC sessions (protocol, method, deadline,
+//! payload size, status and timing) to a rotating recording file. Recordings can be read back with the
+//! [RpcRecordingReader] and replayed against a service implementation with [RpcReplay], which is useful for
+//! reproducing issues that were observed on a live node.
+
+mod error;
+pub use error::RpcRecordingError;
+
+mod frame;
+pub use frame::{RecordedFrameKind, RecordingSide, RpcRecordedFrame};
+
+mod reader;
+pub use reader::RpcRecordingReader;
+
+mod recorder;
+pub(super) use recorder::RecordingSession;
+pub use recorder::{rotated_path, RpcRecorder, RpcRecorderConfig};
+
+mod replay;
+pub use replay::{ReplayedRequest, RpcReplay, RpcReplayReport};
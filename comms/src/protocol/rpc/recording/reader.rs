@@ -0,0 +1,99 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{RpcRecordedFrame, RpcRecordingError};
+use crate::proto;
+use prost::Message;
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+/// Frames larger than this are considered corrupt
+const MAX_RECORDED_FRAME_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Reads the frames of an RPC recording file in the order that they were recorded
+pub struct RpcRecordingReader<R> {
+    reader: R,
+}
+
+impl RpcRecordingReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RpcRecordingError> {
+        let file = File::open(path)?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: Read> RpcRecordingReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// Reads the next frame, returning None at the end of the recording
+    pub fn read_frame(&mut self) -> Result<Option<RpcRecordedFrame>, RpcRecordingError> {
+        let len = match self.read_length_delimiter()? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        if len > MAX_RECORDED_FRAME_SIZE {
+            return Err(RpcRecordingError::InvalidFrame(format!(
+                "frame size {} exceeds the maximum of {} bytes",
+                len, MAX_RECORDED_FRAME_SIZE
+            )));
+        }
+        let mut buf = vec![0u8; len as usize];
+        self.reader.read_exact(&mut buf)?;
+        let frame = proto::rpc::RpcRecordedFrame::decode(buf.as_slice())?;
+        let frame = RpcRecordedFrame::try_from(frame)?;
+        Ok(Some(frame))
+    }
+
+    fn read_length_delimiter(&mut self) -> Result<Option<u64>, RpcRecordingError> {
+        let mut len = 0u64;
+        // A u64 varint is at most 10 bytes
+        for i in 0..10 {
+            let mut byte = [0u8; 1];
+            match self.reader.read_exact(&mut byte) {
+                Ok(_) => {},
+                // A clean end of the recording
+                Err(err) if i == 0 && err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err.into()),
+            }
+            len |= u64::from(byte[0] & 0x7f) << (7 * i);
+            if byte[0] & 0x80 == 0 {
+                return Ok(Some(len));
+            }
+        }
+        Err(RpcRecordingError::InvalidFrame("invalid length delimiter".to_string()))
+    }
+}
+
+impl<R: Read> Iterator for RpcRecordingReader<R> {
+    type Item = Result<RpcRecordedFrame, RpcRecordingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
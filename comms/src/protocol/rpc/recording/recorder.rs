@@ -0,0 +1,382 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{RecordingSide, RpcRecordingError};
+use crate::{
+    peer_manager::NodeId,
+    proto,
+    protocol::{rpc::message::RpcMessageFlags, ProtocolId},
+};
+use log::*;
+use prost::Message;
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    fs::File,
+    io,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc,
+        Arc,
+    },
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tari_crypto::tari_utilities::ByteArray;
+
+const LOG_TARGET: &str = "comms::rpc::recorder";
+
+#[derive(Debug, Clone)]
+pub struct RpcRecorderConfig {
+    /// The path of the recording file. When the file is rotated, it is renamed with a numeric suffix (e.g.
+    /// `rpc.rec.1`), with higher numbers being older.
+    pub path: PathBuf,
+    /// The size in bytes at which the recording file is rotated
+    /// Default: 50 MiB
+    pub max_file_size: u64,
+    /// The number of rotated files to keep
+    /// Default: 5
+    pub max_rotated_files: usize,
+    /// If true, response payloads are recorded in addition to request payloads. Response payloads are not needed for
+    /// replay and can be large (e.g. block sync).
+    /// Default: false
+    pub record_response_payloads: bool,
+    /// The number of frames that may be queued for writing. Frames are dropped, rather than waited for, if the queue
+    /// is full.
+    /// Default: 1000
+    pub max_pending_frames: usize,
+}
+
+impl RpcRecorderConfig {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            max_file_size: 50 * 1024 * 1024,
+            max_rotated_files: 5,
+            record_response_payloads: false,
+            max_pending_frames: 1000,
+        }
+    }
+}
+
+enum RecorderMessage {
+    Frame(proto::rpc::RpcRecordedFrame),
+    Flush(mpsc::Sender<io::Result<()>>),
+}
+
+/// Captures RPC request and response frames to a rotating recording file. Frames are written on a dedicated thread so
+/// that recording never blocks an RPC session.
+#[derive(Debug, Clone)]
+pub struct RpcRecorder {
+    sender: mpsc::SyncSender<RecorderMessage>,
+    record_response_payloads: bool,
+    next_session_id: Arc<AtomicU64>,
+    num_dropped_frames: Arc<AtomicU64>,
+}
+
+impl RpcRecorder {
+    /// Opens the recording file and starts the writer thread
+    pub fn start(config: RpcRecorderConfig) -> Result<Self, RpcRecordingError> {
+        let mut writer = RotatingFileWriter::open(&config.path, config.max_file_size, config.max_rotated_files)?;
+        let (sender, receiver) = mpsc::sync_channel(config.max_pending_frames);
+        thread::Builder::new()
+            .name("rpc-recorder".to_string())
+            .spawn(move || writer.run(receiver))?;
+
+        Ok(Self {
+            sender,
+            record_response_payloads: config.record_response_payloads,
+            next_session_id: Arc::new(AtomicU64::new(0)),
+            num_dropped_frames: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Waits until all frames recorded so far have been written to the recording file
+    pub fn flush(&self) -> Result<(), RpcRecordingError> {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.sender
+            .send(RecorderMessage::Flush(reply_tx))
+            .map_err(|_| RpcRecordingError::RecorderShutdown)?;
+        reply_rx.recv().map_err(|_| RpcRecordingError::RecorderShutdown)??;
+        Ok(())
+    }
+
+    /// The number of frames that were not recorded because the writer could not keep up
+    pub fn num_dropped_frames(&self) -> u64 {
+        self.num_dropped_frames.load(Ordering::Relaxed)
+    }
+
+    pub(in crate::protocol::rpc) fn start_session(
+        &self,
+        side: RecordingSide,
+        peer: Option<&NodeId>,
+        protocol: &ProtocolId,
+    ) -> RecordingSession {
+        RecordingSession {
+            recorder: self.clone(),
+            session_id: self.next_session_id.fetch_add(1, Ordering::Relaxed),
+            side,
+            peer_node_id: peer.map(|node_id| node_id.to_vec()).unwrap_or_default(),
+            protocol: protocol.clone(),
+            pending_requests: HashMap::new(),
+        }
+    }
+
+    fn record(&self, frame: proto::rpc::RpcRecordedFrame) {
+        if self.sender.try_send(RecorderMessage::Frame(frame)).is_err() {
+            let num_dropped = self.num_dropped_frames.fetch_add(1, Ordering::Relaxed) + 1;
+            debug!(
+                target: LOG_TARGET,
+                "RPC recorder queue is full or closed. {} frame(s) dropped", num_dropped
+            );
+        }
+    }
+}
+
+/// Records the frames of a single RPC session
+pub(in crate::protocol::rpc) struct RecordingSession {
+    recorder: RpcRecorder,
+    session_id: u64,
+    side: RecordingSide,
+    peer_node_id: Vec<u8>,
+    protocol: ProtocolId,
+    pending_requests: HashMap<u32, Instant>,
+}
+
+impl RecordingSession {
+    pub fn record_request(&mut self, request: &proto::rpc::RpcRequest) {
+        self.pending_requests.insert(request.request_id, Instant::now());
+        let frame = proto::rpc::RpcRecordedFrame {
+            kind: proto::rpc::rpc_recorded_frame::Kind::Request as i32,
+            request_id: request.request_id,
+            method: request.method,
+            deadline: request.deadline,
+            flags: request.flags,
+            payload_size: request.payload.len() as u64,
+            payload: request.payload.clone(),
+            ..self.new_frame()
+        };
+        self.recorder.record(frame);
+    }
+
+    pub fn record_response(&mut self, response: &proto::rpc::RpcResponse) {
+        let flags = RpcMessageFlags::from_bits_truncate(response.flags as u8);
+        let is_final = is_final_response(self.side, flags, response.status);
+        let started = if is_final {
+            self.pending_requests.remove(&response.request_id)
+        } else {
+            self.pending_requests.get(&response.request_id).copied()
+        };
+        let frame = proto::rpc::RpcRecordedFrame {
+            kind: proto::rpc::rpc_recorded_frame::Kind::Response as i32,
+            request_id: response.request_id,
+            status: response.status,
+            flags: response.flags,
+            payload_size: response.payload.len() as u64,
+            payload: if self.recorder.record_response_payloads {
+                response.payload.clone()
+            } else {
+                Vec::new()
+            },
+            elapsed: started.map(|t| t.elapsed().as_micros() as u64).unwrap_or(0),
+            ..self.new_frame()
+        };
+        self.recorder.record(frame);
+    }
+
+    fn new_frame(&self) -> proto::rpc::RpcRecordedFrame {
+        proto::rpc::RpcRecordedFrame {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_micros() as u64)
+                .unwrap_or(0),
+            side: match self.side {
+                RecordingSide::Server => proto::rpc::rpc_recorded_frame::Side::Server as i32,
+                RecordingSide::Client => proto::rpc::rpc_recorded_frame::Side::Client as i32,
+            },
+            session_id: self.session_id,
+            peer_node_id: self.peer_node_id.clone(),
+            protocol: self.protocol.to_vec(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes length-delimited frames to a file, rotating the file once it reaches the maximum size
+struct RotatingFileWriter {
+    path: PathBuf,
+    max_file_size: u64,
+    max_rotated_files: usize,
+    file: BufWriter<File>,
+    file_size: u64,
+}
+
+impl RotatingFileWriter {
+    fn open(path: &Path, max_file_size: u64, max_rotated_files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        let file_size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_file_size,
+            max_rotated_files,
+            file: BufWriter::new(file),
+            file_size,
+        })
+    }
+
+    fn run(&mut self, receiver: mpsc::Receiver<RecorderMessage>) {
+        // Exits once all recorders have been dropped
+        while let Ok(msg) = receiver.recv() {
+            match msg {
+                RecorderMessage::Frame(frame) => {
+                    if let Err(err) = self.write_frame(&frame.encode_length_delimited_to_vec()) {
+                        warn!(target: LOG_TARGET, "Failed to write RPC recording frame: {}", err);
+                    }
+                },
+                RecorderMessage::Flush(reply) => {
+                    let _ = reply.send(self.file.flush());
+                },
+            }
+        }
+        if let Err(err) = self.file.flush() {
+            warn!(target: LOG_TARGET, "Failed to flush RPC recording: {}", err);
+        }
+    }
+
+    fn write_frame(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if self.file_size > 0 && self.file_size + bytes.len() as u64 > self.max_file_size {
+            self.rotate()?;
+        }
+        self.file.write_all(bytes)?;
+        self.file_size += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_rotated_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for i in (1..self.max_rotated_files).rev() {
+                let from = rotated_path(&self.path, i);
+                if from.exists() {
+                    fs::rename(from, rotated_path(&self.path, i + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.file = BufWriter::new(file);
+        self.file_size = 0;
+        Ok(())
+    }
+}
+
+/// Returns the path of the `n`th rotated recording file
+pub fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(format!(".{}", n));
+    path.into()
+}
+
+/// Returns true if the response is the last for the request. The client records responses after the chunks have been
+/// reassembled, so the MORE flag of the first chunk does not apply.
+pub(super) fn is_final_response(side: RecordingSide, flags: RpcMessageFlags, status: u32) -> bool {
+    let is_more = side == RecordingSide::Server && flags.is_more();
+    !is_more && (flags.is_fin() || flags.contains(RpcMessageFlags::ACK) || status != 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocol::rpc::recording::RpcRecordingReader;
+
+    fn create_request(request_id: u32, payload: &[u8]) -> proto::rpc::RpcRequest {
+        proto::rpc::RpcRequest {
+            request_id,
+            method: 1,
+            deadline: 10,
+            flags: 0,
+            payload: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn it_records_and_reads_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rpc.rec");
+        let recorder = RpcRecorder::start(RpcRecorderConfig::new(&path)).unwrap();
+        let node_id = crate::test_utils::node_id::random();
+        let mut session =
+            recorder.start_session(RecordingSide::Server, Some(&node_id), &ProtocolId::from_static(b"t/1"));
+        session.record_request(&create_request(0, b"hello"));
+        session.record_response(&proto::rpc::RpcResponse {
+            request_id: 0,
+            status: 0,
+            flags: RpcMessageFlags::FIN.bits().into(),
+            retry_after_ms: 0,
+            payload: b"world".to_vec(),
+        });
+        recorder.flush().unwrap();
+
+        let frames = RpcRecordingReader::open(&path)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].is_request());
+        assert_eq!(frames[0].peer.as_ref(), Some(&node_id));
+        assert_eq!(frames[0].protocol, ProtocolId::from_static(b"t/1"));
+        assert_eq!(frames[0].deadline.as_secs(), 10);
+        assert_eq!(&frames[0].payload[..], b"hello");
+        assert!(frames[1].is_final_response());
+        assert_eq!(frames[1].payload_size, 5);
+        // Response payloads are not recorded by default
+        assert!(frames[1].payload.is_empty());
+        assert!(frames[1].elapsed.is_some());
+    }
+
+    #[test]
+    fn it_rotates_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rpc.rec");
+        let mut writer = RotatingFileWriter::open(&path, 10, 2).unwrap();
+        for _ in 0..4 {
+            writer.write_frame(&[0u8; 8]).unwrap();
+        }
+        writer.file.flush().unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), 8);
+        assert!(rotated_path(&path, 1).exists());
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{RecordingSide, RpcRecordedFrame, RpcRecordingError};
+use crate::{
+    peer_manager::PeerFeatures,
+    protocol::{
+        rpc::{
+            message::BaseRequest,
+            mock::MockRpcServer,
+            Body,
+            NamedProtocolService,
+            Request,
+            Response,
+            RpcClient,
+            RpcClientConfig,
+            RpcServerError,
+            RpcStatus,
+            RpcStatusCode,
+            RPC_MAX_FRAME_SIZE,
+        },
+        ProtocolId,
+    },
+    test_utils::node_identity::build_node_identity,
+    Bytes,
+};
+use log::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+use tower::Service;
+use tower_make::MakeService;
+
+const LOG_TARGET: &str = "comms::rpc::replay";
+
+/// Replays the requests from an RPC recording against a service implementation. Each recorded session is replayed in
+/// its own session to a [MockRpcServer](crate::protocol::rpc::mock::MockRpcServer), and the responses are compared to
+/// the recorded responses.
+///
+/// Services that use the request context to fetch or dial peers are not supported, as the mock server has no
+/// connectivity.
+pub struct RpcReplay<TSvc> {
+    service: TSvc,
+    side: RecordingSide,
+    client_config: RpcClientConfig,
+}
+
+impl<TSvc> RpcReplay<TSvc>
+where
+    TSvc: MakeService<
+            ProtocolId,
+            Request<Bytes>,
+            MakeError = RpcServerError,
+            Response = Response<Body>,
+            Error = RpcStatus,
+        > + NamedProtocolService
+        + Send
+        + Sync
+        + 'static,
+    TSvc::Service: Send + 'static,
+    <TSvc::Service as Service<Request<Bytes>>>::Future: Send + 'static,
+    TSvc::Future: Send + 'static,
+{
+    pub fn new(service: TSvc) -> Self {
+        Self {
+            service,
+            side: RecordingSide::Server,
+            client_config: Default::default(),
+        }
+    }
+
+    /// Replay the requests captured on the given side of the session. Default: Server
+    pub fn with_side(mut self, side: RecordingSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn with_client_config(mut self, config: RpcClientConfig) -> Self {
+        self.client_config = config;
+        self
+    }
+
+    /// Replays the recorded requests for the protocol of the service, in the order in which they were recorded.
+    /// Frames for other protocols are ignored.
+    pub async fn run<I>(self, frames: I) -> Result<RpcReplayReport, RpcRecordingError>
+    where I: IntoIterator<Item = RpcRecordedFrame> {
+        let protocol = ProtocolId::from_static(TSvc::PROTOCOL_NAME);
+        let sessions = collect_sessions(frames, &protocol, self.side);
+        debug!(
+            target: LOG_TARGET,
+            "Replaying {} session(s) for protocol `{}`",
+            sessions.len(),
+            String::from_utf8_lossy(&protocol)
+        );
+
+        let mut server = MockRpcServer::new(self.service, build_node_identity(PeerFeatures::COMMUNICATION_NODE));
+        let server_hnd = server.serve();
+
+        let mut report = RpcReplayReport::default();
+        for (session_id, requests) in sessions {
+            let peer = build_node_identity(PeerFeatures::COMMUNICATION_CLIENT).to_peer();
+            let mut conn = server.create_connection(peer, protocol.clone()).await;
+            let framed = conn
+                .open_framed_substream(&protocol, RPC_MAX_FRAME_SIZE)
+                .await
+                .map_err(|err| RpcRecordingError::RpcError(err.into()))?;
+            let mut client = RpcClient::connect(self.client_config, framed, protocol.clone()).await?;
+
+            for recorded in requests {
+                let timer = Instant::now();
+                let mut responses = client
+                    .call_inner(BaseRequest::new(recorded.method.into(), recorded.payload))
+                    .await?;
+                let mut status = RpcStatusCode::Ok;
+                let mut num_responses = 0;
+                let mut payload_size = 0;
+                while let Some(result) = responses.recv().await {
+                    match result {
+                        Ok(resp) => {
+                            num_responses += 1;
+                            payload_size += resp.payload.len();
+                        },
+                        Err(err) => {
+                            status = err.status_code();
+                            break;
+                        },
+                    }
+                }
+
+                report.requests.push(ReplayedRequest {
+                    session_id,
+                    request_id: recorded.request_id,
+                    method: recorded.method,
+                    recorded_status: recorded.status,
+                    recorded_payload_size: recorded.payload_size,
+                    status,
+                    num_responses,
+                    payload_size,
+                    elapsed: timer.elapsed(),
+                });
+            }
+
+            client.close().await;
+        }
+
+        server_hnd.abort();
+        Ok(report)
+    }
+}
+
+/// The outcome of replaying a recording
+#[derive(Debug, Clone, Default)]
+pub struct RpcReplayReport {
+    pub requests: Vec<ReplayedRequest>,
+}
+
+impl RpcReplayReport {
+    /// Returns the replayed requests whose status differs from the recorded status
+    pub fn mismatches(&self) -> impl Iterator<Item = &ReplayedRequest> + '_ {
+        self.requests.iter().filter(|req| !req.is_match())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayedRequest {
+    pub session_id: u64,
+    pub request_id: u32,
+    pub method: u32,
+    /// The final status of the recorded response. This is None if the recording does not contain a final response
+    /// for the request.
+    pub recorded_status: Option<RpcStatusCode>,
+    /// The total payload size of the recorded responses
+    pub recorded_payload_size: usize,
+    pub status: RpcStatusCode,
+    pub num_responses: usize,
+    /// The total payload size of the replayed responses
+    pub payload_size: usize,
+    pub elapsed: Duration,
+}
+
+impl ReplayedRequest {
+    /// Returns true if the replayed status matches the recorded status, or if there is no recorded status
+    pub fn is_match(&self) -> bool {
+        self.recorded_status.map(|status| status == self.status).unwrap_or(true)
+    }
+}
+
+struct RecordedRequest {
+    request_id: u32,
+    method: u32,
+    payload: Bytes,
+    status: Option<RpcStatusCode>,
+    payload_size: usize,
+}
+
+/// Groups the recorded requests for the protocol by session, matching each response to its request
+fn collect_sessions<I>(frames: I, protocol: &ProtocolId, side: RecordingSide) -> BTreeMap<u64, Vec<RecordedRequest>>
+where I: IntoIterator<Item = RpcRecordedFrame> {
+    let mut sessions = BTreeMap::<_, Vec<RecordedRequest>>::new();
+    // Maps the session and request ID to the index of the most recent request with that ID
+    let mut pending = HashMap::new();
+    for frame in frames {
+        if frame.side != side || frame.protocol != *protocol || frame.is_ack() {
+            continue;
+        }
+        if frame.is_request() {
+            let requests = sessions.entry(frame.session_id).or_default();
+            pending.insert((frame.session_id, frame.request_id), requests.len());
+            requests.push(RecordedRequest {
+                request_id: frame.request_id,
+                method: frame.method,
+                payload: frame.payload,
+                status: None,
+                payload_size: 0,
+            });
+            continue;
+        }
+
+        let request = match pending
+            .get(&(frame.session_id, frame.request_id))
+            .and_then(|index| sessions.get_mut(&frame.session_id)?.get_mut(*index))
+        {
+            Some(request) => request,
+            None => continue,
+        };
+        // The payload of an error response contains the status details, which are not counted in the replayed payload
+        if frame.status.is_ok() {
+            request.payload_size += frame.payload_size;
+        }
+        if frame.is_final_response() {
+            request.status = Some(frame.status);
+            pending.remove(&(frame.session_id, frame.request_id));
+        }
+    }
+
+    sessions
+}
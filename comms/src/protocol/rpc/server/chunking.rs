@@ -140,6 +140,7 @@ impl ChunkedResponseIter {
             request_id: self.message.request_id,
             status: RpcStatusCode::MalformedResponse as u32,
             flags: RpcMessageFlags::FIN.bits().into(),
+            retry_after_ms: 0,
             payload: msg.into_bytes(),
         }
     }
@@ -186,6 +187,7 @@ impl Iterator for ChunkedResponseIter {
             request_id,
             status,
             flags: flags.bits().into(),
+            retry_after_ms: 0,
             payload: chunk.to_vec(),
         };
 
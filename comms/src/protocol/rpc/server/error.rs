@@ -33,6 +33,8 @@ pub enum RpcServerError {
     Io(#[from] io::Error),
     #[error("Maximum number of RPC sessions reached")]
     MaximumSessionsReached,
+    #[error("Maximum number of RPC sessions for the client reached")]
+    MaximumSessionsPerClientReached,
     #[error("Internal service request canceled")]
     RequestCanceled,
     #[error("Handshake error: {0}")]
@@ -0,0 +1,354 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Per-client and per-method limits for the RPC server.
+//!
+//! The [RpcLimiter] is shared by all sessions of a [PeerRpcServer](super::PeerRpcServer) and tracks the number of
+//! active sessions for each client, the number of active calls to each method and each client's request budget. Limits
+//! on requests are reported to the client as a busy [RpcStatus], which indicates how long the client should wait
+//! before retrying.
+
+use super::RpcServerBuilder;
+use crate::{
+    peer_manager::NodeId,
+    protocol::{rpc::RpcStatus, ProtocolId},
+    rate_limit::TokenBucket,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The time that a client is asked to wait before retrying a call that exceeded a concurrency limit
+const CONCURRENCY_LIMIT_RETRY_AFTER: Duration = Duration::from_secs(2);
+
+type MethodKey = (ProtocolId, u32);
+
+/// Limits that apply to calls to a single RPC method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RpcMethodLimits {
+    cost: usize,
+    maximum_concurrent_per_client: Option<usize>,
+    maximum_concurrent: Option<usize>,
+}
+
+impl RpcMethodLimits {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The number of tokens taken from the client's request budget for each call to this method. Default: 1
+    pub fn with_cost(mut self, cost: usize) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// The maximum number of calls to this method that a single client may have in progress at any one time
+    pub fn with_maximum_concurrent_per_client(mut self, limit: usize) -> Self {
+        self.maximum_concurrent_per_client = Some(limit);
+        self
+    }
+
+    /// The maximum number of calls to this method that may be in progress at any one time across all clients
+    pub fn with_maximum_concurrent(mut self, limit: usize) -> Self {
+        self.maximum_concurrent = Some(limit);
+        self
+    }
+
+    pub fn cost(&self) -> usize {
+        self.cost
+    }
+}
+
+impl Default for RpcMethodLimits {
+    fn default() -> Self {
+        Self {
+            cost: 1,
+            maximum_concurrent_per_client: None,
+            maximum_concurrent: None,
+        }
+    }
+}
+
+pub(super) struct RpcLimiter {
+    maximum_sessions_per_client: Option<usize>,
+    client_request_budget: Option<(usize, Duration)>,
+    method_limits: HashMap<MethodKey, RpcMethodLimits>,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl RpcLimiter {
+    pub fn new(config: &RpcServerBuilder) -> Self {
+        Self {
+            maximum_sessions_per_client: config.maximum_sessions_per_client,
+            client_request_budget: config.client_request_budget,
+            method_limits: config.method_limits.clone(),
+            state: Default::default(),
+        }
+    }
+
+    /// Registers a new session for the client. None is returned if the client has reached the maximum number of
+    /// sessions. The session is active until the returned guard is dropped.
+    pub fn try_start_session(&self, node_id: &NodeId) -> Option<SessionGuard> {
+        let mut state = acquire_lock!(self.state);
+        state.prune_idle_clients(self.client_idle_timeout());
+        let client = state.client_mut(node_id, self.client_request_budget);
+        if let Some(limit) = self.maximum_sessions_per_client {
+            if client.num_sessions >= limit {
+                return None;
+            }
+        }
+        client.num_sessions += 1;
+
+        Some(SessionGuard {
+            state: self.state.clone(),
+            node_id: node_id.clone(),
+        })
+    }
+
+    /// Checks the limits for a call to the given method, and registers the call if no limits are exceeded. The call is
+    /// in progress until the returned guard is dropped.
+    pub fn try_start_request(
+        &self,
+        node_id: &NodeId,
+        protocol: &ProtocolId,
+        method: u32,
+    ) -> Result<RequestGuard, RpcStatus> {
+        let key = (protocol.clone(), method);
+        let limits = self.method_limits.get(&key).copied().unwrap_or_default();
+
+        let mut state = acquire_lock!(self.state);
+        if let Some(limit) = limits.maximum_concurrent {
+            if state.active_calls.get(&key).copied().unwrap_or(0) >= limit {
+                return Err(RpcStatus::busy(
+                    format!(
+                        "The maximum number of concurrent calls ({}) to this method is reached",
+                        limit
+                    ),
+                    CONCURRENCY_LIMIT_RETRY_AFTER,
+                ));
+            }
+        }
+
+        let client = state.client_mut(node_id, self.client_request_budget);
+        if let Some(limit) = limits.maximum_concurrent_per_client {
+            if client.active_calls.get(&key).copied().unwrap_or(0) >= limit {
+                return Err(RpcStatus::busy(
+                    format!(
+                        "The maximum number of concurrent calls ({}) to this method per client is reached",
+                        limit
+                    ),
+                    CONCURRENCY_LIMIT_RETRY_AFTER,
+                ));
+            }
+        }
+
+        if let Some(bucket) = client.request_budget.as_mut() {
+            bucket
+                .try_take(limits.cost)
+                .map_err(|retry_after| RpcStatus::busy("Request budget exhausted", retry_after))?;
+        }
+
+        *client.active_calls.entry(key.clone()).or_default() += 1;
+        *state.active_calls.entry(key.clone()).or_default() += 1;
+
+        Ok(RequestGuard {
+            state: self.state.clone(),
+            node_id: node_id.clone(),
+            key,
+        })
+    }
+
+    /// Clients without sessions are kept for the restock interval of their request budget, so that the budget cannot
+    /// be reset by reconnecting.
+    fn client_idle_timeout(&self) -> Duration {
+        self.client_request_budget
+            .map(|(_, restock_interval)| restock_interval)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Default)]
+struct LimiterState {
+    clients: HashMap<NodeId, ClientState>,
+    active_calls: HashMap<MethodKey, usize>,
+}
+
+impl LimiterState {
+    fn client_mut(&mut self, node_id: &NodeId, request_budget: Option<(usize, Duration)>) -> &mut ClientState {
+        self.clients.entry(node_id.clone()).or_insert_with(|| ClientState {
+            num_sessions: 0,
+            request_budget: request_budget
+                .map(|(capacity, restock_interval)| TokenBucket::new(capacity, restock_interval)),
+            active_calls: HashMap::new(),
+            last_active: Instant::now(),
+        })
+    }
+
+    fn prune_idle_clients(&mut self, idle_timeout: Duration) {
+        self.clients
+            .retain(|_, client| client.num_sessions > 0 || client.last_active.elapsed() < idle_timeout);
+    }
+
+    fn decrement_active_calls(&mut self, node_id: &NodeId, key: &MethodKey) {
+        if let Some(client) = self.clients.get_mut(node_id) {
+            decrement_count(&mut client.active_calls, key);
+        }
+        decrement_count(&mut self.active_calls, key);
+    }
+}
+
+fn decrement_count(counts: &mut HashMap<MethodKey, usize>, key: &MethodKey) {
+    if let Some(count) = counts.get_mut(key) {
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            counts.remove(key);
+        }
+    }
+}
+
+struct ClientState {
+    num_sessions: usize,
+    request_budget: Option<TokenBucket>,
+    active_calls: HashMap<MethodKey, usize>,
+    last_active: Instant,
+}
+
+/// Releases the client session when dropped
+pub(super) struct SessionGuard {
+    state: Arc<Mutex<LimiterState>>,
+    node_id: NodeId,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let mut state = acquire_lock!(self.state);
+        if let Some(client) = state.clients.get_mut(&self.node_id) {
+            client.num_sessions = client.num_sessions.saturating_sub(1);
+            client.last_active = Instant::now();
+        }
+    }
+}
+
+/// Releases the call when dropped
+pub(super) struct RequestGuard {
+    state: Arc<Mutex<LimiterState>>,
+    node_id: NodeId,
+    key: MethodKey,
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        acquire_lock!(self.state).decrement_active_calls(&self.node_id, &self.key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{protocol::rpc::RpcStatusCode, test_utils::node_id};
+
+    fn create_limiter(config: RpcServerBuilder) -> RpcLimiter {
+        RpcLimiter::new(&config)
+    }
+
+    #[test]
+    fn it_limits_sessions_per_client() {
+        let client1 = node_id::random();
+        let client2 = node_id::random();
+        let limiter = create_limiter(RpcServerBuilder::default().with_maximum_sessions_per_client(2));
+        let _s1 = limiter.try_start_session(&client1).unwrap();
+        let s2 = limiter.try_start_session(&client1).unwrap();
+        assert!(limiter.try_start_session(&client1).is_none());
+        let _other = limiter.try_start_session(&client2).unwrap();
+
+        drop(s2);
+        let _s3 = limiter.try_start_session(&client1).unwrap();
+    }
+
+    #[test]
+    fn it_limits_concurrent_calls() {
+        let protocol = ProtocolId::from_static(b"test/1");
+        let client1 = node_id::random();
+        let client2 = node_id::random();
+        let limiter = create_limiter(
+            RpcServerBuilder::default()
+                .with_method_limits(
+                    protocol.clone(),
+                    1,
+                    RpcMethodLimits::new().with_maximum_concurrent_per_client(1),
+                )
+                .with_method_limits(protocol.clone(), 2, RpcMethodLimits::new().with_maximum_concurrent(1)),
+        );
+
+        let call = limiter.try_start_request(&client1, &protocol, 1).unwrap();
+        let status = limiter.try_start_request(&client1, &protocol, 1).unwrap_err();
+        assert_eq!(status.status_code(), RpcStatusCode::Busy);
+        assert_eq!(status.retry_after(), Some(CONCURRENCY_LIMIT_RETRY_AFTER));
+        let _other = limiter.try_start_request(&client2, &protocol, 1).unwrap();
+        drop(call);
+        let _call = limiter.try_start_request(&client1, &protocol, 1).unwrap();
+
+        let _call = limiter.try_start_request(&client1, &protocol, 2).unwrap();
+        let status = limiter.try_start_request(&client2, &protocol, 2).unwrap_err();
+        assert!(status.status_code().is_busy());
+    }
+
+    #[test]
+    fn it_charges_the_method_cost_to_the_request_budget() {
+        let protocol = ProtocolId::from_static(b"test/1");
+        let client1 = node_id::random();
+        let client2 = node_id::random();
+        let limiter = create_limiter(
+            RpcServerBuilder::default()
+                .with_client_request_budget(10, Duration::from_secs(100))
+                .with_method_limits(protocol.clone(), 1, RpcMethodLimits::new().with_cost(6)),
+        );
+
+        let _session = limiter.try_start_session(&client1).unwrap();
+        limiter.try_start_request(&client1, &protocol, 1).unwrap();
+        limiter.try_start_request(&client1, &protocol, 2).unwrap();
+        let status = limiter.try_start_request(&client1, &protocol, 1).unwrap_err();
+        assert!(status.status_code().is_busy());
+        assert!(status.retry_after().unwrap() > Duration::from_secs(99));
+        for _ in 0..3 {
+            limiter.try_start_request(&client1, &protocol, 2).unwrap();
+        }
+        limiter.try_start_request(&client1, &protocol, 2).unwrap_err();
+        limiter.try_start_request(&client2, &protocol, 1).unwrap();
+    }
+
+    #[test]
+    fn it_keeps_the_request_budget_after_the_session_ends() {
+        let client1 = node_id::random();
+        let protocol = ProtocolId::from_static(b"test/1");
+        let limiter =
+            create_limiter(RpcServerBuilder::default().with_client_request_budget(1, Duration::from_secs(100)));
+
+        let session = limiter.try_start_session(&client1).unwrap();
+        limiter.try_start_request(&client1, &protocol, 1).unwrap();
+        drop(session);
+        let _session = limiter.try_start_session(&client1).unwrap();
+        limiter.try_start_request(&client1, &protocol, 1).unwrap_err();
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::protocol::rpc::RpcStatusCode;
+use once_cell::sync::Lazy;
+use tari_metrics::{Histogram, HistogramVec, IntCounter, IntCounterVec};
+
+/// The label used for requests to methods that the service has not handled. Method ids are chosen by the peer, so only
+/// the ids of methods the service implements are used as labels.
+const UNKNOWN_METHOD_LABEL: &str = "unknown";
+
+/// `method` is `None` if the service is not known to implement the requested method
+pub fn requests(protocol: &str, method: Option<u32>, status: RequestStatus) -> IntCounter {
+    static METER: Lazy<IntCounterVec> = Lazy::new(|| {
+        tari_metrics::register_int_counter_vec(
+            "comms_rpc_requests",
+            "Number of RPC requests handled by the RPC server by protocol, method and status",
+            &["protocol", "method", "status"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[protocol, &method_label(method), status.as_str()])
+}
+
+/// `method` is `None` if the service is not known to implement the requested method
+pub fn request_duration(protocol: &str, method: Option<u32>) -> Histogram {
+    static METER: Lazy<HistogramVec> = Lazy::new(|| {
+        tari_metrics::register_histogram_vec(
+            "comms_rpc_request_duration_seconds",
+            "Time taken to handle an RPC request, including streaming the response, by protocol and method",
+            &["protocol", "method"],
+        )
+        .unwrap()
+    });
+
+    METER.with_label_values(&[protocol, &method_label(method)])
+}
+
+fn method_label(method: Option<u32>) -> String {
+    method
+        .map(|m| m.to_string())
+        .unwrap_or_else(|| UNKNOWN_METHOD_LABEL.to_string())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RequestStatus {
+    Ok,
+    Error(RpcStatusCode),
+    Timeout,
+}
+
+impl RequestStatus {
+    fn as_str(&self) -> &'static str {
+        use RpcStatusCode::*;
+        match self {
+            RequestStatus::Ok => "ok",
+            RequestStatus::Timeout => "timeout",
+            RequestStatus::Error(code) => match code {
+                Ok => "ok",
+                BadRequest => "bad_request",
+                UnsupportedMethod => "unsupported_method",
+                NotImplemented => "not_implemented",
+                Timeout => "timeout",
+                MalformedResponse => "malformed_response",
+                General => "general",
+                NotFound => "not_found",
+                ProtocolError => "protocol_error",
+                Forbidden => "forbidden",
+                Busy => "busy",
+                InvalidRpcStatusCode => "invalid_status_code",
+            },
+        }
+    }
+}
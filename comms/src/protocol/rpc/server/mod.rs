@@ -30,6 +30,13 @@ mod handle;
 pub use handle::RpcServerHandle;
 use handle::RpcServerRequest;
 
+mod limits;
+pub use limits::RpcMethodLimits;
+use limits::{RequestGuard, RpcLimiter, SessionGuard};
+
+mod metrics;
+use metrics::RequestStatus;
+
 pub mod mock;
 
 mod router;
@@ -41,7 +48,8 @@ use super::{
     error::HandshakeRejectReason,
     message::{Request, Response, RpcMessageFlags},
     not_found::ProtocolServiceNotFound,
-    status::RpcStatus,
+    recording::{RecordingSession, RecordingSide, RpcRecorder},
+    status::{RpcStatus, RpcStatusCode},
     Handshake,
     RPC_MAX_FRAME_SIZE,
 };
@@ -67,6 +75,8 @@ use futures::{stream, SinkExt, StreamExt};
 use prost::Message;
 use std::{
     borrow::Cow,
+    cmp,
+    collections::{HashMap, HashSet},
     future::Future,
     sync::Arc,
     time::{Duration, Instant},
@@ -158,8 +168,12 @@ impl Default for RpcServer {
 #[derive(Clone)]
 pub struct RpcServerBuilder {
     maximum_simultaneous_sessions: Option<usize>,
+    maximum_sessions_per_client: Option<usize>,
+    client_request_budget: Option<(usize, Duration)>,
+    method_limits: HashMap<(ProtocolId, u32), RpcMethodLimits>,
     minimum_client_deadline: Duration,
     handshake_timeout: Duration,
+    recorder: Option<RpcRecorder>,
 }
 
 impl RpcServerBuilder {
@@ -177,11 +191,41 @@ impl RpcServerBuilder {
         self
     }
 
+    /// Sets the maximum number of sessions that a single client may have open at any one time
+    pub fn with_maximum_sessions_per_client(mut self, limit: usize) -> Self {
+        self.maximum_sessions_per_client = Some(limit);
+        self
+    }
+
+    pub fn with_unlimited_sessions_per_client(mut self) -> Self {
+        self.maximum_sessions_per_client = None;
+        self
+    }
+
+    /// Limits each client to a total request cost of `capacity` within `restock_interval`. Each request costs 1 unless
+    /// otherwise specified in the [RpcMethodLimits](self::RpcMethodLimits) for the method.
+    pub fn with_client_request_budget(mut self, capacity: usize, restock_interval: Duration) -> Self {
+        self.client_request_budget = Some((capacity, restock_interval));
+        self
+    }
+
+    /// Sets the cost and concurrency limits for a method of the given protocol
+    pub fn with_method_limits(mut self, protocol: ProtocolId, method: u32, limits: RpcMethodLimits) -> Self {
+        self.method_limits.insert((protocol, method), limits);
+        self
+    }
+
     pub fn with_minimum_client_deadline(mut self, deadline: Duration) -> Self {
         self.minimum_client_deadline = deadline;
         self
     }
 
+    /// Records the request and response frames of all sessions using the given recorder
+    pub fn with_recorder(mut self, recorder: RpcRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     pub fn finish(self) -> RpcServer {
         let (request_tx, request_rx) = mpsc::channel(10);
         RpcServer {
@@ -196,8 +240,12 @@ impl Default for RpcServerBuilder {
     fn default() -> Self {
         Self {
             maximum_simultaneous_sessions: Some(1000),
+            maximum_sessions_per_client: None,
+            client_request_budget: None,
+            method_limits: HashMap::new(),
             minimum_client_deadline: Duration::from_secs(1),
             handshake_timeout: Duration::from_secs(15),
+            recorder: None,
         }
     }
 }
@@ -205,6 +253,7 @@ impl Default for RpcServerBuilder {
 pub(super) struct PeerRpcServer<TSvc, TCommsProvider> {
     executor: BoundedExecutor,
     config: RpcServerBuilder,
+    limiter: Arc<RpcLimiter>,
     service: TSvc,
     protocol_notifications: Option<ProtocolNotificationRx<Substream>>,
     comms_provider: TCommsProvider,
@@ -238,6 +287,7 @@ where
                 Some(num) => BoundedExecutor::from_current(num),
                 None => BoundedExecutor::allow_maximum(),
             },
+            limiter: Arc::new(RpcLimiter::new(&config)),
             config,
             service,
             protocol_notifications: Some(protocol_notifications),
@@ -339,6 +389,22 @@ where
             return Err(RpcServerError::MaximumSessionsReached);
         }
 
+        let session_guard = match self.limiter.try_start_session(&node_id) {
+            Some(guard) => guard,
+            None => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Rejecting RPC session request for peer `{}` because the maximum number of sessions per client \
+                     has been reached",
+                    node_id,
+                );
+                handshake
+                    .reject_with_reason(HandshakeRejectReason::NoSessionsAvailable)
+                    .await?;
+                return Err(RpcServerError::MaximumSessionsPerClientReached);
+            },
+        };
+
         let service = match self.service.make_service(protocol.clone()).await {
             Ok(s) => s,
             Err(err) => {
@@ -363,6 +429,8 @@ where
 
         let service = ActivePeerRpcService::new(
             self.config.clone(),
+            self.limiter.clone(),
+            session_guard,
             protocol,
             node_id.clone(),
             service,
@@ -380,12 +448,17 @@ where
 
 struct ActivePeerRpcService<TSvc, TCommsProvider> {
     config: RpcServerBuilder,
+    limiter: Arc<RpcLimiter>,
+    _session_guard: SessionGuard,
     protocol: ProtocolId,
     node_id: NodeId,
     service: TSvc,
     framed: CanonicalFraming<Substream>,
     comms_provider: TCommsProvider,
     logging_context_string: Arc<String>,
+    recording: Option<RecordingSession>,
+    /// The methods the service has handled in this session, which are the only method ids used as metric labels
+    known_methods: HashSet<u32>,
 }
 
 impl<TSvc, TCommsProvider> ActivePeerRpcService<TSvc, TCommsProvider>
@@ -395,13 +468,20 @@ where
 {
     pub(self) fn new(
         config: RpcServerBuilder,
+        limiter: Arc<RpcLimiter>,
+        session_guard: SessionGuard,
         protocol: ProtocolId,
         node_id: NodeId,
         service: TSvc,
         framed: CanonicalFraming<Substream>,
         comms_provider: TCommsProvider,
     ) -> Self {
+        let recording = config
+            .recorder
+            .as_ref()
+            .map(|recorder| recorder.start_session(RecordingSide::Server, Some(&node_id), &protocol));
         Self {
+            recording,
             logging_context_string: Arc::new(format!(
                 "stream_id: {}, peer: {}, protocol: {}",
                 framed.stream_id(),
@@ -410,11 +490,14 @@ where
             )),
 
             config,
+            limiter,
+            _session_guard: session_guard,
             protocol,
             node_id,
             service,
             framed,
             comms_provider,
+            known_methods: HashSet::new(),
         }
     }
 
@@ -475,6 +558,9 @@ where
     #[instrument(name = "rpc::server::handle_req", skip(self, request), err, fields(request_size = request.len()))]
     async fn handle_request(&mut self, mut request: Bytes) -> Result<(), RpcServerError> {
         let decoded_msg = proto::rpc::RpcRequest::decode(&mut request)?;
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record_request(&decoded_msg);
+        }
 
         let request_id = decoded_msg.request_id;
         let method = decoded_msg.method.into();
@@ -491,13 +577,7 @@ where
                 "Invalid deadline ({:.0?}). The deadline MUST be greater than {:.0?}.",
                 self.node_id, deadline,
             ));
-            let bad_request = proto::rpc::RpcResponse {
-                request_id,
-                status: status.as_code(),
-                flags: RpcMessageFlags::FIN.bits().into(),
-                payload: status.to_details_bytes(),
-            };
-            self.framed.send(bad_request.to_encoded_bytes().into()).await?;
+            self.send_error_response(request_id, &status).await?;
             return Ok(());
         }
 
@@ -513,7 +593,7 @@ where
                 flags: RpcMessageFlags::ACK.bits().into(),
                 ..Default::default()
             };
-            self.framed.send(ack.to_encoded_bytes().into()).await?;
+            self.send_response(ack).await?;
             return Ok(());
         }
 
@@ -522,6 +602,27 @@ where
             "({}) Request: {}", self.logging_context_string, decoded_msg
         );
 
+        let method_id = decoded_msg.method;
+        // Held until the request completes so that the concurrency limits for the method are enforced
+        let _request_guard = match self.try_start_request(method_id) {
+            Ok(guard) => guard,
+            Err(status) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "({}) Request for method {} rejected: {}", self.logging_context_string, method_id, status
+                );
+                metrics::requests(
+                    &self.protocol_name(),
+                    self.method_label(method_id),
+                    RequestStatus::Error(status.status_code()),
+                )
+                .inc();
+                self.send_error_response(request_id, &status).await?;
+                return Ok(());
+            },
+        };
+        let start = Instant::now();
+
         let req = Request::with_context(
             self.create_request_context(request_id),
             method,
@@ -546,15 +647,30 @@ where
                     self.node_id,
                     self.protocol_name()
                 );
+                let method = self.method_label(method_id);
+                metrics::requests(&self.protocol_name(), method, RequestStatus::Timeout).inc();
+                metrics::request_duration(&self.protocol_name(), method).observe(start.elapsed().as_secs_f64());
                 return Ok(());
             },
         };
 
         match service_result {
             Ok(body) => {
-                self.process_body(request_id, deadline, body).await?;
+                self.known_methods.insert(method_id);
+                metrics::requests(&self.protocol_name(), Some(method_id), RequestStatus::Ok).inc();
+                let result = self.process_body(request_id, deadline, body).await;
+                // The duration includes streaming the response
+                metrics::request_duration(&self.protocol_name(), Some(method_id))
+                    .observe(start.elapsed().as_secs_f64());
+                result?;
             },
             Err(err) => {
+                if err.status_code() != RpcStatusCode::UnsupportedMethod {
+                    self.known_methods.insert(method_id);
+                }
+                let method = self.method_label(method_id);
+                metrics::requests(&self.protocol_name(), method, RequestStatus::Error(err.status_code())).inc();
+                metrics::request_duration(&self.protocol_name(), method).observe(start.elapsed().as_secs_f64());
                 debug!(
                     target: LOG_TARGET,
                     "(peer: {}, protocol: {}) Service returned an error: {}",
@@ -562,20 +678,44 @@ where
                     self.protocol_name(),
                     err
                 );
-                let resp = proto::rpc::RpcResponse {
-                    request_id,
-                    status: err.as_code(),
-                    flags: RpcMessageFlags::FIN.bits().into(),
-                    payload: err.to_details_bytes(),
-                };
-
-                self.framed.send(resp.to_encoded_bytes().into()).await?;
+                self.send_error_response(request_id, &err).await?;
             },
         }
 
         Ok(())
     }
 
+    fn method_label(&self, method_id: u32) -> Option<u32> {
+        Some(method_id).filter(|id| self.known_methods.contains(id))
+    }
+
+    fn try_start_request(&self, method_id: u32) -> Result<RequestGuard, RpcStatus> {
+        self.limiter.try_start_request(&self.node_id, &self.protocol, method_id)
+    }
+
+    async fn send_error_response(&mut self, request_id: u32, status: &RpcStatus) -> Result<(), RpcServerError> {
+        let resp = proto::rpc::RpcResponse {
+            request_id,
+            status: status.as_code(),
+            flags: RpcMessageFlags::FIN.bits().into(),
+            // Zero indicates that no retry delay was given, so round up to at least 1ms
+            retry_after_ms: status
+                .retry_after()
+                .map(|retry_after| cmp::max(retry_after.as_millis() as u64, 1))
+                .unwrap_or(0),
+            payload: status.to_details_bytes(),
+        };
+        self.send_response(resp).await
+    }
+
+    async fn send_response(&mut self, resp: proto::rpc::RpcResponse) -> Result<(), RpcServerError> {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record_response(&resp);
+        }
+        self.framed.send(resp.to_encoded_bytes().into()).await?;
+        Ok(())
+    }
+
     fn protocol_name(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(&self.protocol)
     }
@@ -590,8 +730,7 @@ where
         let mut stream = body
             .into_message()
             .map(|result| into_response(request_id, result))
-            .flat_map(|message| stream::iter(ChunkedResponseIter::new(message)))
-            .map(|resp| Bytes::from(resp.to_encoded_bytes()));
+            .flat_map(|message| stream::iter(ChunkedResponseIter::new(message)));
 
         loop {
             let next_item = log_timing(
@@ -606,10 +745,10 @@ where
                         target: LOG_TARGET,
                         "({}) Sending body len = {}",
                         self.logging_context_string,
-                        msg.len()
+                        msg.payload.len()
                     );
 
-                    self.framed.send(msg).await?;
+                    self.send_response(msg).await?;
                 },
                 Ok(None) => {
                     debug!(target: LOG_TARGET, "{} Request complete", self.logging_context_string,);
@@ -29,6 +29,7 @@ use crate::{
             either::Either,
             message::{Request, Response},
             not_found::ProtocolServiceNotFound,
+            recording::RpcRecorder,
             server::{NamedProtocolService, RpcServerHandle},
             RpcError,
             RpcServer,
@@ -95,6 +96,12 @@ impl<A, B> Router<A, B> {
         self.server.get_handle()
     }
 
+    /// Records the request and response frames of all sessions for the routed services using the given recorder
+    pub fn with_recorder(mut self, recorder: RpcRecorder) -> Self {
+        self.server.builder = self.server.builder.with_recorder(recorder);
+        self
+    }
+
     pub fn into_boxed(self) -> Box<Self>
     where Self: 'static {
         Box::new(self)
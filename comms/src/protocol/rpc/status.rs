@@ -23,7 +23,7 @@
 use super::RpcError;
 use crate::proto;
 use log::*;
-use std::{fmt, fmt::Display};
+use std::{fmt, fmt::Display, time::Duration};
 use thiserror::Error;
 
 const LOG_TARGET: &str = "comms::rpc::status";
@@ -32,6 +32,7 @@ const LOG_TARGET: &str = "comms::rpc::status";
 pub struct RpcStatus {
     code: RpcStatusCode,
     details: String,
+    retry_after: Option<Duration>,
 }
 
 impl RpcStatus {
@@ -39,6 +40,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::Ok,
             details: Default::default(),
+            retry_after: None,
         }
     }
 
@@ -46,6 +48,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::UnsupportedMethod,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -53,6 +56,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::NotImplemented,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -60,6 +64,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::BadRequest,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -69,6 +74,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::General,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -80,6 +86,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::Timeout,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -87,6 +94,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::NotFound,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -94,6 +102,17 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::Forbidden,
             details: details.to_string(),
+            retry_after: None,
+        }
+    }
+
+    /// Returns a busy status, indicating that a limit was reached and that the request may be retried after the given
+    /// duration.
+    pub fn busy<T: ToString>(details: T, retry_after: Duration) -> Self {
+        Self {
+            code: RpcStatusCode::Busy,
+            details: details.to_string(),
+            retry_after: Some(retry_after),
         }
     }
 
@@ -110,6 +129,7 @@ impl RpcStatus {
         Self {
             code: RpcStatusCode::ProtocolError,
             details: details.to_string(),
+            retry_after: None,
         }
     }
 
@@ -125,6 +145,11 @@ impl RpcStatus {
         &self.details
     }
 
+    /// Returns the length of time the client should wait before retrying the request, if the server specified one
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
     pub fn to_details_bytes(&self) -> Vec<u8> {
         self.details.as_bytes().to_vec()
     }
@@ -163,6 +188,9 @@ impl<'a> From<&'a proto::rpc::RpcResponse> for RpcStatus {
         RpcStatus {
             code: status_code,
             details: String::from_utf8_lossy(&resp.payload).to_string(),
+            retry_after: Some(resp.retry_after_ms)
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
         }
     }
 }
@@ -195,6 +223,8 @@ pub enum RpcStatusCode {
     ProtocolError = 8,
     /// RPC forbidden error
     Forbidden = 9,
+    /// A limit on the server was reached. The request may be retried later.
+    Busy = 10,
     // The following status represents anything that is not recognised (i.e not one of the above codes).
     /// Unrecognised RPC status code
     InvalidRpcStatusCode,
@@ -212,6 +242,10 @@ impl RpcStatusCode {
     pub fn is_timeout(self) -> bool {
         self == Self::Timeout
     }
+
+    pub fn is_busy(self) -> bool {
+        self == Self::Busy
+    }
 }
 
 impl From<u32> for RpcStatusCode {
@@ -228,6 +262,7 @@ impl From<u32> for RpcStatusCode {
             7 => NotFound,
             8 => ProtocolError,
             9 => Forbidden,
+            10 => Busy,
             _ => InvalidRpcStatusCode,
         }
     }
@@ -251,6 +286,7 @@ mod test {
         assert_eq!(RpcStatusCode::from(InvalidRpcStatusCode as u32), InvalidRpcStatusCode);
         assert_eq!(RpcStatusCode::from(ProtocolError as u32), ProtocolError);
         assert_eq!(RpcStatusCode::from(Forbidden as u32), Forbidden);
+        assert_eq!(RpcStatusCode::from(Busy as u32), Busy);
         assert_eq!(RpcStatusCode::from(123), InvalidRpcStatusCode);
     }
 }
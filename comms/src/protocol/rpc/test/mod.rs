@@ -25,4 +25,5 @@ mod comms_integration;
 mod greeting_service;
 mod handshake;
 mod mock;
+mod recording;
 mod smoke;
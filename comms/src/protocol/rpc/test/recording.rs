@@ -0,0 +1,84 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    framing,
+    protocol::rpc::{
+        recording::{RpcRecorder, RpcRecorderConfig, RpcRecordingReader, RpcReplay},
+        test::{
+            greeting_service::{GreetingClient, GreetingServer, GreetingService, SayHelloRequest},
+            smoke::setup_with_builder,
+        },
+        RpcServer,
+        RpcStatusCode,
+    },
+    runtime,
+};
+use futures::StreamExt;
+
+#[runtime::test]
+async fn it_records_and_replays_a_session() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rpc.rec");
+    let recorder = RpcRecorder::start(RpcRecorderConfig::new(&path)).unwrap();
+    let (mut muxer, _outbound, _, _, _shutdown) = setup_with_builder(
+        RpcServer::builder().with_recorder(recorder.clone()),
+        GreetingService::default(),
+    )
+    .await;
+    let socket = muxer.incoming_mut().next().await.unwrap();
+    let framed = framing::canonical(socket, 1024);
+    let mut client = GreetingClient::builder().connect(framed).await.unwrap();
+
+    client
+        .say_hello(SayHelloRequest {
+            name: "Yathvan".to_string(),
+            language: 1,
+        })
+        .await
+        .unwrap();
+    client.return_error().await.unwrap_err();
+    let greetings = client.get_greetings(5).await.unwrap().collect::<Vec<_>>().await;
+    assert_eq!(greetings.len(), 5);
+    recorder.flush().unwrap();
+
+    let frames = RpcRecordingReader::open(&path)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let requests = frames.iter().filter(|f| f.is_request()).collect::<Vec<_>>();
+    assert_eq!(requests.iter().map(|f| f.method).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    let report = RpcReplay::new(GreetingServer::new(GreetingService::default()))
+        .run(frames)
+        .await
+        .unwrap();
+    assert_eq!(report.requests.len(), 3);
+    assert_eq!(report.mismatches().count(), 0);
+    assert_eq!(report.requests[0].status, RpcStatusCode::Ok);
+    assert_eq!(report.requests[1].recorded_status, Some(RpcStatusCode::NotImplemented));
+    assert_eq!(
+        report.requests[2].payload_size,
+        report.requests[2].recorded_payload_size
+    );
+    assert!(report.requests[2].payload_size > 0);
+}
@@ -29,6 +29,7 @@ use crate::{
             context::RpcCommsBackend,
             error::HandshakeRejectReason,
             handshake::RpcHandshakeError,
+            server::RpcServerBuilder,
             test::{
                 greeting_service::{
                     GreetingClient,
@@ -72,6 +73,22 @@ pub(super) async fn setup_service<T: GreetingRpc>(
     task::JoinHandle<()>,
     RpcCommsBackend,
     Shutdown,
+) {
+    setup_service_with_builder(
+        RpcServer::builder().with_maximum_simultaneous_sessions(num_concurrent_sessions),
+        service_impl,
+    )
+    .await
+}
+
+pub(super) async fn setup_service_with_builder<T: GreetingRpc>(
+    builder: RpcServerBuilder,
+    service_impl: T,
+) -> (
+    mpsc::Sender<ProtocolNotification<Substream>>,
+    task::JoinHandle<()>,
+    RpcCommsBackend,
+    Shutdown,
 ) {
     let (notif_tx, notif_rx) = mpsc::channel(1);
     let shutdown = Shutdown::new();
@@ -80,8 +97,7 @@ pub(super) async fn setup_service<T: GreetingRpc>(
         let context = context.clone();
         let shutdown_signal = shutdown.to_signal();
         async move {
-            let fut = RpcServer::builder()
-                .with_maximum_simultaneous_sessions(num_concurrent_sessions)
+            let fut = builder
                 .with_minimum_client_deadline(Duration::from_secs(0))
                 .finish()
                 .add_service(GreetingServer::new(service_impl))
@@ -101,7 +117,18 @@ pub(super) async fn setup<T: GreetingRpc>(
     service_impl: T,
     num_concurrent_sessions: usize,
 ) -> (Yamux, Yamux, task::JoinHandle<()>, Arc<NodeIdentity>, Shutdown) {
-    let (notif_tx, server_hnd, context, shutdown) = setup_service(service_impl, num_concurrent_sessions).await;
+    setup_with_builder(
+        RpcServer::builder().with_maximum_simultaneous_sessions(num_concurrent_sessions),
+        service_impl,
+    )
+    .await
+}
+
+pub(super) async fn setup_with_builder<T: GreetingRpc>(
+    builder: RpcServerBuilder,
+    service_impl: T,
+) -> (Yamux, Yamux, task::JoinHandle<()>, Arc<NodeIdentity>, Shutdown) {
+    let (notif_tx, server_hnd, context, shutdown) = setup_service_with_builder(builder, service_impl).await;
     let (_, inbound, outbound) = build_multiplexed_connections().await;
     let substream = outbound.get_yamux_control().open_stream().await.unwrap();
 
@@ -364,6 +391,92 @@ async fn rejected_no_sessions_available() {
     ));
 }
 
+#[runtime::test]
+async fn rejected_maximum_sessions_per_client() {
+    let (mut muxer, _outbound, _, _, _shutdown) = setup_with_builder(
+        RpcServer::builder().with_maximum_sessions_per_client(0),
+        GreetingService::default(),
+    )
+    .await;
+    let socket = muxer.incoming_mut().next().await.unwrap();
+    let framed = framing::canonical(socket, 1024);
+    let err = GreetingClient::builder().connect(framed).await.unwrap_err();
+    assert!(matches!(
+        err,
+        RpcError::HandshakeError(RpcHandshakeError::Rejected(HandshakeRejectReason::NoSessionsAvailable))
+    ));
+}
+
+#[runtime::test]
+async fn busy_when_request_budget_exhausted() {
+    let (mut muxer, _outbound, _, _, _shutdown) = setup_with_builder(
+        RpcServer::builder().with_client_request_budget(1, Duration::from_secs(100)),
+        GreetingService::default(),
+    )
+    .await;
+    let socket = muxer.incoming_mut().next().await.unwrap();
+    let framed = framing::canonical(socket, 1024);
+    let mut client = GreetingClient::builder()
+        .with_max_busy_retries(0)
+        .connect(framed)
+        .await
+        .unwrap();
+
+    let req = || SayHelloRequest {
+        name: "Yathvan".to_string(),
+        language: 1,
+    };
+    client.say_hello(req()).await.unwrap();
+    let err = client.say_hello(req()).await.unwrap_err();
+    unpack_enum!(RpcError::RequestFailed(status) = err);
+    assert_eq!(status.status_code(), RpcStatusCode::Busy);
+    assert!(status.retry_after().unwrap() > Duration::from_secs(90));
+}
+
+#[runtime::test]
+async fn unsupported_methods_are_not_used_as_metric_labels() {
+    let (mut muxer, _outbound, _, _, _shutdown) = setup(GreetingService::default(), 1).await;
+    let socket = muxer.incoming_mut().next().await.unwrap();
+    let framed = framing::canonical(socket, 1024);
+    let mut client = rpc::RpcClient::connect(
+        Default::default(),
+        framed,
+        ProtocolId::from_static(b"/test/greeting/1.0"),
+    )
+    .await
+    .unwrap();
+
+    let err = client.request_response::<_, (), _>((), 0xdead_beef).await.unwrap_err();
+    unpack_enum!(RpcError::RequestFailed(status) = err);
+    assert_eq!(status.status_code(), RpcStatusCode::UnsupportedMethod);
+
+    let metrics = tari_metrics::encode_text().unwrap();
+    assert!(!metrics.contains(&format!("method=\"{}\"", 0xdead_beef_u32)));
+    assert!(metrics
+        .lines()
+        .any(|l| l.contains("method=\"unknown\"") && l.contains("status=\"unsupported_method\"")));
+}
+
+#[runtime::test]
+async fn client_retries_when_busy() {
+    let (mut muxer, _outbound, _, _, _shutdown) = setup_with_builder(
+        RpcServer::builder().with_client_request_budget(1, Duration::from_millis(100)),
+        GreetingService::default(),
+    )
+    .await;
+    let socket = muxer.incoming_mut().next().await.unwrap();
+    let framed = framing::canonical(socket, 1024);
+    let mut client = GreetingClient::builder().connect(framed).await.unwrap();
+
+    let req = || SayHelloRequest {
+        name: "Yathvan".to_string(),
+        language: 1,
+    };
+    client.say_hello(req()).await.unwrap();
+    let resp = client.say_hello(req()).await.unwrap();
+    assert_eq!(resp.greeting, "Jambo Yathvan");
+}
+
 #[runtime::test]
 async fn stream_still_works_after_cancel() {
     let service_impl = GreetingService::default();
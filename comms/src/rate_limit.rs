@@ -23,17 +23,21 @@
 //! Rate limited flow control implementation that allows a certain number of items to be obtained from the stream within
 //! a given time interval. The underlying stream will begin to buffer and produce backpressure if producers exceed the
 //! capacity and restock_intervals.
+//!
+//! A synchronous [TokenBucket](self::TokenBucket) with the same capacity and restock semantics is also provided for
+//! cases where the caller should be rejected rather than made to wait.
 
 // This is slightly changed from the libra rate limiter implementation
 
 use futures::FutureExt;
 use pin_project::pin_project;
 use std::{
+    cmp,
     future::Future,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{
     sync::{AcquireError, OwnedSemaphorePermit, Semaphore},
@@ -133,6 +137,72 @@ impl<T: Stream> Stream for RateLimiter<T> {
     }
 }
 
+/// Allows a maximum of `capacity` tokens to be taken within `restock_interval`. Unlike the
+/// [RateLimiter](self::RateLimiter), tokens are never waited for: if there are not enough tokens available, the caller
+/// is told how long to wait before the bucket is restocked.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: usize,
+    restock_interval: Duration,
+    available: usize,
+    last_restock: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: usize, restock_interval: Duration) -> Self {
+        Self {
+            capacity,
+            restock_interval,
+            available: capacity,
+            last_restock: Instant::now(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of tokens that can currently be taken
+    pub fn available(&mut self) -> usize {
+        self.restock();
+        self.available
+    }
+
+    /// Attempts to take `num_tokens` from the bucket. If there are not enough tokens, the duration until the next
+    /// restock is returned. Requests for more tokens than the capacity of the bucket are limited to the capacity, so
+    /// that they can succeed when the bucket is full.
+    pub fn try_take(&mut self, num_tokens: usize) -> Result<(), Duration> {
+        self.restock();
+        let num_tokens = cmp::min(num_tokens, self.capacity);
+        if num_tokens > self.available {
+            return Err(self.time_until_restock());
+        }
+        self.available -= num_tokens;
+        Ok(())
+    }
+
+    /// Takes as many of `num_tokens` as are available and returns the number of tokens taken. This is used to account
+    /// for tokens that have already been used, so it never fails.
+    pub fn take_up_to(&mut self, num_tokens: usize) -> usize {
+        self.restock();
+        let num_tokens = cmp::min(num_tokens, self.available);
+        self.available -= num_tokens;
+        num_tokens
+    }
+
+    /// Returns the time remaining until the bucket is restocked
+    pub fn time_until_restock(&self) -> Duration {
+        self.restock_interval.saturating_sub(self.last_restock.elapsed())
+    }
+
+    fn restock(&mut self) {
+        if self.last_restock.elapsed() >= self.restock_interval {
+            self.available = self.capacity;
+            self.last_restock = Instant::now();
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -190,4 +260,46 @@ mod test {
         // Test that at least 1 restock happens.
         assert!(count > 10);
     }
+
+    mod token_bucket {
+        use super::*;
+        use std::thread;
+
+        #[test]
+        fn it_limits_tokens_to_capacity() {
+            let mut bucket = TokenBucket::new(10, Duration::from_secs(100));
+            bucket.try_take(4).unwrap();
+            bucket.try_take(6).unwrap();
+            assert_eq!(bucket.available(), 0);
+            let retry_after = bucket.try_take(1).unwrap_err();
+            assert!(retry_after > Duration::from_secs(99));
+        }
+
+        #[test]
+        fn it_allows_a_take_larger_than_capacity_when_full() {
+            let mut bucket = TokenBucket::new(10, Duration::from_secs(100));
+            bucket.try_take(100).unwrap();
+            assert_eq!(bucket.available(), 0);
+            bucket.try_take(100).unwrap_err();
+        }
+
+        #[test]
+        fn it_takes_up_to_the_available_tokens() {
+            let mut bucket = TokenBucket::new(10, Duration::from_secs(100));
+            assert_eq!(bucket.take_up_to(4), 4);
+            assert_eq!(bucket.take_up_to(100), 6);
+            assert_eq!(bucket.take_up_to(1), 0);
+            assert!(bucket.time_until_restock() > Duration::from_secs(99));
+        }
+
+        #[test]
+        fn it_restocks() {
+            let mut bucket = TokenBucket::new(2, Duration::from_millis(10));
+            bucket.try_take(2).unwrap();
+            bucket.try_take(1).unwrap_err();
+            thread::sleep(Duration::from_millis(15));
+            assert_eq!(bucket.available(), 2);
+            bucket.try_take(1).unwrap();
+        }
+    }
 }
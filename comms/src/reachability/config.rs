@@ -0,0 +1,84 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::multiaddr::Multiaddr;
+use std::{net::Ipv4Addr, time::Duration};
+
+#[derive(Debug, Clone)]
+pub struct ReachabilityConfig {
+    /// True if this node should periodically ask peers to dial back its public address, otherwise false. Dial back
+    /// requests from other peers are always handled.
+    /// Default: true
+    pub is_self_check_enabled: bool,
+    /// The time to wait after startup before the first check. This gives the node time to connect to peers.
+    /// Default: 2 minutes
+    pub initial_check_delay: Duration,
+    /// The interval between reachability checks.
+    /// Default: 30 minutes
+    pub check_interval: Duration,
+    /// The number of connected base nodes that are asked to dial back this node's addresses.
+    /// Default: 3
+    pub num_peers_to_ask: usize,
+    /// The time to wait for a peer to respond to a dial back request.
+    /// Default: 60s
+    pub dial_back_timeout: Duration,
+    /// The maximum time to spend dialing back a single address for another peer.
+    /// Default: 20s
+    pub probe_timeout: Duration,
+    /// The maximum number of dial back requests from other peers that are handled concurrently. Requests in excess of
+    /// this are dropped.
+    /// Default: 10
+    pub max_concurrent_dial_back_requests: usize,
+    /// True to attempt to map the listener port on the local gateway using NAT-PMP (and UPnP if the `upnp` feature is
+    /// enabled).
+    /// Default: false
+    pub is_port_mapping_enabled: bool,
+    /// The gateway to send NAT-PMP requests to. If not set, the default route gateway is used where it can be
+    /// determined.
+    /// Default: None
+    pub port_mapping_gateway: Option<Ipv4Addr>,
+    /// The requested lifetime of port mappings. Mappings are renewed on every check, so this should be longer than
+    /// `check_interval`.
+    /// Default: 1 hour
+    pub port_mapping_lifetime: Duration,
+    /// An address (typically a tor onion address) to advertise if none of the node's other addresses are reachable.
+    /// Default: None
+    pub tor_fallback_address: Option<Multiaddr>,
+}
+
+impl Default for ReachabilityConfig {
+    fn default() -> Self {
+        Self {
+            is_self_check_enabled: true,
+            initial_check_delay: Duration::from_secs(2 * 60),
+            check_interval: Duration::from_secs(30 * 60),
+            num_peers_to_ask: 3,
+            dial_back_timeout: Duration::from_secs(60),
+            probe_timeout: Duration::from_secs(20),
+            max_concurrent_dial_back_requests: 10,
+            is_port_mapping_enabled: false,
+            port_mapping_gateway: None,
+            port_mapping_lifetime: Duration::from_secs(60 * 60),
+            tor_fallback_address: None,
+        }
+    }
+}
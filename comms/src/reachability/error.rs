@@ -0,0 +1,94 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    connection_manager::PeerConnectionError,
+    connectivity::ConnectivityError,
+    peer_manager::PeerManagerError,
+    protocol::ReachabilityProtocolError,
+};
+use std::io;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReachabilityError {
+    #[error("Connectivity error: {0}")]
+    ConnectivityError(#[from] ConnectivityError),
+    #[error("Peer manager error: {0}")]
+    PeerManagerError(#[from] PeerManagerError),
+    #[error("Reachability protocol error: {0}")]
+    ProtocolError(#[from] ReachabilityProtocolError),
+    #[error("Peer connection error: {0}")]
+    PeerConnectionError(#[from] PeerConnectionError),
+    #[error("No connected base nodes are available to perform a reachability check")]
+    NoPeersAvailable,
+    #[error("None of the connected peers responded to the reachability check")]
+    NoPeersResponded,
+    #[error("The node does not advertise any addresses that can be checked")]
+    NoCheckableAddresses,
+    #[error("The peer requesting a dial back is not connected")]
+    PeerNotConnected,
+    #[error("Failed to send request to the reachability service")]
+    SendToServiceFailed,
+    #[error("The reachability service cancelled the request")]
+    RequestCancelled,
+}
+
+#[derive(Debug, Error)]
+pub enum PortMappingError {
+    #[error("IO error: {0}")]
+    IoError(#[from] io::Error),
+    #[error("Unable to determine the local gateway address")]
+    GatewayNotFound,
+    #[error("The gateway did not respond")]
+    GatewayTimeout,
+    #[error("The gateway rejected the request with result code {0}")]
+    GatewayRejectedRequest(u16),
+    #[error("The gateway sent an invalid response: {0}")]
+    InvalidResponse(String),
+    #[error("Gateway error: {0}")]
+    GatewayError(String),
+}
+
+#[cfg(feature = "upnp")]
+mod upnp_errors {
+    use super::PortMappingError;
+
+    macro_rules! impl_from_igd_error {
+        ($($err:ty),+) => {
+            $(
+                impl From<$err> for PortMappingError {
+                    fn from(err: $err) -> Self {
+                        PortMappingError::GatewayError(err.to_string())
+                    }
+                }
+            )+
+        };
+    }
+
+    impl_from_igd_error!(
+        igd::SearchError,
+        igd::GetExternalIpError,
+        igd::AddPortError,
+        igd::RemovePortError
+    );
+}
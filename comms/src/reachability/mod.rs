@@ -0,0 +1,55 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Reachability
+//!
+//! The reachability service periodically asks connected base nodes to dial back this node's public address using the
+//! [reachability protocol](crate::protocol::REACHABILITY_PROTOCOL) and reports whether the address is reachable,
+//! unreachable or only partially reachable. This helps operators that run nodes behind NAT to discover that their
+//! advertised address is unusable.
+//!
+//! Optionally, the service attempts to map the listener port on the local gateway using NAT-PMP (or UPnP when the
+//! `upnp` feature is enabled) and falls back to advertising a configured tor address if no other address is reachable.
+//!
+//! The service also responds to dial back requests from other peers.
+
+mod config;
+pub use config::ReachabilityConfig;
+
+mod error;
+pub use error::{PortMappingError, ReachabilityError};
+
+mod port_mapping;
+pub use port_mapping::{MappingProtocol, PortMapping, PortMappingMethod};
+
+mod report;
+pub use report::{AddressReachability, ReachabilityReport, ReachabilityStatus};
+
+mod requester;
+pub(crate) use requester::ReachabilityRequest;
+pub use requester::ReachabilityRequester;
+
+mod service;
+pub(crate) use service::ReachabilityService;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,426 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Best-effort port mapping against the local internet gateway using NAT-PMP ([RFC 6886](https://tools.ietf.org/html/rfc6886))
+//! and, if the `upnp` feature is enabled, UPnP IGD.
+
+use super::error::PortMappingError;
+use crate::multiaddr::{Multiaddr, Protocol};
+use log::*;
+use std::{
+    fmt,
+    net::{Ipv4Addr, SocketAddrV4},
+    time::Duration,
+};
+use tokio::{net::UdpSocket, time};
+
+const LOG_TARGET: &str = "comms::reachability::port_mapping";
+
+const NAT_PMP_PORT: u16 = 5351;
+const NAT_PMP_VERSION: u8 = 0;
+const NAT_PMP_OP_EXTERNAL_ADDRESS: u8 = 0;
+const NAT_PMP_OP_MAP_UDP: u8 = 1;
+const NAT_PMP_OP_MAP_TCP: u8 = 2;
+const NAT_PMP_RESPONSE_FLAG: u8 = 128;
+/// Number of attempts before giving up on the gateway. The first attempt waits 250ms for a response and each
+/// subsequent attempt doubles this time as recommended in the RFC.
+const NAT_PMP_MAX_ATTEMPTS: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingProtocol {
+    Tcp,
+    Udp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortMappingMethod {
+    NatPmp,
+    #[cfg(feature = "upnp")]
+    Upnp,
+}
+
+impl fmt::Display for PortMappingMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortMappingMethod::NatPmp => write!(f, "NAT-PMP"),
+            #[cfg(feature = "upnp")]
+            PortMappingMethod::Upnp => write!(f, "UPnP"),
+        }
+    }
+}
+
+/// A port mapping that has been established on the gateway
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortMapping {
+    pub method: PortMappingMethod,
+    pub protocol: MappingProtocol,
+    pub gateway: Ipv4Addr,
+    pub internal_port: u16,
+    pub external_address: Ipv4Addr,
+    pub external_port: u16,
+    pub lifetime: Duration,
+}
+
+impl PortMapping {
+    /// The public address that peers should use to reach this node through the mapping
+    pub fn to_multiaddr(&self) -> Multiaddr {
+        let mut addr = Multiaddr::empty();
+        addr.push(Protocol::Ip4(self.external_address));
+        match self.protocol {
+            MappingProtocol::Tcp => addr.push(Protocol::Tcp(self.external_port)),
+            MappingProtocol::Udp => {
+                addr.push(Protocol::Udp(self.external_port));
+                addr.push(Protocol::Quic);
+            },
+        }
+        addr
+    }
+}
+
+/// The details of a mapping to request from the gateway
+#[derive(Debug, Clone, Copy)]
+pub struct PortMappingRequest {
+    pub protocol: MappingProtocol,
+    pub internal_port: u16,
+    pub suggested_external_port: u16,
+    pub lifetime: Duration,
+}
+
+impl PortMappingRequest {
+    /// Construct a mapping request for the given listener address. The port of `public_address` is used as the
+    /// suggested external port if it has the same transport protocol as the listener. Returns None if the listener
+    /// address is not a TCP or QUIC address.
+    pub fn from_addresses(
+        listener_address: &Multiaddr,
+        public_address: &Multiaddr,
+        lifetime: Duration,
+    ) -> Option<Self> {
+        let (protocol, internal_port) = transport_port(listener_address)?;
+        if internal_port == 0 {
+            return None;
+        }
+        let suggested_external_port = transport_port(public_address)
+            .filter(|(p, port)| *p == protocol && *port != 0)
+            .map(|(_, port)| port)
+            .unwrap_or(internal_port);
+        Some(Self {
+            protocol,
+            internal_port,
+            suggested_external_port,
+            lifetime,
+        })
+    }
+}
+
+fn transport_port(address: &Multiaddr) -> Option<(MappingProtocol, u16)> {
+    address.iter().find_map(|p| match p {
+        Protocol::Tcp(port) => Some((MappingProtocol::Tcp, port)),
+        Protocol::Udp(port) => Some((MappingProtocol::Udp, port)),
+        _ => None,
+    })
+}
+
+/// Attempt to map a port on the gateway. NAT-PMP is tried first, followed by UPnP if the `upnp` feature is enabled.
+pub async fn map_port(gateway: Option<Ipv4Addr>, request: PortMappingRequest) -> Result<PortMapping, PortMappingError> {
+    let gateway = gateway.or_else(default_gateway);
+
+    let nat_pmp_err = match gateway {
+        Some(gateway) => match nat_pmp_map_port(gateway, request).await {
+            Ok(mapping) => return Ok(mapping),
+            Err(err) => {
+                debug!(target: LOG_TARGET, "NAT-PMP port mapping failed: {}", err);
+                err
+            },
+        },
+        None => PortMappingError::GatewayNotFound,
+    };
+
+    #[cfg(feature = "upnp")]
+    {
+        match upnp_map_port(request).await {
+            Ok(mapping) => return Ok(mapping),
+            Err(err) => debug!(target: LOG_TARGET, "UPnP port mapping failed: {}", err),
+        }
+    }
+
+    Err(nat_pmp_err)
+}
+
+/// Remove a previously established mapping from the gateway
+pub async fn remove_mapping(mapping: &PortMapping) -> Result<(), PortMappingError> {
+    match mapping.method {
+        PortMappingMethod::NatPmp => {
+            // A mapping request with a lifetime and suggested external port of zero deletes the mapping
+            let request = PortMappingRequest {
+                protocol: mapping.protocol,
+                internal_port: mapping.internal_port,
+                suggested_external_port: 0,
+                lifetime: Duration::from_secs(0),
+            };
+            nat_pmp_map_port(mapping.gateway, request).await?;
+            Ok(())
+        },
+        #[cfg(feature = "upnp")]
+        PortMappingMethod::Upnp => {
+            let mapping = mapping.clone();
+            tokio::task::spawn_blocking(move || {
+                let gateway = igd::search_gateway(Default::default())?;
+                gateway.remove_port(to_igd_protocol(mapping.protocol), mapping.external_port)?;
+                Ok(())
+            })
+            .await
+            .map_err(|err| PortMappingError::GatewayError(err.to_string()))?
+        },
+    }
+}
+
+async fn nat_pmp_map_port(gateway: Ipv4Addr, request: PortMappingRequest) -> Result<PortMapping, PortMappingError> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.connect(SocketAddrV4::new(gateway, NAT_PMP_PORT)).await?;
+
+    let external_address = {
+        let response = nat_pmp_request(&socket, &[NAT_PMP_VERSION, NAT_PMP_OP_EXTERNAL_ADDRESS]).await?;
+        let body = parse_nat_pmp_response(&response, NAT_PMP_OP_EXTERNAL_ADDRESS, 12)?;
+        Ipv4Addr::new(body[0], body[1], body[2], body[3])
+    };
+
+    let op = match request.protocol {
+        MappingProtocol::Udp => NAT_PMP_OP_MAP_UDP,
+        MappingProtocol::Tcp => NAT_PMP_OP_MAP_TCP,
+    };
+    let lifetime = lifetime_secs(request.lifetime);
+    let mut msg = Vec::with_capacity(12);
+    msg.extend_from_slice(&[NAT_PMP_VERSION, op, 0, 0]);
+    msg.extend_from_slice(&request.internal_port.to_be_bytes());
+    msg.extend_from_slice(&request.suggested_external_port.to_be_bytes());
+    msg.extend_from_slice(&lifetime.to_be_bytes());
+    let response = nat_pmp_request(&socket, &msg).await?;
+    let body = parse_nat_pmp_response(&response, op, 16)?;
+    let internal_port = u16::from_be_bytes([body[0], body[1]]);
+    let external_port = u16::from_be_bytes([body[2], body[3]]);
+    let lifetime = u32::from_be_bytes([body[4], body[5], body[6], body[7]]);
+
+    if internal_port != request.internal_port {
+        return Err(PortMappingError::InvalidResponse(format!(
+            "Gateway mapped internal port {} but {} was requested",
+            internal_port, request.internal_port
+        )));
+    }
+
+    Ok(PortMapping {
+        method: PortMappingMethod::NatPmp,
+        protocol: request.protocol,
+        gateway,
+        internal_port,
+        external_address,
+        external_port,
+        lifetime: Duration::from_secs(u64::from(lifetime)),
+    })
+}
+
+async fn nat_pmp_request(socket: &UdpSocket, msg: &[u8]) -> Result<Vec<u8>, PortMappingError> {
+    let mut buf = [0u8; 16];
+    let mut wait = Duration::from_millis(250);
+    for _ in 0..NAT_PMP_MAX_ATTEMPTS {
+        socket.send(msg).await?;
+        match time::timeout(wait, socket.recv(&mut buf)).await {
+            Ok(Ok(n)) => return Ok(buf[..n].to_vec()),
+            Ok(Err(err)) => return Err(err.into()),
+            Err(_) => {
+                wait *= 2;
+            },
+        }
+    }
+    Err(PortMappingError::GatewayTimeout)
+}
+
+/// Validates the common NAT-PMP response header and returns the opcode-specific body that follows the seconds since
+/// epoch field.
+fn parse_nat_pmp_response(response: &[u8], op: u8, expected_len: usize) -> Result<&[u8], PortMappingError> {
+    if response.len() < expected_len {
+        return Err(PortMappingError::InvalidResponse(format!(
+            "Expected {} bytes but got {}",
+            expected_len,
+            response.len()
+        )));
+    }
+    if response[0] != NAT_PMP_VERSION || response[1] != NAT_PMP_RESPONSE_FLAG + op {
+        return Err(PortMappingError::InvalidResponse(format!(
+            "Unexpected version {} or opcode {}",
+            response[0], response[1]
+        )));
+    }
+    let result_code = u16::from_be_bytes([response[2], response[3]]);
+    if result_code != 0 {
+        return Err(PortMappingError::GatewayRejectedRequest(result_code));
+    }
+    Ok(&response[8..expected_len])
+}
+
+fn lifetime_secs(lifetime: Duration) -> u32 {
+    lifetime.as_secs().min(u64::from(u32::MAX)) as u32
+}
+
+#[cfg(feature = "upnp")]
+async fn upnp_map_port(request: PortMappingRequest) -> Result<PortMapping, PortMappingError> {
+    tokio::task::spawn_blocking(move || {
+        let gateway = igd::search_gateway(Default::default())?;
+        let local_ip = local_address_for(*gateway.addr.ip())?;
+        let external_address = gateway.get_external_ip()?;
+        let lifetime = lifetime_secs(request.lifetime);
+        gateway.add_port(
+            to_igd_protocol(request.protocol),
+            request.suggested_external_port,
+            SocketAddrV4::new(local_ip, request.internal_port),
+            lifetime,
+            "tari",
+        )?;
+        Ok(PortMapping {
+            method: PortMappingMethod::Upnp,
+            protocol: request.protocol,
+            gateway: *gateway.addr.ip(),
+            internal_port: request.internal_port,
+            external_address,
+            external_port: request.suggested_external_port,
+            lifetime: request.lifetime,
+        })
+    })
+    .await
+    .map_err(|err| PortMappingError::GatewayError(err.to_string()))?
+}
+
+#[cfg(feature = "upnp")]
+fn to_igd_protocol(protocol: MappingProtocol) -> igd::PortMappingProtocol {
+    match protocol {
+        MappingProtocol::Tcp => igd::PortMappingProtocol::TCP,
+        MappingProtocol::Udp => igd::PortMappingProtocol::UDP,
+    }
+}
+
+/// Returns the local address that the OS would use to route packets to the given address
+#[cfg(feature = "upnp")]
+fn local_address_for(remote: Ipv4Addr) -> Result<Ipv4Addr, PortMappingError> {
+    let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.connect(SocketAddrV4::new(remote, NAT_PMP_PORT))?;
+    match socket.local_addr()?.ip() {
+        std::net::IpAddr::V4(ip) => Ok(ip),
+        std::net::IpAddr::V6(_) => Err(PortMappingError::GatewayNotFound),
+    }
+}
+
+/// Returns the IPv4 default gateway from the kernel routing table
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    parse_default_gateway(&routes)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<Ipv4Addr> {
+    None
+}
+
+/// Parses the default gateway from the contents of /proc/net/route. Addresses in this file are hex encoded in host
+/// (little endian) byte order.
+#[cfg_attr(not(any(test, target_os = "linux")), allow(dead_code))]
+fn parse_default_gateway(routes: &str) -> Option<Ipv4Addr> {
+    routes.lines().skip(1).find_map(|line| {
+        let mut cols = line.split_whitespace();
+        let _iface = cols.next()?;
+        let destination = cols.next()?;
+        let gateway = cols.next()?;
+        if destination != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(gateway, 16).ok()?;
+        if gateway == 0 {
+            return None;
+        }
+        Some(Ipv4Addr::from(gateway.to_le_bytes()))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_default_gateway() {
+        let routes = "Iface\tDestination\tGateway \
+                      \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\neth0\t0001A8C0\t00000000\t0001\t0\t0\\
+                      t0\t00FFFFFF\t0\t0\t0\neth0\t00000000\t0101A8C0\t0003\t0\t0\t0\t00000000\t0\t0\t0\n";
+        assert_eq!(parse_default_gateway(routes), Some(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(parse_default_gateway("Iface\tDestination\tGateway\n"), None);
+    }
+
+    #[test]
+    fn it_validates_nat_pmp_responses() {
+        let response = [0, 130, 0, 0, 0, 0, 0, 1, 0x46, 0xBD, 0x46, 0xBE, 0, 0, 0x0E, 0x10];
+        let body = parse_nat_pmp_response(&response, NAT_PMP_OP_MAP_TCP, 16).unwrap();
+        assert_eq!(u16::from_be_bytes([body[0], body[1]]), 18109);
+        assert_eq!(u16::from_be_bytes([body[2], body[3]]), 18110);
+        assert_eq!(u32::from_be_bytes([body[4], body[5], body[6], body[7]]), 3600);
+
+        let mut rejected = response;
+        rejected[3] = 2;
+        let err = parse_nat_pmp_response(&rejected, NAT_PMP_OP_MAP_TCP, 16).unwrap_err();
+        assert!(matches!(err, PortMappingError::GatewayRejectedRequest(2)));
+
+        let err = parse_nat_pmp_response(&response, NAT_PMP_OP_MAP_UDP, 16).unwrap_err();
+        assert!(matches!(err, PortMappingError::InvalidResponse(_)));
+        let err = parse_nat_pmp_response(&response[..10], NAT_PMP_OP_MAP_TCP, 16).unwrap_err();
+        assert!(matches!(err, PortMappingError::InvalidResponse(_)));
+    }
+
+    #[test]
+    fn it_builds_mapping_requests() {
+        let listener = "/ip4/0.0.0.0/tcp/18189".parse().unwrap();
+        let public = "/ip4/1.2.3.4/tcp/18000".parse().unwrap();
+        let request = PortMappingRequest::from_addresses(&listener, &public, Duration::from_secs(60)).unwrap();
+        assert_eq!(request.protocol, MappingProtocol::Tcp);
+        assert_eq!(request.internal_port, 18189);
+        assert_eq!(request.suggested_external_port, 18000);
+
+        let public = "/dns4/my.node/udp/18000/quic".parse().unwrap();
+        let request = PortMappingRequest::from_addresses(&listener, &public, Duration::from_secs(60)).unwrap();
+        assert_eq!(request.suggested_external_port, 18189);
+
+        let listener = "/memory/123".parse().unwrap();
+        assert!(PortMappingRequest::from_addresses(&listener, &public, Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn it_converts_mappings_to_multiaddrs() {
+        let mut mapping = PortMapping {
+            method: PortMappingMethod::NatPmp,
+            protocol: MappingProtocol::Tcp,
+            gateway: Ipv4Addr::new(192, 168, 1, 1),
+            internal_port: 18189,
+            external_address: Ipv4Addr::new(1, 2, 3, 4),
+            external_port: 18190,
+            lifetime: Duration::from_secs(60),
+        };
+        assert_eq!(mapping.to_multiaddr().to_string(), "/ip4/1.2.3.4/tcp/18190");
+        mapping.protocol = MappingProtocol::Udp;
+        assert_eq!(mapping.to_multiaddr().to_string(), "/ip4/1.2.3.4/udp/18190/quic");
+    }
+}
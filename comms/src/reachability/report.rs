@@ -0,0 +1,208 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::port_mapping::PortMapping;
+use crate::multiaddr::Multiaddr;
+use chrono::{NaiveDateTime, Utc};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReachabilityStatus {
+    /// No reachability check has completed
+    Unknown,
+    /// All peers were able to dial back all checked addresses
+    Reachable,
+    /// Some addresses could only be reached by some peers
+    PartiallyReachable,
+    /// No peer was able to dial back any checked address
+    Unreachable,
+}
+
+impl ReachabilityStatus {
+    pub fn is_reachable(self) -> bool {
+        matches!(
+            self,
+            ReachabilityStatus::Reachable | ReachabilityStatus::PartiallyReachable
+        )
+    }
+
+    /// Combine the statuses of multiple addresses into a single status
+    pub fn combine<I: IntoIterator<Item = ReachabilityStatus>>(statuses: I) -> Self {
+        use ReachabilityStatus::*;
+        statuses.into_iter().fold(Unknown, |acc, status| match (acc, status) {
+            (Unknown, s) | (s, Unknown) => s,
+            (Reachable, Reachable) => Reachable,
+            (Unreachable, Unreachable) => Unreachable,
+            _ => PartiallyReachable,
+        })
+    }
+}
+
+impl Default for ReachabilityStatus {
+    fn default() -> Self {
+        ReachabilityStatus::Unknown
+    }
+}
+
+impl fmt::Display for ReachabilityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ReachabilityStatus::*;
+        match self {
+            Unknown => write!(f, "Unknown"),
+            Reachable => write!(f, "Reachable"),
+            PartiallyReachable => write!(f, "Partially reachable"),
+            Unreachable => write!(f, "Unreachable"),
+        }
+    }
+}
+
+/// The result of asking peers to dial back a single address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressReachability {
+    pub address: Multiaddr,
+    /// The number of peers that were able to dial back the address
+    pub num_reachable: usize,
+    /// The number of peers that attempted to dial back the address
+    pub num_checked: usize,
+    /// The last dial back error reported by a peer, if any
+    pub last_error: Option<String>,
+}
+
+impl AddressReachability {
+    pub fn new(address: Multiaddr) -> Self {
+        Self {
+            address,
+            num_reachable: 0,
+            num_checked: 0,
+            last_error: None,
+        }
+    }
+
+    pub fn status(&self) -> ReachabilityStatus {
+        match self.num_reachable {
+            _ if self.num_checked == 0 => ReachabilityStatus::Unknown,
+            0 => ReachabilityStatus::Unreachable,
+            n if n == self.num_checked => ReachabilityStatus::Reachable,
+            _ => ReachabilityStatus::PartiallyReachable,
+        }
+    }
+}
+
+impl fmt::Display for AddressReachability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ({}/{} peers)",
+            self.address,
+            self.status(),
+            self.num_reachable,
+            self.num_checked
+        )?;
+        if let Some(err) = self.last_error.as_ref() {
+            write!(f, " last error: {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+/// The latest reachability information for this node
+#[derive(Debug, Clone, Default)]
+pub struct ReachabilityReport {
+    pub status: ReachabilityStatus,
+    pub addresses: Vec<AddressReachability>,
+    /// The port mapping established on the local gateway, if any
+    pub port_mapping: Option<PortMapping>,
+    /// True if the node is advertising the configured tor fallback address because no other address is reachable
+    pub is_tor_fallback_active: bool,
+    pub last_checked: Option<NaiveDateTime>,
+    /// The reason the last check could not be completed, if any
+    pub last_error: Option<String>,
+}
+
+impl ReachabilityReport {
+    pub(super) fn set_checked(&mut self) {
+        self.last_checked = Some(Utc::now().naive_utc());
+    }
+}
+
+impl fmt::Display for ReachabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reachability: {}", self.status)?;
+        match self.last_checked {
+            Some(checked) => write!(f, " (last checked {})", checked.format("%Y-%m-%d %H:%M:%S"))?,
+            None => write!(f, " (not checked yet)")?,
+        }
+        for address in &self.addresses {
+            write!(f, "\n  {}", address)?;
+        }
+        if let Some(mapping) = self.port_mapping.as_ref() {
+            write!(
+                f,
+                "\n  Port mapping ({}): {} -> {}",
+                mapping.method,
+                mapping.to_multiaddr(),
+                mapping.internal_port
+            )?;
+        }
+        if self.is_tor_fallback_active {
+            write!(f, "\n  Advertising tor fallback address")?;
+        }
+        if let Some(err) = self.last_error.as_ref() {
+            write!(f, "\n  Last check failed: {}", err)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_combines_statuses() {
+        use ReachabilityStatus::*;
+        assert_eq!(ReachabilityStatus::combine(vec![]), Unknown);
+        assert_eq!(ReachabilityStatus::combine(vec![Reachable, Unknown]), Reachable);
+        assert_eq!(ReachabilityStatus::combine(vec![Reachable, Reachable]), Reachable);
+        assert_eq!(ReachabilityStatus::combine(vec![Unreachable, Unreachable]), Unreachable);
+        assert_eq!(
+            ReachabilityStatus::combine(vec![Reachable, Unreachable]),
+            PartiallyReachable
+        );
+        assert_eq!(
+            ReachabilityStatus::combine(vec![PartiallyReachable, Reachable]),
+            PartiallyReachable
+        );
+    }
+
+    #[test]
+    fn it_determines_address_status() {
+        let mut address = AddressReachability::new("/ip4/1.2.3.4/tcp/18189".parse().unwrap());
+        assert_eq!(address.status(), ReachabilityStatus::Unknown);
+        address.num_checked = 3;
+        assert_eq!(address.status(), ReachabilityStatus::Unreachable);
+        address.num_reachable = 1;
+        assert_eq!(address.status(), ReachabilityStatus::PartiallyReachable);
+        address.num_reachable = 3;
+        assert_eq!(address.status(), ReachabilityStatus::Reachable);
+    }
+}
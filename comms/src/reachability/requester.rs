@@ -0,0 +1,64 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{error::ReachabilityError, report::ReachabilityReport};
+use tokio::sync::{mpsc, oneshot, watch};
+
+#[derive(Debug)]
+pub enum ReachabilityRequest {
+    /// Perform a reachability check now and reply with the resulting report. If a check is already in progress, the
+    /// reply is sent once it completes.
+    CheckNow(oneshot::Sender<ReachabilityReport>),
+}
+
+/// Handle to the reachability service
+#[derive(Debug, Clone)]
+pub struct ReachabilityRequester {
+    sender: mpsc::Sender<ReachabilityRequest>,
+    report_rx: watch::Receiver<ReachabilityReport>,
+}
+
+impl ReachabilityRequester {
+    pub fn new(sender: mpsc::Sender<ReachabilityRequest>, report_rx: watch::Receiver<ReachabilityReport>) -> Self {
+        Self { sender, report_rx }
+    }
+
+    /// Returns the report from the most recent reachability check
+    pub fn get_report(&self) -> ReachabilityReport {
+        self.report_rx.borrow().clone()
+    }
+
+    /// Returns a watch receiver that is updated after every reachability check
+    pub fn subscribe_reports(&self) -> watch::Receiver<ReachabilityReport> {
+        self.report_rx.clone()
+    }
+
+    /// Ask connected peers to dial back this node's addresses and return the resulting report
+    pub async fn check_now(&mut self) -> Result<ReachabilityReport, ReachabilityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ReachabilityRequest::CheckNow(reply_tx))
+            .await
+            .map_err(|_| ReachabilityError::SendToServiceFailed)?;
+        reply_rx.await.map_err(|_| ReachabilityError::RequestCancelled)
+    }
+}
@@ -0,0 +1,537 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{
+    config::ReachabilityConfig,
+    error::ReachabilityError,
+    port_mapping,
+    port_mapping::{PortMapping, PortMappingRequest},
+    report::{AddressReachability, ReachabilityReport, ReachabilityStatus},
+    requester::ReachabilityRequest,
+};
+use crate::{
+    bounded_executor::BoundedExecutor,
+    connection_manager::ConnectionManagerRequester,
+    connectivity::{ConnectivityRequester, ConnectivitySelection},
+    multiaddr::{Multiaddr, Protocol},
+    peer_manager::{NodeId, NodeIdentity, PeerManager},
+    protocol::{
+        request_dial_back,
+        respond_to_dial_back,
+        DialBackResult,
+        ProtocolEvent,
+        ProtocolNotification,
+        ProtocolNotificationRx,
+        REACHABILITY_PROTOCOL,
+    },
+    runtime::task,
+    Substream,
+};
+use futures::future;
+use log::*;
+use std::{net::IpAddr, sync::Arc};
+use tari_shutdown::ShutdownSignal;
+use tokio::{
+    sync::{mpsc, oneshot, watch},
+    task::JoinHandle,
+    time,
+    time::MissedTickBehavior,
+};
+
+const LOG_TARGET: &str = "comms::reachability";
+
+pub struct ReachabilityService {
+    pub config: ReachabilityConfig,
+    pub node_identity: Arc<NodeIdentity>,
+    pub peer_manager: Arc<PeerManager>,
+    pub connectivity: ConnectivityRequester,
+    pub connection_manager: ConnectionManagerRequester,
+    /// The address that the node is listening on. This is used as the internal address for port mapping.
+    pub listener_address: Multiaddr,
+    pub request_rx: mpsc::Receiver<ReachabilityRequest>,
+    pub protocol_rx: ProtocolNotificationRx<Substream>,
+    pub report_tx: watch::Sender<ReachabilityReport>,
+    pub shutdown_signal: ShutdownSignal,
+}
+
+impl ReachabilityService {
+    pub fn spawn(self) -> JoinHandle<()> {
+        let (check_result_tx, check_result_rx) = mpsc::channel(1);
+        ReachabilityServiceActor {
+            dial_back_executor: BoundedExecutor::from_current(self.config.max_concurrent_dial_back_requests),
+            configured_address: self.node_identity.public_address(),
+            config: self.config,
+            node_identity: self.node_identity,
+            peer_manager: self.peer_manager,
+            connectivity: self.connectivity,
+            connection_manager: self.connection_manager,
+            listener_address: self.listener_address,
+            request_rx: self.request_rx,
+            protocol_rx: self.protocol_rx,
+            report_tx: self.report_tx,
+            report: ReachabilityReport::default(),
+            check_result_tx,
+            check_result_rx,
+            is_checking: false,
+            pending_replies: Vec::new(),
+            port_mapping: None,
+            shutdown_signal: self.shutdown_signal,
+        }
+        .spawn()
+    }
+}
+
+struct CheckOutcome {
+    result: Result<Vec<AddressReachability>, ReachabilityError>,
+    port_mapping: Option<PortMapping>,
+}
+
+struct ReachabilityServiceActor {
+    config: ReachabilityConfig,
+    node_identity: Arc<NodeIdentity>,
+    peer_manager: Arc<PeerManager>,
+    connectivity: ConnectivityRequester,
+    connection_manager: ConnectionManagerRequester,
+    listener_address: Multiaddr,
+    /// The public address that the node was started with. Checks are always performed against this address, even if
+    /// the node is currently advertising another address.
+    configured_address: Multiaddr,
+    request_rx: mpsc::Receiver<ReachabilityRequest>,
+    protocol_rx: ProtocolNotificationRx<Substream>,
+    report_tx: watch::Sender<ReachabilityReport>,
+    report: ReachabilityReport,
+    check_result_tx: mpsc::Sender<CheckOutcome>,
+    check_result_rx: mpsc::Receiver<CheckOutcome>,
+    is_checking: bool,
+    pending_replies: Vec<oneshot::Sender<ReachabilityReport>>,
+    port_mapping: Option<PortMapping>,
+    dial_back_executor: BoundedExecutor,
+    shutdown_signal: ShutdownSignal,
+}
+
+impl ReachabilityServiceActor {
+    pub fn spawn(self) -> JoinHandle<()> {
+        task::spawn(self.run())
+    }
+
+    async fn run(mut self) {
+        debug!(target: LOG_TARGET, "Reachability service started");
+        let mut shutdown_signal = self.shutdown_signal.clone();
+        let start = time::Instant::now() + self.config.initial_check_delay;
+        let mut check_interval = time::interval_at(start, self.config.check_interval);
+        check_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                Some(request) = self.request_rx.recv() => self.handle_request(request),
+                Some(notification) = self.protocol_rx.recv() => self.handle_protocol_notification(notification),
+                Some(outcome) = self.check_result_rx.recv() => self.handle_check_outcome(outcome),
+                _ = check_interval.tick(), if self.config.is_self_check_enabled => self.start_check(),
+                _ = &mut shutdown_signal => {
+                    info!(target: LOG_TARGET, "Reachability service shutting down because the shutdown signal was received");
+                    break;
+                }
+            }
+        }
+
+        if let Some(mapping) = self.port_mapping.take() {
+            if let Err(err) = port_mapping::remove_mapping(&mapping).await {
+                debug!(target: LOG_TARGET, "Failed to remove port mapping: {}", err);
+            }
+        }
+    }
+
+    fn handle_request(&mut self, request: ReachabilityRequest) {
+        use ReachabilityRequest::*;
+        match request {
+            CheckNow(reply) => {
+                self.pending_replies.push(reply);
+                self.start_check();
+            },
+        }
+    }
+
+    fn handle_protocol_notification(&self, notification: ProtocolNotification<Substream>) {
+        match notification.event {
+            ProtocolEvent::NewInboundSubstream(node_id, substream) => {
+                let connectivity = self.connectivity.clone();
+                let connection_manager = self.connection_manager.clone();
+                let peer_manager = self.peer_manager.clone();
+                let config = self.config.clone();
+                let peer = node_id.clone();
+                let result = self.dial_back_executor.try_spawn(async move {
+                    if let Err(err) = handle_dial_back_request(
+                        config,
+                        node_id,
+                        substream,
+                        connectivity,
+                        connection_manager,
+                        peer_manager,
+                    )
+                    .await
+                    {
+                        debug!(target: LOG_TARGET, "Dial back request failed: {}", err);
+                    }
+                });
+                if result.is_err() {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Dropping dial back request from peer '{}' because the maximum number of concurrent requests \
+                         has been reached",
+                        peer.short_str()
+                    );
+                }
+            },
+        }
+    }
+
+    fn start_check(&mut self) {
+        if self.is_checking {
+            return;
+        }
+        self.is_checking = true;
+
+        let check = ReachabilityCheck {
+            config: self.config.clone(),
+            connectivity: self.connectivity.clone(),
+            listener_address: self.listener_address.clone(),
+            configured_address: self.configured_address.clone(),
+            port_mapping: self.port_mapping.clone(),
+        };
+        let check_result_tx = self.check_result_tx.clone();
+        task::spawn(async move {
+            let outcome = check.run().await;
+            let _ = check_result_tx.send(outcome).await;
+        });
+    }
+
+    fn handle_check_outcome(&mut self, outcome: CheckOutcome) {
+        self.is_checking = false;
+        self.port_mapping = outcome.port_mapping;
+
+        let mut report = self.report.clone();
+        report.port_mapping = self.port_mapping.clone();
+        report.set_checked();
+        match outcome.result {
+            Ok(addresses) => {
+                report.status = ReachabilityStatus::combine(addresses.iter().map(|a| a.status()));
+                report.addresses = addresses;
+                report.last_error = None;
+                self.update_public_address(&mut report);
+                info!(target: LOG_TARGET, "{}", report);
+            },
+            Err(err) => {
+                warn!(target: LOG_TARGET, "Reachability check failed: {}", err);
+                report.last_error = Some(err.to_string());
+            },
+        }
+
+        let _ = self.report_tx.send(report.clone());
+        for reply in self.pending_replies.drain(..) {
+            let _ = reply.send(report.clone());
+        }
+        self.report = report;
+    }
+
+    /// Selects the address to advertise based on the check results. The configured address is preferred if it is
+    /// reachable, followed by the port mapped address and finally the tor fallback address.
+    fn update_public_address(&self, report: &mut ReachabilityReport) {
+        let status_of = |address: &Multiaddr| {
+            report
+                .addresses
+                .iter()
+                .find(|a| a.address == *address)
+                .map(|a| a.status())
+                .unwrap_or_default()
+        };
+
+        let mapped_address = self.port_mapping.as_ref().map(|m| m.to_multiaddr());
+        let next_address = if status_of(&self.configured_address).is_reachable() {
+            Some(self.configured_address.clone())
+        } else if let Some(addr) = mapped_address.filter(|a| status_of(a).is_reachable()) {
+            Some(addr)
+        } else if report.status == ReachabilityStatus::Unreachable {
+            self.config.tor_fallback_address.clone()
+        } else {
+            None
+        };
+
+        let next_address = match next_address {
+            Some(addr) => addr,
+            None => return,
+        };
+
+        report.is_tor_fallback_active = self.config.tor_fallback_address.as_ref() == Some(&next_address);
+        if self.node_identity.public_address() != next_address {
+            info!(
+                target: LOG_TARGET,
+                "Changing this node's public address from '{}' to '{}'",
+                self.node_identity.public_address(),
+                next_address
+            );
+            self.node_identity.set_public_address(next_address);
+        }
+    }
+}
+
+/// A single reachability check. This is run in its own task so that the service can continue to respond to dial back
+/// requests from other peers while waiting for responses.
+struct ReachabilityCheck {
+    config: ReachabilityConfig,
+    connectivity: ConnectivityRequester,
+    listener_address: Multiaddr,
+    configured_address: Multiaddr,
+    port_mapping: Option<PortMapping>,
+}
+
+impl ReachabilityCheck {
+    async fn run(mut self) -> CheckOutcome {
+        let port_mapping = self.update_port_mapping().await;
+        let mut addresses = Vec::with_capacity(2);
+        if is_checkable_address(&self.configured_address) {
+            addresses.push(self.configured_address.clone());
+        }
+        if let Some(mapped) = port_mapping.as_ref().map(|m| m.to_multiaddr()) {
+            if !addresses.contains(&mapped) {
+                addresses.push(mapped);
+            }
+        }
+
+        let result = self.check_addresses(addresses).await;
+        CheckOutcome { result, port_mapping }
+    }
+
+    async fn update_port_mapping(&self) -> Option<PortMapping> {
+        if !self.config.is_port_mapping_enabled {
+            return None;
+        }
+
+        let request = PortMappingRequest::from_addresses(
+            &self.listener_address,
+            &self.configured_address,
+            self.config.port_mapping_lifetime,
+        )?;
+        match port_mapping::map_port(self.config.port_mapping_gateway, request).await {
+            Ok(mapping) => {
+                if self.port_mapping.as_ref() != Some(&mapping) {
+                    info!(
+                        target: LOG_TARGET,
+                        "Mapped port {} to '{}' using {}",
+                        mapping.internal_port,
+                        mapping.to_multiaddr(),
+                        mapping.method
+                    );
+                }
+                Some(mapping)
+            },
+            Err(err) => {
+                warn!(target: LOG_TARGET, "Port mapping failed: {}", err);
+                None
+            },
+        }
+    }
+
+    async fn check_addresses(
+        &mut self,
+        addresses: Vec<Multiaddr>,
+    ) -> Result<Vec<AddressReachability>, ReachabilityError> {
+        if addresses.is_empty() {
+            return Err(ReachabilityError::NoCheckableAddresses);
+        }
+
+        let conns = self
+            .connectivity
+            .select_connections(ConnectivitySelection::random_nodes(
+                self.config.num_peers_to_ask,
+                vec![],
+            ))
+            .await?
+            .into_iter()
+            .filter(|conn| conn.peer_features().is_node())
+            .collect::<Vec<_>>();
+
+        if conns.is_empty() {
+            return Err(ReachabilityError::NoPeersAvailable);
+        }
+
+        debug!(
+            target: LOG_TARGET,
+            "Asking {} peer(s) to dial back {} address(es)",
+            conns.len(),
+            addresses.len()
+        );
+
+        let timeout = self.config.dial_back_timeout;
+        let requests = conns.into_iter().map(|mut conn| {
+            let addresses = addresses.clone();
+            async move {
+                let result = async {
+                    let substream = conn.open_substream(&REACHABILITY_PROTOCOL).await?;
+                    let results = request_dial_back(substream.stream, &addresses, timeout).await?;
+                    Result::<_, ReachabilityError>::Ok(results)
+                }
+                .await;
+                (conn.peer_node_id().clone(), result)
+            }
+        });
+
+        let responses = future::join_all(requests).await;
+        let mut addresses = addresses.into_iter().map(AddressReachability::new).collect::<Vec<_>>();
+        let mut num_responses = 0;
+        for (peer, response) in responses {
+            match response {
+                Ok(results) => {
+                    num_responses += 1;
+                    aggregate_results(&mut addresses, results);
+                },
+                Err(err) => debug!(
+                    target: LOG_TARGET,
+                    "Peer '{}' failed to respond to dial back request: {}",
+                    peer.short_str(),
+                    err
+                ),
+            }
+        }
+
+        if num_responses == 0 {
+            return Err(ReachabilityError::NoPeersResponded);
+        }
+
+        Ok(addresses)
+    }
+}
+
+fn aggregate_results(addresses: &mut [AddressReachability], results: Vec<DialBackResult>) {
+    for result in results {
+        if let Some(address) = addresses.iter_mut().find(|a| a.address == result.address) {
+            address.num_checked += 1;
+            if result.is_reachable {
+                address.num_reachable += 1;
+            } else {
+                address.last_error = result.error;
+            }
+        }
+    }
+}
+
+/// Handles a dial back request from a peer. Only addresses that have the same IP as the connection to the requesting
+/// peer are dialed, so that this node cannot be used to probe arbitrary hosts.
+async fn handle_dial_back_request(
+    config: ReachabilityConfig,
+    node_id: NodeId,
+    substream: Substream,
+    mut connectivity: ConnectivityRequester,
+    connection_manager: ConnectionManagerRequester,
+    peer_manager: Arc<PeerManager>,
+) -> Result<(), ReachabilityError> {
+    let conn = connectivity
+        .get_connection(node_id.clone())
+        .await?
+        .ok_or(ReachabilityError::PeerNotConnected)?;
+    let peer = peer_manager.find_by_node_id(&node_id).await?;
+    let connection_address = conn.address().clone();
+
+    let num_dialed = respond_to_dial_back(substream, |address| {
+        let mut connection_manager = connection_manager.clone();
+        let public_key = peer.public_key.clone();
+        let is_allowed = is_dial_back_allowed(&connection_address, &address);
+        let probe_timeout = config.probe_timeout;
+        async move {
+            if !is_allowed {
+                return Err("Address does not match the address of the requesting peer".to_string());
+            }
+            time::timeout(probe_timeout, connection_manager.probe_address(public_key, address))
+                .await
+                .map_err(|_| "Timed out".to_string())?
+                .map_err(|err| err.to_string())
+        }
+    })
+    .await?;
+
+    debug!(
+        target: LOG_TARGET,
+        "Dialed back {} address(es) for peer '{}'",
+        num_dialed,
+        node_id.short_str()
+    );
+    Ok(())
+}
+
+fn ip_of(address: &Multiaddr) -> Option<IpAddr> {
+    address.iter().next().and_then(|p| match p {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+/// Returns true if the requested address is an IP address that matches the IP of the connection to the requesting peer
+fn is_dial_back_allowed(connection_address: &Multiaddr, requested_address: &Multiaddr) -> bool {
+    match (ip_of(connection_address), ip_of(requested_address)) {
+        (Some(conn_ip), Some(requested_ip)) => conn_ip == requested_ip,
+        _ => false,
+    }
+}
+
+/// Only IP addresses are checked. Onion addresses cannot be dialed back by peers that are not using a tor proxy and
+/// peers will refuse to dial DNS addresses (see `is_dial_back_allowed`).
+fn is_checkable_address(address: &Multiaddr) -> bool {
+    ip_of(address).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_only_allows_dial_back_to_the_connection_ip() {
+        let conn_addr = "/ip4/1.2.3.4/tcp/49152".parse().unwrap();
+        assert!(is_dial_back_allowed(
+            &conn_addr,
+            &"/ip4/1.2.3.4/tcp/18189".parse().unwrap()
+        ));
+        assert!(!is_dial_back_allowed(
+            &conn_addr,
+            &"/ip4/1.2.3.5/tcp/18189".parse().unwrap()
+        ));
+        assert!(!is_dial_back_allowed(
+            &conn_addr,
+            &"/dns4/my.node/tcp/18189".parse().unwrap()
+        ));
+        assert!(!is_dial_back_allowed(
+            &"/memory/1".parse().unwrap(),
+            &"/memory/1".parse().unwrap()
+        ));
+    }
+
+    #[test]
+    fn it_determines_checkable_addresses() {
+        assert!(is_checkable_address(&"/ip4/1.2.3.4/tcp/18189".parse().unwrap()));
+        assert!(!is_checkable_address(&"/dns4/my.node/tcp/18189".parse().unwrap()));
+        assert!(!is_checkable_address(
+            &"/onion3/mqsfoi62gonulivatrhitugwil3hcxf23eisaieetgyw7x2pdi2bzpyd:18141"
+                .parse()
+                .unwrap()
+        ));
+        assert!(!is_checkable_address(&"/memory/1".parse().unwrap()));
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{ReachabilityConfig, ReachabilityReport, ReachabilityRequester, ReachabilityService, ReachabilityStatus};
+use crate::{
+    bandwidth::ConnectionBandwidth,
+    connection_manager::{ConnectionDirection, PeerConnection},
+    multiaddr::Multiaddr,
+    peer_manager::{NodeIdentity, PeerFeatures, PeerManager},
+    protocol::{
+        request_dial_back,
+        respond_to_dial_back,
+        DialBackResult,
+        ProtocolEvent,
+        ProtocolNotification,
+        ProtocolNotificationTx,
+        REACHABILITY_PROTOCOL,
+    },
+    runtime,
+    runtime::task,
+    test_utils::{
+        mocks::{
+            create_connection_manager_mock,
+            create_connectivity_mock,
+            new_peer_connection_mock_pair,
+            ConnectionManagerMockState,
+            ConnectivityManagerMockState,
+            PeerConnectionMockState,
+        },
+        node_identity::build_node_identity,
+        test_node::build_peer_manager,
+    },
+    utils::atomic_ref_counter::AtomicRefCounter,
+};
+use rand::rngs::OsRng;
+use std::{sync::Arc, time::Duration};
+use tari_shutdown::Shutdown;
+use tokio::sync::{mpsc, watch};
+
+struct TestContext {
+    requester: ReachabilityRequester,
+    node_identity: Arc<NodeIdentity>,
+    peer_manager: Arc<PeerManager>,
+    connectivity: ConnectivityManagerMockState,
+    connection_manager: ConnectionManagerMockState,
+    protocol_tx: ProtocolNotificationTx<crate::Substream>,
+    _shutdown: Shutdown,
+}
+
+fn setup(config: ReachabilityConfig) -> TestContext {
+    let node_identity = Arc::new(NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/127.0.0.1/tcp/18189".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    ));
+    let peer_manager = build_peer_manager();
+    let (connectivity, connectivity_mock) = create_connectivity_mock();
+    let connectivity_state = connectivity_mock.spawn();
+    let (connection_manager, connection_manager_mock) = create_connection_manager_mock();
+    let connection_manager_state = connection_manager_mock.get_shared_state();
+    connection_manager_mock.spawn();
+
+    let (request_tx, request_rx) = mpsc::channel(1);
+    let (protocol_tx, protocol_rx) = mpsc::channel(1);
+    let (report_tx, report_rx) = watch::channel(ReachabilityReport::default());
+    let shutdown = Shutdown::new();
+
+    ReachabilityService {
+        config,
+        node_identity: node_identity.clone(),
+        peer_manager: peer_manager.clone(),
+        connectivity,
+        connection_manager,
+        listener_address: "/ip4/0.0.0.0/tcp/18189".parse().unwrap(),
+        request_rx,
+        protocol_rx,
+        report_tx,
+        shutdown_signal: shutdown.to_signal(),
+    }
+    .spawn();
+
+    TestContext {
+        requester: ReachabilityRequester::new(request_tx, report_rx),
+        node_identity,
+        peer_manager,
+        connectivity: connectivity_state,
+        connection_manager: connection_manager_state,
+        protocol_tx,
+        _shutdown: shutdown,
+    }
+}
+
+fn test_config() -> ReachabilityConfig {
+    ReachabilityConfig {
+        initial_check_delay: Duration::from_secs(60 * 60),
+        dial_back_timeout: Duration::from_secs(5),
+        ..Default::default()
+    }
+}
+
+/// Sets up a connected peer that responds to dial back requests with the given result
+async fn add_responding_peer(context: &TestContext, is_reachable: bool) -> PeerConnectionMockState {
+    let (conn, _, _, peer_state) = new_peer_connection_mock_pair().await;
+    context.connectivity.set_selected_connections(vec![conn]).await;
+    let state = peer_state.clone();
+    task::spawn(async move {
+        while let Some(substream) = state.next_incoming_substream().await {
+            respond_to_dial_back(substream, |_| async move {
+                if is_reachable {
+                    Ok(())
+                } else {
+                    Err("Connection refused".to_string())
+                }
+            })
+            .await
+            .unwrap();
+        }
+    });
+    peer_state
+}
+
+#[runtime::test]
+async fn it_reports_reachable_addresses() {
+    let mut context = setup(test_config());
+    let _peer = add_responding_peer(&context, true).await;
+
+    let report = context.requester.check_now().await.unwrap();
+    assert_eq!(report.status, ReachabilityStatus::Reachable);
+    assert_eq!(report.addresses.len(), 1);
+    assert_eq!(report.addresses[0].address, context.node_identity.public_address());
+    assert_eq!(report.addresses[0].num_reachable, 1);
+    assert!(report.last_checked.is_some());
+    assert_eq!(context.requester.get_report().status, ReachabilityStatus::Reachable);
+}
+
+#[runtime::test]
+async fn it_reports_an_error_if_no_peers_are_connected() {
+    let mut context = setup(test_config());
+
+    let report = context.requester.check_now().await.unwrap();
+    assert_eq!(report.status, ReachabilityStatus::Unknown);
+    assert!(report.last_error.is_some());
+}
+
+#[runtime::test]
+async fn it_falls_back_to_the_tor_address_when_unreachable() {
+    let tor_address: Multiaddr = "/onion3/mqsfoi62gonulivatrhitugwil3hcxf23eisaieetgyw7x2pdi2bzpyd:18141"
+        .parse()
+        .unwrap();
+    let mut context = setup(ReachabilityConfig {
+        tor_fallback_address: Some(tor_address.clone()),
+        ..test_config()
+    });
+    let configured_address = context.node_identity.public_address();
+    let _peer = add_responding_peer(&context, false).await;
+
+    let report = context.requester.check_now().await.unwrap();
+    assert_eq!(report.status, ReachabilityStatus::Unreachable);
+    assert_eq!(report.addresses[0].last_error.as_deref(), Some("Connection refused"));
+    assert!(report.is_tor_fallback_active);
+    assert_eq!(context.node_identity.public_address(), tor_address);
+
+    // The configured address is advertised again once it becomes reachable
+    let _peer = add_responding_peer(&context, true).await;
+    let report = context.requester.check_now().await.unwrap();
+    assert_eq!(report.status, ReachabilityStatus::Reachable);
+    assert!(!report.is_tor_fallback_active);
+    assert_eq!(context.node_identity.public_address(), configured_address);
+}
+
+#[runtime::test]
+async fn it_dials_back_addresses_for_peers() {
+    let context = setup(test_config());
+    let peer_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    context.peer_manager.add_peer(peer_identity.to_peer()).await.unwrap();
+
+    let (_, state1, _, state2) = new_peer_connection_mock_pair().await;
+    let (tx, _rx) = mpsc::channel(1);
+    let conn = PeerConnection::new(
+        1,
+        tx,
+        peer_identity.node_id().clone(),
+        PeerFeatures::COMMUNICATION_NODE,
+        "/ip4/1.2.3.4/tcp/49152".parse().unwrap(),
+        ConnectionDirection::Inbound,
+        AtomicRefCounter::new(),
+        ConnectionBandwidth::new(),
+    );
+    context.connectivity.add_active_connection(conn).await;
+
+    let reachable: Multiaddr = "/ip4/1.2.3.4/tcp/18189".parse().unwrap();
+    let unreachable: Multiaddr = "/ip4/1.2.3.4/tcp/18190".parse().unwrap();
+    let other_host: Multiaddr = "/ip4/5.6.7.8/tcp/18189".parse().unwrap();
+    context
+        .connection_manager
+        .add_reachable_address(reachable.clone())
+        .await;
+    context
+        .connection_manager
+        .add_reachable_address(other_host.clone())
+        .await;
+
+    let outbound = state1.open_substream().await.unwrap();
+    let inbound = state2.next_incoming_substream().await.unwrap();
+    context
+        .protocol_tx
+        .send(ProtocolNotification::new(
+            REACHABILITY_PROTOCOL.clone(),
+            ProtocolEvent::NewInboundSubstream(peer_identity.node_id().clone(), inbound),
+        ))
+        .await
+        .unwrap();
+
+    let results = request_dial_back(
+        outbound,
+        &[reachable.clone(), unreachable.clone(), other_host.clone()],
+        Duration::from_secs(5),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0], DialBackResult::reachable(reachable));
+    assert_eq!(results[1].address, unreachable);
+    assert!(!results[1].is_reachable);
+    // Peers must not be able to make this node dial arbitrary hosts
+    assert_eq!(results[2].address, other_host);
+    assert!(!results[2].is_reachable);
+    assert_eq!(context.connection_manager.call_count(), 2);
+}
@@ -28,11 +28,12 @@ use crate::{
         ConnectionManagerRequester,
         PeerConnection,
     },
+    multiaddr::Multiaddr,
     peer_manager::NodeId,
     runtime::task,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
@@ -54,6 +55,7 @@ pub struct ConnectionManagerMockState {
     call_count: Arc<AtomicUsize>,
     calls: Arc<Mutex<Vec<String>>>,
     active_conns: Arc<Mutex<HashMap<NodeId, PeerConnection>>>,
+    reachable_addresses: Arc<Mutex<HashSet<Multiaddr>>>,
     event_tx: broadcast::Sender<Arc<ConnectionManagerEvent>>,
 }
 
@@ -64,6 +66,7 @@ impl ConnectionManagerMockState {
             calls: Arc::new(Mutex::new(Vec::new())),
             event_tx,
             active_conns: Arc::new(Mutex::new(HashMap::new())),
+            reachable_addresses: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
@@ -89,6 +92,12 @@ impl ConnectionManagerMockState {
         self.active_conns.lock().await.insert(node_id, conn);
     }
 
+    /// Probes for this address will succeed. Probes for any other address fail.
+    #[allow(dead_code)]
+    pub async fn add_reachable_address(&self, address: Multiaddr) {
+        self.reachable_addresses.lock().await.insert(address);
+    }
+
     #[allow(dead_code)]
     pub fn publish_event(&self, event: ConnectionManagerEvent) {
         self.event_tx.send(Arc::new(event)).unwrap();
@@ -148,6 +157,18 @@ impl ConnectionManagerMock {
             },
             CancelDial(_) => {},
             NotifyListening(_reply_tx) => {},
+            ProbeAddress { address, reply_tx, .. } => {
+                let result = if self.state.reachable_addresses.lock().await.contains(&address) {
+                    Ok(())
+                } else {
+                    Err(ConnectionManagerError::DialConnectFailedAllAddresses)
+                };
+                let _ = reply_tx.send(result);
+            },
+            SetFirewallRules(_) => {},
+            GetFirewallRules(reply_tx) => {
+                let _ = reply_tx.send(Default::default());
+            },
         }
     }
 }
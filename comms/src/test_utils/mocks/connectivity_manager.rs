@@ -262,6 +262,10 @@ impl ConnectivityManagerMock {
             },
             GetAllConnectionStates(_) => unimplemented!(),
             BanPeer(_, _, _) => {},
+            ReportMisbehaviour(_, _, _) => {},
+            CreditPeer(_, _) => {},
+            GetReputationScores(reply) => reply.send(Ok(Vec::new())).unwrap(),
+            CompactPeers(reply) => reply.send(Ok(0)).unwrap(),
             GetActiveConnections(reply) => {
                 self.state
                     .with_state(|state| reply.send(state.active_conns.values().cloned().collect()).unwrap())
@@ -21,6 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
+    bandwidth::ConnectionBandwidth,
     connection_manager::{
         ConnectionDirection,
         NegotiatedSubstream,
@@ -58,6 +59,7 @@ pub fn create_dummy_peer_connection(node_id: NodeId) -> (PeerConnection, mpsc::R
             Multiaddr::empty(),
             ConnectionDirection::Inbound,
             AtomicRefCounter::new(),
+            ConnectionBandwidth::new(),
         ),
         rx,
     )
@@ -95,6 +97,7 @@ pub async fn create_peer_connection_mock_pair(
             listen_addr.clone(),
             ConnectionDirection::Inbound,
             mock_state_in.substream_counter(),
+            mock_state_in.bandwidth(),
         ),
         mock_state_in,
         PeerConnection::new(
@@ -105,6 +108,7 @@ pub async fn create_peer_connection_mock_pair(
             listen_addr,
             ConnectionDirection::Outbound,
             mock_state_out.substream_counter(),
+            mock_state_out.bandwidth(),
         ),
         mock_state_out,
     )
@@ -127,17 +131,20 @@ pub struct PeerConnectionMockState {
     mux_control: Arc<Mutex<multiplexing::Control>>,
     mux_incoming: Arc<Mutex<IncomingSubstreams>>,
     substream_counter: AtomicRefCounter,
+    bandwidth: ConnectionBandwidth,
 }
 
 impl PeerConnectionMockState {
     pub fn new(muxer: Yamux) -> Self {
         let control = muxer.get_yamux_control();
         let substream_counter = control.substream_counter();
+        let bandwidth = control.bandwidth();
         Self {
             call_count: Arc::new(AtomicUsize::new(0)),
             mux_control: Arc::new(Mutex::new(control)),
             mux_incoming: Arc::new(Mutex::new(muxer.into_incoming())),
             substream_counter,
+            bandwidth,
         }
     }
 
@@ -157,6 +164,10 @@ impl PeerConnectionMockState {
         self.substream_counter.clone()
     }
 
+    pub fn bandwidth(&self) -> ConnectionBandwidth {
+        self.bandwidth.clone()
+    }
+
     pub fn num_open_substreams(&self) -> usize {
         self.substream_counter.get()
     }
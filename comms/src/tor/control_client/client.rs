@@ -25,7 +25,7 @@ use super::{
     commands::{AddOnionFlag, AddOnionResponse, TorCommand},
     error::TorClientError,
     response::ResponseLine,
-    types::{KeyBlob, KeyType, PortMapping},
+    types::{ClientAuthPrivateKey, ClientAuthPublicKey, KeyBlob, KeyType, PortMapping},
     PrivateKey,
     LOG_TARGET,
 };
@@ -176,14 +176,43 @@ impl TorControlPortClient {
         port: P,
         num_streams: Option<NonZeroU16>,
     ) -> Result<AddOnionResponse, TorClientError> {
-        let (key_type, key_blob) = match private_key {
-            PrivateKey::Rsa1024(key) => (KeyType::Rsa1024, KeyBlob::String(key)),
-            PrivateKey::Ed25519V3(key) => (KeyType::Ed25519V3, KeyBlob::String(key)),
-        };
+        let (key_type, key_blob) = private_key.to_key_type_and_blob();
         self.add_onion_custom(key_type, key_blob, flags, port, num_streams)
             .await
     }
 
+    /// The ADD_ONION command for a v3 onion service that only accepts connections from clients holding the private
+    /// key for one of the given client authorization public keys.
+    pub async fn add_onion_with_client_auth<P: Into<PortMapping>>(
+        &mut self,
+        key_type: KeyType,
+        key_blob: KeyBlob<'_>,
+        flags: Vec<AddOnionFlag>,
+        port: P,
+        client_auth: Vec<ClientAuthPublicKey>,
+    ) -> Result<AddOnionResponse, TorClientError> {
+        let command =
+            commands::AddOnion::new(key_type, key_blob, flags, port.into(), None).with_client_auth(client_auth);
+        self.request_response(command).await
+    }
+
+    /// The ONION_CLIENT_AUTH_ADD command. Tor will use the given private key when connecting to the v3 onion service
+    /// with the given service id.
+    pub async fn onion_client_auth_add(
+        &mut self,
+        service_id: &str,
+        private_key: &ClientAuthPrivateKey,
+    ) -> Result<(), TorClientError> {
+        let command = commands::OnionClientAuthAdd::new(service_id, private_key);
+        self.request_response(command).await
+    }
+
+    /// The ONION_CLIENT_AUTH_REMOVE command.
+    pub async fn onion_client_auth_remove(&mut self, service_id: &str) -> Result<(), TorClientError> {
+        let command = commands::OnionClientAuthRemove::new(service_id);
+        self.request_response(command).await
+    }
+
     /// The DEL_ONION command.
     pub async fn del_onion(&mut self, service_id: &str) -> Result<(), TorClientError> {
         let command = commands::DelOnion::new(service_id);
@@ -497,6 +526,90 @@ mod test {
         unpack_enum!(TorClientError::TorCommandFailed(_s) = err);
     }
 
+    #[runtime::test]
+    async fn add_onion_with_client_auth_ok() {
+        let (mut tor, mock_state) = setup_test().await;
+
+        mock_state.set_canned_response(canned_responses::ADD_ONION_OK).await;
+
+        let client_key = "D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA"
+            .parse::<ClientAuthPublicKey>()
+            .unwrap();
+        let response = tor
+            .add_onion_with_client_auth(KeyType::New, KeyBlob::Ed25519V3, vec![], 8080, vec![client_key])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.service_id,
+            "qigbgbs4ue3ghbupsotgh73cmmkjrin2aprlyxsrnrvpmcmzy3g4wbid"
+        );
+        assert!(response.private_key.is_some());
+
+        let request = mock_state.take_requests().await.pop().unwrap();
+        assert_eq!(
+            request,
+            "ADD_ONION NEW:ED25519-V3 Flags=V3Auth Port=8080,127.0.0.1:8080 \
+             ClientAuthV3=D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA"
+        );
+    }
+
+    #[runtime::test]
+    async fn onion_client_auth_add_ok() {
+        let (mut tor, mock_state) = setup_test().await;
+
+        mock_state
+            .set_canned_response(canned_responses::ONION_CLIENT_AUTH_REPLACED)
+            .await;
+
+        let private_key = "yOGUOxPNqA6hpDyhQKMuYrwVJ6YbJ2lvW1jy2aGmAlk=".parse().unwrap();
+        tor.onion_client_auth_add("some-fake-id", &private_key).await.unwrap();
+
+        let request = mock_state.take_requests().await.pop().unwrap();
+        assert_eq!(
+            request,
+            "ONION_CLIENT_AUTH_ADD some-fake-id x25519:yOGUOxPNqA6hpDyhQKMuYrwVJ6YbJ2lvW1jy2aGmAlk="
+        );
+    }
+
+    #[runtime::test]
+    async fn onion_client_auth_remove_err() {
+        let (mut tor, mock_state) = setup_test().await;
+
+        mock_state.set_canned_response(canned_responses::ERR_552).await;
+
+        let err = tor.onion_client_auth_remove("some-fake-id").await.unwrap_err();
+        unpack_enum!(TorClientError::TorCommandFailed(_s) = err);
+
+        let request = mock_state.take_requests().await.pop().unwrap();
+        assert_eq!(request, "ONION_CLIENT_AUTH_REMOVE some-fake-id");
+    }
+
+    #[runtime::test]
+    async fn it_emits_control_events() {
+        let (_, mock_state, socket) = test_server::spawn().await;
+        let (event_tx, _) = broadcast::channel(10);
+        let mut tor = TorControlPortClient::new(socket, event_tx);
+        let mut events = tor.get_event_stream();
+
+        tor.set_events(&["NETWORK_LIVENESS", "SIGNAL"]).await.unwrap();
+        let request = mock_state.take_requests().await.pop().unwrap();
+        assert_eq!(request, "SETEVENTS NETWORK_LIVENESS SIGNAL");
+
+        mock_state.send_event("650 NETWORK_LIVENESS UP");
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event, TorControlEvent::NetworkLivenessUp);
+
+        mock_state.send_event("650 SIGNAL SHUTDOWN");
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event, TorControlEvent::Signal("SHUTDOWN".to_string()));
+
+        mock_state.disconnect();
+        let event = events.next().await.unwrap().unwrap();
+        assert_eq!(event, TorControlEvent::TorControlDisconnected);
+        assert!(!tor.is_connected());
+    }
+
     #[runtime::test]
     async fn del_onion_ok() {
         let (mut tor, mock_state) = setup_test().await;
@@ -26,7 +26,7 @@ use crate::tor::control_client::{
     parsers,
     parsers::ParseError,
     response::ResponseLine,
-    types::{KeyBlob, KeyType, PortMapping, PrivateKey},
+    types::{ClientAuthPublicKey, KeyBlob, KeyType, PortMapping, PrivateKey},
 };
 use std::{borrow::Cow, fmt, num::NonZeroU16};
 
@@ -43,6 +43,8 @@ pub enum AddOnionFlag {
     NonAnonymous,
     /// Close the circuit is the maximum streams allowed is reached.
     MaxStreamsCloseCircuit,
+    /// Client authorization is required using the "v3" method. Authorized clients are given using `ClientAuthV3`.
+    V3Auth,
 }
 
 impl fmt::Display for AddOnionFlag {
@@ -54,6 +56,7 @@ impl fmt::Display for AddOnionFlag {
             BasicAuth => write!(f, "BasicAuth"),
             NonAnonymous => write!(f, "NonAnonymous"),
             MaxStreamsCloseCircuit => write!(f, "MaxStreamsCloseCircuit"),
+            V3Auth => write!(f, "V3Auth"),
         }
     }
 }
@@ -67,6 +70,7 @@ pub struct AddOnion<'a> {
     flags: Vec<AddOnionFlag>,
     port_mapping: PortMapping,
     num_streams: Option<NonZeroU16>,
+    client_auth: Vec<ClientAuthPublicKey>,
 }
 
 impl<'a> AddOnion<'a> {
@@ -83,8 +87,19 @@ impl<'a> AddOnion<'a> {
             flags,
             port_mapping,
             num_streams,
+            client_auth: Vec::new(),
         }
     }
+
+    /// Only allow clients holding the private key for one of the given public keys to connect to the onion service.
+    /// The `V3Auth` flag is added if there are any authorized clients.
+    pub fn with_client_auth(mut self, client_auth: Vec<ClientAuthPublicKey>) -> Self {
+        if !client_auth.is_empty() && !self.flags.iter().any(|f| matches!(f, AddOnionFlag::V3Auth)) {
+            self.flags.push(AddOnionFlag::V3Auth);
+        }
+        self.client_auth = client_auth;
+        self
+    }
 }
 
 impl TorCommand for AddOnion<'_> {
@@ -113,6 +128,10 @@ impl TorCommand for AddOnion<'_> {
             self.port_mapping.proxied_address()
         ));
 
+        for client_auth in &self.client_auth {
+            s.push_str(&format!(" ClientAuthV3={}", client_auth.as_tor_repr()));
+        }
+
         Ok(s)
     }
 
@@ -176,13 +195,14 @@ impl fmt::Display for AddOnion<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "ADD_ONION (KeyType={} KeyBlob={} Flags={} PortMapping={})",
+            "ADD_ONION (KeyType={} KeyBlob={} Flags={} PortMapping={} AuthorizedClients={})",
             self.key_type.as_tor_repr(),
             self.key_blob,
             self.flags
                 .iter()
                 .fold(String::new(), |acc, f| format!("{}, {}", acc, f)),
-            self.port_mapping
+            self.port_mapping,
+            self.client_auth.len()
         )
     }
 }
@@ -213,4 +233,25 @@ mod test {
             format!("ADD_ONION NEW:{} Port=9090,127.0.0.1:9090", key)
         );
     }
+
+    #[test]
+    fn to_command_string_with_client_auth() {
+        let client_key = "D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA"
+            .parse::<ClientAuthPublicKey>()
+            .unwrap();
+        let command = AddOnion::new(
+            KeyType::New,
+            KeyBlob::Ed25519V3,
+            vec![AddOnionFlag::Detach],
+            PortMapping::from_port(9090),
+            None,
+        )
+        .with_client_auth(vec![client_key.clone(), client_key]);
+        assert_eq!(
+            command.to_command_string().unwrap(),
+            "ADD_ONION NEW:ED25519-V3 Flags=Detach,V3Auth Port=9090,127.0.0.1:9090 \
+             ClientAuthV3=D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA \
+             ClientAuthV3=D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA"
+        );
+    }
 }
@@ -0,0 +1,132 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::tor::control_client::{
+    commands::TorCommand,
+    error::TorClientError,
+    response::ResponseLine,
+    types::ClientAuthPrivateKey,
+};
+use std::fmt;
+
+/// The ONION_CLIENT_AUTH_ADD command.
+///
+/// This instructs Tor to use the given client authorization private key when connecting to the onion service.
+pub struct OnionClientAuthAdd<'a> {
+    service_id: &'a str,
+    private_key: &'a ClientAuthPrivateKey,
+}
+
+impl<'a> OnionClientAuthAdd<'a> {
+    pub fn new(service_id: &'a str, private_key: &'a ClientAuthPrivateKey) -> Self {
+        Self {
+            service_id,
+            private_key,
+        }
+    }
+}
+
+impl<'a> TorCommand for OnionClientAuthAdd<'a> {
+    type Error = TorClientError;
+    type Output = ();
+
+    fn to_command_string(&self) -> Result<String, Self::Error> {
+        Ok(format!(
+            "ONION_CLIENT_AUTH_ADD {} x25519:{}",
+            self.service_id,
+            self.private_key.as_tor_repr()
+        ))
+    }
+
+    fn parse_responses(&self, responses: Vec<ResponseLine>) -> Result<Self::Output, Self::Error> {
+        parse_client_auth_responses(responses)
+    }
+}
+
+impl fmt::Display for OnionClientAuthAdd<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ONION_CLIENT_AUTH_ADD (ServiceId = {})", self.service_id)
+    }
+}
+
+/// The ONION_CLIENT_AUTH_REMOVE command.
+///
+/// This instructs Tor to forget the client authorization credentials for the onion service.
+pub struct OnionClientAuthRemove<'a> {
+    service_id: &'a str,
+}
+
+impl<'a> OnionClientAuthRemove<'a> {
+    pub fn new(service_id: &'a str) -> Self {
+        Self { service_id }
+    }
+}
+
+impl<'a> TorCommand for OnionClientAuthRemove<'a> {
+    type Error = TorClientError;
+    type Output = ();
+
+    fn to_command_string(&self) -> Result<String, Self::Error> {
+        Ok(format!("ONION_CLIENT_AUTH_REMOVE {}", self.service_id))
+    }
+
+    fn parse_responses(&self, responses: Vec<ResponseLine>) -> Result<Self::Output, Self::Error> {
+        parse_client_auth_responses(responses)
+    }
+}
+
+impl fmt::Display for OnionClientAuthRemove<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ONION_CLIENT_AUTH_REMOVE (ServiceId = {})", self.service_id)
+    }
+}
+
+fn parse_client_auth_responses(mut responses: Vec<ResponseLine>) -> Result<(), TorClientError> {
+    let last_response = responses.pop().ok_or(TorClientError::UnexpectedEof)?;
+    // Tor responds with 251/252 if existing credentials were replaced or did not exist
+    if last_response.is_success() {
+        return Ok(());
+    }
+
+    Err(TorClientError::TorCommandFailed(last_response.value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_command_string() {
+        let private_key = "yOGUOxPNqA6hpDyhQKMuYrwVJ6YbJ2lvW1jy2aGmAlk=".parse().unwrap();
+        let command = OnionClientAuthAdd::new("some-service-id", &private_key);
+        assert_eq!(
+            command.to_command_string().unwrap(),
+            "ONION_CLIENT_AUTH_ADD some-service-id x25519:yOGUOxPNqA6hpDyhQKMuYrwVJ6YbJ2lvW1jy2aGmAlk="
+        );
+
+        let command = OnionClientAuthRemove::new("some-service-id");
+        assert_eq!(
+            command.to_command_string().unwrap(),
+            "ONION_CLIENT_AUTH_REMOVE some-service-id"
+        );
+    }
+}
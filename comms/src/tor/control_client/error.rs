@@ -42,6 +42,8 @@ pub enum TorClientError {
     AddOnionNoServiceId,
     #[error("The given service id was invalid")]
     InvalidServiceId,
+    #[error("The given client authorization key was invalid")]
+    InvalidClientAuthKey,
     #[error("Onion address is exists")]
     OnionAddressCollision,
     #[error("Response returned an no value for key")]
@@ -31,11 +31,22 @@ pub enum ControlEventError {
     InvalidEventData,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TorControlEvent {
     NetworkLivenessUp,
     NetworkLivenessDown,
     TorControlDisconnected,
+    /// Tor received a signal e.g. RELOAD or SHUTDOWN
+    Signal(String),
+    /// Tor has established a circuit and is ready to handle traffic
+    CircuitEstablished,
+    /// The descriptor for the onion service with the given service id was uploaded to a hidden service directory
+    HsDescUploaded(String),
+    /// A hidden service descriptor upload or fetch failed
+    HsDescFailed {
+        service_id: String,
+        reason: Option<String>,
+    },
     Unsupported(String),
 }
 
@@ -56,7 +67,83 @@ impl TorControlEvent {
                     _ => Err(ControlEventError::InvalidEventData),
                 }
             },
+            "SIGNAL" => {
+                let signal = parts.next().ok_or(ControlEventError::InvalidEventData)?;
+                Ok(TorControlEvent::Signal(signal.trim().to_owned()))
+            },
+            "STATUS_CLIENT" => {
+                let data = parts.next().ok_or(ControlEventError::InvalidEventData)?;
+                // STATUS_CLIENT Severity Action [Arguments]
+                match data.split_whitespace().nth(1) {
+                    Some("CIRCUIT_ESTABLISHED") => Ok(TorControlEvent::CircuitEstablished),
+                    Some(_) => Ok(TorControlEvent::Unsupported(event_type.to_owned())),
+                    None => Err(ControlEventError::InvalidEventData),
+                }
+            },
+            "HS_DESC" => {
+                let data = parts.next().ok_or(ControlEventError::InvalidEventData)?;
+                // HS_DESC Action HSAddress AuthType HsDir [DescriptorID] [REASON=Reason] ...
+                let mut fields = data.split_whitespace();
+                let action = fields.next().ok_or(ControlEventError::InvalidEventData)?;
+                let service_id = fields.next().ok_or(ControlEventError::InvalidEventData)?.to_owned();
+                match action {
+                    "UPLOADED" => Ok(TorControlEvent::HsDescUploaded(service_id)),
+                    "FAILED" => Ok(TorControlEvent::HsDescFailed {
+                        service_id,
+                        reason: fields.find_map(|f| f.strip_prefix("REASON=")).map(ToOwned::to_owned),
+                    }),
+                    _ => Ok(TorControlEvent::Unsupported(event_type.to_owned())),
+                }
+            },
             s => Ok(TorControlEvent::Unsupported(s.to_owned())),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tor::control_client::parsers;
+
+    fn parse_event(line: &str) -> TorControlEvent {
+        TorControlEvent::try_from_response(parsers::response_line(line).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn try_from_response() {
+        assert_eq!(
+            parse_event("650 NETWORK_LIVENESS DOWN"),
+            TorControlEvent::NetworkLivenessDown
+        );
+        assert_eq!(
+            parse_event("650 SIGNAL RELOAD"),
+            TorControlEvent::Signal("RELOAD".to_string())
+        );
+        assert_eq!(
+            parse_event("650 STATUS_CLIENT NOTICE CIRCUIT_ESTABLISHED"),
+            TorControlEvent::CircuitEstablished
+        );
+        assert_eq!(
+            parse_event("650 HS_DESC UPLOADED qigbgbs4ue3ghbupsotgh73cmmkjrin2aprlyxsrnrvpmcmzy3g4wbid UNKNOWN $AAAA"),
+            TorControlEvent::HsDescUploaded("qigbgbs4ue3ghbupsotgh73cmmkjrin2aprlyxsrnrvpmcmzy3g4wbid".to_string())
+        );
+        assert_eq!(
+            parse_event(
+                "650 HS_DESC FAILED qigbgbs4ue3ghbupsotgh73cmmkjrin2aprlyxsrnrvpmcmzy3g4wbid UNKNOWN $AAAA \
+                 REASON=UPLOAD_REJECTED"
+            ),
+            TorControlEvent::HsDescFailed {
+                service_id: "qigbgbs4ue3ghbupsotgh73cmmkjrin2aprlyxsrnrvpmcmzy3g4wbid".to_string(),
+                reason: Some("UPLOAD_REJECTED".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_event("650 HS_DESC REQUESTED abcdef NO_AUTH $AAAA"),
+            TorControlEvent::Unsupported("HS_DESC".to_string())
+        );
+        assert_eq!(
+            parse_event("650 CIRC 1000 EXTENDED"),
+            TorControlEvent::Unsupported("CIRC".to_string())
+        );
+    }
+}
@@ -36,9 +36,17 @@ mod parsers;
 mod response;
 
 mod types;
-pub use types::{KeyBlob, KeyType, PortMapping, PrivateKey};
+pub use types::{
+    generate_client_auth_keypair,
+    ClientAuthPrivateKey,
+    ClientAuthPublicKey,
+    KeyBlob,
+    KeyType,
+    PortMapping,
+    PrivateKey,
+};
 
 #[cfg(test)]
-mod test_server;
+pub mod test_server;
 
 const LOG_TARGET: &str = "comms::tor::control_client";
@@ -37,6 +37,11 @@ impl ResponseLine {
         self.code == OK_CODE
     }
 
+    /// Returns true if the response has a 2xx code. Some commands use codes other than 250 to indicate success.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code)
+    }
+
     pub fn has_more(&self) -> bool {
         self.has_more
     }
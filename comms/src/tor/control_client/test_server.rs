@@ -20,9 +20,20 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{memsocket::MemorySocket, multiaddr::Multiaddr, runtime, test_utils::transport::build_connected_sockets};
+use crate::{
+    memsocket::MemorySocket,
+    multiaddr::Multiaddr,
+    runtime,
+    test_utils::transport::build_connected_sockets,
+    utils::multiaddr::socketaddr_to_multiaddr,
+};
 use futures::{lock::Mutex, stream, SinkExt, StreamExt};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::broadcast,
+};
 use tokio_util::codec::{Framed, LinesCodec};
 
 pub async fn spawn() -> (Multiaddr, State, MemorySocket) {
@@ -35,17 +46,46 @@ pub async fn spawn() -> (Multiaddr, State, MemorySocket) {
     (addr, state, socket_out)
 }
 
+/// Spawns a test server that accepts TCP connections on a local port. Connections are served one at a time, so a
+/// client may reconnect after `State::disconnect` is called, as it would after a Tor restart.
+pub async fn spawn_tcp() -> (Multiaddr, State) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = socketaddr_to_multiaddr(&listener.local_addr().unwrap());
+    let state = State::new();
+    runtime::current().spawn({
+        let state = state.clone();
+        async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                serve(state.clone(), socket).await;
+            }
+        }
+    });
+
+    (addr, state)
+}
+
+#[derive(Debug, Clone)]
+enum ControlMessage {
+    Line(String),
+    Disconnect,
+}
+
 #[derive(Clone)]
 pub struct State {
     request_lines: Arc<Mutex<Vec<String>>>,
     canned_response: Arc<Mutex<Vec<String>>>,
+    command_responses: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    control_tx: broadcast::Sender<ControlMessage>,
 }
 
 impl State {
     pub fn new() -> Self {
+        let (control_tx, _) = broadcast::channel(10);
         Self {
             request_lines: Arc::new(Mutex::new(Vec::new())),
             canned_response: Arc::new(Mutex::new(all_to_owned(canned_responses::OK))),
+            command_responses: Arc::new(Mutex::new(HashMap::new())),
+            control_tx,
         }
     }
 
@@ -53,9 +93,38 @@ impl State {
         *self.canned_response.lock().await = all_to_owned(lines);
     }
 
+    /// Respond with the given lines to requests that start with `command`, instead of the canned response
+    pub async fn set_command_response<'a, T: AsRef<[&'a str]>>(&self, command: &str, lines: T) {
+        self.command_responses
+            .lock()
+            .await
+            .insert(command.to_string(), all_to_owned(lines));
+    }
+
     pub async fn take_requests(&self) -> Vec<String> {
         self.request_lines.lock().await.drain(..).collect()
     }
+
+    /// Send an asynchronous event line (e.g. `650 NETWORK_LIVENESS UP`) to the connected client
+    pub fn send_event(&self, line: &str) {
+        self.control_tx.send(ControlMessage::Line(line.to_string())).unwrap();
+    }
+
+    /// Close the connection to the connected client
+    pub fn disconnect(&self) {
+        self.control_tx.send(ControlMessage::Disconnect).unwrap();
+    }
+
+    async fn responses_for(&self, request: &str) -> Vec<String> {
+        let command_responses = self.command_responses.lock().await;
+        match command_responses
+            .iter()
+            .find(|(command, _)| request.starts_with(command.as_str()))
+        {
+            Some((_, lines)) => lines.clone(),
+            None => self.canned_response.lock().await.clone(),
+        }
+    }
 }
 
 pub struct TorControlPortTestServer {
@@ -76,12 +145,35 @@ impl TorControlPortTestServer {
     }
 
     pub async fn run(self) {
-        let mut framed = Framed::new(self.socket, LinesCodec::new());
-        let state = self.state;
-        while let Some(msg) = framed.next().await {
-            state.request_lines.lock().await.push(msg.unwrap());
-            let mut responses = stream::iter(state.canned_response.lock().await.clone()).map(Ok);
-            framed.send_all(&mut responses).await.unwrap();
+        serve(self.state, self.socket).await
+    }
+}
+
+async fn serve<TSocket: AsyncRead + AsyncWrite + Unpin>(state: State, socket: TSocket) {
+    let mut control_rx = state.control_tx.subscribe();
+    let mut framed = Framed::new(socket, LinesCodec::new());
+    loop {
+        tokio::select! {
+            msg = framed.next() => {
+                let msg = match msg {
+                    Some(msg) => msg.unwrap(),
+                    None => break,
+                };
+                let responses = state.responses_for(&msg).await;
+                state.request_lines.lock().await.push(msg);
+                let mut responses = stream::iter(responses).map(Ok);
+                if framed.send_all(&mut responses).await.is_err() {
+                    break;
+                }
+            },
+            Ok(msg) = control_rx.recv() => match msg {
+                ControlMessage::Line(line) => {
+                    if framed.send(line).await.is_err() {
+                        break;
+                    }
+                },
+                ControlMessage::Disconnect => break,
+            },
         }
     }
 }
@@ -123,5 +215,7 @@ pub mod canned_responses {
         "250 OK",
     ];
 
+    pub const ONION_CLIENT_AUTH_REPLACED: &[&str] = &["251 Client for onion existed and replaced"];
+
     pub const ERR_552: &[&str] = &["552 Unrecognised configuration key \"dummy\""];
 }
@@ -20,8 +20,12 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use super::error::TorClientError;
+use data_encoding::{BASE32_NOPAD, BASE64};
+use rand::{rngs::OsRng, RngCore};
 use serde_derive::{Deserialize, Serialize};
-use std::{fmt, net::SocketAddr};
+use std::{fmt, net::SocketAddr, str::FromStr};
+use x25519_dalek::{PublicKey, StaticSecret};
 
 #[derive(Clone, Copy, Debug)]
 pub enum KeyType {
@@ -85,6 +89,16 @@ pub enum PrivateKey {
     Ed25519V3(String),
 }
 
+impl PrivateKey {
+    /// Returns the ADD_ONION key type and key blob for this key
+    pub fn to_key_type_and_blob(&self) -> (KeyType, KeyBlob<'_>) {
+        match self {
+            PrivateKey::Rsa1024(key) => (KeyType::Rsa1024, KeyBlob::String(key)),
+            PrivateKey::Ed25519V3(key) => (KeyType::Ed25519V3, KeyBlob::String(key)),
+        }
+    }
+}
+
 impl Drop for PrivateKey {
     fn drop(&mut self) {
         use clear_on_drop::clear::Clear;
@@ -145,3 +159,106 @@ impl fmt::Display for PortMapping {
         write!(f, "PortMapping [{} -> {}]", self.0, self.1)
     }
 }
+
+const CLIENT_AUTH_KEY_BYTES: usize = 32;
+
+/// The public key of a client that is authorized to connect to a v3 onion service that requires client authorization.
+/// This is the base32-encoded x25519 public key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientAuthPublicKey(String);
+
+impl ClientAuthPublicKey {
+    pub fn as_tor_repr(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for ClientAuthPublicKey {
+    type Err = TorClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_start_matches("descriptor:x25519:").to_uppercase();
+        match BASE32_NOPAD.decode(s.as_bytes()) {
+            Ok(bytes) if bytes.len() == CLIENT_AUTH_KEY_BYTES => Ok(Self(s)),
+            _ => Err(TorClientError::InvalidClientAuthKey),
+        }
+    }
+}
+
+impl fmt::Display for ClientAuthPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The private key used by a client to connect to a v3 onion service that requires client authorization. This is the
+/// base64-encoded x25519 private key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientAuthPrivateKey(String);
+
+impl ClientAuthPrivateKey {
+    pub fn as_tor_repr(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for ClientAuthPrivateKey {
+    type Err = TorClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim_start_matches("x25519:");
+        match BASE64.decode(s.as_bytes()) {
+            Ok(bytes) if bytes.len() == CLIENT_AUTH_KEY_BYTES => Ok(Self(s.to_string())),
+            _ => Err(TorClientError::InvalidClientAuthKey),
+        }
+    }
+}
+
+impl Drop for ClientAuthPrivateKey {
+    fn drop(&mut self) {
+        use clear_on_drop::clear::Clear;
+        Clear::clear(&mut self.0);
+    }
+}
+
+/// Generates a new x25519 keypair for v3 onion service client authorization. The public key is given to the onion
+/// service operator and the private key is used by the client.
+pub fn generate_client_auth_keypair() -> (ClientAuthPublicKey, ClientAuthPrivateKey) {
+    let mut secret_bytes = [0u8; CLIENT_AUTH_KEY_BYTES];
+    OsRng.fill_bytes(&mut secret_bytes);
+    let secret = StaticSecret::from(secret_bytes);
+    let public = PublicKey::from(&secret);
+    let keypair = (
+        ClientAuthPublicKey(BASE32_NOPAD.encode(public.as_bytes())),
+        ClientAuthPrivateKey(BASE64.encode(&secret.to_bytes())),
+    );
+    clear_on_drop::clear::Clear::clear(&mut secret_bytes);
+    keypair
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn client_auth_keys() {
+        let (public_key, private_key) = generate_client_auth_keypair();
+        assert_eq!(public_key.as_tor_repr().len(), 52);
+        assert_eq!(
+            public_key.as_tor_repr().parse::<ClientAuthPublicKey>().unwrap(),
+            public_key
+        );
+        let prefixed = format!("descriptor:x25519:{}", public_key.as_tor_repr().to_lowercase());
+        assert_eq!(prefixed.parse::<ClientAuthPublicKey>().unwrap(), public_key);
+        assert_eq!(
+            format!("x25519:{}", private_key.as_tor_repr())
+                .parse::<ClientAuthPrivateKey>()
+                .unwrap(),
+            private_key
+        );
+
+        assert!("not-a-key".parse::<ClientAuthPublicKey>().is_err());
+        assert!(BASE32_NOPAD.encode(&[0u8; 31]).parse::<ClientAuthPublicKey>().is_err());
+        assert!(BASE64.encode(&[0u8; 33]).parse::<ClientAuthPrivateKey>().is_err());
+    }
+}
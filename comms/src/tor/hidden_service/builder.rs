@@ -25,8 +25,9 @@ use crate::{
     multiaddr::Multiaddr,
     socks,
     tor::{
-        hidden_service::{controller::HiddenServiceController, TorProxyOpts},
+        hidden_service::{controller::HiddenServiceController, OnionServiceConfig, TorProxyOpts},
         Authentication,
+        ClientAuthPublicKey,
         PortMapping,
         TorIdentity,
     },
@@ -63,6 +64,8 @@ bitflags! {
 #[derive(Default)]
 pub struct HiddenServiceBuilder {
     identity: Option<TorIdentity>,
+    client_auth: Vec<ClientAuthPublicKey>,
+    additional_services: Vec<OnionServiceConfig>,
     port_mapping: Option<PortMapping>,
     socks_addr_override: Option<Multiaddr>,
     control_server_addr: Option<Multiaddr>,
@@ -116,6 +119,14 @@ impl HiddenServiceBuilder {
         Option<TorIdentity>
     );
 
+    setter!(
+        /// Only clients holding the private key for one of these public keys may connect to the hidden service (Tor v3
+        /// client authorization). If empty, any client may connect.
+        with_client_auth,
+        client_auth,
+        Vec<ClientAuthPublicKey>
+    );
+
     setter!(
         /// Configuration flags for the hidden service
         with_hs_flags,
@@ -144,6 +155,12 @@ impl HiddenServiceBuilder {
         self
     }
 
+    /// Publish an additional onion service alongside the hidden service, for example to expose a gRPC endpoint.
+    pub fn add_onion_service(mut self, config: OnionServiceConfig) -> Self {
+        self.additional_services.push(config);
+        self
+    }
+
     /// Set the PortMapping to use when creating this hidden service. A PortMapping maps a Tor port to a proxied address
     /// (usually local). An error will result if this is not provided.
     pub fn with_port_mapping<P: Into<PortMapping>>(mut self, port_mapping: P) -> Self {
@@ -176,6 +193,8 @@ impl HiddenServiceBuilder {
             self.socks_addr_override,
             self.socks_auth,
             self.identity,
+            self.client_auth,
+            self.additional_services,
             self.hs_flags,
             self.proxy_opts,
             self.shutdown_signal,
@@ -29,10 +29,16 @@ use crate::{
             commands::{AddOnionFlag, AddOnionResponse},
             TorControlEvent,
         },
-        hidden_service::TorProxyOpts,
+        hidden_service::{
+            requester::{HiddenServiceEvent, HiddenServiceRequest, HiddenServiceRequester, OnionServiceConfig},
+            TorProxyOpts,
+        },
         Authentication,
+        ClientAuthPublicKey,
         HiddenService,
         HsFlags,
+        KeyBlob,
+        KeyType,
         PortMapping,
         TorClientError,
         TorControlPortClient,
@@ -46,10 +52,17 @@ use log::*;
 use std::{net::SocketAddr, sync::Arc, time::Duration};
 use tari_shutdown::OptionalShutdownSignal;
 use thiserror::Error;
-use tokio::{sync::broadcast, time};
+use tokio::{
+    sync::{broadcast, mpsc},
+    time,
+};
+use tokio_stream::wrappers::BroadcastStream;
 
 const LOG_TARGET: &str = "comms::tor::hidden_service_controller";
 
+/// The Tor control port events that the controller subscribes to
+const CONTROL_EVENTS: &[&str] = &["NETWORK_LIVENESS", "SIGNAL", "STATUS_CLIENT", "HS_DESC"];
+
 #[derive(Debug, Error)]
 pub enum HiddenServiceControllerError {
     #[error("Tor client is not connected")]
@@ -64,6 +77,16 @@ pub enum HiddenServiceControllerError {
     InvalidDetachedServiceId,
     #[error("The shutdown signal interrupted the HiddenServiceController")]
     ShutdownSignalInterrupt,
+    #[error("No onion service with service id '{0}' is being managed")]
+    ServiceNotFound(String),
+    #[error("An onion service with service id '{0}' is already being managed")]
+    ServiceAlreadyExists(String),
+    #[error("The comms hidden service cannot be removed")]
+    CannotRemoveCommsService,
+    #[error("The hidden service controller has shut down")]
+    ControllerDisconnected,
+    #[error("The hidden service controller cancelled the request")]
+    ControllerResponseCancelled,
 }
 
 pub struct HiddenServiceController {
@@ -74,6 +97,8 @@ pub struct HiddenServiceController {
     socks_address_override: Option<Multiaddr>,
     socks_auth: socks::Authentication,
     identity: Option<TorIdentity>,
+    client_auth: Vec<ClientAuthPublicKey>,
+    additional_services: Vec<OnionServiceConfig>,
     hs_flags: HsFlags,
     is_authenticated: bool,
     proxy_opts: TorProxyOpts,
@@ -89,6 +114,8 @@ impl HiddenServiceController {
         socks_address_override: Option<Multiaddr>,
         socks_auth: socks::Authentication,
         identity: Option<TorIdentity>,
+        client_auth: Vec<ClientAuthPublicKey>,
+        additional_services: Vec<OnionServiceConfig>,
         hs_flags: HsFlags,
         proxy_opts: TorProxyOpts,
         shutdown_signal: OptionalShutdownSignal,
@@ -102,6 +129,8 @@ impl HiddenServiceController {
             socks_auth,
             hs_flags,
             identity,
+            client_auth,
+            additional_services,
             is_authenticated: false,
             proxy_opts,
             shutdown_signal,
@@ -125,56 +154,143 @@ impl HiddenServiceController {
     }
 
     /// Connects, authenticates to the Tor control port and creates a hidden service using the tor identity if provided,
-    /// otherwise a new tor identity will be created. The creation of a hidden service is idempotent i.e. if the
-    /// hidden service exists, the
+    /// otherwise a new tor identity will be created. Any additional onion services are published alongside it. The
+    /// creation of a hidden service is idempotent i.e. if the hidden service exists, the
     pub async fn create_hidden_service(mut self) -> Result<HiddenService, HiddenServiceControllerError> {
         self.connect_and_auth().await?;
         self.set_events().await?;
 
-        let hidden_service = self.create_hidden_service_from_identity().await?;
-        let mut shutdown_signal = hidden_service.shutdown_signal.clone();
-        let mut event_stream = self.client.as_ref().unwrap().get_event_stream();
-
-        task::spawn({
-            async move {
-                loop {
-                    let either = future::select(&mut shutdown_signal, event_stream.next()).await;
-                    match either {
-                        Either::Left(_) => {
-                            debug!(
+        let identity = self.publish_services().await?;
+
+        let (request_tx, request_rx) = mpsc::channel(10);
+        let (event_tx, _) = broadcast::channel(20);
+        let hidden_service = HiddenService {
+            identity,
+            proxied_addr: socketaddr_to_multiaddr(self.proxied_port_mapping.proxied_address()),
+            shutdown_signal: self.shutdown_signal.clone(),
+            requester: HiddenServiceRequester::new(request_tx, event_tx.clone()),
+        };
+        let shutdown_signal = self.shutdown_signal.clone();
+        let control_events = self.client.as_ref().unwrap().get_event_stream();
+
+        task::spawn(self.run(request_rx, control_events, event_tx, shutdown_signal));
+
+        Ok(hidden_service)
+    }
+
+    async fn run(
+        mut self,
+        mut request_rx: mpsc::Receiver<HiddenServiceRequest>,
+        mut control_events: BroadcastStream<TorControlEvent>,
+        event_tx: broadcast::Sender<HiddenServiceEvent>,
+        mut shutdown_signal: OptionalShutdownSignal,
+    ) {
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_signal => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Tor controller shut down because the shutdown signal was received"
+                    );
+                    break;
+                },
+                Some(request) = request_rx.recv() => {
+                    self.handle_request(request, &event_tx).await;
+                },
+                Some(Ok(event)) = control_events.next() => {
+                    if let TorControlEvent::TorControlDisconnected = event {
+                        if let Err(err) = self.handle_control_disconnected(&event_tx, &mut shutdown_signal).await {
+                            error!(
                                 target: LOG_TARGET,
-                                "Tor controller shut down because the shutdown signal was received"
+                                "Failed to reestablish connection to tor control server because '{:?}'", err
                             );
                             break;
-                        },
-                        Either::Right((Some(Ok(TorControlEvent::TorControlDisconnected)), shutdown_signal)) => {
-                            let event_tx = self
-                                .client
-                                .as_ref()
-                                .map(|c| c.event_sender().clone())
-                                .expect("HiddenServiceController::client was None");
-                            warn!(
-                                target: LOG_TARGET,
-                                "Tor control server disconnected. Attempting to reestablish connection..."
-                            );
-                            if let Err(err) = self.reestablish_hidden_service(event_tx, shutdown_signal).await {
-                                error!(
-                                    target: LOG_TARGET,
-                                    "Failed to reestablish connection to tor control server because '{:?}'", err
-                                );
-                                break;
-                            }
-                        },
-                        Either::Right((Some(Ok(evt)), _)) => {
-                            trace!(target: LOG_TARGET, "Tor control event: {:?}", evt);
-                        },
-                        _ => {},
+                        }
+                    } else {
+                        self.handle_control_event(event, &event_tx);
                     }
-                }
+                },
             }
-        });
+        }
+    }
 
-        Ok(hidden_service)
+    async fn handle_control_disconnected(
+        &mut self,
+        event_tx: &broadcast::Sender<HiddenServiceEvent>,
+        shutdown_signal: &mut OptionalShutdownSignal,
+    ) -> Result<(), HiddenServiceControllerError> {
+        let _ = event_tx.send(HiddenServiceEvent::TorControlDisconnected);
+        let control_event_tx = self
+            .client
+            .as_ref()
+            .map(|c| c.event_sender().clone())
+            .expect("HiddenServiceController::client was None");
+        warn!(
+            target: LOG_TARGET,
+            "Tor control server disconnected. Attempting to reestablish connection..."
+        );
+        self.reestablish_hidden_service(control_event_tx, shutdown_signal)
+            .await?;
+        let _ = event_tx.send(HiddenServiceEvent::ServicesReestablished(
+            self.identities().cloned().collect(),
+        ));
+        Ok(())
+    }
+
+    async fn handle_request(
+        &mut self,
+        request: HiddenServiceRequest,
+        event_tx: &broadcast::Sender<HiddenServiceEvent>,
+    ) {
+        use HiddenServiceRequest::*;
+        match request {
+            AddService(config, reply) => {
+                let _ = reply.send(self.add_service(config).await);
+            },
+            RemoveService(service_id, reply) => {
+                let _ = reply.send(self.remove_service(&service_id).await);
+            },
+            RotateIdentity(service_id, reply) => {
+                let result = self.rotate_identity(&service_id).await;
+                if let Ok(identity) = &result {
+                    let _ = event_tx.send(HiddenServiceEvent::IdentityRotated {
+                        previous_service_id: service_id,
+                        identity: identity.clone(),
+                    });
+                }
+                let _ = reply.send(result);
+            },
+            GetIdentities(reply) => {
+                let _ = reply.send(self.identities().cloned().collect());
+            },
+        }
+    }
+
+    fn handle_control_event(&self, event: TorControlEvent, event_tx: &broadcast::Sender<HiddenServiceEvent>) {
+        match event {
+            TorControlEvent::HsDescUploaded(service_id) if self.is_managed(&service_id) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Descriptor for onion service '{}' uploaded", service_id
+                );
+                let _ = event_tx.send(HiddenServiceEvent::DescriptorUploaded(service_id));
+            },
+            TorControlEvent::HsDescFailed { service_id, reason } if self.is_managed(&service_id) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Descriptor for onion service '{}' failed to upload ({})",
+                    service_id,
+                    reason.as_deref().unwrap_or("unknown reason")
+                );
+                let _ = event_tx.send(HiddenServiceEvent::DescriptorUploadFailed { service_id, reason });
+            },
+            TorControlEvent::Signal(signal) => {
+                info!(target: LOG_TARGET, "Tor received signal '{}'", signal);
+            },
+            evt => {
+                trace!(target: LOG_TARGET, "Tor control event: {:?}", evt);
+            },
+        }
     }
 
     pub async fn connect_and_auth(&mut self) -> Result<(), HiddenServiceControllerError> {
@@ -205,7 +321,12 @@ impl HiddenServiceController {
                     self.client = Some(client);
                     self.authenticate().await?;
                     self.set_events().await?;
-                    let _ = self.create_hidden_service_from_identity().await;
+                    if let Err(err) = self.publish_services().await {
+                        warn!(
+                            target: LOG_TARGET,
+                            "Failed to republish onion services because '{:?}'", err
+                        );
+                    }
                     break Ok(());
                 },
                 Either::Left((Err(err), shutdown_signal)) => {
@@ -256,7 +377,7 @@ impl HiddenServiceController {
     }
 
     async fn set_events(&mut self) -> Result<(), HiddenServiceControllerError> {
-        self.client_mut()?.set_events(&["NETWORK_LIVENESS"]).await?;
+        self.client_mut()?.set_events(CONTROL_EVENTS).await?;
         Ok(())
     }
 
@@ -286,47 +407,145 @@ impl HiddenServiceController {
         }
     }
 
-    async fn create_hidden_service_from_identity(&mut self) -> Result<HiddenService, HiddenServiceControllerError> {
+    /// Publishes the comms hidden service followed by any additional onion services, returning the identity of the
+    /// comms hidden service
+    async fn publish_services(&mut self) -> Result<TorIdentity, HiddenServiceControllerError> {
         let socks_addr = self.get_socks_address().await?;
         debug!(target: LOG_TARGET, "Tor SOCKS address is '{}'", socks_addr);
 
         // Initialize a onion hidden service - either from the given private key or by creating a new one
-        match self.identity.take() {
-            Some(identity) => {
-                let resp = self.create_or_reuse_onion(&identity).await?;
-                self.identity = Some(TorIdentity {
-                    onion_port: resp.onion_port,
-                    ..identity
-                });
-            },
-            None => {
-                let port_mapping = self.proxied_port_mapping;
-                let resp = self.client_mut()?.add_onion(vec![], port_mapping, None).await?;
-                let private_key = resp
-                    .private_key
-                    .clone()
-                    .expect("Tor server MUST return private key according to spec");
+        let identity = self.identity.clone();
+        let identity = self
+            .add_onion(identity.as_ref(), self.proxied_port_mapping, self.client_auth.clone())
+            .await?;
+        debug!(
+            target: LOG_TARGET,
+            "Added hidden service with service id '{}' on port '{}'", identity.service_id, identity.onion_port
+        );
+        self.identity = Some(identity.clone());
+
+        let mut services = Vec::with_capacity(self.additional_services.len());
+        for service in self.additional_services.clone() {
+            let service_identity = self
+                .add_onion(
+                    service.identity.as_ref(),
+                    service.port_mapping,
+                    service.client_auth.clone(),
+                )
+                .await?;
+            debug!(
+                target: LOG_TARGET,
+                "Added onion service with service id '{}' on port '{}'",
+                service_identity.service_id,
+                service_identity.onion_port
+            );
+            services.push(OnionServiceConfig {
+                identity: Some(service_identity),
+                ..service
+            });
+        }
+        self.additional_services = services;
 
-                self.identity = Some(TorIdentity {
-                    private_key,
-                    service_id: resp.service_id,
-                    onion_port: resp.onion_port,
-                });
+        Ok(identity)
+    }
+
+    async fn add_service(&mut self, config: OnionServiceConfig) -> Result<TorIdentity, HiddenServiceControllerError> {
+        if let Some(identity) = config.identity.as_ref() {
+            if self.is_managed(&identity.service_id) {
+                return Err(HiddenServiceControllerError::ServiceAlreadyExists(
+                    identity.service_id.clone(),
+                ));
+            }
+        }
+
+        let identity = self
+            .add_onion(
+                config.identity.as_ref(),
+                config.port_mapping,
+                config.client_auth.clone(),
+            )
+            .await?;
+        info!(
+            target: LOG_TARGET,
+            "Published onion service '{}' ({} authorized client(s))",
+            identity.service_id,
+            config.client_auth.len()
+        );
+        self.additional_services.push(OnionServiceConfig {
+            identity: Some(identity.clone()),
+            ..config
+        });
+        Ok(identity)
+    }
+
+    async fn remove_service(&mut self, service_id: &str) -> Result<(), HiddenServiceControllerError> {
+        if self.is_comms_service(service_id) {
+            return Err(HiddenServiceControllerError::CannotRemoveCommsService);
+        }
+        let pos = self
+            .position_of_additional_service(service_id)
+            .ok_or_else(|| HiddenServiceControllerError::ServiceNotFound(service_id.to_string()))?;
+        self.client_mut()?.del_onion(service_id).await?;
+        self.additional_services.remove(pos);
+        info!(target: LOG_TARGET, "Removed onion service '{}'", service_id);
+        Ok(())
+    }
+
+    async fn rotate_identity(&mut self, service_id: &str) -> Result<TorIdentity, HiddenServiceControllerError> {
+        let additional_pos = self.position_of_additional_service(service_id);
+        let (port_mapping, client_auth) = match additional_pos {
+            Some(pos) => {
+                let service = &self.additional_services[pos];
+                (service.port_mapping, service.client_auth.clone())
             },
+            None if self.is_comms_service(service_id) => (self.proxied_port_mapping, self.client_auth.clone()),
+            None => return Err(HiddenServiceControllerError::ServiceNotFound(service_id.to_string())),
         };
 
-        let identity = self.identity.as_ref().map(Clone::clone).expect("already checked");
-        debug!(
+        // The service may no longer exist (e.g. if tor was restarted), in which case the key is rotated regardless
+        if let Err(err) = self.client_mut()?.del_onion(service_id).await {
+            warn!(
+                target: LOG_TARGET,
+                "Failed to delete onion service '{}' before rotating its key: {}", service_id, err
+            );
+        }
+
+        let identity = self.add_onion(None, port_mapping, client_auth).await?;
+        match additional_pos {
+            Some(pos) => self.additional_services[pos].identity = Some(identity.clone()),
+            None => self.identity = Some(identity.clone()),
+        }
+        info!(
             target: LOG_TARGET,
-            "Added hidden service with service id '{}' on port '{}'", identity.service_id, identity.onion_port
+            "Rotated onion service key. Service '{}' is now '{}'", service_id, identity.service_id
         );
+        Ok(identity)
+    }
 
-        let proxied_addr = socketaddr_to_multiaddr(self.proxied_port_mapping.proxied_address());
+    /// Returns the identities of all published services, starting with the comms hidden service
+    fn identities(&self) -> impl Iterator<Item = &TorIdentity> {
+        self.identity
+            .iter()
+            .chain(self.additional_services.iter().filter_map(|s| s.identity.as_ref()))
+    }
 
-        Ok(HiddenService {
-            identity,
-            proxied_addr,
-            shutdown_signal: self.shutdown_signal.clone(),
+    fn is_managed(&self, service_id: &str) -> bool {
+        self.identities().any(|identity| identity.service_id == service_id)
+    }
+
+    fn is_comms_service(&self, service_id: &str) -> bool {
+        self.identity
+            .as_ref()
+            .map(|identity| identity.service_id == service_id)
+            .unwrap_or(false)
+    }
+
+    fn position_of_additional_service(&self, service_id: &str) -> Option<usize> {
+        self.additional_services.iter().position(|s| {
+            s.identity
+                .as_ref()
+                .map(|identity| identity.service_id == service_id)
+                .unwrap_or(false)
         })
     }
 
@@ -336,22 +555,63 @@ impl HiddenServiceController {
         )
     }
 
+    /// Adds an onion service using the given identity if provided, otherwise a new identity will be created
+    async fn add_onion(
+        &mut self,
+        identity: Option<&TorIdentity>,
+        port_mapping: PortMapping,
+        client_auth: Vec<ClientAuthPublicKey>,
+    ) -> Result<TorIdentity, HiddenServiceControllerError> {
+        match identity {
+            Some(identity) => {
+                let resp = self.create_or_reuse_onion(identity, port_mapping, client_auth).await?;
+                Ok(TorIdentity {
+                    onion_port: resp.onion_port,
+                    ..identity.clone()
+                })
+            },
+            None => {
+                // Client authorization is only supported by v3 onion services
+                let key_blob = if client_auth.is_empty() {
+                    KeyBlob::Best
+                } else {
+                    KeyBlob::Ed25519V3
+                };
+                let resp = self
+                    .client_mut()?
+                    .add_onion_with_client_auth(KeyType::New, key_blob, vec![], port_mapping, client_auth)
+                    .await?;
+                let private_key = resp
+                    .private_key
+                    .clone()
+                    .expect("Tor server MUST return private key according to spec");
+
+                Ok(TorIdentity {
+                    private_key,
+                    service_id: resp.service_id,
+                    onion_port: resp.onion_port,
+                })
+            },
+        }
+    }
+
     async fn create_or_reuse_onion(
         &mut self,
         identity: &TorIdentity,
+        port_mapping: PortMapping,
+        client_auth: Vec<ClientAuthPublicKey>,
     ) -> Result<AddOnionResponse, HiddenServiceControllerError> {
         let mut flags = Vec::new();
         if self.hs_flags.contains(HsFlags::DETACH) {
             flags.push(AddOnionFlag::Detach);
         }
 
-        let port_mapping = self.proxied_port_mapping;
-
         let client = self.client_mut()?;
 
         loop {
+            let (key_type, key_blob) = identity.private_key.to_key_type_and_blob();
             let result = client
-                .add_onion_from_private_key(&identity.private_key, flags.clone(), port_mapping, None)
+                .add_onion_with_client_auth(key_type, key_blob, flags.clone(), port_mapping, client_auth.clone())
                 .await;
 
             match result {
@@ -382,3 +642,160 @@ impl HiddenServiceController {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        runtime,
+        tor::{
+            control_client::{test_server, test_server::canned_responses},
+            HiddenServiceBuilder,
+            PrivateKey,
+        },
+    };
+    use std::net::SocketAddr;
+    use tari_test_utils::unpack_enum;
+
+    const SERVICE_ID: &str = "qigbgbs4ue3ghbupsotgh73cmmkjrin2aprlyxsrnrvpmcmzy3g4wbid";
+    const CLIENT_KEY: &str = "D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA";
+
+    async fn setup(
+        client_auth: Vec<ClientAuthPublicKey>,
+        additional_services: Vec<OnionServiceConfig>,
+    ) -> (HiddenService, test_server::State) {
+        let (addr, mock_state) = test_server::spawn_tcp().await;
+        mock_state
+            .set_command_response(
+                "GETINFO net/listeners/socks",
+                canned_responses::GET_INFO_NET_LISTENERS_OK,
+            )
+            .await;
+        mock_state
+            .set_command_response("ADD_ONION", canned_responses::ADD_ONION_OK)
+            .await;
+
+        let builder = additional_services.into_iter().fold(
+            HiddenServiceBuilder::new()
+                .with_control_server_address(addr)
+                .with_port_mapping(PortMapping::new(18141, SocketAddr::from(([127u8, 0, 0, 1], 8081u16))))
+                .with_hs_flags(HsFlags::DETACH)
+                .with_client_auth(client_auth),
+            |builder, service| builder.add_onion_service(service),
+        );
+        let hidden_service = builder.build().await.unwrap().create_hidden_service().await.unwrap();
+        (hidden_service, mock_state)
+    }
+
+    fn other_identity() -> TorIdentity {
+        TorIdentity {
+            private_key: PrivateKey::Ed25519V3("other-key".to_string()),
+            service_id: "nhqdqym6j35rk7tdou4cdj4gjjqagimutxxxxxxxxxxxxxxxxxxxxxxx".to_string(),
+            onion_port: 18142,
+        }
+    }
+
+    #[runtime::test]
+    async fn it_publishes_multiple_services_with_client_auth() {
+        let client_key = CLIENT_KEY.parse::<ClientAuthPublicKey>().unwrap();
+        let grpc_service = OnionServiceConfig::new(18142).with_identity(other_identity());
+        let (hidden_service, mock_state) = setup(vec![client_key], vec![grpc_service]).await;
+        assert_eq!(hidden_service.service_id(), SERVICE_ID);
+
+        let requests = mock_state.take_requests().await;
+        assert_eq!(requests, [
+            "AUTHENTICATE",
+            "SETEVENTS NETWORK_LIVENESS SIGNAL STATUS_CLIENT HS_DESC",
+            "GETINFO net/listeners/socks",
+            "ADD_ONION NEW:ED25519-V3 Flags=V3Auth Port=18141,127.0.0.1:8081 \
+             ClientAuthV3=D2IVSVNRPFMJ7WCYHJ3BTE2FEKU7ZKODUOZMEB5ZEXUIQG3VSAKA",
+            "ADD_ONION ED25519-V3:other-key Flags=Detach Port=18142,127.0.0.1:18142",
+        ]);
+
+        let identities = hidden_service.requester().get_identities().await.unwrap();
+        assert_eq!(identities.len(), 2);
+        assert_eq!(identities[0].service_id, SERVICE_ID);
+        assert_eq!(identities[1].service_id, other_identity().service_id);
+    }
+
+    #[runtime::test]
+    async fn it_adds_removes_and_rotates_services() {
+        let (hidden_service, mock_state) = setup(vec![], vec![]).await;
+        let requester = hidden_service.requester();
+        let mut events = requester.get_event_subscription();
+        mock_state.take_requests().await;
+
+        let identity = requester
+            .add_service(OnionServiceConfig::new(18142).with_identity(other_identity()))
+            .await
+            .unwrap();
+        assert_eq!(identity.service_id, other_identity().service_id);
+        let err = requester
+            .add_service(OnionServiceConfig::new(18143).with_identity(other_identity()))
+            .await
+            .unwrap_err();
+        unpack_enum!(HiddenServiceControllerError::ServiceAlreadyExists(_s) = err);
+
+        let err = requester.remove_service(SERVICE_ID.to_string()).await.unwrap_err();
+        unpack_enum!(HiddenServiceControllerError::CannotRemoveCommsService = err);
+        requester.remove_service(identity.service_id.clone()).await.unwrap();
+        let err = requester.remove_service(identity.service_id.clone()).await.unwrap_err();
+        unpack_enum!(HiddenServiceControllerError::ServiceNotFound(_s) = err);
+
+        let rotated = requester.rotate_identity(SERVICE_ID.to_string()).await.unwrap();
+        let event = events.recv().await.unwrap();
+        unpack_enum!(
+            HiddenServiceEvent::IdentityRotated {
+                previous_service_id,
+                identity
+            } = event
+        );
+        assert_eq!(previous_service_id, SERVICE_ID);
+        assert_eq!(identity.service_id, rotated.service_id);
+
+        let requests = mock_state.take_requests().await;
+        let del_onion = format!("DEL_ONION {}", SERVICE_ID);
+        assert_eq!(requests, [
+            "ADD_ONION ED25519-V3:other-key Flags=Detach Port=18142,127.0.0.1:18142",
+            "DEL_ONION nhqdqym6j35rk7tdou4cdj4gjjqagimutxxxxxxxxxxxxxxxxxxxxxxx",
+            del_onion.as_str(),
+            "ADD_ONION NEW:BEST Port=18141,127.0.0.1:8081",
+        ]);
+    }
+
+    #[runtime::test]
+    async fn it_republishes_services_when_tor_restarts() {
+        let grpc_service = OnionServiceConfig::new(18142).with_identity(other_identity());
+        let (hidden_service, mock_state) = setup(vec![], vec![grpc_service]).await;
+        let mut events = hidden_service.requester().get_event_subscription();
+        mock_state.take_requests().await;
+
+        mock_state.send_event(&format!("650 HS_DESC UPLOADED {} UNKNOWN $AAAA", SERVICE_ID));
+        let event = events.recv().await.unwrap();
+        unpack_enum!(HiddenServiceEvent::DescriptorUploaded(service_id) = event);
+        assert_eq!(service_id, SERVICE_ID);
+
+        mock_state.disconnect();
+        let event = events.recv().await.unwrap();
+        unpack_enum!(HiddenServiceEvent::TorControlDisconnected = event);
+        let event = events.recv().await.unwrap();
+        unpack_enum!(HiddenServiceEvent::ServicesReestablished(identities) = event);
+        assert_eq!(identities.len(), 2);
+
+        let requests = mock_state.take_requests().await;
+        let private_key = match hidden_service.tor_identity().private_key {
+            PrivateKey::Ed25519V3(ref key) => key.clone(),
+            _ => panic!("Expected ED25519-V3 key"),
+        };
+        assert_eq!(requests, [
+            "AUTHENTICATE".to_string(),
+            "SETEVENTS NETWORK_LIVENESS SIGNAL STATUS_CLIENT HS_DESC".to_string(),
+            "GETINFO net/listeners/socks".to_string(),
+            format!(
+                "ADD_ONION ED25519-V3:{} Flags=Detach Port=18141,127.0.0.1:8081",
+                private_key
+            ),
+            "ADD_ONION ED25519-V3:other-key Flags=Detach Port=18142,127.0.0.1:18142".to_string(),
+        ]);
+    }
+}
@@ -29,6 +29,9 @@ pub use controller::{HiddenServiceController, HiddenServiceControllerError};
 mod proxy_opts;
 pub use proxy_opts::TorProxyOpts;
 
+mod requester;
+pub use requester::{HiddenServiceEvent, HiddenServiceRequester, OnionServiceConfig};
+
 use crate::{
     multiaddr::Multiaddr,
     tor::{PrivateKey, TorClientError},
@@ -47,6 +50,8 @@ pub struct HiddenService {
     pub(super) proxied_addr: Multiaddr,
     /// Shutdown signal for hidden service
     pub(super) shutdown_signal: OptionalShutdownSignal,
+    /// Handle used to manage the onion services published by the controller
+    pub(super) requester: HiddenServiceRequester,
 }
 
 impl HiddenService {
@@ -64,9 +69,17 @@ impl HiddenService {
         &self.proxied_addr
     }
 
+    /// The identity of the hidden service when it was created. This does not change if the key is later rotated
+    /// using the requester.
     pub fn tor_identity(&self) -> &TorIdentity {
         &self.identity
     }
+
+    /// Returns a handle that can be used to publish additional onion services, rotate keys and subscribe to hidden
+    /// service events
+    pub fn requester(&self) -> HiddenServiceRequester {
+        self.requester.clone()
+    }
 }
 
 fn multiaddr_from_service_id_and_port(service_id: &str, onion_port: u16) -> Result<Multiaddr, TorClientError> {
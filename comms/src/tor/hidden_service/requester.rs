@@ -0,0 +1,158 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::{controller::HiddenServiceControllerError, TorIdentity};
+use crate::tor::{ClientAuthPublicKey, PortMapping};
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// Configuration for an onion service that is published in addition to the comms hidden service, for example to
+/// expose a gRPC endpoint.
+#[derive(Debug, Clone)]
+pub struct OnionServiceConfig {
+    /// The onion -> local address mapping for the service
+    pub port_mapping: PortMapping,
+    /// The identity of the service. If not supplied, a new service will be requested from the Tor Control Port.
+    pub identity: Option<TorIdentity>,
+    /// The clients that are authorized to connect to this service. If empty, any client may connect.
+    pub client_auth: Vec<ClientAuthPublicKey>,
+}
+
+impl OnionServiceConfig {
+    pub fn new<P: Into<PortMapping>>(port_mapping: P) -> Self {
+        Self {
+            port_mapping: port_mapping.into(),
+            identity: None,
+            client_auth: Vec::new(),
+        }
+    }
+
+    pub fn with_identity(mut self, identity: TorIdentity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    pub fn with_client_auth(mut self, client_auth: Vec<ClientAuthPublicKey>) -> Self {
+        self.client_auth = client_auth;
+        self
+    }
+}
+
+/// Events emitted by the hidden service controller
+#[derive(Debug, Clone)]
+pub enum HiddenServiceEvent {
+    /// The connection to the Tor control port was lost, typically because Tor was restarted
+    TorControlDisconnected,
+    /// The connection to the Tor control port was re-established and all onion services have been re-published
+    ServicesReestablished(Vec<TorIdentity>),
+    /// The key for an onion service was rotated. The new identity should be persisted.
+    IdentityRotated {
+        previous_service_id: String,
+        identity: TorIdentity,
+    },
+    /// The descriptor for the onion service was uploaded to a hidden service directory
+    DescriptorUploaded(String),
+    /// The descriptor for the onion service failed to upload to a hidden service directory
+    DescriptorUploadFailed { service_id: String, reason: Option<String> },
+}
+
+#[derive(Debug)]
+pub enum HiddenServiceRequest {
+    AddService(
+        OnionServiceConfig,
+        oneshot::Sender<Result<TorIdentity, HiddenServiceControllerError>>,
+    ),
+    RemoveService(String, oneshot::Sender<Result<(), HiddenServiceControllerError>>),
+    RotateIdentity(
+        String,
+        oneshot::Sender<Result<TorIdentity, HiddenServiceControllerError>>,
+    ),
+    GetIdentities(oneshot::Sender<Vec<TorIdentity>>),
+}
+
+/// Handle used to manage the onion services published by the hidden service controller
+#[derive(Debug, Clone)]
+pub struct HiddenServiceRequester {
+    sender: mpsc::Sender<HiddenServiceRequest>,
+    event_tx: broadcast::Sender<HiddenServiceEvent>,
+}
+
+impl HiddenServiceRequester {
+    pub(super) fn new(
+        sender: mpsc::Sender<HiddenServiceRequest>,
+        event_tx: broadcast::Sender<HiddenServiceEvent>,
+    ) -> Self {
+        Self { sender, event_tx }
+    }
+
+    pub fn get_event_subscription(&self) -> broadcast::Receiver<HiddenServiceEvent> {
+        self.event_tx.subscribe()
+    }
+
+    /// Publish an additional onion service. Returns the identity of the service, which should be persisted if the
+    /// same onion address should be used in future.
+    pub async fn add_service(&self, config: OnionServiceConfig) -> Result<TorIdentity, HiddenServiceControllerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(HiddenServiceRequest::AddService(config, reply_tx)).await?;
+        reply_rx
+            .await
+            .map_err(|_| HiddenServiceControllerError::ControllerResponseCancelled)?
+    }
+
+    /// Remove an additional onion service. The comms hidden service cannot be removed.
+    pub async fn remove_service(&self, service_id: String) -> Result<(), HiddenServiceControllerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(HiddenServiceRequest::RemoveService(service_id, reply_tx))
+            .await?;
+        reply_rx
+            .await
+            .map_err(|_| HiddenServiceControllerError::ControllerResponseCancelled)?
+    }
+
+    /// Replace the key of the onion service with a newly generated key. The service is published at a new onion
+    /// address on the same port and the previous address becomes unreachable. Returns the new identity.
+    ///
+    /// If this is the comms hidden service, the caller is responsible for updating the node's public address.
+    pub async fn rotate_identity(&self, service_id: String) -> Result<TorIdentity, HiddenServiceControllerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(HiddenServiceRequest::RotateIdentity(service_id, reply_tx))
+            .await?;
+        reply_rx
+            .await
+            .map_err(|_| HiddenServiceControllerError::ControllerResponseCancelled)?
+    }
+
+    /// Returns the identities of all published onion services, starting with the comms hidden service
+    pub async fn get_identities(&self) -> Result<Vec<TorIdentity>, HiddenServiceControllerError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.send(HiddenServiceRequest::GetIdentities(reply_tx)).await?;
+        reply_rx
+            .await
+            .map_err(|_| HiddenServiceControllerError::ControllerResponseCancelled)
+    }
+
+    async fn send(&self, request: HiddenServiceRequest) -> Result<(), HiddenServiceControllerError> {
+        self.sender
+            .send(request)
+            .await
+            .map_err(|_| HiddenServiceControllerError::ControllerDisconnected)
+    }
+}
@@ -32,7 +32,10 @@
 
 mod control_client;
 pub use control_client::{
+    generate_client_auth_keypair,
     Authentication,
+    ClientAuthPrivateKey,
+    ClientAuthPublicKey,
     KeyBlob,
     KeyType,
     PortMapping,
@@ -48,6 +51,9 @@ pub use hidden_service::{
     HiddenServiceBuilderError,
     HiddenServiceController,
     HiddenServiceControllerError,
+    HiddenServiceEvent,
+    HiddenServiceRequester,
     HsFlags,
+    OnionServiceConfig,
     TorIdentity,
 };
@@ -24,7 +24,10 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{connection_manager::ConnectionDirection, multiplexing::Yamux, noise::NoiseSocket};
 use multiaddr::Multiaddr;
+use std::io;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_stream::Stream;
 
 mod dns;
@@ -43,6 +46,11 @@ pub use tcp::TcpTransport;
 mod tcp_with_tor;
 pub use tcp_with_tor::TcpWithTorTransport;
 
+#[cfg(feature = "quic")]
+mod quic;
+#[cfg(feature = "quic")]
+pub use quic::{QuicConfig, QuicInbound, QuicSocket, QuicTransport};
+
 #[crate::async_trait]
 pub trait Transport {
     /// The output of the transport after a connection is established
@@ -57,4 +65,11 @@ pub trait Transport {
 
     /// Connect (dial) to the given multiaddr
     async fn dial(&self, addr: Multiaddr) -> Result<Self::Output, Self::Error>;
+
+    /// Upgrade a socket that has been authenticated with noise to a multiplexed connection. Transports that do not
+    /// natively support multiplexing use this default implementation, which layers yamux over the socket.
+    async fn multiplex(socket: NoiseSocket<Self::Output>, direction: ConnectionDirection) -> io::Result<Yamux>
+    where Self::Output: AsyncRead + AsyncWrite + Send + Unpin + 'static {
+        Yamux::upgrade_connection(socket, direction).await
+    }
 }
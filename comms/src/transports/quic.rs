@@ -0,0 +1,530 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::Transport;
+use crate::{
+    connection_manager::ConnectionDirection,
+    multiplexing::{self, Yamux},
+    noise::NoiseSocket,
+};
+use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use log::*;
+use multiaddr::{Multiaddr, Protocol};
+use std::{
+    convert::TryFrom,
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time,
+};
+use tokio_stream::Stream;
+
+const LOG_TARGET: &str = "comms::transports::quic";
+
+/// The ALPN protocol negotiated for comms QUIC connections
+const ALPN_PROTOCOL: &[u8] = b"tari-comms/1";
+/// The server name used in the TLS handshake. Peers are authenticated by the noise handshake, so the certificate is
+/// not verified.
+const SERVER_NAME: &str = "tari";
+
+#[derive(Debug, Clone)]
+pub struct QuicConfig {
+    /// The interval at which keep-alive packets are sent on idle connections.
+    /// Default: 10s
+    pub keep_alive_interval: Duration,
+    /// Connections that have had no activity for this long are closed.
+    /// Default: 30s
+    pub max_idle_timeout: Duration,
+    /// The maximum number of concurrent streams (substreams) the peer may open on a connection.
+    /// Default: 1024
+    pub max_concurrent_streams: u32,
+    /// The maximum time allowed for a connection to complete the QUIC handshake and open the initial stream.
+    /// Default: 10s
+    pub handshake_timeout: Duration,
+}
+
+impl Default for QuicConfig {
+    fn default() -> Self {
+        Self {
+            keep_alive_interval: Duration::from_secs(10),
+            max_idle_timeout: Duration::from_secs(30),
+            max_concurrent_streams: 1024,
+            handshake_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Transport implementation for QUIC. Addresses take the form `/ip4/1.2.3.4/udp/1234/quic`.
+///
+/// The first stream of each connection carries the wire mode byte and the noise handshake, exactly as a TCP socket
+/// would. Once authenticated, further QUIC streams are used as substreams instead of yamux (see
+/// [Transport::multiplex]).
+#[derive(Clone)]
+pub struct QuicTransport {
+    config: QuicConfig,
+    client_config: quinn::ClientConfig,
+    endpoints: Arc<Mutex<Vec<quinn::Endpoint>>>,
+}
+
+impl QuicTransport {
+    pub fn new(config: QuicConfig) -> Self {
+        let mut client_crypto = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+            .with_no_client_auth();
+        client_crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        let mut client_config = quinn::ClientConfig::new(Arc::new(client_crypto));
+        client_config.transport = Arc::new(create_transport_config(&config));
+
+        Self {
+            config,
+            client_config,
+            endpoints: Default::default(),
+        }
+    }
+
+    fn create_server_config(&self) -> io::Result<quinn::ServerConfig> {
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()]).map_err(to_io_error)?;
+        let cert_der = cert.serialize_der().map_err(to_io_error)?;
+        let key_der = cert.serialize_private_key_der();
+        let mut server_crypto = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(vec![rustls::Certificate(cert_der)], rustls::PrivateKey(key_der))
+            .map_err(to_io_error)?;
+        server_crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        let mut server_config = quinn::ServerConfig::with_crypto(Arc::new(server_crypto));
+        server_config.transport = Arc::new(create_transport_config(&self.config));
+        Ok(server_config)
+    }
+
+    /// Returns an endpoint that can dial the given address. Listening endpoints are reused for outbound connections
+    /// so that peers see a consistent source port, otherwise a client endpoint is bound to an ephemeral port.
+    fn get_dial_endpoint(&self, addr: &SocketAddr) -> io::Result<quinn::Endpoint> {
+        let mut endpoints = acquire_lock!(self.endpoints);
+        let existing = endpoints.iter().find(|endpoint| {
+            endpoint
+                .local_addr()
+                .map(|local| can_dial_from(&local, addr))
+                .unwrap_or(false)
+        });
+        if let Some(endpoint) = existing {
+            return Ok(endpoint.clone());
+        }
+
+        let bind_addr = match addr {
+            SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+        };
+        let endpoint = quinn::Endpoint::client(bind_addr)?;
+        endpoints.push(endpoint.clone());
+        Ok(endpoint)
+    }
+}
+
+impl Default for QuicTransport {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+#[crate::async_trait]
+impl Transport for QuicTransport {
+    type Error = io::Error;
+    type Listener = QuicInbound;
+    type Output = QuicSocket;
+
+    async fn listen(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let (endpoint, incoming) = quinn::Endpoint::server(self.create_server_config()?, socket_addr)?;
+        let local_addr = socketaddr_to_multiaddr(&endpoint.local_addr()?);
+        acquire_lock!(self.endpoints).push(endpoint);
+        Ok((QuicInbound::new(incoming, self.config.handshake_timeout), local_addr))
+    }
+
+    async fn dial(&self, addr: Multiaddr) -> Result<Self::Output, Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let endpoint = self.get_dial_endpoint(&socket_addr)?;
+        let connecting = endpoint
+            .connect_with(self.client_config.clone(), socket_addr, SERVER_NAME)
+            .map_err(to_io_error)?;
+
+        let connect = async move {
+            let quinn::NewConnection {
+                connection, bi_streams, ..
+            } = connecting.await?;
+            let (send, recv) = connection.open_bi().await?;
+            Result::<_, io::Error>::Ok(QuicSocket::new(connection, bi_streams, send, recv))
+        };
+
+        time::timeout(self.config.handshake_timeout, connect)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "QUIC handshake timed out"))?
+    }
+
+    async fn multiplex(socket: NoiseSocket<Self::Output>, direction: ConnectionDirection) -> io::Result<Yamux> {
+        multiplexing::quic::upgrade_connection(socket, direction).await
+    }
+}
+
+/// A QUIC connection. Reads and writes are performed on the initial bidirectional stream.
+#[derive(Debug)]
+pub struct QuicSocket {
+    connection: quinn::Connection,
+    incoming: quinn::IncomingBiStreams,
+    send: quinn::SendStream,
+    recv: quinn::RecvStream,
+}
+
+impl QuicSocket {
+    fn new(
+        connection: quinn::Connection,
+        incoming: quinn::IncomingBiStreams,
+        send: quinn::SendStream,
+        recv: quinn::RecvStream,
+    ) -> Self {
+        Self {
+            connection,
+            incoming,
+            send,
+            recv,
+        }
+    }
+
+    /// Returns the underlying QUIC connection
+    pub fn connection(&self) -> &quinn::Connection {
+        &self.connection
+    }
+
+    /// Returns the address of the remote peer
+    pub fn remote_address(&self) -> SocketAddr {
+        self.connection.remote_address()
+    }
+
+    pub(crate) fn into_parts(self) -> (quinn::Connection, quinn::IncomingBiStreams) {
+        (self.connection, self.incoming)
+    }
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.recv).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_shutdown(cx)
+    }
+}
+
+/// Stream of inbound QUIC connections. Handshakes are performed concurrently, so a slow or malicious peer cannot
+/// block other peers from connecting.
+pub struct QuicInbound {
+    incoming: quinn::Incoming,
+    pending: FuturesUnordered<BoxFuture<'static, io::Result<(QuicSocket, Multiaddr)>>>,
+    handshake_timeout: Duration,
+    is_incoming_done: bool,
+}
+
+impl QuicInbound {
+    fn new(incoming: quinn::Incoming, handshake_timeout: Duration) -> Self {
+        Self {
+            incoming,
+            pending: FuturesUnordered::new(),
+            handshake_timeout,
+            is_incoming_done: false,
+        }
+    }
+
+    async fn accept(connecting: quinn::Connecting, handshake_timeout: Duration) -> io::Result<(QuicSocket, Multiaddr)> {
+        let peer_addr = socketaddr_to_multiaddr(&connecting.remote_address());
+        let accept = async move {
+            let quinn::NewConnection {
+                connection,
+                mut bi_streams,
+                ..
+            } = connecting.await?;
+            let (send, recv) = bi_streams.next().await.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "QUIC connection closed before the initial stream was opened",
+                )
+            })??;
+            Result::<_, io::Error>::Ok(QuicSocket::new(connection, bi_streams, send, recv))
+        };
+
+        let socket = time::timeout(handshake_timeout, accept)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "QUIC handshake timed out"))??;
+        Ok((socket, peer_addr))
+    }
+}
+
+impl Stream for QuicInbound {
+    type Item = io::Result<(QuicSocket, Multiaddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while !self.is_incoming_done {
+            match Pin::new(&mut self.incoming).poll_next(cx) {
+                Poll::Ready(Some(connecting)) => {
+                    trace!(
+                        target: LOG_TARGET,
+                        "Inbound QUIC connection from '{}'",
+                        connecting.remote_address()
+                    );
+                    let accept = Self::accept(connecting, self.handshake_timeout).boxed();
+                    self.pending.push(accept);
+                },
+                Poll::Ready(None) => {
+                    self.is_incoming_done = true;
+                },
+                Poll::Pending => break,
+            }
+        }
+
+        match self.pending.poll_next_unpin(cx) {
+            Poll::Ready(Some(result)) => Poll::Ready(Some(result)),
+            // No handshakes are pending, the incoming stream will wake this task when a connection arrives
+            Poll::Ready(None) if !self.is_incoming_done => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+fn create_transport_config(config: &QuicConfig) -> quinn::TransportConfig {
+    let mut transport = quinn::TransportConfig::default();
+    transport
+        .keep_alive_interval(Some(config.keep_alive_interval))
+        .max_concurrent_bidi_streams(config.max_concurrent_streams.into())
+        // Substreams are always bidirectional
+        .max_concurrent_uni_streams(0u32.into());
+    match quinn::IdleTimeout::try_from(config.max_idle_timeout) {
+        Ok(timeout) => {
+            transport.max_idle_timeout(Some(timeout));
+        },
+        Err(_) => {
+            warn!(
+                target: LOG_TARGET,
+                "QUIC max idle timeout {:.2?} is too large and will not be applied", config.max_idle_timeout
+            );
+        },
+    }
+    transport
+}
+
+/// The TLS layer of QUIC is only used for encryption. The remote peer is authenticated by the noise handshake, which
+/// is bound to the TLS session using exported keying material, so any certificate is accepted here.
+struct SkipServerVerification;
+
+impl rustls::client::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// Returns true if an endpoint bound to `local` is able to send to `remote`
+fn can_dial_from(local: &SocketAddr, remote: &SocketAddr) -> bool {
+    local.is_ipv4() == remote.is_ipv4() &&
+        (local.ip().is_unspecified() || local.ip().is_loopback() == remote.ip().is_loopback())
+}
+
+/// Convert a `/ip4/.../udp/.../quic` or `/ip6/.../udp/.../quic` multiaddr to a socket address
+fn multiaddr_to_socketaddr(addr: &Multiaddr) -> io::Result<SocketAddr> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid QUIC address '{}'", addr));
+    let mut iter = addr.iter();
+    let ip = match iter.next() {
+        Some(Protocol::Ip4(ip)) => IpAddr::V4(ip),
+        Some(Protocol::Ip6(ip)) => IpAddr::V6(ip),
+        _ => return Err(invalid()),
+    };
+    let port = match iter.next() {
+        Some(Protocol::Udp(port)) => port,
+        _ => return Err(invalid()),
+    };
+    match (iter.next(), iter.next()) {
+        (Some(Protocol::Quic), None) => Ok(SocketAddr::new(ip, port)),
+        _ => Err(invalid()),
+    }
+}
+
+fn socketaddr_to_multiaddr(socket_addr: &SocketAddr) -> Multiaddr {
+    let mut addr: Multiaddr = match socket_addr.ip() {
+        IpAddr::V4(addr) => Protocol::Ip4(addr).into(),
+        IpAddr::V6(addr) => Protocol::Ip6(addr).into(),
+    };
+    addr.push(Protocol::Udp(socket_addr.port()));
+    addr.push(Protocol::Quic);
+    addr
+}
+
+fn to_io_error<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        noise::NoiseConfig,
+        peer_manager::PeerFeatures,
+        runtime,
+        test_utils::node_identity::build_node_identity,
+    };
+    use futures::future;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn connect(transport: &QuicTransport) -> (QuicSocket, QuicSocket) {
+        let (mut inbound, addr) = transport
+            .listen("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())
+            .await
+            .unwrap();
+        let mut socket_out = transport.dial(addr).await.unwrap();
+        // The initial stream is only visible to the listener once data has been sent
+        socket_out.write_all(&[1]).await.unwrap();
+        socket_out.flush().await.unwrap();
+        let (mut socket_in, _) = inbound.next().await.unwrap().unwrap();
+        let mut buf = [0u8; 1];
+        socket_in.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf, [1]);
+        (socket_in, socket_out)
+    }
+
+    #[runtime::test]
+    async fn listen_and_dial() {
+        let transport = QuicTransport::default();
+        let (mut listener, addr) = transport
+            .listen("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())
+            .await
+            .unwrap();
+        let mut iter = addr.iter();
+        assert!(matches!(iter.next(), Some(Protocol::Ip4(_))));
+        assert!(matches!(iter.next(), Some(Protocol::Udp(port)) if port > 0));
+        assert!(matches!(iter.next(), Some(Protocol::Quic)));
+
+        let mut outbound = transport.dial(addr).await.unwrap();
+        outbound.write_all(b"hello world").await.unwrap();
+        outbound.shutdown().await.unwrap();
+
+        let (mut inbound, peer_addr) = listener.next().await.unwrap().unwrap();
+        assert!(matches!(peer_addr.iter().nth(2), Some(Protocol::Quic)));
+        let mut buf = Vec::new();
+        inbound.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello world");
+    }
+
+    #[runtime::test]
+    async fn multiplex_substreams() {
+        let transport = QuicTransport::default();
+        let (socket_in, socket_out) = connect(&transport).await;
+
+        let noise_in = NoiseConfig::new(build_node_identity(PeerFeatures::COMMUNICATION_NODE));
+        let noise_out = NoiseConfig::new(build_node_identity(PeerFeatures::COMMUNICATION_NODE));
+        let (socket_in, socket_out) = future::join(
+            noise_in.upgrade_socket(socket_in, ConnectionDirection::Inbound),
+            noise_out.upgrade_socket(socket_out, ConnectionDirection::Outbound),
+        )
+        .await;
+
+        let (muxer_in, muxer_out) = future::join(
+            QuicTransport::multiplex(socket_in.unwrap(), ConnectionDirection::Inbound),
+            QuicTransport::multiplex(socket_out.unwrap(), ConnectionDirection::Outbound),
+        )
+        .await;
+        let mut muxer_in = muxer_in.unwrap();
+        let mut muxer_out = muxer_out.unwrap();
+
+        let mut substream_out = muxer_out.get_yamux_control().open_stream().await.unwrap();
+        substream_out.write_all(b"ping").await.unwrap();
+        substream_out.flush().await.unwrap();
+        let mut substream_in = muxer_in.incoming_mut().next().await.unwrap();
+        let mut buf = [0u8; 4];
+        substream_in.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        // Substreams can be opened from either side
+        let mut substream_in2 = muxer_in.get_yamux_control().open_stream().await.unwrap();
+        substream_in2.write_all(b"pong").await.unwrap();
+        substream_in2.flush().await.unwrap();
+        let mut substream_out2 = muxer_out.incoming_mut().next().await.unwrap();
+        substream_out2.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pong");
+        assert_eq!(muxer_out.substream_count(), 2);
+
+        muxer_out.get_yamux_control().close().await.unwrap();
+        assert!(muxer_in.incoming_mut().next().await.is_none());
+    }
+
+    #[runtime::test]
+    async fn unsupported_multiaddrs() {
+        let transport = QuicTransport::default();
+        let err = transport
+            .listen("/ip4/127.0.0.1/tcp/0".parse().unwrap())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = transport
+            .dial("/ip4/127.0.0.1/udp/1234".parse().unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = transport.dial("/memory/1234".parse().unwrap()).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn dial_endpoint_selection() {
+        let unspecified = "0.0.0.0:1234".parse().unwrap();
+        let loopback = "127.0.0.1:1234".parse().unwrap();
+        let public = "1.2.3.4:1234".parse().unwrap();
+        let public_v6 = "[2001:db8::1]:1234".parse().unwrap();
+        assert!(can_dial_from(&unspecified, &public));
+        assert!(can_dial_from(&unspecified, &loopback));
+        assert!(can_dial_from(&loopback, &loopback));
+        assert!(!can_dial_from(&loopback, &public));
+        assert!(!can_dial_from(&unspecified, &public_v6));
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! # Tari metrics
+//!
+//! A shared registry of Prometheus metrics. Every crate registers its metrics in the default registry when they are
+//! first used and keeps them in its own `metrics` module. All metric names are prefixed with `tari_`. With the
+//! `server` feature the registered metrics can be served in the Prometheus text format by the
+//! [MetricsServer](server::MetricsServer).
+
+#![cfg_attr(not(debug_assertions), deny(unused_variables))]
+#![cfg_attr(not(debug_assertions), deny(unused_imports))]
+#![cfg_attr(not(debug_assertions), deny(dead_code))]
+#![cfg_attr(not(debug_assertions), deny(unused_extern_crates))]
+#![deny(unused_must_use)]
+#![deny(unreachable_patterns)]
+#![deny(unknown_lints)]
+
+mod error;
+pub use error::MetricsError;
+
+#[cfg(feature = "server")]
+pub mod server;
+
+use once_cell::sync::Lazy;
+use prometheus::{Encoder, HistogramOpts, Opts, TextEncoder};
+pub use prometheus::{
+    Histogram,
+    HistogramTimer,
+    HistogramVec,
+    IntCounter,
+    IntCounterVec,
+    IntGauge,
+    IntGaugeVec,
+    Registry,
+};
+
+/// The prefix of every metric name
+pub const NAMESPACE: &str = "tari";
+
+static DEFAULT_REGISTRY: Lazy<Registry> =
+    Lazy::new(|| Registry::new_custom(Some(NAMESPACE.to_string()), None).expect("namespace is valid"));
+
+/// The registry that all metrics are registered in
+pub fn get_default_registry() -> &'static Registry {
+    &DEFAULT_REGISTRY
+}
+
+pub fn register_int_counter(name: &str, help: &str) -> Result<IntCounter, MetricsError> {
+    let counter = IntCounter::with_opts(Opts::new(name, help))?;
+    DEFAULT_REGISTRY.register(Box::new(counter.clone()))?;
+    Ok(counter)
+}
+
+pub fn register_int_counter_vec(name: &str, help: &str, labels: &[&str]) -> Result<IntCounterVec, MetricsError> {
+    let counter = IntCounterVec::new(Opts::new(name, help), labels)?;
+    DEFAULT_REGISTRY.register(Box::new(counter.clone()))?;
+    Ok(counter)
+}
+
+pub fn register_int_gauge(name: &str, help: &str) -> Result<IntGauge, MetricsError> {
+    let gauge = IntGauge::with_opts(Opts::new(name, help))?;
+    DEFAULT_REGISTRY.register(Box::new(gauge.clone()))?;
+    Ok(gauge)
+}
+
+pub fn register_int_gauge_vec(name: &str, help: &str, labels: &[&str]) -> Result<IntGaugeVec, MetricsError> {
+    let gauge = IntGaugeVec::new(Opts::new(name, help), labels)?;
+    DEFAULT_REGISTRY.register(Box::new(gauge.clone()))?;
+    Ok(gauge)
+}
+
+/// Register a histogram with the default buckets, which suit latencies measured in seconds
+pub fn register_histogram(name: &str, help: &str) -> Result<Histogram, MetricsError> {
+    let histogram = Histogram::with_opts(HistogramOpts::new(name, help))?;
+    DEFAULT_REGISTRY.register(Box::new(histogram.clone()))?;
+    Ok(histogram)
+}
+
+/// Register a histogram vector with the default buckets, which suit latencies measured in seconds
+pub fn register_histogram_vec(name: &str, help: &str, labels: &[&str]) -> Result<HistogramVec, MetricsError> {
+    let histogram = HistogramVec::new(HistogramOpts::new(name, help), labels)?;
+    DEFAULT_REGISTRY.register(Box::new(histogram.clone()))?;
+    Ok(histogram)
+}
+
+/// Encode the current value of every registered metric in the Prometheus text format
+pub fn encode_text() -> Result<String, MetricsError> {
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&DEFAULT_REGISTRY.gather(), &mut buf)?;
+    Ok(String::from_utf8(buf).expect("the text encoder only writes UTF-8"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_encodes_registered_metrics() {
+        let counter = register_int_counter("test_events", "Number of test events").unwrap();
+        let gauge = register_int_gauge_vec("test_peers", "Number of test peers", &["role"]).unwrap();
+        counter.inc_by(3);
+        gauge.with_label_values(&["node"]).set(5);
+
+        let text = encode_text().unwrap();
+        assert!(text.contains("# TYPE tari_test_events counter"));
+        assert!(text.contains("tari_test_events 3"));
+        assert!(text.contains("tari_test_peers{role=\"node\"} 5"));
+    }
+
+    #[test]
+    fn it_rejects_duplicate_metrics() {
+        register_int_counter("test_duplicate", "A metric").unwrap();
+        assert!(register_int_counter("test_duplicate", "A metric").is_err());
+    }
+}
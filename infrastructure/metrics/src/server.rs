@@ -0,0 +1,137 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Serves the registered metrics over HTTP at `/metrics` in the Prometheus text format
+
+use crate::{encode_text, MetricsError};
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body,
+    Method,
+    Request,
+    Response,
+    Server,
+    StatusCode,
+};
+use log::*;
+use prometheus::TEXT_FORMAT;
+use std::{
+    convert::Infallible,
+    future::{self, Future},
+    net::SocketAddr,
+};
+
+const LOG_TARGET: &str = "metrics::server";
+
+pub struct MetricsServer {
+    listener_address: SocketAddr,
+}
+
+impl MetricsServer {
+    pub fn new(listener_address: SocketAddr) -> Self {
+        Self { listener_address }
+    }
+
+    /// Serve the metrics until the future is dropped
+    pub async fn run(self) -> Result<(), MetricsError> {
+        self.run_with_shutdown(future::pending()).await
+    }
+
+    /// Serve the metrics until the shutdown future resolves
+    pub async fn run_with_shutdown<F>(self, shutdown: F) -> Result<(), MetricsError>
+    where F: Future<Output = ()> {
+        let server = Server::try_bind(&self.listener_address)?.serve(make_service_fn(|_| async {
+            Ok::<_, Infallible>(service_fn(handle_request))
+        }));
+        info!(
+            target: LOG_TARGET,
+            "Serving metrics on http://{}/metrics",
+            server.local_addr()
+        );
+        server.with_graceful_shutdown(shutdown).await?;
+        Ok(())
+    }
+}
+
+async fn handle_request(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (request.method(), request.uri().path()) {
+        (&Method::GET, "/metrics") => match encode_text() {
+            Ok(text) => Response::builder()
+                .header(CONTENT_TYPE, TEXT_FORMAT)
+                .body(Body::from(text)),
+            Err(e) => {
+                error!(target: LOG_TARGET, "Could not encode metrics: {}", e);
+                Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::empty())
+            },
+        },
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()),
+    };
+    Ok(response.expect("response is valid"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::register_int_counter;
+    use std::net::TcpListener;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    async fn get(address: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn it_serves_metrics() {
+        register_int_counter("test_server_requests", "Number of test requests")
+            .unwrap()
+            .inc();
+        // Find a free port for the server
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        tokio::spawn(MetricsServer::new(address).run());
+
+        let mut response = String::new();
+        for _ in 0..50 {
+            if TcpStream::connect(address).await.is_ok() {
+                response = get(address, "/metrics").await;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("tari_test_server_requests 1"));
+
+        assert!(get(address, "/other").await.starts_with("HTTP/1.1 404"));
+    }
+}
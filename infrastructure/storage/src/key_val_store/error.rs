@@ -35,6 +35,10 @@ pub enum KeyValStoreError {
     DeserializationError(String),
     #[error("The specified key did not exist in the key-val store")]
     KeyNotFound,
+    #[error("Failed to encrypt value: `{0}`")]
+    EncryptionError(String),
+    #[error("Failed to decrypt value: `{0}`")]
+    DecryptionError(String),
 }
 
 impl From<LMDBError> for KeyValStoreError {
@@ -24,6 +24,7 @@ use crate::{
     key_val_store::{
         key_val_store::{IterationResult, KeyValueStore},
         KeyValStoreError,
+        ValueCipher,
     },
     lmdb_store::LMDBDatabase,
 };
@@ -36,6 +37,7 @@ use std::{marker::PhantomData, sync::Arc};
 /// instances of LMDBDatabase each with different K and V types.
 pub struct LMDBWrapper<K, V> {
     inner: Arc<LMDBDatabase>,
+    cipher: Option<Arc<dyn ValueCipher>>,
     _k: PhantomData<K>,
     _v: PhantomData<V>,
 }
@@ -45,15 +47,38 @@ impl<K, V> LMDBWrapper<K, V> {
     pub fn new(db: Arc<LMDBDatabase>) -> LMDBWrapper<K, V> {
         LMDBWrapper {
             inner: db,
+            cipher: None,
             _k: PhantomData,
             _v: PhantomData,
         }
     }
 
+    /// Encrypt values at rest using the given cipher. Values that were written without this cipher cannot be read
+    /// through this wrapper.
+    pub fn with_cipher(mut self, cipher: Arc<dyn ValueCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
     /// Get access to the underlying LMDB database
     pub fn inner(&self) -> Arc<LMDBDatabase> {
         Arc::clone(&self.inner)
     }
+
+    /// Returns the cipher used to encrypt values, or None if values are stored in the clear
+    pub fn cipher(&self) -> Option<Arc<dyn ValueCipher>> {
+        self.cipher.clone()
+    }
+}
+
+fn encrypt_value<V: Serialize>(cipher: &dyn ValueCipher, value: &V) -> Result<Vec<u8>, KeyValStoreError> {
+    let plaintext = bincode::serialize(value).map_err(|e| KeyValStoreError::SerializationError(e.to_string()))?;
+    cipher.encrypt(&plaintext)
+}
+
+fn decrypt_value<V: DeserializeOwned>(cipher: &dyn ValueCipher, ciphertext: &[u8]) -> Result<V, KeyValStoreError> {
+    let plaintext = cipher.decrypt(ciphertext)?;
+    bincode::deserialize(&plaintext).map_err(|e| KeyValStoreError::DeserializationError(e.to_string()))
 }
 
 impl<K, V> KeyValueStore<K, V> for LMDBWrapper<K, V>
@@ -63,18 +88,43 @@ where
 {
     /// Inserts a key-value pair into the key-value database.
     fn insert(&self, key: K, value: V) -> Result<(), KeyValStoreError> {
-        self.inner.insert::<K, V>(&key, &value).map_err(Into::into)
+        match self.cipher.as_deref() {
+            Some(cipher) => {
+                let ciphertext = encrypt_value(cipher, &value)?;
+                self.inner.insert::<K, Vec<u8>>(&key, &ciphertext).map_err(Into::into)
+            },
+            None => self.inner.insert::<K, V>(&key, &value).map_err(Into::into),
+        }
     }
 
     /// Get the value corresponding to the provided key from the key-value database.
     fn get(&self, key: &K) -> Result<Option<V>, KeyValStoreError>
     where for<'t> V: serde::de::DeserializeOwned {
-        self.inner.get::<K, V>(key).map_err(Into::into)
+        match self.cipher.as_deref() {
+            Some(cipher) => self
+                .inner
+                .get::<K, Vec<u8>>(key)?
+                .map(|ciphertext| decrypt_value(cipher, &ciphertext))
+                .transpose(),
+            None => self.inner.get::<K, V>(key).map_err(Into::into),
+        }
     }
 
     /// Get the values corresponding to the provided keys from the key-value database.
     fn get_many(&self, keys: &[K]) -> Result<Vec<V>, KeyValStoreError>
     where for<'t> V: serde::de::DeserializeOwned {
+        if let Some(cipher) = self.cipher.as_deref() {
+            return self.inner.with_read_transaction(|access| {
+                keys.iter()
+                    .filter_map(|k| match access.get::<K, Vec<u8>>(k) {
+                        Ok(Some(v)) => Some(decrypt_value(cipher, &v)),
+                        Ok(None) => None,
+                        Err(e) => Some(Err(e.into())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })?;
+        }
+
         self.inner
             .with_read_transaction(|access| {
                 keys.iter()
@@ -94,9 +144,17 @@ where
     }
 
     /// Iterate over all the stored records and execute the function `f` for each pair in the key-value database.
-    fn for_each<F>(&self, f: F) -> Result<(), KeyValStoreError>
+    fn for_each<F>(&self, mut f: F) -> Result<(), KeyValStoreError>
     where F: FnMut(Result<(K, V), KeyValStoreError>) -> IterationResult {
-        self.inner.for_each::<K, V, F>(f).map_err(Into::into)
+        match self.cipher.as_deref() {
+            Some(cipher) => self
+                .inner
+                .for_each::<K, Vec<u8>, _>(|pair| {
+                    f(pair.and_then(|(key, ciphertext)| decrypt_value(cipher, &ciphertext).map(|value| (key, value))))
+                })
+                .map_err(Into::into),
+            None => self.inner.for_each::<K, V, F>(f).map_err(Into::into),
+        }
     }
 
     /// Checks whether a record exist in the key-value database that corresponds to the provided `key`.
@@ -207,4 +265,60 @@ mod test {
         }
         clean_up_datastore(database_name); // In Windows file handles must be released before files can be deleted
     }
+
+    /// Toy cipher for testing. XORs each byte with the key and appends the key as a "tag".
+    struct XorCipher(u8);
+
+    impl ValueCipher for XorCipher {
+        fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, KeyValStoreError> {
+            let mut ciphertext = plaintext.iter().map(|b| b ^ self.0).collect::<Vec<_>>();
+            ciphertext.push(self.0);
+            Ok(ciphertext)
+        }
+
+        fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, KeyValStoreError> {
+            match ciphertext.split_last() {
+                Some((tag, ciphertext)) if *tag == self.0 => Ok(ciphertext.iter().map(|b| b ^ self.0).collect()),
+                _ => Err(KeyValStoreError::DecryptionError("Invalid tag".to_string())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lmdb_kvstore_encrypted() {
+        let database_name = "test_lmdb_kvstore_encrypted"; // Note: every test should have unique database
+        {
+            let datastore = init_datastore(database_name).unwrap();
+            let db = Arc::new(datastore.get_handle(database_name).unwrap());
+            #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+            struct Foo {
+                value: u64,
+            }
+            let encrypted = LMDBWrapper::<u64, Foo>::new(db.clone()).with_cipher(Arc::new(XorCipher(0xAB)));
+            let val1 = Foo { value: 1 };
+            let val2 = Foo { value: 2 };
+            encrypted.insert(1, val1.clone()).unwrap();
+            encrypted.insert(2, val2.clone()).unwrap();
+
+            assert_eq!(encrypted.get(&1).unwrap().unwrap(), val1);
+            assert_eq!(encrypted.get_many(&[1, 2, 3]).unwrap(), vec![val1.clone(), val2]);
+            assert_eq!(encrypted.size().unwrap(), 2);
+            let mut values = Vec::new();
+            encrypted
+                .for_each(|pair| {
+                    values.push(pair.unwrap().1);
+                    IterationResult::Continue
+                })
+                .unwrap();
+            assert_eq!(values.len(), 2);
+
+            // The stored bytes are not the plaintext value
+            let plaintext = LMDBWrapper::<u64, Foo>::new(db.clone());
+            assert_ne!(plaintext.get(&1).ok().flatten(), Some(val1));
+
+            let wrong_key = LMDBWrapper::<u64, Foo>::new(db).with_cipher(Arc::new(XorCipher(0xCD)));
+            assert!(matches!(wrong_key.get(&1), Err(KeyValStoreError::DecryptionError(_))));
+        }
+        clean_up_datastore(database_name); // In Windows file handles must be released before files can be deleted
+    }
 }
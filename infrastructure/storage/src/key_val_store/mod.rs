@@ -20,12 +20,14 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+pub mod cipher;
 pub mod error;
 pub mod hmap_database;
 #[allow(clippy::module_inception)]
 pub mod key_val_store;
 pub mod lmdb_database;
 
+pub use cipher::ValueCipher;
 pub use error::KeyValStoreError;
 pub use hmap_database::HashmapDatabase;
 pub use key_val_store::KeyValueStore;
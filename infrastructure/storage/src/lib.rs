@@ -14,4 +14,5 @@ pub use key_val_store::{
     HashmapDatabase,
     KeyValStoreError,
     KeyValueStore,
+    ValueCipher,
 };
@@ -0,0 +1,81 @@
+@wallet-grpc @wallet
+Feature: Wallet GRPC
+
+  Scenario: As a client I want to manage contacts via gRPC
+    Given I have a seed node SEED
+    And I have wallet WALLET_A connected to all seed nodes
+    And I have wallet WALLET_B connected to all seed nodes
+    When I add wallet WALLET_B as contact BOB to wallet WALLET_A via gRPC
+    Then wallet WALLET_A has contact BOB for wallet WALLET_B via gRPC
+    When I add wallet WALLET_B as contact ROBERT to wallet WALLET_A via gRPC
+    Then wallet WALLET_A has contact ROBERT for wallet WALLET_B via gRPC
+    And wallet WALLET_A has 1 contacts via gRPC
+    When I remove contact wallet WALLET_B from wallet WALLET_A via gRPC
+    Then wallet WALLET_A has 0 contacts via gRPC
+
+  Scenario: As a client I want to back up the seed words via gRPC
+    Given I have a seed node SEED
+    And I have wallet WALLET with its seed words available via gRPC
+    Then getting the seed words of wallet WALLET with passphrase wrongpwd via gRPC is refused
+    Then the seed words of wallet WALLET via gRPC match its seed words file
+
+  Scenario: As a wallet owner I want the seed words to be unavailable via gRPC by default
+    Given I have a seed node SEED
+    And I have wallet WALLET connected to all seed nodes
+    Then getting the seed words of wallet WALLET with passphrase kensentme via gRPC is refused
+
+  Scenario: As a client I want to change the base node via gRPC
+    Given I have a base node NODE1
+    And I have a base node NODE2
+    And I have wallet WALLET connected to base node NODE1
+    When I set base node of wallet WALLET to NODE2 via gRPC
+    Then wallet WALLET is connected to base node NODE2
+
+  Scenario: As a client I want to change the wallet passphrase via gRPC
+    Given I have a seed node SEED
+    And I have wallet WALLET connected to all seed nodes
+    Then changing the passphrase of wallet WALLET from wrongpwd to changedpwd via gRPC is refused
+    When I change the passphrase of wallet WALLET from kensentme to changedpwd via gRPC
+    And I stop wallet WALLET
+    Then the password of wallet WALLET is not kensentme
+    Then the password of wallet WALLET is changedpwd
+
+  Scenario: As a client I want to remove and apply the wallet encryption via gRPC
+    Given I have a seed node SEED
+    And I have wallet WALLET connected to all seed nodes
+    When I remove the encryption of wallet WALLET with passphrase kensentme via gRPC
+    And I encrypt wallet WALLET with passphrase grpcpwd via gRPC
+    And I stop wallet WALLET
+    Then the password of wallet WALLET is not kensentme
+    Then the password of wallet WALLET is grpcpwd
+
+  Scenario: As a client I want to sign and verify messages via gRPC
+    Given I have a seed node SEED
+    And I have wallet WALLET connected to all seed nodes
+    When I sign message "Tari via gRPC" with wallet WALLET via gRPC
+    Then the signature is valid for message "Tari via gRPC" via wallet WALLET
+    And the signature is invalid for message "Something else" via wallet WALLET
+
+  @long-running
+  Scenario: As a client I want to send one-sided, split coins and list UTXOs via gRPC
+    Given I have a seed node NODE
+    And I have wallet SENDER connected to all seed nodes
+    And I have wallet RECEIVER connected to all seed nodes
+    And I have mining node MINER connected to base node NODE and wallet SENDER
+    When mining node MINER mines 5 blocks
+    Then I wait for wallet SENDER to have at least 1100000 uT
+    When I send a one-sided transaction of 1000000 uT from SENDER to RECEIVER at fee 20
+    And I coin split tari in wallet SENDER to produce 5 UTXOs of 20000 uT each with fee_per_gram 20 uT
+    And mining node MINER mines 5 blocks
+    Then wallet SENDER has at least 5 unspent outputs of 20000 uT via gRPC
+
+  @long-running
+  Scenario: As a client I want to start and monitor a recovery via gRPC
+    Given I have a seed node NODE
+    And I have wallet WALLET connected to all seed nodes
+    And I have mining node MINER connected to base node NODE and wallet WALLET
+    When mining node MINER mines 5 blocks
+    Then I wait for wallet WALLET to have at least 1000000 uT
+    When I start recovery of wallet WALLET via gRPC
+    Then the recovery of wallet WALLET via gRPC finishes
+    And I wait for wallet WALLET to have at least 1000000 uT
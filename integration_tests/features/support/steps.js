@@ -389,6 +389,16 @@ Given(
   }
 );
 
+Given(
+  "I have wallet {word} with its seed words available via gRPC",
+  { timeout: 20 * 1000 },
+  async function (name) {
+    await this.createAndAddWallet(name, this.seedAddresses(), {
+      grpcSeedWordsEnabled: true,
+    });
+  }
+);
+
 Given(
   /I have non-default wallet (.*) connected to all seed nodes using (.*)/,
   { timeout: 20 * 1000 },
@@ -3830,3 +3840,223 @@ Then(
     expect(resp["result"]["untrusted"]).to.be.equal(false);
   }
 );
+
+When(
+  "I add wallet {word} as contact {word} to wallet {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (contactWallet, alias, walletName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    await walletClient.upsertContact(alias, this.getWalletPubkey(contactWallet));
+  }
+);
+
+When(
+  "I remove contact wallet {word} from wallet {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (contactWallet, walletName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const contact = await walletClient.removeContact(
+      this.getWalletPubkey(contactWallet)
+    );
+    expect(contact.public_key).to.equal(this.getWalletPubkey(contactWallet));
+  }
+);
+
+Then(
+  "wallet {word} has contact {word} for wallet {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName, alias, contactWallet) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const contacts = await walletClient.getContacts();
+    expect(contacts).to.deep.include({
+      alias,
+      public_key: this.getWalletPubkey(contactWallet),
+    });
+  }
+);
+
+Then(
+  "wallet {word} has {int} contacts via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName, count) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const contacts = await walletClient.getContacts();
+    expect(contacts.length).to.equal(count);
+  }
+);
+
+Then(
+  "getting the seed words of wallet {word} with passphrase {word} via gRPC is refused",
+  { timeout: 20 * 1000 },
+  async function (walletName, passphrase) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    let error;
+    try {
+      await walletClient.getSeedWords(passphrase);
+    } catch (err) {
+      error = err;
+    }
+    expect(error, "The seed words request should be refused").to.not.be
+      .undefined;
+  }
+);
+
+Then(
+  "the seed words of wallet {word} via gRPC match its seed words file",
+  { timeout: 20 * 1000 },
+  async function (walletName) {
+    const wallet = this.getWallet(walletName);
+    const walletClient = await wallet.connectClient();
+    const { seed_words, birthday } = await walletClient.getSeedWords(
+      "kensentme"
+    );
+    expect(seed_words.length).to.equal(24);
+    expect(seed_words.join(" ")).to.equal(wallet.getSeedWords().trim());
+    expect(birthday).to.match(/^\d{4}-\d{2}-\d{2}$/);
+  }
+);
+
+When(
+  "I set base node of wallet {word} to {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName, nodeName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const [publicKey, address] = this.getNode(nodeName)
+      .peerAddress()
+      .split("::");
+    await walletClient.setBaseNode(publicKey, address);
+  }
+);
+
+Then(
+  "wallet {word} is connected to base node {word}",
+  { timeout: 65 * 1000 },
+  async function (walletName, nodeName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const publicKey = this.getNode(nodeName).peerAddress().split("::")[0];
+    await waitFor(
+      async () =>
+        (await walletClient.listConnectedPeers()).some(
+          (peer) => peer.public_key === publicKey
+        ),
+      true,
+      60 * 1000
+    );
+    const peers = await walletClient.listConnectedPeers();
+    expect(peers.map((peer) => peer.public_key)).to.include(publicKey);
+  }
+);
+
+When(
+  "I remove the encryption of wallet {word} with passphrase {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName, passphrase) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    await walletClient.removeEncryption(passphrase);
+  }
+);
+
+When(
+  "I encrypt wallet {word} with passphrase {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName, passphrase) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    await walletClient.applyEncryption(passphrase);
+  }
+);
+
+When(
+  "I change the passphrase of wallet {word} from {word} to {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName, passphrase, newPassphrase) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    await walletClient.changePassphrase(passphrase, newPassphrase);
+  }
+);
+
+Then(
+  "changing the passphrase of wallet {word} from {word} to {word} via gRPC is refused",
+  { timeout: 20 * 1000 },
+  async function (walletName, passphrase, newPassphrase) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    let error;
+    try {
+      await walletClient.changePassphrase(passphrase, newPassphrase);
+    } catch (err) {
+      error = err;
+    }
+    expect(error, "The passphrase change should be refused").to.not.be
+      .undefined;
+    expect(error.details).to.contain("password");
+  }
+);
+
+When(
+  "I sign message {string} with wallet {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (message, walletName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    this.lastResult = await walletClient.signMessage(message);
+    expect(this.lastResult.public_key).to.equal(
+      this.getWalletPubkey(walletName)
+    );
+  }
+);
+
+Then(
+  "the signature is {word} for message {string} via wallet {word}",
+  { timeout: 20 * 1000 },
+  async function (validity, message, walletName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const isValid = await walletClient.verifyMessageSignature(
+      message,
+      this.lastResult
+    );
+    expect(isValid).to.equal(validity === "valid");
+  }
+);
+
+Then(
+  "wallet {word} has at least {int} unspent outputs of {int} uT via gRPC",
+  { timeout: 65 * 1000 },
+  async function (walletName, count, value) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    const filter = { min_value: value, max_value: value };
+    await waitFor(
+      async () =>
+        (await walletClient.getUnspentOutputs(filter)).total_count >= count,
+      true,
+      60 * 1000
+    );
+    const { outputs, total_count, total_value } =
+      await walletClient.getUnspentOutputs({ ...filter, limit: 1 });
+    expect(total_count).to.be.greaterThanOrEqual(count);
+    expect(total_value).to.equal(total_count * value);
+    expect(outputs.length).to.equal(1);
+    expect(outputs[0].value).to.equal(value);
+  }
+);
+
+When(
+  "I start recovery of wallet {word} via gRPC",
+  { timeout: 20 * 1000 },
+  async function (walletName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    await walletClient.startRecovery();
+    expect(await walletClient.isRecoveryInProgress()).to.equal(true);
+  }
+);
+
+Then(
+  "the recovery of wallet {word} via gRPC finishes",
+  { timeout: 125 * 1000 },
+  async function (walletName) {
+    const walletClient = await this.getWallet(walletName).connectClient();
+    await waitFor(
+      async () => walletClient.isRecoveryInProgress(),
+      false,
+      120 * 1000
+    );
+    expect(await walletClient.isRecoveryInProgress()).to.equal(false);
+  }
+);
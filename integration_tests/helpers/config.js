@@ -23,6 +23,10 @@ function mapEnvs(options) {
   } else {
     res.TARI_WALLET__TRANSACTION_BROADCAST_MONITORING_TIMEOUT = 3;
   }
+  if (options.grpcSeedWordsEnabled) {
+    // In the config toml file: `wallet.grpc_seed_words_enabled`
+    res.TARI_WALLET__GRPC_SEED_WORDS_ENABLED = true;
+  }
   if ("mineOnTipOnly" in options) {
     res.TARI_MINING_NODE__MINE_ON_TIP_ONLY = options.mineOnTipOnly;
   }
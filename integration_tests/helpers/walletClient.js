@@ -391,6 +391,75 @@ class WalletClient {
       };
     }
   }
+
+  async getContacts() {
+    const { contacts } = await this.client.getContacts();
+    return contacts;
+  }
+
+  async upsertContact(alias, public_key) {
+    return await this.client.upsertContact({
+      contact: { alias, public_key },
+    });
+  }
+
+  async removeContact(public_key) {
+    const { contact } = await this.client.removeContact({ public_key });
+    return contact;
+  }
+
+  async getSeedWords(passphrase) {
+    return await this.client.getSeedWords({ passphrase });
+  }
+
+  async setBaseNode(public_key, net_address) {
+    return await this.client.setBaseNode({ public_key, net_address });
+  }
+
+  async applyEncryption(passphrase) {
+    return await this.client.applyEncryption({ passphrase });
+  }
+
+  async removeEncryption(passphrase) {
+    return await this.client.removeEncryption({ passphrase });
+  }
+
+  async changePassphrase(passphrase, new_passphrase) {
+    return await this.client.changePassphrase({ passphrase, new_passphrase });
+  }
+
+  async signMessage(message) {
+    return await this.client.signMessage({ message });
+  }
+
+  async verifyMessageSignature(message, signature) {
+    const { is_valid } = await this.client.verifyMessageSignature({
+      message,
+      ...signature,
+    });
+    return is_valid;
+  }
+
+  async getUnspentOutputs(filter) {
+    const resp = await this.client.getUnspentOutputs(filter);
+    return {
+      outputs: resp.outputs.map((output) => ({
+        ...output,
+        value: parseInt(output.value),
+      })),
+      total_count: parseInt(resp.total_count),
+      total_value: parseInt(resp.total_value),
+    };
+  }
+
+  async startRecovery(base_node_public_keys = []) {
+    return await this.client.startRecovery({ base_node_public_keys });
+  }
+
+  async isRecoveryInProgress() {
+    const { in_progress } = await this.client.getRecoveryStatus();
+    return in_progress;
+  }
 }
 
 module.exports = WalletClient;